@@ -1,6 +1,7 @@
 //! Vo CLI launcher - compiles and runs the Vo CLI (cmd/vo).
 
 use std::env;
+use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 use std::process;
 
@@ -33,6 +34,23 @@ fn main() {
         i += 1;
     }
     
+    // The REPL runs in the launcher itself rather than through cmd/vo
+    if pass_args.first().map(String::as_str) == Some("repl") {
+        run_repl();
+        return;
+    }
+    
+    // These need compiler data that cmd/vo only sees as an error message
+    if pass_args.first().map(String::as_str) == Some("check") {
+        let check_args = &pass_args[1..];
+        if check_args.iter().any(|a| a == "--json") {
+            process::exit(check_json(check_args));
+        }
+        if check_args.iter().any(|a| a == "-m") {
+            process::exit(check_escapes(check_args));
+        }
+    }
+    
     // Find cmd/vo directory
     let cmd_vo_path = cmd_vo_path.unwrap_or_else(find_cmd_vo);
     
@@ -63,6 +81,54 @@ fn main() {
     }
 }
 
+/// Read entries from stdin until EOF, printing what each one outputs.
+fn run_repl() {
+    let mut repl = vo_engine::Repl::new();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("{}", if repl.is_pending() { "... " } else { ">>> " });
+        let _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else { break };
+        match repl.feed(&line) {
+            Ok(Some(out)) => print!("{}", out),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    println!();
+}
+
+/// The path operand of `check` (default ".").
+fn check_path(args: &[String]) -> &str {
+    args.iter().find(|a| !a.starts_with('-')).map_or(".", String::as_str)
+}
+
+/// Print the diagnostics for the checked path as a JSON array.
+/// Returns the exit code: 1 if any diagnostic is an error.
+fn check_json(args: &[String]) -> i32 {
+    let path = check_path(args);
+    let diagnostics = vo_engine::compile_file_diagnostics(path);
+    println!("{}", vo_engine::diagnostics_to_json(&diagnostics));
+    let failed = diagnostics.iter().any(|d| d.severity == vo_engine::Severity::Error);
+    if failed { 1 } else { 0 }
+}
+
+/// Print each local variable of the checked path that was moved to the heap.
+fn check_escapes(args: &[String]) -> i32 {
+    match vo_engine::compile_escape_notes(check_path(args)) {
+        Ok(notes) => {
+            for note in notes {
+                println!("{}", note);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
 fn find_cmd_vo() -> PathBuf {
     // Try relative to executable
     if let Ok(exe_path) = env::current_exe() {
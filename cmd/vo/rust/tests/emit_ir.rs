@@ -0,0 +1,53 @@
+//! `vo run --emit=ir` end to end, through the launcher and cmd/vo.
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_run_emit_ir_prints_functions_and_loops() {
+    // add becomes hot through calls, main's second loop through its back edge
+    let source = r#"package main
+
+func add(a, b int) int {
+	return a + b
+}
+
+func main() {
+	sum := 0
+	for i := 0; i < 200; i++ {
+		sum = add(sum, i)
+	}
+	for i := 0; i < 5000; i++ {
+		sum += i
+	}
+	println(sum)
+}
+"#;
+    let dir = std::env::temp_dir().join(format!("vo-emit-ir-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.vo");
+    fs::write(&file, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vo"))
+        .args(["run", file.to_str().unwrap(), "--emit=ir"])
+        .env_remove("VO_JIT_CALL_THRESHOLD")
+        .env_remove("VO_JIT_LOOP_THRESHOLD")
+        .output()
+        .expect("failed to run vo");
+    fs::remove_dir_all(&dir).ok();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "vo run failed:\n{}\n{}", stdout, stderr);
+    assert!(stdout.contains("12517400"), "unexpected stdout:\n{}", stdout);
+
+    let headers: Vec<&str> = stderr.lines().filter(|l| l.starts_with("=== JIT ")).collect();
+    assert!(headers.iter().any(|h| h.starts_with("=== JIT func_") && h.ends_with(" add ===")),
+        "no listing for add:\n{}", stderr);
+    let lp = stderr.find("=== JIT loop func_").expect("no loop listing");
+    let header = stderr[lp..].lines().next().unwrap();
+    assert!(header.contains(" main @"), "unexpected loop header: {}", header);
+    let body = &stderr[lp..];
+    assert!(body.contains("--- bytecode ---") && body.contains("--- cranelift ir ---"),
+        "unexpected loop listing:\n{}", body);
+}
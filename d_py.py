@@ -303,6 +303,30 @@ def run_vo_file(file: str, mode: str = 'vm', codegen: bool = False):
 # TEST RUNNER
 # =============================================================================
 
+ALL_MODES = ['vm', 'jit', 'nostd', 'wasm']
+
+
+def header_skips(path: Path) -> list:
+    """Modes a test file excludes in the comment lines before `package`:
+    `// skip: jit wasm` skips the listed modes, `// modes: vm jit` runs only those."""
+    if path.suffix != '.vo' or not path.is_file():
+        return []
+    skip = []
+    for line in path.read_text(encoding='utf-8', errors='replace').splitlines():
+        trimmed = line.strip()
+        if not trimmed:
+            continue
+        if not trimmed.startswith('//'):
+            break
+        comment = trimmed[2:].strip()
+        if comment.startswith('skip:'):
+            skip.extend(comment[len('skip:'):].split())
+        elif comment.startswith('modes:'):
+            modes = comment[len('modes:'):].split()
+            skip.extend(m for m in ALL_MODES if m not in modes)
+    return skip
+
+
 class TestRunner:
     def __init__(self, verbose: bool = False, arch: str = '64', direct: bool = False):
         self.verbose = verbose
@@ -354,9 +378,9 @@ class TestRunner:
         return config
 
     def should_skip(self, file: str, mode: str) -> bool:
-        if file in self.config:
-            return mode in self.config[file].get('skip', [])
-        return False
+        if file in self.config and mode in self.config[file].get('skip', []):
+            return True
+        return mode in header_skips(TEST_DIR / file)
 
     def should_fail(self, file: str) -> bool:
         if file in self.config:
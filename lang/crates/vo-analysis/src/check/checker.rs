@@ -112,6 +112,8 @@ pub struct Checker {
     pub trace_enabled: bool,
     /// Current trace indentation level.
     pub trace_indent: Rc<RefCell<usize>>,
+    /// Report imports a file never uses.
+    pub report_unused_imports: bool,
     
     // --- Per-check state ---
     /// Positions of unused dot-imported packages for each file scope.
@@ -148,6 +150,7 @@ impl Checker {
             result: TypeInfo::new(),
             trace_enabled,
             trace_indent: Rc::new(RefCell::new(0)),
+            report_unused_imports: true,
             unused_dot_imports: HashMap::new(),
             methods: HashMap::new(),
             ifaces: HashMap::new(),
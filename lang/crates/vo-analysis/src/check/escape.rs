@@ -7,7 +7,7 @@ use crate::objects::{ObjKey, ScopeKey, TCObjects};
 use crate::typ;
 use super::type_info::TypeInfo;
 use vo_syntax::ast::{
-    AssignOp, Block, Decl, Expr, ExprKind, File, FuncDecl, Stmt, StmtKind, UnaryOp,
+    AssignOp, Block, CommClause, Decl, Expr, ExprKind, File, FuncDecl, Ident, Stmt, StmtKind, UnaryOp,
 };
 use crate::selection::SelectionKind;
 use std::collections::{HashMap, HashSet};
@@ -323,6 +323,21 @@ impl<'a> EscapeAnalyzer<'a> {
             }
             StmtKind::Select(ss) => {
                 for case in &ss.cases {
+                    match &case.comm {
+                        Some(CommClause::Send(send)) => {
+                            self.visit_expr(&send.chan);
+                            self.visit_expr(&send.value);
+                        }
+                        Some(CommClause::Recv(recv)) => {
+                            if !recv.define {
+                                for ident in &recv.lhs {
+                                    self.visit_ident(ident);
+                                }
+                            }
+                            self.visit_expr(&recv.expr);
+                        }
+                        None => {}
+                    }
                     for s in &case.body {
                         self.visit_stmt(s);
                     }
@@ -403,29 +418,7 @@ impl<'a> EscapeAnalyzer<'a> {
             }
 
             // 4. Variable reference → check if captured by closure
-            ExprKind::Ident(ident) => {
-                if let Some(func_scope) = self.func_scope {
-                    if let Some(&obj) = self.type_info.uses.get(&ident.id) {
-                        if self.is_captured(obj, func_scope) {
-                            self.escaped.insert(obj);
-                            // Record capture for closures that actually need to capture this var
-                            // A closure needs to capture a var if the var is declared outside that closure's scope
-                            for entry in &self.closure_stack {
-                                if let Some(scope) = entry.scope {
-                                    // Only add if var is captured by THIS closure (not its own param/local)
-                                    if self.is_captured(obj, scope) {
-                                        if let Some(captures) = self.closure_captures.get_mut(&entry.id) {
-                                            if !captures.contains(&obj) {
-                                                captures.push(obj);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            ExprKind::Ident(ident) => self.visit_ident(ident),
 
             // Recurse into other expressions
             ExprKind::Binary(b) => {
@@ -477,6 +470,32 @@ impl<'a> EscapeAnalyzer<'a> {
         }
     }
 
+    /// A variable reference: record it as a capture of every enclosing
+    /// closure it is declared outside of.
+    fn visit_ident(&mut self, ident: &Ident) {
+        if let Some(func_scope) = self.func_scope {
+            if let Some(&obj) = self.type_info.uses.get(&ident.id) {
+                if self.is_captured(obj, func_scope) {
+                    self.escaped.insert(obj);
+                    // Record capture for closures that actually need to capture this var
+                    // A closure needs to capture a var if the var is declared outside that closure's scope
+                    for entry in &self.closure_stack {
+                        if let Some(scope) = entry.scope {
+                            // Only add if var is captured by THIS closure (not its own param/local)
+                            if self.is_captured(obj, scope) {
+                                if let Some(captures) = self.closure_captures.get_mut(&entry.id) {
+                                    if !captures.contains(&obj) {
+                                        captures.push(obj);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Check if variable is captured by closure.
     /// A variable is captured if it's declared outside the current function scope.
     fn is_captured(&self, obj: ObjKey, func_scope: ScopeKey) -> bool {
@@ -715,6 +734,28 @@ mod tests {
         assert!(escaped.contains(&"x".to_string()), "x should escape: {:?}", escaped);
     }
 
+    #[test]
+    fn test_closure_capture_in_select() {
+        let escaped = get_escaped_vars(r#"
+            package main
+            func main() {
+                in := make(chan int)
+                out := make(chan int)
+                var v int
+                f := func() {
+                    select {
+                    case v = <-in:
+                    case out <- 1:
+                    }
+                }
+                f()
+            }
+        "#);
+        for name in ["in", "out", "v"] {
+            assert!(escaped.contains(&name.to_string()), "{} should escape: {:?}", name, escaped);
+        }
+    }
+
     #[test]
     fn test_closure_capture_struct() {
         let escaped = get_escaped_vars(r#"
@@ -748,6 +748,9 @@ impl Checker {
 
     /// Checks for unused imports.
     pub(crate) fn unused_imports(&mut self) {
+        if !self.report_unused_imports {
+            return;
+        }
         // Check regular imported packages
         let pkg_scope = *self.package(self.pkg).scope();
         for &child_scope in self.scope(pkg_scope).children() {
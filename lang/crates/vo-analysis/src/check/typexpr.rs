@@ -374,28 +374,9 @@ impl Checker {
         );
 
         // Collect parameters - FuncType uses Vec<Param> with names preserved
-        let mut params = Vec::new();
-        for param in &func.params {
-            let ty = self.indirect_type(&param.ty);
-            // Use first name if available, otherwise empty
-            let name = param.names.first()
-                .map(|n| self.resolve_ident(n).to_string())
-                .unwrap_or_default();
-            let var = self.new_param_var(Span::default(), Some(self.pkg), name, Some(ty));
-            params.push(var);
-        }
+        let params = self.func_type_vars(&func.params);
         let variadic = false; // FuncType in Vo doesn't have variadic marker
-
-        // Collect results
-        let mut results = Vec::new();
-        for result in &func.results {
-            let ty = self.indirect_type(&result.ty);
-            let name = result.names.first()
-                .map(|n| self.resolve_ident(n).to_string())
-                .unwrap_or_default();
-            let var = self.new_param_var(Span::default(), Some(self.pkg), name, Some(ty));
-            results.push(var);
-        }
+        let results = self.func_type_vars(&func.results);
 
         let params_tuple = self.new_tuple(params);
         let results_tuple = self.new_tuple(results);
@@ -403,6 +384,23 @@ impl Checker {
         self.new_t_signature(Some(scope_key), None, params_tuple, results_tuple, variadic)
     }
 
+    /// One var per name in a func type's parameter list: `func(i, j int)`
+    /// has two parameters. An unnamed group is a single parameter.
+    fn func_type_vars(&mut self, params: &[Param]) -> Vec<ObjKey> {
+        let mut vars = Vec::new();
+        for param in params {
+            let ty = self.indirect_type(&param.ty);
+            if param.names.is_empty() {
+                vars.push(self.new_param_var(Span::default(), Some(self.pkg), String::new(), Some(ty)));
+            }
+            for name in &param.names {
+                let name = self.resolve_ident(name).to_string();
+                vars.push(self.new_param_var(Span::default(), Some(self.pkg), name, Some(ty)));
+            }
+        }
+        vars
+    }
+
     /// Type-checks a function signature and returns its type.
     /// Aligned with goscript's func_type implementation.
     pub fn func_type_from_sig(
@@ -115,6 +115,8 @@ impl std::error::Error for AnalysisError {}
 pub struct AnalysisOptions {
     /// Enable type checker trace output.
     pub trace: bool,
+    /// Do not report imports main never uses.
+    pub allow_unused_imports: bool,
 }
 
 /// Result of project analysis.
@@ -277,6 +279,7 @@ pub fn analyze_project_with_options<R: Resolver>(
     {
         let mut state_ref = state.borrow_mut();
         let mut checker = Checker::new_with_trace(main_pkg_key, state_ref.interner.clone(), options.trace);
+        checker.report_unused_imports = !options.allow_unused_imports;
         
         // Swap tc_objs so checker uses our shared one (imports already loaded)
         std::mem::swap(&mut checker.tc_objs, &mut state_ref.tc_objs);
@@ -361,7 +364,10 @@ fn parse_single_file(
 fn parse_files(files: &FileSet, state: &Rc<RefCell<ProjectState>>) -> Result<Vec<File>, AnalysisError> {
     let mut parsed_files = Vec::new();
     
-    for (path, content) in &files.files {
+    // In name order, like Go, so init order and codegen do not vary between runs
+    let mut sorted: Vec<_> = files.files.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    for (path, content) in sorted {
         let id_state = state.borrow().id_state.clone();
         let (file, new_id_state) = parse_single_file(path, content, state, id_state)?;
         state.borrow_mut().id_state = new_id_state;
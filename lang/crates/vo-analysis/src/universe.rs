@@ -366,6 +366,9 @@ impl Universe {
             "error".to_string(),
             Some(error_type),
         ));
+        if let Type::Named(named) = &mut objs.types[error_type] {
+            named.set_obj(type_name);
+        }
 
         // Insert into universe scope
         Scope::insert(universe_scope, type_name, objs);
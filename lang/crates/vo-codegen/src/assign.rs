@@ -217,8 +217,11 @@ fn emit_concrete_to_iface_from_slot(
     let iface_meta_id = info.get_or_create_interface_meta_id(iface_type, ctx);
     let const_idx = compute_iface_assign_const(src_type, src_vk, iface_meta_id, ctx, info);
     
-    if src_vk.needs_boxing() {
-        // Struct/Array: allocate box and copy data
+    if src_vk == vo_runtime::ValueKind::Array {
+        let gcref_slot = emit_box_array(src_slot, src_type, ctx, func, info);
+        func.emit_with_flags(Opcode::IfaceAssign, src_vk as u8, dst, gcref_slot, const_idx);
+    } else if src_vk.needs_boxing() {
+        // Struct: allocate box and copy data
         let src_slots = info.type_slot_count(src_type);
         let meta_idx = ctx.get_or_create_value_meta(src_type, info);
         
@@ -235,6 +238,45 @@ fn emit_concrete_to_iface_from_slot(
     Ok(())
 }
 
+/// Box an array held in stack slots as a heap array, the layout arrays in
+/// interfaces have wherever they come from (escaped locals, globals), and the
+/// one IfaceAssert, comparison and hashing read.
+fn emit_box_array(
+    src_slot: u16,
+    array_type: TypeKey,
+    ctx: &mut CodegenContext,
+    func: &mut FuncBuilder,
+    info: &TypeInfoWrapper,
+) -> u16 {
+    let elem_slots = info.array_elem_slots(array_type);
+    let elem_bytes = info.array_elem_bytes(array_type);
+    let elem_vk = info.type_value_kind(info.array_elem_type(array_type));
+    let arr_len = info.array_len(array_type);
+
+    let flags = vo_common_core::elem_flags(elem_bytes, elem_vk);
+    let gcref_slot = func.alloc_temp_typed(&[SlotType::GcRef]);
+    let meta_reg = func.alloc_temp_typed(&[SlotType::Value]);
+    // When flags=0 (dynamic), len and elem_bytes go in consecutive registers
+    let num_regs = if flags == 0 { 2 } else { 1 };
+    let len_reg = func.alloc_temp_typed(&vec![SlotType::Value; num_regs]);
+    let meta_idx = ctx.get_or_create_array_elem_meta(array_type, info);
+    func.emit_op(Opcode::LoadConst, meta_reg, meta_idx, 0);
+    let len_idx = ctx.const_int(arr_len as i64);
+    func.emit_op(Opcode::LoadConst, len_reg, len_idx, 0);
+    if flags == 0 {
+        let eb_idx = ctx.const_int(elem_bytes as i64);
+        func.emit_op(Opcode::LoadConst, len_reg + 1, eb_idx, 0);
+    }
+    func.emit_with_flags(Opcode::ArrayNew, flags, gcref_slot, meta_reg, len_reg);
+
+    let idx_reg = func.alloc_temp_typed(&[SlotType::Value]);
+    for i in 0..arr_len as u16 {
+        func.emit_op(Opcode::LoadInt, idx_reg, i, 0);
+        func.emit_array_set(gcref_slot, idx_reg, src_slot + i * elem_slots, elem_bytes, elem_vk, ctx);
+    }
+    gcref_slot
+}
+
 /// Compile interface assignment from expression.
 /// Optimized version that avoids extra copies for heap values.
 fn compile_iface_assign_internal(
@@ -277,6 +319,12 @@ fn compile_iface_assign_internal(
                 func.emit_op(Opcode::GlobalGet, gcref_slot, index, 0);
                 func.emit_with_flags(Opcode::IfaceAssign, src_vk as u8, dst, gcref_slot, const_idx);
             }
+            _ if src_vk == vo_runtime::ValueKind::Array => {
+                let tmp_data = func.alloc_temp_typed(&info.type_slot_types(src_type));
+                crate::expr::compile_expr_to(expr, tmp_data, ctx, func, info)?;
+                let gcref_slot = emit_box_array(tmp_data, src_type, ctx, func, info);
+                func.emit_with_flags(Opcode::IfaceAssign, src_vk as u8, dst, gcref_slot, const_idx);
+            }
             _ => {
                 // Stack value or expression: allocate box and copy data
                 let src_slots = info.type_slot_count(src_type);
@@ -34,6 +34,32 @@ enum MethodValueWrapperKey {
     EmbeddedInterface { embed_offset: u16, method_idx: u32 },
 }
 
+/// A local variable the codegen placed on the heap because it escapes,
+/// recorded when `CodegenOptions::escape_notes` is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeNote {
+    /// Function (or closure) the variable belongs to.
+    pub func: String,
+    pub name: String,
+    /// Where the variable is declared.
+    pub loc: Option<SourceLoc>,
+    /// Captured by a closure, rather than escaping through its address.
+    pub captured: bool,
+}
+
+impl std::fmt::Display for EscapeNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(loc) = &self.loc {
+            write!(f, "{}: ", loc)?;
+        }
+        write!(f, "moved to heap: {}", self.name)?;
+        if self.captured {
+            write!(f, " (captured by closure)")?;
+        }
+        Ok(())
+    }
+}
+
 /// Get ret_slots for builtin extern functions.
 /// 
 /// All externs registered via `get_or_register_extern` MUST be listed here.
@@ -78,6 +104,7 @@ use vo_vm::bytecode::{
 };
 use vo_common::SourceMap;
 use vo_common::span::Span;
+use vo_common_core::SourceLoc;
 
 /// Package-level codegen context.
 pub struct CodegenContext {
@@ -96,14 +123,12 @@ pub struct CodegenContext {
     /// Next global slot offset (accumulated from all globals)
     global_slot_offset: u32,
 
-    /// Constant pool: int value -> const_idx
-    const_int: HashMap<i64, u16>,
-
-    /// Constant pool: float bits -> const_idx
-    const_float: HashMap<u64, u16>,
+    /// Array globals declared without an initializer: (slot_offset, array type).
+    /// `__init__` allocates their zeroed backing arrays.
+    zero_array_globals: Vec<(u32, TypeKey)>,
 
-    /// Constant pool: string -> const_idx
-    const_string: HashMap<String, u16>,
+    /// Constant pool: constant -> const_idx (floats compare by bits)
+    const_pool: HashMap<Constant, u16>,
 
     /// Type meta_id: TypeKey -> struct_meta_id
     struct_meta_ids: HashMap<TypeKey, u32>,
@@ -123,7 +148,7 @@ pub struct CodegenContext {
     /// ObjKey -> iface_func_id (wrapper for value receiver methods, or original for pointer receiver)
     objkey_to_iface_func: HashMap<ObjKey, u32>,
 
-    /// init functions (in declaration order)
+    /// init functions of the unit being compiled (in declaration order)
     init_functions: Vec<u32>,
     
     /// main function id (if exists)
@@ -137,6 +162,9 @@ pub struct CodegenContext {
     /// Ensures same (type, interface) pair always gets same itab_id
     itab_cache: HashMap<(u32, u32), u32>,
 
+    /// Itab ids taken at runtime by a VM this module is reloaded into, sorted
+    reserved_itabs: Vec<u32>,
+
     /// Current function ID being compiled (for debug info recording)
     current_func_id: Option<u32>,
     
@@ -149,6 +177,9 @@ pub struct CodegenContext {
     /// Unified wrapper cache (name -> func_id)
     /// Used for: defer_extern, defer_iface, method_expr_iface, etc.
     wrapper_cache: HashMap<String, u32>,
+
+    /// Heap-allocated locals, when escape notes are enabled
+    escape_notes: Option<Vec<EscapeNote>>,
 }
 
 impl CodegenContext {
@@ -188,9 +219,8 @@ impl CodegenContext {
             extern_names: HashMap::new(),
             global_indices: HashMap::new(),
             global_slot_offset: 0,
-            const_int: HashMap::new(),
-            const_float: HashMap::new(),
-            const_string: HashMap::new(),
+            zero_array_globals: Vec::new(),
+            const_pool: HashMap::new(),
             struct_meta_ids: HashMap::new(),
             interface_meta_ids: HashMap::new(),
             named_type_ids: HashMap::new(),
@@ -201,13 +231,42 @@ impl CodegenContext {
             main_func_id: None,
             pending_itabs: Vec::new(),
             itab_cache: HashMap::new(),
+            reserved_itabs: Vec::new(),
             current_func_id: None,
             builtin_protocols: BuiltinProtocols::default(),
             method_value_wrappers: HashMap::new(),
             wrapper_cache: HashMap::new(),
+            escape_notes: None,
         }
     }
     
+    /// Start recording an `EscapeNote` for every heap-allocated local.
+    pub fn enable_escape_notes(&mut self) {
+        self.escape_notes.get_or_insert_with(Vec::new);
+    }
+
+    /// Record that local `obj` of function `func` is heap-allocated.
+    pub fn note_heap_local(&mut self, func: &str, obj: ObjKey, info: &crate::type_info::TypeInfoWrapper) {
+        let Some(notes) = &mut self.escape_notes else { return };
+        let lobj = &info.tc_objs().lobjs[obj];
+        let span = lobj.span();
+        let loc = info.project.source_map.lookup_file(span.start).map(|file| {
+            let lc = file.line_col(span.start);
+            let len = (span.end.to_u32() - span.start.to_u32()) as u16;
+            SourceLoc::new(file.name(), lc.line, lc.column as u16, len)
+        });
+        notes.push(EscapeNote {
+            func: func.to_string(),
+            name: lobj.name().to_string(),
+            loc,
+            captured: info.is_captured_by_closure(obj),
+        });
+    }
+
+    pub fn take_escape_notes(&mut self) -> Vec<EscapeNote> {
+        self.escape_notes.take().unwrap_or_default()
+    }
+    
     /// Register a builtin protocol interface.
     pub fn register_builtin_protocol(&mut self, name: &str, meta: InterfaceMeta) {
         let meta_id = self.module.interface_metas.len() as u32;
@@ -581,7 +640,7 @@ impl CodegenContext {
         } else {
             // Non-empty interface: defer itab building
             let packed = ((rttid as i64) << 32) | 0;
-            let const_idx = self.add_const(Constant::Int(packed));
+            let const_idx = self.add_unshared_const(Constant::Int(packed));
             // Store type_key for lookup_field_or_method during itab building
             // Only add to pending if type_key is a Named type (has methods)
             if let Some(tk) = type_key {
@@ -602,6 +661,26 @@ impl CodegenContext {
         self.const_int(packed)
     }
 
+    /// Keep itab ids in `ids` free: compile-time itabs skip them, leaving
+    /// empty placeholders in their place.
+    pub fn reserve_itabs(&mut self, ids: &[u32]) {
+        self.reserved_itabs = ids.to_vec();
+        self.reserved_itabs.sort_unstable();
+    }
+
+    /// Fill reserved ids up to the next free one, or with `all`, every one.
+    fn fill_reserved_itabs(&mut self, all: bool) {
+        for &id in &self.reserved_itabs {
+            let len = self.module.itabs.len() as u32;
+            if id > len && !all {
+                break;
+            }
+            if id >= len {
+                self.module.itabs.resize(id as usize + 1, Itab::default());
+            }
+        }
+    }
+
     /// Build pending itabs after all methods are registered.
     /// Uses lookup_field_or_method to find methods (including promoted methods from embedded fields).
     pub fn finalize_itabs(&mut self, tc_objs: &vo_analysis::objects::TCObjects, interner: &vo_common::SymbolInterner) {
@@ -611,6 +690,7 @@ impl CodegenContext {
             let packed = ((rttid as i64) << 32) | (itab_id as i64);
             self.module.constants[const_idx as usize] = Constant::Int(packed);
         }
+        self.fill_reserved_itabs(true);
     }
 
     fn build_itab(&mut self, type_key: TypeKey, iface_meta_id: u32, tc_objs: &vo_analysis::objects::TCObjects, _interner: &vo_common::SymbolInterner) -> u32 {
@@ -647,6 +727,7 @@ impl CodegenContext {
             })
             .collect();
 
+        self.fill_reserved_itabs(false);
         let itab_id = self.module.itabs.len() as u32;
         self.module.itabs.push(Itab { methods });
         self.itab_cache.insert(cache_key, itab_id);
@@ -752,45 +833,49 @@ impl CodegenContext {
         slot_offset
     }
 
+    /// Global slots registered so far: the slot offset the next global gets.
+    pub fn global_slots(&self) -> u32 {
+        self.global_slot_offset
+    }
+
     pub fn get_global_index(&self, obj_key: vo_analysis::objects::ObjKey) -> Option<u32> {
         self.global_indices.get(&obj_key).copied()
     }
 
+    pub fn add_zero_array_global(&mut self, global_idx: u32, array_type: TypeKey) {
+        self.zero_array_globals.push((global_idx, array_type));
+    }
+
+    pub fn take_zero_array_globals(&mut self) -> Vec<(u32, TypeKey)> {
+        std::mem::take(&mut self.zero_array_globals)
+    }
+
     // === Constant pool ===
 
     pub fn const_int(&mut self, val: i64) -> u16 {
-        if let Some(&idx) = self.const_int.get(&val) {
-            return idx;
-        }
-        let idx = self.module.constants.len() as u16;
-        self.module.constants.push(Constant::Int(val));
-        self.const_int.insert(val, idx);
-        idx
+        self.add_const(Constant::Int(val))
     }
 
     pub fn const_float(&mut self, val: f64) -> u16 {
-        let bits = val.to_bits();
-        if let Some(&idx) = self.const_float.get(&bits) {
-            return idx;
-        }
-        let idx = self.module.constants.len() as u16;
-        self.module.constants.push(Constant::Float(val));
-        self.const_float.insert(bits, idx);
-        idx
+        self.add_const(Constant::Float(val))
     }
 
     pub fn const_string(&mut self, val: &str) -> u16 {
-        if let Some(&idx) = self.const_string.get(val) {
+        self.add_const(Constant::String(val.to_string()))
+    }
+
+    /// Add a constant, reusing the existing entry if an equal one was added before.
+    pub fn add_const(&mut self, c: Constant) -> u16 {
+        if let Some(&idx) = self.const_pool.get(&c) {
             return idx;
         }
-        let idx = self.module.constants.len() as u16;
-        self.module.constants.push(Constant::String(val.to_string()));
-        self.const_string.insert(val.to_string(), idx);
+        let idx = self.add_unshared_const(c.clone());
+        self.const_pool.insert(c, idx);
         idx
     }
 
-    /// Add a raw constant (e.g., ValueMeta)
-    pub fn add_const(&mut self, c: Constant) -> u16 {
+    /// Add a constant that is patched later (e.g., itab placeholders), so it must not be shared.
+    pub fn add_unshared_const(&mut self, c: Constant) -> u16 {
         let idx = self.module.constants.len() as u16;
         self.module.constants.push(c);
         idx
@@ -985,7 +1070,9 @@ impl CodegenContext {
         let wrapper_param_slots = 1 + other_param_slots;
         
         let mut code = Vec::new();
-        let recv_reg = wrapper_param_slots;
+        // Call buffer: results, then receiver + params
+        let buf = wrapper_param_slots;
+        let recv_reg = buf + ret_slots;
         
         // ClosureGet + PtrGet: unbox receiver value
         code.push(Instruction::new(Opcode::ClosureGet, recv_reg, 0, 0));
@@ -996,17 +1083,16 @@ impl CodegenContext {
         }
         
         // Copy other params after receiver
-        let args_start = recv_reg;
         Self::emit_copy_params(&mut code, 1, recv_reg + recv_slots as u16, other_param_slots);
         
         // Call and return
         let (func_id_low, func_id_high) = crate::type_info::encode_func_id(method_func_id);
         let call_c = crate::type_info::encode_call_args(param_slots, ret_slots);
-        code.push(Instruction::with_flags(Opcode::Call, func_id_high, func_id_low, args_start, call_c));
-        code.push(Instruction::with_flags(Opcode::Return, 0, args_start, ret_slots, 0));
+        code.push(Instruction::with_flags(Opcode::Call, func_id_high, func_id_low, buf, call_c));
+        code.push(Instruction::with_flags(Opcode::Return, 0, buf, ret_slots, 0));
         
         let wrapper_name = format!("__method_value_{}_{}", method_name, method_func_id);
-        let local_slots = wrapper_param_slots + param_slots.max(ret_slots);
+        let local_slots = wrapper_param_slots + ret_slots + param_slots;
         let wrapper_id = self.register_wrapper_func(wrapper_name, wrapper_param_slots, ret_slots, local_slots, code, cache_key);
         Ok(wrapper_id)
     }
@@ -1036,24 +1122,24 @@ impl CodegenContext {
         let wrapper_param_slots = 1 + other_param_slots;
         
         let mut code = Vec::new();
-        let ptr_reg = wrapper_param_slots;
+        // Call buffer: results, then receiver + params
+        let buf = wrapper_param_slots;
+        let ptr_reg = buf + ret_slots;
         
         // ClosureGet: get pointer from capture 0
         code.push(Instruction::new(Opcode::ClosureGet, ptr_reg, 0, 0));
         
         // Copy other params after receiver
-        let args_start = ptr_reg;
         Self::emit_copy_params(&mut code, 1, ptr_reg + recv_slots, other_param_slots);
         
         // Call and return
         let (func_id_low, func_id_high) = crate::type_info::encode_func_id(method_func_id);
         let call_c = crate::type_info::encode_call_args(param_slots, ret_slots);
-        code.push(Instruction::with_flags(Opcode::Call, func_id_high, func_id_low, args_start, call_c));
-        code.push(Instruction::with_flags(Opcode::Return, 0, args_start, ret_slots, 0));
+        code.push(Instruction::with_flags(Opcode::Call, func_id_high, func_id_low, buf, call_c));
+        code.push(Instruction::with_flags(Opcode::Return, 0, buf, ret_slots, 0));
         
         let wrapper_name = format!("__method_value_ptr_{}_{}", method_name, method_func_id);
-        // Fix: use max(param_slots, ret_slots) for consistency
-        let local_slots = wrapper_param_slots + param_slots.max(ret_slots);
+        let local_slots = wrapper_param_slots + ret_slots + param_slots;
         let wrapper_id = self.register_wrapper_func(wrapper_name, wrapper_param_slots, ret_slots, local_slots, code, cache_key);
         Ok(wrapper_id)
     }
@@ -1084,17 +1170,16 @@ impl CodegenContext {
         code.push(Instruction::new(Opcode::ClosureGet, iface_reg, 0, 0));
         code.push(Instruction::new(Opcode::ClosureGet, iface_reg + 1, 1, 0));
         
-        // Copy params and call
-        let args_start = iface_reg + iface_slots;
-        Self::emit_copy_params(&mut code, 1, args_start, param_slots);
+        // Copy params into the call buffer after the results, and call
+        let buf = iface_reg + iface_slots;
+        Self::emit_copy_params(&mut code, 1, buf + ret_slots, param_slots);
         
         let call_c = crate::type_info::encode_call_args(param_slots, ret_slots);
-        code.push(Instruction::with_flags(Opcode::CallIface, method_idx as u8, iface_reg, args_start, call_c));
-        code.push(Instruction::with_flags(Opcode::Return, 0, args_start, ret_slots, 0));
+        code.push(Instruction::with_flags(Opcode::CallIface, method_idx as u8, iface_reg, buf, call_c));
+        code.push(Instruction::with_flags(Opcode::Return, 0, buf, ret_slots, 0));
         
         let wrapper_name = format!("__method_value_iface_{}_{}", method_name, method_idx);
-        // Fix: use max(param_slots, ret_slots) for consistency
-        let local_slots = wrapper_param_slots + iface_slots + param_slots.max(ret_slots);
+        let local_slots = wrapper_param_slots + iface_slots + ret_slots + param_slots;
         let wrapper_id = self.register_wrapper_func(wrapper_name, wrapper_param_slots, ret_slots, local_slots, code, cache_key);
         Ok(wrapper_id)
     }
@@ -1123,21 +1208,21 @@ impl CodegenContext {
         let mut code = Vec::new();
         let outer_ptr = wrapper_param_slots;
         let iface_reg = outer_ptr + 1;
-        let args_start = iface_reg + iface_slots;
+        let buf = iface_reg + iface_slots;
         
         // ClosureGet + PtrGet: get embedded interface from boxed outer struct
         code.push(Instruction::new(Opcode::ClosureGet, outer_ptr, 0, 0));
         code.push(Instruction::with_flags(Opcode::PtrGet, iface_slots as u8, iface_reg, outer_ptr, embed_offset));
         
-        // Copy params and call
-        Self::emit_copy_params(&mut code, 1, args_start, param_slots);
+        // Copy params into the call buffer after the results, and call
+        Self::emit_copy_params(&mut code, 1, buf + ret_slots, param_slots);
         
         let call_c = crate::type_info::encode_call_args(param_slots, ret_slots);
-        code.push(Instruction::with_flags(Opcode::CallIface, method_idx as u8, iface_reg, args_start, call_c));
-        code.push(Instruction::with_flags(Opcode::Return, 0, args_start, ret_slots, 0));
+        code.push(Instruction::with_flags(Opcode::CallIface, method_idx as u8, iface_reg, buf, call_c));
+        code.push(Instruction::with_flags(Opcode::Return, 0, buf, ret_slots, 0));
         
         let wrapper_name = format!("__method_value_embed_iface_{}_{}", method_name, method_idx);
-        let local_slots = wrapper_param_slots + 1 + iface_slots + param_slots.max(ret_slots);
+        let local_slots = wrapper_param_slots + 1 + iface_slots + ret_slots + param_slots;
         let wrapper_id = self.register_wrapper_func(wrapper_name, wrapper_param_slots, ret_slots, local_slots, code, cache_key);
         Ok(wrapper_id)
     }
@@ -1162,8 +1247,9 @@ impl CodegenContext {
         self.init_functions.push(func_id);
     }
 
-    pub fn init_functions(&self) -> &[u32] {
-        &self.init_functions
+    /// The init functions registered since the last call, in declaration order.
+    pub fn take_init_functions(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.init_functions)
     }
 
     // === Finish ===
@@ -1215,10 +1301,10 @@ impl CodegenContext {
         }
     }
 
-    /// Record debug location for current function from span.
-    /// Use this during function compilation when you have access to the span.
-    pub fn record_debug_loc(&mut self, pc: u32, span: Span, source_map: &SourceMap) {
-        if let Some(func_id) = self.current_func_id {
+    /// Record the statement locations collected by a function's builder
+    /// (`FuncBuilder::take_debug_locs`) once the function has an id.
+    pub fn add_debug_locs(&mut self, func_id: u32, locs: &[(u32, Span)], source_map: &SourceMap) {
+        for &(pc, span) in locs {
             self.add_debug_loc_from_span(func_id, pc, span, source_map);
         }
     }
@@ -123,7 +123,8 @@ pub fn compile_binary(
         _ => return Err(CodegenError::UnsupportedExpr(format!("binary op {:?}", bin.op))),
     };
 
-    func.emit_op(opcode, dst, actual_left, actual_right);
+    let flags = super::int_arith_flags(opcode, operand_type, info);
+    func.emit_with_flags(opcode, flags, dst, actual_left, actual_right);
     // float32 arithmetic result: convert f64 back to f32 bits
     // (comparison results are bool, don't need conversion)
     let is_arith = matches!(bin.op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div);
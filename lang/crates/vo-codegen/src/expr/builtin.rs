@@ -273,10 +273,15 @@ fn compile_builtin_call_impl(
         "append" => {
             // append(slice, elem...) - variadic, supports multiple elements
             // append(slice, other...) - spread: append all elements from other slice
-            if call.args.len() < 2 {
-                return Err(CodegenError::Internal("append requires at least 2 args".to_string()));
+            if call.args.is_empty() {
+                return Err(CodegenError::Internal("append requires a slice argument".to_string()));
             }
             let slice_reg = compile_expr(&call.args[0], ctx, func, info)?;
+            if call.args.len() == 1 {
+                // append(slice) appends nothing and returns slice unchanged
+                func.emit_op(Opcode::Copy, dst, slice_reg, 0);
+                return Ok(());
+            }
             
             let slice_type = info.expr_type(call.args[0].id);
             let elem_bytes = info.slice_elem_bytes(slice_type);
@@ -389,8 +394,7 @@ fn compile_builtin_call_impl(
             let (args_start, actual_count) = compile_args_as_interfaces(&call.args, ctx, func, info)?;
             
             // Record debug info for assert (may cause panic)
-            let pc = func.current_pc() as u32;
-            ctx.record_debug_loc(pc, expr.span, &info.project.source_map);
+            func.mark_span(expr.span);
             
             func.emit_with_flags(Opcode::CallExtern, (actual_count * 2) as u8, dst, extern_id as u16, args_start);
         }
@@ -12,24 +12,27 @@ use crate::type_info::TypeInfoWrapper;
 
 use super::{compile_expr, compile_expr_to};
 
-/// Allocate a call buffer with proper slot types for return values.
+/// Allocate a call buffer: the return values first, then the arguments.
 /// 
-/// Return values are written to the beginning of the buffer after the call,
-/// so the first `ret_slots` slots must have correct types for GC tracking.
-/// The remaining slots (for arguments) are typed as Value.
-fn alloc_call_buffer(func: &mut FuncBuilder, buffer_size: u16, ret_slots: u16, ret_slot_types: &[SlotType]) -> u16 {
-    if ret_slots == 0 || buffer_size == 0 {
-        return func.alloc_temp_typed(&vec![SlotType::Value; buffer_size.max(1) as usize]);
-    }
-    
-    // Build combined slot types: ret_slot_types for return value, Value for the rest
+/// Call instructions take the buffer start; arguments begin `ret_slots`
+/// slots later. Keeping the two regions apart lets both carry their real
+/// slot types, so the GC reads the arguments correctly while they are
+/// evaluated and the results once the call returns.
+pub(crate) fn alloc_call_buffer(func: &mut FuncBuilder, ret_slot_types: &[SlotType], arg_slot_types: &[SlotType]) -> u16 {
     let mut types = ret_slot_types.to_vec();
-    for _ in ret_slots..buffer_size {
+    types.extend_from_slice(arg_slot_types);
+    if types.is_empty() {
         types.push(SlotType::Value);
     }
     func.alloc_temp_typed(&types)
 }
 
+/// Slot types of the arguments passed for `param_types`.
+/// A packed variadic slice has the type of the last parameter.
+fn param_slot_types(param_types: &[TypeKey], info: &TypeInfoWrapper) -> Vec<SlotType> {
+    param_types.iter().flat_map(|&t| info.type_slot_types(t)).collect()
+}
+
 // =============================================================================
 // Call Expression - Main Entry
 // =============================================================================
@@ -43,6 +46,15 @@ pub fn compile_call(
     func: &mut FuncBuilder,
     info: &TypeInfoWrapper,
 ) -> Result<(), CodegenError> {
+    // Constant calls (conversions of constants, len of constant strings, ...)
+    // were already evaluated by the type checker.
+    if let Some(val) = super::get_const_value(expr.id, info) {
+        let target_type = info.expr_type(expr.id);
+        if !info.is_interface(target_type) {
+            return super::compile_const_value(val, dst, target_type, ctx, func, info);
+        }
+    }
+    
     // Check if method call (selector expression)
     if let ExprKind::Selector(sel) = &call.func.kind {
         return compile_method_call(expr, call, sel, dst, ctx, func, info);
@@ -74,6 +86,7 @@ pub fn compile_call(
     
     // Get return slot count for this call
     let ret_slots = info.type_slot_count(info.expr_type(expr.id));
+    let ret_slot_types = info.type_slot_types(info.expr_type(expr.id));
     
     // Get function type and parameter types for interface conversion
     let func_type = info.expr_type(call.func.id);
@@ -94,14 +107,14 @@ pub fn compile_call(
         
         if is_closure {
             let closure_reg = compile_expr(&call.func, ctx, func, info)?;
-            let args_start = func.alloc_temp_typed(&vec![SlotType::Value; total_arg_slots.max(ret_slots) as usize]);
-            compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
+            let buf = alloc_call_buffer(func, &ret_slot_types, &param_slot_types(&param_types, info));
+            compile_method_args(call, &param_types, is_variadic, buf + ret_slots, ctx, func, info)?;
             
             let c = crate::type_info::encode_call_args(total_arg_slots as u16, ret_slots as u16);
-            func.emit_op(Opcode::CallClosure, closure_reg, args_start, c);
+            func.emit_op(Opcode::CallClosure, closure_reg, buf, c);
             
-            if ret_slots > 0 && dst != args_start {
-                func.emit_copy(dst, args_start, ret_slots);
+            if ret_slots > 0 && dst != buf {
+                func.emit_copy(dst, buf, ret_slots);
             }
             return Ok(());
         }
@@ -114,21 +127,21 @@ pub fn compile_call(
             let func_idx = ctx.get_func_by_objkey(obj_key)
                 .ok_or_else(|| CodegenError::Internal(format!("function not registered: {:?}", ident.symbol)))?;
             
-            let need_slots = total_arg_slots.max(ret_slots);
-            let args_start = if ret_slots > 0 && ret_slots >= total_arg_slots {
+            // Without arguments the results can go straight to dst
+            let buf = if ret_slots > 0 && total_arg_slots == 0 {
                 dst
             } else {
-                func.alloc_temp_typed(&vec![SlotType::Value; need_slots as usize])
+                alloc_call_buffer(func, &ret_slot_types, &param_slot_types(&param_types, info))
             };
             
-            compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
+            compile_method_args(call, &param_types, is_variadic, buf + ret_slots, ctx, func, info)?;
             
             let c = crate::type_info::encode_call_args(total_arg_slots as u16, ret_slots as u16);
             let (func_id_low, func_id_high) = crate::type_info::encode_func_id(func_idx);
-            func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, args_start, c);
+            func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, buf, c);
             
-            if ret_slots > 0 && dst != args_start {
-                func.emit_copy(dst, args_start, ret_slots);
+            if ret_slots > 0 && dst != buf {
+                func.emit_copy(dst, buf, ret_slots);
             }
             return Ok(());
         } else {
@@ -165,6 +178,7 @@ pub fn compile_closure_call_from_reg(
     info: &TypeInfoWrapper,
 ) -> Result<(), CodegenError> {
     let ret_slots = info.type_slot_count(info.expr_type(expr.id)) as u16;
+    let ret_slot_types = info.type_slot_types(info.expr_type(expr.id));
     
     // Get function type from the closure expression to handle variadic properly
     let func_type = info.expr_type(call.func.id);
@@ -174,14 +188,14 @@ pub fn compile_closure_call_from_reg(
     // Calculate arg slots with variadic packing
     let total_arg_slots = calc_method_arg_slots(call, &param_types, is_variadic, info);
     
-    let args_start = func.alloc_temp_typed(&vec![SlotType::Value; total_arg_slots.max(ret_slots).max(1) as usize]);
-    compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
+    let buf = alloc_call_buffer(func, &ret_slot_types, &param_slot_types(&param_types, info));
+    compile_method_args(call, &param_types, is_variadic, buf + ret_slots, ctx, func, info)?;
     
     let c = crate::type_info::encode_call_args(total_arg_slots, ret_slots);
-    func.emit_op(Opcode::CallClosure, closure_reg, args_start, c);
+    func.emit_op(Opcode::CallClosure, closure_reg, buf, c);
     
-    if ret_slots > 0 && dst != args_start {
-        func.emit_copy(dst, args_start, ret_slots);
+    if ret_slots > 0 && dst != buf {
+        func.emit_copy(dst, buf, ret_slots);
     }
     
     Ok(())
@@ -265,7 +279,7 @@ fn compile_method_call(
     // 1. Check for package function call or type conversion (e.g., bytes.Contains, json.Number)
     if let ExprKind::Ident(pkg_ident) = &sel.expr.kind {
         // Check if it's a package reference
-        if info.package_path(pkg_ident).is_some() {
+        if let Some(pkg_path) = info.package_path(pkg_ident) {
             // Check if sel.sel refers to a type (type conversion: pkg.Type(x))
             let obj_key = info.get_use(&sel.sel);
             let obj = &info.project.tc_objs.lobjs[obj_key];
@@ -279,6 +293,17 @@ fn compile_method_call(
                 }
             }
             
+            // math and strings intrinsics compile to a single opcode
+            let func_name = info.project.interner.resolve(sel.sel.symbol).unwrap_or("");
+            if let Some((op, arity)) = intrinsic(&pkg_path, func_name) {
+                if call.args.len() == arity {
+                    let b = compile_expr(&call.args[0], ctx, func, info)?;
+                    let c = if arity == 2 { compile_expr(&call.args[1], ctx, func, info)? } else { 0 };
+                    func.emit_op(op, dst, b, c);
+                    return Ok(());
+                }
+            }
+            
             // Check if it's a Vo function (has body) or extern (no body)
             if obj.entity_type().func_has_body() {
                 // Vo function - use normal Call with proper interface conversion
@@ -286,23 +311,24 @@ fn compile_method_call(
                 let func_idx = ctx.get_func_by_objkey(obj_key)
                     .ok_or_else(|| CodegenError::Internal(format!("pkg func not registered: {:?}", sel.sel.symbol)))?;
                 let ret_slots = info.type_slot_count(info.expr_type(expr.id));
+                let ret_slot_types = info.type_slot_types(info.expr_type(expr.id));
                 let func_type = info.expr_type(call.func.id);
                 let param_types = info.func_param_types(func_type);
                 let is_variadic = info.is_variadic(func_type);
                 
                 // Compute total arg slots using PARAMETER types (handles interface conversion)
                 let total_arg_slots = calc_method_arg_slots(call, &param_types, is_variadic, info);
-                let args_start = func.alloc_temp_typed(&vec![SlotType::Value; total_arg_slots.max(ret_slots).max(1) as usize]);
+                let buf = alloc_call_buffer(func, &ret_slot_types, &param_slot_types(&param_types, info));
                 
                 // Compile arguments with interface conversion
-                compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
+                compile_method_args(call, &param_types, is_variadic, buf + ret_slots, ctx, func, info)?;
                 
                 let c = crate::type_info::encode_call_args(total_arg_slots, ret_slots);
                 let (func_id_low, func_id_high) = crate::type_info::encode_func_id(func_idx);
-                func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, args_start, c);
+                func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, buf, c);
                 
-                if ret_slots > 0 && dst != args_start {
-                    func.emit_copy(dst, args_start, ret_slots);
+                if ret_slots > 0 && dst != buf {
+                    func.emit_copy(dst, buf, ret_slots);
                 }
                 return Ok(());
             }
@@ -359,6 +385,19 @@ fn compile_method_call(
     compile_method_call_dispatch(expr, call, sel, &call_info, dst, ctx, func, info)
 }
 
+/// Package functions with a dedicated opcode, and their arity.
+fn intrinsic(pkg_path: &str, name: &str) -> Option<(Opcode, usize)> {
+    match (pkg_path, name) {
+        ("math", "Sqrt") => Some((Opcode::SqrtF, 1)),
+        ("math", "Abs") => Some((Opcode::AbsF, 1)),
+        ("math", "Min") => Some((Opcode::MinF, 2)),
+        ("math", "Max") => Some((Opcode::MaxF, 2)),
+        ("strings", "Contains") => Some((Opcode::StrContains, 2)),
+        ("strings", "Index") => Some((Opcode::StrIndexOf, 2)),
+        _ => None,
+    }
+}
+
 /// Emit interface method call (common for Interface and EmbeddedInterface dispatch).
 fn emit_interface_call(
     expr: &Expr,
@@ -377,16 +416,15 @@ fn emit_interface_call(
     let ret_type = info.expr_type(expr.id);
     let ret_slots = info.type_slot_count(ret_type);
     let ret_slot_types = info.type_slot_types(ret_type);
-    let buffer_size = arg_slots.max(ret_slots).max(1);
-    let args_start = alloc_call_buffer(func, buffer_size, ret_slots, &ret_slot_types);
+    let buf = alloc_call_buffer(func, &ret_slot_types, &param_slot_types(&param_types, info));
     
-    compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
+    compile_method_args(call, &param_types, is_variadic, buf + ret_slots, ctx, func, info)?;
     
     let c = crate::type_info::encode_call_args(arg_slots, ret_slots);
-    func.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, args_start, c);
+    func.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, buf, c);
     
-    if ret_slots > 0 && dst != args_start {
-        func.emit_copy(dst, args_start, ret_slots);
+    if ret_slots > 0 && dst != buf {
+        func.emit_copy(dst, buf, ret_slots);
     }
     Ok(())
 }
@@ -443,10 +481,15 @@ fn compile_method_call_dispatch(
             let total_slots = recv_slots + arg_slots;
             let ret_type = info.expr_type(expr.id);
             let ret_slots = info.type_slot_count(ret_type);
-            // Return values are written to beginning of buffer, use proper types for GC tracking
             let ret_slot_types = info.type_slot_types(ret_type);
-            let buffer_size = total_slots.max(ret_slots);
-            let args_start = alloc_call_buffer(func, buffer_size, ret_slots, &ret_slot_types);
+            let mut arg_slot_types = if *expects_ptr_recv {
+                vec![SlotType::GcRef]
+            } else {
+                info.type_slot_types(actual_recv_type)
+            };
+            arg_slot_types.extend(param_slot_types(&param_types, info));
+            let buf = alloc_call_buffer(func, &ret_slot_types, &arg_slot_types);
+            let args_start = buf + ret_slots;
             
             // Emit receiver
             let recv_storage = if let ExprKind::Ident(ident) = &sel.expr.kind {
@@ -465,10 +508,10 @@ fn compile_method_call_dispatch(
             // Call
             let c = crate::type_info::encode_call_args(total_slots, ret_slots);
             let (func_id_low, func_id_high) = crate::type_info::encode_func_id(*func_id);
-            func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, args_start, c);
+            func.emit_with_flags(Opcode::Call, func_id_high, func_id_low, buf, c);
             
-            if ret_slots > 0 && dst != args_start {
-                func.emit_copy(dst, args_start, ret_slots);
+            if ret_slots > 0 && dst != buf {
+                func.emit_copy(dst, buf, ret_slots);
             }
             Ok(())
         }
@@ -499,7 +542,8 @@ pub fn compile_extern_call(
     
     // Use compile_method_args for proper type conversion (e.g., boxing to `any`)
     let total_slots = calc_method_arg_slots(call, &param_types, is_variadic, info);
-    let args_start = func.alloc_temp_typed(&vec![SlotType::Value; total_slots.max(1) as usize]);
+    // Results go to dst, so the buffer only holds arguments
+    let args_start = alloc_call_buffer(func, &[], &param_slot_types(&param_types, info));
     compile_method_args(call, &param_types, is_variadic, args_start, ctx, func, info)?;
     
     func.emit_with_flags(Opcode::CallExtern, total_slots as u8, dst, extern_id as u16, args_start);
@@ -106,20 +106,25 @@ fn emit_numeric_conversion(
     let dst_is_int = info.is_int(dst_type);
     let dst_is_float = info.is_float(dst_type);
     
+    // 64-bit unsigned values don't fit the signed conversion; narrower ones
+    // are zero-extended in their slot and convert correctly either way.
+    let is_u64 = |t| matches!(info.type_value_kind(t), ValueKind::Uint | ValueKind::Uint64);
+
     if src_is_int && dst_is_float {
         // ConvI2F: int -> f64, then maybe f64 -> f32
-        func.emit_op(Opcode::ConvI2F, dst, src_reg, 0);
+        func.emit_with_flags(Opcode::ConvI2F, is_u64(src_type) as u8, dst, src_reg, 0);
         if info.is_float32(dst_type) {
             func.emit_op(Opcode::ConvF64F32, dst, dst, 0);
         }
     } else if src_is_float && dst_is_int {
         // ConvF2I: maybe f32 -> f64, then f64 -> int
+        let flags = is_u64(dst_type) as u8;
         if info.is_float32(src_type) {
             let tmp = func.alloc_temp_typed(&[SlotType::Value]);
             func.emit_op(Opcode::ConvF32F64, tmp, src_reg, 0);
-            func.emit_op(Opcode::ConvF2I, dst, tmp, 0);
+            func.emit_with_flags(Opcode::ConvF2I, flags, dst, tmp, 0);
         } else {
-            func.emit_op(Opcode::ConvF2I, dst, src_reg, 0);
+            func.emit_with_flags(Opcode::ConvF2I, flags, dst, src_reg, 0);
         }
         // Apply truncation for narrower int types
         emit_int_truncation(dst, dst, dst_type, func, info);
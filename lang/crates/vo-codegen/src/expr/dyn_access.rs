@@ -10,9 +10,16 @@ use crate::func::FuncBuilder;
 use crate::type_info::encode_i32;
 use crate::type_info::TypeInfoWrapper;
 
+use super::call::alloc_call_buffer;
 use super::compile_expr_to;
 use crate::stmt::{IFACE_ASSERT_WITH_OK, PROTOCOL_METHOD_IDX};
 
+/// Result slots of a protocol method returning (any, error).
+const PROTOCOL_RESULT_TYPES: [SlotType; 4] = [
+    SlotType::Interface0, SlotType::Interface1,
+    SlotType::Interface0, SlotType::Interface1,
+];
+
 /// Emit dyn_get_attr extern call: (base, name) -> (any, error)
 /// Returns the result register (4 slots: any[2], error[2])
 fn emit_dyn_get_attr(
@@ -135,8 +142,7 @@ pub fn compile_dyn_access(
     let is_tuple_any_error = info.is_tuple_any_error(base_type);
     
     // Record debug info
-    let pc = func.current_pc() as u32;
-    ctx.record_debug_loc(pc, expr.span, &info.project.source_map);
+    func.mark_span(expr.span);
     
     if is_tuple_any_error {
         // Short-circuit: if error slot is not nil, propagate error
@@ -195,21 +201,17 @@ fn compile_dyn_op(
                 let fallback_jump = func.emit_jump(Opcode::JumpIfNot, iface_reg + 2);
                 
                 // Protocol method call: DynAttr(name string) (any, error)
-                // Args: string[1], result overwrites args starting at args_start
-                // Result layout: (any[2], error[2]) = 4 slots
-                let args_start = func.alloc_temp_typed(&[
-                    SlotType::Interface0, SlotType::Interface1,  // result any (overwrites string arg)
-                    SlotType::Interface0, SlotType::Interface1,  // result error
-                ]);
+                // Result layout: (any[2], error[2]) = 4 slots, then the string arg
+                let buf = alloc_call_buffer(func, &PROTOCOL_RESULT_TYPES, &[SlotType::GcRef]);
                 let name_idx = ctx.const_string(field_name);
-                func.emit_op(Opcode::StrNew, args_start, name_idx, 0);
+                func.emit_op(Opcode::StrNew, buf + 4, name_idx, 0);
                 
-                // CallIface: returns (any[2], error[2]) = 4 slots, writes to args_start
+                // CallIface: returns (any[2], error[2]) = 4 slots, writes to buf
                 let c = crate::type_info::encode_call_args(1, 4);
-                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, args_start, c);
+                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, buf, c);
                 
-                // Unbox result with type check (result is at args_start)
-                emit_unbox_with_type_check(ret_type, dst, args_start, error_slot, ctx, func, info);
+                // Unbox result with type check (result is at buf)
+                emit_unbox_with_type_check(ret_type, dst, buf, error_slot, ctx, func, info);
                 let end_jump = func.emit_jump(Opcode::Jump, 0);
                 
                 func.patch_jump(fallback_jump, func.current_pc());
@@ -239,11 +241,9 @@ fn compile_dyn_op(
                 let fallback_jump = func.emit_jump(Opcode::JumpIfNot, iface_reg + 2);
                 
                 // Protocol method call: DynIndex(key any) (any, error)
-                // Allocate max(args, returns) = max(2, 4) = 4 slots
-                let args_start = func.alloc_temp_typed(&[
-                    SlotType::Interface0, SlotType::Interface1,
-                    SlotType::Interface0, SlotType::Interface1,
-                ]);
+                // Result layout: (any[2], error[2]) = 4 slots, then the key arg
+                let buf = alloc_call_buffer(func, &PROTOCOL_RESULT_TYPES, &[SlotType::Interface0, SlotType::Interface1]);
+                let args_start = buf + 4;
                 let any_type = info.any_type();
                 let key_type = info.expr_type(index_expr.id);
                 let key_slots = info.type_slot_count(key_type);
@@ -253,12 +253,12 @@ fn compile_dyn_op(
                     crate::assign::emit_assign(args_start, crate::assign::AssignSource::Expr(index_expr), any_type, ctx, func, info)?;
                 }
                 
-                // CallIface: returns (any[2], error[2]) = 4 slots, writes to args_start
+                // CallIface: returns (any[2], error[2]) = 4 slots, writes to buf
                 let c = crate::type_info::encode_call_args(2, 4);
-                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, args_start, c);
+                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, buf, c);
                 
-                // Unbox result with type check (result is at args_start)
-                emit_unbox_with_type_check(ret_type, dst, args_start, error_slot, ctx, func, info);
+                // Unbox result with type check (result is at buf)
+                emit_unbox_with_type_check(ret_type, dst, buf, error_slot, ctx, func, info);
                 let end_jump = func.emit_jump(Opcode::Jump, 0);
                 
                 func.patch_jump(fallback_jump, func.current_pc());
@@ -356,21 +356,16 @@ fn compile_dyn_op(
                     func.emit_slice_set(slice_reg, idx_reg, val_reg, elem_bytes, elem_vk, ctx);
                 }
                 
-                // Allocate args for CallIface: slice[1], but returns overwrite starting at args_start
-                // Need max(arg_slots, ret_slots) = max(1, 4) = 4 slots
-                let protocol_args = func.alloc_temp_typed(&[
-                    SlotType::Interface0, SlotType::Interface1,  // result any (overwrites slice arg)
-                    SlotType::Interface0, SlotType::Interface1,  // result error
-                ]);
-                func.emit_op(Opcode::Copy, protocol_args, slice_reg, 0);
+                // CallIface buffer: results (any[2], error[2]), then the slice arg
+                let buf = alloc_call_buffer(func, &PROTOCOL_RESULT_TYPES, &[SlotType::GcRef]);
+                func.emit_op(Opcode::Copy, buf + 4, slice_reg, 0);
                 
                 // CallIface: DynCall([]any) returns (any[2], error[2]) = 4 slots
-                // Returns overwrite args starting at protocol_args
                 let c = crate::type_info::encode_call_args(1, 4);
-                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, protocol_args, c);
+                func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, buf, c);
                 
-                // Unbox result with type check (result starts at protocol_args)
-                emit_unbox_with_type_check(ret_type, dst, protocol_args, error_slot, ctx, func, info);
+                // Unbox result with type check (result starts at buf)
+                emit_unbox_with_type_check(ret_type, dst, buf, error_slot, ctx, func, info);
                 let end_jump = func.emit_jump(Opcode::Jump, 0);
                 
                 func.patch_jump(fallback_jump, func.current_pc());
@@ -399,14 +394,10 @@ fn compile_dyn_op(
                 let fallback_jump = func.emit_jump(Opcode::JumpIfNot, iface_reg + 2);
                 
                 // Protocol method call: DynAttr(name string) (any, error)
-                // Args: string[1], result is (any[2], error[2])
-                let protocol_args = func.alloc_temp_typed(&[
-                    SlotType::GcRef,  // string arg
-                    SlotType::Interface0, SlotType::Interface1,  // result any
-                    SlotType::Interface0, SlotType::Interface1,  // result error
-                ]);
+                // Result layout: (any[2], error[2]) = 4 slots, then the string arg
+                let protocol_args = alloc_call_buffer(func, &PROTOCOL_RESULT_TYPES, &[SlotType::GcRef]);
                 let name_idx = ctx.const_string(method_name);
-                func.emit_op(Opcode::StrNew, protocol_args, name_idx, 0);
+                func.emit_op(Opcode::StrNew, protocol_args + 4, name_idx, 0);
                 
                 let c = crate::type_info::encode_call_args(1, 4);
                 func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, protocol_args, c);
@@ -242,7 +242,7 @@ fn compile_struct_lit(
 
 /// Resolve element index from CompositeLitElem key.
 /// Updates current_index for next unkeyed element.
-fn resolve_elem_index(
+pub(crate) fn resolve_elem_index(
     elem: &vo_syntax::ast::CompositeLitElem,
     current_index: &mut u64,
     info: &TypeInfoWrapper,
@@ -454,6 +454,7 @@ pub fn compile_func_lit(
             // Add param type for each named parameter
             closure_builder.add_param_type(meta_raw, slots);
             if info.needs_boxing(obj_key, type_key) {
+                ctx.note_heap_local(closure_builder.name(), obj_key, info);
                 escaped_params.push((name.symbol, type_key, slots, slot_types.clone()));
             }
         }
@@ -514,6 +515,7 @@ pub fn compile_func_lit(
             let escapes = any_escapes || info.is_escaped(obj_key);
             
             let slot = if escapes {
+                ctx.note_heap_local(closure_builder.name(), obj_key, info);
                 let gcref_slot = closure_builder.define_local_heap_boxed(name.symbol, slots, info.is_pointer(result_type));
                 escaped_returns.push(EscapedReturn { gcref_slot, slots, result_type });
                 gcref_slot
@@ -540,8 +542,10 @@ pub fn compile_func_lit(
     closure_builder.emit_op(Opcode::Return, 0, 0, 0);
     
     // Build and add closure function to module
+    let locs = closure_builder.take_debug_locs();
     let closure_func = closure_builder.build();
     let func_id = ctx.add_function(closure_func);
+    ctx.add_debug_locs(func_id, &locs, &info.project.source_map);
     
     // Emit ClosureNew instruction
     let capture_count = captures.len() as u16;
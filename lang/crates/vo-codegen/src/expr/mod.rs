@@ -589,3 +589,30 @@ pub fn emit_int_trunc(
         _ => {} // No truncation needed for Int64, Uint64, and Int/Uint on 64-bit
     }
 }
+
+/// Flags for an integer arithmetic instruction on `type_key` operands.
+/// AddI/SubI/MulI on unsigned operands are marked so checked JIT arithmetic
+/// lets them wrap, and on narrow signed ones carry the width it checks.
+pub fn int_arith_flags(
+    op: Opcode,
+    type_key: vo_analysis::objects::TypeKey,
+    info: &TypeInfoWrapper,
+) -> u8 {
+    use vo_runtime::ValueKind;
+    use vo_vm::instruction::{ARITH_FLAG_UNSIGNED, ARITH_FLAG_WIDTH_SHIFT};
+    if !matches!(op, Opcode::AddI | Opcode::SubI | Opcode::MulI) {
+        return 0;
+    }
+    if info.is_unsigned(type_key) {
+        return ARITH_FLAG_UNSIGNED;
+    }
+    let width: u8 = match info.type_value_kind(type_key) {
+        ValueKind::Int8 => 1,
+        ValueKind::Int16 => 2,
+        ValueKind::Int32 => 4,
+        #[cfg(target_pointer_width = "32")]
+        ValueKind::Int => 4,
+        _ => 0,
+    };
+    width << ARITH_FLAG_WIDTH_SHIFT
+}
@@ -1,9 +1,11 @@
 //! Function builder - manages function-level codegen state.
 
+use std::cell::Cell;
 use std::collections::HashMap;
+use vo_common::span::Span;
 use vo_common::symbol::Symbol;
 use vo_common_core::instruction::{
-    HINT_LOOP_BEGIN, HINT_LOOP_END, HINT_LOOP_META,
+    HINT_LOOP_BEGIN, HINT_LOOP_END, HINT_LOOP_META, HINT_NOP,
     LOOP_FLAG_HAS_DEFER, LOOP_FLAG_HAS_LABELED_BREAK, LOOP_FLAG_HAS_LABELED_CONTINUE,
 };
 use vo_runtime::SlotType;
@@ -132,6 +134,9 @@ pub struct FuncBuilder {
     named_return_slots: Vec<(u16, u16, bool)>,    // (slot, slots, escaped) for named return variables
     slot_types: Vec<SlotType>,
     code: Vec<Instruction>,
+    // Highest pc handed out by current_pc(). An instruction emitted there may
+    // be a jump target, so it must not be folded into the one before it.
+    observed_pc: Cell<usize>,
     loop_stack: Vec<LoopContext>,
     return_types: Vec<vo_analysis::objects::TypeKey>,
     // Label support for goto
@@ -152,6 +157,8 @@ pub struct FuncBuilder {
     // Parameter types for cross-island transfer.
     // Each entry: (ValueMeta raw, slot_count) for one parameter.
     param_types: Vec<(u32, u16)>,
+    // (first pc, span) of each statement, resolved to debug info once the function has an id.
+    debug_locs: Vec<(u32, Span)>,
 }
 
 impl FuncBuilder {
@@ -168,6 +175,7 @@ impl FuncBuilder {
             named_return_slots: Vec::new(),
             slot_types: Vec::new(),
             code: Vec::new(),
+            observed_pc: Cell::new(0),
             loop_stack: Vec::new(),
             return_types: Vec::new(),
             labels: HashMap::new(),
@@ -177,6 +185,7 @@ impl FuncBuilder {
             error_ret_slot: -1,
             capture_types: Vec::new(),
             param_types: Vec::new(),
+            debug_locs: Vec::new(),
         }
     }
 
@@ -257,6 +266,10 @@ impl FuncBuilder {
         self.emit_ptr_set(gcref_slot, 0, param_slot, value_slots);
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     // === Local variable definition ===
 
     /// Bind a symbol to a storage location. Handles shadowing automatically.
@@ -414,15 +427,35 @@ impl FuncBuilder {
     // === Instruction emission ===
 
     pub fn emit(&mut self, inst: Instruction) {
-        self.code.push(inst);
+        self.push_inst(inst);
     }
 
     pub fn emit_op(&mut self, op: Opcode, a: u16, b: u16, c: u16) {
-        self.code.push(Instruction::new(op, a, b, c));
+        self.push_inst(Instruction::new(op, a, b, c));
     }
 
     pub fn emit_with_flags(&mut self, op: Opcode, flags: u8, a: u16, b: u16, c: u16) {
-        self.code.push(Instruction::with_flags(op, flags, a, b, c));
+        self.push_inst(Instruction::with_flags(op, flags, a, b, c));
+    }
+
+    /// Append an instruction, dropping copies that cannot change any slot:
+    /// `Copy x, x`, and `Copy a, b` right after `Copy b, a` when nothing can
+    /// jump between the two.
+    fn push_inst(&mut self, inst: Instruction) {
+        match inst.opcode() {
+            Opcode::Copy | Opcode::CopyN if inst.a == inst.b => return,
+            Opcode::Copy => {
+                let pc = self.code.len();
+                let undone = self.code.last().is_some_and(|last| {
+                    last.opcode() == Opcode::Copy && last.a == inst.b && last.b == inst.a
+                });
+                if undone && self.observed_pc.get() < pc {
+                    return;
+                }
+            }
+            _ => {}
+        }
+        self.code.push(inst);
     }
 
     /// Emit ClosureNew with proper func_id encoding (handles func_id > 65535)
@@ -698,10 +731,30 @@ impl FuncBuilder {
         }
     }
 
+    // === Debug locations ===
+
+    /// Attribute the code emitted from here on to `span`.
+    /// A nested statement starting at the same pc replaces its parent.
+    /// The pc comes from `current_pc`, so no copy is folded across it.
+    pub fn mark_span(&mut self, span: Span) {
+        let pc = self.current_pc() as u32;
+        match self.debug_locs.last_mut() {
+            Some(last) if last.0 == pc => last.1 = span,
+            _ => self.debug_locs.push((pc, span)),
+        }
+    }
+
+    /// Take the spans recorded by `mark_span`.
+    pub fn take_debug_locs(&mut self) -> Vec<(u32, Span)> {
+        std::mem::take(&mut self.debug_locs)
+    }
+
     // === Jump ===
 
     pub fn current_pc(&self) -> usize {
-        self.code.len()
+        let pc = self.code.len();
+        self.observed_pc.set(pc);
+        pc
     }
 
     /// Emit jump, return position to patch later.
@@ -1028,6 +1081,8 @@ impl FuncBuilder {
             self.patch_jump(jump_pc, target_pc);
         }
         
+        self.trim_unreachable_tail();
+        
         // Ensure local_slots is at least ret_slots (for return value)
         let local_slots = self.next_slot.max(self.ret_slots);
         
@@ -1062,6 +1117,51 @@ impl FuncBuilder {
         }
     }
     
+    /// Drop unreachable instructions at the end of the code, such as the
+    /// implicit `Return` after a body whose every path already returned.
+    ///
+    /// Only the tail is removed, so no live jump offset or debug location
+    /// moves. Loop hints are kept, and the code must still end in `Return`/`Jump`.
+    fn trim_unreachable_tail(&mut self) {
+        let len = self.code.len();
+        let mut reachable = vec![false; len];
+        let mut work = vec![0usize];
+        while let Some(pc) = work.pop() {
+            if pc >= len || reachable[pc] {
+                continue;
+            }
+            reachable[pc] = true;
+            let inst = &self.code[pc];
+            let op = inst.opcode();
+            if matches!(op, Opcode::Jump | Opcode::JumpIf | Opcode::JumpIfNot) {
+                work.push((pc as i64 + inst.imm32() as i64) as usize);
+            }
+            if op == Opcode::JumpTable {
+                work.extend(pc + 1..=pc + 1 + inst.b as usize);
+            }
+            if !matches!(op, Opcode::Return | Opcode::Jump) {
+                work.push(pc + 1);
+            }
+        }
+        
+        let mut end = len;
+        while end > 1 && !reachable[end - 1] && self.code[end - 1].opcode() != Opcode::Hint {
+            end -= 1;
+        }
+        if end < len && matches!(self.code[end - 1].opcode(), Opcode::Return | Opcode::Jump) {
+            self.code.truncate(end);
+            // Dead jumps into the removed tail (e.g. an if arm's jump past
+            // the else) would point out of range: make them NOPs
+            for pc in 0..end {
+                let inst = &self.code[pc];
+                let is_jump = matches!(inst.opcode(), Opcode::Jump | Opcode::JumpIf | Opcode::JumpIfNot);
+                if !reachable[pc] && is_jump && pc as i64 + inst.imm32() as i64 >= end as i64 {
+                    self.code[pc] = Instruction::with_flags(Opcode::Hint, HINT_NOP, 0, 0, 0);
+                }
+            }
+        }
+    }
+    
     /// Add a capture type for cross-island serialization.
     pub fn add_capture_type(&mut self, meta_raw: u32, slots: u16) {
         self.capture_types.push((meta_raw, slots));
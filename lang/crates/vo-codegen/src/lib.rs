@@ -14,7 +14,7 @@ mod type_info;
 mod type_interner;
 mod wrapper;
 
-pub use context::CodegenContext;
+pub use context::{CodegenContext, EscapeNote};
 pub use error::CodegenError;
 pub use func::FuncBuilder;
 pub use type_info::TypeInfoWrapper;
@@ -24,45 +24,86 @@ use vo_analysis::Project;
 use vo_syntax::ast::Decl;
 use vo_vm::bytecode::Module;
 
+/// Options for `compile_project_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// Report every local variable placed on the heap because it escapes.
+    pub escape_notes: bool,
+    /// Compile main one file at a time, in file order, each file initializing
+    /// its own globals. A file may then only depend on the files before it,
+    /// and the module compiled from files `f1..fn` starts with the module
+    /// compiled from `f1..fn-1`, so a running VM can take it with `Vm::reload`.
+    pub file_units: bool,
+    /// Itab ids a running VM has handed out at runtime (`Vm::runtime_itabs`).
+    /// Compile-time itabs skip them, leaving placeholders in their place.
+    pub runtime_itabs: Vec<u32>,
+}
+
 /// Compile a type-checked project to VM bytecode.
 pub fn compile_project(project: &Project) -> Result<Module, CodegenError> {
+    compile_project_with_options(project, &CodegenOptions::default()).map(|(module, _)| module)
+}
+
+/// Compile with custom options. Escape notes are empty unless enabled.
+pub fn compile_project_with_options(
+    project: &Project,
+    options: &CodegenOptions,
+) -> Result<(Module, Vec<EscapeNote>), CodegenError> {
     let info = TypeInfoWrapper::for_main_package(project);
     let pkg_name = "main"; // TODO: get from project
     let mut ctx = CodegenContext::new(pkg_name);
+    if options.escape_notes {
+        ctx.enable_escape_notes();
+    }
+    ctx.reserve_itabs(&options.runtime_itabs);
     
-    // 1. Register types (StructMeta, InterfaceMeta)
+    // 1. Register universe types (error, builtin protocols)
     register_types(project, &mut ctx, &info)?;
     
-    // 2. Collect declarations (functions, globals, externs)
-    collect_declarations(project, &mut ctx, &info)?;
-    
-    // 3. Compile functions
-    compile_functions(project, &mut ctx, &info)?;
-    
-    // 4. Generate __init__ and __entry__
-    compile_init_and_entry(project, &mut ctx, &info)?;
+    // 2. Per unit, dependencies first and main last: register types
+    // (StructMeta, InterfaceMeta), collect declarations (functions, globals),
+    // compile functions, record methods in NamedTypeMeta (direct ones, then
+    // promoted ones, which need the direct ones), then generate the unit's
+    // __init__. Finishing each unit before the next keeps the ids it is given
+    // independent of the units after it, so appending to main never
+    // renumbers anything already compiled.
+    let mut unit_inits = Vec::new();
+    for unit in compile_units(project, options.file_units) {
+        register_pkg_types(unit.pkg_path, unit.files, project, &mut ctx, &unit.info)?;
+        let first_global = ctx.global_slots();
+        for file in unit.files {
+            collect_file_declarations(file, project, &mut ctx, &unit.info)?;
+        }
+        let globals = first_global..ctx.global_slots();
+        let mut method_mappings = Vec::new();
+        for file in unit.files {
+            compile_file_functions(file, project, &mut ctx, &unit.info, &mut method_mappings)?;
+        }
+        update_named_type_methods(&mut ctx, &method_mappings, &info);
+        collect_promoted_methods(project, &mut ctx, &info);
+        unit_inits.push(compile_unit_init(&mut ctx, &unit.info, globals)?);
+    }
     
-    // 5. Collect promoted methods from embedded interfaces
-    // This must happen after compile_functions (direct methods registered)
-    // and before finalize_itabs (itabs need complete method set)
-    collect_promoted_methods(project, &mut ctx, &info);
+    // 3. Generate __entry__
+    compile_entry(&mut ctx, &unit_inits);
     
-    // 6. Build all pending itabs (from functions + __init__)
+    // 4. Build all pending itabs (from functions + __init__)
     ctx.finalize_itabs(&info.project.tc_objs, &info.project.interner);
     
-    // 7. Build runtime_types after all codegen (all types have been assigned rttid)
+    // 5. Build runtime_types after all codegen (all types have been assigned rttid)
     build_runtime_types(project, &mut ctx, &info);
     
-    // 8. Fill WellKnownTypes for fast error creation
+    // 6. Fill WellKnownTypes for fast error creation
     ctx.fill_well_known_types();
     
-    // 9. Finalize debug info (sort entries by PC)
+    // 7. Finalize debug info (sort entries by PC)
     ctx.finalize_debug_info();
     
-    // 10. Final check: all IDs within 24-bit limit
+    // 8. Final check: all IDs within 24-bit limit
     ctx.check_id_limits().map_err(CodegenError::Internal)?;
     
-    Ok(ctx.finish())
+    let notes = ctx.take_escape_notes();
+    Ok((ctx.finish(), notes))
 }
 
 fn register_types(
@@ -71,189 +112,6 @@ fn register_types(
     info: &TypeInfoWrapper,
 ) -> Result<(), CodegenError> {
     use vo_vm::bytecode::InterfaceMeta;
-    
-    
-
-    fn register_pkg_types(
-        pkg_path: &str,
-        files: &[vo_syntax::ast::File],
-        project: &Project,
-        ctx: &mut CodegenContext,
-        info: &TypeInfoWrapper,
-    ) -> Result<(), CodegenError> {
-        use vo_syntax::ast::{Decl, TypeExprKind};
-        use vo_vm::bytecode::{StructMeta, InterfaceMeta, NamedTypeMeta};
-        use vo_runtime::ValueMeta;
-        use std::collections::HashMap;
-
-        // Collect all type declarations (including those inside functions)
-        let mut type_decls = Vec::new();
-
-        for file in files {
-            for decl in &file.decls {
-                match decl {
-                    Decl::Type(type_decl) => {
-                        type_decls.push(type_decl.clone());
-                    }
-                    Decl::Func(func_decl) => {
-                        // Also collect type declarations from function bodies
-                        if let Some(body) = &func_decl.body {
-                            collect_type_decls_from_stmts(&body.stmts, &mut type_decls);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        // Process all collected type declarations
-        // Named types are dynamically registered during intern_type_key if not already present
-        for type_decl in &type_decls {
-            let type_name = project
-                .interner
-                .resolve(type_decl.name.symbol)
-                .unwrap_or("?");
-            let qualified_type_name = format!("{}.{}", pkg_path, type_name);
-
-            // Get underlying type key from type expression, and obj_key from declaration name
-            let underlying_key = info.type_expr_type(type_decl.ty.id);
-            let obj_key = info.get_def(&type_decl.name);
-            let named_key = info.obj_type(obj_key, "type declaration must have type");
-
-            // Register type-specific metadata first
-            let underlying_meta = match &type_decl.ty.kind {
-                TypeExprKind::Struct(struct_type) => {
-                    // Build StructMeta with FieldMeta
-                    let mut fields = Vec::new();
-                    let mut slot_types = Vec::new();
-                    let mut offset = 0u16;
-
-                    for field in &struct_type.fields {
-                        let field_type = info.type_expr_type(field.ty.id);
-                        let slot_count = info.type_slot_count(field_type);
-                        let slot_type_list = info.type_slot_types(field_type);
-                        let field_vk = info.type_value_kind(field_type);
-                        let field_rttid = ctx.intern_type_key(field_type, info);
-                        let mut tag = field.tag.as_ref().map(|t| t.value.clone());
-                        
-                        if field.names.is_empty() {
-                            // Embedded field: name comes from the type
-                            let field_name = info.get_type_name(field_type);
-                            slot_types.extend(slot_type_list);
-                            fields.push(vo_vm::bytecode::FieldMeta {
-                                name: field_name,
-                                offset,
-                                slot_count,
-                                type_info: vo_runtime::ValueRttid::new(field_rttid, field_vk),
-                                embedded: true,
-                                tag,
-                            });
-                            offset += slot_count;
-                        } else {
-                            // Named field(s) - tag is shared among all names
-                            let names_count = field.names.len();
-                            for (i, name) in field.names.iter().enumerate() {
-                                let field_name = project.interner.resolve(name.symbol).unwrap_or("?").to_string();
-                                slot_types.extend(slot_type_list.clone());
-                                fields.push(vo_vm::bytecode::FieldMeta {
-                                    name: field_name,
-                                    offset,
-                                    slot_count,
-                                    type_info: vo_runtime::ValueRttid::new(field_rttid, field_vk),
-                                    embedded: false,
-                                    tag: if i == names_count - 1 { tag.take() } else { tag.clone() },
-                                });
-                                offset += slot_count;
-                            }
-                        }
-                    }
-
-                    // Empty struct still needs 1 slot for zero-size type workaround
-                    if slot_types.is_empty() {
-                        slot_types.push(vo_runtime::SlotType::Value);
-                    }
-                    let field_index: std::collections::HashMap<String, usize> = fields.iter()
-                        .enumerate()
-                        .map(|(i, f)| (f.name.clone(), i))
-                        .collect();
-                    let meta = StructMeta { slot_types, fields, field_index };
-                    let struct_meta_id = ctx.register_struct_meta(underlying_key, meta);
-                    ctx.alias_struct_meta_id(named_key, struct_meta_id);
-                    ValueMeta::new(struct_meta_id as u32, vo_runtime::ValueKind::Struct)
-                }
-                TypeExprKind::Interface(_) => {
-                    // Build InterfaceMeta
-                    let tc_objs = &info.project.tc_objs;
-                    let (method_names, methods) =
-                        if let vo_analysis::typ::Type::Interface(iface) = &tc_objs.types[underlying_key] {
-                            let all_methods_ref = iface.all_methods();
-                            let method_objs: Vec<vo_analysis::objects::ObjKey> =
-                                if let Some(methods) = all_methods_ref.as_ref() {
-                                    methods.iter().cloned().collect()
-                                } else {
-                                    iface.methods().iter().cloned().collect()
-                                };
-
-                            let names: Vec<String> = method_objs
-                                .iter()
-                                .map(|m| tc_objs.lobjs[*m].name().to_string())
-                                .collect();
-
-                            let metas: Vec<vo_vm::bytecode::InterfaceMethodMeta> = method_objs
-                                .iter()
-                                .map(|&m| {
-                                    let obj = &tc_objs.lobjs[m];
-                                    let name = obj.name().to_string();
-                                    let sig = if let Some(sig_type) = obj.typ() {
-                                        signature_type_to_runtime_type(sig_type, tc_objs, info, ctx)
-                                    } else {
-                                        vo_runtime::RuntimeType::Func {
-                                            params: Vec::new(),
-                                            results: Vec::new(),
-                                            variadic: false,
-                                        }
-                                    };
-                                    let signature_rttid = ctx.intern_rttid(sig);
-                                    vo_vm::bytecode::InterfaceMethodMeta { name, signature_rttid }
-                                })
-                                .collect();
-
-                            (names, metas)
-                        } else {
-                            (Vec::new(), Vec::new())
-                        };
-
-                    let meta = InterfaceMeta {
-                        name: qualified_type_name.to_string(),
-                        method_names,
-                        methods,
-                    };
-                    let iface_meta_id = ctx.register_interface_meta(underlying_key, meta);
-                    ValueMeta::new(iface_meta_id as u32, vo_runtime::ValueKind::Interface)
-                }
-                _ => {
-                    // Other types (Map, Slice, Chan, etc.): intern underlying type to get rttid
-                    let underlying_vk = info.type_value_kind(underlying_key);
-                    let underlying_rt = info.type_to_runtime_type(underlying_key, ctx);
-                    let underlying_rttid = ctx.intern_rttid(underlying_rt);
-                    ValueMeta::new(underlying_rttid, underlying_vk)
-                }
-            };
-
-            // All named types get NamedTypeMeta (keyed by ObjKey, the true identity)
-            let named_type_meta = NamedTypeMeta {
-                name: qualified_type_name.to_string(),
-                underlying_meta,
-                methods: HashMap::new(),
-            };
-            ctx.register_named_type_meta(obj_key, named_type_meta);
-        }
-
-        // Finalize runtime types: fill meta_id fields after all types are registered
-        ctx.finalize_runtime_types();
-
-        Ok(())
-    }
 
     // Ensure builtin error interface has a stable InterfaceMeta name for runtime lookup.
     {
@@ -300,21 +158,202 @@ fn register_types(
             };
             ctx.register_interface_meta(underlying, meta);
         }
+        // error is a named type too: give it its own NamedTypeMeta so rttids naming it resolve
+        if let Some(&obj_key) = tc_objs.types[error_type].try_as_named().and_then(|n| n.obj().as_ref()) {
+            let iface_meta_id = ctx.get_interface_meta_id(underlying).unwrap_or(0);
+            ctx.register_named_type_meta(obj_key, vo_vm::bytecode::NamedTypeMeta {
+                name: "error".to_string(),
+                underlying_meta: vo_runtime::ValueMeta::new(iface_meta_id, vo_runtime::ValueKind::Interface),
+                methods: std::collections::HashMap::new(),
+            });
+        }
     }
     
     // Register builtin protocol interfaces (DynAttr, DynSetAttr, etc.)
     // These don't depend on user imports - they're language-level semantics.
     register_builtin_protocols(project, ctx, info);
-    
-    register_pkg_types("main", &project.files, project, ctx, info)?;
 
-    for (pkg_path, files) in &project.imported_files {
-        if let Some(pkg_type_info) = project.imported_type_infos.get(pkg_path) {
-            let pkg_info = TypeInfoWrapper::for_package(project, pkg_type_info);
-            register_pkg_types(pkg_path, files, project, ctx, &pkg_info)?;
+    Ok(())
+}
+
+fn register_pkg_types(
+    pkg_path: &str,
+    files: &[vo_syntax::ast::File],
+    project: &Project,
+    ctx: &mut CodegenContext,
+    info: &TypeInfoWrapper,
+) -> Result<(), CodegenError> {
+    use vo_syntax::ast::{Decl, TypeExprKind};
+    use vo_vm::bytecode::{StructMeta, InterfaceMeta, NamedTypeMeta};
+    use vo_runtime::ValueMeta;
+    use std::collections::HashMap;
+
+    // Collect all type declarations (including those inside functions)
+    let mut type_decls = Vec::new();
+
+    for file in files {
+        for decl in &file.decls {
+            match decl {
+                Decl::Type(type_decl) => {
+                    type_decls.push(type_decl.clone());
+                }
+                Decl::Func(func_decl) => {
+                    // Also collect type declarations from function bodies
+                    if let Some(body) = &func_decl.body {
+                        collect_type_decls_from_stmts(&body.stmts, &mut type_decls);
+                    }
+                }
+                _ => {}
+            }
         }
     }
 
+    // Process all collected type declarations
+    // Named types are dynamically registered during intern_type_key if not already present
+    for type_decl in &type_decls {
+        let type_name = project
+            .interner
+            .resolve(type_decl.name.symbol)
+            .unwrap_or("?");
+        let qualified_type_name = format!("{}.{}", pkg_path, type_name);
+
+        // Get underlying type key from type expression, and obj_key from declaration name
+        let underlying_key = info.type_expr_type(type_decl.ty.id);
+        let obj_key = info.get_def(&type_decl.name);
+        let named_key = info.obj_type(obj_key, "type declaration must have type");
+
+        // Register type-specific metadata first
+        let underlying_meta = match &type_decl.ty.kind {
+            TypeExprKind::Struct(struct_type) => {
+                // Build StructMeta with FieldMeta
+                let mut fields = Vec::new();
+                let mut slot_types = Vec::new();
+                let mut offset = 0u16;
+
+                for field in &struct_type.fields {
+                    let field_type = info.type_expr_type(field.ty.id);
+                    let slot_count = info.type_slot_count(field_type);
+                    let slot_type_list = info.type_slot_types(field_type);
+                    let field_vk = info.type_value_kind(field_type);
+                    let field_rttid = ctx.intern_type_key(field_type, info);
+                    let mut tag = field.tag.as_ref().map(|t| t.value.clone());
+                    
+                    if field.names.is_empty() {
+                        // Embedded field: name comes from the type
+                        let field_name = info.get_type_name(field_type);
+                        slot_types.extend(slot_type_list);
+                        fields.push(vo_vm::bytecode::FieldMeta {
+                            name: field_name,
+                            offset,
+                            slot_count,
+                            type_info: vo_runtime::ValueRttid::new(field_rttid, field_vk),
+                            embedded: true,
+                            tag,
+                        });
+                        offset += slot_count;
+                    } else {
+                        // Named field(s) - tag is shared among all names
+                        let names_count = field.names.len();
+                        for (i, name) in field.names.iter().enumerate() {
+                            let field_name = project.interner.resolve(name.symbol).unwrap_or("?").to_string();
+                            slot_types.extend(slot_type_list.clone());
+                            fields.push(vo_vm::bytecode::FieldMeta {
+                                name: field_name,
+                                offset,
+                                slot_count,
+                                type_info: vo_runtime::ValueRttid::new(field_rttid, field_vk),
+                                embedded: false,
+                                tag: if i == names_count - 1 { tag.take() } else { tag.clone() },
+                            });
+                            offset += slot_count;
+                        }
+                    }
+                }
+
+                // Empty struct still needs 1 slot for zero-size type workaround
+                if slot_types.is_empty() {
+                    slot_types.push(vo_runtime::SlotType::Value);
+                }
+                let field_index: std::collections::HashMap<String, usize> = fields.iter()
+                    .enumerate()
+                    .map(|(i, f)| (f.name.clone(), i))
+                    .collect();
+                let meta = StructMeta { slot_types, fields, field_index };
+                let struct_meta_id = ctx.register_struct_meta(underlying_key, meta);
+                ctx.alias_struct_meta_id(named_key, struct_meta_id);
+                ValueMeta::new(struct_meta_id as u32, vo_runtime::ValueKind::Struct)
+            }
+            TypeExprKind::Interface(_) => {
+                // Build InterfaceMeta
+                let tc_objs = &info.project.tc_objs;
+                let (method_names, methods) =
+                    if let vo_analysis::typ::Type::Interface(iface) = &tc_objs.types[underlying_key] {
+                        let all_methods_ref = iface.all_methods();
+                        let method_objs: Vec<vo_analysis::objects::ObjKey> =
+                            if let Some(methods) = all_methods_ref.as_ref() {
+                                methods.iter().cloned().collect()
+                            } else {
+                                iface.methods().iter().cloned().collect()
+                            };
+
+                        let names: Vec<String> = method_objs
+                            .iter()
+                            .map(|m| tc_objs.lobjs[*m].name().to_string())
+                            .collect();
+
+                        let metas: Vec<vo_vm::bytecode::InterfaceMethodMeta> = method_objs
+                            .iter()
+                            .map(|&m| {
+                                let obj = &tc_objs.lobjs[m];
+                                let name = obj.name().to_string();
+                                let sig = if let Some(sig_type) = obj.typ() {
+                                    signature_type_to_runtime_type(sig_type, tc_objs, info, ctx)
+                                } else {
+                                    vo_runtime::RuntimeType::Func {
+                                        params: Vec::new(),
+                                        results: Vec::new(),
+                                        variadic: false,
+                                    }
+                                };
+                                let signature_rttid = ctx.intern_rttid(sig);
+                                vo_vm::bytecode::InterfaceMethodMeta { name, signature_rttid }
+                            })
+                            .collect();
+
+                        (names, metas)
+                    } else {
+                        (Vec::new(), Vec::new())
+                    };
+
+                let meta = InterfaceMeta {
+                    name: qualified_type_name.to_string(),
+                    method_names,
+                    methods,
+                };
+                let iface_meta_id = ctx.register_interface_meta(underlying_key, meta);
+                ValueMeta::new(iface_meta_id as u32, vo_runtime::ValueKind::Interface)
+            }
+            _ => {
+                // Other types (Map, Slice, Chan, etc.): intern underlying type to get rttid
+                let underlying_vk = info.type_value_kind(underlying_key);
+                let underlying_rt = info.type_to_runtime_type(underlying_key, ctx);
+                let underlying_rttid = ctx.intern_rttid(underlying_rt);
+                ValueMeta::new(underlying_rttid, underlying_vk)
+            }
+        };
+
+        // All named types get NamedTypeMeta (keyed by ObjKey, the true identity)
+        let named_type_meta = NamedTypeMeta {
+            name: qualified_type_name.to_string(),
+            underlying_meta,
+            methods: HashMap::new(),
+        };
+        ctx.register_named_type_meta(obj_key, named_type_meta);
+    }
+
+    // Finalize runtime types: fill meta_id fields after all types are registered
+    ctx.finalize_runtime_types();
+
     Ok(())
 }
 
@@ -464,26 +503,55 @@ fn build_runtime_types(
     ctx.set_runtime_types(runtime_types);
 }
 
-fn collect_declarations(
-    project: &Project,
-    ctx: &mut CodegenContext,
-    info: &TypeInfoWrapper,
-) -> Result<(), CodegenError> {
-    // First collect main package declarations (using main type_info)
-    for file in &project.files {
-        collect_file_declarations(file, project, ctx, info)?;
+/// A package, or with `CodegenOptions::file_units` a single file of main,
+/// compiled as a whole before the next one starts.
+struct Unit<'a> {
+    pkg_path: &'a str,
+    files: &'a [vo_syntax::ast::File],
+    info: TypeInfoWrapper<'a>,
+}
+
+/// Every unit in compile order: imported packages in dependency order, then
+/// main. With `file_units`, each file of main instead follows just the
+/// packages it needs, so the units for a file never depend on the files
+/// after it.
+fn compile_units(project: &Project, file_units: bool) -> Vec<Unit<'_>> {
+    let packages: Vec<_> = project.imported_packages_in_order().into_iter()
+        .filter_map(|(pkg_path, pkg_type_info)| {
+            let files = project.imported_files.get(pkg_path)?;
+            Some(Unit { pkg_path, files: files.as_slice(), info: TypeInfoWrapper::for_package(project, pkg_type_info) })
+        })
+        .collect();
+    let main_info = || TypeInfoWrapper::for_main_package(project);
+    if !file_units {
+        let mut units = packages;
+        units.push(Unit { pkg_path: "main", files: project.files.as_slice(), info: main_info() });
+        return units;
     }
-    
-    // Then collect imported package declarations (using their respective type_info)
-    for (pkg_path, files) in &project.imported_files {
-        if let Some(pkg_type_info) = project.imported_type_infos.get(pkg_path) {
-            let pkg_info = TypeInfoWrapper::for_package(project, pkg_type_info);
-            for file in files {
-                collect_file_declarations(file, project, ctx, &pkg_info)?;
-            }
+
+    // Packages are in dependency order, so emitting every package up to the
+    // last one a file imports covers that file's imports and theirs. The
+    // always-linked core packages come before any file.
+    let paths: Vec<&str> = packages.iter().map(|unit| unit.pkg_path).collect();
+    let position = |path: &str| paths.iter().position(|&p| p == path);
+    let mut packages = packages.into_iter();
+    let mut units = Vec::new();
+    let mut emitted = position("errors").map_or(0, |i| i + 1);
+    units.extend(packages.by_ref().take(emitted));
+    for file in &project.files {
+        let upto = file.imports.iter()
+            .map(|import| position(&import.path.value).map_or(paths.len(), |i| i + 1))
+            .max()
+            .unwrap_or(0);
+        if upto > emitted {
+            units.extend(packages.by_ref().take(upto - emitted));
+            emitted = upto;
         }
+        units.push(Unit { pkg_path: "main", files: std::slice::from_ref(file), info: main_info() });
     }
-    Ok(())
+    // Unreachable when every import matched, but never leave a package out
+    units.extend(packages);
+    units
 }
 
 fn collect_file_declarations(
@@ -523,7 +591,12 @@ fn collect_file_declarations(
                 // Register global variables (so functions can reference them)
                 for spec in &var_decl.specs {
                     for (i, name) in spec.names.iter().enumerate() {
-                        let type_key = if let Some(ty) = &spec.ty {
+                        let obj_key = info.get_def(name);
+                        // Prefer the declared object's type: it is correct for
+                        // `var a, b = f()` where there is no per-name value expr.
+                        let type_key = if let Some(t) = info.project.tc_objs.lobjs[obj_key].typ() {
+                            t
+                        } else if let Some(ty) = &spec.ty {
                             info.type_expr_type(ty.id)
                         } else if i < spec.values.len() {
                             info.expr_type(spec.values[i].id)
@@ -537,18 +610,22 @@ fn collect_file_declarations(
                         } else {
                             (info.type_slot_count(type_key), info.type_slot_types(type_key))
                         };
-                        let value_kind = info.type_value_kind(type_key) as u8;
-                        let obj_key = info.get_def(name);
-                        ctx.register_global(
+                        let value_meta = ctx.compute_value_meta_raw(type_key, info);
+                        let value_kind = value_meta as u8;
+                        let meta_id = value_meta >> 8;
+                        let global_idx = ctx.register_global(
                             obj_key,
                             vo_vm::bytecode::GlobalDef {
                                 name: project.interner.resolve(name.symbol).unwrap_or("?").to_string(),
                                 slots,
                                 value_kind,
-                                meta_id: 0,
+                                meta_id,
                                 slot_types,
                             },
                         );
+                        if info.is_array(type_key) && spec.values.is_empty() {
+                            ctx.add_zero_array_global(global_idx, type_key);
+                        }
                     }
                 }
             }
@@ -558,41 +635,6 @@ fn collect_file_declarations(
     Ok(())
 }
 
-fn compile_functions(
-    project: &Project,
-    ctx: &mut CodegenContext,
-    info: &TypeInfoWrapper,
-) -> Result<(), CodegenError> {
-    // Collect method info for NamedTypeMeta.methods update
-    // (recv_type, method_name, func_id, is_pointer_receiver, signature)
-    let mut method_mappings: Vec<(vo_analysis::objects::TypeKey, String, u32, bool, u32)> = Vec::new();
-    
-    // Note: Functions are already pre-registered by collect_declarations via declare_func.
-    // This handles forward references (function A calls function B defined later).
-    
-    // Compile function bodies - main package files first
-    for file in &project.files {
-        compile_file_functions(file, project, ctx, info, &mut method_mappings)?;
-    }
-    // Then imported package files
-    for (pkg_path, files) in &project.imported_files {
-        if let Some(pkg_type_info) = project.imported_type_infos.get(pkg_path) {
-            let pkg_info = TypeInfoWrapper::for_package(project, pkg_type_info);
-            for file in files {
-                compile_file_functions(file, project, ctx, &pkg_info, &mut method_mappings)?;
-            }
-        }
-    }
-    
-    // Update NamedTypeMeta.methods with method func_ids (direct methods only)
-    update_named_type_methods(ctx, &method_mappings, info);
-    
-    // Note: finalize_itabs is called in compile_project after compile_init_and_entry
-    // to handle itabs from both functions and global variable initialization
-    
-    Ok(())
-}
-
 fn compile_file_functions(
     file: &vo_syntax::ast::File,
     project: &Project,
@@ -698,19 +740,20 @@ fn collect_promoted_methods(
     let tc_objs = &info.project.tc_objs;
     let interner = &info.project.interner;
     
-    // Collect all (type_key, obj_key, named_type_id) for Named struct types
-    let named_structs: Vec<_> = ctx.all_named_type_ids()
+    // Runs once per unit; types already done only skip their registered methods
+    let named_type_keys: std::collections::HashMap<_, _> = tc_objs.types.iter()
+        .filter_map(|(k, t)| match t {
+            Type::Named(n) => n.obj().map(|obj_key| (obj_key, k)),
+            _ => None,
+        })
+        .collect();
+    
+    // Collect all (type_key, obj_key, named_type_id) for Named struct types,
+    // in id order so wrappers get the same ids on every compile
+    let mut named_structs: Vec<_> = ctx.all_named_type_ids()
         .filter_map(|(obj_key, named_type_id)| {
             // Find the type_key for this obj_key
-            let type_key = tc_objs.types.iter()
-                .find(|(_, t)| {
-                    if let Type::Named(n) = t {
-                        n.obj().as_ref() == Some(&obj_key)
-                    } else {
-                        false
-                    }
-                })
-                .map(|(k, _)| k)?;
+            let type_key = *named_type_keys.get(&obj_key)?;
             
             // Check if underlying is struct
             let underlying = vo_analysis::typ::underlying_type(type_key, tc_objs);
@@ -721,6 +764,7 @@ fn collect_promoted_methods(
             }
         })
         .collect();
+    named_structs.sort_by_key(|&(_, _, named_type_id)| named_type_id);
     
     // For each struct, find all promoted methods by collecting from embedded fields recursively
     for (type_key, _obj_key, named_type_id) in named_structs {
@@ -729,7 +773,7 @@ fn collect_promoted_methods(
             .and_then(|n| n.obj().and_then(|obj_key| tc_objs.lobjs[obj_key].pkg()));
         
         // Collect all potential method names from embedded types (recursively)
-        let mut method_names: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut method_names: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
         collect_embedded_method_names(type_key, tc_objs, &mut method_names);
         
         // For each method name, use lookup_field_or_method to find it and generate wrapper
@@ -813,7 +857,7 @@ fn collect_promoted_methods(
 fn collect_embedded_method_names(
     type_key: vo_analysis::objects::TypeKey,
     tc_objs: &vo_analysis::objects::TCObjects,
-    method_names: &mut std::collections::HashSet<String>,
+    method_names: &mut std::collections::BTreeSet<String>,
 ) {
     
     
@@ -952,8 +996,9 @@ fn compile_func_decl_at(
     info: &TypeInfoWrapper,
 ) -> Result<(), CodegenError> {
     ctx.set_current_func_id(func_id);
-    let func_def = compile_func_body(func_decl, ctx, info)?;
+    let (func_def, locs) = compile_func_body(func_decl, ctx, info)?;
     ctx.replace_function(func_id, func_def);
+    ctx.add_debug_locs(func_id, &locs, &info.project.source_map);
     Ok(())
 }
 
@@ -963,15 +1008,18 @@ fn compile_func_decl_new(
     ctx: &mut CodegenContext,
     info: &TypeInfoWrapper,
 ) -> Result<u32, CodegenError> {
-    let func_def = compile_func_body(func_decl, ctx, info)?;
-    Ok(ctx.add_function(func_def))
+    let (func_def, locs) = compile_func_body(func_decl, ctx, info)?;
+    let func_id = ctx.add_function(func_def);
+    ctx.add_debug_locs(func_id, &locs, &info.project.source_map);
+    Ok(func_id)
 }
 
+/// Returns the function and the statement spans by pc.
 fn compile_func_body(
     func_decl: &vo_syntax::ast::FuncDecl,
     ctx: &mut CodegenContext,
     info: &TypeInfoWrapper,
-) -> Result<vo_vm::bytecode::FunctionDef, CodegenError> {
+) -> Result<(vo_vm::bytecode::FunctionDef, Vec<(u32, vo_common::span::Span)>), CodegenError> {
     let name = info.project.interner.resolve(func_decl.name.symbol)
         .unwrap_or("unknown");
     
@@ -997,6 +1045,7 @@ fn compile_func_body(
             let obj_key = info.get_def(name);
             let type_key = info.obj_type(obj_key, "receiver must have type");
             if info.needs_boxing(obj_key, type_key) {
+                ctx.note_heap_local(builder.name(), obj_key, info);
                 escaped_params.push((name.symbol, type_key, slots, slot_types.clone()));
             }
         }
@@ -1011,6 +1060,7 @@ fn compile_func_body(
             let type_key = info.obj_type(obj_key, "param must have type");
             builder.define_param(Some(name.symbol), slots, &slot_types);
             if info.needs_boxing(obj_key, type_key) {
+                ctx.note_heap_local(builder.name(), obj_key, info);
                 escaped_params.push((name.symbol, type_key, slots, slot_types.clone()));
             }
         }
@@ -1077,6 +1127,7 @@ fn compile_func_body(
             
             let slot = if escapes {
                 // Named return escapes - allocate GcRef slot only (PtrNew emitted later)
+                ctx.note_heap_local(builder.name(), obj_key, info);
                 let gcref_slot = builder.define_local_heap_boxed(name.symbol, slots, info.is_pointer(result_type));
                 escaped_returns.push(EscapedReturn { gcref_slot, slots, result_type });
                 gcref_slot
@@ -1104,8 +1155,8 @@ fn compile_func_body(
     // Add return if not present at end
     builder.emit_op(vo_vm::instruction::Opcode::Return, 0, 0, 0);
     
-    // Build and return FunctionDef
-    Ok(builder.build())
+    let locs = builder.take_debug_locs();
+    Ok((builder.build(), locs))
 }
 
 /// Emit GlobalSet or GlobalSetN depending on slot count.
@@ -1124,8 +1175,9 @@ fn emit_global_set(builder: &mut FuncBuilder, global_idx: u32, src: u16, slots:
 }
 
 /// Compile global array initialization: allocate heap array and store GcRef in global.
+/// With no `rhs` the array is left zeroed.
 fn compile_global_array_init(
-    rhs: &vo_syntax::ast::Expr,
+    rhs: Option<&vo_syntax::ast::Expr>,
     array_type: vo_analysis::objects::TypeKey,
     global_idx: u32,
     ctx: &mut CodegenContext,
@@ -1136,19 +1188,17 @@ fn compile_global_array_init(
     
     let array_len = info.array_len(array_type);
     let elem_type = info.array_elem_type(array_type);
-    let elem_slots = info.type_slot_count(elem_type);
-    let elem_bytes = (elem_slots as u32 * 8) as u16;
+    let elem_bytes = info.array_elem_bytes(array_type);
     let elem_vk = info.type_value_kind(elem_type);
     
-    // Allocate registers for: gcref, meta_reg, len_reg, temp for elements
+    // Allocate registers for: gcref, meta_reg, len_reg (+ elem_bytes when dynamic)
+    let flags = vo_common_core::elem_flags(elem_bytes, elem_vk);
     let gcref_slot = func.alloc_temp_typed(&[vo_runtime::SlotType::GcRef]);
     let meta_reg = func.alloc_temp_typed(&[vo_runtime::SlotType::Value]);
-    let len_reg = func.alloc_temp_typed(&[vo_runtime::SlotType::Value]);
+    let num_regs = if flags == 0 { 2 } else { 1 };
+    let len_reg = func.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; num_regs]);
     
-    // Get elem_meta: (rttid << 8) | elem_vk
-    let elem_rttid = ctx.intern_type_key(elem_type, info);
-    let elem_meta = ((elem_rttid as u64) << 8) | (elem_vk as u64);
-    let meta_idx = ctx.const_int(elem_meta as i64);
+    let meta_idx = ctx.get_or_create_array_elem_meta(array_type, info);
     func.emit_op(Opcode::LoadConst, meta_reg, meta_idx, 0);
     
     // Load array length
@@ -1156,24 +1206,24 @@ fn compile_global_array_init(
     func.emit_op(Opcode::LoadConst, len_reg, len_idx, 0);
     
     // Emit ArrayNew: gcref_slot = ArrayNew(meta_reg, len_reg)
-    let flags = if elem_bytes <= 8 { elem_bytes as u8 } else { 0 };
     if flags == 0 {
-        // Dynamic elem_bytes: need extra register
         let eb_idx = ctx.const_int(elem_bytes as i64);
         func.emit_op(Opcode::LoadConst, len_reg + 1, eb_idx, 0);
     }
     func.emit_with_flags(Opcode::ArrayNew, flags, gcref_slot, meta_reg, len_reg);
     
-    // Compile array elements and set them
-    if let vo_syntax::ast::ExprKind::CompositeLit(lit) = &rhs.kind {
+    // Compile array elements and set them, honoring keyed indices
+    if let Some(vo_syntax::ast::ExprKind::CompositeLit(lit)) = rhs.map(|e| &e.kind) {
         let elem_slot_types = info.type_slot_types(elem_type);
         let tmp_elem = func.alloc_temp_typed(&elem_slot_types);
         let idx_reg = func.alloc_temp_typed(&[vo_runtime::SlotType::Value]);
         
-        for (i, elem) in lit.elems.iter().enumerate() {
+        let mut current_index: u64 = 0;
+        for elem in lit.elems.iter() {
+            let index = crate::expr::literal::resolve_elem_index(elem, &mut current_index, info);
             crate::expr::compile_elem_to(&elem.value, tmp_elem, elem_type, ctx, func, info)?;
-            func.emit_op(Opcode::LoadInt, idx_reg, i as u16, 0);
-            func.emit_array_set(gcref_slot, idx_reg, tmp_elem, elem_bytes as usize, elem_vk, ctx);
+            func.emit_op(Opcode::LoadInt, idx_reg, index as u16, 0);
+            func.emit_array_set(gcref_slot, idx_reg, tmp_elem, elem_bytes, elem_vk, ctx);
         }
     }
     
@@ -1183,14 +1233,19 @@ fn compile_global_array_init(
     Ok(())
 }
 
-/// Compile global variable initialization for a single package.
+/// Compile the initializers of a package's globals in `globals` (slot offsets).
 fn compile_package_globals(
     ctx: &mut CodegenContext,
     init_builder: &mut FuncBuilder,
     info: &TypeInfoWrapper,
+    globals: &core::ops::Range<u32>,
 ) -> Result<(), CodegenError> {
     // Initialize global variables in dependency order (from type checker analysis)
-    for initializer in info.init_order() {
+    let in_unit = |obj_key| ctx.get_global_index(obj_key).is_some_and(|idx| globals.contains(&idx));
+    let initializers: Vec<_> = info.init_order().iter()
+        .filter(|initializer| initializer.lhs.iter().any(|&obj_key| in_unit(obj_key)))
+        .collect();
+    for initializer in initializers {
         // Each initializer has lhs (variables) and rhs (expression)
         // For now, handle single variable assignment (most common case)
         if initializer.lhs.len() == 1 {
@@ -1204,7 +1259,7 @@ fn compile_package_globals(
                 if let Some(tk) = type_key {
                     if info.is_array(tk) {
                         compile_global_array_init(
-                            &initializer.rhs, tk, global_idx,
+                            Some(&initializer.rhs), tk, global_idx,
                             ctx, init_builder, info
                         )?;
                         continue;
@@ -1229,80 +1284,78 @@ fn compile_package_globals(
                 emit_global_set(init_builder, global_idx, tmp, slots);
             }
         } else {
-            // Multi-variable assignment: var a, b = expr
-            // TODO: handle tuple unpacking if needed
+            // Multi-variable assignment: var a, b = f()
+            // Evaluate the tuple once, then distribute its elements.
+            let tuple = crate::expr::CompiledTuple::compile(&initializer.rhs, ctx, init_builder, info)?;
+            let mut offset = 0u16;
             for (i, &obj_key) in initializer.lhs.iter().enumerate() {
-                let obj = &info.project.tc_objs.lobjs[obj_key];
+                let elem_type = info.tuple_elem_type(tuple.tuple_type, i);
+                let elem_slot = tuple.base + offset;
+                offset += info.type_slot_count(elem_type);
                 
-                if let Some(global_idx) = ctx.get_global_index(obj_key) {
-                    let type_key = obj.typ();
-                    let slots = type_key.map(|t| info.type_slot_count(t)).unwrap_or(1);
-                    let slot_types = type_key
-                        .map(|t| info.type_slot_types(t))
-                        .unwrap_or_else(|| vec![vo_runtime::SlotType::Value]);
-                    
-                    // For multi-var, compile rhs once and extract values
-                    // For now, just compile rhs for each (inefficient but correct)
-                    let tmp = init_builder.alloc_temp_typed(&slot_types);
-                    crate::expr::compile_expr_to(&initializer.rhs, tmp, ctx, init_builder, info)?;
-                    emit_global_set(init_builder, global_idx, tmp, slots);
+                let Some(global_idx) = ctx.get_global_index(obj_key) else { continue };
+                let Some(var_type) = info.project.tc_objs.lobjs[obj_key].typ() else { continue };
+                if info.is_interface(var_type) && !info.is_interface(elem_type) {
+                    let tmp = init_builder.alloc_temp_typed(&[vo_runtime::SlotType::Interface0, vo_runtime::SlotType::Interface1]);
+                    crate::assign::emit_assign(tmp, crate::assign::AssignSource::Slot { slot: elem_slot, type_key: elem_type }, var_type, ctx, init_builder, info)?;
+                    emit_global_set(init_builder, global_idx, tmp, 2);
+                } else {
+                    emit_global_set(init_builder, global_idx, elem_slot, info.type_slot_count(var_type));
                 }
-                let _ = i; // suppress unused warning
             }
         }
     }
     Ok(())
 }
 
-fn compile_init_and_entry(
-    project: &Project,
+/// Emit a no-arg, no-result call from `__init__` or `__entry__`.
+/// Func ids above u16 range carry their high bits in flags.
+fn emit_init_call(builder: &mut FuncBuilder, func_id: u32) {
+    let (func_id_low, func_id_high) = type_info::encode_func_id(func_id);
+    builder.emit_with_flags(vo_vm::instruction::Opcode::Call, func_id_high, func_id_low, 0, 0);
+}
+
+/// Generate a unit's `__init__`: initialize the globals in `globals` (slot
+/// offsets), then call the unit's `init()` functions in declaration order.
+fn compile_unit_init(
     ctx: &mut CodegenContext,
     info: &TypeInfoWrapper,
-) -> Result<(), CodegenError> {
-    // 1. Generate __init__ function for global variable initialization
+    globals: core::ops::Range<u32>,
+) -> Result<u32, CodegenError> {
     let mut init_builder = FuncBuilder::new("__init__");
     
-    // Initialize imported packages' global variables in dependency order
-    // (dependencies are initialized before dependents)
-    for (_, pkg_type_info) in project.imported_packages_in_order() {
-        let pkg_info = TypeInfoWrapper::for_package(project, pkg_type_info);
-        compile_package_globals(ctx, &mut init_builder, &pkg_info)?;
+    // Array globals are heap-allocated; give uninitialized ones their zero value
+    // before any initializer can read them.
+    for (global_idx, array_type) in ctx.take_zero_array_globals() {
+        compile_global_array_init(None, array_type, global_idx, ctx, &mut init_builder, info)?;
     }
     
-    // Then, initialize main package's global variables
-    compile_package_globals(ctx, &mut init_builder, info)?;
-    
-    // Add return
-    init_builder.emit_op(vo_vm::instruction::Opcode::Return, 0, 0, 0);
-    let init_func = init_builder.build();
-    let init_func_id = ctx.add_function(init_func);
-    // Note: __init__ is NOT registered as a user init function - it's handled separately
+    compile_package_globals(ctx, &mut init_builder, info, &globals)?;
     
-    // 2. Find main function
-    let main_func_id = ctx.main_func_id();
+    for user_init_id in ctx.take_init_functions() {
+        emit_init_call(&mut init_builder, user_init_id);
+    }
     
-    // 3. Generate __entry__ function
+    init_builder.emit_op(vo_vm::instruction::Opcode::Return, 0, 0, 0);
+    Ok(ctx.add_function(init_builder.build()))
+}
+
+/// Generate `__entry__`: run every unit's `__init__` in order, then main.
+fn compile_entry(ctx: &mut CodegenContext, unit_inits: &[u32]) {
     let mut entry_builder = FuncBuilder::new("__entry__");
     
-    // Call __init__ for global variable initialization
-    entry_builder.emit_op(vo_vm::instruction::Opcode::Call, init_func_id as u16, 0, 0);
-    
-    // Call user-defined init() functions in declaration order
-    for &user_init_id in ctx.init_functions() {
-        entry_builder.emit_op(vo_vm::instruction::Opcode::Call, user_init_id as u16, 0, 0);
+    for &init_id in unit_inits {
+        emit_init_call(&mut entry_builder, init_id);
     }
     
     // Call main if exists
-    if let Some(main_id) = main_func_id {
-        entry_builder.emit_op(vo_vm::instruction::Opcode::Call, main_id as u16, 0, 0);
+    if let Some(main_id) = ctx.main_func_id() {
+        emit_init_call(&mut entry_builder, main_id);
     }
     
-    // Return
     entry_builder.emit_op(vo_vm::instruction::Opcode::Return, 0, 0, 0);
     
     let entry_func = entry_builder.build();
     let entry_func_id = ctx.add_function(entry_func);
     ctx.set_entry_func(entry_func_id);
-    
-    Ok(())
 }
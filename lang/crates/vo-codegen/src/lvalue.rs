@@ -8,7 +8,7 @@
 //! - Pointer dereference (*p)
 
 use vo_runtime::SlotType;
-use vo_syntax::ast::{Expr, ExprKind};
+use vo_syntax::ast::{Expr, ExprKind, Ident};
 use vo_vm::instruction::Opcode;
 
 use crate::context::CodegenContext;
@@ -212,6 +212,43 @@ fn emit_nested_stack_array_index(
     flattened_idx
 }
 
+/// Resolve a variable name to its storage: local, global, or closure capture.
+pub fn resolve_ident_lvalue(
+    ident: &Ident,
+    ctx: &mut CodegenContext,
+    func: &mut FuncBuilder,
+    info: &TypeInfoWrapper,
+) -> Result<LValue, CodegenError> {
+    // Check local variable first - storage is already computed in LocalVar
+    if let Some(local) = func.lookup_local(ident.symbol) {
+        return Ok(LValue::Variable(local.storage));
+    }
+    
+    // Check global variable
+    let obj_key = info.get_use(ident);
+    if let Some(global_idx) = ctx.get_global_index(obj_key) {
+        let type_key = info.obj_type(obj_key, "global must have type");
+        // Global arrays are stored as GcRef (1 slot)
+        let slots = if info.is_array(type_key) { 1 } else { info.type_slot_count(type_key) };
+        return Ok(LValue::Variable(StorageKind::Global { 
+            index: global_idx as u16, 
+            slots 
+        }));
+    }
+    
+    // Check closure capture
+    if let Some(capture) = func.lookup_capture(ident.symbol) {
+        let type_key = info.obj_type(obj_key, "capture must have type");
+        let value_slots = info.type_slot_count(type_key);
+        return Ok(LValue::Capture { 
+            capture_index: capture.index, 
+            value_slots 
+        });
+    }
+    
+    Err(CodegenError::VariableNotFound(format!("{:?}", ident.symbol)))
+}
+
 /// Resolve an expression to an LValue (if it's assignable).
 pub fn resolve_lvalue(
     expr: &Expr,
@@ -221,37 +258,8 @@ pub fn resolve_lvalue(
 ) -> Result<LValue, CodegenError> {
     match &expr.kind {
         // === Identifier ===
-        ExprKind::Ident(ident) => {
-            // Check local variable first - storage is already computed in LocalVar
-            if let Some(local) = func.lookup_local(ident.symbol) {
-                return Ok(LValue::Variable(local.storage));
-            }
-            
-            // Check global variable
-            let obj_key = info.get_use(ident);
-            if let Some(global_idx) = ctx.get_global_index(obj_key) {
-                let type_key = info.obj_type(obj_key, "global must have type");
-                // Global arrays are stored as GcRef (1 slot)
-                let slots = if info.is_array(type_key) { 1 } else { info.type_slot_count(type_key) };
-                return Ok(LValue::Variable(StorageKind::Global { 
-                    index: global_idx as u16, 
-                    slots 
-                }));
-            }
-            
-            // Check closure capture
-            if let Some(capture) = func.lookup_capture(ident.symbol) {
-                let type_key = info.obj_type(obj_key, "capture must have type");
-                let value_slots = info.type_slot_count(type_key);
-                return Ok(LValue::Capture { 
-                    capture_index: capture.index, 
-                    value_slots 
-                });
-            }
-            
-            Err(CodegenError::VariableNotFound(format!("{:?}", ident.symbol)))
-        }
-        
+        ExprKind::Ident(ident) => resolve_ident_lvalue(ident, ctx, func, info),
+
         // === Selector (field access) ===
         ExprKind::Selector(sel) => {
             let recv_type = info.expr_type(sel.expr.id);
@@ -9,7 +9,7 @@ use vo_vm::instruction::Opcode;
 
 use crate::context::CodegenContext;
 use crate::error::CodegenError;
-use crate::expr::{compile_expr_to, emit_int_trunc};
+use crate::expr::{compile_expr_to, emit_int_trunc, int_arith_flags};
 use crate::func::{FuncBuilder, StorageKind};
 use crate::type_info::TypeInfoWrapper;
 
@@ -341,6 +341,7 @@ fn compile_compound_assign(
         (AssignOp::Shr, _, _, true) => Opcode::ShrU,
         (AssignOp::Assign, _, _, _) => unreachable!("plain assign handled separately"),
     };
+    let flags = int_arith_flags(opcode, lhs_type, info);
     
     // Resolve LHS to an LValue
     let lv = resolve_lvalue(lhs, ctx, func, info)?;
@@ -350,7 +351,7 @@ fn compile_compound_assign(
     
     // Fast path: single-slot stack variable - operate directly, no load/store needed
     if let LValue::Variable(StorageKind::StackValue { slot, slots: 1 }) = &lv {
-        func.emit_op(opcode, *slot, *slot, rhs_reg);
+        func.emit_with_flags(opcode, flags, *slot, *slot, rhs_reg);
         // Apply truncation for narrow integer types (Go semantics)
         emit_int_trunc(*slot, lhs_type, func, info);
         return Ok(());
@@ -361,7 +362,7 @@ fn compile_compound_assign(
     let slot_type = if is_string { vo_runtime::SlotType::GcRef } else { vo_runtime::SlotType::Value };
     let tmp = func.alloc_temp_typed(&[slot_type]);
     emit_lvalue_load(&lv, tmp, ctx, func);
-    func.emit_op(opcode, tmp, tmp, rhs_reg);
+    func.emit_with_flags(opcode, flags, tmp, tmp, rhs_reg);
     // Apply truncation for narrow integer types (Go semantics)
     emit_int_trunc(tmp, lhs_type, func, info);
     emit_lvalue_store(&lv, tmp, ctx, func, &[slot_type]);
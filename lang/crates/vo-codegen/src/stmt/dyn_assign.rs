@@ -9,6 +9,7 @@ use vo_vm::instruction::Opcode;
 
 use crate::context::CodegenContext;
 use crate::error::CodegenError;
+use crate::expr::call::alloc_call_buffer;
 use crate::expr::compile_expr_to;
 use crate::func::FuncBuilder;
 use crate::type_info::TypeInfoWrapper;
@@ -20,6 +21,9 @@ pub const IFACE_ASSERT_WITH_OK: u8 = 1 | (1 << 2) | (2 << 3);
 /// All protocol interfaces have exactly one method at index 0
 pub const PROTOCOL_METHOD_IDX: u8 = 0;
 
+/// Result slots of a protocol setter returning error.
+const ERROR_RESULT_TYPES: [SlotType; 2] = [SlotType::Interface0, SlotType::Interface1];
+
 /// Emit panic with error: call panic_with_error extern.
 /// Dynamic write always panics on error (does not propagate).
 fn emit_dyn_write_panic(
@@ -94,14 +98,14 @@ fn emit_protocol_and_fallback(
         func.emit_with_flags(Opcode::IfaceAssert, IFACE_ASSERT_WITH_OK, iface_reg, any_base_reg, iface_meta_id as u16);
         let fallback_jump = func.emit_jump(Opcode::JumpIfNot, iface_reg + 2);
 
-        // Protocol method call
-        let args_start = compile_protocol_args(func, ctx)?;
+        // Protocol method call: the error result comes first in the buffer
+        let buf = compile_protocol_args(func, ctx)?;
         let c = crate::type_info::encode_call_args(protocol_arg_slots, 2);
-        func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, args_start, c);
+        func.emit_with_flags(Opcode::CallIface, PROTOCOL_METHOD_IDX, iface_reg, buf, c);
 
         // Check error from protocol method
-        let ok_err_jump = func.emit_jump(Opcode::JumpIfNot, args_start);
-        emit_dyn_write_panic(args_start, ctx, func);
+        let ok_err_jump = func.emit_jump(Opcode::JumpIfNot, buf);
+        emit_dyn_write_panic(buf, ctx, func);
         func.patch_jump(ok_err_jump, func.current_pc());
         let end_jump = func.emit_jump(Opcode::Jump, 0);
 
@@ -151,13 +155,13 @@ pub(crate) fn compile_dyn_field_assign(
         protocol_meta_id,
         // Protocol args: name string, value any
         |func, ctx| {
-            let args_start = func.alloc_temp_typed(&[
+            let buf = alloc_call_buffer(func, &ERROR_RESULT_TYPES, &[
                 SlotType::GcRef,  // string
                 SlotType::Interface0, SlotType::Interface1,  // any value
             ]);
-            func.emit_op(Opcode::StrNew, args_start, name_idx, 0);
-            crate::assign::emit_assign(args_start + 1, crate::assign::AssignSource::Expr(value), any_type, ctx, func, info)?;
-            Ok(args_start)
+            func.emit_op(Opcode::StrNew, buf + 2, name_idx, 0);
+            crate::assign::emit_assign(buf + 3, crate::assign::AssignSource::Expr(value), any_type, ctx, func, info)?;
+            Ok(buf)
         },
         3,  // protocol_arg_slots
         // Extern args: base any, name string, value any
@@ -198,13 +202,13 @@ pub(crate) fn compile_dyn_index_assign(
         protocol_meta_id,
         // Protocol args: key any, value any
         |func, ctx| {
-            let args_start = func.alloc_temp_typed(&[
+            let buf = alloc_call_buffer(func, &ERROR_RESULT_TYPES, &[
                 SlotType::Interface0, SlotType::Interface1,
                 SlotType::Interface0, SlotType::Interface1,
             ]);
-            crate::assign::emit_assign(args_start, crate::assign::AssignSource::Expr(key_expr), any_type, ctx, func, info)?;
-            crate::assign::emit_assign(args_start + 2, crate::assign::AssignSource::Expr(value), any_type, ctx, func, info)?;
-            Ok(args_start)
+            crate::assign::emit_assign(buf + 2, crate::assign::AssignSource::Expr(key_expr), any_type, ctx, func, info)?;
+            crate::assign::emit_assign(buf + 4, crate::assign::AssignSource::Expr(value), any_type, ctx, func, info)?;
+            Ok(buf)
         },
         4,  // protocol_arg_slots
         // Extern args: base any, key any, value any
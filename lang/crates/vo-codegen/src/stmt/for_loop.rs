@@ -51,6 +51,7 @@ fn define_loop_var(
         && !sc.info.is_reference_type(type_key) && !sc.info.is_array(type_key);
     
     if needs_go122 {
+        sc.ctx.note_heap_local(sc.func.name(), obj_key, sc.info);
         let slot_types = sc.info.type_slot_types(type_key);
         let value_slots = slot_types.len() as u16;
         let ctrl_slot = sc.func.alloc_temp_typed(&slot_types);
@@ -328,7 +328,10 @@ pub(crate) fn compile_for_range(
         let key_info = range_var_info(&mut sc, key.as_ref(), kt, define)?;
         let val_info = range_var_info(&mut sc, value.as_ref(), vt, define)?;
         
-        let iter_slot = sc.func.alloc_temp_typed(&[SlotType::Value; MAP_ITER_SLOTS]);
+        let mut iter_types = [SlotType::Value; MAP_ITER_SLOTS];
+        // A sorted iterator holds its own snapshot of the keys
+        iter_types[vo_runtime::objects::map::MAP_ITER_ORDER_SLOT] = SlotType::GcRef;
+        let iter_slot = sc.func.alloc_temp_typed(&iter_types);
         let ok_slot = sc.func.alloc_temp_typed(&[SlotType::Value]);
         
         // MapIterInit: a=iter_slot, b=map_reg
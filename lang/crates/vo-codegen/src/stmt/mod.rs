@@ -43,6 +43,7 @@ fn compile_stmt_with_label(
     info: &TypeInfoWrapper,
     label: Option<vo_common::Symbol>,
 ) -> Result<(), CodegenError> {
+    func.mark_span(stmt.span);
     match &stmt.kind {
         // === Variable declaration ===
         StmtKind::Var(var_decl) => {
@@ -228,14 +229,12 @@ fn compile_stmt_with_label(
             let one = func.alloc_temp_typed(&[SlotType::Value]);
             func.emit_op(Opcode::LoadInt, one, 1, 0);
             
-            if inc_dec.is_inc {
-                func.emit_op(Opcode::AddI, tmp, tmp, one);
-            } else {
-                func.emit_op(Opcode::SubI, tmp, tmp, one);
-            }
+            let expr_type = info.expr_type(inc_dec.expr.id);
+            let op = if inc_dec.is_inc { Opcode::AddI } else { Opcode::SubI };
+            let flags = crate::expr::int_arith_flags(op, expr_type, info);
+            func.emit_with_flags(op, flags, tmp, tmp, one);
             
             // Truncate for narrow integer types (Go semantics: wrap on overflow)
-            let expr_type = info.expr_type(inc_dec.expr.id);
             crate::expr::emit_int_trunc(tmp, expr_type, func, info);
             
             // Inc/dec on integers - no GC refs
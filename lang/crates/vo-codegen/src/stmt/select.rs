@@ -138,12 +138,8 @@ fn bind_recv_variables(
     let first = &recv.lhs[0];
     if recv.define {
         func.define_local_at(first.symbol, recv_info.dst_reg, recv_info.elem_slots);
-    } else if let Some(local) = func.lookup_local(first.symbol) {
-        // Existing variable: need to check for interface conversion
-        let storage = local.storage.clone();
-        let obj_key = info.get_use(first);
-        let lhs_type = info.obj_type(obj_key, "recv var must have type");
-        crate::assign::emit_store_to_storage(storage, recv_info.dst_reg, elem_type, lhs_type, ctx, func, info)?;
+    } else {
+        assign_recv_var(first, recv_info.dst_reg, elem_type, ctx, func, info)?;
     }
     
     // Second variable: ok bool (if present)
@@ -152,10 +148,31 @@ fn bind_recv_variables(
         let ok_reg = recv_info.dst_reg + recv_info.elem_slots;
         if recv.define {
             func.define_local_at(second.symbol, ok_reg, 1);
-        } else if let Some(local) = func.lookup_local(second.symbol) {
-            // ok is always bool, no interface conversion needed
-            func.emit_storage_store(local.storage, ok_reg, &[SlotType::Value]);
+        } else {
+            assign_recv_var(second, ok_reg, info.bool_type(), ctx, func, info)?;
         }
     }
     Ok(())
 }
+
+/// Assign a received value to an existing variable, which may be a local,
+/// a global or a closure capture. `_` discards it.
+fn assign_recv_var(
+    ident: &vo_syntax::ast::Ident,
+    src: u16,
+    src_type: TypeKey,
+    ctx: &mut CodegenContext,
+    func: &mut FuncBuilder,
+    info: &TypeInfoWrapper,
+) -> Result<(), CodegenError> {
+    if info.project.interner.resolve(ident.symbol) == Some("_") {
+        return Ok(());
+    }
+    let lv = crate::lvalue::resolve_ident_lvalue(ident, ctx, func, info)?;
+    let lhs_type = info.obj_type(info.get_use(ident), "recv var must have type");
+    let slot_types = info.type_slot_types(lhs_type);
+    let tmp = func.alloc_temp_typed(&slot_types);
+    crate::assign::emit_assign(tmp, crate::assign::AssignSource::Slot { slot: src, type_key: src_type }, lhs_type, ctx, func, info)?;
+    crate::lvalue::emit_lvalue_store(&lv, tmp, ctx, func, &slot_types);
+    Ok(())
+}
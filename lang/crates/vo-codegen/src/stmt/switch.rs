@@ -2,8 +2,8 @@
 
 use vo_analysis::objects::TypeKey;
 use vo_runtime::SlotType;
-use vo_syntax::ast::StmtKind;
-use vo_vm::instruction::Opcode;
+use vo_syntax::ast::{Expr, StmtKind, SwitchStmt};
+use vo_vm::instruction::{Opcode, ARITH_FLAG_UNSIGNED};
 
 use crate::context::CodegenContext;
 use crate::error::CodegenError;
@@ -78,7 +78,8 @@ fn emit_type_switch_binding(
     }
     
     // Box if captured by closure
-    if needs_boxing {
+    if let (true, Some(obj)) = (needs_boxing, case_var_obj) {
+        ctx.note_heap_local(func.name(), obj, info);
         let stores_pointer = info.is_pointer(type_key);
         let gcref_slot = func.define_local_heap_boxed(name, slots, stores_pointer);
         let meta_idx = ctx.get_boxing_meta(type_key, info);
@@ -282,6 +283,151 @@ fn emit_switch_case_comparison(
     Ok(cmp_result)
 }
 
+/// String switches with at least this many constant cases find their case by
+/// binary search instead of comparing against each case in turn.
+const STRING_SEARCH_MIN_CASES: usize = 8;
+
+/// Search ranges this small compare each case directly.
+const STRING_SEARCH_LINEAR_MAX: usize = 3;
+
+/// Constant case values of a string switch, sorted by bytes, with the index
+/// of the case each selects. None unless the tag is a string, every case
+/// value is a constant and there are enough of them to be worth searching.
+fn string_search_cases<'a>(
+    switch_stmt: &'a SwitchStmt,
+    tag_type: TypeKey,
+    info: &TypeInfoWrapper,
+) -> Option<Vec<(String, usize, &'a Expr)>> {
+    if !info.is_string(tag_type) {
+        return None;
+    }
+    let mut cases = Vec::new();
+    for (case_idx, case) in switch_stmt.cases.iter().enumerate() {
+        for expr in &case.exprs {
+            match info.const_value(expr.id)? {
+                vo_analysis::ConstValue::Str(s) => cases.push((s.clone(), case_idx, expr)),
+                _ => return None,
+            }
+        }
+    }
+    if cases.len() < STRING_SEARCH_MIN_CASES {
+        return None;
+    }
+    // Stable sort keeps the first of any duplicates, which is the one that matches
+    cases.sort_by(|a, b| a.0.cmp(&b.0));
+    cases.dedup_by(|a, b| a.0 == b.0);
+    Some(cases)
+}
+
+/// Emit a binary search of the sorted `cases` for `tag`: StrLt against the
+/// middle case narrows the range, small ranges are matched with StrEq.
+/// Matches are pushed to `case_jumps`, misses to `miss_jumps`.
+fn emit_string_search(
+    tag: u16,
+    cases: &[(String, usize, &Expr)],
+    case_jumps: &mut Vec<(usize, usize)>,
+    miss_jumps: &mut Vec<usize>,
+    ctx: &mut CodegenContext,
+    func: &mut FuncBuilder,
+    info: &TypeInfoWrapper,
+) -> Result<(), CodegenError> {
+    if cases.len() <= STRING_SEARCH_LINEAR_MAX {
+        for &(_, case_idx, expr) in cases {
+            let case_val = crate::expr::compile_expr(expr, ctx, func, info)?;
+            let eq = func.alloc_temp_typed(&[SlotType::Value]);
+            func.emit_op(Opcode::StrEq, eq, tag, case_val);
+            case_jumps.push((func.emit_jump(Opcode::JumpIf, eq), case_idx));
+        }
+        miss_jumps.push(func.emit_jump(Opcode::Jump, 0));
+        return Ok(());
+    }
+    let mid = cases.len() / 2;
+    let pivot = crate::expr::compile_expr(cases[mid].2, ctx, func, info)?;
+    let lt = func.alloc_temp_typed(&[SlotType::Value]);
+    func.emit_op(Opcode::StrLt, lt, tag, pivot);
+    let upper_jump = func.emit_jump(Opcode::JumpIfNot, lt);
+    emit_string_search(tag, &cases[..mid], case_jumps, miss_jumps, ctx, func, info)?;
+    let upper_pc = func.current_pc();
+    func.patch_jump(upper_jump, upper_pc);
+    emit_string_search(tag, &cases[mid..], case_jumps, miss_jumps, ctx, func, info)
+}
+
+/// Integer switches with at least this many constant cases may use a jump table.
+const JUMP_TABLE_MIN_CASES: usize = 4;
+
+/// A jump table is used only if at least 1/JUMP_TABLE_MAX_SPREAD of its
+/// entries select a case; sparser switches compare each case in turn.
+const JUMP_TABLE_MAX_SPREAD: usize = 2;
+
+/// Jump table for an integer switch whose case values are all constants and
+/// dense enough: the smallest value's expression, and for each value from it
+/// upward the case it selects. None if the switch doesn't qualify.
+fn int_jump_table<'a>(
+    switch_stmt: &'a SwitchStmt,
+    tag_type: TypeKey,
+    info: &TypeInfoWrapper,
+) -> Option<(&'a Expr, Vec<Option<usize>>)> {
+    if !info.is_int(tag_type) {
+        return None;
+    }
+    let mut cases = Vec::new();
+    for (case_idx, case) in switch_stmt.cases.iter().enumerate() {
+        for expr in &case.exprs {
+            let value = info.const_value(expr.id).filter(|v| v.is_int())?;
+            let (n, exact) = value.int_as_i64();
+            if !exact {
+                return None;
+            }
+            cases.push((n, case_idx, expr));
+        }
+    }
+    if cases.len() < JUMP_TABLE_MIN_CASES {
+        return None;
+    }
+    let &(min, _, min_expr) = cases.iter().min_by_key(|c| c.0)?;
+    let max = cases.iter().map(|c| c.0).max()?;
+    let len = usize::try_from(max as i128 - min as i128 + 1).ok()?;
+    if len > cases.len() * JUMP_TABLE_MAX_SPREAD || len > u16::MAX as usize {
+        return None;
+    }
+    let mut table = vec![None; len];
+    for (n, case_idx, _) in cases {
+        // The first case listing a value is the one that matches
+        table[(n - min) as usize].get_or_insert(case_idx);
+    }
+    Some((min_expr, table))
+}
+
+/// Emit a JumpTable from `int_jump_table`, indexed by `tag` minus the
+/// smallest case value, then one Jump per entry. Entries with a case are
+/// pushed to `case_jumps`; empty entries and the out-of-range jump after
+/// the table go to `miss_jumps`.
+fn emit_jump_table(
+    tag: u16,
+    (min_expr, table): &(&Expr, Vec<Option<usize>>),
+    case_jumps: &mut Vec<(usize, usize)>,
+    miss_jumps: &mut Vec<usize>,
+    ctx: &mut CodegenContext,
+    func: &mut FuncBuilder,
+    info: &TypeInfoWrapper,
+) -> Result<(), CodegenError> {
+    let min = crate::expr::compile_expr(min_expr, ctx, func, info)?;
+    let index = func.alloc_temp_typed(&[SlotType::Value]);
+    // Wrapping subtraction sends values below the minimum past the table too,
+    // so it is unsigned even when the tag isn't
+    func.emit_with_flags(Opcode::SubI, ARITH_FLAG_UNSIGNED, index, tag, min);
+    func.emit_op(Opcode::JumpTable, index, table.len() as u16, 0);
+    for entry in table {
+        let jump = func.emit_jump(Opcode::Jump, 0);
+        match entry {
+            Some(case_idx) => case_jumps.push((jump, *case_idx)),
+            None => miss_jumps.push(jump),
+        }
+    }
+    miss_jumps.push(func.emit_jump(Opcode::Jump, 0));
+    Ok(())
+}
+
 /// Compile switch statement with optional label (for break support).
 pub(crate) fn compile_switch(
     switch_stmt: &vo_syntax::ast::SwitchStmt,
@@ -312,18 +458,25 @@ pub(crate) fn compile_switch(
     // case_jumps[i] -> case_body_idx[i]: maps each conditional jump to its target case
     let mut case_jumps: Vec<(usize, usize)> = Vec::new();  // (jump_pc, case_idx)
     let mut end_jumps: Vec<usize> = Vec::new();
-    let mut default_case_idx: Option<usize> = None;
+    // Jumps taken when no case matches: to default or end
+    let mut miss_jumps: Vec<usize> = Vec::new();
+    let default_case_idx = switch_stmt.cases.iter().position(|case| case.exprs.is_empty());
     
     // Get tag type for comparison dispatch
     let tag_type = switch_stmt.tag.as_ref().map(|t| info.expr_type(t.id));
-    
-    // Generate comparison and conditional jumps for each case
-    // NOTE: Default case jump is emitted AFTER all other cases are checked,
-    // regardless of its position in the source code.
-    for (case_idx, case) in switch_stmt.cases.iter().enumerate() {
-        if case.exprs.is_empty() {
-            default_case_idx = Some(case_idx);
-        } else {
+    let search_cases = tag_type.and_then(|tt| string_search_cases(switch_stmt, tt, info));
+    let jump_table = tag_type.and_then(|tt| int_jump_table(switch_stmt, tt, info));
+    
+    // Case values in both are constants, so testing them out of order is unobservable
+    if let (Some(tag), Some(cases)) = (tag_reg, &search_cases) {
+        emit_string_search(tag, cases, &mut case_jumps, &mut miss_jumps, ctx, func, info)?;
+    } else if let (Some(tag), Some(table)) = (tag_reg, &jump_table) {
+        emit_jump_table(tag, table, &mut case_jumps, &mut miss_jumps, ctx, func, info)?;
+    } else {
+        // Generate comparison and conditional jumps for each case
+        // NOTE: Default case jump is emitted AFTER all other cases are checked,
+        // regardless of its position in the source code.
+        for (case_idx, case) in switch_stmt.cases.iter().enumerate() {
             for case_expr in &case.exprs {
                 let cmp_result = if let (Some(tag), Some(tt)) = (tag_reg, tag_type) {
                     emit_switch_case_comparison(tag, tt, case_expr, ctx, func, info)?
@@ -335,11 +488,11 @@ pub(crate) fn compile_switch(
                 case_jumps.push((func.emit_jump(Opcode::JumpIf, cmp_result), case_idx));
             }
         }
+        
+        // After all case conditions checked: jump to default or end
+        miss_jumps.push(func.emit_jump(Opcode::Jump, 0));
     }
     
-    // After all case conditions checked: jump to default or end
-    let fallthrough_jump = func.emit_jump(Opcode::Jump, 0);
-    
     // Compile case bodies
     let mut case_body_starts: Vec<usize> = Vec::new();
     for case in &switch_stmt.cases {
@@ -377,11 +530,13 @@ pub(crate) fn compile_switch(
         func.patch_jump(jump_pc, case_body_starts[case_idx]);
     }
     
-    // Patch fallthrough jump: to default case if exists, otherwise to end
-    let fallthrough_target = default_case_idx
+    // Patch miss jumps: to default case if exists, otherwise to end
+    let miss_target = default_case_idx
         .map(|idx| case_body_starts[idx])
         .unwrap_or(end_pc);
-    func.patch_jump(fallthrough_jump, fallthrough_target);
+    for jump_pc in miss_jumps {
+        func.patch_jump(jump_pc, miss_target);
+    }
     
     // Patch end jumps (implicit break at end of case) and explicit breaks
     for jump_pc in end_jumps.into_iter().chain(break_patches) {
@@ -131,6 +131,9 @@ impl<'a, 'b> LocalDefiner<'a, 'b> {
 
         // Use centralized boxing decision logic
         let needs_box = obj_key.map_or(escapes, |k| self.info.needs_boxing(k, type_key));
+        if let (true, Some(obj)) = (needs_box, obj_key) {
+            self.ctx.note_heap_local(self.func.name(), obj, self.info);
+        }
 
         if self.info.is_reference_type(type_key) {
             if needs_box {
@@ -156,6 +156,11 @@ impl<'a> TypeInfoWrapper<'a> {
         typ::identical(type_key, universe.error_type(), self.tc_objs())
     }
     
+    /// The predeclared `bool` type.
+    pub fn bool_type(&self) -> TypeKey {
+        self.tc_objs().universe().types()[&typ::BasicType::Bool]
+    }
+    
     /// Check if a call expression's callee is a builtin function.
     /// Returns Some(Builtin) if the callee expression is a builtin, None otherwise.
     /// This uses the OperandMode recorded during type checking, which correctly handles
@@ -44,18 +44,25 @@ fn tuple_slot_count(tuple_key: TypeKey, tc_objs: &vo_analysis::objects::TCObject
         .unwrap_or(0)
 }
 
+/// Allocate a call buffer: `ret_slots` result slots, then the arguments.
+/// Returns the buffer start and the start of the arguments.
+fn alloc_call_buffer(builder: &mut FuncBuilder, arg_slots: u16, ret_slots: u16) -> (u16, u16) {
+    let buf = builder.alloc_temp_typed(&vec![SlotType::Value; (ret_slots + arg_slots).max(1) as usize]);
+    (buf, buf + ret_slots)
+}
+
 /// Emit a function call instruction.
-fn emit_call(builder: &mut FuncBuilder, func_id: u32, args_start: u16, arg_slots: u16, ret_slots: u16) {
+fn emit_call(builder: &mut FuncBuilder, func_id: u32, buf: u16, arg_slots: u16, ret_slots: u16) {
     let c = crate::type_info::encode_call_args(arg_slots, ret_slots);
     let (func_id_low, func_id_high) = crate::type_info::encode_func_id(func_id);
-    builder.emit_with_flags(Opcode::Call, func_id_high, func_id_low, args_start, c);
+    builder.emit_with_flags(Opcode::Call, func_id_high, func_id_low, buf, c);
 }
 
 /// Emit call and return - common wrapper epilogue.
-fn emit_call_and_return(builder: &mut FuncBuilder, func_id: u32, args_start: u16, arg_slots: u16, ret_slots: u16) {
-    emit_call(builder, func_id, args_start, arg_slots, ret_slots);
+fn emit_call_and_return(builder: &mut FuncBuilder, func_id: u32, buf: u16, arg_slots: u16, ret_slots: u16) {
+    emit_call(builder, func_id, buf, arg_slots, ret_slots);
     builder.set_ret_slots(ret_slots);
-    builder.emit_op(Opcode::Return, args_start, ret_slots, 0);
+    builder.emit_op(Opcode::Return, buf, ret_slots, 0);
 }
 
 // =============================================================================
@@ -109,8 +116,7 @@ pub fn generate_iface_wrapper(
     // Allocate args area for call (ensure enough space for return values)
     let forwarded_param_slots: u16 = wrapper_param_slots.iter().map(|(_, s)| *s).sum();
     let total_arg_slots = recv_slots + forwarded_param_slots;
-    let alloc_slots = total_arg_slots.max(ret_slots);
-    let args_start = builder.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; alloc_slots as usize]);
+    let (buf, args_start) = alloc_call_buffer(&mut builder, total_arg_slots, ret_slots);
     
     if needs_unbox {
         // Struct/Array: dereference GcRef to get value
@@ -130,7 +136,7 @@ pub fn generate_iface_wrapper(
     }
     
     // Call and return
-    emit_call_and_return(&mut builder, original_func_id, args_start, total_arg_slots, ret_slots);
+    emit_call_and_return(&mut builder, original_func_id, buf, total_arg_slots, ret_slots);
     
     let func_def = builder.build();
     let wrapper_id = ctx.add_function(func_def);
@@ -204,8 +210,7 @@ pub fn generate_method_expr_promoted_wrapper(
     
     // Allocate args area for call
     let total_arg_slots = recv_slots_for_call + forwarded_param_slots;
-    let alloc_slots = total_arg_slots.max(ret_slots);
-    let args_start = builder.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; alloc_slots as usize]);
+    let (buf, args_start) = alloc_call_buffer(&mut builder, total_arg_slots, ret_slots);
     
     // Emit receiver extraction based on path
     let start = crate::embed::TraverseStart::new(outer_recv, outer_is_pointer);
@@ -218,7 +223,7 @@ pub fn generate_method_expr_promoted_wrapper(
     }
     
     // Call and return
-    emit_call_and_return(&mut builder, original_func_id, args_start, total_arg_slots, ret_slots);
+    emit_call_and_return(&mut builder, original_func_id, buf, total_arg_slots, ret_slots);
     
     let func_def = builder.build();
     ctx.add_function(func_def)
@@ -277,8 +282,7 @@ pub fn generate_promoted_wrapper(
     
     // Allocate args area for call (ensure enough space for return values)
     let total_arg_slots = recv_slots_for_call + forwarded_param_slots;
-    let alloc_slots = total_arg_slots.max(ret_slots);
-    let args_start = builder.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; alloc_slots as usize]);
+    let (buf, args_start) = alloc_call_buffer(&mut builder, total_arg_slots, ret_slots);
     
     // Emit receiver loading based on embedding type
     // For promoted wrapper, outer is always GcRef (outer_is_pointer = true)
@@ -292,7 +296,7 @@ pub fn generate_promoted_wrapper(
     }
     
     // Call and return
-    emit_call_and_return(&mut builder, original_func_id, args_start, total_arg_slots, ret_slots);
+    emit_call_and_return(&mut builder, original_func_id, buf, total_arg_slots, ret_slots);
     
     let func_def = builder.build();
     ctx.add_function(func_def)
@@ -377,16 +381,16 @@ fn generate_embedded_iface_wrapper_impl(
     crate::embed::emit_embed_path_traversal(&mut builder, start, &embed_path.steps, false, 2, iface_slot);
     
     // Allocate args and copy forwarded params
-    let args_start = builder.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; param_slots.max(ret_slots).max(1) as usize]);
+    let (buf, args_start) = alloc_call_buffer(&mut builder, param_slots, ret_slots);
     if let Some(first_param) = first_param_slot {
         builder.emit_copy(args_start, first_param, param_slots);
     }
     
     // CallIface and return
     let c = crate::type_info::encode_call_args(param_slots, ret_slots);
-    builder.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, args_start, c);
+    builder.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, buf, c);
     builder.set_ret_slots(ret_slots);
-    builder.emit_op(Opcode::Return, args_start, ret_slots, 0);
+    builder.emit_op(Opcode::Return, buf, ret_slots, 0);
     
     ctx.add_function(builder.build())
 }
@@ -417,18 +421,18 @@ fn generate_iface_call_wrapper(
     let first_param_slot = define_forwarded_params(&mut builder, param_slots);
     
     // Allocate args buffer
-    let args_start = builder.alloc_temp_typed(&vec![vo_runtime::SlotType::Value; param_slots.max(ret_slots).max(1) as usize]);
+    let (buf, args_start) = alloc_call_buffer(&mut builder, param_slots, ret_slots);
     if let Some(first_param) = first_param_slot {
         builder.emit_copy(args_start, first_param, param_slots);
     }
     
     // CallIface
     let c = crate::type_info::encode_call_args(param_slots, ret_slots);
-    builder.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, args_start, c);
+    builder.emit_with_flags(Opcode::CallIface, method_idx as u8, iface_slot, buf, c);
     
     // Return
     builder.set_ret_slots(ret_slots);
-    builder.emit_op(Opcode::Return, args_start, ret_slots, 0);
+    builder.emit_op(Opcode::Return, buf, ret_slots, 0);
     
     ctx.register_wrapper_from_builder(wrapper_name, builder)
 }
@@ -2,7 +2,7 @@
 
 use vo_analysis::{Checker, Project, AnalysisError};
 use vo_analysis::importer::NullImporter;
-use vo_codegen::compile_project;
+use vo_codegen::{compile_project, compile_project_with_options, CodegenOptions};
 use vo_syntax::parser;
 use vo_common::SourceMap;
 use vo_vm::vm::Vm;
@@ -14,9 +14,11 @@ fn analyze_source(source: &str) -> Result<Project, AnalysisError> {
     use vo_analysis::arena::ArenaKey;
     use vo_analysis::objects::PackageKey;
     
-    let (file, diags, interner) = parser::parse(source, 0);
+    let mut source_map = SourceMap::new();
+    let file_id = source_map.add_file("main.vo", source);
+    let (file, diags, interner) = parser::parse(source, source_map.file_base(file_id).unwrap());
     if diags.has_errors() {
-        return Err(AnalysisError::Parse(diags, SourceMap::new()));
+        return Err(AnalysisError::Parse(diags, source_map));
     }
     
     let mut checker = Checker::new_with_trace(PackageKey::null(), interner.clone(), false);
@@ -26,7 +28,7 @@ fn analyze_source(source: &str) -> Result<Project, AnalysisError> {
     let mut importer = NullImporter::new(PathBuf::from("."));
     if checker.check_with_importer(&[file.clone()], &mut importer).is_err() {
         let diags = checker.diagnostics.take();
-        return Err(AnalysisError::Check(diags, SourceMap::new()));
+        return Err(AnalysisError::Check(diags, source_map));
     }
     
     Ok(Project {
@@ -38,7 +40,7 @@ fn analyze_source(source: &str) -> Result<Project, AnalysisError> {
         files: vec![file],
         imported_files: BTreeMap::new(),
         imported_type_infos: BTreeMap::new(),
-        source_map: SourceMap::new(),
+        source_map,
         extensions: Vec::new(),
     })
 }
@@ -46,7 +48,11 @@ fn analyze_source(source: &str) -> Result<Project, AnalysisError> {
 /// Helper: compile Vo source to Module
 fn compile_source(source: &str) -> vo_vm::bytecode::Module {
     let project = analyze_source(source).expect("analysis failed");
-    compile_project(&project).expect("codegen failed")
+    let module = compile_project(&project).expect("codegen failed");
+    if let Err(errors) = vo_vm::validate::validate(&module) {
+        panic!("codegen produced invalid bytecode: {:?}", errors);
+    }
+    module
 }
 
 /// Helper: compile and run, verify execution completes
@@ -87,6 +93,110 @@ func main() int {
     }
 }
 
+/// `__entry__` must be the module entry, run `__init__` first, then call main.
+#[test]
+fn test_entry_calls_init_then_main() {
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+var g int = 7
+
+func main() int {
+    return g
+}
+"#;
+
+    let module = compile_source(source);
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} not found", name)) as u32;
+    let init_id = func_id("__init__");
+    let main_id = func_id("main");
+    let entry_id = func_id("__entry__");
+    assert_eq!(module.entry_func, entry_id);
+
+    let callees: Vec<u32> = module.functions[entry_id as usize].code.iter()
+        .filter(|inst| inst.opcode() == Opcode::Call)
+        .map(|inst| (inst.a as u32) | ((inst.flags as u32) << 16))
+        .collect();
+    assert_eq!(callees, vec![init_id, main_id]);
+}
+
+/// Globals are laid out with their real slot counts, not a 1-slot placeholder.
+#[test]
+fn test_global_slot_layout() {
+    let source = r#"
+package main
+
+type Point struct {
+    x int
+    y int
+    z int
+}
+
+var p Point
+var any interface{}
+var n = 1
+
+func pair() (int, string) {
+    return 1, "a"
+}
+
+var a, b = pair()
+
+func main() int {
+    return p.x + n
+}
+"#;
+
+    let module = compile_source(source);
+    let global = |name: &str| module.globals.iter()
+        .find(|g| g.name == name)
+        .unwrap_or_else(|| panic!("global {} not found", name));
+
+    assert_eq!(global("p").slots, 3);
+    assert_eq!(global("p").slot_types.len(), 3);
+    assert_eq!(global("any").slots, 2);
+    assert_eq!(global("n").slots, 1);
+    assert_eq!(global("a").slots, 1);
+    assert_eq!(global("b").slots, 1);
+    assert_ne!(global("p").value_kind, global("n").value_kind);
+}
+
+/// `var a, b = f()` evaluates f once and unpacks each result.
+#[test]
+fn test_vm_global_tuple_init() {
+    let source = r#"
+package main
+
+var calls int
+
+func pair() (int, string) {
+    calls++
+    return 7, "seven"
+}
+
+var n, s = pair()
+var x, e interface{} = pair()
+
+func main() int {
+    if n != 7 || s != "seven" {
+        panic("WRONG: tuple globals not unpacked")
+    }
+    if x != 7 || e != "seven" {
+        panic("WRONG: interface tuple globals not converted")
+    }
+    if calls != 2 {
+        panic("WRONG: initializer evaluated more than once")
+    }
+    return 0
+}
+"#;
+    compile_and_run(source);
+}
+
 #[test]
 fn test_simple_arithmetic() {
     let source = r#"
@@ -821,3 +931,2342 @@ func main() int {
 "#;
     compile_and_run(source);
 }
+
+/// Hot functions are promoted to JIT after `call_threshold` calls and
+/// must keep producing the same results as the interpreter.
+#[test]
+fn test_vm_jit_tiered_hot_function() {
+    use vo_vm::vm::jit_mgr::CompileState;
+
+    let source = r#"
+package main
+
+func add(a int, b int) int {
+    return a + b
+}
+
+func main() int {
+    sum := 0
+    for i := 0; i < 200; i++ {
+        sum = add(sum, i)
+    }
+    if sum != 19900 {
+        panic("WRONG: JIT result differs from interpreter")
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let add_id = module.functions.iter()
+        .position(|f| f.name == "add")
+        .expect("add function not found") as u32;
+
+    let mut vm = Vm::with_jit_thresholds(10, u32::MAX);
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(add_id), Some(CompileState::FullyCompiled));
+    assert_eq!(mgr.call_count(add_id), 10);
+
+    let stat = &mgr.stats()[add_id as usize];
+    assert_eq!(stat.func_id, add_id);
+    assert!(stat.compiled && stat.code_size > 0);
+    assert_eq!(stat.call_count, 10);
+}
+
+/// JIT-compiled map reads: the plain form yields the zero value for a missing
+/// key, the comma-ok form also writes the presence flag.
+#[test]
+fn test_vm_jit_map_get_comma_ok() {
+    use vo_vm::vm::jit_mgr::CompileState;
+
+    let source = r#"
+package main
+
+type Pair struct {
+    a int
+    b int
+}
+
+func get(m map[string]int, k string) int {
+    return m[k]
+}
+
+func getOk(m map[string]int, k string) (int, bool) {
+    v, ok := m[k]
+    return v, ok
+}
+
+func getPair(m map[int]Pair, k int) (Pair, bool) {
+    p, ok := m[k]
+    return p, ok
+}
+
+func main() int {
+    m := map[string]int{"one": 1, "zero": 0}
+    pairs := map[int]Pair{7: Pair{a: 3, b: 4}}
+    var nilMap map[string]int
+    for i := 0; i < 20; i++ {
+        if get(m, "one") != 1 || get(m, "missing") != 0 || get(nilMap, "one") != 0 {
+            panic("WRONG: plain map get")
+        }
+        v, ok := getOk(m, "zero")
+        if v != 0 || !ok {
+            panic("WRONG: present zero value reported missing")
+        }
+        v, ok = getOk(m, "missing")
+        if v != 0 || ok {
+            panic("WRONG: missing key reported present")
+        }
+        v, ok = getOk(nilMap, "one")
+        if v != 0 || ok {
+            panic("WRONG: nil map reported present")
+        }
+        p, ok := getPair(pairs, 7)
+        if p.a != 3 || p.b != 4 || !ok {
+            panic("WRONG: multi-slot value")
+        }
+        p, ok = getPair(pairs, 8)
+        if p.a != 0 || p.b != 0 || ok {
+            panic("WRONG: missing multi-slot value not zeroed")
+        }
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} function not found", name)) as u32;
+    let ids = [func_id("get"), func_id("getOk"), func_id("getPair")];
+
+    let mut vm = Vm::with_jit_thresholds(5, u32::MAX);
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    for id in ids {
+        assert_eq!(mgr.compile_state(id), Some(CompileState::FullyCompiled));
+    }
+}
+
+/// Compiled string literals are read from data, not rebuilt byte by byte.
+#[test]
+fn test_vm_jit_long_string_literal() {
+    use vo_vm::vm::jit_mgr::CompileState;
+
+    let literal: String = (0..1000).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+    let source = format!(r#"
+package main
+
+func banner() string {{
+    return "{literal}"
+}}
+
+func main() {{
+    for i := 0; i < 20; i++ {{
+        s := banner()
+        if len(s) != 1000 || s[0] != 'a' || s[999] != 'l' {{
+            panic("WRONG: long literal")
+        }}
+    }}
+}}
+"#);
+    let module = compile_source(&source);
+    let banner_id = module.functions.iter()
+        .position(|f| f.name == "banner")
+        .expect("banner function not found") as u32;
+
+    let mut vm = Vm::with_jit_thresholds(5, u32::MAX);
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(banner_id), Some(CompileState::FullyCompiled));
+}
+
+/// Unbounded recursion panics with a recoverable stack overflow, both
+/// interpreted and through JIT code calling back into the VM.
+#[test]
+fn test_vm_stack_overflow_panics() {
+    use vo_vm::vm::VmError;
+
+    let source = r#"
+package main
+
+func down(n int) int {
+    return down(n+1) + 1
+}
+
+func tryDown() (recovered bool) {
+    defer func() {
+        recovered = recover() != nil
+    }()
+    down(0)
+    return false
+}
+
+func main() {
+    for i := 0; i < 3; i++ {
+        if !tryDown() {
+            panic("WRONG: stack overflow not recovered")
+        }
+    }
+    down(0)
+}
+"#;
+    let module = compile_source(source);
+    for jit in [false, true] {
+        let mut vm = if jit { Vm::with_jit_thresholds(5, u32::MAX) } else { Vm::new() };
+        vm.set_max_call_depth(10_000);
+        vm.load(module.clone());
+        match vm.run() {
+            Err(VmError::PanicUnwound { msg: Some(msg), .. }) => {
+                assert!(msg.contains("stack overflow"), "jit={}: unexpected panic: {}", jit, msg);
+            }
+            other => panic!("jit={}: expected stack overflow panic, got {:?}", jit, other),
+        }
+    }
+}
+
+/// With `checked_arith` compiled integer multiply panics on overflow (and can
+/// be recovered); by default it wraps as Go defines.
+#[test]
+fn test_vm_jit_checked_arith() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = |expect_overflow: bool| format!(r#"
+package main
+
+func mul(a int, b int) int {{
+    return a * b
+}}
+
+func tryMul(a int, b int) (r int, overflowed bool) {{
+    defer func() {{
+        if e := recover(); e != nil {{
+            overflowed = true
+        }}
+    }}()
+    return mul(a, b), false
+}}
+
+func main() int {{
+    for i := 0; i < 20; i++ {{
+        if mul(i, 3) != i*3 {{
+            panic("WRONG: small multiply")
+        }}
+    }}
+    r, overflowed := tryMul(1<<62, 4)
+    if overflowed != {expect_overflow} {{
+        panic("WRONG: overflow handling")
+    }}
+    if !overflowed && r != 0 {{
+        panic("WRONG: unchecked multiply did not wrap")
+    }}
+    return 0
+}}
+"#);
+
+    for checked_arith in [false, true] {
+        let module = compile_source(&source(checked_arith));
+        let mul_id = module.functions.iter()
+            .position(|f| f.name == "mul")
+            .expect("mul function not found") as u32;
+
+        let mut vm = Vm::with_jit_config(JitConfig {
+            call_threshold: 5,
+            loop_threshold: u32::MAX,
+            checked_arith,
+            ..Default::default()
+        });
+        vm.load(module);
+        vm.run().expect("VM execution failed");
+
+        let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+        assert_eq!(mgr.compile_state(mul_id), Some(CompileState::FullyCompiled));
+    }
+}
+
+/// Checked arithmetic leaves unsigned arithmetic alone: uint add, subtract,
+/// multiply and `++`/`--`/`+=` wrap in compiled code as Go defines.
+#[test]
+fn test_vm_jit_checked_arith_uint_wraps() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+func wrap(a uint, b uint) uint {
+    s := a + a
+    d := a - b
+    m := a * 2
+    d++
+    t := a
+    t--
+    u := a
+    u += a
+    return s ^ d ^ m ^ t ^ u
+}
+
+func main() int {
+    for i := 0; i < 20; i++ {
+        wrap(uint(i), 1)
+    }
+    // With a = 1<<63 every operation crosses the int64 boundary:
+    // s = 0, d = 1<<63, m = 0, t = 1<<63 - 1, u = 0
+    if wrap(1<<63, 1) != ^uint(0) {
+        panic("WRONG: uint arithmetic did not wrap")
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let wrap_id = module.functions.iter()
+        .position(|f| f.name == "wrap")
+        .expect("wrap function not found") as u32;
+
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: 5,
+        loop_threshold: u32::MAX,
+        checked_arith: true,
+        ..Default::default()
+    });
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(wrap_id), Some(CompileState::FullyCompiled));
+}
+
+/// Checked arithmetic on int8/int16/int32 panics when the result leaves the
+/// operand type's range, though it still fits in 64 bits.
+#[test]
+fn test_vm_jit_checked_arith_narrow() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = |expect_overflow: bool| format!(r#"
+package main
+
+func add8(a int8, b int8) int8 {{ return a + b }}
+func mul16(a int16, b int16) int16 {{ return a * b }}
+func sub32(a int32, b int32) int32 {{ return a - b }}
+func inc8(a int8) int8 {{ a++; return a }}
+func addAssign32(a int32, b int32) int32 {{ a += b; return a }}
+
+func overflows(f func()) (overflowed bool) {{
+    defer func() {{
+        if e := recover(); e != nil {{
+            overflowed = true
+        }}
+    }}()
+    f()
+    return false
+}}
+
+func main() int {{
+    for i := 0; i < 20; i++ {{
+        if add8(int8(i), 100) != int8(i)+100 || mul16(int16(i), 1000) != int16(i)*1000 ||
+            sub32(int32(i), 7) != int32(i)-7 || inc8(int8(i)) != int8(i)+1 ||
+            addAssign32(int32(i), 1<<30) != int32(i)+1<<30 {{
+            panic("WRONG: in-range narrow arithmetic")
+        }}
+    }}
+    var r8 int8
+    var r16 int16
+    var r32 int32
+    cases := []func(){{
+        func() {{ r8 = add8(127, 1) }},
+        func() {{ r16 = mul16(300, 300) }},
+        func() {{ r32 = sub32(-2147483648, 1) }},
+        func() {{ r8 = inc8(127) }},
+        func() {{ r32 = addAssign32(2147483647, 1) }},
+    }}
+    for _, f := range cases {{
+        if overflows(f) != {expect_overflow} {{
+            panic("WRONG: narrow overflow handling")
+        }}
+    }}
+    if !{expect_overflow} && (r8 != -128 || r16 != 24464 || r32 != -2147483648) {{
+        panic("WRONG: unchecked narrow arithmetic did not wrap")
+    }}
+    return 0
+}}
+"#);
+
+    for checked_arith in [false, true] {
+        let module = compile_source(&source(checked_arith));
+        let ids: Vec<u32> = ["add8", "mul16", "sub32", "inc8", "addAssign32"].iter()
+            .map(|name| module.functions.iter()
+                .position(|f| f.name == *name)
+                .expect("function not found") as u32)
+            .collect();
+
+        let mut vm = Vm::with_jit_config(JitConfig {
+            call_threshold: 5,
+            loop_threshold: u32::MAX,
+            checked_arith,
+            ..Default::default()
+        });
+        vm.load(module);
+        vm.run().expect("VM execution failed");
+
+        let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+        for id in ids {
+            assert_eq!(mgr.compile_state(id), Some(CompileState::FullyCompiled));
+        }
+    }
+}
+
+/// `==` on interfaces holding slices, maps or funcs panics in JIT code with
+/// the same recoverable runtime error as in the interpreter.
+#[test]
+fn test_vm_jit_iface_eq_uncomparable_panics() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+func eq(a any, b any) bool {
+    return a == b
+}
+
+func tryEq(a any, b any) (panicked bool) {
+    defer func() {
+        if r := recover(); r != nil {
+            panicked = true
+        }
+    }()
+    eq(a, b)
+    return false
+}
+
+func main() int {
+    for i := 0; i < 20; i++ {
+        if !eq(i, i) || eq(i, "x") {
+            panic("WRONG: comparable values")
+        }
+    }
+    if !tryEq([]int{1}, []int{1}) {
+        panic("WRONG: slice comparison")
+    }
+    if !tryEq(map[int]int{}, map[int]int{}) {
+        panic("WRONG: map comparison")
+    }
+    return 0
+}
+"#;
+
+    let module = compile_source(source);
+    let eq_id = module.functions.iter()
+        .position(|f| f.name == "eq")
+        .expect("eq function not found") as u32;
+
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: 5,
+        loop_threshold: u32::MAX,
+        ..Default::default()
+    });
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(eq_id), Some(CompileState::FullyCompiled));
+}
+
+/// With async compilation the VM keeps interpreting a hot function while
+/// the worker compiles it, then switches over once the code is installed.
+#[test]
+fn test_vm_jit_async_compile() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+func add(a int, b int) int {
+    return a + b
+}
+
+func main() int {
+    sum := 0
+    for i := 0; i < 2000; i++ {
+        sum = add(sum, i)
+    }
+    if sum != 1999000 {
+        panic("WRONG: JIT result differs from interpreter")
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let add_id = module.functions.iter()
+        .position(|f| f.name == "add")
+        .expect("add function not found") as u32;
+
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: 10,
+        loop_threshold: u32::MAX,
+        async_compile: true,
+        ..Default::default()
+    });
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_mut().expect("JIT manager not initialized");
+    // Calls past the threshold were still interpreted while compiling.
+    assert!(mgr.call_count(add_id) > 10);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while mgr.compile_state(add_id) == Some(CompileState::Compiling) {
+        assert!(std::time::Instant::now() < deadline, "background compile never finished");
+        std::thread::yield_now();
+        mgr.poll_background();
+    }
+    assert_eq!(mgr.compile_state(add_id), Some(CompileState::FullyCompiled));
+    assert!(mgr.get_entry(add_id).is_some());
+}
+
+/// JIT-compiled interface calls resolve the method through the flat itab
+/// table, including itabs built at runtime by interface-to-interface
+/// conversion, so none of them reach the runtime's slow lookup.
+#[test]
+fn test_vm_jit_iface_call_resolved_inline() {
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+type Shape interface {
+    Area() int
+}
+
+type Named interface {
+    Area() int
+    Name() string
+}
+
+type Rect struct {
+    w int
+    h int
+}
+
+func (r Rect) Area() int { return r.w * r.h }
+func (r Rect) Name() string { return "rect" }
+
+type Square struct {
+    s int
+}
+
+func (q *Square) Area() int { return q.s * q.s }
+func (q *Square) Name() string { return "square" }
+
+func total(shapes []Shape, rounds int) int {
+    sum := 0
+    for r := 0; r < rounds; r++ {
+        for i := 0; i < len(shapes); i++ {
+            sum += shapes[i].Area()
+        }
+    }
+    return sum
+}
+
+func main() int {
+    var named Named = &Square{s: 3}
+    var fromIface Shape = named
+    shapes := []Shape{Rect{w: 2, h: 5}, &Square{s: 4}, fromIface}
+    sum := 0
+    for i := 0; i < 50; i++ {
+        sum += total(shapes, 20)
+    }
+    if sum != 35000 {
+        panic("WRONG: JIT result differs from interpreter")
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let total_id = module.functions.iter()
+        .position(|f| f.name == "total")
+        .expect("total function not found") as u32;
+    // Every method an interface call can land on, including Rect's
+    // boxed-receiver wrapper.
+    let mut targets: Vec<u32> = module.itabs.iter().flat_map(|t| t.methods.iter().copied()).collect();
+    targets.sort_unstable();
+    targets.dedup();
+    assert!(!targets.is_empty(), "no itab methods");
+
+    // Only total is compiled. The methods stay interpreted, so every call
+    // the JIT code makes to them is counted on entry to the VM.
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: u32::MAX,
+        loop_threshold: u32::MAX,
+        ..Default::default()
+    });
+    vm.load(module.clone());
+    let mgr = vm.jit_mgr.as_mut().expect("JIT manager not initialized");
+    mgr.compile_full(total_id, &module.functions[total_id as usize], &module)
+        .expect("total failed to compile");
+    vm.run().expect("VM execution failed");
+
+    // 50 calls of total, 20 rounds over 3 shapes each.
+    let iface_calls = 50 * 20 * 3;
+    let mgr = vm.jit_mgr.as_ref().unwrap();
+    let dispatched: u64 = targets.iter().map(|&id| mgr.call_count(id) as u64).sum();
+    assert_eq!(dispatched, iface_calls);
+    assert_eq!(vm.state.itab_cache.unresolved_calls(), 0);
+}
+
+/// Interface-to-interface assignment of a boxed struct deoptimizes: the
+/// VM re-runs the call in the interpreter and drops the JIT code. Once the
+/// function has had side effects it cannot restart, so it copies inline.
+#[test]
+fn test_vm_jit_deopt_iface_to_iface() {
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+type Shape interface {
+    Area() int
+}
+
+type Named interface {
+    Area() int
+    Name() string
+}
+
+type Rect struct {
+    w int
+    h int
+}
+
+func (r Rect) Area() int { return r.w * r.h }
+func (r Rect) Name() string { return "rect" }
+
+var calls int
+
+func area(n Named) int {
+    var s Shape = n
+    return s.Area()
+}
+
+func countedArea(n Named) int {
+    calls++
+    var s Shape = n
+    return s.Area()
+}
+
+func main() int {
+    var n Named = Rect{w: 3, h: 4}
+    sum := 0
+    for i := 0; i < 10; i++ {
+        sum += area(n)
+        sum += countedArea(n)
+    }
+    if sum != 240 {
+        panic("WRONG: deopt result differs from interpreter")
+    }
+    if calls != 10 {
+        panic("WRONG: side effect repeated")
+    }
+    return 0
+}
+"#;
+    let module = compile_source(source);
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} function not found", name)) as u32;
+    let area_id = func_id("area");
+    let counted_id = func_id("countedArea");
+
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: 1,
+        loop_threshold: u32::MAX,
+        ..Default::default()
+    });
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(area_id), Some(CompileState::Deoptimized));
+    assert!(mgr.get_entry(area_id).is_none());
+    assert_eq!(mgr.compile_state(counted_id), Some(CompileState::FullyCompiled));
+}
+
+/// Native code can call a Vo closure and read its results back typed.
+#[test]
+fn test_vm_call_closure_ret() {
+    use vo_runtime::gc::GcRef;
+    use vo_runtime::PanicKind;
+    use vo_vm::vm::VmError;
+
+    let source = r#"
+package main
+
+var base = 10
+var double func(int) int
+var describe func(int) (string, bool)
+var check func(int) int
+
+func main() int {
+    k := 5
+    check = func(x int) int {
+        if x < 0 {
+            panic("negative")
+        }
+        return []int{1, 2}[x]
+    }
+    double = func(x int) int { return x*2 + k + base }
+    describe = func(x int) (string, bool) {
+        if x > 0 {
+            return "positive", true
+        }
+        return "other", false
+    }
+    return 0
+}
+"#;
+
+    let module = compile_source(source);
+    let global_slot = |name: &str| module.globals.iter()
+        .take_while(|g| g.name != name)
+        .map(|g| g.slots as usize)
+        .sum::<usize>();
+    let (double_slot, describe_slot) = (global_slot("double"), global_slot("describe"));
+    let check_slot = global_slot("check");
+
+    let mut vm = Vm::new();
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let double = vm.state.globals[double_slot] as GcRef;
+    let describe = vm.state.globals[describe_slot] as GcRef;
+    assert_eq!(vm.call_closure_ret::<i64>(double, &[7]).unwrap(), 29);
+    assert_eq!(vm.call_closure_ret::<(String, bool)>(describe, &[3]).unwrap(), ("positive".to_string(), true));
+    assert!(matches!(
+        vm.call_closure_ret::<()>(double, &[7]),
+        Err(VmError::ReturnSlotMismatch { expected: 0, found: 1, .. })
+    ));
+
+    // A panic comes back with its message and where it was raised
+    let check = vm.state.globals[check_slot] as GcRef;
+    match vm.call_closure_ret::<i64>(check, &[-1i64 as u64]) {
+        Err(VmError::PanicUnwound { msg: Some(msg), loc: Some(_), kind: None }) => assert_eq!(msg, "negative"),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match vm.call_closure_ret::<i64>(check, &[5]) {
+        Err(VmError::PanicUnwound { msg: Some(msg), loc: Some(_), kind: Some(PanicKind::IndexOutOfBounds) }) => {
+            assert!(msg.contains("index out of range"), "{}", msg)
+        }
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    assert_eq!(vm.call_closure_ret::<i64>(check, &[1]).unwrap(), 2);
+}
+
+/// A method value binds its receiver when it is evaluated: a copy for value
+/// receivers, the pointer for pointer receivers.
+#[test]
+fn test_vm_method_value_captures_receiver() {
+    use vo_runtime::gc::GcRef;
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+type Counter struct {
+    n int
+}
+
+func (c Counter) Get() int { return c.n }
+func (c *Counter) Inc() { c.n++ }
+
+var get func() int
+var inc func()
+var read func() int
+
+func main() int {
+    c := Counter{n: 1}
+    get = c.Get
+    c.n = 5
+    p := &Counter{n: 10}
+    inc = p.Inc
+    read = func() int { return p.n }
+    return 0
+}
+"#;
+
+    let module = compile_source(source);
+    let main_func = module.functions.iter().find(|f| f.name == "main").unwrap();
+    let closures = main_func.code.iter().filter(|i| i.opcode() == Opcode::ClosureNew).count();
+    assert_eq!(closures, 3);
+
+    let global_slot = |name: &str| module.globals.iter()
+        .take_while(|g| g.name != name)
+        .map(|g| g.slots as usize)
+        .sum::<usize>();
+    let slots = [global_slot("get"), global_slot("inc"), global_slot("read")];
+
+    let mut vm = Vm::new();
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let [get, inc, read] = slots.map(|s| vm.state.globals[s] as GcRef);
+    assert_eq!(vm.call_closure_ret::<i64>(get, &[]).unwrap(), 1);
+    vm.call_closure_ret::<()>(inc, &[]).unwrap();
+    vm.call_closure_ret::<()>(inc, &[]).unwrap();
+    assert_eq!(vm.call_closure_ret::<i64>(read, &[]).unwrap(), 12);
+}
+
+/// Constant expressions (arithmetic, conversions, len of constant strings)
+/// compile to the same code as the equivalent literal.
+#[test]
+fn test_constant_folding() {
+    let source = r#"
+package main
+
+const hour = 3600
+const greeting = "hello"
+
+func product() int {
+    return 2 * hour
+}
+
+func literal() int {
+    return 7200
+}
+
+func convert() float64 {
+    return float64(hour)
+}
+
+func literal_float() float64 {
+    return 3600.0
+}
+
+func length() int {
+    return len(greeting + ", world")
+}
+
+func literal_len() int {
+    return 12
+}
+
+func main() int {
+    return 0
+}
+"#;
+
+    let module = compile_source(source);
+    let code_len = |name: &str| module.functions.iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} not found", name))
+        .code.len();
+
+    assert_eq!(code_len("product"), code_len("literal"));
+    assert_eq!(code_len("convert"), code_len("literal_float"));
+    assert_eq!(code_len("length"), code_len("literal_len"));
+}
+
+/// Repeated literals and value metas share one constant pool entry.
+#[test]
+fn test_constant_pool_dedup() {
+    use vo_vm::bytecode::Constant;
+
+    let source = r#"
+package main
+
+func greet() int {
+    a := "hello"
+    b := "hello"
+    c := "hello" + "!"
+    s1 := make([]int, 2)
+    s2 := make([]int, 3)
+    return len(a) + len(b) + len(c) + len(s1) + len(s2) + len("hello")
+}
+
+func main() int {
+    return greet() + len("hello")
+}
+"#;
+
+    let module = compile_source(source);
+    let hello = module.constants.iter()
+        .filter(|c| **c == Constant::String("hello".into()))
+        .count();
+    assert_eq!(hello, 1);
+
+    let unique: std::collections::HashSet<&Constant> = module.constants.iter().collect();
+    assert_eq!(unique.len(), module.constants.len(), "duplicate constants: {:?}", module.constants);
+}
+
+/// Copies that cannot change any slot are not emitted, unless a jump may land between them.
+#[test]
+fn test_redundant_copies_dropped() {
+    use vo_codegen::FuncBuilder;
+    use vo_vm::instruction::Opcode;
+
+    let mut b = FuncBuilder::new("f");
+    b.emit_op(Opcode::Copy, 1, 1, 0);  // self copy
+    b.emit_copy(4, 4, 3);              // self CopyN
+    b.emit_op(Opcode::Copy, 2, 3, 0);
+    b.emit_op(Opcode::Copy, 3, 2, 0);  // undoes nothing: 3 already equals 2
+    b.emit_op(Opcode::Copy, 5, 6, 0);
+    let target = b.current_pc();
+    b.emit_op(Opcode::Copy, 6, 5, 0);  // kept: reached by the jump below
+    b.emit_jump_to(Opcode::JumpIf, 7, target);
+    b.emit_op(Opcode::Copy, 8, 9, 0);
+    b.mark_span(vo_common::span::Span::dummy());
+    b.emit_op(Opcode::Copy, 9, 8, 0);  // kept: starts a debug location
+    b.emit_op(Opcode::Return, 0, 0, 0);
+
+    let code: Vec<_> = b.build().code.iter().map(|i| (i.opcode(), i.a, i.b)).collect();
+    assert_eq!(code, vec![
+        (Opcode::Copy, 2, 3),
+        (Opcode::Copy, 5, 6),
+        (Opcode::Copy, 6, 5),
+        (Opcode::JumpIf, 7, 0xFFFF),
+        (Opcode::Copy, 8, 9),
+        (Opcode::Copy, 9, 8),
+        (Opcode::Return, 0, 0),
+    ]);
+}
+
+/// When every path of the body returns, the implicit trailing `Return` is dropped.
+#[test]
+fn test_unreachable_tail_trimmed() {
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+func sign(x int) int {
+    if x < 0 {
+        return -1
+    } else {
+        return 1
+    }
+}
+
+func loop(n int) int {
+    for {
+        if n > 10 {
+            return n
+        }
+        n++
+    }
+}
+
+func fallsOff(x int) {
+    if x > 0 {
+        println(x)
+    }
+}
+
+func main() {
+    if sign(-5) != -1 || sign(5) != 1 || loop(0) != 11 {
+        panic("WRONG")
+    }
+    fallsOff(1)
+}
+"#;
+
+    let module = compile_source(source);
+    let code = |name: &str| module.functions.iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} not found", name))
+        .code.clone();
+    let returns = |name: &str| code(name).iter().filter(|i| i.opcode() == Opcode::Return).count();
+
+    assert_eq!(returns("sign"), 2);
+    assert_eq!(code("sign").last().unwrap().opcode(), Opcode::Return);
+    // A loop left only by return ends with its back edge
+    assert_eq!(code("loop").last().unwrap().opcode(), Opcode::Jump);
+    // Reached by the if's jump
+    assert_eq!(returns("fallsOff"), 1);
+
+    let mut vm = Vm::new();
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+}
+
+/// A collection triggered from inside JIT code keeps objects that are only
+/// referenced from JIT locals, both in compiled functions and in OSR loops.
+#[test]
+fn test_vm_jit_gc_roots() {
+    use vo_runtime::ffi::{ExternCallContext, ExternResult};
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    fn collect_and_count(call: &mut ExternCallContext) -> ExternResult {
+        let vm = unsafe { &mut *(call.vm_ptr() as *mut Vm) };
+        vm.collect_garbage();
+        call.ret_i64(0, vm.state.gc.object_count() as i64);
+        ExternResult::Ok
+    }
+
+    let source = r#"
+package main
+
+type Node struct {
+    v    int
+    next *Node
+}
+
+func collectAndCount() int
+
+func build(n int) *Node {
+    var head *Node
+    for i := 0; i < n; i++ {
+        head = &Node{v: i, next: head}
+        if collectAndCount() < i+1 {
+            panic("WRONG: live node collected")
+        }
+    }
+    return head
+}
+
+func main() {
+    for round := 0; round < 10; round++ {
+        sum := 0
+        for n := build(50); n != nil; n = n.next {
+            sum += n.v
+        }
+        if sum != 1225 {
+            panic("WRONG: list corrupted")
+        }
+    }
+}
+"#;
+
+    let configs = [
+        (3, u32::MAX), // build compiled as a whole function
+        (u32::MAX, 5), // build's loop entered through OSR
+    ];
+    for (call_threshold, loop_threshold) in configs {
+        let module = compile_source(source);
+        let extern_id = module.externs.iter()
+            .position(|e| e.name == "main_collectAndCount")
+            .expect("collectAndCount extern not found") as u32;
+        let build_id = module.functions.iter()
+            .position(|f| f.name == "build")
+            .expect("build function not found") as u32;
+
+        let mut vm = Vm::with_jit_config(JitConfig {
+            call_threshold,
+            loop_threshold,
+            ..Default::default()
+        });
+        vm.state.extern_registry.register_with_context(extern_id, collect_and_count);
+        vm.load(module);
+        vm.run().expect("VM execution failed");
+
+        if call_threshold != u32::MAX {
+            let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+            assert_eq!(mgr.compile_state(build_id), Some(CompileState::FullyCompiled));
+        }
+    }
+}
+
+/// A collection while call arguments are being evaluated sees them with
+/// their own slot types: the pointer argument stays alive and the integer
+/// arguments are not read as pointers.
+#[test]
+fn test_vm_gc_during_call_args() {
+    use vo_runtime::ffi::{ExternCallContext, ExternResult};
+    use vo_vm::JitConfig;
+
+    fn collect_and_count(call: &mut ExternCallContext) -> ExternResult {
+        let vm = unsafe { &mut *(call.vm_ptr() as *mut Vm) };
+        vm.collect_garbage();
+        call.ret_i64(0, vm.state.gc.object_count() as i64);
+        ExternResult::Ok
+    }
+
+    let source = r#"
+package main
+
+type Node struct {
+    v    int
+    next *Node
+}
+
+func collectAndCount() int
+
+func link(n int, p *Node, live int) *Node {
+    if live < 2*n+1 {
+        panic("WRONG: live node collected")
+    }
+    return &Node{v: n + p.v, next: p}
+}
+
+func main() {
+    var head *Node
+    for i := 0; i < 20; i++ {
+        head = link(i, &Node{v: i, next: head}, collectAndCount())
+    }
+    sum := 0
+    for n := head; n != nil; n = n.next {
+        sum += n.v
+    }
+    if sum != 570 {
+        panic("WRONG: list corrupted")
+    }
+}
+"#;
+
+    let configs = [
+        (u32::MAX, u32::MAX), // interpreted
+        (u32::MAX, 5),        // main's loop entered through OSR
+    ];
+    for (call_threshold, loop_threshold) in configs {
+        let module = compile_source(source);
+        let extern_id = module.externs.iter()
+            .position(|e| e.name == "main_collectAndCount")
+            .expect("collectAndCount extern not found") as u32;
+
+        let mut vm = Vm::with_jit_config(JitConfig {
+            call_threshold,
+            loop_threshold,
+            ..Default::default()
+        });
+        vm.state.extern_registry.register_with_context(extern_id, collect_and_count);
+        vm.load(module);
+        vm.run().expect("VM execution failed");
+    }
+}
+
+/// Runtime errors carry the faulting pc, and the module's debug info maps it
+/// back to the source line, from interpreted and compiled code alike.
+#[test]
+fn test_vm_runtime_error_location() {
+    use vo_vm::vm::VmError;
+
+    let template = r#"
+package main
+
+type T struct{ v int }
+
+func crash(kind int) int {
+    a := []int{1, 2}
+    var p *T
+    zero := 0
+    if kind == 0 {
+        return a[kind+5]
+    }
+    if kind == 1 {
+        return p.v
+    }
+    if kind == 2 {
+        return kind / zero
+    }
+    if kind == 3 {
+        panic("boom")
+    }
+    return 0
+}
+
+func main() {
+    for i := 0; i < 10; i++ {
+        crash(-1)
+    }
+    crash(KIND)
+}
+"#;
+    for (kind, line) in [(0, 11), (1, 14), (2, 17), (3, 20)] {
+        let module = compile_source(&template.replace("KIND", &kind.to_string()));
+        for jit in [false, true] {
+            let mut vm = if jit { Vm::with_jit_thresholds(2, u32::MAX) } else { Vm::new() };
+            vm.load(module.clone());
+            let err = vm.run().expect_err("program should fail");
+            let loc = match &err {
+                VmError::PanicUnwound { loc, .. }
+                | VmError::IndexOutOfBounds(loc)
+                | VmError::NilPointerDereference(loc)
+                | VmError::DivisionByZero(loc) => loc.as_ref(),
+                _ => None,
+            };
+            let src = loc.and_then(|l| module.debug_info.lookup(l.func_id, l.pc));
+            let src = src.unwrap_or_else(|| panic!("kind={} jit={}: no location for {:?}", kind, jit, err));
+            assert_eq!((src.file.as_str(), src.line), ("main.vo", line), "kind={} jit={}: {:?}", kind, jit, err);
+        }
+    }
+}
+
+/// `heap_stats` counts every allocation, freed or not, and the collections run.
+#[test]
+fn test_vm_heap_stats() {
+    let run = |n: usize| {
+        let source = format!(r#"
+package main
+
+func main() {{
+    total := 0
+    for i := 0; i < {}; i++ {{
+        s := make([]int, 64)
+        total += len(s)
+    }}
+    if total != {} * 64 {{
+        panic("WRONG")
+    }}
+}}
+"#, n, n);
+        let mut vm = Vm::new();
+        vm.load(compile_source(&source));
+        vm.run().unwrap();
+        vm
+    };
+    let small = run(10).heap_stats();
+    let mut vm = run(1000);
+    let large = vm.heap_stats();
+    assert!(large.bytes_allocated >= small.bytes_allocated + 990 * 64 * 8,
+        "{:?} vs {:?}", large, small);
+    assert!(large.live_objects >= 1000);
+    assert_eq!(large.gc_cycles, 0);
+
+    vm.collect_garbage();
+    let after = vm.heap_stats();
+    assert_eq!(after.gc_cycles, 1);
+    assert_eq!(after.bytes_allocated, large.bytes_allocated);
+    assert!(after.live_objects < large.live_objects);
+}
+
+/// A string switch with many constant cases is lowered to a binary search:
+/// a few StrLt comparisons narrow the range before StrEq picks the case.
+#[test]
+fn test_string_switch_binary_search() {
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+func classify(s string) int {
+    switch s {
+    case "jan":
+        return 1
+    case "feb":
+        return 2
+    case "mar":
+        return 3
+    case "apr":
+        return 4
+    case "may":
+        return 5
+    case "jun":
+        return 6
+    case "jul":
+        return 7
+    case "aug":
+        return 8
+    case "sep":
+        return 9
+    case "oct":
+        return 10
+    default:
+        return 0
+    }
+}
+
+func short(s string) int {
+    switch s {
+    case "x":
+        return 1
+    case "y":
+        return 2
+    case "z":
+        return 3
+    }
+    return 0
+}
+
+func main() {
+    months := []string{"jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct"}
+    for i, m := range months {
+        if classify(m) != i+1 {
+            panic("WRONG: " + m)
+        }
+    }
+    for _, m := range []string{"", "a", "nov", "dec", "junk", "ja", "zzz"} {
+        if classify(m) != 0 {
+            panic("WRONG: matched " + m)
+        }
+    }
+    if short("y") != 2 || short("w") != 0 {
+        panic("WRONG: short switch")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let count = |name: &str, op: Opcode| module.functions.iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("function {} not found", name))
+        .code.iter()
+        .filter(|inst| inst.opcode() == op)
+        .count();
+    // 10 cases: three pivots split them into ranges of 2 or 3, each case tested once
+    assert_eq!(count("classify", Opcode::StrLt), 3);
+    assert_eq!(count("classify", Opcode::StrEq), 10);
+    // Few cases: compared in order
+    assert_eq!(count("short", Opcode::StrLt), 0);
+    assert_eq!(count("short", Opcode::StrEq), 3);
+
+    compile_and_run(source);
+}
+
+/// Dense integer switches dispatch through a JumpTable; sparse ones keep
+/// comparing case by case.
+#[test]
+fn test_int_switch_jump_table() {
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+func dense(n int) int {
+    switch n {
+    case 3:
+        return 1
+    case 4:
+        return 2
+    case 5, 6:
+        return 3
+    case 8:
+        return 4
+    }
+    return 0
+}
+
+func withDefault(n int) int {
+    switch n {
+    case -2:
+        return 1
+    default:
+        return 9
+    case -1:
+        return 2
+    case 0:
+        return 3
+    case 1:
+        return 4
+    }
+}
+
+func sparse(n int) int {
+    switch n {
+    case 1:
+        return 1
+    case 10:
+        return 2
+    case 100:
+        return 3
+    case 1000:
+        return 4
+    }
+    return 0
+}
+
+func main() {
+    want := []int{0, 0, 0, 1, 2, 3, 3, 0, 4, 0}
+    for i, w := range want {
+        if dense(i) != w || dense(-i-1) != 0 {
+            panic("WRONG: dense")
+        }
+    }
+    if withDefault(-3) != 9 || withDefault(-2) != 1 || withDefault(1) != 4 || withDefault(2) != 9 {
+        panic("WRONG: withDefault")
+    }
+    if sparse(10) != 2 || sparse(1000) != 4 || sparse(11) != 0 {
+        panic("WRONG: sparse")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let count = |name: &str, op: Opcode| module.functions.iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("function {} not found", name))
+        .code.iter()
+        .filter(|inst| inst.opcode() == op)
+        .count();
+    for (name, table, compares) in [("dense", 1, 0), ("withDefault", 1, 0), ("sparse", 0, 4)] {
+        assert_eq!(count(name, Opcode::JumpTable), table, "{}", name);
+        assert_eq!(count(name, Opcode::EqI), compares, "{}", name);
+    }
+
+    compile_and_run(source);
+}
+
+/// Escape notes report each local the codegen heap-allocates, and only those.
+#[test]
+fn test_escape_notes() {
+    let source = r#"
+package main
+
+type Point struct {
+    x int
+    y int
+}
+
+func newPoint() *Point {
+    p := Point{1, 2}
+    return &p
+}
+
+func counter() func() int {
+    n := 0
+    return func() int {
+        n++
+        return n
+    }
+}
+
+func sum(xs []int) int {
+    total := 0
+    for _, x := range xs {
+        total += x
+    }
+    return total
+}
+
+func main() {
+    println(newPoint().x, counter()(), sum([]int{1, 2}))
+}
+"#;
+    let project = analyze_source(source).expect("analysis failed");
+    let (_, notes) = compile_project_with_options(&project, &CodegenOptions { escape_notes: true, ..Default::default() })
+        .expect("codegen failed");
+    let found: Vec<_> = notes.iter()
+        .map(|n| (n.func.as_str(), n.name.as_str(), n.loc.as_ref().map(|l| l.line), n.captured))
+        .collect();
+    assert_eq!(found, vec![
+        ("newPoint", "p", Some(10), false),
+        ("counter", "n", Some(15), true),
+    ]);
+    assert_eq!(notes[0].to_string(), format!("{}:10:5: moved to heap: p", notes[0].loc.as_ref().unwrap().file));
+
+    let (_, notes) = compile_project_with_options(&project, &CodegenOptions::default()).unwrap();
+    assert!(notes.is_empty());
+}
+
+/// Restoring a snapshot undoes changes to globals and to heap objects
+/// reachable from them, and survives a collection in between.
+#[test]
+fn test_vm_snapshot_restore() {
+    use vo_runtime::gc::GcRef;
+
+    let source = r#"
+package main
+
+type Node struct {
+    name string
+    next *Node
+}
+
+var counter int
+var items []int
+var names map[string]int
+var head *Node
+var mutate func()
+var verify func()
+
+func main() {
+    counter = 1
+    items = []int{1, 2, 3}
+    names = map[string]int{"a": 1}
+    head = &Node{name: "first"}
+
+    mutate = func() {
+        counter += 10
+        items[0] = 100
+        items = append(items, 4)
+        names["a"] = 2
+        names["b"] = 3
+        head.name = "changed"
+        head.next = &Node{name: "second"}
+    }
+    verify = func() {
+        if counter != 1 || len(items) != 3 || items[0] != 1 {
+            panic("WRONG: globals not restored")
+        }
+        if len(names) != 1 || names["a"] != 1 {
+            panic("WRONG: map not restored")
+        }
+        if head.name != "first" || head.next != nil {
+            panic("WRONG: struct not restored")
+        }
+    }
+}
+"#;
+    let module = compile_source(source);
+    let offset = |name: &str| module.globals.iter()
+        .take_while(|g| g.name != name)
+        .map(|g| g.slots as usize)
+        .sum::<usize>();
+    let (counter, mutate, verify) = (offset("counter"), offset("mutate"), offset("verify"));
+
+    let mut vm = Vm::new();
+    vm.load(module.clone());
+    vm.run().unwrap();
+    let closure = |vm: &Vm, slot: usize| vm.state.globals[slot] as GcRef;
+
+    let snapshot = vm.snapshot();
+    vm.call_closure_ret::<()>(closure(&vm, verify), &[]).unwrap();
+    vm.call_closure_ret::<()>(closure(&vm, mutate), &[]).unwrap();
+    assert_eq!(vm.state.globals[counter], 11);
+    assert!(vm.call_closure_ret::<()>(closure(&vm, verify), &[]).is_err());
+
+    vm.collect_garbage();
+    vm.restore(&snapshot);
+    assert_eq!(vm.state.globals[counter], 1);
+    vm.call_closure_ret::<()>(closure(&vm, verify), &[]).unwrap();
+
+    // The snapshot can be restored more than once.
+    vm.call_closure_ret::<()>(closure(&vm, mutate), &[]).unwrap();
+    vm.restore(&snapshot);
+    vm.collect_garbage();
+    vm.call_closure_ret::<()>(closure(&vm, verify), &[]).unwrap();
+}
+
+/// Incremental collection keeps up with a program that allocates in a loop
+/// while moving references between already-marked and unmarked objects:
+/// no step marks or sweeps more than a small fraction of the heap, garbage
+/// is reclaimed, and the live data survives intact.
+#[test]
+fn test_vm_incremental_gc_stress() {
+    use vo_runtime::ffi::{ExternCall, ExternResult};
+
+    fn echo(call: &mut ExternCall) -> ExternResult {
+        call.ret_i64(0, call.arg_i64(0));
+        ExternResult::Ok
+    }
+
+    // dst[j], src[i] = src[i], nil, without a barrier
+    fn move_node(call: &mut ExternCall) -> ExternResult {
+        use vo_runtime::objects::slice;
+        let (dst, src) = (call.arg_ref(0), call.arg_ref(1));
+        let (j, i) = (call.arg_i64(2) as usize, call.arg_i64(3) as usize);
+        unsafe {
+            let dst = slice::data_ptr(dst) as *mut u64;
+            let src = slice::data_ptr(src) as *mut u64;
+            *dst.add(j) = *src.add(i);
+            *src.add(i) = 0;
+        }
+        ExternResult::Ok
+    }
+
+    let source = r#"
+package main
+
+type Node struct {
+    v    int
+    next *Node
+}
+
+func churn(n int) int {
+    var head *Node
+    for i := 0; i < n; i++ {
+        head = &Node{v: i, next: head}
+    }
+    sum := 0
+    for p := head; p != nil; p = p.next {
+        sum += p.v
+    }
+    return sum
+}
+
+// Externs write without barriers, so marking must not span one that is
+// passed references; echo is passed none and may run while marking
+func echo(n int) int
+func move(dst []*Node, src []*Node, j int, i int)
+
+func main() {
+    const live = 20000
+    a := make([]*Node, live)
+    b := make([]*Node, live)
+    for i := 0; i < live; i++ {
+        a[i] = &Node{v: i}
+    }
+    var names []string
+    for round := 0; round < 30; round++ {
+        // Each node's only reference moves to a permuted slot of the other
+        // slice, which the collector may already have marked
+        src, dst := a, b
+        if round%2 == 1 {
+            src, dst = b, a
+        }
+        for i := 0; i < live; i++ {
+            j := (i*7919 + round) % live
+            n := src[i]
+            n.next = &Node{v: round}
+            if i%100 == 0 {
+                move(dst, src, j, i)
+            } else {
+                src[i] = nil
+                dst[j] = n
+            }
+        }
+        if churn(2000) != 1999*1000 {
+            panic("WRONG: churn list corrupted")
+        }
+        names = append(names, "round")
+        if echo(round) != round {
+            panic("WRONG: extern result")
+        }
+    }
+    sum := 0
+    for _, n := range a {
+        if n.next.v != 29 {
+            panic("WRONG: next overwritten")
+        }
+        sum += n.v
+    }
+    for _, n := range b {
+        if n != nil {
+            panic("WRONG: stale reference")
+        }
+    }
+    if sum != live*(live-1)/2 || len(names) != 30 || names[29] != "round" {
+        panic("WRONG: live data corrupted")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let extern_id = |name: &str| module.externs.iter()
+        .position(|e| e.name == name)
+        .expect("extern not found") as u32;
+    let (echo_id, move_id) = (extern_id("main_echo"), extern_id("main_move"));
+    for jit in [false, true] {
+        let mut vm = if jit { Vm::with_jit_thresholds(5, 5) } else { Vm::new() };
+        vm.set_incremental_gc(true);
+        vm.state.extern_registry.register(echo_id, echo);
+        vm.state.extern_registry.register(move_id, move_node);
+        vm.load(module.clone());
+        vm.run().unwrap_or_else(|e| panic!("jit={}: {:?}", jit, e));
+
+        let stats = vm.state.gc.stats();
+        assert!(stats.cycles >= 3, "jit={}: only {} cycles", jit, stats.cycles);
+        // The live heap alone is over 40k objects; a stop-the-world pause would visit all of them
+        assert!(stats.max_step_work < 20_000, "jit={}: step did {} objects of work", jit, stats.max_step_work);
+        // Around 700k objects are allocated in total
+        assert!(vm.state.gc.object_count() < 200_000, "jit={}: {} objects left", jit, vm.state.gc.object_count());
+    }
+}
+
+/// Collecting after every allocation finds any value the collector can't
+/// see as a root: it gets freed while still in use and the result is wrong.
+#[cfg(debug_assertions)]
+#[test]
+fn test_vm_collect_every_alloc() {
+    let source = r#"
+package main
+
+type Node struct {
+    v    int
+    name string
+    next *Node
+}
+
+type Shape interface {
+    Area() int
+}
+
+type Rect struct {
+    w, h int
+}
+
+func (r Rect) Area() int { return r.w * r.h }
+
+func build(n int) *Node {
+    var head *Node
+    for i := 0; i < n; i++ {
+        head = &Node{v: i, name: "n" + string(rune('a'+i%26)), next: head}
+    }
+    return head
+}
+
+func adder(base []int) func(int) int {
+    return func(x int) int { return base[0] + x }
+}
+
+func main() {
+    head := build(40)
+    sum := 0
+    names := ""
+    for n := head; n != nil; n = n.next {
+        sum += n.v
+        names += n.name
+    }
+    if sum != 780 || len(names) != 80 || names[:4] != "nnnm" {
+        panic("WRONG: list")
+    }
+
+    m := map[string][]int{}
+    for i := 0; i < 30; i++ {
+        key := "k" + string(rune('0'+i%5))
+        m[key] = append(m[key], i)
+    }
+    if len(m) != 5 || len(m["k3"]) != 6 || m["k3"][5] != 28 {
+        panic("WRONG: map")
+    }
+
+    shapes := []Shape{}
+    for i := 1; i <= 10; i++ {
+        shapes = append(shapes, Rect{i, i + 1})
+    }
+    area := 0
+    for _, s := range shapes {
+        area += s.Area()
+    }
+    if area != 440 {
+        panic("WRONG: interfaces")
+    }
+
+    f := adder([]int{100})
+    ch := make(chan *Node, 4)
+    go func() {
+        for i := 0; i < 20; i++ {
+            ch <- &Node{v: f(i)}
+        }
+        close(ch)
+    }()
+    got := 0
+    for n := range ch {
+        got += n.v
+    }
+    if got != 2190 {
+        panic("WRONG: goroutine")
+    }
+}
+"#;
+    let module = compile_source(source);
+    for jit in [false, true] {
+        let mut vm = if jit { Vm::with_jit_thresholds(2, 2) } else { Vm::new() };
+        vm.state.gc.collect_every_n_allocs(1);
+        vm.load(module.clone());
+        vm.run().unwrap_or_else(|e| panic!("jit={}: {:?}", jit, e));
+        assert!(vm.heap_stats().gc_cycles > 100, "jit={}: only {} collections", jit, vm.heap_stats().gc_cycles);
+    }
+}
+
+/// An injected allocation failure is an ordinary, recoverable runtime panic.
+#[cfg(debug_assertions)]
+#[test]
+fn test_vm_inject_oom() {
+    use vo_vm::vm::VmError;
+
+    let source = r#"
+package main
+
+func grow(n int) (s []int, err any) {
+    defer func() { err = recover() }()
+    for i := 0; i < n; i++ {
+        s = append(s, i)
+    }
+    return s, nil
+}
+
+func main() {
+    s, err := grow(1000)
+    if err == nil {
+        panic("WRONG: no failure")
+    }
+    if len(s) == 1000 {
+        panic("WRONG: finished anyway")
+    }
+    s, err = grow(10)
+    if err != nil || len(s) != 10 {
+        panic("WRONG: failure repeated")
+    }
+}
+"#;
+    let mut vm = Vm::new();
+    vm.load(compile_source(source));
+    vm.state.gc.inject_oom_after(5);
+    vm.run().unwrap();
+
+    // Unrecovered, it ends the program
+    let unrecovered = r#"
+package main
+
+func main() {
+    s := []int{}
+    for i := 0; i < 100; i++ {
+        s = append(s, i)
+    }
+}
+"#;
+    let mut vm = Vm::new();
+    vm.load(compile_source(unrecovered));
+    vm.state.gc.inject_oom_after(3);
+    match vm.run() {
+        Err(VmError::PanicUnwound { msg: Some(msg), .. }) => {
+            assert!(msg.contains("out of memory"), "unexpected panic: {}", msg);
+        }
+        other => panic!("expected out of memory panic, got {:?}", other),
+    }
+}
+
+/// An extern suspended with `yield_and_resume` parks only its own fiber:
+/// the other goroutine keeps running and the extern is re-entered with its state.
+#[test]
+fn test_vm_extern_yield_and_resume() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vo_runtime::ffi::{ExternCall, ExternCallContext, ExternResult};
+
+    static TICKS: AtomicU64 = AtomicU64::new(0);
+    static ENTRIES: AtomicU64 = AtomicU64::new(0);
+
+    fn tick(_call: &mut ExternCall) -> ExternResult {
+        TICKS.fetch_add(1, Ordering::SeqCst);
+        ExternResult::Yield
+    }
+
+    // Waits, without blocking the VM, until `n` ticks happened after its first entry.
+    fn wait_ticks(call: &mut ExternCallContext) -> ExternResult {
+        ENTRIES.fetch_add(1, Ordering::SeqCst);
+        let now = TICKS.load(Ordering::SeqCst);
+        let start = call.resume_state().unwrap_or(now);
+        if now - start < call.arg_i64(0) as u64 {
+            return call.yield_and_resume(start);
+        }
+        call.ret_i64(0, (now - start) as i64);
+        ExternResult::Ok
+    }
+
+    let source = r#"
+package main
+
+func waitTicks(n int) int
+func tick()
+
+func main() {
+    done := make(chan int)
+    go func() {
+        done <- waitTicks(3)
+    }()
+    for i := 0; i < 10; i++ {
+        tick()
+    }
+    if <-done != 3 {
+        panic("WRONG: extern resumed with stale state")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let extern_id = |name: &str| module.externs.iter()
+        .position(|e| e.name == name)
+        .unwrap_or_else(|| panic!("{} extern not found", name)) as u32;
+
+    let mut vm = Vm::new();
+    vm.state.extern_registry.register_with_context(extern_id("main_waitTicks"), wait_ticks);
+    vm.state.extern_registry.register(extern_id("main_tick"), tick);
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    assert_eq!(TICKS.load(Ordering::SeqCst), 10);
+    // First entry plus one re-entry per tick observed while suspended
+    assert_eq!(ENTRIES.load(Ordering::SeqCst), 4);
+}
+
+/// A suspended extern called from JIT code lets the other goroutine run
+/// instead of being re-entered in a spin. `wait` calls it as its first effect,
+/// so the fiber parks by deopting; `waitAfter` has already written a global,
+/// so the other fibers run a scheduler round inside the call.
+#[test]
+fn test_jit_extern_yield_and_resume() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vo_runtime::ffi::{ExternCall, ExternCallContext, ExternResult};
+    use vo_vm::vm::jit_mgr::CompileState;
+    use vo_vm::JitConfig;
+
+    static TICKS: AtomicU64 = AtomicU64::new(0);
+
+    fn tick(_call: &mut ExternCall) -> ExternResult {
+        TICKS.fetch_add(1, Ordering::SeqCst);
+        ExternResult::Yield
+    }
+
+    // Returns once `n` ticks happened after its first entry
+    fn wait_ticks(call: &mut ExternCallContext) -> ExternResult {
+        let now = TICKS.load(Ordering::SeqCst);
+        let start = call.resume_state().unwrap_or(now);
+        if now - start < call.arg_i64(0) as u64 {
+            return call.yield_and_resume(start);
+        }
+        call.ret_i64(0, (now - start) as i64);
+        ExternResult::Ok
+    }
+
+    let source = r#"
+package main
+
+func waitTicks(n int) int
+func tick()
+
+var waits int
+
+func wait(n int) int {
+    return waitTicks(n)
+}
+
+func waitAfter(n int) int {
+    waits++
+    return waitTicks(n)
+}
+
+func main() {
+    for i := 0; i < 10; i++ {
+        wait(0)
+        waitAfter(0)
+    }
+    done := make(chan int)
+    go func() {
+        done <- wait(3)
+    }()
+    for i := 0; i < 10; i++ {
+        tick()
+    }
+    if <-done < 3 {
+        panic("WRONG: wait returned before the ticks")
+    }
+    go func() {
+        done <- waitAfter(3)
+    }()
+    for i := 0; i < 10; i++ {
+        tick()
+    }
+    if <-done < 3 {
+        panic("WRONG: waitAfter returned before the ticks")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let extern_id = |name: &str| module.externs.iter()
+        .position(|e| e.name == name)
+        .unwrap_or_else(|| panic!("{} extern not found", name)) as u32;
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} function not found", name)) as u32;
+    let (wait_id, wait_after_id) = (func_id("wait"), func_id("waitAfter"));
+
+    let mut vm = Vm::with_jit_config(JitConfig {
+        call_threshold: 5,
+        loop_threshold: u32::MAX,
+        ..Default::default()
+    });
+    vm.state.extern_registry.register_with_context(extern_id("main_waitTicks"), wait_ticks);
+    vm.state.extern_registry.register(extern_id("main_tick"), tick);
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    assert_eq!(TICKS.load(Ordering::SeqCst), 20);
+    // Parking keeps the compiled code
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    assert_eq!(mgr.compile_state(wait_id), Some(CompileState::FullyCompiled));
+    assert_eq!(mgr.compile_state(wait_after_id), Some(CompileState::FullyCompiled));
+}
+
+/// Without the errors package there is no `*errors.Error` to build, so
+/// `ret_error_msg` reports a panic carrying the message instead.
+#[test]
+fn test_vm_extern_ret_error_msg_without_errors() {
+    use vo_runtime::ffi::{ExternCallContext, ExternResult};
+    use vo_vm::vm::VmError;
+
+    fn open(call: &mut ExternCallContext) -> ExternResult {
+        call.ret_error_msg(0, "negative id")
+    }
+
+    let source = r#"
+package main
+
+func open(id int) error
+
+func main() {
+    open(-1)
+}
+"#;
+    let module = compile_source(source);
+    assert!(module.well_known.error_named_type_id.is_none());
+    let id = module.externs.iter().position(|e| e.name == "main_open")
+        .expect("main_open extern not found") as u32;
+
+    let mut vm = Vm::new();
+    vm.state.extern_registry.register_with_context(id, open);
+    vm.load(module);
+    match vm.run() {
+        Err(VmError::PanicUnwound { msg: Some(msg), .. }) => assert_eq!(msg, "negative id"),
+        other => panic!("expected a panic, got {:?}", other),
+    }
+}
+
+/// The trace hook sees every interpreted instruction, and `run_traced`
+/// writes one `pc: opcode a b c | slots` line per instruction.
+#[test]
+fn test_vm_trace_hook() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let source = r#"
+package main
+
+func add(a, b int) int {
+    return a + b
+}
+
+func main() {
+    x := add(40, 2)
+    if x != 42 {
+        panic("WRONG")
+    }
+}
+"#;
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut vm = Vm::new();
+    vm.load(compile_source(source));
+    let sink = seen.clone();
+    vm.trace_hook = Some(Box::new(move |fiber, inst| {
+        let pc = fiber.current_frame().unwrap().pc;
+        sink.borrow_mut().push((pc, inst.opcode()));
+    }));
+    vm.run().expect("VM execution failed");
+    let seen = seen.borrow();
+    assert!(seen.iter().any(|&(_, op)| op == vo_vm::instruction::Opcode::AddI));
+    assert!(seen.iter().any(|&(_, op)| op == vo_vm::instruction::Opcode::Call));
+
+    let out = Rc::new(RefCell::new(Vec::new()));
+    let mut vm = Vm::new();
+    vm.load(compile_source(source));
+    vm.run_traced(SharedBuf(out.clone())).expect("VM execution failed");
+    assert!(vm.trace_hook.is_none());
+    let text = String::from_utf8(out.borrow().clone()).unwrap();
+    assert_eq!(text.lines().count(), seen.len());
+    let add_line = text.lines().find(|l| l.contains(": AddI ")).expect("no AddI in trace");
+    assert!(add_line.contains("| [40, 2"), "unexpected slot dump: {}", add_line);
+}
+
+/// With `sorted_map_iter`, range loops visit keys in order, the same way
+/// every time, in both the interpreter and JIT code.
+#[test]
+fn test_vm_sorted_map_iter() {
+    use vo_vm::vm::jit_mgr::CompileState;
+
+    let source = r#"
+package main
+
+func intKeys(m map[int]string) string {
+    s := ""
+    for _, v := range m {
+        s += v
+    }
+    return s
+}
+
+func strKeys(m map[string]int) int {
+    n := 0
+    for k, v := range m {
+        if len(k) != v {
+            panic("WRONG: value does not match key")
+        }
+        n = n*10 + v
+    }
+    return n
+}
+
+func main() {
+    ints := map[int]string{5: "e", -3: "a", 100: "f", 0: "b", 2: "c", 3: "d"}
+    strs := map[string]int{"ccc": 3, "a": 1, "dddd": 4, "bb": 2}
+    for i := 0; i < 20; i++ {
+        if intKeys(ints) != "abcdef" {
+            panic("WRONG: int keys out of order")
+        }
+        if strKeys(strs) != 1234 {
+            panic("WRONG: string keys out of order")
+        }
+    }
+    for k := range ints {
+        delete(ints, k)
+        if k == 2 {
+            delete(ints, 100)
+        }
+    }
+    if len(ints) != 0 {
+        panic("WRONG: delete during sorted iteration")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} function not found", name)) as u32;
+    let ids = [func_id("intKeys"), func_id("strKeys")];
+
+    let mut vm = Vm::new();
+    vm.state.sorted_map_iter = true;
+    vm.load(module.clone());
+    vm.run().expect("VM execution failed");
+
+    let mut vm = Vm::with_jit_thresholds(5, u32::MAX);
+    vm.state.sorted_map_iter = true;
+    vm.load(module);
+    vm.run().expect("VM execution failed");
+
+    let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+    for id in ids {
+        assert_eq!(mgr.compile_state(id), Some(CompileState::FullyCompiled));
+    }
+}
+
+/// Each sorted iterator walks its own snapshot of the keys: nested loops over
+/// one map, and inserts or deletes inside the loop, don't reorder the outer
+/// loop, and the snapshot survives collections while the loop runs.
+#[test]
+fn test_vm_sorted_map_iter_nested() {
+    use vo_vm::vm::jit_mgr::CompileState;
+
+    let source = r#"
+package main
+
+func nested(m map[int]int) int {
+    n := 0
+    for i := range m {
+        for j := range m {
+            n = n*10 + i
+            n = n*10 + j
+        }
+    }
+    return n
+}
+
+func growing(m map[int]int) int {
+    n := 0
+    for k := range m {
+        n = n*10 + k
+        m[-k] = k
+        for range m {
+        }
+    }
+    return n
+}
+
+func strNested(m map[string]int) int {
+    n := 0
+    for k := range m {
+        for j := range m {
+            delete(m, j)
+            s := k + j
+            n = n*10 + len(s) - len(k)
+        }
+        n = n*10 + len(k)
+    }
+    return n
+}
+
+func main() {
+    for i := 0; i < 20; i++ {
+        m := map[int]int{3: 0, 1: 0, 2: 0}
+        if nested(m) != 111213212223313233 {
+            panic("WRONG: nested loops over one map")
+        }
+        if growing(m) != 123 {
+            panic("WRONG: insert during sorted iteration")
+        }
+        s := map[string]int{}
+        key := ""
+        for j := 0; j < 3; j++ {
+            key += "y"
+            s[key] = j
+        }
+        if strNested(s) != 1231 {
+            panic("WRONG: delete during nested sorted iteration")
+        }
+    }
+}
+"#;
+    let module = compile_source(source);
+    let func_id = |name: &str| module.functions.iter()
+        .position(|f| f.name == name)
+        .unwrap_or_else(|| panic!("{} function not found", name)) as u32;
+    // strNested deletes from the map, which keeps it interpreted
+    let ids = [func_id("nested"), func_id("growing")];
+
+    for jit in [false, true] {
+        let mut vm = if jit { Vm::with_jit_thresholds(5, u32::MAX) } else { Vm::new() };
+        vm.state.sorted_map_iter = true;
+        // Snapshots, and deleted string keys in them, must survive collections
+        #[cfg(debug_assertions)]
+        vm.state.gc.collect_every_n_allocs(1);
+        vm.load(module.clone());
+        vm.run().unwrap_or_else(|e| panic!("jit={}: {:?}", jit, e));
+        if jit {
+            let mgr = vm.jit_mgr.as_ref().expect("JIT manager not initialized");
+            for id in ids {
+                assert_eq!(mgr.compile_state(id), Some(CompileState::FullyCompiled));
+            }
+        }
+    }
+}
+
+/// `run` reports a deadlock when main and every goroutine wait on channels,
+/// but not when only goroutines are left blocked after main returns.
+#[test]
+fn test_vm_deadlock_detection() {
+    use vo_vm::vm::VmError;
+
+    let run = |source: &str| {
+        let mut vm = Vm::new();
+        vm.load(compile_source(source));
+        vm.run()
+    };
+
+    let deadlocked = r#"
+package main
+
+func main() {
+    a := make(chan int)
+    b := make(chan int)
+    go func() {
+        <-a
+        b <- 1
+    }()
+    go func() {
+        <-b
+        a <- 1
+    }()
+    select {
+    case <-a:
+    case <-b:
+    }
+}
+"#;
+    assert!(matches!(run(deadlocked), Err(VmError::Deadlock)));
+
+    let leaked = r#"
+package main
+
+func main() {
+    c := make(chan int)
+    done := make(chan int)
+    go func() {
+        done <- 1
+        <-c
+    }()
+    <-done
+}
+"#;
+    run(leaked).expect("blocked goroutines after main returns are not a deadlock");
+}
+
+/// Arithmetic on sub-word integers truncates to the operand width; int
+/// arithmetic needs no Trunc.
+#[test]
+fn test_narrow_int_arith_truncates() {
+    use vo_vm::instruction::Opcode;
+
+    let source = r#"
+package main
+
+func addU8(x, y uint8) uint8 { return x + y }
+
+func mulI16(x, y int16) int16 { return x * y }
+
+func negI8(x int8) int8 { return -x }
+
+func shlU32(x uint32, n int) uint32 { return x << n }
+
+func addInt(x, y int) int { return x + y }
+
+func main() {
+    if addU8(200, 100) != 44 || mulI16(32767, 2) != -2 || negI8(-128) != -128 || shlU32(1, 32) != 0 {
+        panic("WRONG")
+    }
+}
+"#;
+    let module = compile_source(source);
+    let truncs = |name: &str| -> Vec<u8> {
+        module.functions.iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("function {} not found", name))
+            .code.iter()
+            .filter(|inst| inst.opcode() == Opcode::Trunc)
+            .map(|inst| inst.flags)
+            .collect()
+    };
+    for (name, flags) in [("addU8", 0x01), ("mulI16", 0x82), ("negI8", 0x81), ("shlU32", 0x04)] {
+        assert_eq!(truncs(name), vec![flags], "{}", name);
+    }
+    assert!(truncs("addInt").is_empty());
+
+    compile_and_run(source);
+}
+
+/// The sampling profiler attributes a hot loop's time to its function, both
+/// interpreted and when the loop runs as JIT code sampled at safepoints.
+#[test]
+fn test_vm_profiler_finds_hot_function() {
+    use vo_vm::JitConfig;
+
+    let source = r#"
+package main
+
+func spin(n int) int {
+    x := 0
+    for i := 0; i < n; i++ {
+        x = (x*31 + i) % 1000003
+    }
+    return x
+}
+
+func cold(n int) int {
+    return n + 1
+}
+
+func main() {
+    sum := 0
+    for i := 0; i < 10; i++ {
+        sum += cold(i)
+    }
+    sum += spin(1000000)
+    if sum == 0 {
+        panic("WRONG")
+    }
+}
+"#;
+    let vms = [
+        ("vm", Vm::new()),
+        ("jit", Vm::with_jit_config(JitConfig { loop_threshold: 10, ..Default::default() })),
+    ];
+    for (mode, mut vm) in vms {
+        vm.load(compile_source(source));
+        vm.start_profiler(10_000);
+        vm.run().expect("VM execution failed");
+        let report = vm.stop_profiler();
+
+        let total = report.total_samples();
+        assert!(total >= 10, "{}: too few samples: {:?}", mode, report);
+        let hot = &report.functions[0];
+        assert_eq!(hot.name, "spin", "{}: {:?}", mode, report.functions);
+        assert!(hot.self_samples * 10 >= total * 8, "{}: spin has {} of {} samples", mode, hot.self_samples, total);
+        assert!(report.self_time(hot) > std::time::Duration::ZERO);
+        let main = report.functions.iter().find(|f| f.name == "main").expect("main never sampled");
+        assert_eq!(main.total_samples, total, "{}: every sample is under main", mode);
+        assert!(report.folded().contains("main;spin "), "{}: {}", mode, report.folded());
+    }
+
+    assert_eq!(Vm::new().stop_profiler().total_samples(), 0);
+}
@@ -16,7 +16,7 @@ use crate::RuntimeType;
 use crate::instruction::Instruction;
 use crate::debug_info::DebugInfo;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Constant {
     Nil,
     Bool(bool),
@@ -25,7 +25,36 @@ pub enum Constant {
     String(String),
 }
 
-#[derive(Debug, Clone)]
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Nil, Constant::Nil) => true,
+            (Constant::Bool(a), Constant::Bool(b)) => a == b,
+            (Constant::Int(a), Constant::Int(b)) => a == b,
+            // Bitwise: a NaN constant equals itself, and 0.0 differs from -0.0
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::String(a), Constant::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl core::hash::Hash for Constant {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Constant::Nil => {}
+            Constant::Bool(b) => b.hash(state),
+            Constant::Int(i) => i.hash(state),
+            Constant::Float(f) => f.to_bits().hash(state),
+            Constant::String(s) => s.hash(state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct FunctionDef {
     pub name: String,
     pub param_count: u16,
@@ -62,14 +91,14 @@ pub struct FunctionDef {
     pub param_types: Vec<(u32, u16)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExternDef {
     pub name: String,
     pub param_slots: u16,
     pub ret_slots: u16,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GlobalDef {
     pub name: String,
     pub slots: u16,
@@ -79,7 +108,7 @@ pub struct GlobalDef {
 }
 
 /// Metadata for a single struct field.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FieldMeta {
     pub name: String,
     pub offset: u16,
@@ -91,7 +120,7 @@ pub struct FieldMeta {
     pub tag: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StructMeta {
     pub slot_types: Vec<SlotType>,
     pub fields: Vec<FieldMeta>,
@@ -99,14 +128,14 @@ pub struct StructMeta {
     pub field_index: HashMap<String, usize>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MethodInfo {
     pub func_id: u32,
     pub is_pointer_receiver: bool,
     pub signature_rttid: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NamedTypeMeta {
     pub name: String,
     pub underlying_meta: ValueMeta,
@@ -126,13 +155,13 @@ impl StructMeta {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct InterfaceMethodMeta {
     pub name: String,
     pub signature_rttid: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct InterfaceMeta {
     pub name: String,
     pub method_names: Vec<String>,
@@ -140,14 +169,14 @@ pub struct InterfaceMeta {
 }
 
 /// Itab: interface method table (method_idx -> func_id)
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Itab {
     pub methods: Vec<u32>,
 }
 
 /// Pre-computed type IDs for well-known types (errors.Error, etc.)
 /// Filled at codegen time to avoid runtime lookups.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct WellKnownTypes {
     /// errors.Error named_type_id
     pub error_named_type_id: Option<u32>,
@@ -161,7 +190,7 @@ pub struct WellKnownTypes {
     pub error_field_offsets: Option<[u16; 2]>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Module {
     pub name: String,
     pub struct_metas: Vec<StructMeta>,
@@ -7,7 +7,7 @@ use std::fmt;
 
 /// Single debug location entry.
 /// Stores line:col:len for error display and highlighting.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DebugLoc {
     pub pc: u32,
     pub file_id: u16,
@@ -20,7 +20,7 @@ pub struct DebugLoc {
 }
 
 /// Function-level debug information.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct FuncDebugInfo {
     pub entries: Vec<DebugLoc>,
 }
@@ -40,7 +40,7 @@ impl FuncDebugInfo {
 }
 
 /// Module-level debug information.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct DebugInfo {
     pub files: Vec<String>,
     pub funcs: Vec<FuncDebugInfo>,
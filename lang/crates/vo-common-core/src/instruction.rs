@@ -20,6 +20,14 @@ pub const LOOP_FLAG_HAS_LABELED_BREAK: u8 = 0x02;
 /// Loop flag: has labeled continue to outer loop
 pub const LOOP_FLAG_HAS_LABELED_CONTINUE: u8 = 0x04;
 
+/// AddI/SubI/MulI flag: the operands are unsigned, so wraparound is defined
+/// and checked arithmetic leaves the result alone
+pub const ARITH_FLAG_UNSIGNED: u8 = 0x01;
+/// AddI/SubI/MulI flags bits 4-7: byte width (1, 2 or 4) of a signed
+/// operand type narrower than 64 bits, so checked arithmetic tests the
+/// result against that type's range; 0 for 64-bit types
+pub const ARITH_FLAG_WIDTH_SHIFT: u8 = 4;
+
 // =============================================================================
 // Instruction format
 // =============================================================================
@@ -123,6 +131,10 @@ pub enum Opcode {
     MulF,
     DivF,
     NegF,
+    SqrtF,   // a=dst, b=src (math.Sqrt)
+    AbsF,    // a=dst, b=src (math.Abs)
+    MinF,    // a=dst, b, c (math.Min)
+    MaxF,    // a=dst, b, c (math.Max)
 
     // === CMP: Integer comparison ===
     EqI,
@@ -161,6 +173,10 @@ pub enum Opcode {
     Jump,
     JumpIf,
     JumpIfNot,
+    /// Indexed jump: a = index_reg, b = entry count. Followed by b `Jump`
+    /// entries; jumps to entry slots[a] if it is below b (unsigned),
+    /// otherwise to the instruction after the last entry.
+    JumpTable,
 
     // === CALL: Function calls ===
     Call,
@@ -182,6 +198,8 @@ pub enum Opcode {
     StrGt,
     StrGe,
     StrDecodeRune,  // Decode UTF-8 rune at position: (rune, width) = decode(str, pos)
+    StrContains,    // a = substring c occurs in b
+    StrIndexOf,     // a = byte offset of first substring c in b, or -1
 
     // === ARRAY: Heap array operations ===
     ArrayNew,
@@ -255,7 +273,9 @@ pub enum Opcode {
     IfaceEq,
 
     // === CONV: Type conversion ===
+    /// a = float64(b); flags=1: b is a 64-bit unsigned integer
     ConvI2F,
+    /// a = int(b), saturating, NaN -> 0; flags=1: a is a 64-bit unsigned integer
     ConvF2I,
     ConvF64F32,
     ConvF32F64,
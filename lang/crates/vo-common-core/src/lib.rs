@@ -8,6 +8,7 @@
 //! - `runtime_type` - Runtime type representation for type identity
 //! - `instruction` - Bytecode instruction format and opcodes
 //! - `bytecode` - Module and function definitions
+//! - `validate` - Structural validation of loaded modules
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -20,6 +21,7 @@ pub mod runtime_type;
 pub mod instruction;
 pub mod bytecode;
 pub mod serialize;
+pub mod validate;
 pub mod debug_info;
 pub mod source_provider;
 
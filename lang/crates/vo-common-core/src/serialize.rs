@@ -29,7 +29,9 @@ use crate::bytecode::{
 use crate::instruction::Instruction;
 
 const MAGIC: &[u8; 3] = b"VOB";
-const VERSION: u32 = 1;
+/// Bytecode format version. Bump it whenever the encoding of a `Module`
+/// changes; `deserialize` rejects any other version.
+pub const VERSION: u32 = 3;
 
 #[derive(Debug)]
 pub enum SerializeError {
@@ -101,17 +103,20 @@ impl ByteWriter {
     }
 }
 
-// Helper functions for Option<u32> serialization
+// Helper functions for Option<u32> serialization.
+// A tag byte rather than a 1+v encoding, so Some(u32::MAX) survives.
 fn write_option_u32(w: &mut ByteWriter, opt: Option<u32>) {
     match opt {
-        Some(v) => w.write_u32(1 + v),  // 1+v for Some(v)
-        None => w.write_u32(0),          // 0 for None
+        Some(v) => {
+            w.write_u8(1);
+            w.write_u32(v);
+        }
+        None => w.write_u8(0),
     }
 }
 
 fn read_option_u32(r: &mut ByteReader) -> Result<Option<u32>, SerializeError> {
-    let raw = r.read_u32()?;
-    Ok(if raw == 0 { None } else { Some(raw - 1) })
+    Ok(if r.read_u8()? != 0 { Some(r.read_u32()?) } else { None })
 }
 
 // RuntimeType serialization tags
@@ -138,11 +143,7 @@ fn write_runtime_type(w: &mut ByteWriter, rt: &RuntimeType) {
         RuntimeType::Named { id, struct_meta_id } => {
             w.write_u8(RT_NAMED);
             w.write_u32(*id);
-            // Write struct_meta_id as Option<u32>: 0 = None, 1+id = Some(id)
-            match struct_meta_id {
-                Some(meta_id) => w.write_u32(1 + *meta_id),
-                None => w.write_u32(0),
-            }
+            write_option_u32(w, *struct_meta_id);
         }
         RuntimeType::Pointer(elem_rttid) => {
             w.write_u8(RT_POINTER);
@@ -230,8 +231,7 @@ fn read_runtime_type(r: &mut ByteReader) -> Result<RuntimeType, SerializeError>
         }
         RT_NAMED => {
             let id = r.read_u32()?;
-            let struct_meta_raw = r.read_u32()?;
-            let struct_meta_id = if struct_meta_raw == 0 { None } else { Some(struct_meta_raw - 1) };
+            let struct_meta_id = read_option_u32(r)?;
             Ok(RuntimeType::Named { id, struct_meta_id })
         }
         RT_POINTER => {
@@ -459,8 +459,11 @@ impl Module {
         w.write_vec(&self.named_type_metas, |w, m| {
             w.write_string(&m.name);
             w.write_u32(m.underlying_meta.to_raw());
-            w.write_u32(m.methods.len() as u32);
-            for (name, info) in &m.methods {
+            // Sorted so the same module always serializes to the same bytes
+            let mut methods: Vec<_> = m.methods.iter().collect();
+            methods.sort_by(|a, b| a.0.cmp(b.0));
+            w.write_u32(methods.len() as u32);
+            for (name, info) in methods {
                 w.write_string(name);
                 w.write_u32(info.func_id);
                 w.write_u8(info.is_pointer_receiver as u8);
@@ -860,4 +863,186 @@ mod tests {
         assert_eq!(module.functions[0].name, module2.functions[0].name);
         assert_eq!(module.functions[0].code.len(), module2.functions[0].code.len());
     }
+
+    mod roundtrip {
+        use super::*;
+        use crate::debug_info::{DebugInfo, DebugLoc, FuncDebugInfo};
+        use crate::runtime_type::{ChanDir, InterfaceMethod, StructField};
+        use crate::types::ValueKind;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        fn name() -> impl Strategy<Value = String> {
+            "\\PC{0,6}"
+        }
+
+        fn slot_type() -> impl Strategy<Value = SlotType> {
+            (0u8..4).prop_map(SlotType::from_u8)
+        }
+
+        fn rttid() -> impl Strategy<Value = ValueRttid> {
+            any::<u32>().prop_map(ValueRttid::from_raw)
+        }
+
+        fn constant() -> impl Strategy<Value = Constant> {
+            prop_oneof![
+                Just(Constant::Nil),
+                any::<bool>().prop_map(Constant::Bool),
+                any::<i64>().prop_map(Constant::Int),
+                // Every bit pattern, including NaN payloads and -0.0
+                any::<u64>().prop_map(|b| Constant::Float(f64::from_bits(b))),
+                name().prop_map(Constant::String),
+            ]
+        }
+
+        fn runtime_type() -> impl Strategy<Value = RuntimeType> {
+            let kind = (0u8..=255).prop_filter_map("value kind", |b| ValueKind::try_from(b).ok());
+            let dir = prop_oneof![Just(ChanDir::Both), Just(ChanDir::Send), Just(ChanDir::Recv)];
+            let field = (name(), rttid(), name(), any::<bool>(), name())
+                .prop_map(|(n, t, tag, e, pkg)| StructField::new(n, t, tag, e, pkg));
+            let method = (name(), rttid()).prop_map(|(n, sig)| InterfaceMethod::new(n, sig));
+            prop_oneof![
+                kind.prop_map(RuntimeType::Basic),
+                (any::<u32>(), any::<Option<u32>>())
+                    .prop_map(|(id, struct_meta_id)| RuntimeType::Named { id, struct_meta_id }),
+                rttid().prop_map(RuntimeType::Pointer),
+                (any::<u64>(), rttid()).prop_map(|(len, elem)| RuntimeType::Array { len, elem }),
+                rttid().prop_map(RuntimeType::Slice),
+                (rttid(), rttid()).prop_map(|(key, val)| RuntimeType::Map { key, val }),
+                (dir, rttid()).prop_map(|(dir, elem)| RuntimeType::Chan { dir, elem }),
+                (vec(rttid(), 0..3), vec(rttid(), 0..3), any::<bool>())
+                    .prop_map(|(params, results, variadic)| RuntimeType::Func { params, results, variadic }),
+                (vec(field, 0..3), any::<u32>())
+                    .prop_map(|(fields, meta_id)| RuntimeType::Struct { fields, meta_id }),
+                (vec(method, 0..3), any::<u32>())
+                    .prop_map(|(methods, meta_id)| RuntimeType::Interface { methods, meta_id }),
+                vec(rttid(), 0..3).prop_map(RuntimeType::Tuple),
+                rttid().prop_map(RuntimeType::Port),
+                Just(RuntimeType::Island),
+            ]
+        }
+
+        fn struct_meta() -> impl Strategy<Value = StructMeta> {
+            let field = (name(), any::<u16>(), any::<u16>(), rttid(), any::<bool>(), proptest::option::of(name()))
+                .prop_map(|(name, offset, slot_count, type_info, embedded, tag)| {
+                    FieldMeta { name, offset, slot_count, type_info, embedded, tag }
+                });
+            (vec(slot_type(), 0..4), vec(field, 0..4)).prop_map(|(slot_types, fields)| {
+                let field_index = fields.iter().enumerate().map(|(i, f)| (f.name.clone(), i)).collect();
+                StructMeta { slot_types, fields, field_index }
+            })
+        }
+
+        fn interface_meta() -> impl Strategy<Value = InterfaceMeta> {
+            let method = (name(), any::<u32>())
+                .prop_map(|(name, signature_rttid)| InterfaceMethodMeta { name, signature_rttid });
+            (name(), vec(name(), 0..3), vec(method, 0..3))
+                .prop_map(|(name, method_names, methods)| InterfaceMeta { name, method_names, methods })
+        }
+
+        fn named_type_meta() -> impl Strategy<Value = NamedTypeMeta> {
+            let info = (any::<u32>(), any::<bool>(), any::<u32>()).prop_map(|(func_id, is_pointer_receiver, signature_rttid)| {
+                MethodInfo { func_id, is_pointer_receiver, signature_rttid }
+            });
+            (name(), any::<u32>(), proptest::collection::hash_map(name(), info, 0..4)).prop_map(|(name, meta, methods)| {
+                NamedTypeMeta { name, underlying_meta: ValueMeta::from_raw(meta), methods: methods.into_iter().collect() }
+            })
+        }
+
+        fn well_known() -> impl Strategy<Value = WellKnownTypes> {
+            (any::<Option<u32>>(), any::<Option<u32>>(), any::<Option<u32>>(), any::<Option<u32>>(), any::<Option<[u16; 2]>>())
+                .prop_map(|(a, b, c, d, e)| WellKnownTypes {
+                    error_named_type_id: a,
+                    error_iface_meta_id: b,
+                    error_ptr_rttid: c,
+                    error_struct_meta_id: d,
+                    error_field_offsets: e,
+                })
+        }
+
+        fn global() -> impl Strategy<Value = GlobalDef> {
+            (name(), any::<u16>(), any::<u8>(), any::<u32>(), vec(slot_type(), 0..4)).prop_map(
+                |(name, slots, value_kind, meta_id, slot_types)| GlobalDef { name, slots, value_kind, meta_id, slot_types },
+            )
+        }
+
+        fn function() -> impl Strategy<Value = FunctionDef> {
+            let inst = (any::<u8>(), any::<u8>(), any::<u16>(), any::<u16>(), any::<u16>())
+                .prop_map(|(op, flags, a, b, c)| Instruction { op, flags, a, b, c });
+            let header = (name(), any::<[u16; 7]>(), vec(any::<u16>(), 0..3), any::<bool>(), any::<i16>());
+            let body = (vec(inst, 0..8), vec(slot_type(), 0..6), vec(any::<(u32, u16)>(), 0..3), vec(any::<(u32, u16)>(), 0..3));
+            (header, body).prop_map(|((name, h, heap_ret_slots, is_closure, error_ret_slot), (code, slot_types, capture_types, param_types))| {
+                FunctionDef {
+                    name,
+                    param_count: h[0],
+                    param_slots: h[1],
+                    local_slots: h[2],
+                    ret_slots: h[3],
+                    recv_slots: h[4],
+                    heap_ret_gcref_count: h[5],
+                    heap_ret_gcref_start: h[6],
+                    heap_ret_slots,
+                    is_closure,
+                    error_ret_slot,
+                    code,
+                    slot_types,
+                    capture_types,
+                    param_types,
+                }
+            })
+        }
+
+        fn debug_info() -> impl Strategy<Value = DebugInfo> {
+            let entry = (any::<u32>(), any::<u16>(), any::<u32>(), any::<u16>(), any::<u16>())
+                .prop_map(|(pc, file_id, line, col, len)| DebugLoc { pc, file_id, line, col, len });
+            let func = vec(entry, 0..4).prop_map(|entries| FuncDebugInfo { entries });
+            (vec(name(), 0..3), vec(func, 0..3)).prop_map(|(files, funcs)| DebugInfo { files, funcs })
+        }
+
+        fn module() -> impl Strategy<Value = Module> {
+            let types = (
+                vec(struct_meta(), 0..3),
+                vec(interface_meta(), 0..3),
+                vec(named_type_meta(), 0..3),
+                vec(runtime_type(), 0..6),
+                vec(vec(any::<u32>(), 0..3).prop_map(|methods| Itab { methods }), 0..3),
+                well_known(),
+            );
+            let code = (
+                name(),
+                vec(constant(), 0..6),
+                vec(global(), 0..3),
+                vec(function(), 0..3),
+                vec((name(), any::<u16>(), any::<u16>()).prop_map(|(name, param_slots, ret_slots)| ExternDef { name, param_slots, ret_slots }), 0..3),
+                any::<u32>(),
+                debug_info(),
+            );
+            (types, code).prop_map(|(t, c)| Module {
+                name: c.0,
+                struct_metas: t.0,
+                interface_metas: t.1,
+                named_type_metas: t.2,
+                runtime_types: t.3,
+                itabs: t.4,
+                well_known: t.5,
+                constants: c.1,
+                globals: c.2,
+                functions: c.3,
+                externs: c.4,
+                entry_func: c.5,
+                debug_info: c.6,
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn test_roundtrip(module in module()) {
+                let bytes = module.serialize();
+                let module2 = Module::deserialize(&bytes).unwrap();
+                prop_assert_eq!(&module2, &module);
+                // RuntimeType equality is type identity and ignores meta ids; the bytes don't.
+                prop_assert_eq!(module2.serialize(), bytes);
+            }
+        }
+    }
 }
@@ -0,0 +1,491 @@
+//! Structural validation of bytecode modules.
+//!
+//! `Module::deserialize` only checks the file encoding; a truncated or
+//! hand-edited module can still reference functions, constants, globals or
+//! registers that don't exist, which the VM would hit as an index panic.
+//! `validate` checks every such operand up front.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use crate::bytecode::Module;
+use crate::instruction::{Instruction, Opcode};
+use crate::types::ValueKind;
+
+/// Slots of a map iterator on the stack (see `MapIterInit`).
+const MAP_ITER_SLOTS: u32 = 7;
+
+/// A single validation failure. `func_id`/`pc` locate the offending instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    InvalidEntryFunc(u32),
+    InvalidOpcode { func_id: u32, pc: usize, op: u8 },
+    FuncOutOfRange { func_id: u32, pc: usize, target: u32 },
+    ExternOutOfRange { func_id: u32, pc: usize, extern_id: u32 },
+    ConstOutOfRange { func_id: u32, pc: usize, index: u32 },
+    GlobalOutOfRange { func_id: u32, pc: usize, slot: u32 },
+    JumpOutOfRange { func_id: u32, pc: usize, target: i64 },
+    SlotOutOfRange { func_id: u32, pc: usize, slot: u32 },
+    ItabFuncOutOfRange { itab: usize, target: u32 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidEntryFunc(id) => write!(f, "entry func_{} does not exist", id),
+            ValidationError::InvalidOpcode { func_id, pc, op } => {
+                write!(f, "func_{} pc {}: invalid opcode {}", func_id, pc, op)
+            }
+            ValidationError::FuncOutOfRange { func_id, pc, target } => {
+                write!(f, "func_{} pc {}: func_{} does not exist", func_id, pc, target)
+            }
+            ValidationError::ExternOutOfRange { func_id, pc, extern_id } => {
+                write!(f, "func_{} pc {}: extern_{} does not exist", func_id, pc, extern_id)
+            }
+            ValidationError::ConstOutOfRange { func_id, pc, index } => {
+                write!(f, "func_{} pc {}: const_{} does not exist", func_id, pc, index)
+            }
+            ValidationError::GlobalOutOfRange { func_id, pc, slot } => {
+                write!(f, "func_{} pc {}: global slot {} out of range", func_id, pc, slot)
+            }
+            ValidationError::JumpOutOfRange { func_id, pc, target } => {
+                write!(f, "func_{} pc {}: jump target {} outside function", func_id, pc, target)
+            }
+            ValidationError::SlotOutOfRange { func_id, pc, slot } => {
+                write!(f, "func_{} pc {}: register r{} exceeds local_slots", func_id, pc, slot)
+            }
+            ValidationError::ItabFuncOutOfRange { itab, target } => {
+                write!(f, "itab {}: func_{} does not exist", itab, target)
+            }
+        }
+    }
+}
+
+/// Check that every cross-reference in `module` is in range.
+/// Returns all failures found, not just the first.
+pub fn validate(module: &Module) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let func_count = module.functions.len() as u32;
+
+    if module.entry_func >= func_count {
+        errors.push(ValidationError::InvalidEntryFunc(module.entry_func));
+    }
+
+    for (i, itab) in module.itabs.iter().enumerate() {
+        for &target in &itab.methods {
+            if target >= func_count {
+                errors.push(ValidationError::ItabFuncOutOfRange { itab: i, target });
+            }
+        }
+    }
+
+    let global_slots: u32 = module.globals.iter().map(|g| g.slots as u32).sum();
+    let mut v = Validator { module, global_slots, errors };
+    for (func_id, func) in module.functions.iter().enumerate() {
+        for (pc, inst) in func.code.iter().enumerate() {
+            v.check_inst(func_id as u32, pc, func.code.len(), func.local_slots as u32, inst);
+        }
+    }
+
+    if v.errors.is_empty() { Ok(()) } else { Err(v.errors) }
+}
+
+struct Validator<'a> {
+    module: &'a Module,
+    global_slots: u32,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    fn check_inst(&mut self, func_id: u32, pc: usize, code_len: usize, local_slots: u32, inst: &Instruction) {
+        let (a, b, c, flags) = (inst.a as u32, inst.b as u32, inst.c as u32, inst.flags as u32);
+        // Register range [start, start + n) must fit in the frame.
+        let regs = |v: &mut Self, start: u32, n: u32| {
+            if n > 0 && start + n > local_slots {
+                v.errors.push(ValidationError::SlotOutOfRange { func_id, pc, slot: start + n - 1 });
+            }
+        };
+
+        match inst.opcode() {
+            Opcode::Invalid => {
+                self.errors.push(ValidationError::InvalidOpcode { func_id, pc, op: inst.op });
+            }
+
+            Opcode::Jump | Opcode::JumpIf | Opcode::JumpIfNot => {
+                let target = pc as i64 + inst.imm32() as i64;
+                if target < 0 || target >= code_len as i64 {
+                    self.errors.push(ValidationError::JumpOutOfRange { func_id, pc, target });
+                }
+                if inst.opcode() != Opcode::Jump {
+                    regs(self, a, 1);
+                }
+            }
+            Opcode::JumpTable => {
+                // The entries and the miss target after them must all exist
+                let target = pc as i64 + 1 + b as i64;
+                if target >= code_len as i64 {
+                    self.errors.push(ValidationError::JumpOutOfRange { func_id, pc, target });
+                }
+                regs(self, a, 1);
+            }
+
+            Opcode::LoadInt => regs(self, a, 1),
+            Opcode::LoadConst | Opcode::StrNew => {
+                self.check_const(func_id, pc, b);
+                regs(self, a, 1);
+            }
+
+            Opcode::Copy => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+            }
+            Opcode::CopyN => {
+                regs(self, a, c);
+                regs(self, b, c);
+            }
+
+            Opcode::GlobalGet => {
+                self.check_global(func_id, pc, b, 1);
+                regs(self, a, 1);
+            }
+            Opcode::GlobalGetN => {
+                self.check_global(func_id, pc, b, flags);
+                regs(self, a, flags);
+            }
+            Opcode::GlobalSet => {
+                self.check_global(func_id, pc, a, 1);
+                regs(self, b, 1);
+            }
+            Opcode::GlobalSetN => {
+                self.check_global(func_id, pc, a, flags);
+                regs(self, b, flags);
+            }
+
+            Opcode::AddI | Opcode::SubI | Opcode::MulI | Opcode::DivI | Opcode::DivU
+            | Opcode::ModI | Opcode::ModU
+            | Opcode::AddF | Opcode::SubF | Opcode::MulF | Opcode::DivF | Opcode::MinF | Opcode::MaxF
+            | Opcode::EqI | Opcode::NeI | Opcode::LtI | Opcode::LeI | Opcode::GtI | Opcode::GeI
+            | Opcode::LtU | Opcode::LeU | Opcode::GtU | Opcode::GeU
+            | Opcode::EqF | Opcode::NeF | Opcode::LtF | Opcode::LeF | Opcode::GtF | Opcode::GeF
+            | Opcode::And | Opcode::Or | Opcode::Xor | Opcode::AndNot
+            | Opcode::Shl | Opcode::ShrS | Opcode::ShrU
+            | Opcode::StrIndex | Opcode::StrConcat | Opcode::StrEq | Opcode::StrNe
+            | Opcode::StrLt | Opcode::StrLe | Opcode::StrGt | Opcode::StrGe
+            | Opcode::StrContains | Opcode::StrIndexOf
+            | Opcode::PtrAdd | Opcode::ArrayAddr | Opcode::SliceAddr
+            | Opcode::SlotGet | Opcode::SlotSet | Opcode::MapSet => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, 1);
+            }
+            Opcode::NegI | Opcode::NegF | Opcode::SqrtF | Opcode::AbsF | Opcode::Not | Opcode::BoolNot
+            | Opcode::ConvI2F | Opcode::ConvF2I | Opcode::ConvF64F32 | Opcode::ConvF32F64 | Opcode::Trunc
+            | Opcode::StrLen | Opcode::SliceLen | Opcode::SliceCap | Opcode::MapLen | Opcode::MapDelete
+            | Opcode::ChanLen | Opcode::ChanCap | Opcode::PortLen | Opcode::PortCap
+            | Opcode::IndexCheck | Opcode::PtrNew | Opcode::PtrGet => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+            }
+            Opcode::MapNew | Opcode::ChanClose | Opcode::PortClose | Opcode::SelectExec
+            | Opcode::IslandNew | Opcode::ClosureGet => regs(self, a, 1),
+            // a=case count, not a register
+            Opcode::SelectBegin | Opcode::Hint => {}
+
+            // Stack arrays: the base register is checked, the dynamic index can't be
+            Opcode::SlotGetN => {
+                regs(self, a, flags);
+                regs(self, b, flags);
+                regs(self, c, 1);
+            }
+            Opcode::SlotSetN => {
+                regs(self, a, flags);
+                regs(self, b, 1);
+                regs(self, c, flags);
+            }
+
+            Opcode::PtrSet => {
+                regs(self, a, 1);
+                regs(self, c, 1);
+            }
+            Opcode::PtrGetN => {
+                regs(self, a, flags);
+                regs(self, b, 1);
+            }
+            Opcode::PtrSetN => {
+                regs(self, a, 1);
+                regs(self, c, flags);
+            }
+
+            Opcode::StrSlice => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, 2);
+            }
+            // (rune, width)
+            Opcode::StrDecodeRune => {
+                regs(self, a, 2);
+                regs(self, b, 1);
+                regs(self, c, 1);
+            }
+
+            // flags=0: elem_bytes is read from the register after the length/index
+            Opcode::ArrayNew => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, if flags == 0 { 2 } else { 1 });
+            }
+            Opcode::ArrayGet | Opcode::SliceGet => {
+                regs(self, a, elem_slots(flags));
+                regs(self, b, 1);
+                regs(self, c, if flags == 0 { 2 } else { 1 });
+            }
+            Opcode::ArraySet | Opcode::SliceSet => {
+                regs(self, a, 1);
+                regs(self, b, if flags == 0 { 2 } else { 1 });
+                regs(self, c, elem_slots(flags));
+            }
+            // len at c, cap at c+1, dynamic elem_bytes at c+2
+            Opcode::SliceNew => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, if flags == 0 { 3 } else { 2 });
+            }
+            // lo at c, hi at c+1, max at c+2
+            Opcode::SliceSlice => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, if flags & 2 != 0 { 3 } else { 2 });
+            }
+            // elem_meta at c, then (dynamic elem_bytes and) the element
+            Opcode::SliceAppend => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, 1 + (flags == 0) as u32 + elem_slots(flags));
+            }
+
+            // Key and value slot counts live in registers, so only the first slot is checked
+            Opcode::MapGet => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, 1);
+            }
+            Opcode::MapIterInit => {
+                regs(self, a, MAP_ITER_SLOTS);
+                regs(self, b, 1);
+            }
+            Opcode::MapIterNext => {
+                regs(self, a, (flags & 0x0F) + (flags >> 4));
+                regs(self, b, MAP_ITER_SLOTS);
+                regs(self, c, 1);
+            }
+
+            Opcode::ChanNew | Opcode::PortNew => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, 1);
+            }
+            Opcode::ChanSend | Opcode::PortSend => {
+                regs(self, a, 1);
+                regs(self, b, flags);
+            }
+            // flags = elem_slots<<1 | has_ok
+            Opcode::ChanRecv | Opcode::PortRecv => {
+                regs(self, a, ((flags >> 1) & 0x7F) + (flags & 1));
+                regs(self, b, 1);
+            }
+            Opcode::SelectSend => {
+                regs(self, a, 1);
+                regs(self, b, flags.max(1));
+            }
+            Opcode::SelectRecv => {
+                regs(self, a, ((flags >> 1) & 0x7F).max(1) + (flags & 1));
+                regs(self, b, 1);
+            }
+
+            Opcode::IfaceAssign => {
+                self.check_const(func_id, pc, c);
+                regs(self, a, 2);
+                regs(self, b, if flags == ValueKind::Interface as u32 { 2 } else { 1 });
+            }
+            // flags: assert_kind (bits 0-1), has_ok (bit 2), target_slots (bits 3-7)
+            Opcode::IfaceAssert => {
+                let target_slots = flags >> 3;
+                let dst_slots = if flags & 3 == 1 { 2 } else { target_slots.max(1) };
+                regs(self, a, dst_slots + ((flags >> 2) & 1));
+                regs(self, b, 2);
+            }
+            Opcode::IfaceEq => {
+                regs(self, a, 1);
+                regs(self, b, 2);
+                regs(self, c, 2);
+            }
+            // Interface values
+            Opcode::Panic | Opcode::Recover => regs(self, a, 2),
+
+            // a=func_id_low, b=buffer (results, then args), c=(arg_slots<<8|ret_slots), flags=func_id_high
+            Opcode::Call => {
+                self.check_func(func_id, pc, a | (flags << 16));
+                regs(self, b, (c >> 8) + (c & 0xFF));
+            }
+            // a=result_start, b=extern_id, c=arg_start, flags=arg_count
+            Opcode::CallExtern => {
+                // ret_slots is an upper bound (dyn_call_* return as many as the
+                // callee does), so only the first result slot is checked
+                match self.module.externs.get(b as usize) {
+                    Some(ext) => regs(self, a, (ext.ret_slots as u32).min(1)),
+                    None => self.errors.push(ValidationError::ExternOutOfRange { func_id, pc, extern_id: b }),
+                }
+                regs(self, c, flags);
+            }
+            // a=closure, b=buffer (results, then args), c=(arg_slots<<8|ret_slots)
+            Opcode::CallClosure => {
+                regs(self, a, 1);
+                regs(self, b, (c >> 8) + (c & 0xFF));
+            }
+            // a=interface (2 slots), flags=method_idx
+            Opcode::CallIface => {
+                regs(self, a, 2);
+                regs(self, b, (c >> 8) + (c & 0xFF));
+            }
+            // a=first result (or heap-return GcRef), b=count
+            Opcode::Return => regs(self, a, b),
+            // a=dst, b=func_id_low, c=capture_count, flags=func_id_high
+            Opcode::ClosureNew => {
+                self.check_func(func_id, pc, b | (flags << 16));
+                regs(self, a, 1);
+            }
+            // flags bit0=is_closure, otherwise a=func_id_low with high bits in flags>>1
+            Opcode::GoStart | Opcode::DeferPush | Opcode::ErrDeferPush => {
+                if flags & 1 == 0 {
+                    self.check_func(func_id, pc, a | ((flags >> 1) << 16));
+                } else {
+                    regs(self, a, 1);
+                }
+                regs(self, b, c);
+            }
+            // a=island, b=closure, c=args_start, flags=arg_slots
+            Opcode::GoIsland => {
+                regs(self, a, 1);
+                regs(self, b, 1);
+                regs(self, c, flags);
+            }
+        }
+    }
+
+    fn check_func(&mut self, func_id: u32, pc: usize, target: u32) {
+        if target as usize >= self.module.functions.len() {
+            self.errors.push(ValidationError::FuncOutOfRange { func_id, pc, target });
+        }
+    }
+
+    fn check_const(&mut self, func_id: u32, pc: usize, index: u32) {
+        if index as usize >= self.module.constants.len() {
+            self.errors.push(ValidationError::ConstOutOfRange { func_id, pc, index });
+        }
+    }
+
+    fn check_global(&mut self, func_id: u32, pc: usize, slot: u32, n: u32) {
+        if slot + n.max(1) > self.global_slots {
+            self.errors.push(ValidationError::GlobalOutOfRange { func_id, pc, slot });
+        }
+    }
+}
+
+/// Register slots of one element for an `elem_bytes` flags encoding.
+/// Dynamic sizes (flags=0) are only known at run time; at least one slot is used.
+fn elem_slots(flags: u32) -> u32 {
+    match flags {
+        0x81 | 0x82 | 0x84 | 0x44 => 1,
+        f => f.div_ceil(8).max(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::{Constant, FunctionDef};
+
+    fn make_func(local_slots: u16, code: Vec<Instruction>) -> FunctionDef {
+        FunctionDef {
+            name: "f".into(),
+            param_count: 0,
+            param_slots: 0,
+            local_slots,
+            ret_slots: 0,
+            recv_slots: 0,
+            heap_ret_gcref_count: 0,
+            heap_ret_gcref_start: 0,
+            heap_ret_slots: Vec::new(),
+            is_closure: false,
+            error_ret_slot: -1,
+            code,
+            slot_types: Vec::new(),
+            capture_types: Vec::new(),
+            param_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_valid_module() {
+        let mut module = Module::new("test".into());
+        module.constants.push(Constant::Int(1));
+        module.functions.push(make_func(2, vec![
+            Instruction::new(Opcode::LoadConst, 0, 0, 0),
+            Instruction::new(Opcode::JumpIfNot, 0, 2, 0),
+            Instruction::new(Opcode::Copy, 1, 0, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]));
+        assert_eq!(validate(&module), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_entry() {
+        let module = Module::new("test".into());
+        assert_eq!(validate(&module), Err(vec![ValidationError::InvalidEntryFunc(0)]));
+    }
+
+    #[test]
+    fn test_out_of_range_operands() {
+        let mut module = Module::new("test".into());
+        module.functions.push(make_func(2, vec![
+            Instruction::new(Opcode::LoadConst, 0, 3, 0),
+            Instruction::with_flags(Opcode::Call, 1, 0, 0, 0),
+            Instruction::new(Opcode::GlobalGet, 0, 0, 0),
+            Instruction::new(Opcode::AddI, 0, 1, 2),
+            Instruction::new(Opcode::Jump, 0, 0xFFF0, 0xFFFF),
+        ]));
+        let errors = validate(&module).unwrap_err();
+        assert_eq!(errors, vec![
+            ValidationError::ConstOutOfRange { func_id: 0, pc: 0, index: 3 },
+            ValidationError::FuncOutOfRange { func_id: 0, pc: 1, target: 0x10000 },
+            ValidationError::GlobalOutOfRange { func_id: 0, pc: 2, slot: 0 },
+            ValidationError::SlotOutOfRange { func_id: 0, pc: 3, slot: 2 },
+            ValidationError::JumpOutOfRange { func_id: 0, pc: 4, target: -12 },
+        ]);
+    }
+
+    #[test]
+    fn test_multi_slot_operands() {
+        let mut module = Module::new("test".into());
+        // Each instruction fits 4 slots only if its operands are one slot wide
+        module.functions.push(make_func(4, vec![
+            Instruction::new(Opcode::StrDecodeRune, 3, 0, 1),               // (rune, width)
+            Instruction::with_flags(Opcode::ArrayGet, 16, 3, 0, 1),         // 16-byte element
+            Instruction::with_flags(Opcode::MapIterNext, 0x11, 0, 1, 2),    // 7-slot iterator
+            Instruction::new(Opcode::IfaceEq, 0, 1, 3),                     // 2-slot interfaces
+            Instruction::with_flags(Opcode::IfaceAssert, 1 | 1 << 2, 2, 0, 0), // iface + ok
+            Instruction::new(Opcode::Panic, 3, 0, 0),
+            Instruction::with_flags(Opcode::SliceSlice, 2, 0, 1, 2),        // lo, hi, max
+            Instruction::with_flags(Opcode::ChanRecv, 2 << 1 | 1, 2, 0, 0), // 2 slots + ok
+        ]));
+        let slots = |errors: Vec<ValidationError>| errors.into_iter().map(|e| match e {
+            ValidationError::SlotOutOfRange { pc, slot, .. } => (pc, slot),
+            e => panic!("unexpected {:?}", e),
+        }).collect::<Vec<_>>();
+        assert_eq!(slots(validate(&module).unwrap_err()), vec![
+            (0, 4), (1, 4), (2, 7), (3, 4), (4, 4), (5, 4), (6, 4), (7, 4),
+        ]);
+    }
+}
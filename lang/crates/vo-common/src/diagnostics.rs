@@ -115,6 +115,11 @@ impl Label {
         self.message = Some(message.into());
         self
     }
+
+    /// Returns true if this is the diagnostic's main location.
+    pub fn is_primary(&self) -> bool {
+        self.style == LabelStyle::Primary
+    }
 }
 
 /// A suggested fix for a diagnostic.
@@ -0,0 +1,61 @@
+//! Computes `VO_COMPILER_BUILD_HASH`, a hash of the sources of every crate
+//! that decides what a `.vo` file compiles to. The compile cache keys on it,
+//! so a compiler change invalidates cached bytecode even when neither the
+//! package version nor the bytecode format version moved.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+/// Crates (relative to this one) whose code shapes the compiled `Module`
+/// or how the VM reads it.
+const COMPILER_CRATES: &[&str] = &[
+    ".",
+    "../vo-syntax",
+    "../vo-analysis",
+    "../vo-codegen",
+    "../vo-common",
+    "../vo-common-core",
+    "../vo-module",
+    "../vo-runtime",
+    "../vo-vm",
+];
+
+fn main() {
+    let mut files = Vec::new();
+    for krate in COMPILER_CRATES {
+        let dir = Path::new(krate);
+        println!("cargo:rerun-if-changed={}", dir.join("src").display());
+        println!("cargo:rerun-if-changed={}", dir.join("Cargo.toml").display());
+        files.push(dir.join("Cargo.toml"));
+        collect_sources(&dir.join("src"), &mut files);
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for path in &files {
+        let bytes = fs::read(path).unwrap_or_default();
+        let name = path.to_string_lossy();
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    println!("cargo:rustc-env=VO_COMPILER_BUILD_HASH={}", hex);
+}
+
+fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sources(&path, out);
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            out.push(path);
+        }
+    }
+}
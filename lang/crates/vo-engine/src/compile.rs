@@ -2,11 +2,12 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
 
-use vo_common::vfs::{FileSet, FileSystem, RealFs, ZipFs};
-use vo_analysis::analyze_project;
-use vo_codegen::compile_project;
+use sha2::{Digest, Sha256};
+
+use vo_common::vfs::{FileSet, FileSystem, MemoryFs, RealFs, ZipFs};
+use vo_analysis::{analyze_project, analyze_project_with_options, AnalysisOptions};
+use vo_codegen::{compile_project, compile_project_with_options, CodegenOptions, EscapeNote};
 use vo_module::{PackageResolverMixed, StdSource, LocalSource, ModSource};
 use vo_runtime::ext_loader::ExtensionManifest;
 use vo_vm::bytecode::Module;
@@ -51,7 +52,7 @@ pub fn compile(path: &str) -> Result<CompileOutput, CompileError> {
     let p = Path::new(path);
     
     if path.ends_with(".voc") || path.ends_with(".vob") {
-        load_bytecode(p)
+        load_bytecode(p, true)
     } else if let Some((zip_path, internal_root)) = parse_zip_path(path) {
         compile_zip(Path::new(&zip_path), internal_root.as_deref())
     } else {
@@ -97,20 +98,17 @@ fn compile_zip(zip_path: &Path, internal_root: Option<&str>) -> Result<CompileOu
 }
 
 /// Compile with cache support.
-/// Caches compiled bytecode in `.vo-cache` directory under the source root.
+/// Caches compiled bytecode under `.vo-cache/<key>/` in the source root,
+/// where the key hashes every source input and the compiler itself (see
+/// `cache_key`). The most recently used `CACHE_ENTRIES` entries are kept.
 pub fn compile_with_cache(path: &str) -> Result<CompileOutput, CompileError> {
     let entry_path = Path::new(path);
     let root = source_root(entry_path);
-    
-    let cache_dir = root.join(".vo-cache");
-    let cache_file = cache_dir.join("module.voc");
-    let meta_file = cache_dir.join("mtime");
-    let ext_file = cache_dir.join("extensions");
-    
-    let current_mtime = max_mtime(&root);
+    let cache_dir = root.join(CACHE_DIR);
+    let key = cache_key(&root, entry_path);
     
     // Try cache
-    if let Some(output) = try_load_cache(&cache_file, &meta_file, &ext_file, &root, current_mtime) {
+    if let Some(output) = key.as_deref().and_then(|k| try_load_cache(&cache_dir, k, &root)) {
         return Ok(output);
     }
     
@@ -118,11 +116,9 @@ pub fn compile_with_cache(path: &str) -> Result<CompileOutput, CompileError> {
     let output = compile(path)?;
     
     // Save cache
-    if let Some(mtime) = current_mtime {
-        let _ = fs::create_dir_all(&cache_dir);
-        let _ = fs::write(&cache_file, output.module.serialize());
-        let _ = fs::write(&meta_file, mtime.to_string());
-        save_extensions(&ext_file, &output.extensions);
+    if let Some(key) = key {
+        save_cache(&cache_dir, &key, &output);
+        prune_cache(&cache_dir, CACHE_ENTRIES);
     }
     
     Ok(output)
@@ -145,7 +141,63 @@ pub fn compile_string(code: &str) -> Result<CompileOutput, CompileError> {
     result
 }
 
-fn source_root(path: &Path) -> PathBuf {
+/// Compile the source file or directory at `path` and report the local
+/// variables moved to the heap because they escape, like Go's `-gcflags=-m`.
+/// Only variables declared in files under the source root are reported.
+pub fn compile_escape_notes(path: &str) -> Result<Vec<EscapeNote>, CompileError> {
+    let p = Path::new(path);
+    let root = source_root(p);
+    let single_file = if p.is_file() { p.file_name() } else { None };
+    let fs = RealFs::new(&root);
+    let file_set = collect_files(&fs, &root, single_file)?;
+    let resolver = create_resolver(&root, fs);
+    
+    let project = analyze_project(file_set, &resolver)
+        .map_err(|e| CompileError::Analysis(format!("{}", e)))?;
+    
+    let options = CodegenOptions { escape_notes: true, ..Default::default() };
+    let (_, notes) = compile_project_with_options(&project, &options)
+        .map_err(|e| CompileError::Codegen(format!("{:?}", e)))?;
+    
+    Ok(notes.into_iter()
+        .filter(|note| note.loc.as_ref().is_some_and(|loc| root.join(&loc.file).is_file()))
+        .collect())
+}
+
+/// Compile one in-memory source file without touching disk.
+/// Imports resolve as if the file lived in the current directory.
+#[cfg(test)]
+pub(crate) fn compile_source(code: &str) -> Result<CompileOutput, CompileError> {
+    let root = std::env::current_dir()?;
+    let fs = MemoryFs::new().with_file("main.vo", code);
+    compile_with_fs(fs, &root, Some(std::ffi::OsStr::new("main.vo")))
+}
+
+/// Compile in-memory files of package main one file at a time, in name
+/// order (see `CodegenOptions::file_units`), leaving `runtime_itabs` free.
+/// Also returns the names main declares at package level.
+/// Imports resolve as if the files lived in the current directory.
+pub(crate) fn compile_file_units(files: &[(String, String)], runtime_itabs: Vec<u32>) -> Result<(Module, Vec<String>), CompileError> {
+    let root = std::env::current_dir()?;
+    let mut file_set = FileSet::new(root.clone());
+    for (name, code) in files {
+        file_set.files.insert(PathBuf::from(name), code.clone());
+    }
+    let resolver = create_resolver(&root, MemoryFs::new());
+    
+    // Every file repeats the session's imports, used or not
+    let analysis_options = AnalysisOptions { allow_unused_imports: true, ..Default::default() };
+    let project = analyze_project_with_options(file_set, &resolver, &analysis_options)
+        .map_err(|e| CompileError::Analysis(format!("{}", e)))?;
+    
+    let options = CodegenOptions { file_units: true, runtime_itabs, ..Default::default() };
+    let (module, _) = compile_project_with_options(&project, &options)
+        .map_err(|e| CompileError::Codegen(format!("{:?}", e)))?;
+    let names = project.tc_objs.scopes[*project.main_pkg().scope()].names().cloned().collect();
+    Ok((module, names))
+}
+
+pub(crate) fn source_root(path: &Path) -> PathBuf {
     if path.is_dir() {
         path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
     } else {
@@ -157,13 +209,23 @@ fn source_root(path: &Path) -> PathBuf {
     }
 }
 
-fn load_bytecode(path: &Path) -> Result<CompileOutput, CompileError> {
+/// Load a `.vob`/`.voc` bytecode file. With `validate`, operands that point
+/// outside the module or the frame (see `vo_vm::validate`) are rejected up
+/// front instead of panicking the VM; skip it only for trusted files.
+pub fn load_bytecode(path: &Path, validate: bool) -> Result<CompileOutput, CompileError> {
     let bytes = fs::read(path)?;
     let module = Module::deserialize(&bytes)
         .map_err(|e| CompileError::Io(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             format!("{:?}", e)
         )))?;
+    if validate {
+        vo_vm::validate::validate(&module)
+            .map_err(|errors| CompileError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format_validation_errors(&errors)
+            )))?;
+    }
     Ok(CompileOutput {
         module,
         source_root: path.parent().unwrap_or(Path::new(".")).to_path_buf(),
@@ -171,20 +233,16 @@ fn load_bytecode(path: &Path) -> Result<CompileOutput, CompileError> {
     })
 }
 
-fn compile_with_fs<F: FileSystem>(fs: F, root: &Path, single_file: Option<&std::ffi::OsStr>) -> Result<CompileOutput, CompileError> {
-    let file_set = if let Some(file_name) = single_file {
-        FileSet::from_file(&fs, Path::new(file_name), root.to_path_buf())?
-    } else {
-        FileSet::collect(&fs, Path::new("."), root.to_path_buf())?
-    };
-    
-    if file_set.files.is_empty() {
-        return Err(CompileError::Io(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "no .vo files found"
-        )));
+fn format_validation_errors(errors: &[vo_vm::validate::ValidationError]) -> String {
+    let mut msg = String::from("invalid bytecode:");
+    for e in errors {
+        msg.push_str(&format!("\n  {}", e));
     }
-    
+    msg
+}
+
+fn compile_with_fs<F: FileSystem>(fs: F, root: &Path, single_file: Option<&std::ffi::OsStr>) -> Result<CompileOutput, CompileError> {
+    let file_set = collect_files(&fs, root, single_file)?;
     let resolver = create_resolver(&root, fs);
     
     let project = analyze_project(file_set, &resolver)
@@ -200,18 +258,39 @@ fn compile_with_fs<F: FileSystem>(fs: F, root: &Path, single_file: Option<&std::
     })
 }
 
-fn create_resolver<F: FileSystem>(local_root: &Path, local_fs: F) -> PackageResolverMixed<EmbeddedStdlib, F, RealFs> {
-    let mod_root = dirs::home_dir()
-        .map(|h| h.join(".vo/mod"))
-        .unwrap_or_else(|| local_root.join(".vo/mod"));
+/// The sources to compile: just `single_file` if given, else every `.vo`
+/// file under `root`.
+pub(crate) fn collect_files<F: FileSystem>(fs: &F, root: &Path, single_file: Option<&std::ffi::OsStr>) -> Result<FileSet, CompileError> {
+    let file_set = if let Some(file_name) = single_file {
+        FileSet::from_file(fs, Path::new(file_name), root.to_path_buf())?
+    } else {
+        FileSet::collect(fs, Path::new("."), root.to_path_buf())?
+    };
     
+    if file_set.files.is_empty() {
+        return Err(CompileError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no .vo files found"
+        )));
+    }
+    Ok(file_set)
+}
+
+pub(crate) fn create_resolver<F: FileSystem>(local_root: &Path, local_fs: F) -> PackageResolverMixed<EmbeddedStdlib, F, RealFs> {
     PackageResolverMixed {
         std: StdSource::with_fs(EmbeddedStdlib::new()),
         local: LocalSource::with_fs(local_fs),
-        r#mod: ModSource::with_fs(RealFs::new(mod_root)),
+        r#mod: ModSource::with_fs(RealFs::new(mod_root(local_root))),
     }
 }
 
+/// Where downloaded modules are resolved from.
+fn mod_root(local_root: &Path) -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".vo/mod"))
+        .unwrap_or_else(|| local_root.join(".vo/mod"))
+}
+
 fn parse_zip_path(path: &str) -> Option<(String, Option<String>)> {
     if path.ends_with(".zip") {
         Some((path.to_string(), None))
@@ -227,53 +306,102 @@ fn parse_zip_path(path: &str) -> Option<(String, Option<String>)> {
     }
 }
 
-fn max_mtime(dir: &Path) -> Option<u64> {
-    let mut max = 0u64;
-    for entry in walkdir(dir) {
-        if entry.extension().map(|e| e == "vo").unwrap_or(false) {
-            if let Ok(meta) = entry.metadata() {
-                if let Ok(mtime) = meta.modified() {
-                    if let Ok(dur) = mtime.duration_since(SystemTime::UNIX_EPOCH) {
-                        max = max.max(dur.as_secs());
-                    }
-                }
-            }
-        }
+const CACHE_DIR: &str = ".vo-cache";
+
+/// Cache entries kept per source root.
+const CACHE_ENTRIES: usize = 8;
+
+/// Files besides `.vo` sources that change what a project compiles to.
+const CACHE_INPUTS: &[&str] = &["vo.mod", "vo.sum", "vo.ext.toml"];
+
+/// Hash of everything a compile under `root` depends on: the compiler (see
+/// `toolchain_key`), the entry (one file or the whole directory), the path
+/// and contents of every source input, and where the resolver looks for
+/// downloaded modules. Returns None if the sources can't be read.
+fn cache_key(root: &Path, entry: &Path) -> Option<String> {
+    cache_key_with(toolchain_key(), root, entry)
+}
+
+fn cache_key_with(toolchain: &[u8], root: &Path, entry: &Path) -> Option<String> {
+    let mut files = Vec::new();
+    collect_cache_inputs(root, root, &mut files).ok()?;
+    files.sort();
+    
+    let mut hasher = Sha256::new();
+    let mut field = |bytes: &[u8]| {
+        // Length prefixes keep "a" + "bc" distinct from "ab" + "c".
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    field(toolchain);
+    let entry_name = if entry.is_file() { entry.file_name() } else { None };
+    field(entry_name.unwrap_or_default().as_encoded_bytes());
+    field(mod_root(root).as_os_str().as_encoded_bytes());
+    for rel in &files {
+        field(rel.as_bytes());
+        field(&fs::read(root.join(rel)).ok()?);
     }
-    if max == 0 { None } else { Some(max) }
+    
+    let digest = hasher.finalize();
+    Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
 }
 
-fn walkdir(path: &Path) -> Vec<PathBuf> {
-    let mut result = Vec::new();
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let p = entry.path();
-            if p.is_dir() {
-                result.extend(walkdir(&p));
-            } else {
-                result.push(p);
+/// Hash of what the compiler brings to every compile: the package version,
+/// the bytecode format version, a hash of the compiler's own sources taken
+/// at build time, and the embedded stdlib. Computed once per process.
+fn toolchain_key() -> &'static [u8] {
+    static KEY: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+    KEY.get_or_init(|| {
+        let stdlib = EmbeddedStdlib::new();
+        let mut files: Vec<_> = stdlib.files().collect();
+        files.sort();
+        
+        let mut hasher = Sha256::new();
+        let mut field = |bytes: &[u8]| {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        field(env!("CARGO_PKG_VERSION").as_bytes());
+        field(&vo_common_core::serialize::VERSION.to_le_bytes());
+        field(env!("VO_COMPILER_BUILD_HASH").as_bytes());
+        for (path, src) in files {
+            field(path.as_os_str().as_encoded_bytes());
+            field(src.as_bytes());
+        }
+        hasher.finalize().to_vec()
+    })
+}
+
+/// Collects source inputs under `dir` relative to `root`, `/`-separated.
+/// Hidden directories (including the cache itself) are skipped.
+fn collect_cache_inputs(root: &Path, dir: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                collect_cache_inputs(root, &path, out)?;
             }
+        } else if name.ends_with(".vo") || CACHE_INPUTS.contains(&name.as_ref()) {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            let parts: Vec<_> = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            out.push(parts.join("/"));
         }
     }
-    result
+    Ok(())
 }
 
-fn try_load_cache(
-    cache_file: &Path,
-    meta_file: &Path,
-    ext_file: &Path,
-    source_root: &Path,
-    current_mtime: Option<u64>,
-) -> Option<CompileOutput> {
-    let current = current_mtime?;
-    let cached: u64 = fs::read_to_string(meta_file).ok()?.trim().parse().ok()?;
-    if cached != current {
-        return None;
-    }
-    
-    let bytes = fs::read(cache_file).ok()?;
+fn try_load_cache(cache_dir: &Path, key: &str, source_root: &Path) -> Option<CompileOutput> {
+    let entry = cache_dir.join(key);
+    let module_path = entry.join("module.voc");
+    let bytes = fs::read(&module_path).ok()?;
     let module = Module::deserialize(&bytes).ok()?;
-    let extensions = load_extensions(ext_file);
+    let extensions = load_extensions(&entry.join("extensions"));
+    
+    // Mark the entry used, for prune_cache
+    if let Ok(f) = fs::File::options().append(true).open(&module_path) {
+        let _ = f.set_modified(std::time::SystemTime::now());
+    }
     
     Some(CompileOutput {
         module,
@@ -282,15 +410,48 @@ fn try_load_cache(
     })
 }
 
-fn save_extensions(path: &Path, extensions: &[ExtensionManifest]) {
-    use std::io::Write;
-    if let Ok(mut f) = fs::File::create(path) {
-        for ext in extensions {
-            let _ = writeln!(f, "{}|{}", ext.name, ext.native_path.display());
-        }
+/// Write the entry for `key` into a scratch directory and rename it into
+/// place, so readers never see a partial entry.
+fn save_cache(cache_dir: &Path, key: &str, output: &CompileOutput) {
+    let tmp = cache_dir.join(format!(".{}.{}", key, std::process::id()));
+    let saved = fs::create_dir_all(&tmp).is_ok()
+        && fs::write(tmp.join("module.voc"), output.module.serialize()).is_ok()
+        && save_extensions(&tmp.join("extensions"), &output.extensions)
+        && fs::rename(&tmp, cache_dir.join(key)).is_ok();
+    if !saved {
+        // Also covers another process having saved the same key first
+        let _ = fs::remove_dir_all(&tmp);
     }
 }
 
+/// Remove all but the `keep` most recently used entries.
+fn prune_cache(cache_dir: &Path, keep: usize) {
+    let Ok(dir) = fs::read_dir(cache_dir) else { return };
+    let mut entries: Vec<_> = dir
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| {
+            let used = fs::metadata(e.path().join("module.voc")).and_then(|m| m.modified()).ok();
+            (used, e.path())
+        })
+        .collect();
+    if entries.len() <= keep {
+        return;
+    }
+    // Newest first; entries without a module sort last and go first
+    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in &entries[keep..] {
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+fn save_extensions(path: &Path, extensions: &[ExtensionManifest]) -> bool {
+    use std::io::Write;
+    let Ok(mut f) = fs::File::create(path) else { return false };
+    extensions.iter()
+        .all(|ext| writeln!(f, "{}|{}", ext.name, ext.native_path.display()).is_ok())
+}
+
 fn load_extensions(path: &Path) -> Vec<ExtensionManifest> {
     let mut result = Vec::new();
     if let Ok(content) = fs::read_to_string(path) {
@@ -305,3 +466,83 @@ fn load_extensions(path: &Path) -> Vec<ExtensionManifest> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &str) {
+        fs::write(dir.path().join(name), content).unwrap();
+    }
+
+    #[test]
+    fn test_cache_hit_and_invalidation() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "main.vo", "package main\n\nfunc main() {\n    println(helper())\n}\n");
+        write(&dir, "helper.vo", "package main\n\nfunc helper() int { return 1 }\n");
+        let path = dir.path().to_str().unwrap();
+        let root = source_root(dir.path());
+        let cache_dir = root.join(CACHE_DIR);
+
+        let first = compile_with_cache(path).unwrap();
+        let key = cache_key(&root, dir.path()).unwrap();
+        let cached = try_load_cache(&cache_dir, &key, &root).expect("second compile should hit");
+        assert_eq!(cached.module.serialize(), first.module.serialize());
+        assert_eq!(compile_with_cache(path).unwrap().module.serialize(), first.module.serialize());
+
+        // Any content change is a new key, even within the same second
+        write(&dir, "helper.vo", "package main\n\nfunc helper() int { return 2 }\n");
+        let edited = cache_key(&root, dir.path()).unwrap();
+        assert_ne!(edited, key);
+        assert!(try_load_cache(&cache_dir, &edited, &root).is_none());
+
+        // Removing a file is also a change
+        fs::remove_file(dir.path().join("helper.vo")).unwrap();
+        assert_ne!(cache_key(&root, dir.path()).unwrap(), edited);
+
+        // Compiling one file of the directory is a different entry
+        write(&dir, "helper.vo", "package main\n\nfunc helper() int { return 2 }\n");
+        assert_ne!(cache_key(&root, &dir.path().join("main.vo")).unwrap(), edited);
+
+        // Both versions live side by side in their own entries
+        compile_with_cache(path).unwrap();
+        write(&dir, "helper.vo", "package main\n\nfunc helper() int { return 1 }\n");
+        assert!(try_load_cache(&cache_dir, &key, &root).is_some());
+        assert!(try_load_cache(&cache_dir, &edited, &root).is_some());
+    }
+
+    #[test]
+    fn test_cache_key_covers_toolchain() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "main.vo", "package main\n\nfunc main() {}\n");
+        let root = source_root(dir.path());
+        // A new compiler, bytecode version or stdlib must not reuse old entries
+        let a = cache_key_with(b"toolchain a", &root, dir.path()).unwrap();
+        let b = cache_key_with(b"toolchain b", &root, dir.path()).unwrap();
+        assert_ne!(a, b);
+        assert_ne!(cache_key(&root, dir.path()).unwrap(), a);
+    }
+
+    #[test]
+    fn test_cache_prune_keeps_recent() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join(CACHE_DIR);
+        let now = std::time::SystemTime::now();
+        for i in 0..4u64 {
+            let entry = cache_dir.join(format!("k{}", i));
+            fs::create_dir_all(&entry).unwrap();
+            let f = fs::File::create(entry.join("module.voc")).unwrap();
+            f.set_modified(now - std::time::Duration::from_secs(100 - i)).unwrap();
+        }
+        // Scratch directories of in-flight saves are left alone
+        fs::create_dir_all(cache_dir.join(".k9.1")).unwrap();
+
+        prune_cache(&cache_dir, 2);
+        let mut left: Vec<_> = fs::read_dir(&cache_dir).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, [".k9.1", "k2", "k3"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Machine-readable compile diagnostics for editors and other tools.
+//!
+//! `compile` stops at the first failing phase and flattens its errors into
+//! one message. `compile_file_diagnostics` instead reports every diagnostic
+//! the failing phase collected, each with the file and span it points to.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use vo_analysis::{analyze_project, AnalysisError};
+use vo_codegen::compile_project;
+use vo_common::diagnostics::{Diagnostic as SourceDiagnostic, Severity as SourceSeverity};
+use vo_common::source::SourceMap;
+use vo_common::vfs::RealFs;
+
+use crate::compile::{collect_files, create_resolver, source_root};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+/// A position in a source file. `line` and `column` are 1-based, `column`
+/// and `offset` count UTF-8 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub offset: u32,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Position {
+    const START: Position = Position { offset: 0, line: 1, column: 1 };
+}
+
+/// One diagnostic. Errors without a source location (I/O, import cycles,
+/// codegen) point at the start of the compiled path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: String,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Compile the file or directory at `path` and return every diagnostic it
+/// produced. An empty result means it compiled.
+pub fn compile_file_diagnostics(path: &str) -> Vec<Diagnostic> {
+    let p = Path::new(path);
+    let root = source_root(p);
+    let single_file = if p.is_file() { p.file_name() } else { None };
+    let fs = RealFs::new(&root);
+
+    let unlocated = |message: String| vec![Diagnostic {
+        severity: Severity::Error,
+        message,
+        file: path.to_string(),
+        start: Position::START,
+        end: Position::START,
+    }];
+
+    if !p.exists() {
+        return unlocated(format!("{}: no such file or directory", path));
+    }
+    let file_set = match collect_files(&fs, &root, single_file) {
+        Ok(file_set) => file_set,
+        Err(e) => return unlocated(e.to_string()),
+    };
+    let resolver = create_resolver(&root, fs);
+    match analyze_project(file_set, &resolver) {
+        Ok(project) => match compile_project(&project) {
+            Ok(_) => Vec::new(),
+            Err(e) => unlocated(format!("{:?}", e)),
+        },
+        Err(AnalysisError::Parse(sink, source_map) | AnalysisError::Check(sink, source_map)) => {
+            sink.iter().map(|d| convert(d, &source_map, &root, path)).collect()
+        }
+        Err(e) => unlocated(e.to_string()),
+    }
+}
+
+/// Locate `diag` by its primary label, or its first label if none is primary.
+/// Files of the compiled project are reported by their path under `root`.
+fn convert(diag: &SourceDiagnostic, source_map: &SourceMap, root: &Path, path: &str) -> Diagnostic {
+    let severity = match diag.severity {
+        SourceSeverity::Error => Severity::Error,
+        SourceSeverity::Warning => Severity::Warning,
+        SourceSeverity::Note => Severity::Note,
+        SourceSeverity::Help => Severity::Help,
+    };
+    let label = diag.labels.iter()
+        .find(|l| l.is_primary())
+        .or_else(|| diag.labels.first());
+    let located = label.and_then(|l| {
+        let file = source_map.lookup_span(l.span)?;
+        let (start, end) = file.span_line_col(l.span);
+        let rel = file.path().unwrap_or(Path::new(file.name()));
+        let local = root.join(rel);
+        let name = if local.is_file() { local.display() } else { rel.display() }.to_string();
+        Some((name, Position {
+            offset: file.local_offset(l.span.start),
+            line: start.line,
+            column: start.column,
+        }, Position {
+            offset: file.local_offset(l.span.end),
+            line: end.line,
+            column: end.column,
+        }))
+    });
+    let (file, start, end) = located.unwrap_or_else(|| (path.to_string(), Position::START, Position::START));
+    Diagnostic { severity, message: diag.message.clone(), file, start, end }
+}
+
+/// Diagnostics as a JSON array.
+pub fn diagnostics_to_json(diagnostics: &[Diagnostic]) -> String {
+    serde_json::to_string(diagnostics).expect("diagnostics are always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reports_every_error_with_span() {
+        let dir = TempDir::new().unwrap();
+        let source = "package main\n\nfunc main() {\n    var x int = \"hello\"\n    y := undefined\n    println(x, y)\n}\n";
+        let file = dir.path().join("main.vo");
+        std::fs::write(&file, source).unwrap();
+
+        let diagnostics = compile_file_diagnostics(file.to_str().unwrap());
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        let expected = [("\"hello\"", 4, 17), ("undefined", 5, 10)];
+        for (diag, (text, line, column)) in diagnostics.iter().zip(expected) {
+            assert_eq!(diag.severity, Severity::Error);
+            assert_eq!(Path::new(&diag.file).file_name().unwrap(), "main.vo");
+            assert_eq!((diag.start.line, diag.start.column), (line, column));
+            assert_eq!(diag.end.line, line);
+            assert_eq!(&source[diag.start.offset as usize..diag.end.offset as usize], text);
+        }
+
+        let json = diagnostics_to_json(&diagnostics);
+        assert!(json.starts_with("[{\"severity\":\"error\",\"message\":"), "{}", json);
+        assert!(json.contains("\"start\":{\"offset\":44,\"line\":4,\"column\":17}"), "{}", json);
+    }
+
+    #[test]
+    fn test_clean_and_missing_files() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("main.vo");
+        std::fs::write(&file, "package main\n\nfunc main() {}\n").unwrap();
+        assert_eq!(compile_file_diagnostics(file.to_str().unwrap()), Vec::new());
+
+        let missing = dir.path().join("missing.vo");
+        let diagnostics = compile_file_diagnostics(missing.to_str().unwrap());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file, missing.to_str().unwrap());
+        assert_eq!(diagnostics[0].start, Position::START);
+    }
+}
@@ -4,9 +4,14 @@
 //! It is used by both the Vo CLI launcher and the vox library.
 
 mod compile;
+mod diagnostics;
+mod repl;
 mod run;
 
-pub use compile::{compile, compile_with_cache, compile_string, CompileError, CompileOutput};
+pub use compile::{compile, compile_with_cache, compile_string, compile_escape_notes, load_bytecode, CompileError, CompileOutput};
+pub use diagnostics::{compile_file_diagnostics, diagnostics_to_json, Diagnostic, Position, Severity};
+pub use repl::Repl;
 pub use run::{run, RunMode, RunError, RuntimeError, RuntimeErrorKind};
 
+pub use vo_codegen::EscapeNote;
 pub use vo_vm::bytecode::Module;
@@ -0,0 +1,364 @@
+//! Interactive evaluation, one entry at a time.
+//!
+//! A session is a program that grows by one file per entry and runs on a
+//! single VM. Declarations (`func`, `type`, `var`, `const`) go into the file
+//! as written, `a, b := ...` becomes a package-level `var a, b = ...`, and
+//! other statements make up the body of a function of their own. Each entry
+//! recompiles the session a file at a time, so the new module only appends
+//! to the one the VM runs: the VM takes it in place and runs just the new
+//! initializers and the entry's function, keeping its globals and heap.
+//! An entry that fails to compile leaves the session unchanged; one that
+//! panics stays, along with whatever it did before panicking.
+
+use std::collections::HashSet;
+
+use vo_runtime::output;
+use vo_vm::vm::Vm;
+
+use crate::compile::{compile_file_units, CompileError};
+use crate::run::{runtime_error, RunError};
+
+/// Entries starting with these are statements, never expressions to print.
+const STMT_KEYWORDS: &[&str] = &[
+    "for", "if", "switch", "select", "go", "defer",
+    "return", "break", "continue", "goto", "fallthrough",
+];
+
+/// Entries starting with these are package-level declarations.
+const DECL_KEYWORDS: &[&str] = &["func", "type", "var", "const"];
+
+#[derive(Default)]
+pub struct Repl {
+    /// Source of each entry kept, one file per entry.
+    files: Vec<String>,
+    /// Import declarations so far; every file repeats them.
+    imports: Vec<String>,
+    /// Names the session declares at package level.
+    names: HashSet<String>,
+    vm: Option<Vm>,
+    pending: String,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one line of input. Returns `Ok(None)` while the entry is still
+    /// open (an unclosed brace, paren or bracket), otherwise evaluates the
+    /// buffered entry and returns its output.
+    pub fn feed(&mut self, line: &str) -> Result<Option<String>, RunError> {
+        if !self.pending.is_empty() {
+            self.pending.push('\n');
+        }
+        self.pending.push_str(line);
+        if scan(&self.pending, |_, _, _| {}) > 0 {
+            return Ok(None);
+        }
+        let entry = std::mem::take(&mut self.pending);
+        self.eval(&entry).map(Some)
+    }
+
+    /// True while `feed` is waiting for the rest of a multi-line entry.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Evaluate one complete entry and return the output it produced.
+    /// A bare expression prints its value; `a, b := ...` prints the new bindings.
+    pub fn eval(&mut self, entry: &str) -> Result<String, RunError> {
+        let entry = entry.trim();
+        let first = entry.split_whitespace().next().unwrap_or("");
+        let func = format!("__repl{}", self.files.len());
+        if first.is_empty() {
+            return Ok(String::new());
+        }
+        if first == "import" {
+            let mut imports = self.imports.clone();
+            imports.push(entry.to_string());
+            let result = self.run_entry(source(&imports, ""), None);
+            if !matches!(result, Err(RunError::Compile(_))) {
+                self.imports = imports;
+            }
+            return result;
+        }
+        if DECL_KEYWORDS.contains(&first) {
+            return self.run_entry(source(&self.imports, entry), None);
+        }
+        if STMT_KEYWORDS.contains(&first) {
+            return self.run_entry(source(&self.imports, &func_decl(&func, entry)), Some(&func));
+        }
+        if let Some((lhs, rhs)) = split_define(entry) {
+            // New names become globals. Names declared before are assigned
+            // through hidden globals, unless none is new, which is an error.
+            let any_new = lhs.iter().any(|name| *name != "_" && !self.names.contains(*name));
+            let mut vars = Vec::new();
+            let mut body = String::new();
+            for name in &lhs {
+                if any_new && self.names.contains(*name) {
+                    vars.push(format!("{}_{}", func, name));
+                    body.push_str(&format!("{} = {}_{}\n", name, func, name));
+                } else {
+                    vars.push(name.to_string());
+                }
+            }
+            for name in lhs.iter().filter(|name| **name != "_") {
+                body.push_str(&format!("println(\"{} =\", {})\n", name, name));
+            }
+            let decls = format!("var {} = {}\n\n{}", vars.join(", "), rhs, func_decl(&func, &body));
+            return self.run_entry(source(&self.imports, &decls), Some(&func));
+        }
+        let print = func_decl(&func, &format!("println({})", entry));
+        match self.run_entry(source(&self.imports, &print), Some(&func)) {
+            Err(RunError::Compile(_)) => {}
+            result => return result,
+        }
+        self.run_entry(source(&self.imports, &func_decl(&func, entry)), Some(&func))
+    }
+
+    /// Compile the session with `src` as its next file, then run what that
+    /// file adds: the initializers of the packages it is first to import and
+    /// of its own globals, then `func` if given. Returns what they printed.
+    fn run_entry(&mut self, src: String, func: Option<&str>) -> Result<String, RunError> {
+        let mut files: Vec<_> = self.files.iter().enumerate()
+            .map(|(i, file)| (file_name(i), file.clone()))
+            .collect();
+        files.push((file_name(self.files.len()), src.clone()));
+        let runtime_itabs = self.vm.as_ref().map_or_else(Vec::new, Vm::runtime_itabs);
+        let (module, names) = compile_file_units(&files, runtime_itabs)?;
+
+        // Everything before the old __entry__ is already initialized
+        let first_new = self.vm.as_ref().and_then(Vm::module).map_or(0, |m| m.entry_func);
+        let vm = match &mut self.vm {
+            Some(vm) => {
+                vm.reload(module).map_err(|e| CompileError::Codegen(format!("cannot extend the session: {}", e)))?;
+                vm
+            }
+            None => {
+                let mut vm = Vm::new();
+                vm.load(module);
+                self.vm.insert(vm)
+            }
+        };
+        self.files.push(src);
+        self.names = names.into_iter().collect();
+
+        let module = vm.module().expect("module just loaded");
+        let mut funcs: Vec<u32> = (first_new..module.entry_func)
+            .filter(|&id| module.functions[id as usize].name == "__init__")
+            .collect();
+        funcs.extend(func.and_then(|name| module.functions.iter().position(|f| f.name == name)).map(|id| id as u32));
+        output::start_capture();
+        let result = funcs.into_iter().try_for_each(|id| vm.run_func(id));
+        let captured = output::stop_capture();
+        result.map_err(|e| runtime_error(vm, e))?;
+        Ok(captured)
+    }
+}
+
+fn file_name(index: usize) -> String {
+    format!("entry_{:06}.vo", index)
+}
+
+/// A file of package main with `imports` and then `body`.
+fn source(imports: &[String], body: &str) -> String {
+    let mut src = String::from("package main\n\n");
+    for import in imports {
+        src.push_str(import);
+        src.push('\n');
+    }
+    src.push('\n');
+    src.push_str(body);
+    src.push('\n');
+    src
+}
+
+fn func_decl(name: &str, body: &str) -> String {
+    format!("func {}() {{\n{}\n}}", name, body)
+}
+
+/// The names and value of a top-level `a, b := ...`.
+fn split_define(entry: &str) -> Option<(Vec<&str>, &str)> {
+    let bytes = entry.as_bytes();
+    let mut define = None;
+    scan(entry, |i, c, depth| {
+        if c == ':' && depth == 0 && define.is_none() && bytes.get(i + 1) == Some(&b'=') {
+            define = Some(i);
+        }
+    });
+    let end = define?;
+    let names = entry[..end].split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .collect();
+    Some((names, &entry[end + 2..]))
+}
+
+/// Walk the code characters of `src`, skipping strings, runes and comments,
+/// passing each with its byte offset and bracket depth. Returns the depth at
+/// the end; an unterminated raw string or block comment counts as open.
+fn scan(src: &str, mut visit: impl FnMut(usize, char, i32)) -> i32 {
+    let mut depth = 0;
+    let mut chars = src.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                while let Some((_, d)) = chars.next() {
+                    if d == '\\' {
+                        chars.next();
+                    } else if d == c || d == '\n' {
+                        break;
+                    }
+                }
+            }
+            '`' => {
+                if !chars.by_ref().any(|(_, d)| d == '`') {
+                    return depth + 1;
+                }
+            }
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                chars.by_ref().find(|&(_, d)| d == '\n');
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                let mut prev = ' ';
+                let closed = chars.by_ref().any(|(_, d)| {
+                    let end = prev == '*' && d == '/';
+                    prev = d;
+                    end
+                });
+                if !closed {
+                    return depth + 1;
+                }
+            }
+            _ => {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {}
+                }
+                visit(i, c, depth);
+            }
+        }
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(repl: &mut Repl, entry: &str) -> String {
+        repl.eval(entry).unwrap_or_else(|e| panic!("{}: {}", entry, e))
+    }
+
+    #[test]
+    fn test_accumulates_state() {
+        let mut repl = Repl::new();
+        assert_eq!(eval(&mut repl, "x := 2"), "x = 2\n");
+        assert_eq!(eval(&mut repl, "func double(n int) int { return n * 2 }"), "");
+        assert_eq!(eval(&mut repl, "y, _ := double(x) + 1, 0"), "y = 5\n");
+        assert_eq!(eval(&mut repl, "x = 10"), "");
+        assert_eq!(eval(&mut repl, "double(x)"), "20\n");
+        assert_eq!(eval(&mut repl, "println(y)"), "5\n");
+        assert_eq!(eval(&mut repl, "import \"strings\""), "");
+        assert_eq!(eval(&mut repl, "strings.ToUpper(\"vo\")"), "VO\n");
+    }
+
+    #[test]
+    fn test_entries_run_once() {
+        let mut repl = Repl::new();
+        assert_eq!(eval(&mut repl, "n := 0"), "n = 0\n");
+        assert_eq!(eval(&mut repl, "func bump() int { n++; return n }"), "");
+        assert_eq!(eval(&mut repl, "bump()"), "1\n");
+        assert_eq!(eval(&mut repl, "bump()"), "2\n");
+        assert_eq!(eval(&mut repl, "m := map[string]int{\"a\": bump()}"), "m = map[a:3]\n");
+        assert_eq!(eval(&mut repl, "x, n := 7, 10"), "x = 7\nn = 10\n");
+        assert_eq!(eval(&mut repl, "bump() + m[\"a\"]"), "14\n");
+        assert!(matches!(repl.eval("n := 1"), Err(RunError::Compile(_))));
+    }
+
+    #[test]
+    fn test_values_outlive_their_entry() {
+        let mut repl = Repl::new();
+        eval(&mut repl, "type T struct{ v int }");
+        eval(&mut repl, "func (t T) A() int { return t.v }");
+        eval(&mut repl, "func (t T) B() int { return t.v * 2 }");
+        eval(&mut repl, "type A interface{ A() int }");
+        eval(&mut repl, "type B interface{ B() int }");
+        eval(&mut repl, "var a A = T{3}");
+        // Asserting to another interface builds its itab at run time
+        eval(&mut repl, "var b B = nil");
+        eval(&mut repl, "b = a.(B)");
+        eval(&mut repl, "type AB interface{ A() int; B() int }");
+        eval(&mut repl, "var ab AB = T{4}");
+        assert_eq!(eval(&mut repl, "b.B() + ab.B()"), "14\n");
+
+        eval(&mut repl, "ch := make(chan int)");
+        eval(&mut repl, "go func() { for v := range ch { println(v * 2) } }()");
+        assert_eq!(eval(&mut repl, "ch <- 21"), "42\n");
+    }
+
+    #[test]
+    fn test_multi_line_entry() {
+        let mut repl = Repl::new();
+        eval(&mut repl, "total := 0");
+        assert_eq!(repl.feed("for i := 0; i < 4; i++ {").unwrap(), None);
+        assert!(repl.is_pending());
+        assert_eq!(repl.feed("    total += i // sum {").unwrap(), None);
+        assert_eq!(repl.feed("}").unwrap(), Some(String::new()));
+        assert!(!repl.is_pending());
+        assert_eq!(repl.feed("total").unwrap(), Some("6\n".to_string()));
+    }
+
+    #[test]
+    fn test_failed_entry_is_discarded() {
+        let mut repl = Repl::new();
+        eval(&mut repl, "var s []int");
+        assert!(matches!(repl.eval("n := missing"), Err(RunError::Compile(_))));
+        assert!(matches!(repl.eval("s[3]"), Err(RunError::Runtime(_))));
+        assert_eq!(eval(&mut repl, "len(s)"), "0\n");
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_reload_restarts_background_compiler() {
+        use vo_vm::vm::jit_mgr::CompileState;
+        use vo_vm::JitConfig;
+
+        let mut files = vec![(file_name(0), source(&[], "func one() int { return 1 }"))];
+        let (module, _) = compile_file_units(&files, Vec::new()).unwrap();
+        let mut vm = Vm::with_jit_config(JitConfig {
+            call_threshold: 10,
+            loop_threshold: u32::MAX,
+            async_compile: true,
+            ..Default::default()
+        });
+        vm.load(module);
+
+        let body = "s := 0\nfor i := 0; i < 100; i++ { s = add(s, i) }\nprintln(s)";
+        let decls = format!("func add(a int, b int) int {{ return a + b }}\n\n{}", func_decl("__repl1", body));
+        files.push((file_name(1), source(&[], &decls)));
+        let (module, _) = compile_file_units(&files, vm.runtime_itabs()).unwrap();
+        vm.reload(module).unwrap();
+        // The VM and the restarted worker share one copy of the module
+        assert_eq!(std::sync::Arc::strong_count(vm.module.as_ref().unwrap()), 2);
+
+        let module = vm.module().unwrap();
+        let id = |name: &str| module.functions.iter().position(|f| f.name == name).unwrap() as u32;
+        let (add_id, run_id) = (id("add"), id("__repl1"));
+        output::start_capture();
+        vm.run_func(run_id).unwrap();
+        assert_eq!(output::stop_capture(), "4950\n");
+
+        // `add` only exists in the reloaded module
+        let mgr = vm.jit_mgr.as_mut().unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while mgr.compile_state(add_id) == Some(CompileState::Compiling) {
+            assert!(std::time::Instant::now() < deadline, "background compile never finished");
+            std::thread::yield_now();
+            mgr.poll_background();
+        }
+        assert_eq!(mgr.compile_state(add_id), Some(CompileState::FullyCompiled));
+    }
+}
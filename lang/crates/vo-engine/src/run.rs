@@ -4,6 +4,7 @@ use vo_common_core::debug_info::SourceLoc;
 use vo_vm::bytecode::Module;
 use vo_vm::vm::{Vm, VmError};
 use vo_runtime::ext_loader::{ExtensionLoader, ExtensionManifest};
+use vo_runtime::PanicKind;
 
 use crate::compile::{CompileOutput, CompileError};
 
@@ -21,6 +22,10 @@ pub struct RuntimeError {
     pub kind: RuntimeErrorKind,
 }
 
+/// What kind of failure ended the program. Runtime errors the VM or JIT code
+/// raises as recoverable panics carry their kind; `Panic` is anything else
+/// that reached the top of a goroutine, including user `panic` calls, whatever
+/// their message.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RuntimeErrorKind {
     Panic,
@@ -29,9 +34,30 @@ pub enum RuntimeErrorKind {
     TypeAssertionFailed,
     DivisionByZero,
     SendOnClosedChannel,
+    NilMapWrite,
+    StackOverflow,
+    Deadlock,
     Other,
 }
 
+impl RuntimeErrorKind {
+    /// Classify an unrecovered panic by the runtime error kind the VM or JIT
+    /// code raised it with. Panics without one are `Panic`.
+    fn from_panic_kind(kind: Option<PanicKind>) -> Self {
+        match kind {
+            None => RuntimeErrorKind::Panic,
+            Some(PanicKind::IndexOutOfBounds) => RuntimeErrorKind::IndexOutOfBounds,
+            Some(PanicKind::NilPointerDereference) => RuntimeErrorKind::NilPointerDereference,
+            Some(PanicKind::TypeAssertionFailed) => RuntimeErrorKind::TypeAssertionFailed,
+            Some(PanicKind::DivisionByZero) => RuntimeErrorKind::DivisionByZero,
+            Some(PanicKind::SendOnClosedChannel) => RuntimeErrorKind::SendOnClosedChannel,
+            Some(PanicKind::NilMapWrite) => RuntimeErrorKind::NilMapWrite,
+            Some(PanicKind::StackOverflow) => RuntimeErrorKind::StackOverflow,
+            Some(PanicKind::IntegerOverflow | PanicKind::NegativeShift | PanicKind::Other) => RuntimeErrorKind::Other,
+        }
+    }
+}
+
 impl RuntimeError {
     fn from_vm_error(e: &VmError, module: &Module) -> Self {
         let lookup = |loc: &Option<vo_vm::vm::ErrorLocation>| {
@@ -39,8 +65,9 @@ impl RuntimeError {
         };
         
         let (message, location, kind) = match e {
-            VmError::PanicUnwound { msg, loc } => {
-                (msg.as_deref().unwrap_or("panic").to_string(), lookup(loc), RuntimeErrorKind::Panic)
+            VmError::PanicUnwound { msg, loc, kind } => {
+                let msg = msg.as_deref().unwrap_or("panic");
+                (msg.to_string(), lookup(loc), RuntimeErrorKind::from_panic_kind(*kind))
             }
             VmError::IndexOutOfBounds(loc) => {
                 ("index out of bounds".into(), lookup(loc), RuntimeErrorKind::IndexOutOfBounds)
@@ -57,12 +84,24 @@ impl RuntimeError {
             VmError::SendOnClosedChannel(loc) => {
                 ("send on closed channel".into(), lookup(loc), RuntimeErrorKind::SendOnClosedChannel)
             }
+            VmError::Deadlock => {
+                ("fatal error: all goroutines are asleep - deadlock!".into(), None, RuntimeErrorKind::Deadlock)
+            }
             _ => (format!("{:?}", e), None, RuntimeErrorKind::Other),
         };
         RuntimeError { message, location, kind }
     }
 }
 
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(loc) => write!(f, "{} at {}", self.message, loc),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum RunError {
     Compile(CompileError),
@@ -73,7 +112,7 @@ impl std::fmt::Display for RunError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RunError::Compile(e) => write!(f, "{}", e),
-            RunError::Runtime(e) => write!(f, "{}", e.message),
+            RunError::Runtime(e) => write!(f, "{}", e),
         }
     }
 }
@@ -107,8 +146,10 @@ pub fn run(output: CompileOutput, mode: RunMode, args: Vec<String>) -> Result<()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(1000);
             let debug_ir = std::env::var("VO_JIT_DEBUG").is_ok();
+            let async_compile = std::env::var("VO_JIT_ASYNC").is_ok();
+            let checked_arith = std::env::var("VO_JIT_CHECKED_ARITH").is_ok();
             
-            let config = JitConfig { call_threshold, loop_threshold, debug_ir };
+            let config = JitConfig { call_threshold, loop_threshold, debug_ir, async_compile, checked_arith };
             let mut vm = Vm::with_jit_config(config);
             vm.init_jit();
             vm
@@ -126,19 +167,19 @@ pub fn run(output: CompileOutput, mode: RunMode, args: Vec<String>) -> Result<()
     vm.set_program_args(args);
     vm.load_with_extensions(module, ext_loader.as_ref());
     
-    match vm.run() {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            let runtime_err = vm.module()
-                .map(|m| RuntimeError::from_vm_error(&e, m))
-                .unwrap_or_else(|| RuntimeError {
-                    message: format!("{:?}", e),
-                    location: None,
-                    kind: RuntimeErrorKind::Other,
-                });
-            Err(RunError::Runtime(runtime_err))
-        }
-    }
+    vm.run().map_err(|e| runtime_error(&vm, e))
+}
+
+/// The error for a run of `vm` that failed with `e`.
+pub(crate) fn runtime_error(vm: &Vm, e: VmError) -> RunError {
+    let runtime_err = vm.module()
+        .map(|m| RuntimeError::from_vm_error(&e, m))
+        .unwrap_or_else(|| RuntimeError {
+            message: format!("{:?}", e),
+            location: None,
+            kind: RuntimeErrorKind::Other,
+        });
+    RunError::Runtime(runtime_err)
 }
 
 fn load_extensions(manifests: &[ExtensionManifest]) -> Result<Option<ExtensionLoader>, RunError> {
@@ -157,3 +198,157 @@ fn load_extensions(manifests: &[ExtensionManifest]) -> Result<Option<ExtensionLo
     }
     Ok(Some(loader))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::compile_source;
+
+    fn run_main(body: &str, mode: RunMode) -> Result<(), RunError> {
+        let src = format!("package main\n\n{}\n", body);
+        run(compile_source(&src).unwrap(), mode, Vec::new())
+    }
+
+    fn kind_of(body: &str, mode: RunMode) -> RuntimeErrorKind {
+        match run_main(body, mode) {
+            Err(RunError::Runtime(e)) => e.kind,
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    /// Each case fails in `hit(true)`. The calls before it get `hit` JIT
+    /// compiled in JIT mode, so the error is raised by compiled code there.
+    #[test]
+    fn test_runtime_error_kinds() {
+        let cases = [
+            ("func hit(bad bool) { s := []int{1}; i := 0; if bad { i = 3 }; sink += s[i] }", RuntimeErrorKind::IndexOutOfBounds),
+            ("func hit(bad bool) { s := []int{1}; i := 1; if bad { i = 3 }; sink += len(s[:i]) }", RuntimeErrorKind::IndexOutOfBounds),
+            ("type T struct{ x int }\nfunc hit(bad bool) { p := &T{1}; if bad { p = nil }; sink += p.x }", RuntimeErrorKind::NilPointerDereference),
+            ("func hit(bad bool) { var x any = 1; if bad { x = \"a\" }; sink += x.(int) }", RuntimeErrorKind::TypeAssertionFailed),
+            ("func hit(bad bool) { a, b := 1, 1; if bad { b = 0 }; sink += a / b }", RuntimeErrorKind::DivisionByZero),
+            ("func hit(bad bool) { c := make(chan int, 1); if bad { close(c) }; c <- 1 }", RuntimeErrorKind::SendOnClosedChannel),
+            ("func hit(bad bool) { m := map[string]int{}; if bad { m = nil }; m[\"a\"] = 1 }", RuntimeErrorKind::NilMapWrite),
+            ("func hit(bad bool) { n := 1; if bad { n = -1 }; sink += 1 << n }", RuntimeErrorKind::Other),
+            ("func f(n int) int { return f(n+1) + 1 }\nfunc hit(bad bool) { if bad { sink = f(0) } }", RuntimeErrorKind::StackOverflow),
+            ("func hit(bad bool) { if bad { c := make(chan int); <-c } }", RuntimeErrorKind::Deadlock),
+            ("func hit(bad bool) { if bad { panic(\"boom\") } }", RuntimeErrorKind::Panic),
+            // A user panic is a panic whatever its message says
+            ("func hit(bad bool) { if bad { panic(\"runtime error: index out of range\") } }", RuntimeErrorKind::Panic),
+        ];
+        for mode in [RunMode::Vm, RunMode::Jit] {
+            for (hit, kind) in cases {
+                let body = format!(
+                    "var sink int\n{}\nfunc main() {{\n\tfor i := 0; i < 200; i++ {{ hit(false) }}\n\thit(true)\n}}",
+                    hit,
+                );
+                assert_eq!(kind_of(&body, mode), kind, "{:?}: {}", mode, hit);
+            }
+        }
+        assert!(run_main("func main() {}", RunMode::Vm).is_ok());
+        match run_main("func main() { panic(42) }", RunMode::Vm) {
+            Err(RunError::Runtime(e)) => assert_eq!(e.message, "42"),
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_std_mode_selects_externs() {
+        use vo_stdlib::StdMode;
+
+        let src = "package main\n\nimport (\n\t\"os\"\n\t\"strings\"\n)\n\n\
+            func main() {\n\tprintln(strings.Index(\"vo\", \"o\"))\n\t_, err := os.ReadFile(\"x\")\n\tprintln(err)\n}\n";
+        let module = compile_source(src).unwrap().module;
+        let registered = |mode| {
+            let mut vm = Vm::new();
+            vm.set_std_mode(mode);
+            vm.load(module.clone());
+            let registry = &vm.state.extern_registry;
+            let names: Vec<String> = module.externs.iter().enumerate()
+                .filter(|(id, _)| registry.has(*id as u32))
+                .map(|(_, def)| def.name.clone())
+                .collect();
+            (names, vm.run())
+        };
+
+        let (full, result) = registered(StdMode::Full);
+        assert!(result.is_ok());
+        assert!(full.iter().any(|n| n.starts_with("os_")), "{:?}", full);
+
+        let (sandboxed, result) = registered(StdMode::Sandboxed);
+        assert!(result.is_err(), "os.ReadFile must fail when sandboxed");
+        assert!(sandboxed.iter().all(|n| !n.starts_with("os_")), "{:?}", sandboxed);
+        assert!(sandboxed.iter().any(|n| n.starts_with("strings_")), "{:?}", sandboxed);
+    }
+
+    #[test]
+    fn test_cancel_all_unblocks_context_waiters() {
+        let src = "package main\n\nimport \"context\"\n\n\
+            var stopped = make(chan string)\n\n\
+            func worker(ctx context.Context) {\n\t<-ctx.Done()\n\tstopped <- ctx.Err().Error()\n}\n\n\
+            func main() {\n\tctx, cancel := context.WithCancel(context.Background())\n\tdefer cancel()\n\
+            \tgo worker(ctx)\n\tif msg := <-stopped; msg != \"context canceled\" {\n\t\tpanic(msg)\n\t}\n}\n";
+        let mut vm = Vm::new();
+        vm.load(compile_source(src).unwrap().module);
+        // Waiting for the host to cancel is not a deadlock
+        vm.run().unwrap();
+        assert!(vm.scheduler.main_fiber.is_some(), "main should wait for the worker");
+
+        vm.cancel_all();
+        vm.run_scheduled().unwrap();
+        assert!(vm.scheduler.main_fiber.is_none(), "cancel_all should let the worker and main finish");
+
+        // A fiber that only the program could wake is still a deadlock
+        let src = "package main\n\nimport \"context\"\n\n\
+            func main() {\n\t_ = context.Background()\n\t<-make(chan int)\n}\n";
+        let mut vm = Vm::new();
+        vm.load(compile_source(src).unwrap().module);
+        assert!(matches!(vm.run(), Err(VmError::Deadlock)));
+    }
+
+    /// Code nested as deep as the parser allows also fits the default 2MB
+    /// test thread through analysis, codegen and execution, which recurse
+    /// once per level too.
+    #[test]
+    fn test_nesting_limit_fits_test_thread() {
+        let n = vo_syntax::parser::MAX_NESTING_DEPTH as usize - 5;
+        let bodies = [
+            format!("x := {}1{}\n\tprintln(x)", "(".repeat(n), ")".repeat(n)),
+            format!("x := {}true\n\tprintln(x)", "!".repeat(n)),
+            format!("var x {}int\n\tprintln(len(x))", "[]".repeat(n)),
+            format!("x := true\n\t{}println(x){}", "if x {".repeat(n), "}".repeat(n)),
+            format!("x := 0\n\t{}x++{}", "for i := 0; i < 1; i++ {".repeat(n / 2), "}".repeat(n / 2)),
+        ];
+        for body in bodies {
+            let src = format!("func main() {{\n\t{}\n}}", body);
+            for mode in [RunMode::Vm, RunMode::Jit] {
+                assert!(run_main(&src, mode).is_ok(), "{:?}: {}...", mode, &body[..40]);
+            }
+        }
+    }
+
+    /// `ret_error_msg` returns an `*errors.Error` through an extern's error
+    /// result. Projects always link the errors package.
+    #[test]
+    fn test_extern_ret_error_msg() {
+        use vo_runtime::ffi::{ExternCallContext, ExternResult};
+
+        fn open(call: &mut ExternCallContext) -> ExternResult {
+            if call.arg_i64(0) < 0 {
+                return call.ret_error_msg(0, "negative id");
+            }
+            call.ret_nil_error(0);
+            ExternResult::Ok
+        }
+
+        let src = "package main\n\nimport \"errors\"\n\nfunc open(id int) error\n\n\
+            func main() {\n\tif open(1) != nil {\n\t\tpanic(\"nil expected\")\n\t}\n\
+            \terr := open(-1)\n\tif _, ok := err.(*errors.Error); !ok || err.Error() != \"negative id\" {\n\
+            \t\tpanic(err)\n\t}\n}\n";
+        let module = compile_source(src).unwrap().module;
+        let id = module.externs.iter().position(|e| e.name == "main_open").unwrap();
+        let mut vm = Vm::new();
+        vm.state.extern_registry.register_with_context(id as u32, open);
+        vm.load(module);
+        vm.run().unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! Background compilation on a worker thread.
+//!
+//! The worker owns its own `JitCompiler`, so the VM never waits on Cranelift:
+//! it queues a hot function with `request`, keeps interpreting, and picks up
+//! finished code with `poll`. Compiled code lives in the worker's JIT module,
+//! which stays alive until the `BackgroundCompiler` is dropped.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use vo_runtime::bytecode::{FunctionDef, Module as VoModule};
+
+use crate::{JitCompiler, JitError, JitFunc};
+
+/// A finished compilation request.
+pub struct CompileDone {
+    pub func_id: u32,
+    pub result: Result<JitFunc, JitError>,
+}
+
+/// Handle to the compile worker thread.
+pub struct BackgroundCompiler {
+    requests: Option<Sender<(u32, FunctionDef)>>,
+    results: Receiver<CompileDone>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl BackgroundCompiler {
+    /// Start a worker compiling functions of `module`.
+    pub fn spawn(module: Arc<VoModule>, debug_ir: bool, checked_arith: bool) -> Self {
+        let (req_tx, req_rx) = channel::<(u32, FunctionDef)>();
+        let (done_tx, done_rx) = channel();
+
+        let worker = std::thread::Builder::new()
+            .name("vo-jit".into())
+            .spawn(move || {
+                let mut compiler = JitCompiler::with_debug(debug_ir);
+                if let Ok(c) = compiler.as_mut() {
+                    c.set_checked_arith(checked_arith);
+                }
+                for (func_id, func) in req_rx {
+                    let result = match compiler.as_mut() {
+                        Ok(c) => c.compile(func_id, &func, &module).and_then(|()| {
+                            unsafe { c.get_func_ptr(func_id) }
+                                .ok_or_else(|| JitError::Internal("compiled but no pointer".into()))
+                        }),
+                        Err(e) => Err(JitError::Internal(format!("JIT unavailable: {}", e))),
+                    };
+                    if done_tx.send(CompileDone { func_id, result }).is_err() {
+                        break;
+                    }
+                }
+                // The loop only ends once the BackgroundCompiler is dropped,
+                // so the compiler and its code outlive every installed entry.
+            })
+            .ok();
+
+        Self { requests: Some(req_tx), results: done_rx, worker }
+    }
+
+    /// Queue `func` for compilation. Returns false if the worker is gone.
+    pub fn request(&self, func_id: u32, func: &FunctionDef) -> bool {
+        match &self.requests {
+            Some(tx) if self.worker.is_some() => tx.send((func_id, func.clone())).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Take one finished compilation, if any, without blocking.
+    pub fn poll(&self) -> Option<CompileDone> {
+        self.results.try_recv().ok()
+    }
+}
+
+impl Drop for BackgroundCompiler {
+    fn drop(&mut self) {
+        // Closing the request channel ends the worker loop.
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use vo_runtime::instruction::{Instruction, Opcode};
+
+    fn make_func(code: Vec<Instruction>) -> FunctionDef {
+        FunctionDef {
+            name: "f".into(),
+            param_count: 0,
+            param_slots: 0,
+            local_slots: 1,
+            ret_slots: 1,
+            recv_slots: 0,
+            heap_ret_gcref_count: 0,
+            heap_ret_gcref_start: 0,
+            heap_ret_slots: Vec::new(),
+            is_closure: false,
+            error_ret_slot: -1,
+            code,
+            slot_types: Vec::new(),
+            capture_types: Vec::new(),
+            param_types: Vec::new(),
+        }
+    }
+
+    /// Polls without blocking, as the VM does between calls, until `func_id` is done.
+    fn wait_for(bg: &BackgroundCompiler, func_id: u32) -> CompileDone {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        loop {
+            if let Some(done) = bg.poll() {
+                assert_eq!(done.func_id, func_id);
+                return done;
+            }
+            assert!(Instant::now() < deadline, "compile never finished");
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn test_compiles_in_background() {
+        let func = make_func(vec![
+            Instruction::new(Opcode::LoadInt, 0, 42, 0),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+        ]);
+        let mut module = VoModule::new("test".into());
+        module.functions.push(func.clone());
+
+        let bg = BackgroundCompiler::spawn(Arc::new(module), false, false);
+        assert!(bg.request(0, &func));
+        let done = wait_for(&bg, 0);
+        assert!(done.result.is_ok());
+    }
+
+    #[test]
+    fn test_reports_unjittable() {
+        let func = make_func(vec![
+            Instruction::new(Opcode::Recover, 0, 0, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".into());
+        module.functions.push(func.clone());
+
+        let bg = BackgroundCompiler::spawn(Arc::new(module), false, false);
+        assert!(bg.request(0, &func));
+        let done = wait_for(&bg, 0);
+        assert!(matches!(done.result, Err(JitError::NotJittable(0))));
+    }
+}
@@ -10,12 +10,15 @@ use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
 
 use vo_runtime::bytecode::{FunctionDef, Module as VoModule};
 use vo_runtime::instruction::{Instruction, Opcode};
-use crate::translate::translate_inst;
+use crate::translate::{emit_call_iface, emit_jump_table, emit_record_panic_pc, translate_inst};
 use crate::translator::{HelperFuncs, IrEmitter, TranslateResult};
+use vo_runtime::jit_api::JitResult;
+use vo_runtime::SlotType;
 use crate::JitError;
 
 pub struct FunctionCompiler<'a> {
     builder: FunctionBuilder<'a>,
+    func_id: u32,
     func_def: &'a FunctionDef,
     vo_module: &'a VoModule,
     vars: Vec<Variable>,
@@ -25,15 +28,22 @@ pub struct FunctionCompiler<'a> {
     helpers: HelperFuncs,
     reg_consts: HashMap<u16, i64>,
     locals_slot: Option<StackSlot>,
+    /// Instructions before this pc can deopt: no path reaching them has had side effects.
+    restart_safe_end: usize,
+    /// Extern call at which a suspended extern can park the fiber by deopting.
+    parkable_extern: Option<usize>,
+    checked_arith: bool,
 }
 
 impl<'a> FunctionCompiler<'a> {
     pub fn new(
         func: &'a mut Function,
         func_ctx: &'a mut FunctionBuilderContext,
+        func_id: u32,
         func_def: &'a FunctionDef,
         vo_module: &'a VoModule,
         helpers: HelperFuncs,
+        checked_arith: bool,
     ) -> Self {
         let mut builder = FunctionBuilder::new(func, func_ctx);
         let entry_block = builder.create_block();
@@ -41,6 +51,7 @@ impl<'a> FunctionCompiler<'a> {
         
         Self {
             builder,
+            func_id,
             func_def,
             vo_module,
             vars: Vec::new(),
@@ -50,12 +61,17 @@ impl<'a> FunctionCompiler<'a> {
             helpers,
             reg_consts: HashMap::new(),
             locals_slot: None,
+            restart_safe_end: 0,
+            parkable_extern: None,
+            checked_arith,
         }
     }
 
     pub fn compile(mut self) -> Result<(), JitError> {
         self.declare_variables();
         self.scan_jump_targets();
+        self.restart_safe_end = restart_safe_end(&self.func_def.code);
+        self.parkable_extern = parkable_extern(&self.func_def.code);
         
         self.builder.switch_to_block(self.entry_block);
         self.emit_prologue();
@@ -104,6 +120,11 @@ impl<'a> FunctionCompiler<'a> {
                     let target = (pc as i32 + offset) as usize;
                     self.ensure_block(target);
                 }
+                Opcode::JumpTable => {
+                    for target in pc + 1..=pc + 1 + inst.b as usize {
+                        self.ensure_block(target);
+                    }
+                }
                 _ => {}
             }
         }
@@ -118,7 +139,7 @@ impl<'a> FunctionCompiler<'a> {
 
     fn emit_prologue(&mut self) {
         let params = self.builder.block_params(self.entry_block);
-        let _ctx = params[0];
+        let ctx = params[0];
         let args = params[1];
         let _ret = params[2];
         
@@ -153,6 +174,17 @@ impl<'a> FunctionCompiler<'a> {
             for i in param_slots..self.vars.len() {
                 self.builder.def_var(self.vars[i], zero);
             }
+            
+            // Register the mirrored locals as GC roots for this call
+            let has_refs = self.func_def.slot_types.iter()
+                .any(|t| matches!(t, SlotType::GcRef | SlotType::Interface0));
+            if has_refs {
+                if let Some(enter_frame) = self.helpers.enter_frame {
+                    let func_id = self.builder.ins().iconst(types::I32, self.func_id as i64);
+                    let locals = self.builder.ins().stack_addr(types::I64, slot, 0);
+                    self.builder.ins().call(enter_frame, &[ctx, func_id, locals]);
+                }
+            }
         } else {
             // No locals, just initialize SSA vars
             let zero = self.builder.ins().iconst(types::I64, 0);
@@ -173,6 +205,7 @@ impl<'a> FunctionCompiler<'a> {
             Opcode::Jump => { self.jump(inst); Ok(true) }
             Opcode::JumpIf => { self.jump_if(inst); Ok(false) }
             Opcode::JumpIfNot => { self.jump_if_not(inst); Ok(false) }
+            Opcode::JumpTable => { self.jump_table(inst); Ok(true) }
             Opcode::Return => { self.ret(inst); Ok(true) }
             Opcode::Panic => { self.panic(inst); Ok(true) }
             Opcode::Call => { self.call(inst); Ok(false) }
@@ -199,6 +232,14 @@ impl<'a> FunctionCompiler<'a> {
         self.conditional_jump(inst, IntCC::NotEqual);
     }
 
+    fn jump_table(&mut self, inst: &Instruction) {
+        // Entries and the miss target all follow the table, so no safepoint is needed
+        let first = self.current_pc + 1;
+        let entries: Vec<Block> = (first..first + inst.b as usize).map(|pc| self.blocks[&pc]).collect();
+        let miss = self.blocks[&(first + inst.b as usize)];
+        emit_jump_table(self, inst, &entries, miss);
+    }
+
     fn jump_if_not(&mut self, inst: &Instruction) {
         self.conditional_jump(inst, IntCC::Equal);
     }
@@ -273,6 +314,7 @@ impl<'a> FunctionCompiler<'a> {
     }
 
     fn panic(&mut self, inst: &Instruction) {
+        emit_record_panic_pc(self);
         if let Some(panic_func) = self.helpers.panic {
             let ctx = self.builder.block_params(self.entry_block)[0];
             // Panic message is an interface (2 slots): slot0=metadata, slot1=data
@@ -294,9 +336,10 @@ impl<'a> FunctionCompiler<'a> {
         self.do_emit_safepoint();
         
         let func_id = (inst.a as u32) | ((inst.flags as u32) << 16);
-        let arg_start = inst.b as usize;
+        let buf = inst.b as usize;
         let arg_slots = (inst.c >> 8) as usize;
         let call_ret_slots = (inst.c & 0xFF) as usize;
+        let arg_start = buf + call_ret_slots;
         
         // Get target function's actual ret_slots for buffer allocation
         // (callee writes based on its func_def.ret_slots, not call instruction's ret_slots)
@@ -334,11 +377,15 @@ impl<'a> FunctionCompiler<'a> {
         // Must use write_var to sync to locals_slot for var_addr access
         for i in 0..call_ret_slots {
             let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-            self.sync_var((arg_start + i) as u16, val);
+            self.sync_var((buf + i) as u16, val);
         }
     }
 
     fn call_extern(&mut self, inst: &Instruction) {
+        if self.helpers.slice_copy.is_some() && self.vo_module.externs[inst.b as usize].name == "vo_copy" {
+            crate::translate::slice_copy(self, inst);
+            return;
+        }
         let call_extern_func = match self.helpers.call_extern {
             Some(f) => f,
             None => return,
@@ -375,10 +422,13 @@ impl<'a> FunctionCompiler<'a> {
         let extern_id_val = self.builder.ins().iconst(types::I32, extern_id as i64);
         let arg_count_val = self.builder.ins().iconst(types::I32, arg_count as i64);
         let ret_slots_val = self.builder.ins().iconst(types::I32, extern_ret_slots as i64);
+        let can_park = self.parkable_extern == Some(self.current_pc);
+        let can_park_val = self.builder.ins().iconst(types::I32, can_park as i64);
         
-        let call = self.builder.ins().call(call_extern_func, &[ctx, extern_id_val, args_ptr, arg_count_val, args_ptr, ret_slots_val]);
+        let call = self.builder.ins().call(call_extern_func, &[ctx, extern_id_val, args_ptr, arg_count_val, args_ptr, ret_slots_val, can_park_val]);
         let result = self.builder.inst_results(call)[0];
         
+        // A Deopt from a parked extern is returned as-is, like Panic and Block
         self.check_call_result(result);
         
         // Must use write_var to sync to locals_slot for var_addr access
@@ -397,10 +447,11 @@ impl<'a> FunctionCompiler<'a> {
         self.do_emit_safepoint();
         
         let closure_ref = self.builder.use_var(self.vars[inst.a as usize]);
-        let arg_start = inst.b as usize;
+        let buf = inst.b as usize;
         
         let arg_slots = (inst.c >> 8) as usize;
         let ret_slots = (inst.c & 0xFF) as usize;
+        let arg_start = buf + ret_slots;
         
         let arg_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
             cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
@@ -433,63 +484,29 @@ impl<'a> FunctionCompiler<'a> {
         
         // Must use write_var to sync to locals_slot for var_addr access
         for i in 0..ret_slots {
-            if arg_start + i < self.vars.len() {
+            if buf + i < self.vars.len() {
                 let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-                self.sync_var((arg_start + i) as u16, val);
+                self.sync_var((buf + i) as u16, val);
             }
         }
     }
 
     fn call_iface(&mut self, inst: &Instruction) {
-        let call_iface_func = match self.helpers.call_iface {
-            Some(f) => f,
-            None => return,
-        };
-        
         self.do_emit_safepoint();
         
-        let slot0 = self.builder.use_var(self.vars[inst.a as usize]);
-        let slot1 = self.builder.use_var(self.vars[inst.a as usize + 1]);
-        let method_idx = inst.flags as u32;
-        let arg_start = inst.b as usize;
-        let arg_slots = (inst.c >> 8) as usize;
-        let ret_slots = (inst.c & 0xFF) as usize;
-        
-        let arg_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
-            cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
-            (arg_slots.max(1) * 8) as u32,
-            8,
-        ));
-        let ret_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
-            cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
-            (ret_slots.max(1) * 8) as u32,
-            8,
-        ));
-        
-        for i in 0..arg_slots {
-            let val = self.builder.use_var(self.vars[arg_start + i]);
-            self.builder.ins().stack_store(val, arg_slot, (i * 8) as i32);
-        }
-        
-        let ctx = self.builder.block_params(self.entry_block)[0];
-        let args_ptr = self.builder.ins().stack_addr(types::I64, arg_slot, 0);
-        let ret_ptr = self.builder.ins().stack_addr(types::I64, ret_slot, 0);
-        let method_idx_val = self.builder.ins().iconst(types::I32, method_idx as i64);
-        let arg_count = self.builder.ins().iconst(types::I32, arg_slots as i64);
-        let ret_count = self.builder.ins().iconst(types::I32, ret_slots as i64);
-        let func_id = self.builder.ins().iconst(types::I32, 0);
-        
-        let call = self.builder.ins().call(call_iface_func, &[
-            ctx, slot0, slot1, method_idx_val, args_ptr, arg_count, ret_ptr, ret_count, func_id
-        ]);
-        let result = self.builder.inst_results(call)[0];
+        let (result, ret_slot) = match emit_call_iface(self, inst) {
+            Some(r) => r,
+            None => return,
+        };
         
         self.check_call_result(result);
         
+        let buf = inst.b as usize;
+        let ret_slots = (inst.c & 0xFF) as usize;
         // Must use write_var to sync to locals_slot for var_addr access
         for i in 0..ret_slots {
             let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-            self.sync_var((arg_start + i) as u16, val);
+            self.sync_var((buf + i) as u16, val);
         }
     }
 
@@ -497,7 +514,8 @@ impl<'a> FunctionCompiler<'a> {
         let not_ok_block = self.builder.create_block();
         let ok_block = self.builder.create_block();
         
-        // JitResult: Ok=0, Panic=1, Block=2
+        // JitResult: Ok=0, Panic=1, Block=2, Deopt=3 (only from a parked extern;
+        // other callees go through the VM, so never Deopt)
         // If not Ok, return result as-is; VM scheduler handles Panic vs Block
         let zero = self.builder.ins().iconst(types::I32, 0);
         let is_ok = self.builder.ins().icmp(IntCC::Equal, result, zero);
@@ -528,7 +546,9 @@ impl<'a> FunctionCompiler<'a> {
         
         self.builder.switch_to_block(call_block);
         self.builder.seal_block(call_block);
-        self.builder.ins().call(safepoint_func, &[ctx]);
+        let func_id = self.builder.ins().iconst(types::I32, self.func_id as i64);
+        let pc = self.builder.ins().iconst(types::I32, self.current_pc as i64);
+        self.builder.ins().call(safepoint_func, &[ctx, func_id, pc]);
         self.builder.ins().jump(merge_block, &[]);
         
         self.builder.switch_to_block(merge_block);
@@ -566,4 +586,66 @@ impl<'a> IrEmitter<'a> for FunctionCompiler<'a> {
         let locals_slot = self.locals_slot.expect("var_addr called but no locals_slot");
         self.builder.ins().stack_addr(types::I64, locals_slot, (slot as i32) * 8)
     }
+    fn can_deopt(&self) -> bool { self.current_pc < self.restart_safe_end }
+    fn checked_arith(&self) -> bool { self.checked_arith }
+    fn emit_deopt_exit(&mut self) {
+        let deopt = self.builder.ins().iconst(types::I32, JitResult::Deopt as i64);
+        self.builder.ins().return_(&[deopt]);
+    }
+}
+
+/// First pc at which re-running the function from the start could repeat a
+/// side effect: the first instruction with effects outside the frame, or the
+/// target of a backward jump (a loop may come back around after such effects).
+fn restart_safe_end(code: &[Instruction]) -> usize {
+    first_effect(code).min(first_loop(code))
+}
+
+/// The extern call where a suspended extern can park the fiber: the first
+/// effect, if no loop can reach it twice. Re-running the function from the
+/// start in the VM then ends up in the same call.
+fn parkable_extern(code: &[Instruction]) -> Option<usize> {
+    let pc = first_effect(code);
+    (pc < first_loop(code) && code[pc].opcode() == Opcode::CallExtern).then_some(pc)
+}
+
+fn first_effect(code: &[Instruction]) -> usize {
+    code.iter()
+        .position(|inst| !is_restartable(inst.opcode()))
+        .unwrap_or(code.len())
+}
+
+/// Lowest target of a backward jump.
+fn first_loop(code: &[Instruction]) -> usize {
+    code.iter().enumerate()
+        .filter(|(_, inst)| matches!(inst.opcode(), Opcode::Jump | Opcode::JumpIf | Opcode::JumpIfNot))
+        .filter_map(|(pc, inst)| {
+            let target = (pc as i32 + inst.imm32()) as usize;
+            (target <= pc).then_some(target)
+        })
+        .min()
+        .unwrap_or(code.len())
+}
+
+/// Instructions that only read shared state, write the frame, or allocate fresh objects.
+fn is_restartable(op: Opcode) -> bool {
+    use Opcode::*;
+    matches!(op,
+        Hint | LoadInt | LoadConst | Copy | CopyN
+        | SlotGet | SlotSet | SlotGetN | SlotSetN | GlobalGet | GlobalGetN
+        | PtrNew | PtrGet | PtrGetN | PtrAdd
+        | AddI | SubI | MulI | DivI | DivU | ModI | ModU | NegI
+        | AddF | SubF | MulF | DivF | NegF | SqrtF | AbsF | MinF | MaxF
+        | EqI | NeI | LtI | LtU | LeI | LeU | GtI | GtU | GeI | GeU
+        | EqF | NeF | LtF | LeF | GtF | GeF
+        | And | Or | Xor | AndNot | Not | Shl | ShrS | ShrU | BoolNot
+        | Jump | JumpIf | JumpIfNot | JumpTable
+        | StrNew | StrLen | StrIndex | StrConcat | StrSlice
+        | StrEq | StrNe | StrLt | StrLe | StrGt | StrGe | StrDecodeRune | StrContains | StrIndexOf
+        | ArrayNew | ArrayGet | ArrayAddr
+        | SliceNew | SliceGet | SliceLen | SliceCap | SliceSlice | SliceAddr
+        | MapNew | MapGet | MapSet | MapLen | ChanNew | ChanLen | ChanCap
+        | ClosureNew | ClosureGet
+        | IfaceAssign | IfaceAssert | IfaceEq
+        | ConvI2F | ConvF2I | ConvF64F32 | ConvF32F64 | Trunc | IndexCheck)
 }
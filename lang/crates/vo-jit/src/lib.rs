@@ -1,11 +1,13 @@
 //! JIT compiler for Vo bytecode using Cranelift.
 
+pub mod background;
 mod func_compiler;
 pub mod loop_analysis;
 mod loop_compiler;
 mod translate;
 mod translator;
 
+pub use background::{BackgroundCompiler, CompileDone};
 pub use func_compiler::FunctionCompiler;
 pub use loop_analysis::LoopInfo;
 pub use loop_compiler::{CompiledLoop, LoopCompiler, LoopFunc, LOOP_RESULT_PANIC};
@@ -13,11 +15,11 @@ pub use translator::{HelperFuncs, IrEmitter, TranslateResult};
 
 use std::collections::HashMap;
 
-use cranelift_codegen::ir::{types, AbiParam, Signature};
+use cranelift_codegen::ir::{types, AbiParam, Function, GlobalValue, Signature};
 use cranelift_codegen::settings::{self, Configurable};
 use cranelift_frontend::FunctionBuilderContext;
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::Module;
+use cranelift_module::{DataDescription, DataId, Module};
 
 use vo_runtime::bytecode::{FunctionDef, Module as VoModule};
 use vo_runtime::instruction::Opcode;
@@ -78,6 +80,17 @@ unsafe impl Sync for CompiledFunction {}
 
 pub type JitFunc = extern "C" fn(ctx: *mut JitContext, args: *mut u64, ret: *mut u64) -> JitResult;
 
+/// Per-function JIT statistics, for diagnosing performance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuncStat {
+    pub func_id: u32,
+    /// Machine code size in bytes (0 if not compiled here).
+    pub code_size: usize,
+    pub compiled: bool,
+    /// Calls seen by the VM's hot-spot tracking (0 if not tracked).
+    pub call_count: u32,
+}
+
 // =============================================================================
 // JitCache
 // =============================================================================
@@ -128,6 +141,8 @@ struct HelperFuncIds {
     call_closure: cranelift_module::FuncId,
     call_iface: cranelift_module::FuncId,
     panic: cranelift_module::FuncId,
+    panic_runtime: cranelift_module::FuncId,
+    enter_frame: cranelift_module::FuncId,
     call_extern: cranelift_module::FuncId,
     str_new: cranelift_module::FuncId,
     str_len: cranelift_module::FuncId,
@@ -137,6 +152,8 @@ struct HelperFuncIds {
     str_eq: cranelift_module::FuncId,
     str_cmp: cranelift_module::FuncId,
     str_decode_rune: cranelift_module::FuncId,
+    str_contains: cranelift_module::FuncId,
+    str_index_of: cranelift_module::FuncId,
     ptr_clone: cranelift_module::FuncId,
     closure_new: cranelift_module::FuncId,
     chan_new: cranelift_module::FuncId,
@@ -150,6 +167,7 @@ struct HelperFuncIds {
     slice_append: cranelift_module::FuncId,
     slice_slice: cranelift_module::FuncId,
     slice_slice3: cranelift_module::FuncId,
+    slice_copy: cranelift_module::FuncId,
     slice_from_array: cranelift_module::FuncId,
     slice_from_array3: cranelift_module::FuncId,
     map_new: cranelift_module::FuncId,
@@ -165,95 +183,50 @@ struct HelperFuncIds {
 }
 
 // =============================================================================
-// JitCompiler
+// ModuleDecls
 // =============================================================================
 
-pub struct JitCompiler {
-    module: JITModule,
-    ctx: cranelift_codegen::Context,
-    cache: JitCache,
-    helper_funcs: HelperFuncIds,
-    debug_ir: bool,
+/// Runtime helper imports and string constant data declared in one Cranelift
+/// module, shared by every function translated into it.
+///
+/// Not tied to `JITModule`: an object or other `cranelift_module::Module`
+/// can host the same translation, with `FunctionCompiler`/`LoopCompiler`
+/// building each function from the `HelperFuncs` that `import` returns.
+pub struct ModuleDecls {
+    helpers: HelperFuncIds,
+    /// String constants already emitted as read-only data, shared across functions.
+    str_data: HashMap<String, DataId>,
 }
 
-impl JitCompiler {
-    pub fn new() -> Result<Self, JitError> {
-        Self::with_debug(false)
-    }
-    
-    pub fn with_debug(debug_ir: bool) -> Result<Self, JitError> {
-        let mut flag_builder = settings::builder();
-        flag_builder.set("opt_level", "speed").unwrap();
-        
-        let isa_builder = cranelift_native::builder()
-            .map_err(|e| JitError::Internal(e.to_string()))?;
-        let isa = isa_builder
-            .finish(settings::Flags::new(flag_builder))
-            .map_err(|e| JitError::Internal(e.to_string()))?;
-
-        let mut builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
-        
-        // Register runtime helper symbols
-        Self::register_symbols(&mut builder);
-
-        let mut module = JITModule::new(builder);
-        let ctx = module.make_context();
-        let ptr_type = module.target_config().pointer_type();
-        let helper_funcs = Self::declare_helpers(&mut module, ptr_type)?;
-
-        Ok(Self { module, ctx, cache: JitCache::new(), helper_funcs, debug_ir })
+impl ModuleDecls {
+    /// Declare the runtime helpers (`vo_*` in `jit_api`) as imports of `module`.
+    pub fn declare<M: Module>(module: &mut M) -> Result<Self, JitError> {
+        Ok(Self { helpers: Self::declare_helpers(module)?, str_data: HashMap::new() })
     }
 
-    fn register_symbols(builder: &mut JITBuilder) {
-        builder.symbol("vo_gc_safepoint", vo_runtime::jit_api::vo_gc_safepoint as *const u8);
-        builder.symbol("vo_gc_alloc", vo_runtime::jit_api::vo_gc_alloc as *const u8);
-        builder.symbol("vo_gc_write_barrier", vo_runtime::jit_api::vo_gc_write_barrier as *const u8);
-        builder.symbol("vo_call_vm", vo_runtime::jit_api::vo_call_vm as *const u8);
-        builder.symbol("vo_call_closure", vo_runtime::jit_api::vo_call_closure as *const u8);
-        builder.symbol("vo_call_iface", vo_runtime::jit_api::vo_call_iface as *const u8);
-        builder.symbol("vo_str_new", vo_runtime::jit_api::vo_str_new as *const u8);
-        builder.symbol("vo_str_len", vo_runtime::jit_api::vo_str_len as *const u8);
-        builder.symbol("vo_str_index", vo_runtime::jit_api::vo_str_index as *const u8);
-        builder.symbol("vo_str_concat", vo_runtime::jit_api::vo_str_concat as *const u8);
-        builder.symbol("vo_str_slice", vo_runtime::jit_api::vo_str_slice as *const u8);
-        builder.symbol("vo_str_eq", vo_runtime::jit_api::vo_str_eq as *const u8);
-        builder.symbol("vo_str_cmp", vo_runtime::jit_api::vo_str_cmp as *const u8);
-        builder.symbol("vo_str_decode_rune", vo_runtime::jit_api::vo_str_decode_rune as *const u8);
-        builder.symbol("vo_map_new", vo_runtime::jit_api::vo_map_new as *const u8);
-        builder.symbol("vo_map_len", vo_runtime::jit_api::vo_map_len as *const u8);
-        builder.symbol("vo_map_get", vo_runtime::jit_api::vo_map_get as *const u8);
-        builder.symbol("vo_map_set", vo_runtime::jit_api::vo_map_set as *const u8);
-        builder.symbol("vo_map_delete", vo_runtime::jit_api::vo_map_delete as *const u8);
-        builder.symbol("vo_ptr_clone", vo_runtime::jit_api::vo_ptr_clone as *const u8);
-        builder.symbol("vo_panic", vo_runtime::jit_api::vo_panic as *const u8);
-        builder.symbol("vo_call_extern", vo_runtime::jit_api::vo_call_extern as *const u8);
-        builder.symbol("vo_closure_new", vo_runtime::jit_api::vo_closure_new as *const u8);
-        builder.symbol("vo_chan_new", vo_runtime::jit_api::vo_chan_new as *const u8);
-        builder.symbol("vo_chan_len", vo_runtime::jit_api::vo_chan_len as *const u8);
-        builder.symbol("vo_chan_cap", vo_runtime::jit_api::vo_chan_cap as *const u8);
-        builder.symbol("vo_array_new", vo_runtime::jit_api::vo_array_new as *const u8);
-        builder.symbol("vo_array_len", vo_runtime::jit_api::vo_array_len as *const u8);
-        builder.symbol("vo_slice_new", vo_runtime::jit_api::vo_slice_new as *const u8);
-        builder.symbol("vo_slice_len", vo_runtime::jit_api::vo_slice_len as *const u8);
-        builder.symbol("vo_slice_cap", vo_runtime::jit_api::vo_slice_cap as *const u8);
-        builder.symbol("vo_slice_append", vo_runtime::jit_api::vo_slice_append as *const u8);
-        builder.symbol("vo_slice_slice", vo_runtime::jit_api::vo_slice_slice as *const u8);
-        builder.symbol("vo_slice_slice3", vo_runtime::jit_api::vo_slice_slice3 as *const u8);
-        builder.symbol("vo_slice_from_array", vo_runtime::jit_api::vo_slice_from_array as *const u8);
-        builder.symbol("vo_slice_from_array3", vo_runtime::jit_api::vo_slice_from_array3 as *const u8);
-        builder.symbol("vo_map_iter_init", vo_runtime::jit_api::vo_map_iter_init as *const u8);
-        builder.symbol("vo_map_iter_next", vo_runtime::jit_api::vo_map_iter_next as *const u8);
-        builder.symbol("vo_iface_assert", vo_runtime::jit_api::vo_iface_assert as *const u8);
-        builder.symbol("vo_iface_to_iface", vo_runtime::jit_api::vo_iface_to_iface as *const u8);
-        builder.symbol("vo_iface_eq", vo_runtime::jit_api::vo_iface_eq as *const u8);
+    /// Make the helpers and the string constants of `def` visible in `func`,
+    /// the Cranelift function `def` is about to be translated into.
+    pub fn import<M: Module>(
+        &mut self,
+        module: &mut M,
+        func: &mut Function,
+        def: &FunctionDef,
+        vo_module: &VoModule,
+    ) -> Result<HelperFuncs, JitError> {
+        let mut helpers = self.helper_refs(module, func);
+        helpers.str_consts = self.declare_str_consts(module, func, def, vo_module)?;
+        Ok(helpers)
     }
 
-    fn declare_helpers(module: &mut JITModule, ptr: cranelift_codegen::ir::Type) -> Result<HelperFuncIds, JitError> {
+    fn declare_helpers<M: Module>(module: &mut M) -> Result<HelperFuncIds, JitError> {
         use cranelift_module::Linkage::Import;
+        let ptr = module.target_config().pointer_type();
         
         let safepoint = module.declare_function("vo_gc_safepoint", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
             sig.params.push(AbiParam::new(ptr));
+            sig.params.push(AbiParam::new(types::I32));  // func_id
+            sig.params.push(AbiParam::new(types::I32));  // pc
             sig
         })?;
         
@@ -322,6 +295,21 @@ impl JitCompiler {
             sig
         })?;
         
+        let panic_runtime = module.declare_function("vo_panic_runtime", Import, &{
+            let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // ctx
+            sig.params.push(AbiParam::new(types::I32)); // kind
+            sig
+        })?;
+        
+        let enter_frame = module.declare_function("vo_jit_enter_frame", Import, &{
+            let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // ctx
+            sig.params.push(AbiParam::new(types::I32)); // func_id
+            sig.params.push(AbiParam::new(ptr));        // locals
+            sig
+        })?;
+        
         let call_extern = module.declare_function("vo_call_extern", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
             sig.params.push(AbiParam::new(ptr));       // ctx
@@ -330,6 +318,7 @@ impl JitCompiler {
             sig.params.push(AbiParam::new(types::I32)); // arg_count
             sig.params.push(AbiParam::new(ptr));       // ret
             sig.params.push(AbiParam::new(types::I32)); // ret_slots
+            sig.params.push(AbiParam::new(types::I32)); // can_park
             sig.returns.push(AbiParam::new(types::I32));
             sig
         })?;
@@ -401,6 +390,22 @@ impl JitCompiler {
             sig
         })?;
         
+        let str_contains = module.declare_function("vo_str_contains", Import, &{
+            let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(types::I64));
+            sig.params.push(AbiParam::new(types::I64));
+            sig.returns.push(AbiParam::new(types::I64));
+            sig
+        })?;
+        
+        let str_index_of = module.declare_function("vo_str_index_of", Import, &{
+            let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(types::I64));
+            sig.params.push(AbiParam::new(types::I64));
+            sig.returns.push(AbiParam::new(types::I64));
+            sig
+        })?;
+        
         let ptr_clone = module.declare_function("vo_ptr_clone", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
             sig.params.push(AbiParam::new(ptr));
@@ -505,6 +510,15 @@ impl JitCompiler {
             sig
         })?;
         
+        let slice_copy = module.declare_function("vo_slice_copy", Import, &{
+            let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // gc
+            sig.params.push(AbiParam::new(types::I64)); // dst
+            sig.params.push(AbiParam::new(types::I64)); // src
+            sig.returns.push(AbiParam::new(types::I64));
+            sig
+        })?;
+        
         let slice_slice3 = module.declare_function("vo_slice_slice3", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
             sig.params.push(AbiParam::new(ptr));
@@ -558,6 +572,7 @@ impl JitCompiler {
         
         let map_get = module.declare_function("vo_map_get", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // ctx
             sig.params.push(AbiParam::new(types::I64));
             sig.params.push(AbiParam::new(ptr));
             sig.params.push(AbiParam::new(types::I32));
@@ -569,6 +584,7 @@ impl JitCompiler {
         
         let map_set = module.declare_function("vo_map_set", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // ctx
             sig.params.push(AbiParam::new(types::I64));
             sig.params.push(AbiParam::new(ptr));
             sig.params.push(AbiParam::new(types::I32));
@@ -588,6 +604,7 @@ impl JitCompiler {
         
         let map_iter_init = module.declare_function("vo_map_iter_init", Import, &{
             let mut sig = Signature::new(module.target_config().default_call_conv);
+            sig.params.push(AbiParam::new(ptr));        // ctx
             sig.params.push(AbiParam::new(types::I64)); // map
             sig.params.push(AbiParam::new(ptr));        // iter_ptr
             sig
@@ -637,15 +654,219 @@ impl JitCompiler {
         })?;
         
         Ok(HelperFuncIds {
-            safepoint, call_vm, gc_alloc, write_barrier, call_closure, call_iface, panic, call_extern,
+            safepoint, call_vm, gc_alloc, write_barrier, call_closure, call_iface, panic, panic_runtime, enter_frame, call_extern,
             str_new, str_len, str_index, str_concat, str_slice, str_eq, str_cmp, str_decode_rune,
+            str_contains, str_index_of,
             ptr_clone, closure_new, chan_new, chan_len, chan_cap, array_new, array_len,
-            slice_new, slice_len, slice_cap, slice_append, slice_slice, slice_slice3,
+            slice_new, slice_len, slice_cap, slice_append, slice_slice, slice_slice3, slice_copy,
             slice_from_array, slice_from_array3,
             map_new, map_len, map_get, map_set, map_delete, map_iter_init, map_iter_next, iface_assert, iface_to_iface, iface_eq,
         })
     }
 
+    fn helper_refs<M: Module>(&self, module: &mut M, func: &mut Function) -> HelperFuncs {
+        HelperFuncs {
+            safepoint: Some(module.declare_func_in_func(self.helpers.safepoint, func)),
+            call_vm: Some(module.declare_func_in_func(self.helpers.call_vm, func)),
+            gc_alloc: Some(module.declare_func_in_func(self.helpers.gc_alloc, func)),
+            write_barrier: Some(module.declare_func_in_func(self.helpers.write_barrier, func)),
+            call_closure: Some(module.declare_func_in_func(self.helpers.call_closure, func)),
+            call_iface: Some(module.declare_func_in_func(self.helpers.call_iface, func)),
+            panic: Some(module.declare_func_in_func(self.helpers.panic, func)),
+            panic_runtime: Some(module.declare_func_in_func(self.helpers.panic_runtime, func)),
+            enter_frame: Some(module.declare_func_in_func(self.helpers.enter_frame, func)),
+            call_extern: Some(module.declare_func_in_func(self.helpers.call_extern, func)),
+            str_new: Some(module.declare_func_in_func(self.helpers.str_new, func)),
+            str_len: Some(module.declare_func_in_func(self.helpers.str_len, func)),
+            str_index: Some(module.declare_func_in_func(self.helpers.str_index, func)),
+            str_concat: Some(module.declare_func_in_func(self.helpers.str_concat, func)),
+            str_slice: Some(module.declare_func_in_func(self.helpers.str_slice, func)),
+            str_eq: Some(module.declare_func_in_func(self.helpers.str_eq, func)),
+            str_cmp: Some(module.declare_func_in_func(self.helpers.str_cmp, func)),
+            str_decode_rune: Some(module.declare_func_in_func(self.helpers.str_decode_rune, func)),
+            str_contains: Some(module.declare_func_in_func(self.helpers.str_contains, func)),
+            str_index_of: Some(module.declare_func_in_func(self.helpers.str_index_of, func)),
+            ptr_clone: Some(module.declare_func_in_func(self.helpers.ptr_clone, func)),
+            closure_new: Some(module.declare_func_in_func(self.helpers.closure_new, func)),
+            chan_new: Some(module.declare_func_in_func(self.helpers.chan_new, func)),
+            chan_len: Some(module.declare_func_in_func(self.helpers.chan_len, func)),
+            chan_cap: Some(module.declare_func_in_func(self.helpers.chan_cap, func)),
+            array_new: Some(module.declare_func_in_func(self.helpers.array_new, func)),
+            array_len: Some(module.declare_func_in_func(self.helpers.array_len, func)),
+            slice_new: Some(module.declare_func_in_func(self.helpers.slice_new, func)),
+            slice_len: Some(module.declare_func_in_func(self.helpers.slice_len, func)),
+            slice_cap: Some(module.declare_func_in_func(self.helpers.slice_cap, func)),
+            slice_append: Some(module.declare_func_in_func(self.helpers.slice_append, func)),
+            slice_slice: Some(module.declare_func_in_func(self.helpers.slice_slice, func)),
+            slice_slice3: Some(module.declare_func_in_func(self.helpers.slice_slice3, func)),
+            slice_copy: Some(module.declare_func_in_func(self.helpers.slice_copy, func)),
+            slice_from_array: Some(module.declare_func_in_func(self.helpers.slice_from_array, func)),
+            slice_from_array3: Some(module.declare_func_in_func(self.helpers.slice_from_array3, func)),
+            map_new: Some(module.declare_func_in_func(self.helpers.map_new, func)),
+            map_len: Some(module.declare_func_in_func(self.helpers.map_len, func)),
+            map_get: Some(module.declare_func_in_func(self.helpers.map_get, func)),
+            map_set: Some(module.declare_func_in_func(self.helpers.map_set, func)),
+            map_delete: Some(module.declare_func_in_func(self.helpers.map_delete, func)),
+            map_iter_init: Some(module.declare_func_in_func(self.helpers.map_iter_init, func)),
+            map_iter_next: Some(module.declare_func_in_func(self.helpers.map_iter_next, func)),
+            iface_assert: Some(module.declare_func_in_func(self.helpers.iface_assert, func)),
+            iface_to_iface: Some(module.declare_func_in_func(self.helpers.iface_to_iface, func)),
+            iface_eq: Some(module.declare_func_in_func(self.helpers.iface_eq, func)),
+            str_consts: HashMap::new(),
+        }
+    }
+
+    /// Make every string constant `func` creates with StrNew available as read-only data.
+    fn declare_str_consts<M: Module>(
+        &mut self,
+        module: &mut M,
+        func: &mut Function,
+        def: &FunctionDef,
+        vo_module: &VoModule,
+    ) -> Result<HashMap<u16, GlobalValue>, JitError> {
+        use vo_runtime::bytecode::Constant;
+        let mut consts = HashMap::new();
+        for inst in &def.code {
+            if inst.opcode() != Opcode::StrNew || consts.contains_key(&inst.b) {
+                continue;
+            }
+            let s = match vo_module.constants.get(inst.b as usize) {
+                Some(Constant::String(s)) if !s.is_empty() => s,
+                _ => continue,
+            };
+            let data_id = match self.str_data.get(s) {
+                Some(&id) => id,
+                None => {
+                    let id = module.declare_anonymous_data(false, false)?;
+                    let mut desc = DataDescription::new();
+                    desc.define(s.as_bytes().into());
+                    module.define_data(id, &desc)?;
+                    self.str_data.insert(s.clone(), id);
+                    id
+                }
+            };
+            consts.insert(inst.b, module.declare_data_in_func(data_id, func));
+        }
+        Ok(consts)
+    }
+}
+
+// =============================================================================
+// JitCompiler
+// =============================================================================
+
+pub struct JitCompiler {
+    module: JITModule,
+    ctx: cranelift_codegen::Context,
+    cache: JitCache,
+    decls: ModuleDecls,
+    debug_ir: bool,
+    /// Keep each function's Cranelift IR text for `dump_cranelift_ir`.
+    capture_ir: bool,
+    ir_dumps: HashMap<u32, String>,
+    /// Trap signed overflow in AddI/SubI/MulI (see `set_checked_arith`).
+    checked_arith: bool,
+}
+
+impl JitCompiler {
+    pub fn new() -> Result<Self, JitError> {
+        Self::with_debug(false)
+    }
+    
+    pub fn with_debug(debug_ir: bool) -> Result<Self, JitError> {
+        let mut flag_builder = settings::builder();
+        flag_builder.set("opt_level", "speed").unwrap();
+        
+        let isa_builder = cranelift_native::builder()
+            .map_err(|e| JitError::Internal(e.to_string()))?;
+        let isa = isa_builder
+            .finish(settings::Flags::new(flag_builder))
+            .map_err(|e| JitError::Internal(e.to_string()))?;
+
+        let mut builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
+        
+        // Register runtime helper symbols
+        Self::register_symbols(&mut builder);
+
+        let mut module = JITModule::new(builder);
+        let ctx = module.make_context();
+        let decls = ModuleDecls::declare(&mut module)?;
+
+        Ok(Self {
+            module, ctx, cache: JitCache::new(), decls, debug_ir,
+            capture_ir: debug_ir, ir_dumps: HashMap::new(), checked_arith: false,
+        })
+    }
+
+    /// Keep the Cranelift IR of functions compiled from now on, for
+    /// `dump_cranelift_ir`. On by default with `with_debug(true)`.
+    pub fn set_capture_ir(&mut self, capture_ir: bool) {
+        self.capture_ir = capture_ir;
+    }
+
+    /// Make functions compiled from now on panic with "integer overflow" when
+    /// signed AddI/SubI/MulI overflow, instead of wrapping as Go defines.
+    /// int8/16/32 results are checked against their own range (see
+    /// `ARITH_FLAG_WIDTH_SHIFT`). Unsigned ones (`ARITH_FLAG_UNSIGNED`)
+    /// still wrap. A debugging aid:
+    /// interpreted code keeps wrapping.
+    pub fn set_checked_arith(&mut self, checked_arith: bool) {
+        self.checked_arith = checked_arith;
+    }
+
+    fn register_symbols(builder: &mut JITBuilder) {
+        builder.symbol("vo_gc_safepoint", vo_runtime::jit_api::vo_gc_safepoint as *const u8);
+        builder.symbol("vo_gc_alloc", vo_runtime::jit_api::vo_gc_alloc as *const u8);
+        builder.symbol("vo_gc_write_barrier", vo_runtime::jit_api::vo_gc_write_barrier as *const u8);
+        builder.symbol("vo_call_vm", vo_runtime::jit_api::vo_call_vm as *const u8);
+        builder.symbol("vo_call_closure", vo_runtime::jit_api::vo_call_closure as *const u8);
+        builder.symbol("vo_call_iface", vo_runtime::jit_api::vo_call_iface as *const u8);
+        builder.symbol("vo_str_new", vo_runtime::jit_api::vo_str_new as *const u8);
+        builder.symbol("vo_str_len", vo_runtime::jit_api::vo_str_len as *const u8);
+        builder.symbol("vo_str_index", vo_runtime::jit_api::vo_str_index as *const u8);
+        builder.symbol("vo_str_concat", vo_runtime::jit_api::vo_str_concat as *const u8);
+        builder.symbol("vo_str_slice", vo_runtime::jit_api::vo_str_slice as *const u8);
+        builder.symbol("vo_str_eq", vo_runtime::jit_api::vo_str_eq as *const u8);
+        builder.symbol("vo_str_cmp", vo_runtime::jit_api::vo_str_cmp as *const u8);
+        builder.symbol("vo_str_decode_rune", vo_runtime::jit_api::vo_str_decode_rune as *const u8);
+        builder.symbol("vo_str_contains", vo_runtime::jit_api::vo_str_contains as *const u8);
+        builder.symbol("vo_str_index_of", vo_runtime::jit_api::vo_str_index_of as *const u8);
+        builder.symbol("vo_map_new", vo_runtime::jit_api::vo_map_new as *const u8);
+        builder.symbol("vo_map_len", vo_runtime::jit_api::vo_map_len as *const u8);
+        builder.symbol("vo_map_get", vo_runtime::jit_api::vo_map_get as *const u8);
+        builder.symbol("vo_map_set", vo_runtime::jit_api::vo_map_set as *const u8);
+        builder.symbol("vo_map_delete", vo_runtime::jit_api::vo_map_delete as *const u8);
+        builder.symbol("vo_ptr_clone", vo_runtime::jit_api::vo_ptr_clone as *const u8);
+        builder.symbol("vo_panic", vo_runtime::jit_api::vo_panic as *const u8);
+        builder.symbol("vo_panic_runtime", vo_runtime::jit_api::vo_panic_runtime as *const u8);
+        builder.symbol("vo_jit_enter_frame", vo_runtime::jit_api::vo_jit_enter_frame as *const u8);
+        builder.symbol("vo_call_extern", vo_runtime::jit_api::vo_call_extern as *const u8);
+        builder.symbol("vo_closure_new", vo_runtime::jit_api::vo_closure_new as *const u8);
+        builder.symbol("vo_chan_new", vo_runtime::jit_api::vo_chan_new as *const u8);
+        builder.symbol("vo_chan_len", vo_runtime::jit_api::vo_chan_len as *const u8);
+        builder.symbol("vo_chan_cap", vo_runtime::jit_api::vo_chan_cap as *const u8);
+        builder.symbol("vo_array_new", vo_runtime::jit_api::vo_array_new as *const u8);
+        builder.symbol("vo_array_len", vo_runtime::jit_api::vo_array_len as *const u8);
+        builder.symbol("vo_slice_new", vo_runtime::jit_api::vo_slice_new as *const u8);
+        builder.symbol("vo_slice_len", vo_runtime::jit_api::vo_slice_len as *const u8);
+        builder.symbol("vo_slice_cap", vo_runtime::jit_api::vo_slice_cap as *const u8);
+        builder.symbol("vo_slice_append", vo_runtime::jit_api::vo_slice_append as *const u8);
+        builder.symbol("vo_slice_slice", vo_runtime::jit_api::vo_slice_slice as *const u8);
+        builder.symbol("vo_slice_copy", vo_runtime::jit_api::vo_slice_copy as *const u8);
+        builder.symbol("vo_slice_slice3", vo_runtime::jit_api::vo_slice_slice3 as *const u8);
+        builder.symbol("vo_slice_from_array", vo_runtime::jit_api::vo_slice_from_array as *const u8);
+        builder.symbol("vo_slice_from_array3", vo_runtime::jit_api::vo_slice_from_array3 as *const u8);
+        builder.symbol("vo_map_iter_init", vo_runtime::jit_api::vo_map_iter_init as *const u8);
+        builder.symbol("vo_map_iter_next", vo_runtime::jit_api::vo_map_iter_next as *const u8);
+        builder.symbol("vo_iface_assert", vo_runtime::jit_api::vo_iface_assert as *const u8);
+        builder.symbol("vo_iface_to_iface", vo_runtime::jit_api::vo_iface_to_iface as *const u8);
+        builder.symbol("vo_iface_eq", vo_runtime::jit_api::vo_iface_eq as *const u8);
+    }
+
+    /// Whether `func` can be compiled. Only ops that may block or unwind
+    /// through the VM (goroutines, channel send/recv/close, select,
+    /// defer/recover) force interpretation; creating a channel or querying
+    /// its len/cap is handled by runtime helpers.
     pub fn can_jit(&self, func: &FunctionDef, _module: &VoModule) -> bool {
         for inst in &func.code {
             match inst.opcode() {
@@ -658,52 +879,6 @@ impl JitCompiler {
         true
     }
 
-    fn get_helper_refs(&mut self) -> HelperFuncs {
-        HelperFuncs {
-            safepoint: Some(self.module.declare_func_in_func(self.helper_funcs.safepoint, &mut self.ctx.func)),
-            call_vm: Some(self.module.declare_func_in_func(self.helper_funcs.call_vm, &mut self.ctx.func)),
-            gc_alloc: Some(self.module.declare_func_in_func(self.helper_funcs.gc_alloc, &mut self.ctx.func)),
-            write_barrier: Some(self.module.declare_func_in_func(self.helper_funcs.write_barrier, &mut self.ctx.func)),
-            call_closure: Some(self.module.declare_func_in_func(self.helper_funcs.call_closure, &mut self.ctx.func)),
-            call_iface: Some(self.module.declare_func_in_func(self.helper_funcs.call_iface, &mut self.ctx.func)),
-            panic: Some(self.module.declare_func_in_func(self.helper_funcs.panic, &mut self.ctx.func)),
-            call_extern: Some(self.module.declare_func_in_func(self.helper_funcs.call_extern, &mut self.ctx.func)),
-            str_new: Some(self.module.declare_func_in_func(self.helper_funcs.str_new, &mut self.ctx.func)),
-            str_len: Some(self.module.declare_func_in_func(self.helper_funcs.str_len, &mut self.ctx.func)),
-            str_index: Some(self.module.declare_func_in_func(self.helper_funcs.str_index, &mut self.ctx.func)),
-            str_concat: Some(self.module.declare_func_in_func(self.helper_funcs.str_concat, &mut self.ctx.func)),
-            str_slice: Some(self.module.declare_func_in_func(self.helper_funcs.str_slice, &mut self.ctx.func)),
-            str_eq: Some(self.module.declare_func_in_func(self.helper_funcs.str_eq, &mut self.ctx.func)),
-            str_cmp: Some(self.module.declare_func_in_func(self.helper_funcs.str_cmp, &mut self.ctx.func)),
-            str_decode_rune: Some(self.module.declare_func_in_func(self.helper_funcs.str_decode_rune, &mut self.ctx.func)),
-            ptr_clone: Some(self.module.declare_func_in_func(self.helper_funcs.ptr_clone, &mut self.ctx.func)),
-            closure_new: Some(self.module.declare_func_in_func(self.helper_funcs.closure_new, &mut self.ctx.func)),
-            chan_new: Some(self.module.declare_func_in_func(self.helper_funcs.chan_new, &mut self.ctx.func)),
-            chan_len: Some(self.module.declare_func_in_func(self.helper_funcs.chan_len, &mut self.ctx.func)),
-            chan_cap: Some(self.module.declare_func_in_func(self.helper_funcs.chan_cap, &mut self.ctx.func)),
-            array_new: Some(self.module.declare_func_in_func(self.helper_funcs.array_new, &mut self.ctx.func)),
-            array_len: Some(self.module.declare_func_in_func(self.helper_funcs.array_len, &mut self.ctx.func)),
-            slice_new: Some(self.module.declare_func_in_func(self.helper_funcs.slice_new, &mut self.ctx.func)),
-            slice_len: Some(self.module.declare_func_in_func(self.helper_funcs.slice_len, &mut self.ctx.func)),
-            slice_cap: Some(self.module.declare_func_in_func(self.helper_funcs.slice_cap, &mut self.ctx.func)),
-            slice_append: Some(self.module.declare_func_in_func(self.helper_funcs.slice_append, &mut self.ctx.func)),
-            slice_slice: Some(self.module.declare_func_in_func(self.helper_funcs.slice_slice, &mut self.ctx.func)),
-            slice_slice3: Some(self.module.declare_func_in_func(self.helper_funcs.slice_slice3, &mut self.ctx.func)),
-            slice_from_array: Some(self.module.declare_func_in_func(self.helper_funcs.slice_from_array, &mut self.ctx.func)),
-            slice_from_array3: Some(self.module.declare_func_in_func(self.helper_funcs.slice_from_array3, &mut self.ctx.func)),
-            map_new: Some(self.module.declare_func_in_func(self.helper_funcs.map_new, &mut self.ctx.func)),
-            map_len: Some(self.module.declare_func_in_func(self.helper_funcs.map_len, &mut self.ctx.func)),
-            map_get: Some(self.module.declare_func_in_func(self.helper_funcs.map_get, &mut self.ctx.func)),
-            map_set: Some(self.module.declare_func_in_func(self.helper_funcs.map_set, &mut self.ctx.func)),
-            map_delete: Some(self.module.declare_func_in_func(self.helper_funcs.map_delete, &mut self.ctx.func)),
-            map_iter_init: Some(self.module.declare_func_in_func(self.helper_funcs.map_iter_init, &mut self.ctx.func)),
-            map_iter_next: Some(self.module.declare_func_in_func(self.helper_funcs.map_iter_next, &mut self.ctx.func)),
-            iface_assert: Some(self.module.declare_func_in_func(self.helper_funcs.iface_assert, &mut self.ctx.func)),
-            iface_to_iface: Some(self.module.declare_func_in_func(self.helper_funcs.iface_to_iface, &mut self.ctx.func)),
-            iface_eq: Some(self.module.declare_func_in_func(self.helper_funcs.iface_eq, &mut self.ctx.func)),
-        }
-    }
-
     pub fn compile(&mut self, func_id: u32, func: &FunctionDef, vo_module: &VoModule) -> Result<(), JitError> {
         if !self.can_jit(func, vo_module) {
             return Err(JitError::NotJittable(func_id));
@@ -729,22 +904,31 @@ impl JitCompiler {
         self.ctx.func.name = cranelift_codegen::ir::UserFuncName::user(0, func_id);
 
         let mut func_ctx = FunctionBuilderContext::new();
-        let helpers = self.get_helper_refs();
-        let compiler = FunctionCompiler::new(&mut self.ctx.func, &mut func_ctx, func, vo_module, helpers);
+        let helpers = self.decls.import(&mut self.module, &mut self.ctx.func, func, vo_module)?;
+        let compiler = FunctionCompiler::new(&mut self.ctx.func, &mut func_ctx, func_id, func, vo_module, helpers, self.checked_arith);
         compiler.compile()?;
         
-        if self.debug_ir {
-            eprintln!("=== JIT IR for func_{} {} ===", func_id, func.name);
-            eprintln!("{}", self.ctx.func.display());
+        if self.debug_ir || self.capture_ir {
+            let ir = self.ctx.func.display().to_string();
+            if self.debug_ir {
+                let title = format!("func_{} {}", func_id, func.name);
+                eprintln!("{}", debug_listing(&title, func, &ir));
+            }
+            if self.capture_ir {
+                self.ir_dumps.insert(func_id, ir);
+            }
         }
         
         self.module.define_function(func_id_cl, &mut self.ctx)?;
+        let code_size = self.ctx.compiled_code()
+            .map(|code| code.code_info().total_size as usize)
+            .unwrap_or(0);
         self.module.clear_context(&mut self.ctx);
         self.module.finalize_definitions()?;
         
         let code_ptr = self.module.get_finalized_function(func_id_cl);
         let compiled = CompiledFunction {
-            code_ptr, code_size: 0,
+            code_ptr, code_size,
             param_slots: func.param_slots, ret_slots: func.ret_slots,
         };
         self.cache.insert(func_id, compiled);
@@ -776,10 +960,15 @@ impl JitCompiler {
         self.ctx.func.name = cranelift_codegen::ir::UserFuncName::user(1, func_id * 10000 + begin_pc as u32);
 
         let mut func_ctx = FunctionBuilderContext::new();
-        let helpers = self.get_helper_refs();
-        let compiler = LoopCompiler::new(&mut self.ctx.func, &mut func_ctx, func, vo_module, loop_info, helpers);
+        let helpers = self.decls.import(&mut self.module, &mut self.ctx.func, func, vo_module)?;
+        let compiler = LoopCompiler::new(&mut self.ctx.func, &mut func_ctx, func_id, func, vo_module, loop_info, helpers, self.checked_arith);
         compiler.compile()?;
         
+        if self.debug_ir {
+            let title = format!("loop func_{} {} @{}", func_id, func.name, begin_pc);
+            eprintln!("{}", debug_listing(&title, func, &self.ctx.func.display().to_string()));
+        }
+        
         self.module.define_function(func_id_cl, &mut self.ctx)?;
         self.module.clear_context(&mut self.ctx);
         self.module.finalize_definitions()?;
@@ -791,12 +980,481 @@ impl JitCompiler {
     }
 
     pub fn get(&self, func_id: u32) -> Option<&CompiledFunction> { self.cache.get(func_id) }
+    pub fn is_compiled(&self, func_id: u32) -> bool { self.cache.contains(func_id) }
     pub unsafe fn get_func_ptr(&self, func_id: u32) -> Option<JitFunc> { self.cache.get_func_ptr(func_id) }
     pub fn get_loop(&self, func_id: u32, begin_pc: usize) -> Option<&CompiledLoop> { self.cache.get_loop(func_id, begin_pc) }
     pub unsafe fn get_loop_func_ptr(&self, func_id: u32, begin_pc: usize) -> Option<LoopFunc> { self.cache.get_loop_func_ptr(func_id, begin_pc) }
     pub fn cache(&self) -> &JitCache { &self.cache }
+
+    /// Statistics for every compiled function, ordered by func_id.
+    pub fn stats(&self) -> Vec<FuncStat> {
+        let mut stats: Vec<FuncStat> = self.cache.functions.iter()
+            .map(|(&func_id, f)| FuncStat { func_id, code_size: f.code_size, compiled: true, call_count: 0 })
+            .collect();
+        stats.sort_by_key(|s| s.func_id);
+        stats
+    }
+
+    /// Textual Cranelift IR of a compiled function, if IR capture was on
+    /// when it was compiled (see `set_capture_ir`).
+    pub fn dump_cranelift_ir(&self, func_id: u32) -> Option<String> {
+        self.ir_dumps.get(&func_id).cloned()
+    }
+}
+
+/// A function's bytecode followed by the Cranelift IR of a function or loop
+/// compiled from it, as printed with `debug_ir`.
+fn debug_listing(title: &str, func: &FunctionDef, ir: &str) -> String {
+    let mut out = format!("=== JIT {} ===\n--- bytecode ---\n", title);
+    for (pc, inst) in func.code.iter().enumerate() {
+        out.push_str(&format!(
+            "  {:04}: {:?} a={} b={} c={} flags={}\n",
+            pc, inst.opcode(), inst.a, inst.b, inst.c, inst.flags
+        ));
+    }
+    out.push_str("--- cranelift ir ---\n");
+    out.push_str(ir);
+    out
 }
 
 impl Default for JitCompiler {
     fn default() -> Self { Self::new().expect("failed to create JIT compiler") }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vo_runtime::instruction::Instruction;
+    use vo_runtime::PanicKind;
+
+    fn make_func(param_slots: u16, local_slots: u16, ret_slots: u16, code: Vec<Instruction>) -> FunctionDef {
+        FunctionDef {
+            name: "test".to_string(),
+            param_count: param_slots,
+            param_slots,
+            local_slots,
+            ret_slots,
+            recv_slots: 0,
+            heap_ret_gcref_count: 0,
+            heap_ret_gcref_start: 0,
+            heap_ret_slots: vec![],
+            is_closure: false,
+            error_ret_slot: -1,
+            code,
+            slot_types: vec![],
+            capture_types: vec![],
+            param_types: vec![],
+        }
+    }
+
+    fn run(jit: &JitCompiler, func_id: u32, args: &mut [u64], ret: &mut [u64]) -> JitResult {
+        let func = unsafe { jit.get_func_ptr(func_id) }.expect("function not compiled");
+        // Functions without helper calls never touch the context.
+        func(std::ptr::null_mut(), args.as_mut_ptr(), ret.as_mut_ptr())
+    }
+
+    #[test]
+    fn test_run_return_42() {
+        let func = make_func(0, 1, 1, vec![
+            Instruction::new(Opcode::LoadInt, 0, 42, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        assert!(!jit.is_compiled(0));
+        jit.compile(0, &module.functions[0], &module).unwrap();
+        assert!(jit.is_compiled(0));
+
+        let mut ret = [0u64; 1];
+        assert_eq!(run(&jit, 0, &mut [], &mut ret), JitResult::Ok);
+        assert_eq!(ret[0], 42);
+    }
+
+    #[test]
+    fn test_run_add_params() {
+        let func = make_func(2, 3, 1, vec![
+            Instruction::new(Opcode::AddI, 2, 0, 1),
+            Instruction::new(Opcode::Return, 2, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        let mut args = [40u64, 2];
+        let mut ret = [0u64; 1];
+        assert_eq!(run(&jit, 0, &mut args, &mut ret), JitResult::Ok);
+        assert_eq!(ret[0], 42);
+    }
+
+    /// SqrtF/AbsF/MinF/MaxF give the interpreter's results, including for
+    /// negatives, signed zeros, NaN and infinities.
+    #[test]
+    fn test_float_intrinsics_match_interpreter() {
+        let func = make_func(2, 6, 4, vec![
+            Instruction::new(Opcode::SqrtF, 2, 0, 0),
+            Instruction::new(Opcode::AbsF, 3, 0, 0),
+            Instruction::new(Opcode::MinF, 4, 0, 1),
+            Instruction::new(Opcode::MaxF, 5, 0, 1),
+            Instruction::new(Opcode::Return, 2, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        // Same as the VM's SqrtF/AbsF/MinF/MaxF.
+        fn min(a: f64, b: f64) -> f64 {
+            if a.is_nan() || b.is_nan() { f64::NAN } else if a == b { if a.is_sign_negative() { a } else { b } } else { a.min(b) }
+        }
+        fn max(a: f64, b: f64) -> f64 {
+            if a.is_nan() || b.is_nan() { f64::NAN } else if a == b { if a.is_sign_negative() { b } else { a } } else { a.max(b) }
+        }
+        let same = |x: f64, y: f64| (x.is_nan() && y.is_nan()) || x.to_bits() == y.to_bits();
+
+        let inputs = [
+            (4.0, -1.0), (2.0, 3.0), (-4.0, 0.0), (0.0, -0.0), (-0.0, 0.0),
+            (2.0, f64::NAN), (f64::NAN, f64::INFINITY), (f64::INFINITY, f64::NEG_INFINITY), (-1e-300, 1e300),
+        ];
+        for (x, y) in inputs {
+            let mut args = [x.to_bits(), y.to_bits()];
+            let mut ret = [0u64; 4];
+            assert_eq!(run(&jit, 0, &mut args, &mut ret), JitResult::Ok);
+            let got = ret.map(f64::from_bits);
+            let want = [x.sqrt(), x.abs(), min(x, y), max(x, y)];
+            for (g, w) in got.iter().zip(want) {
+                assert!(same(*g, w), "x={} y={}: got {:?}, want {:?}", x, y, got, want);
+            }
+        }
+    }
+
+    /// Trunc wraps to every sub-word width, sign- or zero-extending like the VM.
+    #[test]
+    fn test_trunc_widths() {
+        let flags = [0x81, 0x82, 0x84, 0x01, 0x02, 0x04];
+        let mut code: Vec<Instruction> = flags.iter().enumerate()
+            .map(|(i, &f)| Instruction::with_flags(Opcode::Trunc, f, i as u16 + 1, 0, 0))
+            .collect();
+        code.push(Instruction::new(Opcode::Return, 1, 6, 0));
+        let func = make_func(1, 7, 6, code);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        let inputs: [i64; 7] = [0, 300, -129, 70000, -40000, 0x1_8000_0000, -1];
+        for x in inputs {
+            let mut args = [x as u64];
+            let mut ret = [0u64; 6];
+            assert_eq!(run(&jit, 0, &mut args, &mut ret), JitResult::Ok);
+            let want = [
+                x as i8 as i64, x as i16 as i64, x as i32 as i64,
+                x as u8 as i64, x as u16 as i64, x as u32 as i64,
+            ];
+            assert_eq!(ret.map(|r| r as i64), want, "x={}", x);
+        }
+    }
+
+    #[test]
+    fn test_str_search_helpers() {
+        use vo_runtime::gc::Gc;
+        use vo_runtime::objects::string;
+
+        let func = make_func(2, 4, 2, vec![
+            Instruction::new(Opcode::StrContains, 2, 0, 1),
+            Instruction::new(Opcode::StrIndexOf, 3, 0, 1),
+            Instruction::new(Opcode::Return, 2, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        let mut gc = Gc::new();
+        let cases = [
+            ("hello, world", "world", 1, 7),
+            ("hello, world", "o", 1, 4),
+            ("hello, world", "xyz", 0, -1),
+            ("hello", "hello!", 0, -1),
+            ("hello", "", 1, 0),
+            ("", "", 1, 0),
+            ("", "a", 0, -1),
+        ];
+        for (s, sub, contains, index) in cases {
+            let mut args = [string::from_rust_str(&mut gc, s) as u64, string::from_rust_str(&mut gc, sub) as u64];
+            let mut ret = [0u64; 2];
+            assert_eq!(run(&jit, 0, &mut args, &mut ret), JitResult::Ok);
+            assert_eq!((ret[0], ret[1] as i64), (contains, index), "{:?} in {:?}", sub, s);
+        }
+    }
+
+    #[test]
+    fn test_jump_table() {
+        let jump = |offset: i32| Instruction::new(Opcode::Jump, 0, offset as u16, (offset >> 16) as u16);
+        let func = make_func(1, 2, 1, vec![
+            Instruction::new(Opcode::JumpTable, 0, 3, 0),
+            jump(4),  // 0 -> pc 5
+            jump(5),  // 1 -> pc 7
+            jump(2),  // 2 -> pc 5
+            jump(5),  // miss -> pc 9
+            Instruction::new(Opcode::LoadInt, 0, 10, 0),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+            Instruction::new(Opcode::LoadInt, 0, 20, 0),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+            Instruction::new(Opcode::LoadInt, 0, 30, 0),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        // Indices past the table, including ones that wrap to small i32 values, miss
+        let cases = [(0, 10), (1, 20), (2, 10), (3, 30), (u64::MAX, 30), (1 << 32, 30), ((1 << 32) | 1, 30)];
+        for (index, want) in cases {
+            let mut args = [index];
+            let mut ret = [0u64; 1];
+            assert_eq!(run(&jit, 0, &mut args, &mut ret), JitResult::Ok);
+            assert_eq!(ret[0], want, "index {}", index);
+        }
+    }
+
+    /// A context that only supports the panic path and unmarked stores.
+    fn panic_ctx(gc: &mut vo_runtime::gc::Gc, panic_flag: &mut bool, panic_msg: &mut vo_runtime::InterfaceSlot) -> JitContext {
+        use std::ptr::{null, null_mut};
+        JitContext {
+            gc, globals: null_mut(), safepoint_flag: null(), panic_flag, panic_msg,
+            vm: null_mut(), fiber: null_mut(), call_vm_fn: None, itab_cache: null_mut(),
+            extern_registry: null(), call_extern_fn: None, module: null(),
+            jit_func_table: null(), jit_func_count: 0, program_args: null(),
+            sentinel_errors: null_mut(), itab_table: null(), jit_frames: null_mut(),
+            sorted_map_iter: false, gc_marking: &false, gc_step_fn: None, panic_pc: u32::MAX,
+            panic_kind: 0,
+        }
+    }
+
+    /// Element access past the end panics at the faulting pc instead of touching memory.
+    #[test]
+    fn test_index_out_of_range_panics() {
+        use vo_runtime::gc::Gc;
+        use vo_runtime::objects::{array, slice};
+        use vo_runtime::{InterfaceSlot, ValueKind, ValueMeta};
+
+        let mut gc = Gc::new();
+        let arr = array::create(&mut gc, ValueMeta::new(0, ValueKind::Int), 8, 3);
+        let s = slice::from_array_range(&mut gc, arr, 0, 3);
+
+        // r0 = container, r1 = index, r2 = result
+        let cases = [
+            (Instruction::with_flags(Opcode::SliceGet, 8, 2, 0, 1), s),
+            (Instruction::with_flags(Opcode::SliceSet, 8, 0, 1, 2), s),
+            (Instruction::with_flags(Opcode::SliceAddr, 8, 2, 0, 1), s),
+            (Instruction::with_flags(Opcode::SliceGet, 8, 2, 0, 1), std::ptr::null_mut()),
+            (Instruction::with_flags(Opcode::ArrayGet, 8, 2, 0, 1), arr),
+            (Instruction::with_flags(Opcode::ArraySet, 8, 0, 1, 2), arr),
+            (Instruction::with_flags(Opcode::ArrayAddr, 8, 2, 0, 1), arr),
+        ];
+        let mut module = VoModule::new("test".to_string());
+        for (inst, _) in &cases {
+            module.functions.push(make_func(2, 3, 1, vec![
+                Instruction::new(Opcode::LoadInt, 2, 0, 0),
+                *inst,
+                Instruction::new(Opcode::Return, 2, 0, 0),
+            ]));
+        }
+
+        let mut jit = JitCompiler::new().unwrap();
+        for (func_id, (inst, container)) in cases.iter().enumerate() {
+            let func_id = func_id as u32;
+            jit.compile(func_id, &module.functions[func_id as usize], &module).unwrap();
+            let func = unsafe { jit.get_func_ptr(func_id) }.unwrap();
+            let len = if container.is_null() { 0 } else { 3 };
+
+            for idx in [0, 2, 3, 100, u64::MAX] {
+                let (mut panic_flag, mut panic_msg) = (false, InterfaceSlot::default());
+                let mut ctx = panic_ctx(&mut gc, &mut panic_flag, &mut panic_msg);
+                let mut args = [*container as u64, idx];
+                let mut ret = [0u64; 1];
+                let result = func(&mut ctx, args.as_mut_ptr(), ret.as_mut_ptr());
+                if idx < len {
+                    assert_eq!(result, JitResult::Ok, "{:?} [{}]", inst.opcode(), idx);
+                } else {
+                    assert_eq!(result, JitResult::Panic, "{:?} [{}]", inst.opcode(), idx);
+                    assert_eq!(ctx.panic_pc, 1);
+                    assert_eq!(ctx.panic_kind, PanicKind::IndexOutOfBounds as u8);
+                }
+            }
+        }
+    }
+
+    /// Dereferencing a nil pointer or writing to a nil map panics instead of faulting.
+    #[test]
+    fn test_nil_deref_panics() {
+        use vo_runtime::bytecode::Constant;
+        use vo_runtime::gc::Gc;
+        use vo_runtime::InterfaceSlot;
+
+        // r0 = pointer or map (nil), r1 = MapSet meta (1 key slot, 1 val slot), r2 = key/value
+        let cases = [
+            Instruction::new(Opcode::PtrGet, 1, 0, 0),
+            Instruction::new(Opcode::PtrSet, 0, 0, 2),
+            Instruction::with_flags(Opcode::PtrGetN, 2, 1, 0, 0),
+            Instruction::with_flags(Opcode::PtrSetN, 2, 0, 0, 1),
+            Instruction::new(Opcode::MapSet, 0, 1, 2),
+        ];
+        let mut module = VoModule::new("test".to_string());
+        module.constants.push(Constant::Int(1 << 8 | 1));
+        for inst in &cases {
+            module.functions.push(make_func(1, 3, 0, vec![
+                Instruction::new(Opcode::LoadConst, 1, 0, 0),
+                *inst,
+                Instruction::new(Opcode::Return, 0, 0, 0),
+            ]));
+        }
+
+        let mut jit = JitCompiler::new().unwrap();
+        let mut gc = Gc::new();
+        for (func_id, inst) in cases.iter().enumerate() {
+            let func_id = func_id as u32;
+            jit.compile(func_id, &module.functions[func_id as usize], &module).unwrap();
+            let func = unsafe { jit.get_func_ptr(func_id) }.unwrap();
+
+            let (mut panic_flag, mut panic_msg) = (false, InterfaceSlot::default());
+            let mut ctx = panic_ctx(&mut gc, &mut panic_flag, &mut panic_msg);
+            let mut args = [0u64];
+            let result = func(&mut ctx, args.as_mut_ptr(), [].as_mut_ptr());
+            assert_eq!(result, JitResult::Panic, "{:?}", inst.opcode());
+            assert_eq!(ctx.panic_pc, 1);
+            let kind = if inst.opcode() == Opcode::MapSet { PanicKind::NilMapWrite } else { PanicKind::NilPointerDereference };
+            assert_eq!(ctx.panic_kind, kind as u8, "{:?}", inst.opcode());
+        }
+    }
+
+    #[test]
+    fn test_stats_and_ir_dump() {
+        let func = make_func(0, 1, 1, vec![
+            Instruction::new(Opcode::LoadInt, 0, 42, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func.clone());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.compile(1, &module.functions[1], &module).unwrap();
+        jit.set_capture_ir(true);
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        let stats = jit.stats();
+        assert_eq!(stats.iter().map(|s| s.func_id).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(stats.iter().all(|s| s.compiled && s.code_size > 0));
+
+        let ir = jit.dump_cranelift_ir(0).expect("IR not captured");
+        assert!(ir.contains("iconst.i64 42"), "unexpected IR:\n{}", ir);
+        assert!(jit.dump_cranelift_ir(1).is_none());
+
+        let listing = debug_listing("func_0 test", &module.functions[0], &ir);
+        assert!(listing.contains("0000: LoadInt a=0 b=42"), "unexpected listing:\n{}", listing);
+        assert!(listing.ends_with(&ir));
+    }
+
+    #[test]
+    fn test_str_const_in_data() {
+        use vo_runtime::bytecode::Constant;
+        use vo_runtime::gc::Gc;
+        use vo_runtime::objects::string;
+        use vo_runtime::InterfaceSlot;
+
+        let literal: String = (0..1024).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+        let func = make_func(0, 2, 2, vec![
+            Instruction::new(Opcode::StrNew, 0, 0, 0),
+            Instruction::new(Opcode::StrLen, 1, 0, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.constants.push(Constant::String(literal.clone()));
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        jit.set_capture_ir(true);
+        jit.compile(0, &module.functions[0], &module).unwrap();
+
+        let ir = jit.dump_cranelift_ir(0).expect("IR not captured");
+        assert!(ir.contains("global_value"), "unexpected IR:\n{}", ir);
+        assert!(!ir.contains("iconst.i8"), "unexpected IR:\n{}", ir);
+        assert!(ir.contains("iconst.i64 1024"), "unexpected IR:\n{}", ir);
+
+        // The string built from data has the literal's length and bytes
+        let mut gc = Gc::new();
+        let (mut panic_flag, mut panic_msg) = (false, InterfaceSlot::default());
+        let mut ctx = panic_ctx(&mut gc, &mut panic_flag, &mut panic_msg);
+        let func = unsafe { jit.get_func_ptr(0) }.unwrap();
+        let mut ret = [0u64; 2];
+        assert_eq!(func(&mut ctx, [].as_mut_ptr(), ret.as_mut_ptr()), JitResult::Ok);
+        assert_eq!(ret[1], 1024);
+        assert_eq!(string::as_str(ret[0] as vo_runtime::gc::GcRef), literal);
+    }
+
+    /// The same translation can target an object file instead of JIT memory.
+    #[test]
+    fn test_translate_into_object_module() {
+        use cranelift_object::{ObjectBuilder, ObjectModule};
+        use vo_runtime::bytecode::Constant;
+
+        let func = make_func(0, 1, 1, vec![
+            Instruction::new(Opcode::StrNew, 0, 0, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut vo_module = VoModule::new("test".to_string());
+        vo_module.constants.push(Constant::String("hello".into()));
+        vo_module.functions.push(func);
+
+        let isa = cranelift_native::builder().unwrap()
+            .finish(settings::Flags::new(settings::builder()))
+            .unwrap();
+        let builder = ObjectBuilder::new(isa, "vo_test", cranelift_module::default_libcall_names()).unwrap();
+        let mut module = ObjectModule::new(builder);
+        let mut decls = ModuleDecls::declare(&mut module).unwrap();
+
+        let ptr_type = module.target_config().pointer_type();
+        let mut ctx = module.make_context();
+        ctx.func.signature.params.extend([AbiParam::new(ptr_type); 3]);
+        ctx.func.signature.returns.push(AbiParam::new(types::I32));
+        let id = module.declare_function("vo_func_0", cranelift_module::Linkage::Export, &ctx.func.signature).unwrap();
+
+        let def = &vo_module.functions[0];
+        let helpers = decls.import(&mut module, &mut ctx.func, def, &vo_module).unwrap();
+        let mut func_ctx = FunctionBuilderContext::new();
+        FunctionCompiler::new(&mut ctx.func, &mut func_ctx, 0, def, &vo_module, helpers, false)
+            .compile()
+            .unwrap();
+        module.define_function(id, &mut ctx).unwrap();
+
+        let bytes = module.finish().emit().unwrap();
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"vo_func_0"));
+        assert!(contains(b"vo_str_new"));
+        assert!(contains(b"hello"));
+    }
+
+    #[test]
+    fn test_not_jittable() {
+        let func = make_func(0, 1, 0, vec![
+            Instruction::new(Opcode::Recover, 0, 0, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]);
+        let mut module = VoModule::new("test".to_string());
+        module.functions.push(func);
+
+        let mut jit = JitCompiler::new().unwrap();
+        assert!(matches!(jit.compile(0, &module.functions[0], &module), Err(JitError::NotJittable(0))));
+        assert!(!jit.is_compiled(0));
+    }
+}
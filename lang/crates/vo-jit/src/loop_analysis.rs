@@ -221,11 +221,11 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
     
     match inst.opcode() {
         // Instructions that read from b and/or c
-        Opcode::Copy | Opcode::Not | Opcode::NegI | Opcode::NegF => {
+        Opcode::Copy | Opcode::Not | Opcode::NegI | Opcode::NegF | Opcode::SqrtF | Opcode::AbsF => {
             regs.push(inst.b);
         }
         Opcode::AddI | Opcode::SubI | Opcode::MulI | Opcode::DivI | Opcode::DivU | Opcode::ModI | Opcode::ModU |
-        Opcode::AddF | Opcode::SubF | Opcode::MulF | Opcode::DivF |
+        Opcode::AddF | Opcode::SubF | Opcode::MulF | Opcode::DivF | Opcode::MinF | Opcode::MaxF |
         Opcode::And | Opcode::Or | Opcode::Xor | Opcode::Shl | Opcode::ShrS | Opcode::ShrU |
         Opcode::EqI | Opcode::NeI | Opcode::LtI | Opcode::LeI | Opcode::GtI | Opcode::GeI |
         Opcode::LtU | Opcode::LeU | Opcode::GtU | Opcode::GeU |
@@ -233,8 +233,8 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
             regs.push(inst.b);
             regs.push(inst.c);
         }
-        // Conditional jumps read the condition register
-        Opcode::JumpIf | Opcode::JumpIfNot => {
+        // Conditional jumps read the condition register, JumpTable its index
+        Opcode::JumpIf | Opcode::JumpIfNot | Opcode::JumpTable => {
             regs.push(inst.a);
         }
         // Return reads return value registers
@@ -304,9 +304,9 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
                 regs.push(inst.c + i);
             }
         }
-        // Call reads arguments
+        // Call reads arguments, which follow the return slots at b
         Opcode::Call => {
-            let arg_start = inst.b;
+            let arg_start = inst.b + (inst.c & 0xFF);
             let arg_slots = (inst.c >> 8) as u16;
             for i in 0..arg_slots {
                 regs.push(arg_start + i);
@@ -315,7 +315,7 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
         // CallClosure reads closure ref and arguments
         Opcode::CallClosure => {
             regs.push(inst.a);  // closure ref
-            let arg_start = inst.b;
+            let arg_start = inst.b + (inst.c & 0xFF);
             let arg_slots = (inst.c >> 8) as u16;
             for i in 0..arg_slots {
                 regs.push(arg_start + i);
@@ -330,10 +330,10 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
             }
         }
         Opcode::CallIface => {
-            // CallIface: a=iface_slot (2 slots), b=args_start
+            // CallIface: a=iface_slot (2 slots), b=buffer (returns, then args)
             regs.push(inst.a);
             regs.push(inst.a + 1);
-            let arg_start = inst.b;
+            let arg_start = inst.b + (inst.c & 0xFF);
             let arg_slots = (inst.c >> 8) as u16;
             for i in 0..arg_slots {
                 regs.push(arg_start + i);
@@ -350,9 +350,9 @@ fn get_read_regs(inst: &Instruction) -> Vec<u16> {
 fn get_write_reg(inst: &Instruction) -> Option<u16> {
     match inst.opcode() {
         // Most arithmetic/logic instructions write to a
-        Opcode::Copy | Opcode::Not | Opcode::NegI | Opcode::NegF |
+        Opcode::Copy | Opcode::Not | Opcode::NegI | Opcode::NegF | Opcode::SqrtF | Opcode::AbsF |
         Opcode::AddI | Opcode::SubI | Opcode::MulI | Opcode::DivI | Opcode::DivU | Opcode::ModI | Opcode::ModU |
-        Opcode::AddF | Opcode::SubF | Opcode::MulF | Opcode::DivF |
+        Opcode::AddF | Opcode::SubF | Opcode::MulF | Opcode::DivF | Opcode::MinF | Opcode::MaxF |
         Opcode::And | Opcode::Or | Opcode::Xor | Opcode::Shl | Opcode::ShrS | Opcode::ShrU |
         Opcode::EqI | Opcode::NeI | Opcode::LtI | Opcode::LeI | Opcode::GtI | Opcode::GeI |
         Opcode::LtU | Opcode::LeU | Opcode::GtU | Opcode::GeU |
@@ -371,13 +371,13 @@ fn get_write_regs_multi(inst: &Instruction) -> Vec<u16> {
     
     match inst.opcode() {
         Opcode::Call => {
-            let ret_start = inst.b;  // Return values start at arg_start
+            let ret_start = inst.b;  // Return values start the call buffer
             let ret_slots = (inst.c & 0xFF) as u16;
             for i in 0..ret_slots {
                 regs.push(ret_start + i);
             }
         }
-        // CallClosure: ret_slots in low byte of c, returns start at arg_start (b)
+        // CallClosure: ret_slots in low byte of c, returns start the buffer (b)
         Opcode::CallClosure => {
             let ret_start = inst.b;
             let ret_slots = (inst.c & 0xFF) as u16;
@@ -392,7 +392,7 @@ fn get_write_regs_multi(inst: &Instruction) -> Vec<u16> {
             regs.push(inst.a);
         }
         Opcode::CallIface => {
-            // CallIface: ret_slots in low byte of c, args start at b
+            // CallIface: ret_slots in low byte of c, returns start at b
             let ret_start = inst.b;
             let ret_slots = (inst.c & 0xFF) as u16;
             for i in 0..ret_slots {
@@ -456,6 +456,8 @@ mod tests {
             error_ret_slot: -1,
             code,
             slot_types: vec![],
+            capture_types: vec![],
+            param_types: vec![],
         }
     }
     
@@ -621,9 +623,9 @@ mod tests {
         Instruction { op: Opcode::CallExtern as u8, flags: arg_count, a: dst, b: extern_id, c: arg_start }
     }
 
-    fn call_iface(iface_slot: u16, arg_start: u16, arg_slots: u8, ret_slots: u8, method_idx: u8) -> Instruction {
+    fn call_iface(iface_slot: u16, buf: u16, arg_slots: u8, ret_slots: u8, method_idx: u8) -> Instruction {
         let c = ((arg_slots as u16) << 8) | (ret_slots as u16);
-        Instruction { op: Opcode::CallIface as u8, flags: method_idx, a: iface_slot, b: arg_start, c }
+        Instruction { op: Opcode::CallIface as u8, flags: method_idx, a: iface_slot, b: buf, c }
     }
 
     #[test]
@@ -670,10 +672,10 @@ mod tests {
 
     #[test]
     fn test_get_read_regs_call_iface() {
-        // CallIface: a=iface_slot (2 slots), b=arg_start, c=(arg_slots<<8|ret_slots)
+        // CallIface: a=iface_slot (2 slots), b=buffer, c=(arg_slots<<8|ret_slots)
         let inst = call_iface(5, 10, 2, 1, 0);
         let regs = get_read_regs(&inst);
-        assert_eq!(regs, vec![5, 6, 10, 11], "CallIface should read iface (2 slots) + args");
+        assert_eq!(regs, vec![5, 6, 11, 12], "CallIface should read iface (2 slots) + args after the return slot");
     }
 
     #[test]
@@ -713,9 +715,9 @@ mod tests {
 
     #[test]
     fn test_get_write_regs_multi_call_iface() {
-        // CallIface: ret_slots in low byte of c, returns start at arg_start (b)
+        // CallIface: ret_slots in low byte of c, returns start the buffer (b)
         let inst = call_iface(5, 10, 2, 3, 0);
         let regs = get_write_regs_multi(&inst);
-        assert_eq!(regs, vec![10, 11, 12], "CallIface should write ret_slots to arg_start");
+        assert_eq!(regs, vec![10, 11, 12], "CallIface should write ret_slots to the buffer start");
     }
 }
@@ -7,9 +7,10 @@ use cranelift_codegen::ir::condcodes::IntCC;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
 
 use vo_runtime::bytecode::{FunctionDef, Module as VoModule};
+use vo_runtime::jit_api::JitResult;
 use vo_runtime::instruction::{Instruction, Opcode};
 use crate::loop_analysis::LoopInfo;
-use crate::translate::translate_inst;
+use crate::translate::{emit_call_iface, emit_jump_table, emit_record_panic_pc, translate_inst};
 use crate::translator::{HelperFuncs, IrEmitter, TranslateResult};
 use crate::JitError;
 
@@ -27,6 +28,7 @@ unsafe impl Sync for CompiledLoop {}
 
 pub struct LoopCompiler<'a> {
     builder: FunctionBuilder<'a>,
+    func_id: u32,
     func_def: &'a FunctionDef,
     vo_module: &'a VoModule,
     loop_info: &'a LoopInfo,
@@ -39,16 +41,19 @@ pub struct LoopCompiler<'a> {
     ctx_ptr: Value,
     helpers: HelperFuncs,
     reg_consts: HashMap<u16, i64>,
+    checked_arith: bool,
 }
 
 impl<'a> LoopCompiler<'a> {
     pub fn new(
         func: &'a mut Function,
         func_ctx: &'a mut FunctionBuilderContext,
+        func_id: u32,
         func_def: &'a FunctionDef,
         vo_module: &'a VoModule,
         loop_info: &'a LoopInfo,
         helpers: HelperFuncs,
+        checked_arith: bool,
     ) -> Self {
         let mut builder = FunctionBuilder::new(func, func_ctx);
         let entry_block = builder.create_block();
@@ -57,6 +62,7 @@ impl<'a> LoopCompiler<'a> {
         
         Self {
             builder,
+            func_id,
             func_def,
             vo_module,
             loop_info,
@@ -69,6 +75,7 @@ impl<'a> LoopCompiler<'a> {
             ctx_ptr: Value::from_u32(0),
             helpers,
             reg_consts: HashMap::new(),
+            checked_arith,
         }
     }
 
@@ -153,6 +160,11 @@ impl<'a> LoopCompiler<'a> {
                         self.ensure_block(target);
                     }
                 }
+                Opcode::JumpTable => {
+                    for target in (pc + 1..=pc + 1 + inst.b as usize).filter(|&t| t < loop_end) {
+                        self.ensure_block(target);
+                    }
+                }
                 _ => {}
             }
         }
@@ -201,6 +213,10 @@ impl<'a> LoopCompiler<'a> {
             Opcode::Jump => { self.jump(inst); Ok(true) }
             Opcode::JumpIf => { self.jump_if(inst); Ok(false) }
             Opcode::JumpIfNot => { self.jump_if_not(inst); Ok(false) }
+            Opcode::JumpTable if self.current_pc + 1 + (inst.b as usize) < self.loop_info.end_pc + 2 => {
+                self.jump_table(inst);
+                Ok(true)
+            }
             Opcode::Return => { self.ret(inst); Ok(true) }
             Opcode::Panic => { self.panic(inst); Ok(true) }
             Opcode::Call => { self.call(inst); Ok(false) }
@@ -238,6 +254,14 @@ impl<'a> LoopCompiler<'a> {
         }
     }
 
+    /// Only called when the whole table lies inside the loop body.
+    fn jump_table(&mut self, inst: &Instruction) {
+        let first = self.current_pc + 1;
+        let entries: Vec<Block> = (first..first + inst.b as usize).map(|pc| self.blocks[&pc]).collect();
+        let miss = self.blocks[&(first + inst.b as usize)];
+        emit_jump_table(self, inst, &entries, miss);
+    }
+
     fn jump_if(&mut self, inst: &Instruction) {
         let cond = self.read_var(inst.a);
         let offset = inst.imm32();
@@ -302,6 +326,7 @@ impl<'a> LoopCompiler<'a> {
     }
 
     fn panic(&mut self, inst: &Instruction) {
+        emit_record_panic_pc(self);
         if let Some(panic_func) = self.helpers.panic {
             let ctx = self.ctx_ptr;
             // Panic message is an interface (2 slots): slot0=metadata, slot1=data
@@ -323,9 +348,10 @@ impl<'a> LoopCompiler<'a> {
         self.do_emit_safepoint();
         
         let func_id = (inst.a as u32) | ((inst.flags as u32) << 16);
-        let arg_start = inst.b as usize;
+        let buf = inst.b as usize;
         let arg_slots = (inst.c >> 8) as usize;
         let ret_slots = (inst.c & 0xFF) as usize;
+        let arg_start = buf + ret_slots;
         
         let arg_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
             cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
@@ -357,11 +383,15 @@ impl<'a> LoopCompiler<'a> {
         
         for i in 0..ret_slots {
             let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-            self.write_var((arg_start + i) as u16, val);
+            self.write_var((buf + i) as u16, val);
         }
     }
 
     fn call_extern(&mut self, inst: &Instruction) {
+        if self.helpers.slice_copy.is_some() && self.vo_module.externs[inst.b as usize].name == "vo_copy" {
+            crate::translate::slice_copy(self, inst);
+            return;
+        }
         let call_extern_func = match self.helpers.call_extern {
             Some(f) => f,
             None => return,
@@ -398,10 +428,26 @@ impl<'a> LoopCompiler<'a> {
         let arg_count_val = self.builder.ins().iconst(types::I32, arg_count as i64);
         let ret_slots_val = self.builder.ins().iconst(types::I32, extern_ret_slots as i64);
         
+        // The loop can always exit at this pc
+        let can_park_val = self.builder.ins().iconst(types::I32, 1);
+        
         let ctx = self.ctx_ptr;
-        let call = self.builder.ins().call(call_extern_func, &[ctx, extern_id_val, args_ptr, arg_count_val, args_ptr, ret_slots_val]);
+        let call = self.builder.ins().call(call_extern_func, &[ctx, extern_id_val, args_ptr, arg_count_val, args_ptr, ret_slots_val, can_park_val]);
         let result = self.builder.inst_results(call)[0];
         
+        // Suspended extern: exit at the call so the VM re-enters it and parks the fiber
+        let park_block = self.builder.create_block();
+        let check_block = self.builder.create_block();
+        let deopt = self.builder.ins().iconst(types::I32, JitResult::Deopt as i64);
+        let parked = self.builder.ins().icmp(IntCC::Equal, result, deopt);
+        self.builder.ins().brif(parked, park_block, &[], check_block, &[]);
+        
+        self.builder.switch_to_block(park_block);
+        self.builder.seal_block(park_block);
+        self.emit_deopt_exit();
+        
+        self.builder.switch_to_block(check_block);
+        self.builder.seal_block(check_block);
         self.check_call_result(result);
         
         for i in 0..copy_back_slots {
@@ -419,9 +465,10 @@ impl<'a> LoopCompiler<'a> {
         self.do_emit_safepoint();
         
         let closure_ref = self.read_var(inst.a);
-        let arg_start = inst.b as usize;
+        let buf = inst.b as usize;
         let arg_slots = (inst.c >> 8) as usize;
         let ret_slots = (inst.c & 0xFF) as usize;
+        let arg_start = buf + ret_slots;
         
         let arg_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
             cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
@@ -452,62 +499,28 @@ impl<'a> LoopCompiler<'a> {
         
         let local_count = self.func_def.local_slots as usize;
         for i in 0..ret_slots {
-            if arg_start + i < local_count {
+            if buf + i < local_count {
                 let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-                self.write_var((arg_start + i) as u16, val);
+                self.write_var((buf + i) as u16, val);
             }
         }
     }
 
     fn call_iface(&mut self, inst: &Instruction) {
-        let call_iface_func = match self.helpers.call_iface {
-            Some(f) => f,
-            None => return,
-        };
-        
         self.do_emit_safepoint();
         
-        let slot0 = self.read_var(inst.a);
-        let slot1 = self.read_var(inst.a + 1);
-        let method_idx = inst.flags as u32;
-        let arg_start = inst.b as usize;
-        let arg_slots = (inst.c >> 8) as usize;
-        let ret_slots = (inst.c & 0xFF) as usize;
-        
-        let arg_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
-            cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
-            (arg_slots.max(1) * 8) as u32,
-            8,
-        ));
-        let ret_slot = self.builder.create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
-            cranelift_codegen::ir::StackSlotKind::ExplicitSlot,
-            (ret_slots.max(1) * 8) as u32,
-            8,
-        ));
-        
-        for i in 0..arg_slots {
-            let val = self.read_var((arg_start + i) as u16);
-            self.builder.ins().stack_store(val, arg_slot, (i * 8) as i32);
-        }
-        
-        let args_ptr = self.builder.ins().stack_addr(types::I64, arg_slot, 0);
-        let ret_ptr = self.builder.ins().stack_addr(types::I64, ret_slot, 0);
-        let method_idx_val = self.builder.ins().iconst(types::I32, method_idx as i64);
-        let arg_count = self.builder.ins().iconst(types::I32, arg_slots as i64);
-        let ret_count = self.builder.ins().iconst(types::I32, ret_slots as i64);
-        let func_id = self.builder.ins().iconst(types::I32, 0);
-        
-        let ctx = self.ctx_ptr;
-        let call = self.builder.ins().call(call_iface_func, &[
-            ctx, slot0, slot1, method_idx_val, args_ptr, arg_count, ret_ptr, ret_count, func_id
-        ]);
-        let result = self.builder.inst_results(call)[0];
+        let (result, ret_slot) = match emit_call_iface(self, inst) {
+            Some(r) => r,
+            None => return,
+        };
         
         self.check_call_result(result);
         
+        let buf = inst.b as usize;
+        let ret_slots = (inst.c & 0xFF) as usize;
         for i in 0..ret_slots {
             let val = self.builder.ins().stack_load(types::I64, ret_slot, (i * 8) as i32);
-            self.write_var((arg_start + i) as u16, val);
+            self.write_var((buf + i) as u16, val);
         }
     }
 
@@ -546,7 +559,9 @@ impl<'a> LoopCompiler<'a> {
         self.builder.switch_to_block(call_block);
         self.builder.seal_block(call_block);
         let ctx_for_call = self.ctx_ptr;
-        self.builder.ins().call(safepoint_func, &[ctx_for_call]);
+        let func_id = self.builder.ins().iconst(types::I32, self.func_id as i64);
+        let pc = self.builder.ins().iconst(types::I32, self.current_pc as i64);
+        self.builder.ins().call(safepoint_func, &[ctx_for_call, func_id, pc]);
         self.builder.ins().jump(merge_block, &[]);
         
         self.builder.switch_to_block(merge_block);
@@ -557,7 +572,12 @@ impl<'a> LoopCompiler<'a> {
 impl<'a> IrEmitter<'a> for LoopCompiler<'a> {
     fn builder(&mut self) -> &mut FunctionBuilder<'a> { &mut self.builder }
     fn read_var(&mut self, slot: u16) -> Value { self.builder.use_var(self.vars[slot as usize]) }
-    fn write_var(&mut self, slot: u16, val: Value) { self.builder.def_var(self.vars[slot as usize], val) }
+    fn write_var(&mut self, slot: u16, val: Value) {
+        self.builder.def_var(self.vars[slot as usize], val);
+        // Mirror into the fiber stack: GC scans references there, and helpers
+        // given a `var_addr` (SliceAppend's element, stack arrays) read it
+        self.builder.ins().store(MemFlags::trusted(), val, self.locals_ptr, (slot as i32) * 8);
+    }
     fn ctx_param(&mut self) -> Value { self.ctx_ptr }
     fn gc_ptr(&mut self) -> Value {
         self.builder.ins().load(types::I64, MemFlags::trusted(), self.ctx_ptr, 0)
@@ -576,4 +596,12 @@ impl<'a> IrEmitter<'a> for LoopCompiler<'a> {
         let offset = (slot as i64) * 8;
         self.builder.ins().iadd_imm(self.locals_ptr, offset)
     }
+    // Exiting at the current pc lets the VM carry on exactly where the loop stopped.
+    fn can_deopt(&self) -> bool { true }
+    fn checked_arith(&self) -> bool { self.checked_arith }
+    fn emit_deopt_exit(&mut self) {
+        self.store_vars_to_memory();
+        let ret_pc = self.builder.ins().iconst(types::I32, self.current_pc as i64);
+        self.builder.ins().return_(&[ret_pc]);
+    }
 }
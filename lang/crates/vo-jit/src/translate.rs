@@ -1,10 +1,13 @@
 //! Shared instruction translation logic.
 
-use cranelift_codegen::ir::{types, InstBuilder, MemFlags, Value};
+use cranelift_codegen::ir::{types, Block, InstBuilder, JumpTableData, MemFlags, StackSlot, StackSlotData, StackSlotKind, Value};
 use cranelift_codegen::ir::condcodes::{IntCC, FloatCC};
 
 use vo_runtime::bytecode::Constant;
-use vo_runtime::instruction::{Instruction, Opcode};
+use vo_runtime::instruction::{Instruction, Opcode, ARITH_FLAG_UNSIGNED, ARITH_FLAG_WIDTH_SHIFT};
+use vo_runtime::itab::ItabTable;
+use vo_runtime::jit_api::JitContext;
+use vo_runtime::PanicKind;
 
 use crate::translator::{IrEmitter, TranslateResult};
 use crate::JitError;
@@ -33,6 +36,10 @@ pub fn translate_inst<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) -> Res
         MulF => { mul_f(e, inst); Ok(Completed) }
         DivF => { div_f(e, inst); Ok(Completed) }
         NegF => { neg_f(e, inst); Ok(Completed) }
+        SqrtF => { sqrt_f(e, inst); Ok(Completed) }
+        AbsF => { abs_f(e, inst); Ok(Completed) }
+        MinF => { min_f(e, inst); Ok(Completed) }
+        MaxF => { max_f(e, inst); Ok(Completed) }
         EqI => { cmp_i(e, inst, IntCC::Equal); Ok(Completed) }
         NeI => { cmp_i(e, inst, IntCC::NotEqual); Ok(Completed) }
         LtI => { cmp_i(e, inst, IntCC::SignedLessThan); Ok(Completed) }
@@ -103,6 +110,8 @@ pub fn translate_inst<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) -> Res
         StrGt => { str_cmp(e, inst, IntCC::SignedGreaterThan); Ok(Completed) }
         StrGe => { str_cmp(e, inst, IntCC::SignedGreaterThanOrEqual); Ok(Completed) }
         StrDecodeRune => { str_decode_rune(e, inst); Ok(Completed) }
+        StrContains => { str_contains(e, inst); Ok(Completed) }
+        StrIndexOf => { str_index_of(e, inst); Ok(Completed) }
         // Map operations
         MapNew => { map_new(e, inst); Ok(Completed) }
         MapLen => { map_len(e, inst); Ok(Completed) }
@@ -125,7 +134,7 @@ pub fn translate_inst<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) -> Res
         IfaceAssign => { iface_assign(e, inst); Ok(Completed) }
         IfaceEq => { iface_eq(e, inst); Ok(Completed) }
         // Control flow - compiler specific
-        Jump | JumpIf | JumpIfNot | Return | Panic => Ok(Unhandled),
+        Jump | JumpIf | JumpIfNot | JumpTable | Return | Panic => Ok(Unhandled),
         // Function calls - compiler specific
         Call | CallExtern | CallClosure | CallIface => Ok(Unhandled),
         // Unsupported
@@ -166,21 +175,68 @@ fn copy_n<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     }
 }
 
+/// Whether checked arithmetic applies to this AddI/SubI/MulI. Unsigned
+/// operations wrap by definition and are never checked.
+fn checks_overflow<'a>(e: &impl IrEmitter<'a>, inst: &Instruction) -> bool {
+    e.checked_arith() && inst.flags & ARITH_FLAG_UNSIGNED == 0
+}
+
+/// The operand type of a signed AddI/SubI/MulI narrower than 64 bits.
+fn narrow_type(inst: &Instruction) -> Option<types::Type> {
+    match inst.flags >> ARITH_FLAG_WIDTH_SHIFT {
+        1 => Some(types::I8),
+        2 => Some(types::I16),
+        4 => Some(types::I32),
+        _ => None,
+    }
+}
+
+/// Under checked arithmetic, panic if `r` does not fit the narrow operand
+/// type. Narrow operands are sign-extended, so `r` is exact in 64 bits.
+fn check_narrow_overflow<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction, r: Value) {
+    let Some(ty) = narrow_type(inst).filter(|_| checks_overflow(e, inst)) else { return };
+    let narrowed = e.builder().ins().ireduce(ty, r);
+    let widened = e.builder().ins().sextend(types::I64, narrowed);
+    let overflow = e.builder().ins().icmp(IntCC::NotEqual, widened, r);
+    emit_panic_if(e, overflow, Some(PanicKind::IntegerOverflow));
+}
+
 fn add_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let a = e.read_var(inst.b); let b = e.read_var(inst.c);
-    let r = e.builder().ins().iadd(a, b);
+    let r = if checks_overflow(e, inst) && narrow_type(inst).is_none() {
+        let (r, overflow) = e.builder().ins().sadd_overflow(a, b);
+        emit_panic_if(e, overflow, Some(PanicKind::IntegerOverflow));
+        r
+    } else {
+        e.builder().ins().iadd(a, b)
+    };
+    check_narrow_overflow(e, inst, r);
     e.write_var(inst.a, r);
 }
 
 fn sub_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let a = e.read_var(inst.b); let b = e.read_var(inst.c);
-    let r = e.builder().ins().isub(a, b);
+    let r = if checks_overflow(e, inst) && narrow_type(inst).is_none() {
+        let (r, overflow) = e.builder().ins().ssub_overflow(a, b);
+        emit_panic_if(e, overflow, Some(PanicKind::IntegerOverflow));
+        r
+    } else {
+        e.builder().ins().isub(a, b)
+    };
+    check_narrow_overflow(e, inst, r);
     e.write_var(inst.a, r);
 }
 
 fn mul_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let a = e.read_var(inst.b); let b = e.read_var(inst.c);
-    let r = e.builder().ins().imul(a, b);
+    let r = if checks_overflow(e, inst) && narrow_type(inst).is_none() {
+        let (r, overflow) = e.builder().ins().smul_overflow(a, b);
+        emit_panic_if(e, overflow, Some(PanicKind::IntegerOverflow));
+        r
+    } else {
+        e.builder().ins().imul(a, b)
+    };
+    check_narrow_overflow(e, inst, r);
     e.write_var(inst.a, r);
 }
 
@@ -189,7 +245,7 @@ fn div_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check for division by zero
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_zero = e.builder().ins().icmp(IntCC::Equal, b, zero);
-    emit_panic_if(e, is_zero, true);
+    emit_panic_if(e, is_zero, Some(PanicKind::DivisionByZero));
     // Handle MIN_INT64 / -1 overflow: result would be MAX_INT64+1, which overflows.
     // x86 idiv traps on this. Go semantics: result wraps to MIN_INT64.
     // Replace b with 1 when overflow would occur to avoid the trap.
@@ -210,7 +266,7 @@ fn mod_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check for division by zero
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_zero = e.builder().ins().icmp(IntCC::Equal, b, zero);
-    emit_panic_if(e, is_zero, true);
+    emit_panic_if(e, is_zero, Some(PanicKind::DivisionByZero));
     let r = e.builder().ins().srem(a, b);
     e.write_var(inst.a, r);
 }
@@ -220,7 +276,7 @@ fn div_u<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check for division by zero
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_zero = e.builder().ins().icmp(IntCC::Equal, b, zero);
-    emit_panic_if(e, is_zero, true);
+    emit_panic_if(e, is_zero, Some(PanicKind::DivisionByZero));
     let r = e.builder().ins().udiv(a, b);
     e.write_var(inst.a, r);
 }
@@ -230,7 +286,7 @@ fn mod_u<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check for division by zero
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_zero = e.builder().ins().icmp(IntCC::Equal, b, zero);
-    emit_panic_if(e, is_zero, true);
+    emit_panic_if(e, is_zero, Some(PanicKind::DivisionByZero));
     let r = e.builder().ins().urem(a, b);
     e.write_var(inst.a, r);
 }
@@ -285,6 +341,40 @@ fn neg_f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     e.write_var(inst.a, r);
 }
 
+fn sqrt_f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let a = e.read_var(inst.b);
+    let fa = e.builder().ins().bitcast(types::F64, MemFlags::new(), a);
+    let fr = e.builder().ins().sqrt(fa);
+    let r = e.builder().ins().bitcast(types::I64, MemFlags::new(), fr);
+    e.write_var(inst.a, r);
+}
+
+fn abs_f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let a = e.read_var(inst.b);
+    let fa = e.builder().ins().bitcast(types::F64, MemFlags::new(), a);
+    let fr = e.builder().ins().fabs(fa);
+    let r = e.builder().ins().bitcast(types::I64, MemFlags::new(), fr);
+    e.write_var(inst.a, r);
+}
+
+fn min_f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let a = e.read_var(inst.b); let b = e.read_var(inst.c);
+    let fa = e.builder().ins().bitcast(types::F64, MemFlags::new(), a);
+    let fb = e.builder().ins().bitcast(types::F64, MemFlags::new(), b);
+    let fr = e.builder().ins().fmin(fa, fb);
+    let r = e.builder().ins().bitcast(types::I64, MemFlags::new(), fr);
+    e.write_var(inst.a, r);
+}
+
+fn max_f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let a = e.read_var(inst.b); let b = e.read_var(inst.c);
+    let fa = e.builder().ins().bitcast(types::F64, MemFlags::new(), a);
+    let fb = e.builder().ins().bitcast(types::F64, MemFlags::new(), b);
+    let fr = e.builder().ins().fmax(fa, fb);
+    let r = e.builder().ins().bitcast(types::I64, MemFlags::new(), fr);
+    e.write_var(inst.a, r);
+}
+
 fn cmp_i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction, cc: IntCC) {
     let a = e.read_var(inst.b); let b = e.read_var(inst.c);
     let cmp = e.builder().ins().icmp(cc, a, b);
@@ -342,7 +432,7 @@ fn and_not<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
 fn shift_precheck<'a>(e: &mut impl IrEmitter<'a>, shift_amt: Value) -> (Value, Value) {
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_negative = e.builder().ins().icmp(IntCC::SignedLessThan, shift_amt, zero);
-    emit_panic_if(e, is_negative, true);
+    emit_panic_if(e, is_negative, Some(PanicKind::NegativeShift));
     let sixty_four = e.builder().ins().iconst(types::I64, 64);
     let is_large = e.builder().ins().icmp(IntCC::SignedGreaterThanOrEqual, shift_amt, sixty_four);
     (zero, is_large)
@@ -407,24 +497,116 @@ fn global_set_n<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     }
 }
 
-/// Emit conditional panic: if `condition` is true, return panic; otherwise continue.
-/// Optionally calls vo_panic to set panic_flag for defer/recover support.
-/// For runtime panics (nil pointer, bounds check), msg slots are 0 - VM will use default message.
-fn emit_panic_if<'a>(e: &mut impl IrEmitter<'a>, condition: Value, call_vo_panic: bool) {
+/// Emit an interface method call (CallIface).
+///
+/// The concrete func_id is loaded from the flat itab table in `JitContext`
+/// and called through vo_call_vm; only itabs outside the table fall back to
+/// vo_call_iface. Returns the call's JitResult and the slot holding its
+/// return values, or None if the call helpers are unavailable.
+pub fn emit_call_iface<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) -> Option<(Value, StackSlot)> {
+    let (call_vm_func, call_iface_func) = match (e.helpers().call_vm, e.helpers().call_iface) {
+        (Some(vm), Some(iface)) => (vm, iface),
+        _ => return None,
+    };
+    
+    let slot0 = e.read_var(inst.a);
+    let slot1 = e.read_var(inst.a + 1);
+    let method_idx = inst.flags as i64;
+    let arg_slots = (inst.c >> 8) as usize;
+    let ret_slots = (inst.c & 0xFF) as usize;
+    let arg_start = inst.b as usize + ret_slots;
+    
+    // Receiver goes first so the buffer is the callee's full argument list.
+    let arg_slot = e.builder().create_sized_stack_slot(StackSlotData::new(
+        StackSlotKind::ExplicitSlot,
+        ((arg_slots + 1) * 8) as u32,
+        8,
+    ));
+    let ret_slot = e.builder().create_sized_stack_slot(StackSlotData::new(
+        StackSlotKind::ExplicitSlot,
+        (ret_slots.max(1) * 8) as u32,
+        8,
+    ));
+    e.builder().ins().stack_store(slot1, arg_slot, 0);
+    for i in 0..arg_slots {
+        let val = e.read_var((arg_start + i) as u16);
+        e.builder().ins().stack_store(val, arg_slot, ((i + 1) * 8) as i32);
+    }
+    
+    let ctx = e.ctx_param();
+    let args_ptr = e.builder().ins().stack_addr(types::I64, arg_slot, 0);
+    let ret_ptr = e.builder().ins().stack_addr(types::I64, ret_slot, 0);
+    let ret_count = e.builder().ins().iconst(types::I32, ret_slots as i64);
+    
+    let table = e.builder().ins().load(
+        types::I64, MemFlags::trusted(), ctx, core::mem::offset_of!(JitContext, itab_table) as i32,
+    );
+    let table_len = e.builder().ins().load(
+        types::I32, MemFlags::trusted(), table, core::mem::offset_of!(ItabTable, len) as i32,
+    );
+    let itab_id = e.builder().ins().ushr_imm(slot0, 32);
+    let itab_id_i32 = e.builder().ins().ireduce(types::I32, itab_id);
+    let in_table = e.builder().ins().icmp(IntCC::UnsignedLessThan, itab_id_i32, table_len);
+    
+    let resolved_block = e.builder().create_block();
+    let unresolved_block = e.builder().create_block();
+    let merge_block = e.builder().create_block();
+    e.builder().append_block_param(merge_block, types::I32); // JitResult
+    e.builder().ins().brif(in_table, resolved_block, &[], unresolved_block, &[]);
+    
+    // func_id = methods[offsets[itab_id] + method_idx]
+    e.builder().switch_to_block(resolved_block);
+    e.builder().seal_block(resolved_block);
+    let offsets = e.builder().ins().load(
+        types::I64, MemFlags::trusted(), table, core::mem::offset_of!(ItabTable, offsets) as i32,
+    );
+    let offset_addr = e.builder().ins().ishl_imm(itab_id, 2);
+    let offset_addr = e.builder().ins().iadd(offsets, offset_addr);
+    let method_pos = e.builder().ins().uload32(MemFlags::trusted(), offset_addr, 0);
+    let method_pos = e.builder().ins().iadd_imm(method_pos, method_idx);
+    let methods = e.builder().ins().load(
+        types::I64, MemFlags::trusted(), table, core::mem::offset_of!(ItabTable, methods) as i32,
+    );
+    let method_addr = e.builder().ins().ishl_imm(method_pos, 2);
+    let method_addr = e.builder().ins().iadd(methods, method_addr);
+    let func_id = e.builder().ins().load(types::I32, MemFlags::trusted(), method_addr, 0);
+    let full_arg_count = e.builder().ins().iconst(types::I32, (arg_slots + 1) as i64);
+    let call = e.builder().ins().call(call_vm_func, &[ctx, func_id, args_ptr, full_arg_count, ret_ptr, ret_count]);
+    let result = e.builder().inst_results(call)[0];
+    e.builder().ins().jump(merge_block, &[result]);
+    
+    e.builder().switch_to_block(unresolved_block);
+    e.builder().seal_block(unresolved_block);
+    let method_idx_val = e.builder().ins().iconst(types::I32, method_idx);
+    let rest_args_ptr = e.builder().ins().iadd_imm(args_ptr, 8);
+    let arg_count = e.builder().ins().iconst(types::I32, arg_slots as i64);
+    let func_id_hint = e.builder().ins().iconst(types::I32, 0);
+    let call = e.builder().ins().call(call_iface_func, &[
+        ctx, slot0, slot1, method_idx_val, rest_args_ptr, arg_count, ret_ptr, ret_count, func_id_hint
+    ]);
+    let result = e.builder().inst_results(call)[0];
+    e.builder().ins().jump(merge_block, &[result]);
+    
+    e.builder().switch_to_block(merge_block);
+    e.builder().seal_block(merge_block);
+    Some((e.builder().block_params(merge_block)[0], ret_slot))
+}
+
+/// Emit a panic exit taken when `condition` is true. With `kind`, the JIT
+/// code raises that runtime error itself; `None` means the helper that
+/// produced `condition` already set the panic.
+fn emit_panic_if<'a>(e: &mut impl IrEmitter<'a>, condition: Value, kind: Option<PanicKind>) {
     let panic_block = e.builder().create_block();
     let ok_block = e.builder().create_block();
     e.builder().ins().brif(condition, panic_block, &[], ok_block, &[]);
     
     e.builder().switch_to_block(panic_block);
     e.builder().seal_block(panic_block);
-    if call_vo_panic {
-        if let Some(panic_func) = e.helpers().panic {
-            let ctx = e.ctx_param();
-            // Runtime panics pass 0 for both slots - VM will use default "nil pointer dereference" message
-            let msg_slot0 = e.builder().ins().iconst(types::I64, 0);
-            let msg_slot1 = e.builder().ins().iconst(types::I64, 0);
-            e.builder().ins().call(panic_func, &[ctx, msg_slot0, msg_slot1]);
-        }
+    emit_record_panic_pc(e);
+    if let (Some(kind), Some(panic_func)) = (kind, e.helpers().panic_runtime) {
+        let ctx = e.ctx_param();
+        let kind = e.builder().ins().iconst(types::I32, kind as i64);
+        e.builder().ins().call(panic_func, &[ctx, kind]);
     }
     let panic_ret_val = e.panic_return_value();
     let panic_ret = e.builder().ins().iconst(types::I32, panic_ret_val as i64);
@@ -434,11 +616,51 @@ fn emit_panic_if<'a>(e: &mut impl IrEmitter<'a>, condition: Value, call_vo_panic
     e.builder().seal_block(ok_block);
 }
 
+/// Store the current pc in `JitContext::panic_pc` so the VM can report where
+/// a panic raised by this code happened.
+pub(crate) fn emit_record_panic_pc<'a>(e: &mut impl IrEmitter<'a>) {
+    let ctx = e.ctx_param();
+    let pc = e.current_pc() as i64;
+    let pc = e.builder().ins().iconst(types::I32, pc);
+    e.builder().ins().store(MemFlags::trusted(), pc, ctx, core::mem::offset_of!(JitContext, panic_pc) as i32);
+}
+
+/// JumpTable as a `br_table`: branch to `entries[slots[a]]`, or to `miss` when
+/// the index is out of range. Terminates the current block.
+pub(crate) fn emit_jump_table<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction, entries: &[Block], miss: Block) {
+    let index = e.read_var(inst.a);
+    // br_table takes an i32 index: clamp first so large values can't wrap into range
+    let count = e.builder().ins().iconst(types::I64, entries.len() as i64);
+    let index = e.builder().ins().umin(index, count);
+    let index = e.builder().ins().ireduce(types::I32, index);
+    let dfg = &mut e.builder().func.dfg;
+    let default = dfg.block_call(miss, &[]);
+    let table: Vec<_> = entries.iter().map(|&block| dfg.block_call(block, &[])).collect();
+    let jt = e.builder().create_jump_table(JumpTableData::new(default, &table));
+    e.builder().ins().br_table(index, jt);
+}
+
+/// Emit conditional deopt: if `condition` is true, leave JIT code via
+/// `IrEmitter::emit_deopt_exit`; otherwise continue. Only call when
+/// `e.can_deopt()`.
+fn emit_deopt_if<'a>(e: &mut impl IrEmitter<'a>, condition: Value) {
+    let deopt_block = e.builder().create_block();
+    let ok_block = e.builder().create_block();
+    e.builder().ins().brif(condition, deopt_block, &[], ok_block, &[]);
+    
+    e.builder().switch_to_block(deopt_block);
+    e.builder().seal_block(deopt_block);
+    e.emit_deopt_exit();
+    
+    e.builder().switch_to_block(ok_block);
+    e.builder().seal_block(ok_block);
+}
+
 /// Emit nil check for pointer. Panics if ptr is nil.
 fn emit_nil_ptr_check<'a>(e: &mut impl IrEmitter<'a>, ptr: Value) {
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_nil = e.builder().ins().icmp(IntCC::Equal, ptr, zero);
-    emit_panic_if(e, is_nil, true); // call vo_panic for defer/recover
+    emit_panic_if(e, is_nil, Some(PanicKind::NilPointerDereference));
 }
 
 fn ptr_get<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
@@ -484,6 +706,33 @@ fn ptr_set_n<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
         let offset = ((inst.b as usize + i) * 8) as i32;
         e.builder().ins().store(MemFlags::trusted(), v, ptr, offset);
     }
+    emit_store_barrier(e, ptr);
+}
+
+/// Write barrier for a store at `addr`, which may point inside the object.
+/// Only calls vo_gc_write_barrier while the GC is marking.
+fn emit_store_barrier<'a>(e: &mut impl IrEmitter<'a>, addr: Value) {
+    let Some(wb_ref) = e.helpers().write_barrier else { return };
+    let ctx = e.ctx_param();
+    let flag_ptr = e.builder().ins().load(
+        types::I64, MemFlags::trusted(), ctx, core::mem::offset_of!(JitContext, gc_marking) as i32,
+    );
+    let marking = e.builder().ins().load(types::I8, MemFlags::trusted(), flag_ptr, 0);
+    
+    let barrier_block = e.builder().create_block();
+    let merge_block = e.builder().create_block();
+    e.builder().ins().brif(marking, barrier_block, &[], merge_block, &[]);
+    
+    e.builder().switch_to_block(barrier_block);
+    e.builder().seal_block(barrier_block);
+    let gc = e.gc_ptr();
+    let offset = e.builder().ins().iconst(types::I32, 0);
+    let val = e.builder().ins().iconst(types::I64, 0);
+    e.builder().ins().call(wb_ref, &[gc, addr, offset, val]);
+    e.builder().ins().jump(merge_block, &[]);
+    
+    e.builder().switch_to_block(merge_block);
+    e.builder().seal_block(merge_block);
 }
 
 fn ptr_add<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
@@ -541,15 +790,33 @@ fn slot_set_n<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
 
 fn conv_i2f<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let a = e.read_var(inst.b);
-    let f = e.builder().ins().fcvt_from_sint(types::F64, a);
+    let f = if inst.flags == 1 {
+        e.builder().ins().fcvt_from_uint(types::F64, a)
+    } else {
+        e.builder().ins().fcvt_from_sint(types::F64, a)
+    };
     let r = e.builder().ins().bitcast(types::I64, MemFlags::new(), f);
     e.write_var(inst.a, r);
 }
 
+/// Saturating like the VM's `as` casts (NaN -> 0); plain `fcvt_to_sint`
+/// would trap on NaN and out-of-range values.
 fn conv_f2i<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let a = e.read_var(inst.b);
     let f = e.builder().ins().bitcast(types::F64, MemFlags::new(), a);
-    let r = e.builder().ins().fcvt_to_sint(types::I64, f);
+    let r = if inst.flags == 1 {
+        // Same split as the VM's f64_to_u64: below 2^63 convert directly,
+        // otherwise convert f - 2^63 and set the top bit.
+        let two_63 = e.builder().ins().f64const(9223372036854775808.0);
+        let small = e.builder().ins().fcmp(FloatCC::LessThan, f, two_63);
+        let lo = e.builder().ins().fcvt_to_sint_sat(types::I64, f);
+        let shifted = e.builder().ins().fsub(f, two_63);
+        let hi = e.builder().ins().fcvt_to_sint_sat(types::I64, shifted);
+        let hi = e.builder().ins().bxor_imm(hi, i64::MIN);
+        e.builder().ins().select(small, lo, hi)
+    } else {
+        e.builder().ins().fcvt_to_sint_sat(types::I64, f)
+    };
     e.write_var(inst.a, r);
 }
 
@@ -598,7 +865,7 @@ fn index_check<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let idx = e.read_var(inst.a);
     let len = e.read_var(inst.b);
     let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
-    emit_panic_if(e, out_of_bounds, true);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
 }
 
 // =============================================================================
@@ -687,7 +954,7 @@ fn emit_slice_bounds_check<'a>(e: &mut impl IrEmitter<'a>, s: Value, idx: Value)
     
     // Check idx >= len
     let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
-    emit_panic_if(e, out_of_bounds, false);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
     
     e.builder().ins().load(types::I64, MemFlags::trusted(), s, SLICE_FIELD_DATA_PTR)
 }
@@ -774,7 +1041,10 @@ fn slice_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
                 let v32 = e.builder().ins().ireduce(types::I32, val);
                 e.builder().ins().store(MemFlags::trusted(), v32, addr, 0);
             }
-            _ => { e.builder().ins().store(MemFlags::trusted(), val, addr, 0); }
+            _ => {
+                e.builder().ins().store(MemFlags::trusted(), val, addr, 0);
+                emit_store_barrier(e, addr);
+            }
         }
     } else {
         let elem_slots = (elem_bytes + 7) / 8;
@@ -786,6 +1056,8 @@ fn slice_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
             let addr = e.builder().ins().iadd(data_ptr, slot_off);
             e.builder().ins().store(MemFlags::trusted(), v, addr, 0);
         }
+        let elem = e.builder().ins().iadd(data_ptr, off);
+        emit_store_barrier(e, elem);
     }
 }
 
@@ -847,11 +1119,23 @@ fn slice_slice<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check for bounds error (helper returns u64::MAX on error)
     let error_val = e.builder().ins().iconst(types::I64, -1i64);
     let is_error = e.builder().ins().icmp(IntCC::Equal, result, error_val);
-    emit_panic_if(e, is_error, true);
+    emit_panic_if(e, is_error, Some(PanicKind::IndexOutOfBounds));
     
     e.write_var(inst.a, result);
 }
 
+/// `copy(dst, src)`, compiled from the `vo_copy` CallExtern
+/// (a=result, c=args: dst, src) into a direct call to vo_slice_copy.
+pub(crate) fn slice_copy<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let slice_copy_func = e.helpers().slice_copy.unwrap();
+    let gc_ptr = e.gc_ptr();
+    let dst = e.read_var(inst.c);
+    let src = e.read_var(inst.c + 1);
+    let call = e.builder().ins().call(slice_copy_func, &[gc_ptr, dst, src]);
+    let result = e.builder().inst_results(call)[0];
+    e.write_var(inst.a, result);
+}
+
 fn slice_append<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let slice_append_func = match e.helpers().slice_append {
         Some(f) => f,
@@ -886,7 +1170,7 @@ fn slice_addr<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let s = e.read_var(inst.b);
     let idx = e.read_var(inst.c);
     let elem_bytes = inst.flags as i64;
-    let data_ptr = e.builder().ins().load(types::I64, MemFlags::trusted(), s, SLICE_FIELD_DATA_PTR);
+    let data_ptr = emit_slice_bounds_check(e, s, idx);
     let eb = e.builder().ins().iconst(types::I64, elem_bytes);
     let off = e.builder().ins().imul(idx, eb);
     let addr = e.builder().ins().iadd(data_ptr, off);
@@ -920,7 +1204,7 @@ fn array_get<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Bounds check: load len from ArrayHeader (offset 0)
     let len = e.builder().ins().load(types::I64, MemFlags::trusted(), arr, 0);
     let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
-    emit_panic_if(e, out_of_bounds, true);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
     let (elem_bytes, needs_sext) = resolve_elem_bytes(e, inst.flags, inst.c + 1);
     if elem_bytes <= 8 {
         let eb = e.builder().ins().iconst(types::I64, elem_bytes as i64);
@@ -966,7 +1250,7 @@ fn array_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Bounds check: load len from ArrayHeader (offset 0)
     let len = e.builder().ins().load(types::I64, MemFlags::trusted(), arr, 0);
     let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
-    emit_panic_if(e, out_of_bounds, true);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
     let val = e.read_var(inst.c);
     let (elem_bytes, _) = resolve_elem_bytes(e, inst.flags, inst.b + 1);
     if elem_bytes <= 8 {
@@ -987,7 +1271,10 @@ fn array_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
                 let v32 = e.builder().ins().ireduce(types::I32, val);
                 e.builder().ins().store(MemFlags::trusted(), v32, addr, 0);
             }
-            _ => { e.builder().ins().store(MemFlags::trusted(), val, addr, 0); }
+            _ => {
+                e.builder().ins().store(MemFlags::trusted(), val, addr, 0);
+                emit_store_barrier(e, arr);
+            }
         }
     } else {
         let elem_slots = (elem_bytes + 7) / 8;
@@ -1000,12 +1287,16 @@ fn array_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
             let addr = e.builder().ins().iadd(arr, slot_off);
             e.builder().ins().store(MemFlags::trusted(), v, addr, 0);
         }
+        emit_store_barrier(e, arr);
     }
 }
 
 fn array_addr<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let arr = e.read_var(inst.b);
     let idx = e.read_var(inst.c);
+    let len = e.builder().ins().load(types::I64, MemFlags::trusted(), arr, 0);
+    let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
     let elem_bytes = inst.flags as i64;
     let eb = e.builder().ins().iconst(types::I64, elem_bytes);
     let off = e.builder().ins().imul(idx, eb);
@@ -1035,7 +1326,7 @@ fn str_index<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let len_call = e.builder().ins().call(str_len_func, &[s]);
     let len = e.builder().inst_results(len_call)[0];
     let out_of_bounds = e.builder().ins().icmp(IntCC::UnsignedGreaterThanOrEqual, idx, len);
-    emit_panic_if(e, out_of_bounds, true);
+    emit_panic_if(e, out_of_bounds, Some(PanicKind::IndexOutOfBounds));
     let call = e.builder().ins().call(str_index_func, &[s, idx]);
     let result = e.builder().inst_results(call)[0];
     e.write_var(inst.a, result);
@@ -1108,6 +1399,24 @@ fn str_decode_rune<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     e.write_var(inst.a + 1, width);
 }
 
+fn str_contains<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let func = match e.helpers().str_contains { Some(f) => f, None => return };
+    let s = e.read_var(inst.b);
+    let sub = e.read_var(inst.c);
+    let call = e.builder().ins().call(func, &[s, sub]);
+    let result = e.builder().inst_results(call)[0];
+    e.write_var(inst.a, result);
+}
+
+fn str_index_of<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
+    let func = match e.helpers().str_index_of { Some(f) => f, None => return };
+    let s = e.read_var(inst.b);
+    let sub = e.read_var(inst.c);
+    let call = e.builder().ins().call(func, &[s, sub]);
+    let result = e.builder().inst_results(call)[0];
+    e.write_var(inst.a, result);
+}
+
 // =============================================================================
 // Map operations
 // =============================================================================
@@ -1139,35 +1448,88 @@ fn map_len<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
 
 fn map_get<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let func = match e.helpers().map_get { Some(f) => f, None => return };
-    let m = e.read_var(inst.a);
-    let key = e.read_var(inst.b);
-    let key_bytes = e.builder().ins().iconst(types::I64, inst.c as i64);
-    let val_bytes = e.builder().ins().iconst(types::I64, inst.flags as i64);
-    let call = e.builder().ins().call(func, &[m, key, key_bytes, val_bytes]);
-    let result = e.builder().inst_results(call)[0];
-    e.write_var(inst.a, result);
+    // MapGet: a=dst, b=map, c=meta (key follows at c+1), meta stored by LoadConst
+    // meta = key_slots<<16 | val_slots<<1 | has_ok
+    let meta = e.get_reg_const(inst.c).unwrap() as u64;
+    let key_slots = ((meta >> 16) & 0xFFFF) as usize;
+    let val_slots = ((meta >> 1) & 0x7FFF) as usize;
+    let has_ok = (meta & 1) != 0;
+
+    let key_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
+        cranelift_codegen::ir::StackSlotKind::ExplicitSlot, (key_slots.max(1) * 8) as u32, 8));
+    let val_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
+        cranelift_codegen::ir::StackSlotKind::ExplicitSlot, (val_slots.max(1) * 8) as u32, 8));
+
+    for i in 0..key_slots {
+        let k = e.read_var(inst.c + 1 + i as u16);
+        e.builder().ins().stack_store(k, key_slot, (i * 8) as i32);
+    }
+    // vo_map_get leaves the buffer alone for a missing key (or nil map): zero value
+    let zero = e.builder().ins().iconst(types::I64, 0);
+    for i in 0..val_slots {
+        e.builder().ins().stack_store(zero, val_slot, (i * 8) as i32);
+    }
+
+    let ctx = e.ctx_param();
+    let m = e.read_var(inst.b);
+    let key_ptr = e.builder().ins().stack_addr(types::I64, key_slot, 0);
+    let val_ptr = e.builder().ins().stack_addr(types::I64, val_slot, 0);
+    let key_slots_i32 = e.builder().ins().iconst(types::I32, key_slots as i64);
+    let val_slots_i32 = e.builder().ins().iconst(types::I32, val_slots as i64);
+    let call = e.builder().ins().call(func, &[ctx, m, key_ptr, key_slots_i32, val_ptr, val_slots_i32]);
+    let ok = e.builder().inst_results(call)[0];
+
+    for i in 0..val_slots {
+        let v = e.builder().ins().stack_load(types::I64, val_slot, (i * 8) as i32);
+        e.write_var(inst.a + i as u16, v);
+    }
+    if has_ok {
+        e.write_var(inst.a + val_slots as u16, ok);
+    }
 }
 
 fn map_set<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let func = match e.helpers().map_set { Some(f) => f, None => return };
-    let gc_ptr = e.gc_ptr();
+    // MapSet: a=map, b=meta (key follows at b+1), c=val_start, meta stored by LoadConst
+    // meta = key_slots<<8 | val_slots; flags bit0/bit1 = key/val may hold a GcRef
+    let meta = e.get_reg_const(inst.b).unwrap() as u64;
+    let key_slots = ((meta >> 8) & 0xFF) as usize;
+    let val_slots = (meta & 0xFF) as usize;
+
     let m = e.read_var(inst.a);
-    
     // nil map write panics (Go semantics)
     let zero = e.builder().ins().iconst(types::I64, 0);
     let is_nil = e.builder().ins().icmp(IntCC::Equal, m, zero);
-    emit_panic_if(e, is_nil, false);
-    
-    let key = e.read_var(inst.b);
-    let val = e.read_var(inst.c);
-    let key_bytes = e.builder().ins().iconst(types::I64, (inst.flags & 0x0F) as i64);
-    let val_bytes = e.builder().ins().iconst(types::I64, (inst.flags >> 4) as i64);
-    let call = e.builder().ins().call(func, &[gc_ptr, m, key, val, key_bytes, val_bytes]);
+    emit_panic_if(e, is_nil, Some(PanicKind::NilMapWrite));
+
+    let key_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
+        cranelift_codegen::ir::StackSlotKind::ExplicitSlot, (key_slots.max(1) * 8) as u32, 8));
+    let val_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
+        cranelift_codegen::ir::StackSlotKind::ExplicitSlot, (val_slots.max(1) * 8) as u32, 8));
+    for i in 0..key_slots {
+        let k = e.read_var(inst.b + 1 + i as u16);
+        e.builder().ins().stack_store(k, key_slot, (i * 8) as i32);
+    }
+    for i in 0..val_slots {
+        let v = e.read_var(inst.c + i as u16);
+        e.builder().ins().stack_store(v, val_slot, (i * 8) as i32);
+    }
+
+    let ctx = e.ctx_param();
+    let key_ptr = e.builder().ins().stack_addr(types::I64, key_slot, 0);
+    let val_ptr = e.builder().ins().stack_addr(types::I64, val_slot, 0);
+    let key_slots_i32 = e.builder().ins().iconst(types::I32, key_slots as i64);
+    let val_slots_i32 = e.builder().ins().iconst(types::I32, val_slots as i64);
+    let call = e.builder().ins().call(func, &[ctx, m, key_ptr, key_slots_i32, val_ptr, val_slots_i32]);
     let result = e.builder().inst_results(call)[0];
-    
+
     // Check if vo_map_set returned panic (unhashable interface key)
     let is_panic = e.builder().ins().icmp(IntCC::NotEqual, result, zero);
-    emit_panic_if(e, is_panic, false);
+    emit_panic_if(e, is_panic, None);
+
+    if inst.flags & 0b11 != 0 {
+        emit_store_barrier(e, m);
+    }
 }
 
 fn map_delete<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
@@ -1187,7 +1549,8 @@ fn map_iter_init<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let iter_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
         cranelift_codegen::ir::StackSlotKind::ExplicitSlot, MAP_ITER_BYTES, 8));
     let iter_ptr = e.builder().ins().stack_addr(types::I64, iter_slot, 0);
-    e.builder().ins().call(func, &[m, iter_ptr]);
+    let ctx = e.ctx_param();
+    e.builder().ins().call(func, &[ctx, m, iter_ptr]);
     for i in 0..MAP_ITER_SLOTS {
         let val = e.builder().ins().stack_load(types::I64, iter_slot, (i * 8) as i32);
         e.write_var(inst.a + i as u16, val);
@@ -1281,13 +1644,17 @@ fn ptr_new<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
 
 fn chan_new<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     let func = match e.helpers().chan_new { Some(f) => f, None => return };
-    let gc_ptr = e.gc_ptr();
+    let ctx = e.ctx_param();
     let elem_meta = e.read_var(inst.b);
     let elem_meta_i32 = e.builder().ins().ireduce(types::I32, elem_meta);
     let elem_slots_i32 = e.builder().ins().iconst(types::I32, inst.flags as i64);
     let cap = e.read_var(inst.c);
-    let call = e.builder().ins().call(func, &[gc_ptr, elem_meta_i32, elem_slots_i32, cap]);
+    let call = e.builder().ins().call(func, &[ctx, elem_meta_i32, elem_slots_i32, cap]);
     let result = e.builder().inst_results(call)[0];
+    // Null means negative cap; the helper already set the panic message.
+    let zero = e.builder().ins().iconst(types::I64, 0);
+    let failed = e.builder().ins().icmp(IntCC::Equal, result, zero);
+    emit_panic_if(e, failed, None);
     e.write_var(inst.a, result);
 }
 
@@ -1314,31 +1681,21 @@ fn chan_cap<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
 fn str_new<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     use vo_runtime::bytecode::Constant;
     let func = match e.helpers().str_new { Some(f) => f, None => return };
-    let const_idx = inst.b as usize;
-    let bytes: Vec<u8> = if let Constant::String(s) = &e.vo_module().constants[const_idx] {
-        s.as_bytes().to_vec()
-    } else {
-        let zero = e.builder().ins().iconst(types::I64, 0);
-        e.write_var(inst.a, zero);
-        return;
+    let len = match &e.vo_module().constants[inst.b as usize] {
+        Constant::String(s) => s.len(),
+        _ => 0,
     };
-    let len = bytes.len();
-    if len == 0 {
-        let zero = e.builder().ins().iconst(types::I64, 0);
-        e.write_var(inst.a, zero);
-    } else {
+    // Non-empty string constants are in read-only data; anything else is the empty string
+    if let Some(data) = e.helpers().str_consts.get(&inst.b).copied() {
         let gc_ptr = e.gc_ptr();
-        let stack_slot = e.builder().create_sized_stack_slot(cranelift_codegen::ir::StackSlotData::new(
-            cranelift_codegen::ir::StackSlotKind::ExplicitSlot, len as u32, 0));
-        for (i, &b) in bytes.iter().enumerate() {
-            let byte_val = e.builder().ins().iconst(types::I8, b as i64);
-            e.builder().ins().stack_store(byte_val, stack_slot, i as i32);
-        }
-        let data_ptr = e.builder().ins().stack_addr(types::I64, stack_slot, 0);
+        let data_ptr = e.builder().ins().global_value(types::I64, data);
         let len_val = e.builder().ins().iconst(types::I64, len as i64);
         let call = e.builder().ins().call(func, &[gc_ptr, data_ptr, len_val]);
         let result = e.builder().inst_results(call)[0];
         e.write_var(inst.a, result);
+    } else {
+        let zero = e.builder().ins().iconst(types::I64, 0);
+        e.write_var(inst.a, zero);
     }
 }
 
@@ -1357,7 +1714,33 @@ fn iface_assign<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
         } else { 0 };
         
         let src_slot0 = src;
-        let src_slot1 = e.read_var(inst.b + 1);
+        let mut src_slot1 = e.read_var(inst.b + 1);
+        
+        // A boxed struct/array must be copied like the VM does. Hand that case
+        // to the interpreter while it is still safe to; past that point, clone here.
+        let src_vk = e.builder().ins().band_imm(src_slot0, 0xFF);
+        let is_array = e.builder().ins().icmp_imm(IntCC::Equal, src_vk, 14);
+        let is_struct = e.builder().ins().icmp_imm(IntCC::Equal, src_vk, 15);
+        let needs_copy = e.builder().ins().bor(is_array, is_struct);
+        if e.can_deopt() {
+            emit_deopt_if(e, needs_copy);
+        } else if let Some(ptr_clone_func) = e.helpers().ptr_clone {
+            let copy_block = e.builder().create_block();
+            let merge_block = e.builder().create_block();
+            e.builder().append_block_param(merge_block, types::I64); // slot1
+            e.builder().ins().brif(needs_copy, copy_block, &[], merge_block, &[src_slot1]);
+            
+            e.builder().switch_to_block(copy_block);
+            e.builder().seal_block(copy_block);
+            let gc_ptr = e.gc_ptr();
+            let call = e.builder().ins().call(ptr_clone_func, &[gc_ptr, src_slot1]);
+            let cloned = e.builder().inst_results(call)[0];
+            e.builder().ins().jump(merge_block, &[cloned]);
+            
+            e.builder().switch_to_block(merge_block);
+            e.builder().seal_block(merge_block);
+            src_slot1 = e.builder().block_params(merge_block)[0];
+        }
         
         if iface_meta_id == 0 {
             // Target is any: itab_id=0, preserve rttid and vk from source
@@ -1427,7 +1810,7 @@ fn iface_assert<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     if !has_ok {
         let zero = e.builder().ins().iconst(types::I64, 0);
         let is_panic = e.builder().ins().icmp(IntCC::Equal, result, zero);
-        emit_panic_if(e, is_panic, false);
+        emit_panic_if(e, is_panic, Some(PanicKind::TypeAssertionFailed));
     }
     let dst_slots = if assert_kind == 1 { 2 } else { target_slots.max(1) };
     for i in 0..dst_slots {
@@ -1457,7 +1840,7 @@ fn iface_eq<'a>(e: &mut impl IrEmitter<'a>, inst: &Instruction) {
     // Check if result == 2 (panic for uncomparable type)
     let two = e.builder().ins().iconst(types::I64, 2);
     let is_panic = e.builder().ins().icmp(IntCC::Equal, result, two);
-    emit_panic_if(e, is_panic, false);
+    emit_panic_if(e, is_panic, None);
     
     // Mask result to 0 or 1 (already know it's not 2)
     let one = e.builder().ins().iconst(types::I64, 1);
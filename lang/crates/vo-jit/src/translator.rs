@@ -1,6 +1,8 @@
 //! IrEmitter trait - shared IR generation interface.
 
-use cranelift_codegen::ir::{FuncRef, Value};
+use std::collections::HashMap;
+
+use cranelift_codegen::ir::{FuncRef, GlobalValue, Value};
 use cranelift_frontend::FunctionBuilder;
 use vo_runtime::bytecode::Module as VoModule;
 
@@ -16,7 +18,7 @@ pub enum TranslateResult {
 }
 
 /// Runtime helper function references
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone)]
 pub struct HelperFuncs {
     pub safepoint: Option<FuncRef>,
     pub call_vm: Option<FuncRef>,
@@ -25,6 +27,8 @@ pub struct HelperFuncs {
     pub call_closure: Option<FuncRef>,
     pub call_iface: Option<FuncRef>,
     pub panic: Option<FuncRef>,
+    pub panic_runtime: Option<FuncRef>,
+    pub enter_frame: Option<FuncRef>,
     pub call_extern: Option<FuncRef>,
     pub str_new: Option<FuncRef>,
     pub str_len: Option<FuncRef>,
@@ -34,6 +38,8 @@ pub struct HelperFuncs {
     pub str_eq: Option<FuncRef>,
     pub str_cmp: Option<FuncRef>,
     pub str_decode_rune: Option<FuncRef>,
+    pub str_contains: Option<FuncRef>,
+    pub str_index_of: Option<FuncRef>,
     pub ptr_clone: Option<FuncRef>,
     pub closure_new: Option<FuncRef>,
     pub chan_new: Option<FuncRef>,
@@ -47,6 +53,7 @@ pub struct HelperFuncs {
     pub slice_append: Option<FuncRef>,
     pub slice_slice: Option<FuncRef>,
     pub slice_slice3: Option<FuncRef>,
+    pub slice_copy: Option<FuncRef>,
     pub slice_from_array: Option<FuncRef>,
     pub slice_from_array3: Option<FuncRef>,
     pub map_new: Option<FuncRef>,
@@ -59,6 +66,8 @@ pub struct HelperFuncs {
     pub iface_assert: Option<FuncRef>,
     pub iface_to_iface: Option<FuncRef>,
     pub iface_eq: Option<FuncRef>,
+    /// Read-only data holding the function's string constants, by constant index.
+    pub str_consts: HashMap<u16, GlobalValue>,
 }
 
 /// IR emitter trait - implemented by FunctionCompiler and LoopCompiler
@@ -105,4 +114,15 @@ pub trait IrEmitter<'a> {
     /// Get memory address of a variable slot.
     /// Used by SlotGet/SlotSet for stack array access.
     fn var_addr(&mut self, slot: u16) -> Value;
+    
+    /// Whether the current instruction may deoptimize, i.e. hand control back
+    /// to the interpreter without repeating any side effect.
+    fn can_deopt(&self) -> bool;
+    
+    /// Emit the deopt exit (FunctionCompiler: return JitResult::Deopt so the
+    /// VM re-runs the call; LoopCompiler: exit to the VM at the current pc).
+    fn emit_deopt_exit(&mut self);
+    
+    /// Whether AddI/SubI/MulI panic on signed overflow instead of wrapping.
+    fn checked_arith(&self) -> bool;
 }
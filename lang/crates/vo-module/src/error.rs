@@ -40,6 +40,33 @@ pub enum ModuleError {
         version: String,
     },
 
+    /// Fetching a module into .vodeps failed.
+    DownloadFailed {
+        module: String,
+        version: String,
+        message: String,
+    },
+
+    /// A replace directive points at a directory without a vo.mod.
+    ReplacementNotFound {
+        module: String,
+        dir: PathBuf,
+    },
+
+    /// No vo.sum entry for a module in the closure.
+    ChecksumMissing {
+        module: String,
+        version: String,
+    },
+
+    /// Module contents don't match the vo.sum entry.
+    ChecksumMismatch {
+        module: String,
+        version: String,
+        expected: String,
+        actual: String,
+    },
+
     /// Version conflict: same module required at different versions.
     VersionConflict {
         module: String,
@@ -102,6 +129,26 @@ impl fmt::Display for ModuleError {
                     module, version, module, version
                 )
             }
+            ModuleError::DownloadFailed { module, version, message } => {
+                write!(f, "cannot download {}@{}: {}", module, version, message)
+            }
+            ModuleError::ReplacementNotFound { module, dir } => {
+                write!(f, "replacement for {} not found: no vo.mod in {}", module, dir.display())
+            }
+            ModuleError::ChecksumMissing { module, version } => {
+                write!(
+                    f,
+                    "missing vo.sum entry for {}@{}\n  run: vo get {}@{}",
+                    module, version, module, version
+                )
+            }
+            ModuleError::ChecksumMismatch { module, version, expected, actual } => {
+                write!(
+                    f,
+                    "checksum mismatch for {}@{}\n  vo.sum:     {}\n  downloaded: {}",
+                    module, version, expected, actual
+                )
+            }
             ModuleError::VersionConflict {
                 module,
                 version1,
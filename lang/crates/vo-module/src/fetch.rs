@@ -0,0 +1,217 @@
+//! Dependency download into the `.vodeps` cache.
+//!
+//! Modules are fetched with a shallow `git clone` of the version tag into a
+//! temporary directory next to the final location. Only after the clone
+//! succeeds and its `vo.mod` declares the expected module path is the
+//! directory renamed to `.vodeps/<module>@<version>`, so a failed download
+//! never leaves a half-written module behind.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{ModuleError, ModuleResult};
+use crate::modfile::{is_valid_module_path, is_valid_version, ModFile};
+
+/// Returns the git URL a module path is fetched from.
+pub fn repo_url(module: &str) -> String {
+    format!("https://{}", module)
+}
+
+/// Downloads `module@version` into `deps_dir` from its default repository.
+///
+/// Returns the module root. Already-downloaded modules are not fetched again.
+pub fn download_module(deps_dir: &Path, module: &str, version: &str) -> ModuleResult<PathBuf> {
+    download_module_from(deps_dir, module, version, &repo_url(module))
+}
+
+/// Downloads `module@version` into `deps_dir`, cloning from `url`.
+pub fn download_module_from(
+    deps_dir: &Path,
+    module: &str,
+    version: &str,
+    url: &str,
+) -> ModuleResult<PathBuf> {
+    if !is_valid_module_path(module) {
+        return Err(ModuleError::InvalidModulePath(module.to_string()));
+    }
+    if !is_valid_version(version) {
+        return Err(ModuleError::InvalidVersion(version.to_string()));
+    }
+
+    let dest = deps_dir.join(format!("{}@{}", module, version));
+    if dest.join("vo.mod").is_file() {
+        return Ok(dest);
+    }
+
+    let parent = dest.parent().unwrap_or(deps_dir);
+    fs::create_dir_all(parent).map_err(|e| ModuleError::IoError(parent.to_path_buf(), e.to_string()))?;
+
+    // Stage next to the destination so the final rename stays on one filesystem.
+    let staging = parent.join(format!(
+        ".tmp-{}@{}-{}",
+        module.rsplit('/').next().unwrap_or(module),
+        version,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&staging);
+
+    let result = fetch_into(&staging, module, version, url)
+        .and_then(|()| {
+            fs::rename(&staging, &dest).map_err(|e| ModuleError::IoError(dest.clone(), e.to_string()))
+        });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result.map(|()| dest)
+}
+
+/// Clones the version tag into `dir` and checks its vo.mod.
+fn fetch_into(dir: &Path, module: &str, version: &str, url: &str) -> ModuleResult<()> {
+    let failed = |message: String| ModuleError::DownloadFailed {
+        module: module.to_string(),
+        version: version.to_string(),
+        message,
+    };
+
+    let output = Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", "--branch", version, "--", url])
+        .arg(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| failed(format!("cannot run git: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!("git clone {} failed: {}", url, stderr.trim())));
+    }
+
+    let _ = fs::remove_dir_all(dir.join(".git"));
+
+    let mod_path = dir.join("vo.mod");
+    if !mod_path.is_file() {
+        return Err(failed("no vo.mod at repository root".to_string()));
+    }
+    let mod_file = ModFile::parse_file(&mod_path)?;
+    if mod_file.module != module {
+        return Err(failed(format!("vo.mod declares module {}", mod_file.module)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// Creates a git repo with `files` committed and tagged `tag`.
+    fn make_repo(files: &[(&str, &str)], tag: &str) -> TempDir {
+        let repo = TempDir::new().unwrap();
+        for (name, content) in files {
+            fs::write(repo.path().join(name), content).unwrap();
+        }
+        git(repo.path(), &["init", "--quiet"]);
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["commit", "--quiet", "-m", "init"]);
+        git(repo.path(), &["tag", tag]);
+        repo
+    }
+
+    fn url(repo: &TempDir) -> String {
+        format!("file://{}", repo.path().display())
+    }
+
+    #[test]
+    fn test_download_at_tag() {
+        let repo = make_repo(
+            &[("vo.mod", "module github.com/foo/bar\n"), ("bar.vo", "package bar\n")],
+            "v1.2.3",
+        );
+        let deps = TempDir::new().unwrap();
+
+        let root = download_module_from(deps.path(), "github.com/foo/bar", "v1.2.3", &url(&repo)).unwrap();
+        assert_eq!(root, deps.path().join("github.com/foo/bar@v1.2.3"));
+        assert!(root.join("bar.vo").is_file());
+        assert!(!root.join(".git").exists());
+
+        // The resolver finds the downloaded module.
+        let project = TempDir::new().unwrap();
+        fs::write(project.path().join("vo.mod"), "module app\nrequire bar github.com/foo/bar v1.2.3\n").unwrap();
+        fs::rename(deps.path(), project.path().join(".vodeps")).unwrap();
+        let resolver = crate::ModuleResolver::new(project.path());
+        let mut sum = crate::SumFile::default();
+        let root = resolver.deps_dir().join("github.com/foo/bar@v1.2.3");
+        sum.insert("github.com/foo/bar", "v1.2.3", crate::hash_dir(&root).unwrap()).unwrap();
+        sum.write_file(resolver.sum_file_path()).unwrap();
+        let mod_file = ModFile::parse_file(resolver.mod_file_path()).unwrap();
+        let closure = resolver.compute_closure(&mod_file).unwrap();
+        let pkg = resolver.resolve_import("github.com/foo/bar", &closure).unwrap();
+        assert!(pkg.dir.join("bar.vo").is_file());
+    }
+
+    #[test]
+    fn test_missing_mod_file_leaves_nothing() {
+        let repo = make_repo(&[("bar.vo", "package bar\n")], "v1.0.0");
+        let deps = TempDir::new().unwrap();
+
+        let err = download_module_from(deps.path(), "github.com/foo/bar", "v1.0.0", &url(&repo)).unwrap_err();
+        assert!(matches!(err, ModuleError::DownloadFailed { .. }));
+        assert_eq!(fs::read_dir(deps.path().join("github.com/foo")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_module_path_mismatch() {
+        let repo = make_repo(&[("vo.mod", "module github.com/other/bar\n")], "v1.0.0");
+        let deps = TempDir::new().unwrap();
+
+        let err = download_module_from(deps.path(), "github.com/foo/bar", "v1.0.0", &url(&repo)).unwrap_err();
+        assert!(err.to_string().contains("declares module github.com/other/bar"));
+        assert!(!deps.path().join("github.com/foo/bar@v1.0.0").exists());
+    }
+
+    #[test]
+    fn test_unreachable_repo() {
+        let deps = TempDir::new().unwrap();
+        let missing = deps.path().join("no-such-repo");
+
+        let err = download_module_from(
+            deps.path(),
+            "github.com/foo/bar",
+            "v1.0.0",
+            &format!("file://{}", missing.display()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("cannot download github.com/foo/bar@v1.0.0"));
+        assert!(!deps.path().join("github.com/foo/bar@v1.0.0").exists());
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        let deps = TempDir::new().unwrap();
+        let err = download_module(deps.path(), "github.com/foo/bar", "latest").unwrap_err();
+        assert!(matches!(err, ModuleError::InvalidVersion(_)));
+    }
+
+    #[test]
+    fn test_module_path_escaping_deps() {
+        let root = TempDir::new().unwrap();
+        let deps = root.path().join(".vodeps");
+        let repo = make_repo(&[("vo.mod", "module example.com/../../evil\n")], "v1.0.0");
+        let url = repo.path().to_str().unwrap();
+        for module in ["example.com/../../evil", "../evil", "example.com\\..\\evil"] {
+            let err = download_module_from(&deps, module, "v1.0.0", url).unwrap_err();
+            assert!(matches!(err, ModuleError::InvalidModulePath(_)), "{}", module);
+        }
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 0);
+    }
+}
@@ -5,6 +5,8 @@
 //! - **vo.mod parsing**: Parse module declarations and dependencies
 //! - **Dependency closure**: Compute transitive dependencies with version conflict detection
 //! - **Import resolution**: Resolve import paths to filesystem locations
+//! - **Download**: Fetch dependencies into `.vodeps`
+//! - **Checksums**: Verify dependencies against `vo.sum`
 //!
 //! # Example
 //!
@@ -25,11 +27,15 @@
 mod modfile;
 mod resolver;
 mod error;
+mod fetch;
+mod sumfile;
 pub mod vfs;
 mod ext_manifest;
 
-pub use modfile::{ModFile, Require};
-pub use resolver::{ModuleResolver, ModuleClosure, ResolvedPackage};
+pub use modfile::{ModFile, Require, Replace, ReplaceTarget};
+pub use resolver::{ModuleResolver, ModuleClosure, ResolvedModule, ResolvedPackage};
+pub use sumfile::{SumFile, SumEntry, hash_dir};
 pub use error::{ModuleError, ModuleResult};
+pub use fetch::{download_module, download_module_from, repo_url};
 pub use vfs::{PackageResolver, PackageResolverMixed, Resolver, VfsPackage, VfsFile, StdSource, LocalSource, ModSource};
 pub use ext_manifest::{ExtensionManifest, ExtManifestError, discover_extensions};
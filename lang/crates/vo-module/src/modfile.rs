@@ -6,9 +6,15 @@
 //!
 //! require <alias> <module-path> <version>
 //! require <alias> <module-path> <version>
+//!
+//! replace <module-path>[@<version>] => <local-path>
+//! replace <module-path>[@<version>] => <module-path> <version>
+//! replace <module-path>[@<version>] => <module-path>@<version>
 //! ```
 //!
 //! The alias is used in source code with `@"alias"` syntax for external imports.
+//! A local replacement path starts with `./`, `../` or `/`. A version on the
+//! left limits the replacement to that version of the module.
 
 use std::fmt;
 use std::fs;
@@ -24,6 +30,9 @@ pub struct ModFile {
 
     /// Direct dependencies.
     pub requires: Vec<Require>,
+
+    /// Replace directives.
+    pub replaces: Vec<Replace>,
 }
 
 /// A single require directive.
@@ -39,6 +48,48 @@ pub struct Require {
     pub version: String,
 }
 
+/// A single replace directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replace {
+    /// The module path being replaced.
+    pub module: String,
+
+    /// The only version replaced, or None for every version.
+    pub version: Option<String>,
+
+    /// Where the module is taken from instead.
+    pub target: ReplaceTarget,
+}
+
+/// The target of a replace directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplaceTarget {
+    /// A local directory, relative to the vo.mod that declares it.
+    Path(String),
+
+    /// Another module version in .vodeps.
+    Module { module: String, version: String },
+}
+
+impl fmt::Display for ReplaceTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaceTarget::Path(path) => write!(f, "{}", path),
+            ReplaceTarget::Module { module, version } => write!(f, "{} {}", module, version),
+        }
+    }
+}
+
+impl Replace {
+    /// The left side of the directive: `module` or `module@version`.
+    pub fn source(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.module, version),
+            None => self.module.clone(),
+        }
+    }
+}
+
 impl ModFile {
     /// Parses a vo.mod file from the given path.
     pub fn parse_file<P: AsRef<Path>>(path: P) -> ModuleResult<Self> {
@@ -57,6 +108,7 @@ impl ModFile {
     pub fn parse(content: &str, file_path: &Path) -> ModuleResult<Self> {
         let mut module: Option<String> = None;
         let mut requires: Vec<Require> = Vec::new();
+        let mut replaces: Vec<Replace> = Vec::new();
 
         for (line_num, line) in content.lines().enumerate() {
             let line_num = line_num + 1; // 1-indexed
@@ -138,6 +190,27 @@ impl ModFile {
                 continue;
             }
 
+            // Parse replace directive
+            if line.starts_with("replace ") {
+                let rest = line.strip_prefix("replace ").unwrap().trim();
+                let replace = parse_replace(rest).map_err(|message| ModuleError::ParseError {
+                    file: file_path.to_path_buf(),
+                    line: line_num,
+                    message,
+                })?;
+
+                if replaces.iter().any(|r| r.module == replace.module && r.version == replace.version) {
+                    return Err(ModuleError::ParseError {
+                        file: file_path.to_path_buf(),
+                        line: line_num,
+                        message: format!("duplicate replace for {}", replace.source()),
+                    });
+                }
+
+                replaces.push(replace);
+                continue;
+            }
+
             // Unknown directive
             return Err(ModuleError::ParseError {
                 file: file_path.to_path_buf(),
@@ -148,7 +221,7 @@ impl ModFile {
 
         let module = module.ok_or_else(|| ModuleError::MissingModuleDecl(file_path.to_path_buf()))?;
 
-        Ok(ModFile { module, requires })
+        Ok(ModFile { module, requires, replaces })
     }
 
     /// Creates a new empty ModFile with the given module path.
@@ -156,6 +229,7 @@ impl ModFile {
         ModFile {
             module,
             requires: Vec::new(),
+            replaces: Vec::new(),
         }
     }
 
@@ -172,6 +246,27 @@ impl ModFile {
         self.requires.push(Require { alias, module, version });
     }
 
+    /// Requires `module` at `version`, as `vo get` does.
+    ///
+    /// Updates the version of an existing require for the module, otherwise
+    /// adds one whose alias is the last path element.
+    pub fn require_module(&mut self, module: &str, version: &str) {
+        if let Some(req) = self.requires.iter_mut().find(|r| r.module == module) {
+            req.version = version.to_string();
+            return;
+        }
+        self.add_require(default_alias(module), module.to_string(), version.to_string());
+    }
+
+    /// Finds the replace directive for a module version: one for exactly
+    /// that version, else one for every version of the module.
+    pub fn find_replace(&self, module: &str, version: &str) -> Option<&Replace> {
+        let replaces = || self.replaces.iter().filter(|r| r.module == module);
+        replaces()
+            .find(|r| r.version.as_deref() == Some(version))
+            .or_else(|| replaces().find(|r| r.version.is_none()))
+    }
+
     /// Finds a require by alias.
     pub fn find_by_alias(&self, alias: &str) -> Option<&Require> {
         self.requires.iter().find(|r| r.alias == alias)
@@ -194,10 +289,87 @@ impl fmt::Display for ModFile {
                 writeln!(f, "require {} {} {}", req.alias, req.module, req.version)?;
             }
         }
+        if !self.replaces.is_empty() {
+            writeln!(f)?;
+            for rep in &self.replaces {
+                writeln!(f, "replace {} => {}", rep.source(), rep.target)?;
+            }
+        }
         Ok(())
     }
 }
 
+/// Parses the part of a replace directive after `replace`.
+fn parse_replace(rest: &str) -> Result<Replace, String> {
+    let syntax_err = || {
+        format!(
+            "invalid replace syntax, expected: replace <module>[@<version>] => <path> | <module> <version> | <module>@<version>, got: replace {}",
+            rest
+        )
+    };
+
+    let (old, new) = rest.split_once("=>").ok_or_else(syntax_err)?;
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+    let [old] = old.as_slice() else {
+        return Err(syntax_err());
+    };
+
+    let (module, version) = parse_module_version(old)?;
+    let target = match new.as_slice() {
+        [path] if is_local_path(path) => ReplaceTarget::Path(path.to_string()),
+        [new_module] if new_module.contains('@') => {
+            let (module, version) = parse_module_version(new_module)?;
+            ReplaceTarget::Module { module, version: version.ok_or_else(syntax_err)? }
+        }
+        [new_module, version] if !new_module.contains('@') => {
+            let (module, _) = parse_module_version(new_module)?;
+            if !is_valid_version(version) {
+                return Err(format!("invalid version: {}", version));
+            }
+            ReplaceTarget::Module { module, version: version.to_string() }
+        }
+        _ => return Err(syntax_err()),
+    };
+
+    Ok(Replace { module, version, target })
+}
+
+/// Splits and validates `module` or `module@version`.
+fn parse_module_version(spec: &str) -> Result<(String, Option<String>), String> {
+    let (module, version) = match spec.split_once('@') {
+        Some((module, version)) => (module, Some(version)),
+        None => (spec, None),
+    };
+    if !is_valid_module_path(module) {
+        return Err(format!("invalid module path: {}", module));
+    }
+    if let Some(version) = version {
+        if !is_valid_version(version) {
+            return Err(format!("invalid version: {}", version));
+        }
+    }
+    Ok((module.to_string(), version.map(str::to_string)))
+}
+
+/// Reports whether a replace target is a filesystem path.
+fn is_local_path(path: &str) -> bool {
+    path.starts_with("./") || path.starts_with("../") || path.starts_with('/')
+}
+
+/// Derives an alias from the last element of a module path.
+fn default_alias(module: &str) -> String {
+    let last = module.rsplit('/').next().unwrap_or(module);
+    let mut alias: String = last
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !is_valid_alias(&alias) {
+        alias.insert(0, '_');
+    }
+    alias
+}
+
 /// Validates an alias.
 ///
 /// A valid alias:
@@ -221,22 +393,19 @@ fn is_valid_alias(alias: &str) -> bool {
 /// A valid module path:
 /// - Is not empty
 /// - Does not start or end with /
-/// - Does not contain //
+/// - Has no empty, `.` or `..` element (no //, no way out of `.vodeps`)
+/// - Does not contain \
 /// - Does not start with std/ (reserved for standard library)
-fn is_valid_module_path(path: &str) -> bool {
-    if path.is_empty() {
-        return false;
-    }
-    if path.starts_with('/') || path.ends_with('/') {
+pub(crate) fn is_valid_module_path(path: &str) -> bool {
+    if path.is_empty() || path.contains('\\') {
         return false;
     }
-    if path.contains("//") {
+    if path.split('/').any(|elem| elem.is_empty() || elem == "." || elem == "..") {
         return false;
     }
     if path.starts_with("std/") || path == "std" {
         return false;
     }
-    // Must contain at least one path component
     true
 }
 
@@ -245,7 +414,7 @@ fn is_valid_module_path(path: &str) -> bool {
 /// A valid version:
 /// - Starts with 'v'
 /// - Has format vMAJOR.MINOR.PATCH with optional pre-release/build metadata
-fn is_valid_version(version: &str) -> bool {
+pub(crate) fn is_valid_version(version: &str) -> bool {
     if !version.starts_with('v') {
         return false;
     }
@@ -391,6 +560,7 @@ require bar github.com/foo/bar 1.2.3
                     version: "v1.2.3".to_string(),
                 },
             ],
+            replaces: vec![],
         };
 
         let expected = r#"module github.com/myuser/myproject
@@ -424,6 +594,7 @@ require bar github.com/foo/bar v1.2.3
                     version: "v1.9.0".to_string(),
                 },
             ],
+            replaces: vec![],
         };
 
         assert!(mod_file.find_by_alias("gin").is_some());
@@ -448,6 +619,21 @@ require bar github.com/foo/bar v1.2.3
         assert!(!is_valid_module_path("std/fmt"));
     }
 
+    #[test]
+    fn test_module_path_stays_inside_deps() {
+        assert!(is_valid_module_path("example.com/a..b"));
+        assert!(is_valid_module_path("example.com/.hidden"));
+
+        assert!(!is_valid_module_path(".."));
+        assert!(!is_valid_module_path("../evil"));
+        assert!(!is_valid_module_path("example.com/../../evil"));
+        assert!(!is_valid_module_path("example.com/./foo"));
+        assert!(!is_valid_module_path("example.com/foo/.."));
+        assert!(!is_valid_module_path("."));
+        assert!(!is_valid_module_path("example.com\\..\\evil"));
+        assert!(!is_valid_module_path("example.com/foo\\bar"));
+    }
+
     #[test]
     fn test_add_require() {
         let mut mod_file = ModFile::new("myproject".to_string());
@@ -468,6 +654,25 @@ require bar github.com/foo/bar v1.2.3
         assert_eq!(mod_file.requires.len(), 2);
     }
 
+    #[test]
+    fn test_require_module() {
+        let mut mod_file = ModFile::new("myproject".to_string());
+
+        mod_file.require_module("github.com/foo/go-bar", "v1.0.0");
+        assert_eq!(mod_file.requires[0].alias, "go_bar");
+        assert_eq!(mod_file.requires[0].version, "v1.0.0");
+
+        // Existing require keeps its alias
+        mod_file.requires[0].alias = "bar".to_string();
+        mod_file.require_module("github.com/foo/go-bar", "v1.1.0");
+        assert_eq!(mod_file.requires.len(), 1);
+        assert_eq!(mod_file.requires[0].alias, "bar");
+        assert_eq!(mod_file.requires[0].version, "v1.1.0");
+
+        mod_file.require_module("example.com/2d", "v0.1.0");
+        assert_eq!(mod_file.requires[1].alias, "_2d");
+    }
+
     #[test]
     fn test_parse_invalid_alias() {
         let content = r#"
@@ -549,6 +754,7 @@ require jwt github.com/golang-jwt/jwt v5.0.0
                     version: "v2.0.0-beta.1".to_string(),
                 },
             ],
+            replaces: vec![],
         };
         
         // Serialize to string
@@ -566,4 +772,122 @@ require jwt github.com/golang-jwt/jwt v5.0.0
             assert_eq!(p.version, o.version);
         }
     }
+
+    #[test]
+    fn test_parse_replace_local_path() {
+        let content = r#"
+module myproject
+require bar github.com/foo/bar v1.2.3
+replace github.com/foo/bar => ../bar
+"#;
+        let mod_file = ModFile::parse(content, &PathBuf::from("vo.mod")).unwrap();
+        assert_eq!(mod_file.replaces, vec![Replace {
+            module: "github.com/foo/bar".to_string(),
+            version: None,
+            target: ReplaceTarget::Path("../bar".to_string()),
+        }]);
+        assert!(mod_file.find_replace("github.com/foo/bar", "v1.2.3").is_some());
+        assert!(mod_file.find_replace("github.com/other", "v1.2.3").is_none());
+    }
+
+    #[test]
+    fn test_parse_replace_module_version() {
+        let content = r#"
+module myproject
+replace github.com/foo/bar => github.com/fork/bar v1.2.4
+"#;
+        let mod_file = ModFile::parse(content, &PathBuf::from("vo.mod")).unwrap();
+        assert_eq!(mod_file.replaces[0].target, ReplaceTarget::Module {
+            module: "github.com/fork/bar".to_string(),
+            version: "v1.2.4".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parse_replace_invalid() {
+        for line in [
+            "replace github.com/foo/bar ../bar",
+            "replace github.com/foo/bar => bar",
+            "replace github.com/foo/bar => github.com/fork/bar 1.0",
+            "replace github.com/foo/bar v1.0.0 => ../bar",
+            "replace github.com/foo/bar@1.0 => ../bar",
+            "replace github.com/foo/bar@ => ../bar",
+            "replace github.com/foo/bar => github.com/fork/bar@",
+            "replace github.com/foo/bar => github.com/fork/bar@v1.0.0 v1.0.0",
+            "replace github.com/foo/bar => github.com/fork/bar",
+        ] {
+            let content = format!("module myproject\n{}\n", line);
+            let result = ModFile::parse(&content, &PathBuf::from("vo.mod"));
+            assert!(matches!(result, Err(ModuleError::ParseError { line: 2, .. })), "{}", line);
+        }
+
+        let content = "module myproject\nreplace a.com/x => ../x\nreplace a.com/x => ../y\n";
+        let result = ModFile::parse(content, &PathBuf::from("vo.mod"));
+        assert!(matches!(result, Err(ModuleError::ParseError { line: 3, .. })));
+
+        let content = "module myproject\nreplace a.com/x@v1.0.0 => ../x\nreplace a.com/x@v1.0.0 => ../y\n";
+        let result = ModFile::parse(content, &PathBuf::from("vo.mod"));
+        assert!(matches!(result, Err(ModuleError::ParseError { line: 3, .. })));
+    }
+
+    #[test]
+    fn test_parse_replace_at_version() {
+        let content = r#"
+module myproject
+replace github.com/foo/bar@v1.2.3 => ../bar
+replace github.com/foo/bar => github.com/fork/bar@v1.2.4
+replace github.com/baz/qux@v0.1.0 => github.com/fork/qux@v0.1.1
+"#;
+        let mod_file = ModFile::parse(content, &PathBuf::from("vo.mod")).unwrap();
+        assert_eq!(mod_file.replaces, vec![
+            Replace {
+                module: "github.com/foo/bar".to_string(),
+                version: Some("v1.2.3".to_string()),
+                target: ReplaceTarget::Path("../bar".to_string()),
+            },
+            Replace {
+                module: "github.com/foo/bar".to_string(),
+                version: None,
+                target: ReplaceTarget::Module {
+                    module: "github.com/fork/bar".to_string(),
+                    version: "v1.2.4".to_string(),
+                },
+            },
+            Replace {
+                module: "github.com/baz/qux".to_string(),
+                version: Some("v0.1.0".to_string()),
+                target: ReplaceTarget::Module {
+                    module: "github.com/fork/qux".to_string(),
+                    version: "v0.1.1".to_string(),
+                },
+            },
+        ]);
+
+        // A version-specific replace wins over one for every version
+        let target = |module, version| mod_file.find_replace(module, version).map(|r| &r.target);
+        assert_eq!(target("github.com/foo/bar", "v1.2.3"), Some(&mod_file.replaces[0].target));
+        assert_eq!(target("github.com/foo/bar", "v1.0.0"), Some(&mod_file.replaces[1].target));
+        assert_eq!(target("github.com/baz/qux", "v0.1.0"), Some(&mod_file.replaces[2].target));
+        assert_eq!(target("github.com/baz/qux", "v0.2.0"), None);
+
+        // Written back with the version on the left, and the target as `module version`
+        let written = mod_file.to_string();
+        assert!(written.contains("replace github.com/foo/bar@v1.2.3 => ../bar\n"));
+        assert!(written.contains("replace github.com/baz/qux@v0.1.0 => github.com/fork/qux v0.1.1\n"));
+        let reparsed = ModFile::parse(&written, &PathBuf::from("vo.mod")).unwrap();
+        assert_eq!(reparsed.replaces, mod_file.replaces);
+    }
+
+    #[test]
+    fn test_replace_roundtrip() {
+        let content = r#"module myproject
+
+require bar github.com/foo/bar v1.2.3
+
+replace github.com/foo/bar => ../bar
+replace github.com/baz/qux => github.com/fork/qux v0.2.0
+"#;
+        let mod_file = ModFile::parse(content, &PathBuf::from("vo.mod")).unwrap();
+        assert_eq!(mod_file.to_string(), content);
+    }
 }
@@ -4,7 +4,8 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use crate::error::{ModuleError, ModuleResult};
-use crate::modfile::ModFile;
+use crate::modfile::{ModFile, ReplaceTarget};
+use crate::sumfile::{hash_dir, SumFile};
 
 /// The dependency cache directory name.
 const DEPS_DIR: &str = ".vodeps";
@@ -34,6 +35,9 @@ pub struct ModuleClosure {
     pub root_dir: PathBuf,
     /// All resolved modules (module path -> resolved module).
     pub modules: HashMap<String, ResolvedModule>,
+    /// Modules with no vo.sum entry, as `module@version`. Their contents
+    /// were not verified.
+    pub unverified: Vec<String>,
 }
 
 impl ModuleClosure {
@@ -109,17 +113,42 @@ impl ModuleResolver {
         self.project_root.join(DEPS_DIR)
     }
 
+    /// Returns the path to the vo.sum file.
+    pub fn sum_file_path(&self) -> PathBuf {
+        self.project_root.join("vo.sum")
+    }
+
+    /// Downloads `module@version` into the .vodeps directory and records
+    /// its hash in vo.sum.
+    pub fn download(&self, module: &str, version: &str) -> ModuleResult<PathBuf> {
+        let root = crate::fetch::download_module(&self.deps_dir(), module, version)?;
+        let sum_path = self.sum_file_path();
+        let mut sum = SumFile::parse_file(&sum_path)?;
+        sum.insert(module, version, hash_dir(&root)?)?;
+        sum.write_file(&sum_path)?;
+        Ok(root)
+    }
+
     /// Computes the transitive dependency closure.
     ///
     /// This reads all vo.mod files recursively and builds a complete
     /// module closure. Returns an error if:
     /// - A required module is not found in .vodeps
     /// - The same module is required at different versions
+    /// - A module's contents don't match its vo.sum entry
+    ///
+    /// A module with no vo.sum entry at all (e.g. a project without a
+    /// vo.sum) is not an error; it is listed in `unverified` instead.
+    ///
+    /// Replace directives in `root_mod` redirect a module to a local
+    /// directory or to another module version.
     pub fn compute_closure(&self, root_mod: &ModFile) -> ModuleResult<ModuleClosure> {
+        let sum = SumFile::parse_file(self.sum_file_path())?;
         let mut closure = ModuleClosure {
             root_module: root_mod.module.clone(),
             root_dir: self.project_root.clone(),
             modules: HashMap::new(),
+            unverified: Vec::new(),
         };
 
         // Track visited modules to detect cycles and avoid re-processing
@@ -153,8 +182,8 @@ impl ModuleResolver {
             }
             visited.insert(mod_path.clone());
 
-            // Find the module in .vodeps
-            let versioned_dir = self.find_module_dir(&mod_path, &version)?;
+            // Find the module in .vodeps (or wherever it is replaced to)
+            let versioned_dir = self.locate_module(root_mod, &sum, &mod_path, &version, &mut closure.unverified)?;
 
             // Parse the module's vo.mod
             let dep_mod_file_path = versioned_dir.join("vo.mod");
@@ -190,9 +219,52 @@ impl ModuleResolver {
             }
         }
 
+        closure.unverified.sort();
         Ok(closure)
     }
 
+    /// Finds a required module's root directory and checks it against vo.sum.
+    ///
+    /// Only the root module's replace directives apply. Local replacements
+    /// are not checksummed since they are meant to be edited. A module with
+    /// no vo.sum entry is added to `unverified`.
+    fn locate_module(
+        &self,
+        root_mod: &ModFile,
+        sum: &SumFile,
+        module: &str,
+        version: &str,
+        unverified: &mut Vec<String>,
+    ) -> ModuleResult<PathBuf> {
+        let (module, version) = match root_mod.find_replace(module, version).map(|r| &r.target) {
+            Some(ReplaceTarget::Path(path)) => {
+                let dir = self.project_root.join(path);
+                if !dir.join("vo.mod").is_file() {
+                    return Err(ModuleError::ReplacementNotFound {
+                        module: module.to_string(),
+                        dir,
+                    });
+                }
+                return Ok(dir);
+            }
+            Some(ReplaceTarget::Module { module, version }) => (module.as_str(), version.as_str()),
+            None => (module, version),
+        };
+
+        let dir = self.find_module_dir(module, version)?;
+        if sum.get(module, version).is_none() {
+            unverified.push(format!("{}@{}", module, version));
+            return Ok(dir);
+        }
+        sum.verify(&ResolvedModule {
+            path: module.to_string(),
+            version: version.to_string(),
+            root: dir.clone(),
+            chain: Vec::new(),
+        })?;
+        Ok(dir)
+    }
+
     /// Finds the directory for a module at a specific version.
     fn find_module_dir(&self, module: &str, version: &str) -> ModuleResult<PathBuf> {
         // Expected path: .vodeps/<module>@<version>/
@@ -374,6 +446,128 @@ mod tests {
         assert!(closure.modules.is_empty());
     }
 
+    #[test]
+    fn test_compute_closure_verifies_checksums() {
+        let (temp, resolver) = setup_test_project();
+        fs::write(
+            temp.path().join("vo.mod"),
+            "module github.com/test/project\nrequire bar github.com/foo/bar v1.0.0\n",
+        ).unwrap();
+        let dep = resolver.deps_dir().join("github.com/foo/bar@v1.0.0");
+        fs::create_dir_all(&dep).unwrap();
+        fs::write(dep.join("vo.mod"), "module github.com/foo/bar\n").unwrap();
+        let mod_file = ModFile::parse_file(temp.path().join("vo.mod")).unwrap();
+
+        let mut sum = SumFile::default();
+        sum.insert("github.com/foo/bar", "v1.0.0", hash_dir(&dep).unwrap()).unwrap();
+        sum.write_file(resolver.sum_file_path()).unwrap();
+        let closure = resolver.compute_closure(&mod_file).unwrap();
+        assert_eq!(closure.modules.len(), 1);
+        assert!(closure.unverified.is_empty());
+
+        // Tampered dependency
+        fs::write(dep.join("bar.vo"), "package bar\n").unwrap();
+        let result = resolver.compute_closure(&mod_file);
+        assert!(matches!(result, Err(ModuleError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn test_compute_closure_without_sum_file() {
+        let (temp, resolver) = setup_test_project();
+        fs::write(
+            temp.path().join("vo.mod"),
+            "module github.com/test/project\nrequire bar github.com/foo/bar v1.0.0\nrequire baz github.com/foo/baz v1.0.0\n",
+        ).unwrap();
+        for module in ["bar", "baz"] {
+            let dep = resolver.deps_dir().join(format!("github.com/foo/{}@v1.0.0", module));
+            fs::create_dir_all(&dep).unwrap();
+            fs::write(dep.join("vo.mod"), format!("module github.com/foo/{}\n", module)).unwrap();
+        }
+        let mod_file = ModFile::parse_file(temp.path().join("vo.mod")).unwrap();
+
+        // No vo.sum: everything resolves, unverified
+        let closure = resolver.compute_closure(&mod_file).unwrap();
+        assert_eq!(closure.modules.len(), 2);
+        assert_eq!(closure.unverified, vec!["github.com/foo/bar@v1.0.0", "github.com/foo/baz@v1.0.0"]);
+
+        // Entries that are present are still checked
+        let mut sum = SumFile::default();
+        sum.insert("github.com/foo/bar", "v1.0.0", "h1:0000".to_string()).unwrap();
+        sum.write_file(resolver.sum_file_path()).unwrap();
+        let result = resolver.compute_closure(&mod_file);
+        assert!(matches!(result, Err(ModuleError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn test_replace_with_local_path() {
+        let (temp, resolver) = setup_test_project();
+        fs::write(
+            temp.path().join("vo.mod"),
+            "module github.com/test/project\nrequire bar github.com/foo/bar v1.0.0\nreplace github.com/foo/bar => ./local/bar\n",
+        ).unwrap();
+        let local = temp.path().join("local/bar");
+        fs::create_dir_all(local.join("pkg")).unwrap();
+        fs::write(local.join("vo.mod"), "module github.com/foo/bar\n").unwrap();
+
+        // Local replacements need neither .vodeps nor vo.sum.
+        let mod_file = ModFile::parse_file(temp.path().join("vo.mod")).unwrap();
+        let closure = resolver.compute_closure(&mod_file).unwrap();
+        assert_eq!(closure.modules["github.com/foo/bar"].root, local);
+
+        let resolved = resolver.resolve_import("github.com/foo/bar/pkg", &closure).unwrap();
+        assert_eq!(resolved.dir, local.join("pkg"));
+
+        fs::remove_file(local.join("vo.mod")).unwrap();
+        let result = resolver.compute_closure(&mod_file);
+        assert!(matches!(result, Err(ModuleError::ReplacementNotFound { .. })));
+    }
+
+    #[test]
+    fn test_replace_only_matching_version() {
+        let (temp, resolver) = setup_test_project();
+        let local = temp.path().join("local/bar");
+        fs::create_dir_all(&local).unwrap();
+        fs::write(local.join("vo.mod"), "module github.com/foo/bar\n").unwrap();
+        let dep = resolver.deps_dir().join("github.com/foo/bar@v1.0.0");
+        fs::create_dir_all(&dep).unwrap();
+        fs::write(dep.join("vo.mod"), "module github.com/foo/bar\n").unwrap();
+
+        let closure_root = |replace: &str| {
+            fs::write(
+                temp.path().join("vo.mod"),
+                format!("module github.com/test/project\nrequire bar github.com/foo/bar v1.0.0\n{}\n", replace),
+            ).unwrap();
+            let mod_file = ModFile::parse_file(temp.path().join("vo.mod")).unwrap();
+            resolver.compute_closure(&mod_file).unwrap().modules["github.com/foo/bar"].root.clone()
+        };
+        assert_eq!(closure_root("replace github.com/foo/bar@v1.0.0 => ./local/bar"), local);
+        assert_eq!(closure_root("replace github.com/foo/bar@v2.0.0 => ./local/bar"), dep);
+    }
+
+    #[test]
+    fn test_replace_with_module_version() {
+        let (temp, resolver) = setup_test_project();
+        fs::write(
+            temp.path().join("vo.mod"),
+            "module github.com/test/project\nrequire bar github.com/foo/bar v1.0.0\nreplace github.com/foo/bar => github.com/fork/bar v1.0.1\n",
+        ).unwrap();
+        let fork = resolver.deps_dir().join("github.com/fork/bar@v1.0.1");
+        fs::create_dir_all(&fork).unwrap();
+        fs::write(fork.join("vo.mod"), "module github.com/fork/bar\n").unwrap();
+        let mut sum = SumFile::default();
+        sum.insert("github.com/fork/bar", "v1.0.1", hash_dir(&fork).unwrap()).unwrap();
+        sum.write_file(resolver.sum_file_path()).unwrap();
+
+        let mod_file = ModFile::parse_file(temp.path().join("vo.mod")).unwrap();
+        let closure = resolver.compute_closure(&mod_file).unwrap();
+        let bar = &closure.modules["github.com/foo/bar"];
+        assert_eq!(bar.root, fork);
+        assert_eq!(bar.version, "v1.0.0");
+
+        let resolved = resolver.resolve_import("github.com/foo/bar", &closure).unwrap();
+        assert_eq!(resolved.dir, fork);
+    }
+
     #[test]
     fn test_resolve_root_module_package() {
         let (temp, resolver) = setup_test_project();
@@ -441,6 +635,7 @@ mod tests {
             root_module: "github.com/test/project".to_string(),
             root_dir: PathBuf::from("/tmp"),
             modules: HashMap::new(),
+            unverified: Vec::new(),
         };
 
         assert!(closure.is_root_module_path("github.com/test/project"));
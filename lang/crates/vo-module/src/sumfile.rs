@@ -0,0 +1,277 @@
+//! Parser for vo.sum files.
+//!
+//! Format:
+//! ```text
+//! <module-path> <version> h1:<sha256-hex>
+//! ```
+//!
+//! Each line records the content hash of a downloaded module. The hash covers
+//! every file in the module tree (relative path and contents, in sorted path
+//! order), so a dependency swapped or edited in `.vodeps` no longer matches.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{ModuleError, ModuleResult};
+use crate::resolver::ResolvedModule;
+
+/// Prefix identifying the hash algorithm.
+const HASH_PREFIX: &str = "h1:";
+
+/// A parsed vo.sum file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SumFile {
+    /// Recorded hashes, in file order.
+    pub entries: Vec<SumEntry>,
+}
+
+/// A single vo.sum line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumEntry {
+    /// The module path (e.g., "github.com/foo/bar").
+    pub module: String,
+
+    /// The exact version (e.g., "v1.2.3").
+    pub version: String,
+
+    /// The content hash, including the `h1:` prefix.
+    pub hash: String,
+}
+
+impl SumFile {
+    /// Parses a vo.sum file. A missing file is an empty sum file.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> ModuleResult<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(SumFile::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| ModuleError::IoError(path.to_path_buf(), e.to_string()))?;
+
+        Self::parse(&content, path)
+    }
+
+    /// Parses vo.sum content from a string.
+    pub fn parse(content: &str, file_path: &Path) -> ModuleResult<Self> {
+        let mut entries = Vec::new();
+
+        for (line_num, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 3 || !parts[2].starts_with(HASH_PREFIX) {
+                return Err(ModuleError::ParseError {
+                    file: file_path.to_path_buf(),
+                    line: line_num + 1,
+                    message: format!("invalid sum line, expected: <module> <version> h1:<hash>, got: {}", line),
+                });
+            }
+
+            entries.push(SumEntry {
+                module: parts[0].to_string(),
+                version: parts[1].to_string(),
+                hash: parts[2].to_string(),
+            });
+        }
+
+        Ok(SumFile { entries })
+    }
+
+    /// Finds the recorded hash for `module@version`.
+    pub fn get(&self, module: &str, version: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.module == module && e.version == version)
+            .map(|e| e.hash.as_str())
+    }
+
+    /// Records a hash for `module@version`.
+    ///
+    /// Returns an error if a different hash is already recorded.
+    pub fn insert(&mut self, module: &str, version: &str, hash: String) -> ModuleResult<()> {
+        match self.get(module, version) {
+            Some(existing) if existing == hash => Ok(()),
+            Some(existing) => Err(ModuleError::ChecksumMismatch {
+                module: module.to_string(),
+                version: version.to_string(),
+                expected: existing.to_string(),
+                actual: hash,
+            }),
+            None => {
+                self.entries.push(SumEntry {
+                    module: module.to_string(),
+                    version: version.to_string(),
+                    hash,
+                });
+                self.entries.sort_by(|a, b| (&a.module, &a.version).cmp(&(&b.module, &b.version)));
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks a resolved module's contents against its recorded hash.
+    pub fn verify(&self, resolved: &ResolvedModule) -> ModuleResult<()> {
+        let expected = self.get(&resolved.path, &resolved.version).ok_or_else(|| {
+            ModuleError::ChecksumMissing {
+                module: resolved.path.clone(),
+                version: resolved.version.clone(),
+            }
+        })?;
+
+        let actual = hash_dir(&resolved.root)?;
+        if actual != expected {
+            return Err(ModuleError::ChecksumMismatch {
+                module: resolved.path.clone(),
+                version: resolved.version.clone(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes the SumFile to a file.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> ModuleResult<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string())
+            .map_err(|e| ModuleError::IoError(path.to_path_buf(), e.to_string()))
+    }
+}
+
+impl fmt::Display for SumFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{} {} {}", entry.module, entry.version, entry.hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the `h1:` content hash of a module directory.
+pub fn hash_dir(root: &Path) -> ModuleResult<String> {
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for rel in &files {
+        let path = root.join(rel);
+        let content = fs::read(&path).map_err(|e| ModuleError::IoError(path.clone(), e.to_string()))?;
+        // Separators keep "a" + "bc" distinct from "ab" + "c".
+        hasher.update(rel.as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    let digest = hasher.finalize();
+    let mut hash = String::from(HASH_PREFIX);
+    for byte in digest {
+        hash.push_str(&format!("{:02x}", byte));
+    }
+    Ok(hash)
+}
+
+/// Collects file paths under `dir` relative to `root`, `/`-separated.
+fn collect_files(root: &Path, dir: &Path, out: &mut Vec<String>) -> ModuleResult<()> {
+    let entries = fs::read_dir(dir).map_err(|e| ModuleError::IoError(dir.to_path_buf(), e.to_string()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| ModuleError::IoError(dir.to_path_buf(), e.to_string()))?;
+        let path: PathBuf = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, out)?;
+        } else {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            let parts: Vec<_> = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            out.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn make_module(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn resolved(dir: &TempDir) -> ResolvedModule {
+        ResolvedModule {
+            path: "github.com/foo/bar".to_string(),
+            version: "v1.0.0".to_string(),
+            root: dir.path().to_path_buf(),
+            chain: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_roundtrip() {
+        let content = "github.com/a/b v1.0.0 h1:00ff\ngithub.com/c/d v0.2.0 h1:abcd\n";
+        let sum = SumFile::parse(content, &PathBuf::from("vo.sum")).unwrap();
+        assert_eq!(sum.entries.len(), 2);
+        assert_eq!(sum.get("github.com/c/d", "v0.2.0"), Some("h1:abcd"));
+        assert_eq!(sum.get("github.com/c/d", "v0.3.0"), None);
+        assert_eq!(sum.to_string(), content);
+    }
+
+    #[test]
+    fn test_parse_invalid_line() {
+        let result = SumFile::parse("github.com/a/b v1.0.0\n", &PathBuf::from("vo.sum"));
+        assert!(matches!(result, Err(ModuleError::ParseError { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_hash_dir_is_stable() {
+        let a = make_module(&[("vo.mod", "module x\n"), ("pkg/a.vo", "package pkg\n")]);
+        let b = make_module(&[("pkg/a.vo", "package pkg\n"), ("vo.mod", "module x\n")]);
+        let c = make_module(&[("vo.mod", "module x\n"), ("pkg/a.vo", "package pkg // edited\n")]);
+        let hash = hash_dir(a.path()).unwrap();
+        assert!(hash.starts_with("h1:"));
+        assert_eq!(hash.len(), 3 + 64);
+        assert_eq!(hash, hash_dir(b.path()).unwrap());
+        assert_ne!(hash, hash_dir(c.path()).unwrap());
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = make_module(&[("vo.mod", "module github.com/foo/bar\n")]);
+        let module = resolved(&dir);
+
+        let mut sum = SumFile::default();
+        assert!(matches!(sum.verify(&module), Err(ModuleError::ChecksumMissing { .. })));
+
+        sum.insert(&module.path, &module.version, hash_dir(dir.path()).unwrap()).unwrap();
+        sum.verify(&module).unwrap();
+
+        fs::write(dir.path().join("evil.vo"), "package bar\n").unwrap();
+        let err = sum.verify(&module).unwrap_err();
+        assert!(matches!(err, ModuleError::ChecksumMismatch { .. }));
+        assert!(err.to_string().starts_with("checksum mismatch for github.com/foo/bar@v1.0.0"));
+    }
+
+    #[test]
+    fn test_insert_conflict() {
+        let mut sum = SumFile::default();
+        sum.insert("github.com/a/b", "v1.0.0", "h1:aa".to_string()).unwrap();
+        sum.insert("github.com/a/b", "v1.0.0", "h1:aa".to_string()).unwrap();
+        assert_eq!(sum.entries.len(), 1);
+        let result = sum.insert("github.com/a/b", "v1.0.0", "h1:bb".to_string());
+        assert!(matches!(result, Err(ModuleError::ChecksumMismatch { .. })));
+    }
+}
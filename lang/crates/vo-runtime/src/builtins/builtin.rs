@@ -67,119 +67,23 @@ fn builtin_assert(call: &mut ExternCallContext) -> ExternResult {
 }
 
 fn builtin_copy(call: &mut ExternCallContext) -> ExternResult {
-    use crate::gc::Gc;
-    use crate::objects::{slice, array, string as str_obj};
-    use vo_common_core::types::ValueKind;
-    
     let dst = call.arg_ref(0);
     let src = call.arg_ref(1);
-    
-    if dst.is_null() || src.is_null() {
-        call.ret_i64(0, 0);
-        return ExternResult::Ok;
-    }
-    
-    let dst_len = slice::len(dst);
-    
-    // Check if src is a string (copy([]byte, string) case)
-    let src_kind = Gc::header(src).value_meta.value_kind();
-    let (src_len, src_ptr) = if src_kind == ValueKind::String {
-        let len = str_obj::len(src);
-        let bytes = str_obj::as_bytes(src);
-        (len, bytes.as_ptr() as *mut u8)
-    } else {
-        (slice::len(src), slice::data_ptr(src))
-    };
-    
-    let copy_len = dst_len.min(src_len);
-    
-    if copy_len == 0 {
-        call.ret_i64(0, 0);
-        return ExternResult::Ok;
-    }
-    
-    let dst_arr = slice::array_ref(dst);
-    let elem_bytes = array::elem_bytes(dst_arr);
-    let dst_ptr = slice::data_ptr(dst);
-    
-    // Use copy (not copy_nonoverlapping) to support overlapping regions (Go semantics)
-    unsafe { core::ptr::copy(src_ptr, dst_ptr, copy_len * elem_bytes) };
-    
-    call.ret_i64(0, copy_len as i64);
+    let n = crate::objects::slice::copy(call.gc(), dst, src);
+    call.ret_i64(0, n as i64);
     ExternResult::Ok
 }
 
 /// append(slice, other...) - append all elements from other slice/string
 /// Works for both slice and string sources since they have identical memory layout.
 fn builtin_slice_append_slice(call: &mut ExternCallContext) -> ExternResult {
-    use crate::objects::{slice, array};
     use vo_common_core::types::ValueMeta;
-    
+
     let dst = call.arg_ref(0);
     let src = call.arg_ref(1);
     let elem_meta = ValueMeta::from_raw(call.arg_u64(2) as u32);
-    
-    // Handle nil src
-    if src.is_null() {
-        call.ret_ref(0, dst);
-        return ExternResult::Ok;
-    }
-    
-    // String and slice have identical layout, so we can use slice:: functions for both
-    let src_len = slice::len(src);
-    if src_len == 0 {
-        call.ret_ref(0, dst);
-        return ExternResult::Ok;
-    }
-    
-    let elem_bytes = if dst.is_null() {
-        array::elem_bytes(slice::array_ref(src))
-    } else {
-        array::elem_bytes(slice::array_ref(dst))
-    };
-    
-    // Handle nil dst
-    if dst.is_null() {
-        let new_cap = src_len.max(4);
-        let new_arr = array::create(call.gc(), elem_meta, elem_bytes, new_cap);
-        let src_ptr = slice::data_ptr(src);
-        let dst_ptr = array::data_ptr_bytes(new_arr);
-        unsafe { core::ptr::copy_nonoverlapping(src_ptr, dst_ptr, src_len * elem_bytes) };
-        let result = slice::from_array_range(call.gc(), new_arr, 0, src_len);
-        call.ret_ref(0, result);
-        return ExternResult::Ok;
-    }
-    
-    let dst_len = slice::len(dst);
-    let dst_cap = slice::cap(dst);
-    let new_len = dst_len + src_len;
-    
-    if new_len <= dst_cap {
-        // Enough capacity - write to existing backing array, return new slice header
-        let dst_ptr = slice::data_ptr(dst);
-        let src_ptr = slice::data_ptr(src);
-        unsafe {
-            let write_ptr = dst_ptr.add(dst_len * elem_bytes);
-            core::ptr::copy_nonoverlapping(src_ptr, write_ptr, src_len * elem_bytes);
-        }
-        // Go semantics: append never modifies original slice header
-        let new_s = slice::with_new_len(call.gc(), dst, new_len);
-        call.ret_ref(0, new_s);
-    } else {
-        // Need to grow - allocate new array
-        let new_cap = (new_len * 2).max(4);
-        let new_arr = array::create(call.gc(), elem_meta, elem_bytes, new_cap);
-        let new_arr_ptr = array::data_ptr_bytes(new_arr);
-        let dst_ptr = slice::data_ptr(dst);
-        let src_ptr = slice::data_ptr(src);
-        unsafe {
-            core::ptr::copy_nonoverlapping(dst_ptr, new_arr_ptr, dst_len * elem_bytes);
-            core::ptr::copy_nonoverlapping(src_ptr, new_arr_ptr.add(dst_len * elem_bytes), src_len * elem_bytes);
-        }
-        let result = slice::from_array_range(call.gc(), new_arr, 0, new_len);
-        call.ret_ref(0, result);
-    }
-    
+    let result = crate::objects::slice::append_slice(call.gc(), elem_meta, dst, src);
+    call.ret_ref(0, result);
     ExternResult::Ok
 }
 
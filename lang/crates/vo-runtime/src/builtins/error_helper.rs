@@ -8,16 +8,24 @@ use crate::objects::{interface, struct_ops};
 
 /// Create an error and return as interface slots (slot0, slot1).
 /// Used by vo_errors! macro and write_error_to.
+///
+/// Panics if the program doesn't link the errors package; see `try_create_error`.
 pub fn create_error(call: &mut ExternCallContext, msg: &str) -> (u64, u64) {
+    try_create_error(call, msg).expect("errors.Error not found")
+}
+
+/// Like `create_error`, but returns None when the program doesn't link the
+/// errors package, so there is no `*errors.Error` type to build.
+pub fn try_create_error(call: &mut ExternCallContext, msg: &str) -> Option<(u64, u64)> {
     let wk = call.well_known();
     
-    let named_type_id = wk.error_named_type_id.expect("errors.Error not found");
-    let error_iface_meta_id = wk.error_iface_meta_id.expect("error interface not found");
-    let error_ptr_rttid = wk.error_ptr_rttid.expect("*errors.Error rttid not found");
-    let struct_meta_id = wk.error_struct_meta_id.expect("errors.Error struct_meta_id not found");
-    let field_offsets = wk.error_field_offsets.expect("errors.Error field_offsets not found");
+    let named_type_id = wk.error_named_type_id?;
+    let error_iface_meta_id = wk.error_iface_meta_id?;
+    let error_ptr_rttid = wk.error_ptr_rttid?;
+    let struct_meta_id = wk.error_struct_meta_id?;
+    let field_offsets = wk.error_field_offsets?;
     
-    let struct_meta = call.struct_meta(struct_meta_id as usize).expect("struct meta not found");
+    let struct_meta = call.struct_meta(struct_meta_id as usize)?;
     let slots = struct_meta.slot_count() as usize;
     let err_obj = struct_ops::create(call.gc(), struct_meta_id, slots);
     let err_str = call.alloc_str(msg);
@@ -31,7 +39,7 @@ pub fn create_error(call: &mut ExternCallContext, msg: &str) -> (u64, u64) {
 
     let itab_id = call.get_or_create_itab(named_type_id, error_iface_meta_id, true);
     let err_slot0 = interface::pack_slot0(itab_id, error_ptr_rttid, ValueKind::Pointer);
-    (err_slot0, err_obj as u64)
+    Some((err_slot0, err_obj as u64))
 }
 
 /// Create an error with a cause and return as interface slots (slot0, slot1).
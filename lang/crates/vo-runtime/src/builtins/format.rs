@@ -1,11 +1,21 @@
 //! Value formatting utilities.
 //!
 //! Used by builtin print/println and fmt package.
+//!
+//! Composite values are walked by `print_value`, which follows Go's
+//! `printValue`: structs print as `{1 s}`, arrays and slices as `[1 2]`,
+//! maps as `map[a:1]` with sorted keys, and a pointer to one of these as
+//! `&{...}` when it is the value being printed. The `ValuePrinter` it writes
+//! to prints everything else, so fmt can apply its verbs to each element.
+
+use core::cmp::Ordering;
 
 use crate::gc::{Gc, GcRef};
-use crate::objects::{interface, slice, string as str_obj};
+use crate::objects::{array, interface, map, slice, string as str_obj};
 use crate::ffi::ExternCallContext;
-use vo_common_core::types::ValueKind;
+use crate::slot::SLOT_BYTES;
+use vo_common_core::runtime_type::{ChanDir, RuntimeType};
+use vo_common_core::types::{ValueKind, ValueRttid};
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
@@ -13,7 +23,9 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 use alloc::string::{String, ToString};
 #[cfg(not(feature = "std"))]
-use alloc::format;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec};
 
 #[cfg(feature = "std")]
 use std::string::{String, ToString};
@@ -61,7 +73,23 @@ pub fn format_interface(slot0: u64, slot1: u64) -> String {
 }
 
 /// Format interface with optional ExternCallContext for error message extraction.
+/// With a context, composite values are printed element by element.
 pub fn format_interface_with_ctx(slot0: u64, slot1: u64, call: Option<&ExternCallContext>) -> String {
+    if let Some(call) = call {
+        if let Some(msg) = format_error(slot0, slot1, call) {
+            return msg;
+        }
+        if is_composite(slot0, slot1, 0, call) {
+            let mut p = PlainPrinter { buf: Vec::new(), call };
+            print_value(&mut p, slot0, slot1, 'v', 0, call);
+            return String::from_utf8_lossy(&p.buf).into_owned();
+        }
+    }
+    format_leaf(slot0, slot1)
+}
+
+/// Format a value without looking inside composites.
+fn format_leaf(slot0: u64, slot1: u64) -> String {
     let vk = interface::unpack_value_kind(slot0);
     
     match vk {
@@ -80,22 +108,7 @@ pub fn format_interface_with_ctx(slot0: u64, slot1: u64, call: Option<&ExternCal
         ValueKind::String => {
             str_obj::as_str(slot1 as GcRef).to_string()
         }
-        ValueKind::Pointer => {
-            // Check if this is an error interface
-            if let Some(ctx) = call {
-                let rttid = interface::unpack_rttid(slot0);
-                let wk = ctx.well_known();
-                if wk.error_ptr_rttid.map_or(false, |err_rttid| rttid == err_rttid) {
-                    if let Some(field_offsets) = wk.error_field_offsets {
-                        let ptr = slot1 as GcRef;
-                        if !ptr.is_null() {
-                            return format_error_chain(ptr, field_offsets, ctx);
-                        }
-                    }
-                }
-            }
-            format!("0x{:x}", slot1)
-        }
+        ValueKind::Pointer => format!("0x{:x}", slot1),
         ValueKind::Slice => format_slice_value(slot1 as GcRef),
         ValueKind::Map => format!("map[...]"),
         ValueKind::Channel => format!("0x{:x}", slot1),
@@ -108,6 +121,22 @@ pub fn format_interface_with_ctx(slot0: u64, slot1: u64, call: Option<&ExternCal
     }
 }
 
+/// The message of a non-nil builtin error value, or None for anything else.
+pub fn format_error(slot0: u64, slot1: u64, ctx: &ExternCallContext) -> Option<String> {
+    if interface::unpack_value_kind(slot0) != ValueKind::Pointer {
+        return None;
+    }
+    let wk = ctx.well_known();
+    if wk.error_ptr_rttid != Some(interface::unpack_rttid(slot0)) {
+        return None;
+    }
+    let ptr = slot1 as GcRef;
+    if ptr.is_null() {
+        return None;
+    }
+    Some(format_error_chain(ptr, wk.error_field_offsets?, ctx))
+}
+
 /// Format error chain recursively: "msg: cause_msg: cause_cause_msg..."
 /// field_offsets: [msg, cause]
 fn format_error_chain(ptr: GcRef, field_offsets: [u16; 2], ctx: &ExternCallContext) -> String {
@@ -159,3 +188,448 @@ fn format_slice_value(slice_ref: GcRef) -> String {
     }
     format!("[len={}]", len)
 }
+
+// =============================================================================
+// Type names
+// =============================================================================
+
+/// Convert ValueKind to Go type name string for %T verb.
+pub fn value_kind_to_type_name(vk: ValueKind) -> String {
+    match vk {
+        ValueKind::Void => "<nil>".to_string(),
+        ValueKind::Bool => "bool".to_string(),
+        ValueKind::Int => "int".to_string(),
+        ValueKind::Int8 => "int8".to_string(),
+        ValueKind::Int16 => "int16".to_string(),
+        ValueKind::Int32 => "int32".to_string(),
+        ValueKind::Int64 => "int64".to_string(),
+        ValueKind::Uint => "uint".to_string(),
+        ValueKind::Uint8 => "uint8".to_string(),
+        ValueKind::Uint16 => "uint16".to_string(),
+        ValueKind::Uint32 => "uint32".to_string(),
+        ValueKind::Uint64 => "uint64".to_string(),
+        ValueKind::Float32 => "float32".to_string(),
+        ValueKind::Float64 => "float64".to_string(),
+        ValueKind::String => "string".to_string(),
+        ValueKind::Slice => "[]...".to_string(),
+        ValueKind::Map => "map[...]...".to_string(),
+        ValueKind::Channel => "chan ...".to_string(),
+        ValueKind::Closure => "func(...)".to_string(),
+        ValueKind::Pointer => "*...".to_string(),
+        ValueKind::Array => "[...]...".to_string(),
+        ValueKind::Struct => "struct{...}".to_string(),
+        ValueKind::Interface => "interface{}".to_string(),
+        ValueKind::Port => "port ...".to_string(),
+        ValueKind::Island => "island".to_string(),
+    }
+}
+
+/// Go spelling of the type `rttid`, as %T and %#v print it.
+pub fn rttid_type_name(rttid: u32, call: &ExternCallContext) -> String {
+    let name = |rttid: u32| rttid_type_name(rttid, call);
+    let list = |rttids: &mut dyn Iterator<Item = u32>| {
+        rttids.map(name).collect::<Vec<_>>().join(", ")
+    };
+    match call.runtime_types().get(rttid as usize) {
+        None => "?".to_string(),
+        Some(RuntimeType::Basic(vk)) => value_kind_to_type_name(*vk),
+        Some(RuntimeType::Named { id, .. }) => {
+            call.named_type_meta(*id as usize).map(|m| m.name.clone()).unwrap_or_default()
+        }
+        Some(RuntimeType::Pointer(elem)) => format!("*{}", name(elem.rttid())),
+        Some(RuntimeType::Array { len, elem }) => format!("[{}]{}", len, name(elem.rttid())),
+        Some(RuntimeType::Slice(elem)) => format!("[]{}", name(elem.rttid())),
+        Some(RuntimeType::Map { key, val }) => format!("map[{}]{}", name(key.rttid()), name(val.rttid())),
+        Some(RuntimeType::Chan { dir, elem }) => {
+            let prefix = match dir {
+                ChanDir::Both => "chan ",
+                ChanDir::Send => "chan<- ",
+                ChanDir::Recv => "<-chan ",
+            };
+            format!("{}{}", prefix, name(elem.rttid()))
+        }
+        Some(RuntimeType::Func { params, results, variadic }) => {
+            let mut params: Vec<String> = params.iter().map(|p| name(p.rttid())).collect();
+            if *variadic {
+                if let Some(last) = params.last_mut() {
+                    *last = format!("...{}", last.trim_start_matches("[]"));
+                }
+            }
+            let results = match results.len() {
+                0 => String::new(),
+                1 => format!(" {}", name(results[0].rttid())),
+                _ => format!(" ({})", list(&mut results.iter().map(|r| r.rttid()))),
+            };
+            format!("func({}){}", params.join(", "), results)
+        }
+        Some(RuntimeType::Struct { fields, .. }) => {
+            if fields.is_empty() {
+                return "struct {}".to_string();
+            }
+            let fields: Vec<String> = fields.iter().map(|f| {
+                if f.embedded { name(f.typ.rttid()) } else { format!("{} {}", f.name, name(f.typ.rttid())) }
+            }).collect();
+            format!("struct {{ {} }}", fields.join("; "))
+        }
+        Some(RuntimeType::Interface { methods, .. }) => {
+            if methods.is_empty() {
+                return "interface {}".to_string();
+            }
+            let methods: Vec<String> = methods.iter().map(|m| {
+                format!("{}{}", m.name, name(m.sig.rttid()).trim_start_matches("func"))
+            }).collect();
+            format!("interface {{ {} }}", methods.join("; "))
+        }
+        Some(RuntimeType::Tuple(elems)) => format!("({})", list(&mut elems.iter().map(|e| e.rttid()))),
+        Some(RuntimeType::Port(elem)) => format!("port[{}]", name(elem.rttid())),
+        Some(RuntimeType::Island) => "island".to_string(),
+    }
+}
+
+// =============================================================================
+// Composite values
+// =============================================================================
+
+/// Values nested deeper than this can only come from one that contains
+/// itself, through an interface, slice or map; they print as `...`.
+const MAX_DEPTH: usize = 100;
+
+/// Where `print_value` writes, and how it prints what it doesn't walk.
+pub trait ValuePrinter {
+    fn buf(&mut self) -> &mut Vec<u8>;
+
+    /// Print an interface{} value found at `depth`, unless it should be
+    /// walked as a composite (see `is_composite`). Returns whether it printed.
+    fn print_leaf(&mut self, slot0: u64, slot1: u64, verb: char, depth: usize) -> bool;
+
+    /// %+v: name struct fields.
+    fn plus_v(&self) -> bool {
+        false
+    }
+
+    /// %#v: Go syntax, with type names, quoted strings and ", " separators.
+    fn sharp_v(&self) -> bool {
+        false
+    }
+}
+
+/// Plain `%v` text, for print/println and error messages.
+struct PlainPrinter<'a, 'c> {
+    buf: Vec<u8>,
+    call: &'a ExternCallContext<'c>,
+}
+
+impl ValuePrinter for PlainPrinter<'_, '_> {
+    fn buf(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    fn print_leaf(&mut self, slot0: u64, slot1: u64, _verb: char, depth: usize) -> bool {
+        if let Some(msg) = format_error(slot0, slot1, self.call) {
+            self.buf.extend_from_slice(msg.as_bytes());
+            return true;
+        }
+        if is_composite(slot0, slot1, depth, self.call) {
+            return false;
+        }
+        self.buf.extend_from_slice(format_leaf(slot0, slot1).as_bytes());
+        true
+    }
+}
+
+/// Whether `print_value` walks this value: structs, arrays, slices and maps,
+/// and at depth 0 a non-nil pointer to one of those.
+pub fn is_composite(slot0: u64, slot1: u64, depth: usize, call: &ExternCallContext) -> bool {
+    match interface::unpack_value_kind(slot0) {
+        ValueKind::Struct | ValueKind::Array | ValueKind::Slice | ValueKind::Map => true,
+        ValueKind::Pointer if depth == 0 && slot1 != 0 => {
+            let pointee = call.get_elem_value_rttid_from_base(interface::unpack_rttid(slot0));
+            matches!(
+                pointee.value_kind(),
+                ValueKind::Struct | ValueKind::Array | ValueKind::Slice | ValueKind::Map
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Print an interface{} value found at `depth`, walking composites and
+/// handing everything else, each element included, to `p.print_leaf`.
+pub fn print_value<P: ValuePrinter>(
+    p: &mut P,
+    slot0: u64,
+    slot1: u64,
+    verb: char,
+    depth: usize,
+    call: &ExternCallContext,
+) {
+    if p.print_leaf(slot0, slot1, verb, depth) {
+        return;
+    }
+    if depth > MAX_DEPTH {
+        p.buf().extend_from_slice(b"...");
+        return;
+    }
+    let rttid = interface::unpack_rttid(slot0);
+    match interface::unpack_value_kind(slot0) {
+        ValueKind::Struct => {
+            // Boxed structs are their slots
+            let zeros;
+            let mut ptr = slot1 as *const u8;
+            if ptr.is_null() {
+                zeros = vec![0u64; slot_count(rttid, call)];
+                ptr = zeros.as_ptr() as *const u8;
+            }
+            print_struct(p, rttid, ptr, verb, depth, call);
+        }
+        ValueKind::Array => {
+            let arr = slot1 as GcRef;
+            let seq = if arr.is_null() {
+                Some((core::ptr::null(), 0, 0))
+            } else {
+                Some((array::data_ptr_bytes(arr) as *const u8, array::len(arr), array::elem_bytes(arr)))
+            };
+            print_seq(p, rttid, seq, verb, depth, call);
+        }
+        ValueKind::Slice => {
+            let s = slot1 as GcRef;
+            let seq = if s.is_null() {
+                None
+            } else {
+                Some((slice::data_ptr(s) as *const u8, slice::len(s), array::elem_bytes(slice::array_ref(s))))
+            };
+            print_seq(p, rttid, seq, verb, depth, call);
+        }
+        ValueKind::Map => print_map(p, rttid, slot1 as GcRef, verb, depth, call),
+        ValueKind::Pointer => {
+            p.buf().push(b'&');
+            let pointee = call.get_elem_value_rttid_from_base(rttid);
+            print_stored(p, pointee, slot1 as *const u8, SLOT_BYTES, verb, depth + 1, call);
+        }
+        // print_leaf prints everything else
+        _ => {}
+    }
+}
+
+/// Print a value of type `typ` stored at `ptr`: a struct field, array
+/// element or map entry. Structs and arrays are stored in place, one slot
+/// per scalar; other values take one slot, or `bytes` when packed smaller.
+fn print_stored<P: ValuePrinter>(
+    p: &mut P,
+    typ: ValueRttid,
+    ptr: *const u8,
+    bytes: usize,
+    verb: char,
+    depth: usize,
+    call: &ExternCallContext,
+) {
+    let rttid = typ.rttid();
+    match typ.value_kind() {
+        ValueKind::Struct => print_struct(p, rttid, ptr, verb, depth, call),
+        ValueKind::Array => {
+            let elem = call.get_elem_value_rttid_from_base(rttid);
+            let len = call.get_array_len_from_rttid(rttid);
+            let stride = slot_count(elem.rttid(), call) * SLOT_BYTES;
+            print_seq(p, rttid, Some((ptr, len, stride)), verb, depth, call);
+        }
+        ValueKind::Interface => {
+            let slot0 = unsafe { *(ptr as *const u64) };
+            let slot1 = unsafe { *(ptr as *const u64).add(1) };
+            if interface::is_nil(slot0) {
+                if p.sharp_v() {
+                    let name = rttid_type_name(rttid, call);
+                    let buf = p.buf();
+                    buf.extend_from_slice(name.as_bytes());
+                    buf.extend_from_slice(b"(nil)");
+                } else {
+                    p.buf().extend_from_slice(b"<nil>");
+                }
+            } else {
+                print_value(p, slot0, slot1, verb, depth + 1, call);
+            }
+        }
+        vk => {
+            let val = unsafe {
+                match bytes {
+                    1 => *ptr as u64,
+                    2 => *(ptr as *const u16) as u64,
+                    4 => *(ptr as *const u32) as u64,
+                    _ => *(ptr as *const u64),
+                }
+            };
+            print_value(p, interface::pack_slot0(0, rttid, vk), val, verb, depth, call);
+        }
+    }
+}
+
+/// `{1 s}`; `{X:1 S:s}` for %+v; `main.P{X:1, S:"s"}` for %#v.
+fn print_struct<P: ValuePrinter>(
+    p: &mut P,
+    rttid: u32,
+    ptr: *const u8,
+    verb: char,
+    depth: usize,
+    call: &ExternCallContext,
+) {
+    let sharp_v = p.sharp_v();
+    let names = sharp_v || p.plus_v();
+    if sharp_v {
+        let name = rttid_type_name(rttid, call);
+        p.buf().extend_from_slice(name.as_bytes());
+    }
+    p.buf().push(b'{');
+    let fields = struct_meta_id(rttid, call)
+        .and_then(|id| call.struct_meta(id as usize))
+        .map_or(&[][..], |meta| &meta.fields[..]);
+    for (i, field) in fields.iter().enumerate() {
+        let buf = p.buf();
+        if i > 0 {
+            buf.extend_from_slice(if sharp_v { b", " } else { b" " });
+        }
+        if names {
+            buf.extend_from_slice(field.name.as_bytes());
+            buf.push(b':');
+        }
+        let field_ptr = unsafe { ptr.add(field.offset as usize * SLOT_BYTES) };
+        print_stored(p, field.type_info, field_ptr, SLOT_BYTES, verb, depth + 1, call);
+    }
+    p.buf().push(b'}');
+}
+
+/// `[1 2]`, or `[]int{1, 2}` for %#v. `seq` is the elements' address,
+/// count and stride, or None for a nil slice.
+fn print_seq<P: ValuePrinter>(
+    p: &mut P,
+    rttid: u32,
+    seq: Option<(*const u8, usize, usize)>,
+    verb: char,
+    depth: usize,
+    call: &ExternCallContext,
+) {
+    let sharp_v = p.sharp_v();
+    if sharp_v {
+        let name = rttid_type_name(rttid, call);
+        let buf = p.buf();
+        buf.extend_from_slice(name.as_bytes());
+        if seq.is_none() {
+            buf.extend_from_slice(b"(nil)");
+            return;
+        }
+        buf.push(b'{');
+    } else {
+        p.buf().push(b'[');
+    }
+    let (data, len, stride) = seq.unwrap_or((core::ptr::null(), 0, 0));
+    let elem = if len > 0 { call.get_elem_value_rttid_from_base(rttid) } else { ValueRttid::default() };
+    for i in 0..len {
+        if i > 0 {
+            p.buf().extend_from_slice(if sharp_v { b", " } else { b" " });
+        }
+        let ptr = unsafe { data.add(i * stride) };
+        print_stored(p, elem, ptr, stride, verb, depth + 1, call);
+    }
+    p.buf().push(if sharp_v { b'}' } else { b']' });
+}
+
+/// `map[a:1 b:2]`, or `map[string]int{"a":1, "b":2}` for %#v, in key order.
+fn print_map<P: ValuePrinter>(
+    p: &mut P,
+    rttid: u32,
+    m: GcRef,
+    verb: char,
+    depth: usize,
+    call: &ExternCallContext,
+) {
+    let sharp_v = p.sharp_v();
+    if sharp_v {
+        let name = rttid_type_name(rttid, call);
+        let buf = p.buf();
+        buf.extend_from_slice(name.as_bytes());
+        if m.is_null() {
+            buf.extend_from_slice(b"(nil)");
+            return;
+        }
+        buf.push(b'{');
+    } else {
+        p.buf().extend_from_slice(b"map[");
+    }
+    if let Some(RuntimeType::Map { key, val }) = underlying(rttid, call) {
+        let (key, val) = (*key, *val);
+        let mut entries = Vec::new();
+        let mut iter = map::iter_init(m);
+        while let Some((k, v)) = map::iter_next(&mut iter) {
+            // String keys are lent from the iterator; copy them out
+            entries.push((k.to_vec(), v));
+        }
+        entries.sort_by(|a, b| cmp_keys(key, &a.0, &b.0));
+        for (i, (k, v)) in entries.iter().enumerate() {
+            if i > 0 {
+                p.buf().extend_from_slice(if sharp_v { b", " } else { b" " });
+            }
+            print_stored(p, key, k.as_ptr() as *const u8, SLOT_BYTES, verb, depth + 1, call);
+            p.buf().push(b':');
+            print_stored(p, val, v.as_ptr() as *const u8, SLOT_BYTES, verb, depth + 1, call);
+        }
+    }
+    p.buf().push(if sharp_v { b'}' } else { b']' });
+}
+
+/// Order map keys the way Go's fmt does for the common key types: numbers
+/// and strings by value, false before true, interfaces by kind and then
+/// value. Other keys compare slot by slot, which is at least stable.
+fn cmp_keys(typ: ValueRttid, a: &[u64], b: &[u64]) -> Ordering {
+    let (x, y) = (a[0], b[0]);
+    match typ.value_kind() {
+        ValueKind::Int | ValueKind::Int64 => (x as i64).cmp(&(y as i64)),
+        ValueKind::Int8 => (x as i8).cmp(&(y as i8)),
+        ValueKind::Int16 => (x as i16).cmp(&(y as i16)),
+        ValueKind::Int32 => (x as i32).cmp(&(y as i32)),
+        ValueKind::Uint8 => (x as u8).cmp(&(y as u8)),
+        ValueKind::Uint16 => (x as u16).cmp(&(y as u16)),
+        ValueKind::Uint32 => (x as u32).cmp(&(y as u32)),
+        ValueKind::Float32 => f32::from_bits(x as u32).total_cmp(&f32::from_bits(y as u32)),
+        ValueKind::Float64 => f64::from_bits(x).total_cmp(&f64::from_bits(y)),
+        ValueKind::String => str_obj::as_bytes(x as GcRef).cmp(str_obj::as_bytes(y as GcRef)),
+        ValueKind::Interface => {
+            let (xk, yk) = (interface::unpack_value_kind(x), interface::unpack_value_kind(y));
+            (xk as u8).cmp(&(yk as u8)).then_with(|| {
+                let dynamic = ValueRttid::new(interface::unpack_rttid(x), xk);
+                cmp_keys(dynamic, &a[1..], &b[1..])
+            })
+        }
+        _ => a.cmp(b),
+    }
+}
+
+/// The runtime type of `rttid` with names looked through, except that a
+/// named struct stays named, since it carries the struct meta.
+fn underlying<'a>(mut rttid: u32, call: &ExternCallContext<'a>) -> Option<&'a RuntimeType> {
+    loop {
+        match call.runtime_types().get(rttid as usize)? {
+            RuntimeType::Named { id, struct_meta_id: None } => {
+                rttid = call.named_type_metas().get(*id as usize)?.underlying_meta.meta_id();
+            }
+            rt => return Some(rt),
+        }
+    }
+}
+
+fn struct_meta_id(rttid: u32, call: &ExternCallContext) -> Option<u32> {
+    match underlying(rttid, call)? {
+        RuntimeType::Struct { meta_id, .. } => Some(*meta_id),
+        RuntimeType::Named { struct_meta_id, .. } => *struct_meta_id,
+        _ => None,
+    }
+}
+
+/// Slots a value of type `rttid` takes when stored in place.
+fn slot_count(rttid: u32, call: &ExternCallContext) -> usize {
+    match underlying(rttid, call) {
+        Some(RuntimeType::Struct { meta_id, .. } | RuntimeType::Named { struct_meta_id: Some(meta_id), .. }) => {
+            call.struct_meta(*meta_id as usize).map_or(1, |meta| meta.slot_count() as usize)
+        }
+        Some(RuntimeType::Array { len, elem }) => *len as usize * slot_count(elem.rttid(), call),
+        Some(RuntimeType::Interface { .. }) => 2,
+        _ => 1,
+    }
+}
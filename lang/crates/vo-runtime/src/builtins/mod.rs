@@ -7,5 +7,8 @@ pub mod error_helper;
 pub mod builtin;
 pub mod dynamic;
 
-pub use format::{format_value, format_interface, format_interface_with_ctx};
+pub use format::{
+    format_value, format_interface, format_interface_with_ctx, format_error, is_composite, print_value,
+    rttid_type_name, value_kind_to_type_name, ValuePrinter,
+};
 pub use error_helper::{create_error, write_error_to, write_nil_error};
@@ -86,6 +86,11 @@ impl SentinelErrorCache {
     pub fn insert(&mut self, pkg: &'static str, errors: Vec<(u64, u64)>) {
         self.inner.insert(pkg, errors);
     }
+
+    /// All cached errors as (slot0, slot1) pairs, for GC root scanning.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.inner.values().flat_map(|v| v.iter().copied())
+    }
 }
 
 /// Extern function execution result.
@@ -94,12 +99,21 @@ pub enum ExternResult {
     /// Success.
     Ok,
     /// Yield to scheduler (for async operations).
+    ///
+    /// On its own the call is complete and the fiber simply gives up the rest
+    /// of its time slice. Returned through `ExternCallContext::yield_and_resume`,
+    /// the call is left pending instead: other fibers run, then the same extern
+    /// is entered again with the saved state in `resume_state()`.
     Yield,
     /// Block current fiber (for blocking I/O operations).
     /// The fiber will be parked and must be explicitly woken by runtime.
     Block,
     /// Panic with error message.
     Panic(String),
+    /// Re-raise the panic of a closure called through
+    /// `ExternCallContext::call_closure`. The VM has already moved its value
+    /// onto the calling fiber, so `recover()` sees the original value.
+    Repanic,
 }
 
 /// Extern function signature.
@@ -156,6 +170,55 @@ pub type ClosureCallFn = extern "C" fn(
     ret_count: u32,
 ) -> ClosureCallResult;
 
+/// Rust types that can be decoded from a closure's return slots.
+pub trait FromSlots: Sized {
+    /// Number of return slots the value occupies.
+    const SLOTS: usize;
+    fn from_slots(slots: &[u64]) -> Self;
+}
+
+impl FromSlots for () {
+    const SLOTS: usize = 0;
+    fn from_slots(_: &[u64]) -> Self {}
+}
+
+impl FromSlots for i64 {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { slots[0] as i64 }
+}
+
+impl FromSlots for u64 {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { slots[0] }
+}
+
+impl FromSlots for f64 {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { f64::from_bits(slots[0]) }
+}
+
+impl FromSlots for bool {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { slots[0] != 0 }
+}
+
+impl FromSlots for GcRef {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { slots[0] as GcRef }
+}
+
+impl FromSlots for String {
+    const SLOTS: usize = 1;
+    fn from_slots(slots: &[u64]) -> Self { string::as_str(slots[0] as GcRef).to_string() }
+}
+
+impl<A: FromSlots, B: FromSlots> FromSlots for (A, B) {
+    const SLOTS: usize = A::SLOTS + B::SLOTS;
+    fn from_slots(slots: &[u64]) -> Self {
+        (A::from_slots(slots), B::from_slots(&slots[A::SLOTS..]))
+    }
+}
+
 // ==================== Auto-registration via linkme (std only) ====================
 
 /// Entry for auto-registered extern functions.
@@ -360,6 +423,10 @@ pub struct ExternCallContext<'a> {
     program_args: &'a [String],
     /// Sentinel error cache.
     sentinel_errors: &'a mut SentinelErrorCache,
+    /// State saved by the previous `yield_and_resume` of this call, if re-entered.
+    resume_state: Option<u64>,
+    /// State to resume with, set by `yield_and_resume`.
+    yield_state: Option<u64>,
 }
 
 impl<'a> ExternCallContext<'a> {
@@ -385,6 +452,7 @@ impl<'a> ExternCallContext<'a> {
         well_known: &'a WellKnownTypes,
         program_args: &'a [String],
         sentinel_errors: &'a mut SentinelErrorCache,
+        resume_state: Option<u64>,
     ) -> Self {
         Self {
             call: ExternCall::new(stack, bp, arg_start, arg_count, ret_start),
@@ -402,9 +470,35 @@ impl<'a> ExternCallContext<'a> {
             well_known,
             program_args,
             sentinel_errors,
+            resume_state,
+            yield_state: None,
         }
     }
     
+    /// State passed to `yield_and_resume` by the previous entry of this call.
+    ///
+    /// `None` on the first entry; `Some(state)` each time the extern is
+    /// re-entered after yielding.
+    #[inline]
+    pub fn resume_state(&self) -> Option<u64> {
+        self.resume_state
+    }
+
+    /// Suspend this call and let other fibers run.
+    ///
+    /// Return the result directly from the extern. The fiber is rescheduled
+    /// and the extern is called again with the same arguments and
+    /// `resume_state() == Some(state)`; it keeps yielding until it finally
+    /// returns anything else. Return slots are only read after that last entry.
+    /// Keep anything larger than a `u64` in the extension's own storage, keyed
+    /// by the state. JIT-compiled callers cannot suspend mid-function, so there
+    /// the extern is re-entered immediately without switching fibers.
+    #[inline]
+    pub fn yield_and_resume(&mut self, state: u64) -> ExternResult {
+        self.yield_state = Some(state);
+        ExternResult::Yield
+    }
+
     /// Get program arguments.
     #[inline]
     pub fn program_args(&self) -> &[String] {
@@ -704,6 +798,23 @@ impl<'a> ExternCallContext<'a> {
         self.ret_any(n, InterfaceSlot::nil());
     }
 
+    /// Write a new `errors.New(msg)`-style error value.
+    ///
+    /// Lets an extern report a recoverable failure through its `error` return
+    /// instead of panicking: `return call.ret_error_msg(1, "not found")`.
+    /// The value is an `*errors.Error`. Compiled projects always link the
+    /// errors package; for a module built without it nothing is written and
+    /// this returns `ExternResult::Panic(msg)` instead of `ExternResult::Ok`.
+    pub fn ret_error_msg(&mut self, n: u16, msg: &str) -> ExternResult {
+        match crate::builtins::error_helper::try_create_error(self, msg) {
+            Some((slot0, slot1)) => {
+                self.ret_any(n, InterfaceSlot::new(slot0, slot1));
+                ExternResult::Ok
+            }
+            None => ExternResult::Panic(msg.to_string()),
+        }
+    }
+
     /// Allocate a new string.
     #[inline]
     pub fn alloc_str(&mut self, s: &str) -> GcRef {
@@ -1247,6 +1358,10 @@ impl ExternRegistry {
     }
 
     /// Call an extern function.
+    ///
+    /// `resume` carries the continuation state of a suspended call: pass the
+    /// state saved by the last call (or `None`), and on return it holds the
+    /// state to re-enter with if the extern used `yield_and_resume`.
     pub fn call(
         &self,
         id: u32,
@@ -1269,9 +1384,11 @@ impl ExternRegistry {
         well_known: &WellKnownTypes,
         program_args: &[String],
         sentinel_errors: &mut SentinelErrorCache,
+        resume: &mut Option<u64>,
     ) -> ExternResult {
         match self.funcs.get(id as usize) {
             Some(Some(ExternFnEntry::Simple(f))) => {
+                *resume = None;
                 let mut call = ExternCall::new(stack, bp, arg_start, arg_count, ret_start);
                 f(&mut call)
             }
@@ -1296,8 +1413,11 @@ impl ExternRegistry {
                     well_known,
                     program_args,
                     sentinel_errors,
+                    resume.take(),
                 );
-                f(&mut call)
+                let result = f(&mut call);
+                *resume = call.yield_state.filter(|_| matches!(result, ExternResult::Yield));
+                result
             }
             _ => ExternResult::Panic(format!("extern function {} not found", id)),
         }
@@ -2,6 +2,10 @@
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet;
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
 
 #[cfg(feature = "std")]
 use std::alloc as heap_alloc;
@@ -155,6 +159,65 @@ impl GcHeader {
 /// GC reference - pointer to GcObject data (after header).
 pub type GcRef = *mut Slot;
 
+/// Counters for incremental collection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcStats {
+    /// Completed incremental cycles.
+    pub cycles: usize,
+    /// Steps taken.
+    pub steps: usize,
+    /// Most objects marked, swept or indexed by one step. A step's pause is
+    /// proportional to this, plus a root scan at the start and end of a cycle.
+    pub max_step_work: usize,
+}
+
+/// Heap usage over a program's run, for reporting to users.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes allocated since the heap was created, including those since freed.
+    pub bytes_allocated: usize,
+    /// Objects on the heap, counting garbage a sweep hasn't reached yet.
+    pub live_objects: usize,
+    /// Completed collections, full or incremental.
+    pub gc_cycles: usize,
+}
+
+/// Debug-build knobs that make collection and allocation failure happen at
+/// chosen allocations, so missing GC roots fail deterministically.
+///
+/// Present in every build so `Gc` has one layout: extensions built in one
+/// profile are handed the host's `Gc` from another.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+#[derive(Debug, Default)]
+struct GcStress {
+    collect_every: usize,  // Force a collection every N allocations (0 = off)
+    allocs: usize,         // Allocations since the last forced collection
+    collect_due: bool,     // A forced collection waits for the next safe point
+    oom_after: Option<usize>, // Allocations left before the injected failure
+    oom_pending: bool,     // An allocation "failed"; the VM raises the panic
+}
+
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+impl GcStress {
+    fn on_alloc(&mut self) {
+        if self.collect_every > 0 {
+            self.allocs += 1;
+            if self.allocs >= self.collect_every {
+                self.allocs = 0;
+                self.collect_due = true;
+            }
+        }
+        match self.oom_after {
+            Some(0) => {
+                self.oom_after = None;
+                self.oom_pending = true;
+            }
+            Some(n) => self.oom_after = Some(n - 1),
+            None => {}
+        }
+    }
+}
+
 /// Garbage collector.
 pub struct Gc {
     // ========== Object Storage ==========
@@ -162,46 +225,71 @@ pub struct Gc {
     
     // ========== Mark Queues ==========
     gray: Vec<GcRef>,        // To be scanned
-    grayagain: Vec<GcRef>,   // Re-scan (barrier triggered)
+    grayagain: Vec<GcRef>,   // Written while marking (may point inside an object)
+    object_index: BTreeSet<usize>, // Object addresses, to resolve `grayagain` entries
+    indexed: usize,          // Prefix of all_objects in object_index this cycle
     
     // ========== State ==========
     state: GcState,
+    marking: bool,           // state == Propagate; read by JIT store barriers
+    incremental: bool,       // Collect in steps at safepoints
+    safepoint: bool,         // A step is due; read by JIT safepoints
+    hold_safepoints: bool,   // Keep `safepoint` set for the profiler
     current_white: u8,       // Current white bit (WHITE0_BIT or WHITE1_BIT)
     sweep_pos: usize,        // Read position in sweep phase
     sweep_write_pos: usize,  // Write position for live objects in sweep phase
     
     // ========== Memory Stats ==========
     total_bytes: usize,      // Total allocated bytes
+    bytes_allocated: usize,  // Cumulative allocated bytes, never decreases
+    full_collections: usize, // Completed `collect` calls
     estimate: usize,         // Estimated live bytes after last GC
     debt: isize,             // Work debt (triggers GC when > 0)
+    allocs_since_step: usize, // Allocations during a cycle since the last step
+    stats: GcStats,
     
     // ========== Parameters ==========
     pause: u16,              // Pause multiplier (default 200 = 2x)
-    stepmul: u16,            // Step multiplier (default 100)
-    stepsize: usize,         // Bytes per step (default 8KB)
+    stepmul: u16,            // Extra work per allocation since last step, in % (default 200)
+    stepsize: usize,         // Objects per step (default 256)
+
+    // ========== Stress Testing ==========
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    stress: GcStress,        // Only acted on in debug builds
 }
 
 impl Gc {
     // Default parameters
     const DEFAULT_PAUSE: u16 = 200;      // Trigger at 2x estimated live size
-    const DEFAULT_STEPMUL: u16 = 100;    // Work multiplier
-    const DEFAULT_STEPSIZE: usize = 8192; // 8KB per step
+    const DEFAULT_STEPMUL: u16 = 200;    // Work multiplier
+    const DEFAULT_STEPSIZE: usize = 256; // Objects per step
 
     pub fn new() -> Self {
         Self {
             all_objects: Vec::new(),
             gray: Vec::new(),
             grayagain: Vec::new(),
+            object_index: BTreeSet::new(),
+            indexed: 0,
             state: GcState::Pause,
+            marking: false,
+            incremental: false,
+            safepoint: false,
+            hold_safepoints: false,
             current_white: WHITE0_BIT,
             sweep_pos: 0,
             sweep_write_pos: 0,
             total_bytes: 0,
+            bytes_allocated: 0,
+            full_collections: 0,
             estimate: 0,
             debt: 0,
+            allocs_since_step: 0,
+            stats: GcStats::default(),
             pause: Self::DEFAULT_PAUSE,
             stepmul: Self::DEFAULT_STEPMUL,
             stepsize: Self::DEFAULT_STEPSIZE,
+            stress: GcStress::default(),
         }
     }
     
@@ -255,8 +343,12 @@ impl Gc {
             panic!("GC allocation failed");
         }
 
-        // New object gets current white color
-        let header = GcHeader::new_with_white(value_meta, header_slots, self.current_white);
+        // New object gets current white color, or black while marking so the
+        // cycle never has to traverse objects allocated during it
+        let mut header = GcHeader::new_with_white(value_meta, header_slots, self.current_white);
+        if self.marking {
+            header.set_black();
+        }
         unsafe {
             core::ptr::write(ptr as *mut GcHeader, header);
         }
@@ -265,7 +357,19 @@ impl Gc {
 
         self.all_objects.push(data_ptr);
         self.total_bytes += total_size;
+        self.bytes_allocated += total_size;
         self.debt += total_size as isize;
+        if self.state != GcState::Pause {
+            self.allocs_since_step += 1;
+        } else if self.incremental && self.debt > 0 {
+            self.safepoint = true;
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            self.stress.on_alloc();
+            self.safepoint |= self.stress.collect_due;
+        }
 
         #[cfg(feature = "gc-debug")]
         crate::gc_debug::on_alloc(data_ptr);
@@ -305,14 +409,18 @@ impl Gc {
     /// For large arrays (slots == 0), reads actual size from ArrayHeader.
     #[inline]
     fn object_size_bytes(obj: GcRef) -> usize {
+        GcHeader::SIZE + Self::data_slots(obj) * SLOT_BYTES
+    }
+
+    /// Number of data slots after the header.
+    pub fn data_slots(obj: GcRef) -> usize {
         use crate::objects::array;
         let header = Self::header(obj);
-        let slots = if header.slots == 0 {
+        if header.slots == 0 {
             array::total_slots(obj)
         } else {
             header.slots as usize
-        };
-        GcHeader::SIZE + slots * SLOT_BYTES
+        }
     }
 
     /// Mark an object as gray (pending scan).
@@ -328,22 +436,16 @@ impl Gc {
     }
 
     /// Write barrier for incremental GC (backward barrier).
-    /// Called when a black object writes a white reference.
-    pub fn write_barrier(&mut self, parent: GcRef, child: GcRef) {
+    /// Called after storing into `parent`, which may point inside an object
+    /// (a slice element, say). While marking, the written object is recorded
+    /// and rescanned in the atomic phase if it had already been marked black.
+    /// `child` is not inspected: it may be the data slot of an interface.
+    pub fn write_barrier(&mut self, parent: GcRef, _child: GcRef) {
         #[cfg(feature = "gc-debug")]
-        crate::gc_debug::on_barrier(parent, 0, child as u64);
+        crate::gc_debug::on_barrier(parent, 0, _child as u64);
         
-        if self.state != GcState::Propagate {
-            return;
-        }
-        if parent.is_null() || child.is_null() {
-            return;
-        }
-        let p_header = Self::header(parent);
-        let c_header = Self::header(child);
-        // Backward barrier: black parent writes white child → parent becomes gray
-        if p_header.is_black() && c_header.is_white() {
-            self.barrier_back(parent);
+        if self.marking && !parent.is_null() && self.grayagain.last() != Some(&parent) {
+            self.grayagain.push(parent);
         }
     }
     
@@ -361,19 +463,119 @@ impl Gc {
         Self::header(obj).is_white()
     }
     
-    /// Backward barrier: turn black object back to gray for re-scan.
-    fn barrier_back(&mut self, obj: GcRef) {
-        let header = Self::header_mut(obj);
-        header.set_white(self.current_white);
-        self.grayagain.push(obj);
-    }
-
     /// Check if GC should run (debt-based trigger).
     #[inline]
     pub fn should_step(&self) -> bool {
         self.debt > 0
     }
 
+    /// Collect in bounded steps (`step`) driven from safepoints instead of
+    /// only on explicit `collect`. Off by default.
+    pub fn set_incremental(&mut self, on: bool) {
+        self.incremental = on;
+        self.update_safepoint();
+    }
+
+    #[inline]
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Whether incremental collection wants a step: a cycle is in progress,
+    /// or enough has been allocated to start one.
+    #[inline]
+    pub fn step_pending(&self) -> bool {
+        if self.hold_safepoints { self.step_due() } else { self.safepoint }
+    }
+
+    /// Keep the safepoint flag set even when no step is due, so JIT code
+    /// calls out at every safepoint; the VM's sampling profiler records
+    /// samples there. `step_pending` still reports only collector work.
+    pub fn set_hold_safepoints(&mut self, on: bool) {
+        self.hold_safepoints = on;
+        self.update_safepoint();
+    }
+
+    /// Address of the safepoint flag, polled by JIT safepoints.
+    pub fn safepoint_flag(&self) -> *const bool {
+        &self.safepoint
+    }
+
+    /// Whether a cycle is marking, so stores into heap objects need `write_barrier`.
+    #[inline]
+    pub fn is_marking(&self) -> bool {
+        self.marking
+    }
+
+    /// Address of the `is_marking` flag, checked inline by JIT store barriers.
+    pub fn marking_flag(&self) -> *const bool {
+        &self.marking
+    }
+
+    pub fn stats(&self) -> GcStats {
+        self.stats
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        HeapStats {
+            bytes_allocated: self.bytes_allocated,
+            live_objects: self.all_objects.len(),
+            gc_cycles: self.stats.cycles + self.full_collections,
+        }
+    }
+
+    fn step_due(&self) -> bool {
+        #[cfg(debug_assertions)]
+        if self.stress.collect_due {
+            return true;
+        }
+        self.incremental && (self.state != GcState::Pause || self.debt > 0)
+    }
+
+    fn update_safepoint(&mut self) {
+        self.safepoint = self.step_due() || self.hold_safepoints;
+    }
+
+    /// Debug builds only: after every `n`th allocation, ask for a full
+    /// collection at the next safe point (the end of the allocating VM
+    /// instruction, or a JIT safepoint). `n = 1` collects after every
+    /// allocation; `0` turns this off.
+    #[cfg(debug_assertions)]
+    pub fn collect_every_n_allocs(&mut self, n: usize) {
+        self.stress.collect_every = n;
+        self.stress.allocs = 0;
+    }
+
+    /// Debug builds only: let `n` more allocations succeed, then fail the
+    /// next one. The allocation still returns a valid object; the VM raises
+    /// "runtime error: out of memory" once the allocating instruction ends
+    /// (for JIT code, at the next interpreted instruction).
+    #[cfg(debug_assertions)]
+    pub fn inject_oom_after(&mut self, n: usize) {
+        self.stress.oom_after = Some(n);
+    }
+
+    /// Whether a forced collection or an injected allocation failure is waiting.
+    #[cfg(debug_assertions)]
+    #[inline]
+    pub fn stress_pending(&self) -> bool {
+        self.stress.collect_due || self.stress.oom_pending
+    }
+
+    /// Consume a due forced collection; the caller runs it.
+    #[cfg(debug_assertions)]
+    pub fn take_forced_collect(&mut self) -> bool {
+        let due = core::mem::take(&mut self.stress.collect_due);
+        self.update_safepoint();
+        due
+    }
+
+    /// Consume an injected allocation failure; the caller raises it.
+    #[cfg(debug_assertions)]
+    pub fn take_injected_oom(&mut self) -> bool {
+        core::mem::take(&mut self.stress.oom_pending)
+    }
+
     /// Run garbage collection (legacy full GC, kept for compatibility).
     /// - `scan_object`: marks children of an object (mark phase)
     /// - `finalize_object`: releases native resources before dealloc (sweep phase)
@@ -391,13 +593,6 @@ impl Gc {
             }
         }
         
-        // Process grayagain
-        while let Some(obj) = self.grayagain.pop() {
-            let header = Self::header_mut(obj);
-            header.set_black();
-            scan_object(self, obj);
-        }
-
         // Sweep phase
         let mut new_objects = Vec::new();
         let mut freed_bytes = 0;
@@ -422,13 +617,17 @@ impl Gc {
         self.total_bytes -= freed_bytes;
         self.estimate = self.total_bytes;
         self.debt = 0;
+        self.full_collections += 1;
         
         // Flip white for next cycle
         self.current_white ^= WHITE_BITS;
     }
     
-    /// Incremental GC step. Returns work done (bytes processed).
-    /// Call this when debt > 0, passing scan_roots and scan_object callbacks.
+    /// Incremental GC step. Returns work done (objects marked or swept).
+    /// Does `stepsize` objects of work, plus `stepmul`% of the objects
+    /// allocated since the previous step so a cycle keeps pace with the
+    /// mutator. `scan_roots` runs when a cycle starts and again in the atomic
+    /// phase, since stack and global writes have no barrier.
     pub fn step<R, S, F>(
         &mut self,
         mut scan_roots: R,
@@ -441,35 +640,39 @@ impl Gc {
         F: FnMut(GcRef),
     {
         let mut work = 0usize;
-        let work_limit = self.stepsize * self.stepmul as usize / 100;
+        let work_limit = self.stepsize + self.allocs_since_step * self.stepmul as usize / 100;
         
         loop {
             match self.state {
                 GcState::Pause => {
                     // Start new cycle
                     self.start_cycle(&mut scan_roots);
-                    self.state = GcState::Propagate;
                 }
                 
                 GcState::Propagate => {
                     // Incremental marking
                     work += self.propagate_step(&mut scan_object, work_limit.saturating_sub(work));
                     
-                    if self.gray.is_empty() {
+                    if self.gray.is_empty() && !self.grayagain.is_empty() {
+                        // Rescan what was written so far here rather than in the atomic
+                        // phase, once the objects are indexed to resolve barrier entries
+                        work += self.index_step(work_limit.saturating_sub(work));
+                        if self.indexed == self.all_objects.len() {
+                            work += self.rescan_barriered(&mut scan_object);
+                        }
+                    } else if self.gray.is_empty() {
                         // Move to atomic phase
                         self.state = GcState::Atomic;
-                    } else if work >= work_limit {
+                    }
+                    if self.state == GcState::Propagate && work >= work_limit {
                         // Yield - done enough work this step
                         break;
                     }
                 }
                 
                 GcState::Atomic => {
-                    // Atomic phase: process grayagain, finalize marking
-                    self.atomic_phase(&mut scan_object);
-                    self.state = GcState::Sweep;
-                    self.sweep_pos = 0;
-                    self.sweep_write_pos = 0;
+                    // Atomic phase: rescan roots and barriered objects, finalize marking
+                    work += self.atomic_phase(&mut scan_roots, &mut scan_object);
                 }
                 
                 GcState::Sweep => {
@@ -488,15 +691,45 @@ impl Gc {
             }
         }
         
-        self.debt -= work as isize;
+        self.allocs_since_step = 0;
+        self.stats.steps += 1;
+        self.stats.max_step_work = self.stats.max_step_work.max(work);
+        self.update_safepoint();
         work
     }
+
+    /// Finish marking of an in-progress cycle in one go, leaving only
+    /// sweeping. Needed before code that writes without barriers runs.
+    pub fn finish_marking<R, S>(&mut self, mut scan_roots: R, mut scan_object: S)
+    where
+        R: FnMut(&mut Gc),
+        S: FnMut(&mut Gc, GcRef),
+    {
+        if self.state == GcState::Propagate {
+            self.propagate_step(&mut scan_object, usize::MAX);
+            self.atomic_phase(&mut scan_roots, &mut scan_object);
+        }
+    }
+
+    /// Run an in-progress cycle to completion.
+    pub fn complete_cycle<R, S, F>(&mut self, mut scan_roots: R, mut scan_object: S, mut finalize_object: F)
+    where
+        R: FnMut(&mut Gc),
+        S: FnMut(&mut Gc, GcRef),
+        F: FnMut(GcRef),
+    {
+        while self.state != GcState::Pause {
+            self.step(&mut scan_roots, &mut scan_object, &mut finalize_object);
+        }
+    }
     
     /// Start a new GC cycle.
     fn start_cycle<R: FnMut(&mut Gc)>(&mut self, scan_roots: &mut R) {
         // Flip white for this cycle (objects allocated during GC get new white)
         self.current_white ^= WHITE_BITS;
         scan_roots(self);
+        self.state = GcState::Propagate;
+        self.marking = true;
     }
     
     /// Propagate marking incrementally. Returns work done.
@@ -508,7 +741,7 @@ impl Gc {
             if !header.is_black() {
                 header.set_black();
                 scan_object(self, obj);
-                work += Self::object_size_bytes(obj);
+                work += 1;
                 
                 if work >= limit {
                     break;
@@ -519,25 +752,65 @@ impl Gc {
         work
     }
     
-    /// Atomic phase: process grayagain and finalize marking.
-    fn atomic_phase<S: FnMut(&mut Gc, GcRef)>(&mut self, scan_object: &mut S) {
-        // Process grayagain (objects modified during propagate)
-        while let Some(obj) = self.grayagain.pop() {
-            let header = Self::header_mut(obj);
-            if !header.is_black() {
-                header.set_black();
-                scan_object(self, obj);
-            }
+    /// Atomic phase: finalize marking and move on to sweeping. Returns work done.
+    fn atomic_phase<R, S>(&mut self, scan_roots: &mut R, scan_object: &mut S) -> usize
+    where
+        R: FnMut(&mut Gc),
+        S: FnMut(&mut Gc, GcRef),
+    {
+        self.marking = false;
+        scan_roots(self);
+        let mut work = self.rescan_barriered(scan_object);
+        work += self.propagate_step(scan_object, usize::MAX);
+        // Sweeping frees objects, so the index is rebuilt next cycle
+        self.object_index.clear();
+        self.indexed = 0;
+        
+        self.state = GcState::Sweep;
+        self.sweep_pos = 0;
+        self.sweep_write_pos = 0;
+        work
+    }
+
+    /// Rescan black objects written during propagation. Barrier entries may
+    /// point inside an object, so each is looked up in `object_index`.
+    /// Returns work done, including indexing objects not yet indexed.
+    fn rescan_barriered<S: FnMut(&mut Gc, GcRef)>(&mut self, scan_object: &mut S) -> usize {
+        if self.grayagain.is_empty() {
+            return 0;
         }
+        let mut work = self.index_step(usize::MAX);
+        let mut written = core::mem::take(&mut self.grayagain);
+        written.sort_unstable();
         
-        // Process any new gray objects added during grayagain processing
-        while let Some(obj) = self.gray.pop() {
-            let header = Self::header_mut(obj);
-            if !header.is_black() {
-                header.set_black();
+        let mut last: GcRef = core::ptr::null_mut();
+        for addr in written {
+            let Some(&start) = self.object_index.range(..=addr as usize).next_back() else {
+                continue;
+            };
+            let obj = start as GcRef;
+            let end = start + Self::object_size_bytes(obj) - GcHeader::SIZE;
+            if obj == last || addr as usize >= end {
+                continue;
+            }
+            last = obj;
+            if Self::header(obj).is_black() {
                 scan_object(self, obj);
+                work += 1;
             }
         }
+        work
+    }
+
+    /// Add up to `limit` objects to `object_index`, oldest first. Nothing is
+    /// freed while marking, so the index is built once per cycle, spread
+    /// over steps, and only extended after. Returns the number added.
+    fn index_step(&mut self, limit: usize) -> usize {
+        let end = self.all_objects.len().min(self.indexed.saturating_add(limit));
+        let added = end - self.indexed;
+        self.object_index.extend(self.all_objects[self.indexed..end].iter().map(|&obj| obj as usize));
+        self.indexed = end;
+        added
     }
     
     /// Sweep dead objects incrementally. Returns work done.
@@ -563,7 +836,6 @@ impl Gc {
                 let size_bytes = Self::object_size_bytes(obj);
                 finalize_object(obj);
                 self.total_bytes -= size_bytes;
-                work += size_bytes;
                 
                 let raw_ptr = unsafe { (obj as *mut u8).sub(GcHeader::SIZE) };
                 let layout = core::alloc::Layout::from_size_align(size_bytes, 8).unwrap();
@@ -571,6 +843,7 @@ impl Gc {
             }
             
             self.sweep_pos += 1;
+            work += 1;
         }
         
         // If sweep complete, truncate the vector
@@ -585,10 +858,11 @@ impl Gc {
     fn finish_cycle(&mut self) {
         self.estimate = self.total_bytes;
         self.state = GcState::Pause;
+        self.stats.cycles += 1;
         
-        // Set debt threshold for next cycle
+        // Next cycle starts once the heap grows to `pause`% of its live size
         let threshold = (self.estimate as u64 * self.pause as u64 / 100) as isize;
-        self.debt = self.debt.min(-(threshold.max(1024) as isize));
+        self.debt = -(threshold - self.estimate as isize).max(1024);
     }
 
     pub fn total_bytes(&self) -> usize {
@@ -599,6 +873,11 @@ impl Gc {
         self.all_objects.len()
     }
 
+    /// All allocated objects, live or not yet swept.
+    pub fn objects(&self) -> &[GcRef] {
+        &self.all_objects
+    }
+
     /// Deep copy (clone) a heap object.
     /// Allocates new object with same value_meta and copies all slots.
     /// Used by PtrClone instruction and interface assignment (value semantics).
@@ -72,6 +72,14 @@ fn scan_array(gc: &mut Gc, obj: GcRef, struct_metas: &[StructMeta]) {
         }
     }
     
+    // Interface elements are a header and a data slot; only the data may be a GcRef
+    if elem_kind == ValueKind::Interface {
+        for idx in 0..len {
+            scan_array_struct_elem(gc, obj, idx, elem_bytes, &[SlotType::Interface0, SlotType::Interface1]);
+        }
+        return;
+    }
+
     // For reference types (slice, map, string, etc.), each element is a single GcRef
     for idx in 0..len {
         for slot in 0..elem_slots {
@@ -192,6 +200,29 @@ fn scan_struct(gc: &mut Gc, obj: GcRef, meta_id: usize, struct_metas: &[StructMe
     }
 }
 
+/// Native state a heap object owns outside its slots.
+pub enum NativeState {
+    Map(map::SavedBody),
+    Channel(queue_state::ChannelState),
+}
+
+/// Copy the native state of `obj` so `restore_native_state` can put it back.
+/// Ports are shared with other islands, so theirs is not saved.
+pub fn save_native_state(obj: GcRef) -> Option<NativeState> {
+    match Gc::header(obj).kind() {
+        ValueKind::Map => Some(NativeState::Map(map::save_body(obj))),
+        ValueKind::Channel => Some(NativeState::Channel(channel::save_state(obj))),
+        _ => None,
+    }
+}
+
+pub fn restore_native_state(obj: GcRef, saved: &NativeState) {
+    match saved {
+        NativeState::Map(body) => map::restore_body(obj, body),
+        NativeState::Channel(state) => channel::restore_state(obj, state),
+    }
+}
+
 /// Finalize a GC object before deallocation.
 /// Releases native resources (Box, etc.) not managed by GC.
 pub fn finalize_object(obj: GcRef) {
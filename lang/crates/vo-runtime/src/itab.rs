@@ -19,6 +19,32 @@ use vo_common_core::bytecode::{InterfaceMeta, Itab, NamedTypeMeta, Module};
 use vo_common_core::runtime_type::RuntimeType;
 use crate::ValueKind;
 
+/// Flat view of the itab table for JIT code.
+///
+/// Method `m` of itab `i` is `methods[offsets[i] + m]`, for `i < len`.
+/// Owned by `ItabCache`, which refreshes it whenever an itab is appended.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ItabTable {
+    pub offsets: *const u32,
+    pub methods: *const u32,
+    pub len: u32,
+}
+
+impl Default for ItabTable {
+    fn default() -> Self {
+        Self {
+            offsets: core::ptr::null(),
+            methods: core::ptr::null(),
+            len: 0,
+        }
+    }
+}
+
+// The pointers only ever refer to the owning ItabCache's vectors.
+unsafe impl Send for ItabTable {}
+unsafe impl Sync for ItabTable {}
+
 /// Unified itab table with runtime cache for interface-to-interface assignments.
 #[derive(Debug, Default)]
 pub struct ItabCache {
@@ -26,24 +52,58 @@ pub struct ItabCache {
     cache: HashMap<(u32, u32, bool), u32>,
     /// Unified itab table: initialized from module.itabs, runtime itabs appended
     itabs: Vec<Itab>,
+    /// Start of each itab's methods in `flat_methods`.
+    flat_offsets: Vec<u32>,
+    /// All itab methods back to back.
+    flat_methods: Vec<u32>,
+    /// JIT view of `flat_offsets`/`flat_methods`.
+    table: ItabTable,
+    /// Interface calls JIT code could not resolve inline.
+    unresolved_calls: u64,
 }
 
 impl ItabCache {
     /// Create from module's compile-time itabs
     pub fn from_module_itabs(itabs: Vec<Itab>) -> Self {
-        Self {
-            cache: HashMap::new(),
-            itabs,
+        let mut cache = Self::new();
+        for itab in itabs {
+            cache.push_itab(itab);
         }
+        cache
     }
 
     pub fn new() -> Self {
-        Self {
-            cache: HashMap::new(),
-            itabs: Vec::new(),
+        Self::default()
+    }
+
+    /// Append an itab, keeping the flat table in sync. Returns its id.
+    fn push_itab(&mut self, itab: Itab) -> u32 {
+        let itab_id = self.itabs.len() as u32;
+        self.flat_offsets.push(self.flat_methods.len() as u32);
+        self.flat_methods.extend_from_slice(&itab.methods);
+        self.itabs.push(itab);
+        self.table = ItabTable {
+            offsets: self.flat_offsets.as_ptr(),
+            methods: self.flat_methods.as_ptr(),
+            len: self.itabs.len() as u32,
+        };
+        itab_id
+    }
+
+    /// Append the itabs of a module reloaded over this one. The module keeps
+    /// the ids this cache has handed out, with placeholders for the runtime
+    /// ones, so those keep their itabs and only the rest are taken.
+    pub fn extend_from_module_itabs(&mut self, itabs: &[Itab]) {
+        for itab in itabs.iter().skip(self.itabs.len()) {
+            self.push_itab(itab.clone());
         }
     }
 
+    /// Number of itabs, compile-time and runtime.
+    pub fn itab_count(&self) -> u32 {
+        self.itabs.len() as u32
+    }
+
     pub fn get_itab(&self, itab_id: u32) -> Option<&Itab> {
         self.itabs.get(itab_id as usize)
     }
@@ -86,8 +146,7 @@ impl ItabCache {
         }
 
         let itab = Self::try_build_itab(named_type_id, iface_meta_id, src_is_pointer, named_type_metas, interface_metas)?;
-        let itab_id = self.itabs.len() as u32;
-        self.itabs.push(itab);
+        let itab_id = self.push_itab(itab);
         self.cache.insert(key, itab_id);
 
         Some(itab_id)
@@ -129,6 +188,23 @@ impl ItabCache {
     pub fn itabs_ptr(&self) -> *const core::ffi::c_void {
         self.itabs.as_ptr() as *const core::ffi::c_void
     }
+
+    /// Get pointer to the flat itab table for JIT context.
+    /// Stays valid while this cache is not moved.
+    pub fn table_ptr(&self) -> *const ItabTable {
+        &self.table
+    }
+
+    /// Count an interface call that JIT code had to resolve in the runtime.
+    #[inline]
+    pub fn record_unresolved_call(&mut self) {
+        self.unresolved_calls += 1;
+    }
+
+    /// Number of interface calls JIT code could not resolve inline.
+    pub fn unresolved_calls(&self) -> u64 {
+        self.unresolved_calls
+    }
 }
 
 // =============================================================================
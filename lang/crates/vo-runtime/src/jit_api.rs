@@ -22,9 +22,9 @@ use std::ffi::c_void;
 
 use crate::gc::{Gc, GcRef};
 use crate::slot::slots_for_bytes;
-use crate::itab::ItabCache;
+use crate::itab::{ItabCache, ItabTable};
 use crate::objects::interface::InterfaceSlot;
-use crate::ValueKind;
+use crate::{PanicKind, ValueKind};
 use vo_common_core::bytecode::Module;
 
 // =============================================================================
@@ -60,7 +60,8 @@ pub struct JitContext {
     /// Pointer to the global variables array.
     pub globals: *mut u64,
     
-    /// Pointer to safepoint flag (read by JIT to check if GC wants to run).
+    /// Pointer to safepoint flag (read by JIT to check if GC wants to run,
+    /// or the profiler wants samples).
     /// When this is true, JIT should call vo_gc_safepoint().
     pub safepoint_flag: *const bool,
     
@@ -90,8 +91,8 @@ pub struct JitContext {
     /// Pointer to ExternRegistry for calling extern functions.
     pub extern_registry: *const c_void,
     
-    /// Callback to call extern function: (ctx, registry, gc, module, extern_id, args, arg_count, ret, ret_slots) -> JitResult
-    pub call_extern_fn: Option<extern "C" fn(*mut JitContext, *const c_void, *mut Gc, *const c_void, u32, *const u64, u32, *mut u64, u32) -> JitResult>,
+    /// Callback to call extern function: (ctx, registry, gc, module, extern_id, args, arg_count, ret, ret_slots, can_park) -> JitResult
+    pub call_extern_fn: Option<extern "C" fn(*mut JitContext, *const c_void, *mut Gc, *const c_void, u32, *const u64, u32, *mut u64, u32, u32) -> JitResult>,
     
     /// Pointer to Module for type information.
     pub module: *const Module,
@@ -108,6 +109,44 @@ pub struct JitContext {
     
     /// Pointer to sentinel error cache.
     pub sentinel_errors: *mut crate::ffi::SentinelErrorCache,
+    
+    /// Flat itab table: JIT code loads `(itab_id, method_idx) -> func_id` from here
+    /// and only calls vo_call_iface when the itab is not in the table.
+    pub itab_table: *const ItabTable,
+    
+    /// Live JIT function frames, registered by `vo_jit_enter_frame` as GC roots.
+    pub jit_frames: *mut Vec<JitFrameRoot>,
+    
+    /// Map iteration in key order (`VmState::sorted_map_iter`).
+    pub sorted_map_iter: bool,
+    
+    /// Pointer to the GC's marking flag. While it is set, stores into heap
+    /// objects must call vo_gc_write_barrier().
+    pub gc_marking: *const bool,
+    
+    /// Callback that runs one incremental GC step and takes a profiler
+    /// sample, called by vo_gc_safepoint() with the safepoint's func_id and pc.
+    pub gc_step_fn: Option<extern "C" fn(*mut JitContext, u32, u32)>,
+    
+    /// Pc of the instruction that raised a panic in this JIT function or loop,
+    /// for error locations. `u32::MAX` if the panic came from a call or extern.
+    pub panic_pc: u32,
+
+    /// Which runtime error JIT code raised, as `PanicKind as u8`. 0 for
+    /// panics from `panic` calls.
+    pub panic_kind: u8,
+}
+
+/// GC root for a running JIT function.
+///
+/// A compiled function keeps its locals in a native stack slot the VM cannot
+/// see. Every write to a local is mirrored into that slot, so at any call the
+/// function makes (all GC safepoints are calls), scanning `local_slots` slots
+/// from `locals` with the function's `slot_types` finds every live GcRef.
+#[derive(Debug, Clone, Copy)]
+pub struct JitFrameRoot {
+    pub func_id: u32,
+    pub locals: *const u64,
 }
 
 // =============================================================================
@@ -126,6 +165,12 @@ pub enum JitResult {
     Panic = 1,
     /// Function blocked on I/O, need to return to VM scheduler.
     Block = 2,
+    /// Function hit a runtime case the JIT does not handle. The VM re-runs
+    /// the call from the start in the interpreter and stops using the JIT
+    /// code; JIT code only deopts before it has had any side effects.
+    /// Also returned when an extern suspended at the function's first effect:
+    /// the interpreter re-enters it and parks the fiber, keeping the JIT code.
+    Deopt = 3,
 }
 
 // =============================================================================
@@ -162,7 +207,7 @@ pub extern "C" fn vo_gc_alloc(gc: *mut Gc, meta: u32, slots: u32) -> u64 {
 ///
 /// # Arguments
 /// - `gc`: Pointer to GC instance
-/// - `obj`: The object being written to (GcRef)
+/// - `obj`: The object being written to (GcRef, or an address inside it)
 /// - `offset`: Slot offset within the object
 /// - `val`: The value being stored (may be GcRef)
 ///
@@ -184,28 +229,43 @@ pub extern "C" fn vo_gc_write_barrier(gc: *mut Gc, obj: u64, _offset: u32, val:
 /// GC safepoint.
 ///
 /// Called at loop back-edges and before function calls when safepoint_flag
-/// is set. Currently a no-op placeholder - full GC integration requires
-/// stack map support which is not yet implemented.
-///
-/// The synchronous JIT design means GC can run when:
-/// 1. JIT calls vo_call_vm (VM will handle GC)
-/// 2. JIT calls vo_gc_alloc (checks debt)
+/// is set, i.e. when incremental collection wants a step. Runs one bounded
+/// step through `gc_step_fn`. JIT frames are visible to it through
+/// `JitContext::jit_frames` (function locals) and the fiber stack (loop
+/// locals, whose GcRef slots are stored back on every write).
+/// While the VM profiler runs the flag stays set, and the call also records
+/// a sample at `(func_id, pc)`.
 ///
 /// # Arguments
 /// - `ctx`: JIT context
+/// - `func_id`: Function containing the safepoint
+/// - `pc`: Bytecode pc of the instruction the safepoint precedes
 ///
 /// # Safety
 /// - `ctx` must be a valid pointer to JitContext
 #[no_mangle]
-pub extern "C" fn vo_gc_safepoint(_ctx: *mut JitContext) {
-    // Currently no-op. GC is triggered by:
-    // - vo_gc_alloc when debt > 0
-    // - vo_call_vm which enters VM context where GC can run
-    //
-    // Full safepoint support would require:
-    // 1. Stack maps to identify GcRef locations in JIT frames
-    // 2. Cooperation with VM to scan roots
-    // For now, the synchronous JIT design ensures GC safety at call boundaries.
+pub extern "C" fn vo_gc_safepoint(ctx: *mut JitContext, func_id: u32, pc: u32) {
+    let step = unsafe { ctx.as_ref().and_then(|ctx| ctx.gc_step_fn) };
+    if let Some(step) = step {
+        step(ctx, func_id, pc);
+    }
+}
+
+/// Register the calling JIT frame's locals as a GC root.
+///
+/// Emitted in the prologue of functions with GcRef slots. There is no matching
+/// exit call: whoever invoked the JIT function truncates `jit_frames` back to
+/// its previous length once it returns, whichever way it exits.
+#[no_mangle]
+pub extern "C" fn vo_jit_enter_frame(ctx: *mut JitContext, func_id: u32, locals: *const u64) {
+    push_jit_frame(ctx, JitFrameRoot { func_id, locals });
+}
+
+fn push_jit_frame(ctx: *mut JitContext, root: JitFrameRoot) {
+    let frames = unsafe { ctx.as_ref().and_then(|ctx| ctx.jit_frames.as_mut()) };
+    if let Some(frames) = frames {
+        frames.push(root);
+    }
 }
 
 /// Call a VM-interpreted function from JIT code.
@@ -266,12 +326,34 @@ pub extern "C" fn vo_panic(ctx: *mut JitContext, msg_slot0: u64, msg_slot1: u64)
     unsafe {
         let ctx = &mut *ctx;
         *ctx.panic_flag = true;
+        ctx.panic_kind = 0;
         // Store panic message in JitContext for VM to read
         (*ctx.panic_msg).slot0 = msg_slot0;
         (*ctx.panic_msg).slot1 = msg_slot1;
     }
 }
 
+/// Set a runtime error panic of `kind` with `msg`, as the VM would raise it.
+fn set_runtime_panic(ctx: *mut JitContext, kind: PanicKind, msg: &str) {
+    use crate::objects::string;
+    unsafe {
+        let ctx = &mut *ctx;
+        let s = string::new_from_string(&mut *ctx.gc, msg.to_string());
+        *ctx.panic_flag = true;
+        *ctx.panic_msg = InterfaceSlot::from_string(s);
+        ctx.panic_kind = kind as u8;
+    }
+}
+
+/// Raise a runtime error (`PanicKind as u32`) from a check in JIT code:
+/// bounds, nil pointer, divide by zero, negative shift or, with checked
+/// arithmetic, integer overflow.
+#[no_mangle]
+pub extern "C" fn vo_panic_runtime(ctx: *mut JitContext, kind: u32) {
+    let kind = PanicKind::from_u8(kind as u8).unwrap_or(PanicKind::Other);
+    set_runtime_panic(ctx, kind, kind.message());
+}
+
 /// Call an extern function from JIT code.
 ///
 /// # Arguments
@@ -280,10 +362,13 @@ pub extern "C" fn vo_panic(ctx: *mut JitContext, msg_slot0: u64, msg_slot1: u64)
 /// - `args`: Pointer to argument slots
 /// - `arg_count`: Number of argument slots
 /// - `ret`: Pointer to return value slots
+/// - `can_park`: Nonzero if the caller can exit to the VM at this call
 ///
 /// # Returns
 /// - `JitResult::Ok` if function completed normally
 /// - `JitResult::Panic` if function panicked
+/// - `JitResult::Deopt` if the extern suspended (only when `can_park`): the
+///   caller exits so the VM re-executes the call and parks the fiber
 #[no_mangle]
 pub extern "C" fn vo_call_extern(
     ctx: *mut JitContext,
@@ -292,6 +377,7 @@ pub extern "C" fn vo_call_extern(
     arg_count: u32,
     ret: *mut u64,
     ret_slots: u32,
+    can_park: u32,
 ) -> JitResult {
     let ctx_ref = unsafe { &*ctx };
     
@@ -300,7 +386,7 @@ pub extern "C" fn vo_call_extern(
         None => return JitResult::Panic,
     };
     
-    call_fn(ctx, ctx_ref.extern_registry, ctx_ref.gc, ctx_ref.module as *const c_void, extern_id, args, arg_count, ret, ret_slots)
+    call_fn(ctx, ctx_ref.extern_registry, ctx_ref.gc, ctx_ref.module as *const c_void, extern_id, args, arg_count, ret, ret_slots, can_park)
 }
 
 /// Call a closure from JIT code.
@@ -368,6 +454,9 @@ pub extern "C" fn vo_call_closure(
 
 /// Call an interface method from JIT code.
 ///
+/// JIT code resolves the method through `JitContext::itab_table` and calls
+/// vo_call_vm itself; this is the fallback for itabs outside that table.
+///
 /// # Arguments
 /// - `ctx`: JIT context
 /// - `iface_slot0`: Interface slot0 (itab_id << 32 | value_meta)
@@ -400,7 +489,8 @@ pub extern "C" fn vo_call_iface(
     
     // Lookup func_id from itab directly
     let func_id = unsafe {
-        let itab_cache = &*ctx_ref.itab_cache;
+        let itab_cache = &mut *ctx_ref.itab_cache;
+        itab_cache.record_unresolved_call();
         itab_cache.lookup_method(itab_id, method_idx as usize)
     };
     
@@ -449,17 +539,20 @@ pub extern "C" fn vo_map_len(m: u64) -> u64 {
     map::len(m as crate::gc::GcRef) as u64
 }
 
-/// Get value from map. Returns pointer to value slots, or null if not found.
+/// Get value from map.
 /// key_ptr points to key_slots u64 values.
-/// val_ptr is output buffer for val_slots u64 values.
-/// Returns 1 if found, 0 if not found.
+/// val_ptr is output buffer for val_slots u64 values, left untouched if the key is missing.
+/// Returns 1 if found, 0 if not found (or nil map), for the comma-ok form.
 #[no_mangle]
-pub extern "C" fn vo_map_get(m: u64, key_ptr: *const u64, key_slots: u32, val_ptr: *mut u64, val_slots: u32) -> u64 {
+pub extern "C" fn vo_map_get(ctx: *mut JitContext, m: u64, key_ptr: *const u64, key_slots: u32, val_ptr: *mut u64, val_slots: u32) -> u64 {
     use crate::objects::map;
     if m == 0 { return 0; }
+    // Struct and interface keys hash through the module's type info
+    let ctx_ref = unsafe { &*ctx };
+    let module = unsafe { ctx_ref.module.as_ref() };
     
     let key = unsafe { core::slice::from_raw_parts(key_ptr, key_slots as usize) };
-    let (val_opt, ok) = map::get_with_ok(m as crate::gc::GcRef, key, None);
+    let (val_opt, ok) = map::get_with_ok(m as crate::gc::GcRef, key, module);
     
     if let Some(val) = val_opt {
         let copy_len = (val_slots as usize).min(val.len());
@@ -473,10 +566,13 @@ pub extern "C" fn vo_map_get(m: u64, key_ptr: *const u64, key_slots: u32, val_pt
 /// Set value in map.
 /// Returns: 0 = success, 1 = panic (interface key with uncomparable type)
 #[no_mangle]
-pub extern "C" fn vo_map_set(m: u64, key_ptr: *const u64, key_slots: u32, val_ptr: *const u64, val_slots: u32) -> u64 {
+pub extern "C" fn vo_map_set(ctx: *mut JitContext, m: u64, key_ptr: *const u64, key_slots: u32, val_ptr: *const u64, val_slots: u32) -> u64 {
     use crate::objects::{map, interface};
     use crate::ValueKind;
     if m == 0 { return 0; }
+    // Struct and interface keys hash through the module's type info
+    let ctx_ref = unsafe { &*ctx };
+    let module = unsafe { ctx_ref.module.as_ref() };
     
     let key = unsafe { core::slice::from_raw_parts(key_ptr, key_slots as usize) };
     let val = unsafe { core::slice::from_raw_parts(val_ptr, val_slots as usize) };
@@ -489,14 +585,15 @@ pub extern "C" fn vo_map_set(m: u64, key_ptr: *const u64, key_slots: u32, val_pt
             let inner_vk = interface::unpack_value_kind(slot0);
             match inner_vk {
                 ValueKind::Slice | ValueKind::Map | ValueKind::Closure => {
-                    return 1; // Uncomparable type - should panic
+                    set_runtime_panic(ctx, PanicKind::Other, "runtime error: hash of unhashable type");
+                    return 1;
                 }
                 _ => {}
             }
         }
     }
     
-    map::set(m as crate::gc::GcRef, key, val, None);
+    map::set(m as crate::gc::GcRef, key, val, module);
     0
 }
 
@@ -512,10 +609,15 @@ pub extern "C" fn vo_map_delete(m: u64, key_ptr: *const u64, key_slots: u32) {
 
 /// Initialize a map iterator. Writes MAP_ITER_SLOTS * SLOT_BYTES bytes to iter_ptr.
 #[no_mangle]
-pub extern "C" fn vo_map_iter_init(m: u64, iter_ptr: *mut u64) {
+pub extern "C" fn vo_map_iter_init(ctx: *mut JitContext, m: u64, iter_ptr: *mut u64) {
     use crate::objects::map;
     const SLOTS: usize = map::MAP_ITER_SLOTS;
-    let iter = map::iter_init(m as crate::gc::GcRef);
+    let m = m as crate::gc::GcRef;
+    let iter = if sorted_map_iter(ctx) {
+        map::iter_init_sorted(unsafe { &mut *(*ctx).gc }, m)
+    } else {
+        map::iter_init(m)
+    };
     unsafe {
         core::ptr::copy_nonoverlapping(
             &iter as *const map::MapIterator as *const u64,
@@ -526,6 +628,10 @@ pub extern "C" fn vo_map_iter_init(m: u64, iter_ptr: *mut u64) {
     core::mem::forget(iter);
 }
 
+fn sorted_map_iter(ctx: *const JitContext) -> bool {
+    unsafe { (*ctx).sorted_map_iter }
+}
+
 /// Advance map iterator and get next key-value pair.
 /// Returns 1 if valid entry exists, 0 if exhausted.
 #[no_mangle]
@@ -566,6 +672,20 @@ pub extern "C" fn vo_str_decode_rune(s: u64, pos: u64) -> u64 {
     ((rune as u64) << 32) | (width as u64)
 }
 
+/// Report whether `sub` occurs in `s`. Returns 1 or 0.
+#[no_mangle]
+pub extern "C" fn vo_str_contains(s: u64, sub: u64) -> u64 {
+    use crate::objects::string;
+    string::contains(s as crate::gc::GcRef, sub as crate::gc::GcRef) as u64
+}
+
+/// Byte offset of the first `sub` in `s`, or -1.
+#[no_mangle]
+pub extern "C" fn vo_str_index_of(s: u64, sub: u64) -> i64 {
+    use crate::objects::string;
+    string::index_of(s as crate::gc::GcRef, sub as crate::gc::GcRef)
+}
+
 /// Get string length.
 #[no_mangle]
 pub extern "C" fn vo_str_len(s: u64) -> u64 {
@@ -666,12 +786,17 @@ pub extern "C" fn vo_closure_new(gc: *mut Gc, func_id: u32, capture_count: u32)
 // =============================================================================
 
 /// Create a new channel.
+/// Returns 0 after setting a runtime panic if `cap` is negative.
 #[no_mangle]
-pub extern "C" fn vo_chan_new(gc: *mut Gc, elem_meta: u32, elem_slots: u32, cap: u64) -> u64 {
+pub extern "C" fn vo_chan_new(ctx: *mut JitContext, elem_meta: u32, elem_slots: u32, cap: u64) -> u64 {
     use crate::objects::channel;
     use crate::ValueMeta;
+    if (cap as i64) < 0 {
+        set_runtime_panic(ctx, PanicKind::Other, "runtime error: makechan: size out of range");
+        return 0;
+    }
     unsafe {
-        let gc = &mut *gc;
+        let gc = &mut *(*ctx).gc;
         channel::create(gc, ValueMeta::from_raw(elem_meta), elem_slots as u16, cap as usize) as u64
     }
 }
@@ -931,7 +1056,11 @@ pub extern "C" fn vo_iface_eq(
     let ctx_ref = unsafe { &*ctx };
     let module = unsafe { &*ctx_ref.module };
     
-    compare::iface_eq(b_slot0, b_slot1, c_slot0, c_slot1, module)
+    let result = compare::iface_eq(b_slot0, b_slot1, c_slot0, c_slot1, module);
+    if result == 2 {
+        set_runtime_panic(ctx, PanicKind::Other, "runtime error: comparing uncomparable type in interface value");
+    }
+    result
 }
 
 /// Interface assertion.
@@ -1046,15 +1175,11 @@ unsafe fn write_iface_assert_success(
             }
         }
     } else if src_vk == ValueKind::Array {
-        // Copy elements from array (layout: [GcHeader][ArrayHeader][elements...])
+        // Unpack elements from the heap array
         use crate::objects::array;
-        let gc_ref = slot1 as GcRef;
         let slots = target_slots.max(1);
         if slot1 != 0 {
-            let data_ptr = array::data_ptr_bytes(gc_ref) as *const u64;
-            for i in 0..slots {
-                *dst.add(i) = *data_ptr.add(i);
-            }
+            array::read_slots(slot1 as GcRef, core::slice::from_raw_parts_mut(dst, slots));
         } else {
             for i in 0..slots {
                 *dst.add(i) = 0;
@@ -1070,36 +1195,15 @@ unsafe fn write_iface_assert_success(
 // Slice Copy
 // =============================================================================
 
-/// Copy elements from src slice to dst slice (for JIT).
-/// Returns the number of elements copied (min of dst len and src len).
+/// copy(dst, src) for JIT code; same memmove semantics as the builtin extern.
+/// Returns the number of elements copied.
 #[no_mangle]
-pub extern "C" fn vo_copy(dst: u64, src: u64) -> u64 {
-    use crate::objects::{slice, array};
-    use crate::gc::GcRef;
-    
-    if dst == 0 || src == 0 {
-        return 0;
-    }
-    
-    let dst_ref = dst as GcRef;
-    let src_ref = src as GcRef;
-    
-    let dst_len = slice::len(dst_ref);
-    let src_len = slice::len(src_ref);
-    let copy_len = dst_len.min(src_len);
-    
-    if copy_len == 0 {
-        return 0;
+pub extern "C" fn vo_slice_copy(gc: *mut Gc, dst: u64, src: u64) -> u64 {
+    use crate::objects::slice;
+    unsafe {
+        let gc = &mut *gc;
+        slice::copy(gc, dst as crate::gc::GcRef, src as crate::gc::GcRef) as u64
     }
-    
-    let dst_arr = slice::array_ref(dst_ref);
-    let elem_bytes = array::elem_bytes(dst_arr);
-    let dst_ptr = slice::data_ptr(dst_ref);
-    let src_ptr = slice::data_ptr(src_ref);
-    
-    unsafe { core::ptr::copy_nonoverlapping(src_ptr, dst_ptr, copy_len * elem_bytes) };
-    
-    copy_len as u64
 }
 
 // =============================================================================
@@ -1128,6 +1232,8 @@ pub fn get_runtime_symbols() -> &'static [(&'static str, *const u8)] {
         ("vo_str_eq", vo_str_eq as *const u8),
         ("vo_str_cmp", vo_str_cmp as *const u8),
         ("vo_str_decode_rune", vo_str_decode_rune as *const u8),
+        ("vo_str_contains", vo_str_contains as *const u8),
+        ("vo_str_index_of", vo_str_index_of as *const u8),
         ("vo_closure_new", vo_closure_new as *const u8),
         ("vo_chan_new", vo_chan_new as *const u8),
         ("vo_array_new", vo_array_new as *const u8),
@@ -1140,6 +1246,7 @@ pub fn get_runtime_symbols() -> &'static [(&'static str, *const u8)] {
         ("vo_slice_get", vo_slice_get as *const u8),
         ("vo_slice_set", vo_slice_set as *const u8),
         ("vo_slice_slice", vo_slice_slice as *const u8),
+        ("vo_slice_copy", vo_slice_copy as *const u8),
         ("vo_slice_slice3", vo_slice_slice3 as *const u8),
         ("vo_slice_append", vo_slice_append as *const u8),
         ("vo_slice_from_array", vo_slice_from_array as *const u8),
@@ -1156,7 +1263,6 @@ pub fn get_runtime_symbols() -> &'static [(&'static str, *const u8)] {
         ("vo_map_delete", vo_map_delete as *const u8),
         ("vo_map_iter_init", vo_map_iter_init as *const u8),
         ("vo_map_iter_next", vo_map_iter_next as *const u8),
-        ("vo_copy", vo_copy as *const u8),
     ]
 }
 
@@ -16,6 +16,7 @@ pub mod itab;
 pub mod pack;
 pub mod island;
 pub mod island_msg;
+pub mod panic;
 
 // FFI core types (no_std compatible), registration requires std
 pub mod ffi;
@@ -40,6 +41,7 @@ pub use vo_common_core::runtime_type::{RuntimeType, ChanDir, StructField, Interf
 pub use vo_common_core::instruction::{Instruction, Opcode};
 pub use vo_common_core::bytecode::{Module, FunctionDef, Constant, ExternDef, GlobalDef, StructMeta, InterfaceMeta, Itab};
 pub use vo_common_core::serialize;
+pub use vo_common_core::validate;
 
 // Re-export modules for downstream crates
 pub use vo_common_core::bytecode;
@@ -49,11 +51,12 @@ pub use vo_common_core::symbol;
 
 // Re-export InterfaceSlot from objects::interface (the canonical location)
 pub use objects::interface::InterfaceSlot;
+pub use panic::PanicKind;
 
 // Re-exports from ffi (core types always available)
 pub use ffi::{
     ExternCall, ExternCallContext, ExternFn, ExternFnWithContext, ExternRegistry, ExternResult,
-    ClosureCallResult, ClosureCallFn, SentinelErrorCache,
+    ClosureCallResult, ClosureCallFn, FromSlots, SentinelErrorCache,
 };
 // Re-exports from ffi (std only - linkme registration)
 #[cfg(feature = "std")]
@@ -199,6 +199,36 @@ pub fn set_n(arr: GcRef, idx: usize, src: &[u64], elem_bytes: usize) {
     }
 }
 
+/// Copy all elements into `dst` in stack layout, one slot per scalar, loaded
+/// the way ArrayGet loads them: narrow signed integers are sign-extended.
+/// Slots with no element behind them (zero-size elements) are zeroed.
+pub fn read_slots(arr: GcRef, dst: &mut [u64]) {
+    dst.fill(0);
+    let elem_bytes = elem_bytes(arr);
+    let len = len(arr);
+    match elem_bytes {
+        1 | 2 | 4 => {
+            let kind = elem_kind(arr);
+            for (i, slot) in dst.iter_mut().take(len).enumerate() {
+                let raw = get(arr, i, elem_bytes);
+                *slot = match kind {
+                    ValueKind::Int8 => raw as i8 as i64 as u64,
+                    ValueKind::Int16 => raw as i16 as i64 as u64,
+                    ValueKind::Int32 => raw as i32 as i64 as u64,
+                    _ => raw,
+                };
+            }
+        }
+        _ => {
+            let slots = (len * elem_bytes / SLOT_BYTES).min(dst.len());
+            let src = data_ptr_bytes(arr) as *const u64;
+            for (i, slot) in dst[..slots].iter_mut().enumerate() {
+                *slot = unsafe { *src.add(i) };
+            }
+        }
+    }
+}
+
 /// Copy element range (by elem_bytes)
 pub fn copy_range(src: GcRef, src_idx: usize, dst: GcRef, dst_idx: usize, count: usize, elem_bytes: usize) {
     let src_ptr = unsafe { data_ptr_bytes(src).add(src_idx * elem_bytes) };
@@ -50,6 +50,11 @@ pub fn is_closed(chan: GcRef) -> bool { get_state(chan).is_closed() }
 #[inline]
 pub fn close(chan: GcRef) { get_state(chan).close(); }
 
+/// A copy of the buffer, waiters and closed flag, for `restore_state`.
+pub fn save_state(chan: GcRef) -> ChannelState { get_state(chan).clone() }
+
+pub fn restore_state(chan: GcRef, saved: &ChannelState) { *get_state(chan) = saved.clone(); }
+
 /// # Safety
 /// chan must be a valid Channel GcRef.
 pub unsafe fn drop_inner(chan: GcRef) {
@@ -64,6 +64,15 @@ impl InterfaceSlot {
         }
     }
 
+    /// Create an InterfaceSlot containing a string object.
+    #[inline]
+    pub fn from_string(s: GcRef) -> Self {
+        Self {
+            slot0: pack_slot0(0, ValueKind::String as u32, ValueKind::String),
+            slot1: s as u64,
+        }
+    }
+
     /// Create an InterfaceSlot containing a GcRef (for reference types).
     #[inline]
     pub fn from_ref(gc_ref: GcRef, rttid: u32, vk: ValueKind) -> Self {
@@ -7,17 +7,25 @@
 //! - Delete during iteration: tombstones ensure safe traversal
 //! - Insert during iteration: if resize happens, generation changes, iteration continues
 //!   (may skip or repeat elements, matching Go semantics)
+//!
+//! Sorted iteration (`iter_init_sorted`) visits entries in key order instead of
+//! bucket order, for reproducible output. Each iterator walks its own snapshot
+//! of the keys, taken when the loop starts, and looks every entry up again:
+//! deleted entries are skipped, and entries added during the loop are not seen.
 
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 #[cfg(feature = "std")]
 use std::boxed::Box;
+use core::cmp::Ordering;
 
 use super::vo_map::VoMap;
 
 use crate::gc::{Gc, GcRef};
 use crate::slot::{ptr_to_slot, slot_to_ptr, Slot, SLOT_BYTES};
-use crate::objects::string;
+use crate::objects::{array, string};
 use vo_common_core::bytecode::Module;
 use vo_common_core::types::{ValueKind, ValueMeta};
 
@@ -27,6 +35,7 @@ type SingleKeyMap = VoMap<u64, Box<[u64]>>;
 type MultiKeyMap = VoMap<Box<[u64]>, Box<[u64]>>;
 type StringKeyMap = VoMap<Box<[u8]>, (GcRef, Box<[u64]>)>;
 
+#[derive(Clone)]
 pub struct StructKeyEntry {
     pub key: Box<[u64]>,
     pub val: Box<[u64]>,
@@ -34,6 +43,7 @@ pub struct StructKeyEntry {
 
 type StructKeyMap = VoMap<u64, StructKeyEntry>;
 
+#[derive(Clone)]
 pub struct InterfaceKeyEntry {
     pub key: [u64; 2],
     pub val: Box<[u64]>,
@@ -41,6 +51,7 @@ pub struct InterfaceKeyEntry {
 
 type InterfaceKeyMap = VoMap<u64, InterfaceKeyEntry>;
 
+#[derive(Clone)]
 pub enum MapInner {
     SingleKey(SingleKeyMap),
     MultiKey(MultiKeyMap),
@@ -278,8 +289,11 @@ pub struct MapIterator {
     pub tag: u8,
     pub _pad: [u8; 3],
     pub init_generation: u32,
+    /// Next bucket index, or next position in `order`.
     pub current_index: u64,
-    pub _reserved: [u64; 4],
+    pub _reserved: [u64; 3],
+    /// Keys in iteration order for `iter_init_sorted`, else 0 (see `key_snapshot`).
+    pub order: u64,
     pub map_ref: u64,
 }
 
@@ -287,6 +301,10 @@ pub const MAP_ITER_SLOTS: usize = core::mem::size_of::<MapIterator>() / SLOT_BYT
 const _: () = assert!(core::mem::size_of::<MapIterator>() == MAP_ITER_SLOTS * SLOT_BYTES);
 const _: () = assert!(MAP_ITER_SLOTS == 7);
 
+/// Slot of `MapIterator::order`, the one iterator slot holding a GcRef.
+pub const MAP_ITER_ORDER_SLOT: usize = 5;
+const _: () = assert!(core::mem::offset_of!(MapIterator, order) == MAP_ITER_ORDER_SLOT * SLOT_BYTES);
+
 const TAG_SINGLE_KEY: u8 = 0;
 const TAG_MULTI_KEY: u8 = 1;
 const TAG_STRING_KEY: u8 = 2;
@@ -301,7 +319,8 @@ pub fn iter_init(m: GcRef) -> MapIterator {
             _pad: [0; 3],
             init_generation: 0,
             current_index: 0,
-            _reserved: [0; 4],
+            _reserved: [0; 3],
+            order: 0,
             map_ref: 0,
         };
     }
@@ -319,11 +338,30 @@ pub fn iter_init(m: GcRef) -> MapIterator {
         _pad: [0; 3],
         init_generation: generation(m),
         current_index: 0,
-        _reserved: [0; 4],
+        _reserved: [0; 3],
+        order: 0,
         map_ref: m as u64,
     }
 }
 
+/// Like `iter_init`, but entries come out in key order.
+///
+/// Numbers and strings sort by value; multi-slot keys compare slot by slot.
+/// Struct and interface keys have no natural order and sort by content hash,
+/// which is still stable from run to run.
+///
+/// The iterator owns a snapshot of the keys on the heap, so nested loops over
+/// one map and changes made inside the loop don't disturb its order. The
+/// snapshot stays alive only while the iterator's `MAP_ITER_ORDER_SLOT` is
+/// scanned as a GcRef.
+pub fn iter_init_sorted(gc: &mut Gc, m: GcRef) -> MapIterator {
+    let mut iter = iter_init(m);
+    if !m.is_null() {
+        iter.order = key_snapshot(gc, m) as u64;
+    }
+    iter
+}
+
 pub fn iter_next(iter: &mut MapIterator) -> Option<(&'static [u64], &'static [u64])> {
     if iter.tag == TAG_EXHAUSTED {
         return None;
@@ -343,61 +381,171 @@ pub fn iter_next(iter: &mut MapIterator) -> Option<(&'static [u64], &'static [u6
         // Continue from current index - may skip or repeat elements, which is Go-like behavior
     }
     
-    let idx = iter.current_index as usize;
+    if iter.order != 0 {
+        return sorted_next(iter, m);
+    }
     
+    match entry_from(iter, m, iter.current_index as usize) {
+        Some((idx, k, v)) => {
+            iter.current_index = (idx + 1) as u64;
+            Some((k, v))
+        }
+        None => {
+            iter.tag = TAG_EXHAUSTED;
+            None
+        }
+    }
+}
+
+fn sorted_next(iter: &mut MapIterator, m: GcRef) -> Option<(&'static [u64], &'static [u64])> {
+    let order = iter.order as GcRef;
+    let elem_slots = array::elem_bytes(order) / SLOT_BYTES;
+    while (iter.current_index as usize) < array::len(order) {
+        let pos = iter.current_index as usize;
+        iter.current_index += 1;
+        let key = unsafe {
+            let ptr = array::data_ptr_bytes(order).add(pos * elem_slots * SLOT_BYTES) as *const u64;
+            core::slice::from_raw_parts(ptr, elem_slots)
+        };
+        // Entries deleted since the snapshot are skipped
+        let found = match get_inner(m) {
+            MapInner::SingleKey(map) => map.get(&key[0]).map(|v| (key, v.as_ref())),
+            MapInner::MultiKey(map) => map.get(&Box::from(key)).map(|v| (key, v.as_ref())),
+            MapInner::StringKey(map) => {
+                let str_bytes: Box<[u8]> = string::as_bytes(key[0] as GcRef).into();
+                map.get(&str_bytes).map(|(_, v)| (key, v.as_ref()))
+            }
+            MapInner::StructKey(map) => map.get(&key[0]).map(|e| (e.key.as_ref(), e.val.as_ref())),
+            MapInner::InterfaceKey(map) => map.get(&key[0]).map(|e| (e.key.as_slice(), e.val.as_ref())),
+        };
+        if found.is_some() {
+            return found;
+        }
+    }
+    iter.tag = TAG_EXHAUSTED;
+    None
+}
+
+/// First occupied bucket at or after `idx`, with its key and value slots.
+fn entry_from(iter: &mut MapIterator, m: GcRef, idx: usize) -> Option<(usize, &'static [u64], &'static [u64])> {
     match get_inner(m) {
         MapInner::SingleKey(map) => {
-            if let Some((new_idx, k, v)) = map.iter_from_index(idx) {
-                iter.current_index = (new_idx + 1) as u64;
-                let k_slice = unsafe { core::slice::from_raw_parts(k, 1) };
-                Some((k_slice, v.as_ref()))
-            } else {
-                iter.tag = TAG_EXHAUSTED;
-                None
-            }
+            let (idx, k, v) = map.iter_from_index(idx)?;
+            let k_slice = unsafe { core::slice::from_raw_parts(k, 1) };
+            Some((idx, k_slice, v.as_ref()))
         }
         MapInner::MultiKey(map) => {
-            if let Some((new_idx, k, v)) = map.iter_from_index(idx) {
-                iter.current_index = (new_idx + 1) as u64;
-                Some((k.as_ref(), v.as_ref()))
-            } else {
-                iter.tag = TAG_EXHAUSTED;
-                None
-            }
+            let (idx, k, v) = map.iter_from_index(idx)?;
+            Some((idx, k.as_ref(), v.as_ref()))
         }
         MapInner::StringKey(map) => {
-            if let Some((new_idx, _, (str_ref, v))) = map.iter_from_index(idx) {
-                iter.current_index = (new_idx + 1) as u64;
-                // Store GcRef as Slot in reserved space (works on both 32-bit and 64-bit)
-                iter._reserved[0] = ptr_to_slot(*str_ref);
-                let k_slice = unsafe { core::slice::from_raw_parts(&iter._reserved[0], 1) };
-                Some((k_slice, v.as_ref()))
-            } else {
-                iter.tag = TAG_EXHAUSTED;
-                None
-            }
+            let (idx, _, (str_ref, v)) = map.iter_from_index(idx)?;
+            // Store GcRef as Slot in reserved space (works on both 32-bit and 64-bit)
+            iter._reserved[0] = ptr_to_slot(*str_ref);
+            let k_slice = unsafe { core::slice::from_raw_parts(&iter._reserved[0], 1) };
+            Some((idx, k_slice, v.as_ref()))
         }
         MapInner::StructKey(map) => {
-            if let Some((new_idx, _, entry)) = map.iter_from_index(idx) {
-                iter.current_index = (new_idx + 1) as u64;
-                Some((entry.key.as_ref(), entry.val.as_ref()))
-            } else {
-                iter.tag = TAG_EXHAUSTED;
-                None
-            }
+            let (idx, _, entry) = map.iter_from_index(idx)?;
+            Some((idx, entry.key.as_ref(), entry.val.as_ref()))
         }
         MapInner::InterfaceKey(map) => {
-            if let Some((new_idx, _, entry)) = map.iter_from_index(idx) {
-                iter.current_index = (new_idx + 1) as u64;
-                Some((entry.key.as_slice(), entry.val.as_ref()))
-            } else {
-                iter.tag = TAG_EXHAUSTED;
-                None
-            }
+            let (idx, _, entry) = map.iter_from_index(idx)?;
+            Some((idx, entry.key.as_slice(), entry.val.as_ref()))
         }
     }
 }
 
+/// The keys of `m` in key order, as a GC array with one element per entry
+/// holding what `sorted_next` looks the entry up by: the key slots, or the
+/// content hash that struct and interface maps are keyed by. String keys
+/// are typed as strings so the collector keeps them alive.
+fn key_snapshot(gc: &mut Gc, m: GcRef) -> GcRef {
+    let kind = key_kind(m);
+    let (elem_kind, elem_slots, keys) = match get_inner(m) {
+        MapInner::SingleKey(map) => {
+            let order = key_order(map, |k, _| *k, |a, b| cmp_scalar(kind, *a, *b));
+            (ValueKind::Uint64, 1, snapshot_keys(map, &order, |k, _, out| out.push(*k)))
+        }
+        MapInner::MultiKey(map) => {
+            let order = key_order(map, |k, _| k, |a, b| a.cmp(b));
+            (ValueKind::Uint64, key_slots(m) as usize, snapshot_keys(map, &order, |k, _, out| out.extend_from_slice(k)))
+        }
+        MapInner::StringKey(map) => {
+            let order = key_order(map, |k, _| k, |a, b| a.cmp(b));
+            (ValueKind::String, 1, snapshot_keys(map, &order, |_, (s, _), out| out.push(ptr_to_slot(*s))))
+        }
+        MapInner::StructKey(map) => {
+            let order = key_order(map, |h, _| *h, |a, b| a.cmp(b));
+            (ValueKind::Uint64, 1, snapshot_keys(map, &order, |h, _, out| out.push(*h)))
+        }
+        MapInner::InterfaceKey(map) => {
+            let order = key_order(map, |h, _| *h, |a, b| a.cmp(b));
+            (ValueKind::Uint64, 1, snapshot_keys(map, &order, |h, _, out| out.push(*h)))
+        }
+    };
+    let elem_bytes = elem_slots * SLOT_BYTES;
+    let arr = array::create(gc, ValueMeta::new(0, elem_kind), elem_bytes, keys.len() / elem_slots);
+    for (i, key) in keys.chunks(elem_slots).enumerate() {
+        array::set_n(arr, i, key, elem_bytes);
+    }
+    arr
+}
+
+/// The slots `push` writes for each bucket in `order`, concatenated.
+fn snapshot_keys<K: Eq + core::hash::Hash, V>(
+    map: &VoMap<K, V>,
+    order: &[u32],
+    push: impl Fn(&K, &V, &mut Vec<u64>),
+) -> Vec<u64> {
+    let mut keys = Vec::new();
+    for &idx in order {
+        if let Some((_, k, v)) = map.iter_from_index(idx as usize) {
+            push(k, v, &mut keys);
+        }
+    }
+    keys
+}
+
+/// Occupied bucket indices of `map`, ordered by `cmp` on `key`.
+fn key_order<'a, K: Eq + core::hash::Hash, V, T>(
+    map: &'a VoMap<K, V>,
+    key: impl Fn(&'a K, &'a V) -> T,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> Vec<u32> {
+    let mut entries = Vec::with_capacity(map.len());
+    let mut idx = 0;
+    while let Some((i, k, v)) = map.iter_from_index(idx) {
+        entries.push((i as u32, key(k, v)));
+        idx = i + 1;
+    }
+    entries.sort_by(|a, b| cmp(&a.1, &b.1));
+    entries.into_iter().map(|(i, _)| i).collect()
+}
+
+fn cmp_scalar(kind: ValueKind, a: u64, b: u64) -> Ordering {
+    match kind {
+        ValueKind::Int | ValueKind::Int8 | ValueKind::Int16 | ValueKind::Int32 | ValueKind::Int64 => {
+            (a as i64).cmp(&(b as i64))
+        }
+        ValueKind::Float32 => f32::from_bits(a as u32).total_cmp(&f32::from_bits(b as u32)),
+        ValueKind::Float64 => f64::from_bits(a).total_cmp(&f64::from_bits(b)),
+        _ => a.cmp(&b),
+    }
+}
+
+/// A copy of a map's entries, taken by `save_body`.
+pub struct SavedBody(MapInner);
+
+pub fn save_body(m: GcRef) -> SavedBody {
+    SavedBody(get_inner(m).clone())
+}
+
+/// Put back the entries saved by `save_body`, dropping the current ones.
+pub fn restore_body(m: GcRef, saved: &SavedBody) {
+    *get_inner(m) = saved.0.clone();
+}
+
 pub unsafe fn drop_inner(m: GcRef) {
     let data = MapData::as_mut(m);
     if data.inner != 0 {
@@ -107,6 +107,7 @@ pub enum RecvResult<W> {
 /// Type parameters:
 /// - `W`: Waiter identifier type (e.g., `GoId` for Channel, `WaiterInfo` for Port)
 /// - `M`: Message type (e.g., `Box<[u64]>` for Channel, `PackedValue` for Port)
+#[derive(Clone)]
 pub struct QueueState<W, M> {
     pub buffer: VecDeque<M>,
     pub closed: bool,
@@ -126,13 +127,22 @@ impl<W, M> QueueState<W, M> {
 
     /// Try to send a value. Returns the value back if would block.
     pub fn try_send(&mut self, value: M, cap: usize) -> SendResult<W, M> {
+        self.try_send_claim(value, cap, |_| true)
+    }
+
+    /// `try_send` that only hands the value to a waiting receiver `claim`
+    /// accepts. Refused receivers are dropped from the queue: a waiter
+    /// parked on several queues (a select) completes only one operation.
+    pub fn try_send_claim(&mut self, value: M, cap: usize, mut claim: impl FnMut(&W) -> bool) -> SendResult<W, M> {
         if self.closed {
             return SendResult::Closed;
         }
         // If there's a waiting receiver, buffer the value and wake receiver
-        if let Some(receiver) = self.waiting_receivers.pop_front() {
-            self.buffer.push_back(value);
-            return SendResult::DirectSend(receiver);
+        while let Some(receiver) = self.waiting_receivers.pop_front() {
+            if claim(&receiver) {
+                self.buffer.push_back(value);
+                return SendResult::DirectSend(receiver);
+            }
         }
         // Buffer if capacity allows
         if self.buffer.len() < cap {
@@ -144,16 +154,20 @@ impl<W, M> QueueState<W, M> {
 
     /// Try to receive a value.
     pub fn try_recv(&mut self) -> (RecvResult<W>, Option<M>) {
+        self.try_recv_claim(|_| true)
+    }
+
+    /// `try_recv` that only takes from a waiting sender `claim` accepts,
+    /// dropping refused senders as `try_send_claim` does receivers.
+    pub fn try_recv_claim(&mut self, mut claim: impl FnMut(&W) -> bool) -> (RecvResult<W>, Option<M>) {
         if let Some(value) = self.buffer.pop_front() {
-            let woke_sender = if let Some((sender, sender_value)) = self.waiting_senders.pop_front() {
+            let woke_sender = self.pop_sender(&mut claim).map(|(sender, sender_value)| {
                 self.buffer.push_back(sender_value);
-                Some(sender)
-            } else {
-                None
-            };
+                sender
+            });
             return (RecvResult::Success(woke_sender), Some(value));
         }
-        if let Some((sender, value)) = self.waiting_senders.pop_front() {
+        if let Some((sender, value)) = self.pop_sender(&mut claim) {
             return (RecvResult::Success(Some(sender)), Some(value));
         }
         if self.closed {
@@ -163,10 +177,30 @@ impl<W, M> QueueState<W, M> {
         }
     }
 
+    fn pop_sender(&mut self, claim: &mut impl FnMut(&W) -> bool) -> Option<(W, M)> {
+        while let Some((sender, value)) = self.waiting_senders.pop_front() {
+            if claim(&sender) {
+                return Some((sender, value));
+            }
+        }
+        None
+    }
+
     pub fn register_sender(&mut self, waiter: W, value: M) {
         self.waiting_senders.push_back((waiter, value));
     }
 
+    /// Whether `waiter` is still parked with an unreceived value.
+    pub fn is_waiting_sender(&self, waiter: &W) -> bool where W: PartialEq {
+        self.waiting_senders.iter().any(|(w, _)| w == waiter)
+    }
+
+    /// Withdraw every parked entry of `waiter`, dropping any value it was sending.
+    pub fn remove_waiter(&mut self, waiter: &W) where W: PartialEq {
+        self.waiting_senders.retain(|(w, _)| w != waiter);
+        self.waiting_receivers.retain(|w| w != waiter);
+    }
+
     pub fn register_receiver(&mut self, waiter: W) {
         self.waiting_receivers.push_back(waiter);
     }
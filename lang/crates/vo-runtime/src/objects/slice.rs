@@ -224,6 +224,23 @@ pub fn slice_of_with_cap(gc: &mut Gc, s: GcRef, lo: usize, hi: usize, max: usize
     Some(new_s)
 }
 
+/// `copy(dst, src)`: move `min(len(dst), len(src))` elements like memmove, so
+/// the two may overlap. `src` may be a string (same layout) when `dst` is []byte.
+/// Returns the number of elements copied.
+pub fn copy(gc: &mut Gc, dst: GcRef, src: GcRef) -> usize {
+    if dst.is_null() || src.is_null() {
+        return 0;
+    }
+    let n = len(dst).min(len(src));
+    if n == 0 {
+        return 0;
+    }
+    let arr = array_ref(dst);
+    unsafe { core::ptr::copy(data_ptr(src), data_ptr(dst), n * array::elem_bytes(arr)) };
+    gc.write_barrier(arr, core::ptr::null_mut());
+    n
+}
+
 /// Create new slice header with updated length (same backing array, same start).
 /// Used by append when capacity is sufficient.
 pub fn with_new_len(gc: &mut Gc, s: GcRef, new_len: usize) -> GcRef {
@@ -261,6 +278,7 @@ pub fn append(gc: &mut Gc, em: ValueMeta, elem_bytes: usize, s: GcRef, val: &[u6
                 unsafe { core::ptr::copy_nonoverlapping(src_bytes, ptr, elem_bytes) };
             }
         }
+        gc.write_barrier(ptr as GcRef, core::ptr::null_mut());
         // Go semantics: append never modifies original slice header
         with_new_len(gc, s, cur_len + 1)
     } else {
@@ -275,3 +293,38 @@ pub fn append(gc: &mut Gc, em: ValueMeta, elem_bytes: usize, s: GcRef, val: &[u6
         from_array_range(gc, new_arr, 0, cur_len + 1)
     }
 }
+
+/// `append(s, other...)`: append every element of `other`, which may be a
+/// string (same layout) when `s` is []byte, and may share `s`'s backing array.
+/// em is the element meta used if a new backing array is needed.
+pub fn append_slice(gc: &mut Gc, em: ValueMeta, s: GcRef, other: GcRef) -> GcRef {
+    let other_len = if other.is_null() { 0 } else { len(other) };
+    if other_len == 0 {
+        return s;
+    }
+    let (cur_len, cur_cap) = if s.is_null() { (0, 0) } else { (len(s), cap(s)) };
+    let elem_bytes = array::elem_bytes(array_ref(if s.is_null() { other } else { s }));
+    let new_len = cur_len + other_len;
+    if new_len <= cur_cap {
+        let arr = array_ref(s);
+        // memmove: `other` may overlap the destination range
+        unsafe {
+            let write_ptr = data_ptr(s).add(cur_len * elem_bytes);
+            core::ptr::copy(data_ptr(other), write_ptr, other_len * elem_bytes);
+        }
+        gc.write_barrier(arr, core::ptr::null_mut());
+        // Go semantics: append never modifies original slice header
+        with_new_len(gc, s, new_len)
+    } else {
+        let new_cap = (new_len * 2).max(4);
+        let new_arr = array::create(gc, em, elem_bytes, new_cap);
+        let dst_ptr = array::data_ptr_bytes(new_arr);
+        unsafe {
+            if cur_len > 0 {
+                core::ptr::copy_nonoverlapping(data_ptr(s), dst_ptr, cur_len * elem_bytes);
+            }
+            core::ptr::copy_nonoverlapping(data_ptr(other), dst_ptr.add(cur_len * elem_bytes), other_len * elem_bytes);
+        }
+        from_array_range(gc, new_arr, 0, new_len)
+    }
+}
@@ -72,16 +72,69 @@ pub fn decode_rune_at(s: GcRef, pos: usize) -> (i32, usize) {
 /// Unicode replacement character returned for invalid UTF-8.
 pub const RUNE_ERROR: i32 = 0xFFFD;
 
-/// Decode a single UTF-8 rune from bytes.
-/// Returns (rune, width). For invalid UTF-8, returns (RUNE_ERROR, 1).
-fn decode_rune(bytes: &[u8]) -> (i32, usize) {
-    match core::str::from_utf8(bytes).ok().and_then(|s| s.chars().next()) {
-        Some(c) => (c as i32, c.len_utf8()),
-        None if !bytes.is_empty() => (RUNE_ERROR, 1),
-        None => (RUNE_ERROR, 0),
+/// Decode the first UTF-8 rune in `bytes`. Returns (rune, width).
+/// Like Go's utf8.DecodeRune: empty input gives (RUNE_ERROR, 0), and an
+/// invalid, overlong, surrogate or truncated encoding gives (RUNE_ERROR, 1).
+pub fn decode_rune(bytes: &[u8]) -> (i32, usize) {
+    let Some(&b0) = bytes.first() else { return (RUNE_ERROR, 0) };
+    let width = match b0 {
+        0x00..=0x7F => return (b0 as i32, 1),
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return (RUNE_ERROR, 1),
+    };
+    // Only the candidate sequence is validated, so bad bytes later in the
+    // string don't affect this rune.
+    match bytes.get(..width).and_then(|b| core::str::from_utf8(b).ok()) {
+        Some(c) => (c.chars().next().unwrap() as i32, width),
+        None => (RUNE_ERROR, 1),
+    }
+}
+
+/// Iterator over `(byte_offset, rune)` pairs, the values `for i, r := range s`
+/// visits. Each invalid byte yields RUNE_ERROR on its own.
+pub struct Runes {
+    bytes: &'static [u8],
+    pos: usize,
+}
+
+impl Iterator for Runes {
+    type Item = (usize, i32);
+
+    fn next(&mut self) -> Option<(usize, i32)> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let start = self.pos;
+        let (rune, width) = decode_rune(&self.bytes[start..]);
+        self.pos += width;
+        Some((start, rune))
+    }
+}
+
+pub fn runes(s: GcRef) -> Runes {
+    Runes { bytes: as_bytes(s), pos: 0 }
+}
+
+/// Number of runes in `s`, counting each invalid byte as one rune.
+pub fn rune_count(s: GcRef) -> usize {
+    runes(s).count()
+}
+
+/// Byte offset of the first occurrence of `sub` in `s`, or -1.
+/// The empty string occurs at offset 0 of every string, as in Go.
+pub fn index_of(s: GcRef, sub: GcRef) -> i64 {
+    let (hay, needle) = (as_bytes(s), as_bytes(sub));
+    if needle.is_empty() {
+        return 0;
     }
+    memchr::memmem::find(hay, needle).map_or(-1, |i| i as i64)
 }
 
+#[inline]
+pub fn contains(s: GcRef, sub: GcRef) -> bool { index_of(s, sub) >= 0 }
+
 pub fn concat(gc: &mut Gc, a: GcRef, b: GcRef) -> GcRef {
     if a.is_null() { return b; }
     if b.is_null() { return a; }
@@ -187,16 +240,48 @@ pub fn from_rune_slice_obj(gc: &mut Gc, slice_ref: GcRef) -> GcRef {
 }
 
 /// Convert string to []rune slice object. Returns slice GcRef.
+/// Invalid bytes become RUNE_ERROR, one per byte.
 pub fn to_rune_slice_obj(gc: &mut Gc, s: GcRef) -> GcRef {
-    let str_data = as_str(s);
-    let len = str_data.chars().count();
+    let len = rune_count(s);
     if len == 0 {
         return core::ptr::null_mut();
     }
     let arr = array::create(gc, ValueMeta::new(0, ValueKind::Int32), 4, len);
     let arr_data_ptr = array::data_ptr_bytes(arr) as *mut i32;
-    for (i, c) in str_data.chars().enumerate() {
-        unsafe { *arr_data_ptr.add(i) = c as i32; }
+    for (i, (_, rune)) in runes(s).enumerate() {
+        unsafe { *arr_data_ptr.add(i) = rune; }
     }
     slice::from_array_range(gc, arr, 0, len)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_rune() {
+        assert_eq!(decode_rune(b""), (RUNE_ERROR, 0));
+        assert_eq!(decode_rune(b"a"), ('a' as i32, 1));
+        assert_eq!(decode_rune("é!".as_bytes()), ('é' as i32, 2));
+        assert_eq!(decode_rune("世".as_bytes()), ('世' as i32, 3));
+        assert_eq!(decode_rune("😀".as_bytes()), ('😀' as i32, 4));
+        // A valid rune followed by garbage still decodes.
+        assert_eq!(decode_rune(b"\xc3\xa9\xff"), ('é' as i32, 2));
+    }
+
+    #[test]
+    fn test_decode_rune_invalid() {
+        let cases: &[&[u8]] = &[
+            b"\xff",             // never valid
+            b"\x80",             // stray continuation byte
+            b"\xc3",             // truncated 2-byte sequence
+            b"\xe4\xb8",         // truncated 3-byte sequence
+            b"\xc0\x80",         // overlong NUL
+            b"\xed\xa0\x80",     // surrogate U+D800
+            b"\xf4\x90\x80\x80", // above U+10FFFF
+        ];
+        for bytes in cases {
+            assert_eq!(decode_rune(bytes), (RUNE_ERROR, 1), "{:x?}", bytes);
+        }
+    }
+}
@@ -34,6 +34,7 @@ impl<K, V> Bucket<K, V> {
 }
 
 /// Open addressing hash map with iteration-safe semantics
+#[derive(Clone)]
 pub struct VoMap<K, V> {
     buckets: Box<[Bucket<K, V>]>,
     len: usize,
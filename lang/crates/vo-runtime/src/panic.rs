@@ -0,0 +1,47 @@
+//! Kinds of runtime error raised as panics.
+
+/// Which runtime error a panic raised by the VM or by JIT code is. Panics
+/// from `panic` calls and externs have no kind, whatever their message says.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicKind {
+    IndexOutOfBounds = 1,
+    NilPointerDereference,
+    TypeAssertionFailed,
+    DivisionByZero,
+    SendOnClosedChannel,
+    NilMapWrite,
+    StackOverflow,
+    IntegerOverflow,
+    NegativeShift,
+    /// Any other runtime error: closing a nil channel, bad make size, ...
+    Other,
+}
+
+impl PanicKind {
+    /// Decode a kind passed as an integer, as JIT code does.
+    pub fn from_u8(v: u8) -> Option<Self> {
+        use PanicKind::*;
+        [IndexOutOfBounds, NilPointerDereference, TypeAssertionFailed, DivisionByZero,
+         SendOnClosedChannel, NilMapWrite, StackOverflow, IntegerOverflow, NegativeShift, Other]
+            .into_iter()
+            .find(|&k| k as u8 == v)
+    }
+
+    /// Message for a panic of this kind raised without operand details, as
+    /// JIT code raises them.
+    pub fn message(self) -> &'static str {
+        match self {
+            PanicKind::IndexOutOfBounds => "runtime error: index out of range",
+            PanicKind::NilPointerDereference => "runtime error: nil pointer dereference",
+            PanicKind::TypeAssertionFailed => "runtime error: interface conversion",
+            PanicKind::DivisionByZero => "runtime error: integer divide by zero",
+            PanicKind::SendOnClosedChannel => "runtime error: send on closed channel",
+            PanicKind::NilMapWrite => "runtime error: assignment to entry in nil map",
+            PanicKind::StackOverflow => "runtime error: stack overflow",
+            PanicKind::IntegerOverflow => "runtime error: integer overflow",
+            PanicKind::NegativeShift => "runtime error: negative shift amount",
+            PanicKind::Other => "runtime error",
+        }
+    }
+}
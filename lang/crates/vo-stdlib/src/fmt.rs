@@ -10,91 +10,92 @@
 //!
 //! Vo layer (fmt.vo) provides Print, Println, Printf, Sprint, Sprintln, Sprintf
 //! which call these native functions.
+//!
+//! Formatting follows Go's fmt: the same verb grammar (flags `#0+- `, width,
+//! precision, `*` and `[n]` argument indexes) and the same output for basic
+//! values, including the `%!verb(type=value)` error forms. Structs, arrays,
+//! slices and maps apply the verb to each element, with field names for
+//! `%+v` and Go syntax for `%#v`.
 
 #[cfg(not(feature = "std"))]
 use alloc::string::{String, ToString};
 #[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
 use alloc::format;
 
+use vo_common_core::runtime_type::RuntimeType;
 use vo_common_core::types::ValueKind;
 use vo_ffi_macro::vostd_extern_ctx_nostd;
 use vo_runtime::ffi::{ExternCallContext, ExternResult};
 use vo_runtime::gc::GcRef;
 use vo_runtime::objects::{interface, slice, string as str_obj};
 
+use crate::strconv::append_float;
+
 // =============================================================================
 // Format interface{} values - re-export from vo_runtime::builtins
 // =============================================================================
 
 pub use vo_runtime::builtins::{format_interface, format_interface_with_ctx};
+use vo_runtime::builtins::{
+    format_error, is_composite, print_value, rttid_type_name, value_kind_to_type_name, ValuePrinter,
+};
+
+/// Go spelling of the dynamic type of an interface{} value.
+fn type_name(slot0: u64, call: Option<&ExternCallContext>) -> String {
+    let vk = interface::unpack_value_kind(slot0);
+    match call {
+        Some(call) if vk != ValueKind::Void => rttid_type_name(interface::unpack_rttid(slot0), call),
+        _ => value_kind_to_type_name(vk),
+    }
+}
 
-/// Convert ValueKind to Go type name string for %T verb.
-fn value_kind_to_type_name(vk: ValueKind) -> String {
-    match vk {
-        ValueKind::Void => "<nil>".to_string(),
-        ValueKind::Bool => "bool".to_string(),
-        ValueKind::Int => "int".to_string(),
-        ValueKind::Int8 => "int8".to_string(),
-        ValueKind::Int16 => "int16".to_string(),
-        ValueKind::Int32 => "int32".to_string(),
-        ValueKind::Int64 => "int64".to_string(),
-        ValueKind::Uint => "uint".to_string(),
-        ValueKind::Uint8 => "uint8".to_string(),
-        ValueKind::Uint16 => "uint16".to_string(),
-        ValueKind::Uint32 => "uint32".to_string(),
-        ValueKind::Uint64 => "uint64".to_string(),
-        ValueKind::Float32 => "float32".to_string(),
-        ValueKind::Float64 => "float64".to_string(),
-        ValueKind::String => "string".to_string(),
-        ValueKind::Slice => "[]...".to_string(),
-        ValueKind::Map => "map[...]...".to_string(),
-        ValueKind::Channel => "chan ...".to_string(),
-        ValueKind::Closure => "func(...)".to_string(),
-        ValueKind::Pointer => "*...".to_string(),
-        ValueKind::Array => "[...]...".to_string(),
-        ValueKind::Struct => "struct{...}".to_string(),
-        ValueKind::Interface => "interface{}".to_string(),
-        ValueKind::Port => "port ...".to_string(),
-        ValueKind::Island => "island".to_string(),
+/// Read the interface{} elements of a []interface{} argument slice.
+fn args_of(args_ref: GcRef) -> Vec<Arg> {
+    if args_ref.is_null() {
+        return Vec::new();
     }
+    let len = slice::len(args_ref);
+    let data_ptr = slice::data_ptr(args_ref) as *const u64;
+    // Each interface{} is 2 slots (16 bytes)
+    (0..len)
+        .map(|i| unsafe { Arg { slot0: *data_ptr.add(i * 2), slot1: *data_ptr.add(i * 2 + 1) } })
+        .collect()
 }
 
 /// Format all elements in a []interface{} slice with space separator.
-fn format_args_slice_with_ctx(slice_ref: GcRef, call: Option<&ExternCallContext>) -> String {
-    if slice_ref.is_null() {
-        return String::new();
-    }
-    
-    let len = slice::len(slice_ref);
-    if len == 0 {
-        return String::new();
-    }
-    
-    let data_ptr = slice::data_ptr(slice_ref) as *const u64;
-    let mut result = String::new();
-    
-    for i in 0..len {
+fn format_args_slice_with_ctx(slice_ref: GcRef, call: Option<&ExternCallContext>) -> Vec<u8> {
+    let mut p = Printer::new(call);
+    for (i, arg) in args_of(slice_ref).into_iter().enumerate() {
         if i > 0 {
-            result.push(' ');
+            p.buf.push(b' ');
         }
-        // Each interface{} is 2 slots (16 bytes)
-        let slot0 = unsafe { *data_ptr.add(i * 2) };
-        let slot1 = unsafe { *data_ptr.add(i * 2 + 1) };
-        result.push_str(&format_interface_with_ctx(slot0, slot1, call));
+        p.print_arg(arg, 'v');
     }
-    
-    result
+    p.buf
 }
 
 // =============================================================================
 // Printf format string parsing and formatting
 // =============================================================================
 
+const LDIGITS: &[u8; 17] = b"0123456789abcdefx";
+const UDIGITS: &[u8; 17] = b"0123456789ABCDEFX";
+
+/// Widths, precisions and argument indexes above this are rejected, as in Go.
+const MAX_NUM: usize = 1_000_000;
+
 #[derive(Clone, Copy, Default)]
 struct FormatFlags {
-    left: bool,
+    minus: bool,
     plus: bool,
+    sharp: bool,
+    space: bool,
     zero: bool,
+    // %+v and %#v: set instead of `plus`/`sharp`, which mean something else there
+    plus_v: bool,
+    sharp_v: bool,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -102,299 +103,896 @@ struct FormatSpec {
     flags: FormatFlags,
     width: Option<usize>,
     precision: Option<usize>,
-    verb: char,
 }
 
-fn parse_number(chars: &mut core::iter::Peekable<core::str::Chars<'_>>) -> Option<usize> {
-    let mut n: usize = 0;
-    let mut any = false;
-    while let Some(&c) = chars.peek() {
-        if !c.is_ascii_digit() {
-            break;
-        }
-        any = true;
-        chars.next();
-        n = n * 10 + (c as u8 - b'0') as usize;
-    }
-    if any { Some(n) } else { None }
+/// One interface{} argument.
+#[derive(Clone, Copy)]
+struct Arg {
+    slot0: u64,
+    slot1: u64,
 }
 
-fn parse_format_spec(chars: &mut core::iter::Peekable<core::str::Chars<'_>>) -> Option<FormatSpec> {
-    let mut flags = FormatFlags::default();
+impl Arg {
+    /// A value of a basic type, whose rttid is its ValueKind.
+    fn basic(vk: ValueKind, val: u64) -> Self {
+        Arg { slot0: interface::pack_slot0(0, vk as u32, vk), slot1: val }
+    }
 
-    loop {
-        match chars.peek().copied() {
-            Some('-') => {
-                flags.left = true;
-                let _ = chars.next();
-            }
-            Some('+') => {
-                flags.plus = true;
-                let _ = chars.next();
-            }
-            Some('0') => {
-                flags.zero = true;
-                let _ = chars.next();
-            }
-            _ => break,
+    fn kind(self) -> ValueKind {
+        interface::unpack_value_kind(self.slot0)
+    }
+}
+
+/// Read a decimal number from `s[start..end]`. Returns the number, if there
+/// were digits and it isn't too large, and the index after it; like Go, a
+/// number that is too large consumes the rest of the format.
+fn parse_num(s: &[u8], start: usize, end: usize) -> (Option<usize>, usize) {
+    if start >= end {
+        return (None, end);
+    }
+    let mut n = 0usize;
+    let mut i = start;
+    while i < end && s[i].is_ascii_digit() {
+        if n > MAX_NUM {
+            return (None, end);
         }
+        n = n * 10 + (s[i] - b'0') as usize;
+        i += 1;
     }
+    (if i > start { Some(n) } else { None }, i)
+}
 
-    let width = parse_number(chars);
+/// Parse `[n]` at the start of `s`. Returns the zero-based index (None if it
+/// isn't a positive number), the bytes consumed, and whether brackets were
+/// well formed.
+fn parse_arg_number(s: &[u8]) -> (Option<usize>, usize, bool) {
+    if s.len() < 3 {
+        return (None, 1, false);
+    }
+    for i in 1..s.len() {
+        if s[i] == b']' {
+            return match parse_num(s, 1, i) {
+                (Some(n), end) if end == i => (n.checked_sub(1), i + 1, true),
+                _ => (None, i + 1, false),
+            };
+        }
+    }
+    (None, 1, false)
+}
 
-    let precision = if chars.peek().copied() == Some('.') {
-        let _ = chars.next();
-        Some(parse_number(chars).unwrap_or(0))
-    } else {
-        None
+/// An integer width or precision taken from an argument by `*`.
+fn int_from_arg(arg: Arg) -> Option<i64> {
+    let vk = arg.kind();
+    let n = match vk {
+        ValueKind::Int | ValueKind::Int64 => arg.slot1 as i64,
+        ValueKind::Int8 => arg.slot1 as i8 as i64,
+        ValueKind::Int16 => arg.slot1 as i16 as i64,
+        ValueKind::Int32 => arg.slot1 as i32 as i64,
+        ValueKind::Uint8 => arg.slot1 as u8 as i64,
+        ValueKind::Uint16 => arg.slot1 as u16 as i64,
+        ValueKind::Uint32 => arg.slot1 as u32 as i64,
+        ValueKind::Uint | ValueKind::Uint64 => i64::try_from(arg.slot1).ok()?,
+        _ => return None,
     };
+    if n.unsigned_abs() > MAX_NUM as u64 { None } else { Some(n) }
+}
 
-    let verb = chars.next()?;
-    Some(FormatSpec {
-        flags,
-        width,
-        precision,
-        verb,
-    })
+/// Formatting state for one Sprint/Sprintf call, after Go's `pp` and `fmt`.
+struct Printer<'a, 'c> {
+    buf: Vec<u8>,
+    spec: FormatSpec,
+    call: Option<&'a ExternCallContext<'c>>,
+    // An argument index was used, so unused arguments aren't reported
+    reordered: bool,
+    good_arg_num: bool,
 }
 
-fn pad_width(mut s: String, spec: FormatSpec, default_pad: char) -> String {
-    let Some(width) = spec.width else {
-        return s;
-    };
+impl<'a, 'c> Printer<'a, 'c> {
+    fn new(call: Option<&'a ExternCallContext<'c>>) -> Self {
+        Printer { buf: Vec::new(), spec: FormatSpec::default(), call, reordered: false, good_arg_num: true }
+    }
 
-    let len = s.chars().count();
-    if len >= width {
-        return s;
+    fn push_char(&mut self, c: char) {
+        let mut utf8 = [0u8; 4];
+        self.buf.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
     }
 
-    let pad_len = width - len;
-    let pad_char = if spec.flags.zero && !spec.flags.left { '0' } else { default_pad };
-    if spec.flags.left {
-        for _ in 0..pad_len {
-            s.push(pad_char);
+    /// Apply a `[n]` at `format[i..]`, if there is one.
+    fn arg_number(&mut self, arg_num: usize, format: &[u8], i: usize, num_args: usize) -> (usize, usize, bool) {
+        if format.get(i) != Some(&b'[') {
+            return (arg_num, i, false);
+        }
+        self.reordered = true;
+        let (index, wid, ok) = parse_arg_number(&format[i..]);
+        match index {
+            Some(index) if ok && index < num_args => (index, i + wid, true),
+            _ => {
+                self.good_arg_num = false;
+                (arg_num, i + wid, ok)
+            }
         }
-        return s;
     }
 
-    let mut out = String::new();
-    for _ in 0..pad_len {
-        out.push(pad_char);
+    fn sprintf(&mut self, format: &[u8], args: &[Arg]) {
+        let end = format.len();
+        let mut arg_num = 0usize;
+        let mut i = 0usize;
+        while i < end {
+            self.good_arg_num = true;
+            let lasti = i;
+            while i < end && format[i] != b'%' {
+                i += 1;
+            }
+            self.buf.extend_from_slice(&format[lasti..i]);
+            if i >= end {
+                break;
+            }
+            i += 1;
+
+            self.spec = FormatSpec::default();
+            while i < end {
+                let flags = &mut self.spec.flags;
+                match format[i] {
+                    b'#' => flags.sharp = true,
+                    b'0' => flags.zero = !flags.minus, // Only pad with zeros on the left
+                    b'+' => flags.plus = true,
+                    b'-' => {
+                        flags.minus = true;
+                        flags.zero = false;
+                    }
+                    b' ' => flags.space = true,
+                    _ => break,
+                }
+                i += 1;
+            }
+
+            let mut after_index;
+            (arg_num, i, after_index) = self.arg_number(arg_num, format, i, args.len());
+
+            if format.get(i) == Some(&b'*') {
+                i += 1;
+                let width = args.get(arg_num).and_then(|&a| int_from_arg(a));
+                if arg_num < args.len() {
+                    arg_num += 1;
+                }
+                match width {
+                    Some(w) => {
+                        if w < 0 {
+                            self.spec.flags.minus = true;
+                            self.spec.flags.zero = false;
+                        }
+                        self.spec.width = Some(w.unsigned_abs() as usize);
+                    }
+                    None => self.buf.extend_from_slice(b"%!(BADWIDTH)"),
+                }
+                after_index = false;
+            } else {
+                (self.spec.width, i) = parse_num(format, i, end);
+                if after_index && self.spec.width.is_some() {
+                    // "%[3]2d"
+                    self.good_arg_num = false;
+                }
+            }
+
+            if i + 1 < end && format[i] == b'.' {
+                i += 1;
+                if after_index {
+                    // "%[3].2d"
+                    self.good_arg_num = false;
+                }
+                (arg_num, i, after_index) = self.arg_number(arg_num, format, i, args.len());
+                if format.get(i) == Some(&b'*') {
+                    i += 1;
+                    let prec = args.get(arg_num).and_then(|&a| int_from_arg(a));
+                    if arg_num < args.len() {
+                        arg_num += 1;
+                    }
+                    match prec {
+                        // A negative precision means none
+                        Some(p) if p >= 0 => self.spec.precision = Some(p as usize),
+                        Some(_) => {}
+                        None => self.buf.extend_from_slice(b"%!(BADPREC)"),
+                    }
+                    after_index = false;
+                } else {
+                    let prec;
+                    (prec, i) = parse_num(format, i, end);
+                    self.spec.precision = Some(prec.unwrap_or(0));
+                }
+            }
+
+            if !after_index {
+                (arg_num, i, _) = self.arg_number(arg_num, format, i, args.len());
+            }
+
+            if i >= end {
+                self.buf.extend_from_slice(b"%!(NOVERB)");
+                break;
+            }
+
+            let (rune, size) = str_obj::decode_rune(&format[i..]);
+            let verb = char::from_u32(rune as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+            i += size;
+
+            if verb == '%' {
+                // Percent takes no argument and ignores width and precision
+                self.buf.push(b'%');
+            } else if !self.good_arg_num {
+                self.buf.extend_from_slice(b"%!");
+                self.push_char(verb);
+                self.buf.extend_from_slice(b"(BADINDEX)");
+            } else if arg_num >= args.len() {
+                self.buf.extend_from_slice(b"%!");
+                self.push_char(verb);
+                self.buf.extend_from_slice(b"(MISSING)");
+            } else {
+                if verb == 'v' {
+                    let flags = &mut self.spec.flags;
+                    flags.sharp_v = core::mem::take(&mut flags.sharp);
+                    flags.plus_v = core::mem::take(&mut flags.plus);
+                }
+                self.print_arg(args[arg_num], verb);
+                arg_num += 1;
+            }
+        }
+
+        if !self.reordered && arg_num < args.len() {
+            self.spec = FormatSpec::default();
+            self.buf.extend_from_slice(b"%!(EXTRA ");
+            for (k, &arg) in args[arg_num..].iter().enumerate() {
+                if k > 0 {
+                    self.buf.extend_from_slice(b", ");
+                }
+                if arg.kind() == ValueKind::Void {
+                    self.buf.extend_from_slice(b"<nil>");
+                } else {
+                    self.buf.extend_from_slice(type_name(arg.slot0, self.call).as_bytes());
+                    self.buf.push(b'=');
+                    self.print_arg(arg, 'v');
+                }
+            }
+            self.buf.push(b')');
+        }
     }
-    out.push_str(&s);
-    out
-}
 
-fn pad_numeric(mut s: String, spec: FormatSpec) -> String {
-    let Some(width) = spec.width else {
-        return s;
-    };
+    fn print_arg(&mut self, arg: Arg, verb: char) {
+        let vk = arg.kind();
+        if vk == ValueKind::Void {
+            match verb {
+                'T' | 'v' => self.pad(b"<nil>"),
+                _ => self.bad_verb(arg, verb),
+            }
+            return;
+        }
+        if verb == 'T' {
+            let name = type_name(arg.slot0, self.call);
+            self.fmt_s(name.as_bytes());
+            return;
+        }
+        match self.call {
+            Some(call) if verb != 'p' => print_value(self, arg.slot0, arg.slot1, verb, 0, call),
+            _ => self.print_basic(arg, verb),
+        }
+    }
 
-    let len = s.chars().count();
-    if len >= width {
-        return s;
+    /// Print anything `print_value` doesn't walk: basic values, []byte for
+    /// the string verbs, errors, pointers, channels and funcs.
+    fn print_basic(&mut self, arg: Arg, verb: char) {
+        let vk = arg.kind();
+        let v = arg.slot1;
+        match vk {
+            ValueKind::Bool => match verb {
+                't' | 'v' => self.pad(if v != 0 { b"true" } else { b"false" }),
+                _ => self.bad_verb(arg, verb),
+            },
+            ValueKind::Int | ValueKind::Int64 => self.fmt_integer(v, true, verb, arg),
+            ValueKind::Int8 => self.fmt_integer(v as i8 as u64, true, verb, arg),
+            ValueKind::Int16 => self.fmt_integer(v as i16 as u64, true, verb, arg),
+            ValueKind::Int32 => self.fmt_integer(v as i32 as u64, true, verb, arg),
+            ValueKind::Uint | ValueKind::Uint64 => self.fmt_integer(v, false, verb, arg),
+            ValueKind::Uint8 => self.fmt_integer(v as u8 as u64, false, verb, arg),
+            ValueKind::Uint16 => self.fmt_integer(v as u16 as u64, false, verb, arg),
+            ValueKind::Uint32 => self.fmt_integer(v as u32 as u64, false, verb, arg),
+            ValueKind::Float32 => self.fmt_float(f32::from_bits(v as u32) as f64, 32, verb, arg),
+            ValueKind::Float64 => self.fmt_float(f64::from_bits(v), 64, verb, arg),
+            ValueKind::String => self.fmt_string(str_obj::as_bytes(v as GcRef), verb, arg),
+            ValueKind::Slice if self.is_byte_slice(arg) => self.fmt_bytes(arg, verb),
+            _ => self.fmt_other(arg, verb),
+        }
     }
 
-    let pad_len = width - len;
-    if spec.flags.left {
-        for _ in 0..pad_len {
-            s.push(' ');
+    /// `%!verb(type=value)` for a verb the value's type doesn't support.
+    fn bad_verb(&mut self, arg: Arg, verb: char) {
+        self.buf.extend_from_slice(b"%!");
+        self.push_char(verb);
+        self.buf.push(b'(');
+        if arg.kind() == ValueKind::Void {
+            self.buf.extend_from_slice(b"<nil>");
+        } else {
+            self.buf.extend_from_slice(type_name(arg.slot0, self.call).as_bytes());
+            self.buf.push(b'=');
+            self.print_arg(arg, 'v');
         }
-        return s;
+        self.buf.push(b')');
     }
 
-    let pad_char = if spec.flags.zero { '0' } else { ' ' };
+    /// Write `n` bytes of padding.
+    fn write_padding(&mut self, n: usize) {
+        let pad = if self.spec.flags.zero { b'0' } else { b' ' };
+        self.buf.resize(self.buf.len() + n, pad);
+    }
 
-    let mut out = String::new();
-    let mut it = s.chars();
-    if pad_char == '0' {
-        if let Some(first) = it.next() {
-            if first == '-' || first == '+' {
-                out.push(first);
-                for _ in 0..pad_len {
-                    out.push('0');
+    /// Append `b`, padded to the width; width counts runes, not bytes.
+    fn pad(&mut self, b: &[u8]) {
+        let count = rune_count(b);
+        match self.spec.width {
+            Some(width) if width > count => {
+                if self.spec.flags.minus {
+                    self.buf.extend_from_slice(b);
+                    self.write_padding(width - count);
+                } else {
+                    self.write_padding(width - count);
+                    self.buf.extend_from_slice(b);
                 }
-                out.extend(it);
-                return out;
             }
+            _ => self.buf.extend_from_slice(b),
         }
     }
 
-    for _ in 0..pad_len {
-        out.push(pad_char);
+    /// `pad` with zero padding turned off, for output where leading zeros
+    /// would change the meaning or have already been placed.
+    fn pad_spaces(&mut self, b: &[u8]) {
+        let zero = core::mem::take(&mut self.spec.flags.zero);
+        self.pad(b);
+        self.spec.flags.zero = zero;
     }
-    out.push_str(&s);
-    out
-}
 
-/// Format with printf-style format string.
-fn sprintf_impl(format_str: &str, args_ref: GcRef, call: Option<&ExternCallContext>) -> String {
-    let args_len = if args_ref.is_null() { 0 } else { slice::len(args_ref) };
-    let data_ptr = if args_ref.is_null() { 
-        core::ptr::null() 
-    } else { 
-        slice::data_ptr(args_ref) as *const u64 
-    };
-    
-    let mut result = String::new();
-    let mut chars = format_str.chars().peekable();
-    let mut arg_idx = 0usize;
-    
-    while let Some(c) = chars.next() {
-        if c != '%' {
-            result.push(c);
-            continue;
-        }
+    // ----- Integers -----
 
-        if chars.peek().copied() == Some('%') {
-            let _ = chars.next();
-            result.push('%');
-            continue;
+    fn fmt_integer(&mut self, u: u64, signed: bool, verb: char, arg: Arg) {
+        match verb {
+            'v' if self.spec.flags.sharp_v && !signed => self.fmt_0x64(u, true),
+            'v' | 'd' => self.fmt_int(u, 10, signed, verb, LDIGITS),
+            'b' => self.fmt_int(u, 2, signed, verb, LDIGITS),
+            'o' | 'O' => self.fmt_int(u, 8, signed, verb, LDIGITS),
+            'x' => self.fmt_int(u, 16, signed, verb, LDIGITS),
+            'X' => self.fmt_int(u, 16, signed, verb, UDIGITS),
+            'c' => self.fmt_c(u),
+            'q' => self.fmt_qc(u),
+            'U' => self.fmt_unicode(u),
+            _ => self.bad_verb(arg, verb),
         }
+    }
 
-        let Some(spec) = parse_format_spec(&mut chars) else {
-            result.push('%');
-            break;
-        };
+    /// Hexadecimal, with a 0x prefix if `leading_0x`.
+    fn fmt_0x64(&mut self, u: u64, leading_0x: bool) {
+        let sharp = core::mem::replace(&mut self.spec.flags.sharp, leading_0x);
+        self.fmt_int(u, 16, false, 'v', LDIGITS);
+        self.spec.flags.sharp = sharp;
+    }
 
-        if arg_idx >= args_len {
-            result.push_str("%!");
-            result.push(spec.verb);
-            result.push_str("(MISSING)");
-            continue;
+    fn fmt_int(&mut self, u: u64, base: u64, signed: bool, verb: char, digits: &[u8; 17]) {
+        let flags = self.spec.flags;
+        let negative = signed && (u as i64) < 0;
+        let mut u = if negative { (u as i64).unsigned_abs() } else { u };
+
+        // Minimum digit count: %.3d, or %03d when there is no precision
+        let mut prec = 0usize;
+        if let Some(p) = self.spec.precision {
+            prec = p;
+            // Precision 0 and value 0 print nothing but padding
+            if prec == 0 && u == 0 {
+                let zero = core::mem::take(&mut self.spec.flags.zero);
+                self.write_padding(self.spec.width.unwrap_or(0));
+                self.spec.flags.zero = zero;
+                return;
+            }
+        } else if let (true, Some(width)) = (flags.zero, self.spec.width) {
+            prec = width;
+            if negative || flags.plus || flags.space {
+                prec = prec.saturating_sub(1); // Leave room for the sign
+            }
+        }
+
+        let mut rev: Vec<u8> = Vec::new();
+        loop {
+            rev.push(digits[(u % base) as usize]);
+            u /= base;
+            if u == 0 {
+                break;
+            }
+        }
+        while rev.len() < prec {
+            rev.push(b'0');
         }
+        if flags.sharp {
+            match base {
+                2 => rev.extend_from_slice(b"b0"),
+                8 if rev.last() != Some(&b'0') => rev.push(b'0'),
+                16 => {
+                    rev.push(digits[16]);
+                    rev.push(b'0');
+                }
+                _ => {}
+            }
+        }
+        if verb == 'O' {
+            rev.extend_from_slice(b"o0");
+        }
+        if negative {
+            rev.push(b'-');
+        } else if flags.plus {
+            rev.push(b'+');
+        } else if flags.space {
+            rev.push(b' ');
+        }
+        rev.reverse();
+        // Zero padding was done above, as extra digits
+        self.pad_spaces(&rev);
+    }
 
-        let slot0 = unsafe { *data_ptr.add(arg_idx * 2) };
-        let slot1 = unsafe { *data_ptr.add(arg_idx * 2 + 1) };
-        result.push_str(&format_with_spec(spec, slot0, slot1, call));
-        arg_idx += 1;
+    fn rune_of(u: u64) -> char {
+        u32::try_from(u).ok().and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER)
     }
-    
-    result
-}
 
-fn format_with_spec(spec: FormatSpec, slot0: u64, slot1: u64, call: Option<&ExternCallContext>) -> String {
-    let vk = interface::unpack_value_kind(slot0);
+    /// %c: the character.
+    fn fmt_c(&mut self, u: u64) {
+        let mut utf8 = [0u8; 4];
+        let s = Self::rune_of(u).encode_utf8(&mut utf8);
+        self.pad(s.as_bytes());
+    }
 
-    match spec.verb {
-        'v' => format_interface_with_ctx(slot0, slot1, call),
-        'd' => {
-            if vk.is_integer() {
-                let mut s = (slot1 as i64).to_string();
-                if spec.flags.plus && !s.starts_with('-') {
-                    s = format!("+{}", s);
-                }
+    /// %q on an integer: a single-quoted character literal.
+    fn fmt_qc(&mut self, u: u64) {
+        let r = Self::rune_of(u);
+        let mut out = Vec::new();
+        out.push(b'\'');
+        append_escaped_rune(&mut out, r, b'\'', self.spec.flags.plus);
+        out.push(b'\'');
+        self.pad(&out);
+    }
 
-                if let Some(prec) = spec.precision {
-                    let (sign, digits) = if s.starts_with('-') {
-                        ('-', &s[1..])
-                    } else if s.starts_with('+') {
-                        ('+', &s[1..])
-                    } else {
-                        ('\0', s.as_str())
-                    };
-
-                    let digit_len = digits.chars().count();
-                    if digit_len < prec {
-                        let mut out = String::new();
-                        if sign != '\0' {
-                            out.push(sign);
+    /// %U: U+0041, or with #, U+0041 'A'.
+    fn fmt_unicode(&mut self, u: u64) {
+        let prec = self.spec.precision.map_or(4, |p| p.max(4));
+        let mut out = format!("U+{:0prec$X}", u, prec = prec).into_bytes();
+        if self.spec.flags.sharp {
+            if let Some(c) = u32::try_from(u).ok().and_then(char::from_u32).filter(|&c| is_print(c)) {
+                out.extend_from_slice(b" '");
+                let mut utf8 = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+                out.push(b'\'');
+            }
+        }
+        self.pad_spaces(&out);
+    }
+
+    // ----- Floats -----
+
+    fn fmt_float(&mut self, v: f64, size: u32, verb: char, arg: Arg) {
+        match verb {
+            'v' => self.fmt_float_as(v, size, 'g', -1),
+            'b' | 'g' | 'G' | 'x' | 'X' => self.fmt_float_as(v, size, verb, -1),
+            'f' | 'e' | 'E' => self.fmt_float_as(v, size, verb, 6),
+            'F' => self.fmt_float_as(v, size, 'f', 6),
+            _ => self.bad_verb(arg, verb),
+        }
+    }
+
+    /// Format a float with strconv-style `verb`; `prec` applies unless the
+    /// spec has a precision, and -1 means the shortest exact representation.
+    fn fmt_float_as(&mut self, v: f64, size: u32, verb: char, prec: i32) {
+        let flags = self.spec.flags;
+        let prec = self.spec.precision.map_or(prec, |p| p.min(i32::MAX as usize) as i32);
+        // Leave a byte for a leading sign
+        let mut num = Vec::with_capacity(24);
+        num.push(b'+');
+        append_float(&mut num, v, verb as u8, prec, size);
+        if num[1] == b'-' || num[1] == b'+' {
+            num.remove(0);
+        }
+        // ' ' adds a space where a '+' would go, unless '+' is also set
+        if flags.space && num[0] == b'+' && !flags.plus {
+            num[0] = b' ';
+        }
+        // Infinities and NaN don't look like numbers; never pad them with zeros
+        if num[1] == b'I' || num[1] == b'N' {
+            if num[1] == b'N' && !flags.space && !flags.plus {
+                num.remove(0);
+            }
+            self.pad_spaces(&num);
+            return;
+        }
+        // # keeps the decimal point, and for %g the trailing zeros
+        if flags.sharp && verb != 'b' {
+            let mut digits = match verb {
+                'v' | 'g' | 'G' | 'x' => if prec == -1 { 6 } else { prec },
+                _ => 0,
+            };
+            let mut tail = Vec::new();
+            let mut has_point = false;
+            let mut saw_nonzero = false;
+            let mut k = 1;
+            while k < num.len() {
+                match num[k] {
+                    b'.' => has_point = true,
+                    b'p' | b'P' => {
+                        tail = num.split_off(k);
+                        break;
+                    }
+                    b'e' | b'E' if verb != 'x' && verb != 'X' => {
+                        tail = num.split_off(k);
+                        break;
+                    }
+                    c => {
+                        if c != b'0' {
+                            saw_nonzero = true;
                         }
-                        for _ in 0..(prec - digit_len) {
-                            out.push('0');
+                        if saw_nonzero {
+                            digits -= 1;
                         }
-                        out.push_str(digits);
-                        s = out;
                     }
                 }
-
-                pad_numeric(s, spec)
-            } else {
-                format!("%!d({})", format_interface(slot0, slot1))
+                k += 1;
             }
-        }
-        's' => {
-            match vk {
-                ValueKind::String => {
-                    let mut s = str_obj::as_str(slot1 as GcRef).to_string();
-                    if let Some(prec) = spec.precision {
-                        s = s.chars().take(prec).collect();
-                    }
-                    pad_width(s, spec, ' ')
+            if !has_point {
+                // A lone 0 still counts as a digit
+                if num.len() == 2 && num[1] == b'0' {
+                    digits -= 1;
                 }
-                _ => pad_width(format_interface_with_ctx(slot0, slot1, call), spec, ' '),
-            }
-        }
-        'f' => {
-            match vk {
-                ValueKind::Float32 | ValueKind::Float64 => {
-                    let f = match vk {
-                        ValueKind::Float32 => f32::from_bits(slot1 as u32) as f64,
-                        ValueKind::Float64 => f64::from_bits(slot1),
-                        _ => unreachable!(),
-                    };
-
-                    let prec = spec.precision.unwrap_or(6);
-                    let mut s = if spec.flags.plus {
-                        format!("{:+.*}", prec, f)
-                    } else {
-                        format!("{:.*}", prec, f)
-                    };
-                    s = pad_numeric(s, spec);
-                    s
+                num.push(b'.');
+            }
+            while digits > 0 {
+                num.push(b'0');
+                digits -= 1;
+            }
+            num.extend_from_slice(&tail);
+        }
+        if flags.plus || num[0] != b'+' {
+            // Zero padding goes between the sign and the digits
+            if let (true, Some(width)) = (flags.zero, self.spec.width) {
+                if width > num.len() {
+                    self.buf.push(num[0]);
+                    self.write_padding(width - num.len());
+                    self.buf.extend_from_slice(&num[1..]);
+                    return;
                 }
-                _ => format!("%!f({})", format_interface(slot0, slot1)),
             }
+            self.pad(&num);
+            return;
         }
-        't' => {
-            match vk {
-                ValueKind::Bool => if slot1 != 0 { "true" } else { "false" }.to_string(),
-                _ => format!("%!t({})", format_interface(slot0, slot1)),
+        // Positive, and no sign asked for
+        self.pad(&num[1..]);
+    }
+
+    // ----- Strings and byte slices -----
+
+    fn fmt_string(&mut self, s: &[u8], verb: char, arg: Arg) {
+        match verb {
+            'v' if self.spec.flags.sharp_v => self.fmt_q(s),
+            'v' | 's' => self.fmt_s(s),
+            'x' => self.fmt_sbx(s, LDIGITS),
+            'X' => self.fmt_sbx(s, UDIGITS),
+            'q' => self.fmt_q(s),
+            _ => self.bad_verb(arg, verb),
+        }
+    }
+
+    /// Cut `s` to the precision, counted in runes.
+    fn truncate<'s>(&self, s: &'s [u8]) -> &'s [u8] {
+        let Some(prec) = self.spec.precision else { return s };
+        let mut i = 0;
+        for _ in 0..prec {
+            if i >= s.len() {
+                return s;
             }
+            i += str_obj::decode_rune(&s[i..]).1;
         }
-        'x' => {
-            match vk {
-                ValueKind::Int | ValueKind::Int64 | ValueKind::Uint | ValueKind::Uint64 => {
-                    format!("{:x}", slot1)
-                }
-                ValueKind::Int8 | ValueKind::Uint8 => format!("{:x}", slot1 as u8),
-                ValueKind::Int16 | ValueKind::Uint16 => format!("{:x}", slot1 as u16),
-                ValueKind::Int32 | ValueKind::Uint32 => format!("{:x}", slot1 as u32),
-                ValueKind::String => {
-                    let s = str_obj::as_str(slot1 as GcRef);
-                    s.bytes().map(|b| format!("{:02x}", b)).collect()
+        &s[..i]
+    }
+
+    fn fmt_s(&mut self, s: &[u8]) {
+        let s = self.truncate(s);
+        self.pad(s);
+    }
+
+    /// %x on a string or bytes: two hex digits per byte. With ' ', bytes are
+    /// separated by spaces; with '#', each group gets a 0x prefix.
+    fn fmt_sbx(&mut self, s: &[u8], digits: &[u8; 17]) {
+        let flags = self.spec.flags;
+        let len = self.spec.precision.map_or(s.len(), |p| p.min(s.len()));
+        let mut width = 2 * len;
+        if width == 0 {
+            if let Some(w) = self.spec.width {
+                self.write_padding(w);
+            }
+            return;
+        }
+        if flags.space {
+            if flags.sharp {
+                width *= 2;
+            }
+            width += len - 1;
+        } else if flags.sharp {
+            width += 2;
+        }
+        let padding = self.spec.width.map_or(0, |w| w.saturating_sub(width));
+        if !flags.minus {
+            self.write_padding(padding);
+        }
+        if flags.sharp {
+            self.buf.extend_from_slice(&[b'0', digits[16]]);
+        }
+        for (i, &c) in s[..len].iter().enumerate() {
+            if flags.space && i > 0 {
+                self.buf.push(b' ');
+                if flags.sharp {
+                    self.buf.extend_from_slice(&[b'0', digits[16]]);
                 }
-                _ => format!("%!x({})", format_interface(slot0, slot1)),
+            }
+            self.buf.extend_from_slice(&[digits[(c >> 4) as usize], digits[(c & 0xF) as usize]]);
+        }
+        if flags.minus {
+            self.write_padding(padding);
+        }
+    }
+
+    /// %q: a double-quoted string literal, or with '#', a raw one if possible.
+    fn fmt_q(&mut self, s: &[u8]) {
+        let s = self.truncate(s);
+        let mut out = Vec::with_capacity(s.len() + 2);
+        if self.spec.flags.sharp && can_backquote(s) {
+            out.push(b'`');
+            out.extend_from_slice(s);
+            out.push(b'`');
+        } else {
+            append_quoted(&mut out, s, self.spec.flags.plus);
+        }
+        self.pad(&out);
+    }
+
+    fn is_byte_slice(&self, arg: Arg) -> bool {
+        let s = arg.slot1 as GcRef;
+        if !s.is_null() {
+            return slice::elem_kind(s) == ValueKind::Uint8;
+        }
+        // A nil slice has no array to ask; go by its type
+        let Some(call) = self.call else { return false };
+        let mut rttid = interface::unpack_rttid(arg.slot0);
+        loop {
+            match call.runtime_types().get(rttid as usize) {
+                Some(RuntimeType::Slice(elem)) => return elem.value_kind() == ValueKind::Uint8,
+                Some(RuntimeType::Named { id, .. }) => match call.named_type_meta(*id as usize) {
+                    Some(meta) if meta.underlying_meta.value_kind() == ValueKind::Slice => {
+                        rttid = meta.underlying_meta.meta_id();
+                    }
+                    _ => return false,
+                },
+                _ => return false,
             }
         }
-        'X' => {
-            match vk {
-                ValueKind::Int | ValueKind::Int64 | ValueKind::Uint | ValueKind::Uint64 => {
-                    format!("{:X}", slot1)
+    }
+
+    fn fmt_bytes(&mut self, arg: Arg, verb: char) {
+        let s = arg.slot1 as GcRef;
+        let bytes: &[u8] = if s.is_null() {
+            &[]
+        } else {
+            unsafe { core::slice::from_raw_parts(slice::data_ptr(s), slice::len(s)) }
+        };
+        match verb {
+            'v' | 'd' if self.spec.flags.sharp_v => {
+                let name = type_name(arg.slot0, self.call);
+                self.buf.extend_from_slice(if name == "[]uint8" { b"[]byte" } else { name.as_bytes() });
+                if s.is_null() {
+                    self.buf.extend_from_slice(b"(nil)");
+                    return;
                 }
-                ValueKind::Int8 | ValueKind::Uint8 => format!("{:X}", slot1 as u8),
-                ValueKind::Int16 | ValueKind::Uint16 => format!("{:X}", slot1 as u16),
-                ValueKind::Int32 | ValueKind::Uint32 => format!("{:X}", slot1 as u32),
-                ValueKind::String => {
-                    let s = str_obj::as_str(slot1 as GcRef);
-                    s.bytes().map(|b| format!("{:02X}", b)).collect()
+                self.buf.push(b'{');
+                for (i, &c) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        self.buf.extend_from_slice(b", ");
+                    }
+                    self.fmt_0x64(c as u64, true);
                 }
-                _ => format!("%!X({})", format_interface(slot0, slot1)),
+                self.buf.push(b'}');
+            }
+            's' => self.fmt_s(bytes),
+            'x' => self.fmt_sbx(bytes, LDIGITS),
+            'X' => self.fmt_sbx(bytes, UDIGITS),
+            'q' => self.fmt_q(bytes),
+            // Other verbs, %v and %d included, apply to each byte
+            _ => {
+                self.buf.push(b'[');
+                for (i, &c) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        self.buf.push(b' ');
+                    }
+                    self.fmt_integer(c as u64, false, verb, Arg::basic(ValueKind::Uint8, c as u64));
+                }
+                self.buf.push(b']');
             }
         }
-        'p' => format!("0x{:x}", slot1),
-        'q' => {
-            match vk {
-                ValueKind::String => {
-                    let s = str_obj::as_str(slot1 as GcRef);
-                    format!("{:?}", s)
+    }
+
+    // ----- Everything else -----
+
+    fn fmt_other(&mut self, arg: Arg, verb: char) {
+        // Errors format as their message for the string verbs
+        if let Some(msg) = self.call.and_then(|call| format_error(arg.slot0, arg.slot1, call)) {
+            if matches!(verb, 'v' | 's' | 'q' | 'x' | 'X') {
+                self.fmt_string(msg.as_bytes(), verb, arg);
+                return;
+            }
+        }
+        let vk = arg.kind();
+        // Pointers, channels and funcs print as their address
+        let is_ptr = matches!(vk, ValueKind::Pointer | ValueKind::Channel | ValueKind::Closure);
+        match verb {
+            'p' if is_ptr || matches!(vk, ValueKind::Map | ValueKind::Slice) => {
+                self.fmt_0x64(arg.slot1, !self.spec.flags.sharp)
+            }
+            'p' => self.bad_verb(arg, verb),
+            'v' if is_ptr && self.spec.flags.sharp_v => {
+                self.buf.push(b'(');
+                self.buf.extend_from_slice(type_name(arg.slot0, self.call).as_bytes());
+                self.buf.extend_from_slice(b")(");
+                if arg.slot1 == 0 {
+                    self.buf.extend_from_slice(b"nil");
+                } else {
+                    self.fmt_0x64(arg.slot1, true);
                 }
-                _ => format!("%!q({})", format_interface(slot0, slot1)),
+                self.buf.push(b')');
+            }
+            'v' if is_ptr && arg.slot1 == 0 => self.pad(b"<nil>"),
+            'b' | 'o' | 'd' | 'x' | 'X' if is_ptr => self.fmt_integer(arg.slot1, false, verb, arg),
+            _ => {
+                let s = format_interface_with_ctx(arg.slot0, arg.slot1, self.call);
+                self.pad(s.as_bytes());
+            }
+        }
+    }
+}
+
+impl ValuePrinter for Printer<'_, '_> {
+    fn buf(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    fn print_leaf(&mut self, slot0: u64, slot1: u64, verb: char, depth: usize) -> bool {
+        let arg = Arg { slot0, slot1 };
+        if let Some(call) = self.call {
+            let is_error = format_error(slot0, slot1, call).is_some();
+            // Below the top level, []byte prints as bytes only for the string verbs
+            let bytes = arg.kind() == ValueKind::Slice
+                && (depth == 0 || matches!(verb, 's' | 'q' | 'x' | 'X'))
+                && self.is_byte_slice(arg);
+            if !is_error && !bytes && is_composite(slot0, slot1, depth, call) {
+                return false;
             }
         }
-        'T' => value_kind_to_type_name(vk),
-        _ => format!("%!{}({})", spec.verb, format_interface(slot0, slot1)),
+        self.print_basic(arg, verb);
+        true
+    }
+
+    fn plus_v(&self) -> bool {
+        self.spec.flags.plus_v
     }
+
+    fn sharp_v(&self) -> bool {
+        self.spec.flags.sharp_v
+    }
+}
+
+// =============================================================================
+// Quoting
+// =============================================================================
+
+/// Number of runes in `s`; each invalid byte counts as one.
+fn rune_count(s: &[u8]) -> usize {
+    let mut n = 0;
+    let mut i = 0;
+    while i < s.len() {
+        i += str_obj::decode_rune(&s[i..]).1;
+        n += 1;
+    }
+    n
+}
+
+/// Approximates Go's unicode.IsPrint: letters, marks, numbers, punctuation,
+/// symbols and the ASCII space.
+fn is_print(c: char) -> bool {
+    if c.is_ascii() {
+        return (' '..='~').contains(&c);
+    }
+    !c.is_control()
+        && !c.is_whitespace()
+        && !matches!(c as u32,
+            // Format characters and private use areas
+            0xAD | 0x600..=0x605 | 0x61C | 0x6DD | 0x70F | 0x180E | 0x200B..=0x200F
+            | 0x202A..=0x202E | 0x2060..=0x206F | 0xFEFF | 0xFFF9..=0xFFFB
+            | 0xE000..=0xF8FF | 0xF0000..)
+}
+
+/// Whether `s` can be written as a raw (backquoted) string literal.
+fn can_backquote(s: &[u8]) -> bool {
+    let mut i = 0;
+    while i < s.len() {
+        let (r, width) = str_obj::decode_rune(&s[i..]);
+        i += width;
+        if width > 1 {
+            if r == 0xFEFF {
+                return false;
+            }
+            continue;
+        }
+        if r == str_obj::RUNE_ERROR {
+            return false;
+        }
+        if (r < ' ' as i32 && r != '\t' as i32) || r == '`' as i32 || r == 0x7F {
+            return false;
+        }
+    }
+    true
+}
+
+fn append_hex(out: &mut Vec<u8>, prefix: &[u8], v: u32, digits: usize) {
+    out.extend_from_slice(prefix);
+    for k in (0..digits).rev() {
+        out.push(LDIGITS[((v >> (4 * k)) & 0xF) as usize]);
+    }
+}
+
+/// Append `r` as it appears inside a literal quoted with `quote`.
+fn append_escaped_rune(out: &mut Vec<u8>, r: char, quote: u8, ascii_only: bool) {
+    let mut utf8 = [0u8; 4];
+    if r as u32 == quote as u32 || r == '\\' {
+        out.push(b'\\');
+        out.push(r as u8);
+        return;
+    }
+    let printable = is_print(r) && (r.is_ascii() || !ascii_only);
+    if printable {
+        out.extend_from_slice(r.encode_utf8(&mut utf8).as_bytes());
+        return;
+    }
+    match r {
+        '\x07' => out.extend_from_slice(b"\\a"),
+        '\x08' => out.extend_from_slice(b"\\b"),
+        '\x0C' => out.extend_from_slice(b"\\f"),
+        '\n' => out.extend_from_slice(b"\\n"),
+        '\r' => out.extend_from_slice(b"\\r"),
+        '\t' => out.extend_from_slice(b"\\t"),
+        '\x0B' => out.extend_from_slice(b"\\v"),
+        _ if (r as u32) < 0x20 || r as u32 == 0x7F => append_hex(out, b"\\x", r as u32, 2),
+        _ if (r as u32) < 0x10000 => append_hex(out, b"\\u", r as u32, 4),
+        _ => append_hex(out, b"\\U", r as u32, 8),
+    }
+}
+
+/// Append `s` as a double-quoted Go string literal, escaping invalid UTF-8
+/// byte by byte and, if `ascii_only`, everything outside ASCII.
+fn append_quoted(out: &mut Vec<u8>, s: &[u8], ascii_only: bool) {
+    out.push(b'"');
+    let mut i = 0;
+    while i < s.len() {
+        let (r, width) = str_obj::decode_rune(&s[i..]);
+        if width == 1 && r == str_obj::RUNE_ERROR {
+            append_hex(out, b"\\x", s[i] as u32, 2);
+        } else {
+            let r = char::from_u32(r as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+            append_escaped_rune(out, r, b'"', ascii_only);
+        }
+        i += width;
+    }
+    out.push(b'"');
+}
+
+/// Format with printf-style format string.
+fn sprintf_impl(format: &[u8], args_ref: GcRef, call: Option<&ExternCallContext>) -> Vec<u8> {
+    let mut p = Printer::new(call);
+    p.sprintf(format, &args_of(args_ref));
+    p.buf
 }
 
 // =============================================================================
@@ -415,7 +1013,7 @@ fn native_sprint(call: &mut ExternCallContext) -> ExternResult {
     let args_ref = call.arg_ref(slots::ARG_A);
     let formatted = format_args_slice_with_ctx(args_ref, Some(call));
     let gc = call.gc();
-    let s = str_obj::from_rust_str(gc, &formatted);
+    let s = str_obj::create(gc, &formatted);
     call.ret_ref(0, s);
     ExternResult::Ok
 }
@@ -425,9 +1023,9 @@ fn native_sprint(call: &mut ExternCallContext) -> ExternResult {
 fn native_sprintln(call: &mut ExternCallContext) -> ExternResult {
     let args_ref = call.arg_ref(slots::ARG_A);
     let mut formatted = format_args_slice_with_ctx(args_ref, Some(call));
-    formatted.push('\n');
+    formatted.push(b'\n');
     let gc = call.gc();
-    let s = str_obj::from_rust_str(gc, &formatted);
+    let s = str_obj::create(gc, &formatted);
     call.ret_ref(0, s);
     ExternResult::Ok
 }
@@ -435,11 +1033,11 @@ fn native_sprintln(call: &mut ExternCallContext) -> ExternResult {
 /// nativeSprintf - format with format string
 #[vostd_extern_ctx_nostd("fmt", "nativeSprintf")]
 fn native_sprintf(call: &mut ExternCallContext) -> ExternResult {
-    let format_str = call.arg_str(slots::ARG_FORMAT);
+    let format = call.arg_bytes(slots::ARG_FORMAT);
     let args_ref = call.arg_ref(slots::ARG_A);
-    let formatted = sprintf_impl(format_str, args_ref, Some(call));
+    let formatted = sprintf_impl(format, args_ref, Some(call));
     let gc = call.gc();
-    let s = str_obj::from_rust_str(gc, &formatted);
+    let s = str_obj::create(gc, &formatted);
     call.ret_ref(0, s);
     ExternResult::Ok
 }
@@ -15,9 +15,11 @@ pub mod math;
 pub mod bits;
 pub mod rand;
 pub mod bytes;
+pub mod sort;
 pub mod strings;
 pub mod strconv;
 pub mod unicode;
+pub mod utf8;
 pub mod fmt;
 #[cfg(feature = "std")]
 pub mod regexp;
@@ -42,8 +44,35 @@ pub use source::{EmbeddedStdlib, StdlibFs};
 use vo_runtime::bytecode::ExternDef;
 use vo_runtime::ffi::ExternRegistry;
 
-/// Register all stdlib extern functions.
-pub fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef]) {
+/// Which stdlib packages get native implementations registered.
+///
+/// | package | `Full` | `Sandboxed` |
+/// |---------|--------|-------------|
+/// | math, math/bits, math/rand, bytes, sort, strings, strconv, unicode, unicode/utf8, fmt, encoding/json, encoding/toml | yes | yes |
+/// | regexp, time (std builds) | yes | yes |
+/// | os, os/exec, net, path/filepath (std builds) | yes | no |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StdMode {
+    /// Every package available on the platform.
+    #[default]
+    Full,
+    /// Leave out packages that reach outside the VM: os, os/exec, net and
+    /// path/filepath. Calls into them fail as unregistered externs. For
+    /// running untrusted code on a native host.
+    Sandboxed,
+}
+
+impl StdMode {
+    /// Whether this mode deliberately leaves the extern `name` unregistered.
+    pub fn withholds(self, name: &str) -> bool {
+        self == StdMode::Sandboxed
+            && ["os_", "net_", "path_filepath_"].iter().any(|p| name.starts_with(p))
+    }
+}
+
+/// Register the stdlib extern functions that `mode` allows.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef], mode: StdMode) {
     // Register runtime builtins (builtin, dynamic)
     vo_runtime::builtins::builtin::register_externs(registry, externs);
     vo_runtime::builtins::dynamic::register_externs(registry, externs);
@@ -53,9 +82,11 @@ pub fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef]) {
     bits::register_externs(registry, externs);
     rand::register_externs(registry, externs);
     bytes::register_externs(registry, externs);
+    sort::register_externs(registry, externs);
     strings::register_externs(registry, externs);
     strconv::register_externs(registry, externs);
     unicode::register_externs(registry, externs);
+    utf8::register_externs(registry, externs);
     fmt::register_externs(registry, externs);
     json::register_externs(registry, externs);
     toml_pkg::register_externs(registry, externs);
@@ -65,9 +96,42 @@ pub fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef]) {
     {
         regexp::register_externs(registry, externs);
         time::register_externs(registry, externs);
-        os::register_externs(registry, externs);
-        net::register_externs(registry, externs);
-        filepath::register_externs(registry, externs);
-        exec::register_externs(registry, externs);
+        if mode == StdMode::Full {
+            os::register_externs(registry, externs);
+            net::register_externs(registry, externs);
+            filepath::register_externs(registry, externs);
+            exec::register_externs(registry, externs);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandboxed_mode_omits_host_access() {
+        let externs: Vec<ExternDef> = ["math_Floor", "os_nativeReadFile"].iter()
+            .map(|name| ExternDef { name: name.to_string(), param_slots: 0, ret_slots: 0 })
+            .collect();
+
+        let mut full = ExternRegistry::new();
+        register_externs(&mut full, &externs, StdMode::Full);
+        assert!(full.has(0) && full.has(1));
+
+        let mut sandboxed = ExternRegistry::new();
+        register_externs(&mut sandboxed, &externs, StdMode::Sandboxed);
+        assert!(sandboxed.has(0));
+        assert!(!sandboxed.has(1), "file read must not be available when sandboxed");
+    }
+
+    #[test]
+    fn test_withholds() {
+        for name in ["os_nativeReadFile", "os_exec_lookPath", "net_dial", "path_filepath_evalSymlinks"] {
+            assert!(StdMode::Sandboxed.withholds(name), "{}", name);
+            assert!(!StdMode::Full.withholds(name), "{}", name);
+        }
+        assert!(!StdMode::Sandboxed.withholds("math_Floor"));
+        assert!(!StdMode::Sandboxed.withholds("strings_Index"));
     }
 }
@@ -0,0 +1,134 @@
+//! sort package native function implementations.
+//!
+//! Slice sorts through a Vo `less` closure, calling back into the VM for
+//! every comparison. The typed sorts and searches are implemented in Vo.
+
+#[cfg(not(feature = "std"))]
+use alloc::string::ToString;
+
+use vo_common_core::types::ValueKind;
+use vo_ffi_macro::vostd_extern_ctx_nostd;
+use vo_runtime::ffi::{ExternCallContext, ExternResult};
+use vo_runtime::gc::GcRef;
+use vo_runtime::objects::{array, interface, slice};
+
+/// Sorts in place by swapping whole elements in the slice's backing array,
+/// so `less(i, j)` always sees the current order, as in Go. If `less`
+/// panics the slice is left partially sorted and its panic value propagates.
+#[vostd_extern_ctx_nostd("sort", "Slice")]
+fn native_slice(call: &mut ExternCallContext) -> ExternResult {
+    let slot0 = call.arg_u64(slots::ARG_X);
+    if interface::unpack_value_kind(slot0) != ValueKind::Slice {
+        return ExternResult::Panic("sort.Slice: argument is not a slice".to_string());
+    }
+    let s = call.arg_u64(slots::ARG_X + 1) as GcRef;
+    if s.is_null() || slice::len(s) < 2 {
+        return ExternResult::Ok;
+    }
+    let less = call.arg_ref(slots::ARG_LESS);
+    if less.is_null() {
+        return ExternResult::Panic("sort.Slice: nil less function".to_string());
+    }
+    if !call.can_call_closure() {
+        return ExternResult::Panic("sort.Slice: closure calls not available".to_string());
+    }
+
+    let n = slice::len(s);
+    let mut sorter = Sorter {
+        call,
+        less,
+        base: slice::data_ptr(s),
+        elem_bytes: array::elem_bytes(slice::array_ref(s)),
+    };
+    match sorter.quick_sort(0, n) {
+        Ok(()) => ExternResult::Ok,
+        Err(LessPanicked) => ExternResult::Repanic,
+    }
+}
+
+/// `less` panicked; the VM holds its panic value for `ExternResult::Repanic`.
+struct LessPanicked;
+
+/// Quicksort with insertion sort for short runs, the same scheme as the
+/// typed sorts in sort.vo.
+struct Sorter<'a, 'c> {
+    call: &'a mut ExternCallContext<'c>,
+    less: GcRef,
+    base: *mut u8,
+    elem_bytes: usize,
+}
+
+impl Sorter<'_, '_> {
+    fn less(&mut self, i: usize, j: usize) -> Result<bool, LessPanicked> {
+        let mut ret = [0u64; 1];
+        match self.call.call_closure(self.less, &[i as u64, j as u64], &mut ret) {
+            Ok(_) => Ok(ret[0] != 0),
+            Err(_) => Err(LessPanicked),
+        }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        if i != j {
+            // SAFETY: i and j are distinct in-bounds indexes, so the elements
+            // don't overlap. The GC doesn't move objects, so `base` stays
+            // valid across the closure calls.
+            unsafe {
+                core::ptr::swap_nonoverlapping(
+                    self.base.add(i * self.elem_bytes),
+                    self.base.add(j * self.elem_bytes),
+                    self.elem_bytes,
+                );
+            }
+        }
+    }
+
+    fn quick_sort(&mut self, mut lo: usize, mut hi: usize) -> Result<(), LessPanicked> {
+        while hi - lo > 12 {
+            let p = self.partition(lo, hi)?;
+            if p - lo < hi - p {
+                self.quick_sort(lo, p)?;
+                lo = p + 1;
+            } else {
+                self.quick_sort(p + 1, hi)?;
+                hi = p;
+            }
+        }
+        self.insertion_sort(lo, hi)
+    }
+
+    /// Median-of-three pivot, moved to hi-1 where it stays during the scan.
+    fn partition(&mut self, lo: usize, hi: usize) -> Result<usize, LessPanicked> {
+        let mid = lo + (hi - lo) / 2;
+        if self.less(mid, lo)? {
+            self.swap(lo, mid);
+        }
+        if self.less(hi - 1, lo)? {
+            self.swap(lo, hi - 1);
+        }
+        if self.less(mid, hi - 1)? {
+            self.swap(mid, hi - 1);
+        }
+        let mut i = lo;
+        for j in lo..hi - 1 {
+            if self.less(j, hi - 1)? {
+                self.swap(i, j);
+                i += 1;
+            }
+        }
+        self.swap(i, hi - 1);
+        Ok(i)
+    }
+
+    fn insertion_sort(&mut self, lo: usize, hi: usize) -> Result<(), LessPanicked> {
+        for i in lo + 1..hi {
+            let mut j = i;
+            while j > lo && self.less(j, j - 1)? {
+                self.swap(j, j - 1);
+                j -= 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+vo_runtime::stdlib_register!(sort: Slice);
@@ -35,6 +35,11 @@ impl EmbeddedStdlib {
         }
         Self { files }
     }
+
+    /// Every embedded source file, in no particular order.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.files.iter().map(|(path, src)| (path.as_path(), src.as_str()))
+    }
 }
 
 impl FileSystem for EmbeddedStdlib {
@@ -1,10 +1,15 @@
 //! strconv package native function implementations.
 //!
-//! Native functions for float parsing/formatting (requires complex algorithms).
+//! Float parsing and formatting follow Go's strconv: ParseFloat accepts Go's
+//! float syntax (decimal and hexadecimal literals, underscores, Inf/NaN) and
+//! rounds correctly to the requested size; FormatFloat's shortest form (prec
+//! -1) is the fewest digits that parse back to the same value.
 //! Integer parsing/formatting and quote/unquote are implemented in Vo.
 
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use alloc::format;
 
@@ -12,83 +17,455 @@ use vo_ffi_macro::vostd_extern;
 
 // ==================== Float parsing ====================
 
+/// IEEE 754 layout of a float size.
+struct FloatInfo {
+    mantbits: u32,
+    expbits: u32,
+    bias: i32,
+}
+
+const FLOAT32: FloatInfo = FloatInfo { mantbits: 23, expbits: 8, bias: -127 };
+const FLOAT64: FloatInfo = FloatInfo { mantbits: 52, expbits: 11, bias: -1023 };
+
+/// `ok` is false for a syntax error, with value 0, or when the value
+/// overflows the requested size, with value ±Inf.
 #[vostd_extern("strconv", "ParseFloat")]
 fn parse_float(s: &str, bit_size: i64) -> (f64, bool) {
-    match s.trim().parse::<f64>() {
-        Ok(v) => {
-            if bit_size == 32 {
-                // Check if value fits in f32
-                let f32_val = v as f32;
-                if f32_val.is_infinite() && !v.is_infinite() {
-                    return (v, false); // overflow
+    atof(s.as_bytes(), bit_size == 32).unwrap_or((0.0, false))
+}
+
+/// Parse `s` as a float of 32 or 64 bits. Returns the value and whether it
+/// was in range, or None for a syntax error.
+fn atof(s: &[u8], float32: bool) -> Option<(f64, bool)> {
+    if let Some(v) = special(s) {
+        return Some((v, true));
+    }
+
+    let mut i = 0;
+    let neg = s.first() == Some(&b'-');
+    if matches!(s.first(), Some(b'+' | b'-')) {
+        i = 1;
+    }
+    let hex = s.len() > i + 2 && s[i] == b'0' && s[i + 1] | 0x20 == b'x';
+    if hex {
+        i += 2;
+    }
+
+    // Decimal text is cleaned up for the correctly rounded core parser;
+    // hex digits accumulate into `mant` × 2^`exp`.
+    let mut clean = Vec::with_capacity(s.len());
+    if neg {
+        clean.push(b'-');
+    }
+    let (mut mant, mut exp, mut trunc) = (0u64, 0i64, false);
+    let (mut saw_dot, mut saw_digits, mut underscores) = (false, false, false);
+    while i < s.len() {
+        let c = s[i];
+        if c == b'_' {
+            underscores = true;
+        } else if c == b'.' {
+            if saw_dot {
+                break;
+            }
+            saw_dot = true;
+            clean.push(c);
+        } else if let Some(d) = (c as char).to_digit(if hex { 16 } else { 10 }) {
+            saw_digits = true;
+            if !hex {
+                clean.push(c);
+            } else if mant >> 60 == 0 {
+                mant = mant << 4 | d as u64;
+                if saw_dot {
+                    exp -= 4;
                 }
-                (f32_val as f64, true)
             } else {
-                (v, true)
+                // Out of mantissa room: remember lost bits for rounding
+                trunc |= d != 0;
+                if !saw_dot {
+                    exp += 4;
+                }
             }
+        } else {
+            break;
         }
-        Err(_) => (0.0, false),
+        i += 1;
+    }
+    if !saw_digits {
+        return None;
+    }
+
+    // Exponent: 'e' for decimal, and a mandatory 'p' (power of two) for hex
+    if i < s.len() && s[i] | 0x20 == if hex { b'p' } else { b'e' } {
+        i += 1;
+        clean.push(b'e');
+        let mut esign = 1;
+        if i < s.len() && (s[i] == b'+' || s[i] == b'-') {
+            if s[i] == b'-' {
+                esign = -1;
+                clean.push(b'-');
+            }
+            i += 1;
+        }
+        if i >= s.len() || !s[i].is_ascii_digit() {
+            return None;
+        }
+        let mut e = 0i64;
+        while i < s.len() && (s[i].is_ascii_digit() || s[i] == b'_') {
+            if s[i] == b'_' {
+                underscores = true;
+            } else {
+                clean.push(s[i]);
+                if e < 10000 {
+                    e = e * 10 + (s[i] - b'0') as i64;
+                }
+            }
+            i += 1;
+        }
+        exp += esign * e;
+    } else if hex {
+        return None;
+    }
+    if i != s.len() || underscores && !underscore_ok(s) {
+        return None;
+    }
+
+    let info = if float32 { &FLOAT32 } else { &FLOAT64 };
+    if hex {
+        return Some(atof_hex(mant, exp, neg, trunc, info));
     }
+    let text = core::str::from_utf8(&clean).ok()?;
+    let v = if float32 { text.parse::<f32>().ok()? as f64 } else { text.parse::<f64>().ok()? };
+    Some((v, !v.is_infinite()))
+}
+
+/// Inf, Infinity and NaN, case-insensitive; only the infinities take a sign.
+fn special(s: &[u8]) -> Option<f64> {
+    let (sign, rest) = match s.first() {
+        Some(b'-') => (-1.0, &s[1..]),
+        Some(b'+') => (1.0, &s[1..]),
+        _ => {
+            if s.eq_ignore_ascii_case(b"nan") {
+                return Some(f64::NAN);
+            }
+            (1.0, s)
+        }
+    };
+    if rest.eq_ignore_ascii_case(b"inf") || rest.eq_ignore_ascii_case(b"infinity") {
+        return Some(sign * f64::INFINITY);
+    }
+    None
+}
+
+/// Underscores may only separate digits, or follow a base prefix.
+fn underscore_ok(s: &[u8]) -> bool {
+    // The previous character class: '^' start, '0' digit or base prefix,
+    // '_' underscore, '!' anything else
+    let mut saw = b'^';
+    let s = match s.first() {
+        Some(b'+' | b'-') => &s[1..],
+        _ => s,
+    };
+    let mut i = 0;
+    let mut hex = false;
+    if s.len() >= 2 && s[0] == b'0' && matches!(s[1] | 0x20, b'b' | b'o' | b'x') {
+        i = 2;
+        saw = b'0';
+        hex = s[1] | 0x20 == b'x';
+    }
+    for &c in &s[i..] {
+        if c.is_ascii_digit() || hex && c.is_ascii_hexdigit() {
+            saw = b'0';
+            continue;
+        }
+        if c == b'_' {
+            if saw != b'0' {
+                return false;
+            }
+            saw = b'_';
+            continue;
+        }
+        if saw == b'_' {
+            return false;
+        }
+        saw = b'!';
+    }
+    saw != b'_'
+}
+
+/// Round `mant` × 2^`exp` (plus a sticky bit if `trunc`) to the nearest
+/// float, ties to even.
+fn atof_hex(mut mant: u64, mut exp: i64, neg: bool, trunc: bool, info: &FloatInfo) -> (f64, bool) {
+    let mantbits = info.mantbits;
+    let bias = info.bias as i64;
+    let max_exp = (1i64 << info.expbits) + bias - 2;
+    let min_exp = bias + 1;
+    exp += mantbits as i64; // The mantissa is now implicitly divided by 2^mantbits
+
+    // Normalize to a leading 1 followed by mantbits bits and two rounding
+    // bits, the lowest of which is sticky
+    while mant != 0 && mant >> (mantbits + 2) == 0 {
+        mant <<= 1;
+        exp -= 1;
+    }
+    if trunc {
+        mant |= 1;
+    }
+    while mant >> (mantbits + 3) != 0 {
+        mant = mant >> 1 | mant & 1;
+        exp += 1;
+    }
+    // Denormalize if the exponent is too small (-2 for the rounding bits)
+    while mant > 1 && exp < min_exp - 2 {
+        mant = mant >> 1 | mant & 1;
+        exp += 1;
+    }
+
+    let mut round = mant & 3;
+    mant >>= 2;
+    round |= mant & 1; // Round half to even
+    exp += 2;
+    if round == 3 {
+        mant += 1;
+        if mant == 1 << (mantbits + 1) {
+            mant >>= 1;
+            exp += 1;
+        }
+    }
+    if mant >> mantbits == 0 {
+        exp = bias; // Denormal or zero
+    }
+    let in_range = exp <= max_exp;
+    if !in_range {
+        mant = 1 << mantbits;
+        exp = max_exp + 1;
+    }
+
+    let mut bits = mant & ((1 << mantbits) - 1);
+    bits |= (((exp - bias) as u64) & ((1 << info.expbits) - 1)) << mantbits;
+    if neg {
+        bits |= 1 << (mantbits + info.expbits);
+    }
+    let v = if mantbits == FLOAT32.mantbits { f32::from_bits(bits as u32) as f64 } else { f64::from_bits(bits) };
+    (v, in_range)
 }
 
 // ==================== Float formatting ====================
 
 #[vostd_extern("strconv", "FormatFloat")]
 fn format_float(f: f64, fmt: u8, prec: i64, bit_size: i64) -> String {
-    let f = if bit_size == 32 { (f as f32) as f64 } else { f };
-    
-    match fmt {
-        b'b' => {
-            // Binary exponent format
-            format!("{:b}", f.to_bits())
+    let mut out = Vec::with_capacity(24);
+    let prec = prec.clamp(-1, i32::MAX as i64) as i32;
+    append_float(&mut out, f, fmt, prec, if bit_size == 32 { 32 } else { 64 });
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+
+/// Decimal digits of a float's magnitude: the value is 0.d1d2d3... × 10^dp.
+/// No trailing zeros; zero has no digits.
+struct Decimal {
+    digits: Vec<u8>,
+    dp: i32,
+}
+
+impl Decimal {
+    /// Parse Rust's `{:e}` output, "d.ddde-x".
+    fn from_exp(s: &str) -> Self {
+        let (mantissa, exp) = s.split_once('e').unwrap_or((s, "0"));
+        let mut digits: Vec<u8> = mantissa.bytes().filter(|b| b.is_ascii_digit()).collect();
+        while digits.last() == Some(&b'0') {
+            digits.pop();
         }
-        b'e' => {
-            if prec < 0 {
-                format!("{:e}", f)
-            } else {
-                format!("{:.prec$e}", f, prec = prec as usize)
-            }
+        let dp = if digits.is_empty() { 0 } else { exp.parse::<i32>().unwrap_or(0) + 1 };
+        Decimal { digits, dp }
+    }
+
+    /// Fewest digits that read back as the same float of this size.
+    fn shortest(abs: f64, size: u32) -> Self {
+        if size == 32 {
+            Self::from_exp(&format!("{:e}", abs as f32))
+        } else {
+            Self::from_exp(&format!("{:e}", abs))
         }
-        b'E' => {
-            if prec < 0 {
-                format!("{:E}", f)
-            } else {
-                format!("{:.prec$E}", f, prec = prec as usize)
+    }
+
+    /// Rounded to `n` significant digits (n >= 1), half to even.
+    fn significant(abs: f64, n: usize) -> Self {
+        Self::from_exp(&format!("{:.*e}", n - 1, abs))
+    }
+
+    fn digit(&self, i: i32) -> u8 {
+        if i >= 0 && (i as usize) < self.digits.len() { self.digits[i as usize] } else { b'0' }
+    }
+
+    /// %e: d.ddde±dd with `prec` digits after the point.
+    fn append_e(&self, out: &mut Vec<u8>, prec: i32, fmt: u8) {
+        out.push(self.digit(0));
+        if prec > 0 {
+            out.push(b'.');
+            for i in 1..=prec {
+                out.push(self.digit(i));
             }
         }
-        b'f' | b'F' => {
-            if prec < 0 {
-                format!("{}", f)
-            } else {
-                format!("{:.prec$}", f, prec = prec as usize)
+        out.push(fmt);
+        let exp = if self.digits.is_empty() { 0 } else { self.dp - 1 };
+        out.push(if exp < 0 { b'-' } else { b'+' });
+        let exp = exp.unsigned_abs();
+        if exp < 10 {
+            out.push(b'0');
+        }
+        out.extend_from_slice(exp.to_string().as_bytes());
+    }
+
+    /// %f: ddd.ddd with `prec` digits after the point.
+    fn append_f(&self, out: &mut Vec<u8>, prec: i32) {
+        if self.dp > 0 {
+            for i in 0..self.dp {
+                out.push(self.digit(i));
             }
+        } else {
+            out.push(b'0');
         }
-        b'g' => {
-            // General format - uses e or f depending on exponent
-            if prec < 0 {
-                format!("{}", f)
-            } else {
-                format!("{:.prec$}", f, prec = prec as usize)
+        if prec > 0 {
+            out.push(b'.');
+            for i in 1..=prec {
+                out.push(self.digit(self.dp + i - 1));
             }
         }
-        b'G' => {
-            // General format uppercase
-            if prec < 0 {
-                format!("{}", f).to_uppercase()
-            } else {
-                format!("{:.prec$}", f, prec = prec as usize).to_uppercase()
+    }
+}
+
+/// Append `v` formatted like Go's strconv.AppendFloat(dst, v, fmt, prec, size).
+/// A negative `prec` selects the fewest digits that round-trip.
+pub(crate) fn append_float(out: &mut Vec<u8>, v: f64, fmt: u8, prec: i32, size: u32) {
+    let v = if size == 32 { v as f32 as f64 } else { v };
+    if v.is_nan() {
+        out.extend_from_slice(b"NaN");
+        return;
+    }
+    if v.is_infinite() {
+        out.extend_from_slice(if v > 0.0 { b"+Inf" } else { b"-Inf" });
+        return;
+    }
+    if !b"bfeEgGxX".contains(&fmt) {
+        out.push(b'%');
+        out.push(fmt);
+        return;
+    }
+    if v.is_sign_negative() {
+        out.push(b'-');
+    }
+    let abs = v.abs();
+    match fmt {
+        b'b' | b'x' | b'X' => append_float_binary(out, abs, fmt, prec, size),
+        b'f' if prec >= 0 => out.extend_from_slice(format!("{:.*}", prec as usize, abs).as_bytes()),
+        b'e' | b'E' => {
+            let d = if prec < 0 { Decimal::shortest(abs, size) } else { Decimal::significant(abs, prec as usize + 1) };
+            let prec = if prec < 0 { (d.digits.len() as i32 - 1).max(0) } else { prec };
+            d.append_e(out, prec, fmt);
+        }
+        b'g' | b'G' => {
+            let shortest = prec < 0;
+            let d = if shortest { Decimal::shortest(abs, size) } else { Decimal::significant(abs, prec.max(1) as usize) };
+            let nd = d.digits.len() as i32;
+            let mut prec = if shortest { nd } else { prec.max(1) };
+            // %e is used if the exponent is below -4 or at least the precision;
+            // shortest formatting decides as if the precision were 6
+            let mut eprec = prec;
+            if eprec > nd && nd >= d.dp {
+                eprec = nd;
             }
+            if shortest {
+                eprec = 6;
+            }
+            let exp = d.dp - 1;
+            if exp < -4 || exp >= eprec {
+                if prec > nd {
+                    prec = nd;
+                }
+                d.append_e(out, prec - 1, fmt + b'e' - b'g');
+                return;
+            }
+            if prec > d.dp {
+                prec = nd;
+            }
+            d.append_f(out, (prec - d.dp).max(0));
+        }
+        _ => {
+            // Shortest %f
+            let d = Decimal::shortest(abs, size);
+            d.append_f(out, (d.digits.len() as i32 - d.dp).max(0));
+        }
+    }
+}
+
+/// %b (mantissa p exponent, both decimal) and %x/%X (hexadecimal mantissa).
+fn append_float_binary(out: &mut Vec<u8>, abs: f64, fmt: u8, prec: i32, size: u32) {
+    let (bits, mantbits, expbits, bias) = if size == 32 {
+        ((abs as f32).to_bits() as u64, 23u32, 8u32, -127i32)
+    } else {
+        (abs.to_bits(), 52, 11, -1023)
+    };
+    let mut exp = ((bits >> mantbits) & ((1 << expbits) - 1)) as i32;
+    let mut mant = bits & ((1u64 << mantbits) - 1);
+    if exp == 0 {
+        exp += 1; // Denormal
+    } else {
+        mant |= 1 << mantbits;
+    }
+    exp += bias;
+
+    if fmt == b'b' {
+        out.extend_from_slice(format!("{}p{:+}", mant, exp - mantbits as i32).as_bytes());
+        return;
+    }
+
+    if mant == 0 {
+        exp = 0;
+    }
+    // Put the leading 1, if any, at bit 60
+    mant <<= 60 - mantbits;
+    while mant != 0 && mant & (1 << 60) == 0 {
+        mant <<= 1;
+        exp -= 1;
+    }
+    if (0..15).contains(&prec) {
+        let shift = prec as u32 * 4;
+        let extra = (mant << shift) & ((1 << 60) - 1);
+        mant >>= 60 - shift;
+        if extra | (mant & 1) > 1 << 59 {
+            mant += 1;
+        }
+        mant <<= 60 - shift;
+        if mant & (1 << 61) != 0 {
+            // The round carried into a new leading digit
+            mant >>= 1;
+            exp += 1;
         }
-        b'x' => {
-            // Hexadecimal floating point (lowercase) - not directly supported, use scientific
-            format!("{:e}", f)
+    }
+
+    let digits: &[u8; 16] = if fmt == b'X' { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+    out.extend_from_slice(&[b'0', fmt, b'0' + ((mant >> 60) & 1) as u8]);
+    mant <<= 4; // Drop the leading digit
+    if prec < 0 && mant != 0 {
+        out.push(b'.');
+        while mant != 0 {
+            out.push(digits[((mant >> 60) & 15) as usize]);
+            mant <<= 4;
         }
-        b'X' => {
-            // Hexadecimal floating point (uppercase)
-            format!("{:E}", f)
+    } else if prec > 0 {
+        out.push(b'.');
+        for _ in 0..prec {
+            out.push(digits[((mant >> 60) & 15) as usize]);
+            mant <<= 4;
         }
-        _ => format!("{}", f),
     }
+    out.push(if fmt == b'X' { b'P' } else { b'p' });
+    out.push(if exp < 0 { b'-' } else { b'+' });
+    let exp = exp.unsigned_abs();
+    if exp < 10 {
+        out.push(b'0');
+    }
+    out.extend_from_slice(exp.to_string().as_bytes());
 }
 
 vo_runtime::stdlib_register!(strconv: ParseFloat, FormatFloat);
@@ -0,0 +1,19 @@
+//! unicode/utf8 package native function implementations.
+//!
+//! Most of the package is plain Vo; these go through the runtime's string
+//! decoder so they agree with `for range` on invalid UTF-8.
+
+use vo_ffi_macro::vostd_extern_ctx;
+use vo_runtime::ffi::{ExternCallContext, ExternResult};
+use vo_runtime::objects::string;
+
+#[vostd_extern_ctx("unicode/utf8", "RuneCountInString")]
+fn rune_count_in_string(call: &mut ExternCallContext) -> ExternResult {
+    let n = string::rune_count(call.arg_ref(0));
+    call.ret_i64(0, n as i64);
+    ExternResult::Ok
+}
+
+vo_runtime::stdlib_register!(unicode_utf8:
+    RuneCountInString,
+);
@@ -0,0 +1,11 @@
+//! Feeds arbitrary bytes to the parser. Malformed input must come back as
+//! diagnostics; any panic or stack overflow is a bug.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let source = String::from_utf8_lossy(data);
+    let _ = vo_syntax::parse(&source, 0);
+});
@@ -1067,15 +1067,61 @@ pub enum DynAccessOp {
 // =============================================================================
 
 /// A visitor for traversing the AST.
+///
+/// Every method defaults to walking the node's children, so an implementation
+/// only overrides the hooks it cares about and calls the matching `walk_*`
+/// function to keep descending. Statements and expressions are dispatched by
+/// `visit_stmt`/`visit_expr`; match on `kind` there for per-kind handling.
+///
+/// ```
+/// use vo_syntax::ast::{walk_func_decl, FuncDecl, Visitor};
+///
+/// struct FuncNames(Vec<vo_common::symbol::Symbol>);
+///
+/// impl Visitor for FuncNames {
+///     fn visit_func_decl(&mut self, func: &FuncDecl) {
+///         self.0.push(func.name.symbol);
+///         walk_func_decl(self, func);
+///     }
+/// }
+/// ```
 pub trait Visitor: Sized {
     fn visit_file(&mut self, file: &File) {
         walk_file(self, file);
     }
 
+    fn visit_import(&mut self, import: &ImportDecl) {
+        walk_import(self, import);
+    }
+
     fn visit_decl(&mut self, decl: &Decl) {
         walk_decl(self, decl);
     }
 
+    fn visit_var_decl(&mut self, decl: &VarDecl) {
+        walk_var_decl(self, decl);
+    }
+
+    fn visit_const_decl(&mut self, decl: &ConstDecl) {
+        walk_const_decl(self, decl);
+    }
+
+    fn visit_type_decl(&mut self, decl: &TypeDecl) {
+        walk_type_decl(self, decl);
+    }
+
+    fn visit_func_decl(&mut self, decl: &FuncDecl) {
+        walk_func_decl(self, decl);
+    }
+
+    fn visit_func_sig(&mut self, sig: &FuncSig) {
+        walk_func_sig(self, sig);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
     fn visit_stmt(&mut self, stmt: &Stmt) {
         walk_stmt(self, stmt);
     }
@@ -1096,67 +1142,113 @@ pub fn walk_file<V: Visitor>(visitor: &mut V, file: &File) {
     if let Some(pkg) = &file.package {
         visitor.visit_ident(pkg);
     }
+    for import in &file.imports {
+        visitor.visit_import(import);
+    }
     for decl in &file.decls {
         visitor.visit_decl(decl);
     }
 }
 
+/// Walk an import.
+pub fn walk_import<V: Visitor>(visitor: &mut V, import: &ImportDecl) {
+    if let Some(alias) = &import.alias {
+        visitor.visit_ident(alias);
+    }
+}
+
 /// Walk a declaration.
 pub fn walk_decl<V: Visitor>(visitor: &mut V, decl: &Decl) {
     match decl {
-        Decl::Var(d) => {
-            for spec in &d.specs {
-                for name in &spec.names {
-                    visitor.visit_ident(name);
-                }
-                if let Some(ty) = &spec.ty {
-                    visitor.visit_type_expr(ty);
-                }
-                for value in &spec.values {
-                    visitor.visit_expr(value);
-                }
-            }
+        Decl::Var(d) => visitor.visit_var_decl(d),
+        Decl::Const(d) => visitor.visit_const_decl(d),
+        Decl::Type(d) => visitor.visit_type_decl(d),
+        Decl::Func(d) => visitor.visit_func_decl(d),
+    }
+}
+
+/// Walk a var declaration.
+pub fn walk_var_decl<V: Visitor>(visitor: &mut V, decl: &VarDecl) {
+    for spec in &decl.specs {
+        for name in &spec.names {
+            visitor.visit_ident(name);
         }
-        Decl::Const(d) => {
-            for spec in &d.specs {
-                for name in &spec.names {
-                    visitor.visit_ident(name);
-                }
-                if let Some(ty) = &spec.ty {
-                    visitor.visit_type_expr(ty);
-                }
-                for value in &spec.values {
-                    visitor.visit_expr(value);
-                }
-            }
+        if let Some(ty) = &spec.ty {
+            visitor.visit_type_expr(ty);
         }
-        Decl::Type(d) => {
-            visitor.visit_ident(&d.name);
-            visitor.visit_type_expr(&d.ty);
+        for value in &spec.values {
+            visitor.visit_expr(value);
         }
-        Decl::Func(d) => {
-            visitor.visit_ident(&d.name);
-            if let Some(body) = &d.body {
-                for stmt in &body.stmts {
-                    visitor.visit_stmt(stmt);
-                }
-            }
+    }
+}
+
+/// Walk a const declaration.
+pub fn walk_const_decl<V: Visitor>(visitor: &mut V, decl: &ConstDecl) {
+    for spec in &decl.specs {
+        for name in &spec.names {
+            visitor.visit_ident(name);
+        }
+        if let Some(ty) = &spec.ty {
+            visitor.visit_type_expr(ty);
+        }
+        for value in &spec.values {
+            visitor.visit_expr(value);
         }
     }
 }
 
+/// Walk a type declaration.
+pub fn walk_type_decl<V: Visitor>(visitor: &mut V, decl: &TypeDecl) {
+    visitor.visit_ident(&decl.name);
+    visitor.visit_type_expr(&decl.ty);
+}
+
+/// Walk a function declaration.
+pub fn walk_func_decl<V: Visitor>(visitor: &mut V, decl: &FuncDecl) {
+    if let Some(recv) = &decl.receiver {
+        if let Some(name) = &recv.name {
+            visitor.visit_ident(name);
+        }
+        visitor.visit_ident(&recv.ty);
+    }
+    visitor.visit_ident(&decl.name);
+    visitor.visit_func_sig(&decl.sig);
+    if let Some(body) = &decl.body {
+        visitor.visit_block(body);
+    }
+}
+
+/// Walk a function signature.
+pub fn walk_func_sig<V: Visitor>(visitor: &mut V, sig: &FuncSig) {
+    for param in &sig.params {
+        for name in &param.names {
+            visitor.visit_ident(name);
+        }
+        visitor.visit_type_expr(&param.ty);
+    }
+    for result in &sig.results {
+        if let Some(name) = &result.name {
+            visitor.visit_ident(name);
+        }
+        visitor.visit_type_expr(&result.ty);
+    }
+}
+
+/// Walk a block.
+pub fn walk_block<V: Visitor>(visitor: &mut V, block: &Block) {
+    for stmt in &block.stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
 /// Walk a statement.
 pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
     match &stmt.kind {
         StmtKind::Empty => {}
-        StmtKind::Block(block) => {
-            for s in &block.stmts {
-                visitor.visit_stmt(s);
-            }
-        }
-        StmtKind::Var(d) => visitor.visit_decl(&Decl::Var(d.clone())),
-        StmtKind::Const(d) => visitor.visit_decl(&Decl::Const(d.clone())),
-        StmtKind::Type(d) => visitor.visit_decl(&Decl::Type(d.clone())),
+        StmtKind::Block(block) => visitor.visit_block(block),
+        StmtKind::Var(d) => visitor.visit_var_decl(d),
+        StmtKind::Const(d) => visitor.visit_const_decl(d),
+        StmtKind::Type(d) => visitor.visit_type_decl(d),
         StmtKind::ShortVar(d) => {
             for name in &d.names {
                 visitor.visit_ident(name);
@@ -1185,9 +1277,7 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
                 visitor.visit_stmt(init);
             }
             visitor.visit_expr(&i.cond);
-            for s in &i.then.stmts {
-                visitor.visit_stmt(s);
-            }
+            visitor.visit_block(&i.then);
             if let Some(else_) = &i.else_ {
                 visitor.visit_stmt(else_);
             }
@@ -1195,6 +1285,7 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
         StmtKind::For(f) => {
             match &f.clause {
                 ForClause::Cond(Some(e)) => visitor.visit_expr(e),
+                ForClause::Cond(None) => {}
                 ForClause::Three { init, cond, post } => {
                     if let Some(init) = init {
                         visitor.visit_stmt(init);
@@ -1206,12 +1297,17 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
                         visitor.visit_stmt(post);
                     }
                 }
-                ForClause::Range { expr, .. } => visitor.visit_expr(expr),
-                _ => {}
-            }
-            for s in &f.body.stmts {
-                visitor.visit_stmt(s);
+                ForClause::Range { key, value, expr, .. } => {
+                    if let Some(key) = key {
+                        visitor.visit_expr(key);
+                    }
+                    if let Some(value) = value {
+                        visitor.visit_expr(value);
+                    }
+                    visitor.visit_expr(expr);
+                }
             }
+            visitor.visit_block(&f.body);
         }
         StmtKind::Switch(s) => {
             if let Some(init) = &s.init {
@@ -1233,12 +1329,13 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
             if let Some(init) = &s.init {
                 visitor.visit_stmt(init);
             }
+            if let Some(assign) = &s.assign {
+                visitor.visit_ident(assign);
+            }
             visitor.visit_expr(&s.expr);
             for case in &s.cases {
-                for ty in &case.types {
-                    if let Some(ty) = ty {
-                        visitor.visit_type_expr(ty);
-                    }
+                for ty in case.types.iter().flatten() {
+                    visitor.visit_type_expr(ty);
                 }
                 for s in &case.body {
                     visitor.visit_stmt(s);
@@ -1247,6 +1344,19 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
         }
         StmtKind::Select(s) => {
             for case in &s.cases {
+                match &case.comm {
+                    Some(CommClause::Send(send)) => {
+                        visitor.visit_expr(&send.chan);
+                        visitor.visit_expr(&send.value);
+                    }
+                    Some(CommClause::Recv(recv)) => {
+                        for name in &recv.lhs {
+                            visitor.visit_ident(name);
+                        }
+                        visitor.visit_expr(&recv.expr);
+                    }
+                    None => {}
+                }
                 for stmt in &case.body {
                     visitor.visit_stmt(stmt);
                 }
@@ -1265,7 +1375,13 @@ pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
             visitor.visit_expr(&s.chan);
             visitor.visit_expr(&s.value);
         }
-        StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Goto(_) | StmtKind::Fallthrough => {}
+        StmtKind::Break(BreakStmt { label }) | StmtKind::Continue(ContinueStmt { label }) => {
+            if let Some(label) = label {
+                visitor.visit_ident(label);
+            }
+        }
+        StmtKind::Goto(g) => visitor.visit_ident(&g.label),
+        StmtKind::Fallthrough => {}
         StmtKind::Labeled(l) => {
             visitor.visit_ident(&l.label);
             visitor.visit_stmt(&l.stmt);
@@ -1301,6 +1417,9 @@ pub fn walk_expr<V: Visitor>(visitor: &mut V, expr: &Expr) {
             if let Some(high) = &s.high {
                 visitor.visit_expr(high);
             }
+            if let Some(max) = &s.max {
+                visitor.visit_expr(max);
+            }
         }
         ExprKind::Selector(s) => {
             visitor.visit_expr(&s.expr);
@@ -1317,13 +1436,17 @@ pub fn walk_expr<V: Visitor>(visitor: &mut V, expr: &Expr) {
                 visitor.visit_type_expr(ty);
             }
             for elem in &c.elems {
+                match &elem.key {
+                    Some(CompositeLitKey::Ident(ident)) => visitor.visit_ident(ident),
+                    Some(CompositeLitKey::Expr(key)) => visitor.visit_expr(key),
+                    None => {}
+                }
                 visitor.visit_expr(&elem.value);
             }
         }
         ExprKind::FuncLit(f) => {
-            for stmt in &f.body.stmts {
-                visitor.visit_stmt(stmt);
-            }
+            visitor.visit_func_sig(&f.sig);
+            visitor.visit_block(&f.body);
         }
         ExprKind::Conversion(c) => {
             visitor.visit_type_expr(&c.ty);
@@ -1376,15 +1499,18 @@ pub fn walk_type_expr<V: Visitor>(visitor: &mut V, ty: &TypeExpr) {
         TypeExprKind::Port(p) => visitor.visit_type_expr(p),
         TypeExprKind::Island => {}
         TypeExprKind::Func(f) => {
-            for p in &f.params {
+            for p in f.params.iter().chain(&f.results) {
+                for name in &p.names {
+                    visitor.visit_ident(name);
+                }
                 visitor.visit_type_expr(&p.ty);
             }
-            for r in &f.results {
-                visitor.visit_type_expr(&r.ty);
-            }
         }
         TypeExprKind::Struct(s) => {
             for field in &s.fields {
+                for name in &field.names {
+                    visitor.visit_ident(name);
+                }
                 visitor.visit_type_expr(&field.ty);
             }
         }
@@ -1396,6 +1522,7 @@ pub fn walk_type_expr<V: Visitor>(visitor: &mut V, ty: &TypeExpr) {
                 match elem {
                     InterfaceElem::Method(m) => {
                         visitor.visit_ident(&m.name);
+                        visitor.visit_func_sig(&m.sig);
                     }
                     InterfaceElem::Embedded(e) => {
                         visitor.visit_ident(e);
@@ -1409,3 +1536,86 @@ pub fn walk_type_expr<V: Visitor>(visitor: &mut V, ty: &TypeExpr) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    struct FuncNames<'a> {
+        interner: &'a SymbolInterner,
+        names: Vec<String>,
+    }
+
+    impl Visitor for FuncNames<'_> {
+        fn visit_func_decl(&mut self, func: &FuncDecl) {
+            self.names.push(func.name.as_str(self.interner).unwrap().to_string());
+            walk_func_decl(self, func);
+        }
+    }
+
+    #[derive(Default)]
+    struct Counts {
+        idents: usize,
+        blocks: usize,
+        stmts: usize,
+    }
+
+    impl Visitor for Counts {
+        fn visit_ident(&mut self, _ident: &Ident) {
+            self.idents += 1;
+        }
+
+        fn visit_block(&mut self, block: &Block) {
+            self.blocks += 1;
+            walk_block(self, block);
+        }
+
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            if !matches!(stmt.kind, StmtKind::Empty) {
+                self.stmts += 1;
+            }
+            walk_stmt(self, stmt);
+        }
+    }
+
+    #[test]
+    fn test_collect_func_names() {
+        let source = r#"
+            package main
+            type T struct{}
+            func (t T) Method() {}
+            func helper(x int) int { return x }
+            func main() { helper(1) }
+        "#;
+        let (file, diags, interner) = parse(source, 0);
+        assert!(!diags.has_errors());
+        let mut v = FuncNames { interner: &interner, names: Vec::new() };
+        v.visit_file(&file);
+        assert_eq!(v.names, vec!["Method", "helper", "main"]);
+    }
+
+    #[test]
+    fn test_walk_reaches_nested_nodes() {
+        let source = r#"
+            package p
+            func f(a int) int {
+                g := func(b int) { _ = a + b }
+                select {
+                case x := <-ch:
+                    ch <- x
+                }
+            }
+        "#;
+        let (file, diags, _) = parse(source, 0);
+        assert!(!diags.has_errors());
+        let mut v = Counts::default();
+        v.visit_file(&file);
+        // Function body, func literal body.
+        assert_eq!(v.blocks, 2);
+        // `g := ...`, `_ = a + b`, `select`, `ch <- x`.
+        assert_eq!(v.stmts, 4);
+        // p, f, a, int, int, g, b, int, _, a, b, x, ch, ch, x.
+        assert_eq!(v.idents, 15);
+    }
+}
@@ -76,6 +76,8 @@ pub enum SyntaxError {
     ExpectedType = 1104,
     /// Expected identifier.
     ExpectedIdent = 1105,
+    /// Expressions, types or statements nested too deeply.
+    NestingTooDeep = 1106,
 
     // === Parser: Declarations (1120-1139) ===
     /// Expected package declaration.
@@ -135,6 +137,7 @@ impl SyntaxError {
             SyntaxError::ExpectedStmt => "expected statement",
             SyntaxError::ExpectedType => "expected type",
             SyntaxError::ExpectedIdent => "expected identifier",
+            SyntaxError::NestingTooDeep => "nesting too deep",
 
             // Parser: Declarations
             SyntaxError::ExpectedPackage => "expected package declaration",
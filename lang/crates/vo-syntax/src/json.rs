@@ -0,0 +1,754 @@
+//! JSON export of the AST.
+//!
+//! Produces a machine-readable form of a parsed `File` for external tooling.
+//! Every node is an object with a `kind` and a `span` (`{"start", "end"}`
+//! byte offsets), followed by its children. Identifiers are resolved through
+//! the interner and emitted as `{"kind": "Ident", "name": ...}` nodes.
+
+use std::fmt::Write;
+
+use vo_common::span::Span;
+use vo_common::symbol::{Symbol, SymbolInterner};
+
+use crate::ast::*;
+
+/// Serializes a file's AST to compact JSON.
+pub fn to_json(file: &File, interner: &SymbolInterner) -> String {
+    let mut w = JsonWriter { interner, out: String::new() };
+    w.file(file);
+    w.out
+}
+
+struct JsonWriter<'a> {
+    interner: &'a SymbolInterner,
+    out: String,
+}
+
+impl JsonWriter<'_> {
+    // =========================================================================
+    // Primitives
+    // =========================================================================
+
+    fn begin(&mut self, kind: &str, span: Span) {
+        write!(
+            self.out,
+            "{{\"kind\":\"{}\",\"span\":{{\"start\":{},\"end\":{}}}",
+            kind, span.start.0, span.end.0
+        )
+        .unwrap();
+    }
+
+    fn end(&mut self) {
+        self.out.push('}');
+    }
+
+    fn key(&mut self, name: &str) {
+        write!(self.out, ",\"{}\":", name).unwrap();
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(self.out, "\\u{:04x}", c as u32).unwrap(),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn symbol(&mut self, sym: Symbol) {
+        let s = self.interner.resolve(sym).unwrap_or("");
+        self.string(s);
+    }
+
+    fn str_field(&mut self, name: &str, value: &str) {
+        self.key(name);
+        self.string(value);
+    }
+
+    fn bool_field(&mut self, name: &str, value: bool) {
+        self.key(name);
+        self.out.push_str(if value { "true" } else { "false" });
+    }
+
+    fn list<T>(&mut self, name: &str, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.key(name);
+        self.out.push('[');
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            f(self, item);
+        }
+        self.out.push(']');
+    }
+
+    fn opt<T>(&mut self, name: &str, item: Option<&T>, f: impl FnOnce(&mut Self, &T)) {
+        self.key(name);
+        match item {
+            Some(item) => f(self, item),
+            None => self.out.push_str("null"),
+        }
+    }
+
+    fn ident(&mut self, ident: &Ident) {
+        self.begin("Ident", ident.span);
+        self.key("name");
+        self.symbol(ident.symbol);
+        self.end();
+    }
+
+    fn ident_field(&mut self, name: &str, ident: &Ident) {
+        self.key(name);
+        self.ident(ident);
+    }
+
+    fn idents(&mut self, name: &str, idents: &[Ident]) {
+        self.list(name, idents, |w, id| w.ident(id));
+    }
+
+    fn exprs(&mut self, name: &str, exprs: &[Expr]) {
+        self.list(name, exprs, |w, e| w.expr(e));
+    }
+
+    fn stmts(&mut self, name: &str, stmts: &[Stmt]) {
+        self.list(name, stmts, |w, s| w.stmt(s));
+    }
+
+    fn expr_field(&mut self, name: &str, expr: &Expr) {
+        self.key(name);
+        self.expr(expr);
+    }
+
+    fn type_field(&mut self, name: &str, ty: &TypeExpr) {
+        self.key(name);
+        self.type_expr(ty);
+    }
+
+    // =========================================================================
+    // Declarations
+    // =========================================================================
+
+    fn file(&mut self, file: &File) {
+        self.begin("File", file.span);
+        self.opt("package", file.package.as_ref(), |w, id| w.ident(id));
+        self.list("imports", &file.imports, |w, imp| w.import(imp));
+        self.list("decls", &file.decls, |w, d| w.decl(d));
+        self.end();
+    }
+
+    fn import(&mut self, imp: &ImportDecl) {
+        self.begin("Import", imp.span);
+        self.str_field("path", &imp.path.value);
+        self.bool_field("external", imp.kind == ImportKind::External);
+        self.opt("alias", imp.alias.as_ref(), |w, id| w.ident(id));
+        self.end();
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Var(v) => self.var_decl(v),
+            Decl::Const(c) => self.const_decl(c),
+            Decl::Type(t) => self.type_decl(t),
+            Decl::Func(f) => self.func_decl(f),
+        }
+    }
+
+    fn var_decl(&mut self, v: &VarDecl) {
+        self.begin("VarDecl", v.span);
+        self.list("specs", &v.specs, |w, spec| {
+            w.begin("VarSpec", spec.span);
+            w.idents("names", &spec.names);
+            w.opt("type", spec.ty.as_ref(), |w, ty| w.type_expr(ty));
+            w.exprs("values", &spec.values);
+            w.end();
+        });
+        self.end();
+    }
+
+    fn const_decl(&mut self, c: &ConstDecl) {
+        self.begin("ConstDecl", c.span);
+        self.list("specs", &c.specs, |w, spec| {
+            w.begin("ConstSpec", spec.span);
+            w.idents("names", &spec.names);
+            w.opt("type", spec.ty.as_ref(), |w, ty| w.type_expr(ty));
+            w.exprs("values", &spec.values);
+            w.end();
+        });
+        self.end();
+    }
+
+    fn type_decl(&mut self, t: &TypeDecl) {
+        self.begin("TypeDecl", t.span);
+        self.ident_field("name", &t.name);
+        self.bool_field("alias", t.is_alias);
+        self.type_field("type", &t.ty);
+        self.end();
+    }
+
+    fn func_decl(&mut self, f: &FuncDecl) {
+        self.begin("FuncDecl", f.span);
+        self.opt("receiver", f.receiver.as_ref(), |w, recv| {
+            w.begin("Receiver", recv.span);
+            w.opt("name", recv.name.as_ref(), |w, id| w.ident(id));
+            w.ident_field("type", &recv.ty);
+            w.bool_field("pointer", recv.is_pointer);
+            w.end();
+        });
+        self.ident_field("name", &f.name);
+        self.key("sig");
+        self.func_sig(&f.sig);
+        self.opt("body", f.body.as_ref(), |w, b| w.block(b));
+        self.end();
+    }
+
+    fn func_sig(&mut self, sig: &FuncSig) {
+        self.begin("FuncSig", sig.span);
+        self.list("params", &sig.params, |w, p| w.param(p));
+        self.list("results", &sig.results, |w, r| {
+            w.begin("Result", r.span);
+            w.opt("name", r.name.as_ref(), |w, id| w.ident(id));
+            w.type_field("type", &r.ty);
+            w.end();
+        });
+        self.bool_field("variadic", sig.variadic);
+        self.end();
+    }
+
+    fn param(&mut self, p: &Param) {
+        self.begin("Param", p.span);
+        self.idents("names", &p.names);
+        self.type_field("type", &p.ty);
+        self.end();
+    }
+
+    fn block(&mut self, b: &Block) {
+        self.begin("Block", b.span);
+        self.stmts("stmts", &b.stmts);
+        self.end();
+    }
+
+    // =========================================================================
+    // Types
+    // =========================================================================
+
+    fn type_expr(&mut self, ty: &TypeExpr) {
+        match &ty.kind {
+            TypeExprKind::Ident(id) => {
+                self.begin("NamedType", ty.span);
+                self.ident_field("name", id);
+            }
+            TypeExprKind::Selector(sel) => {
+                self.begin("QualifiedType", ty.span);
+                self.ident_field("pkg", &sel.pkg);
+                self.ident_field("name", &sel.sel);
+            }
+            TypeExprKind::Array(arr) => {
+                self.begin("ArrayType", ty.span);
+                self.expr_field("len", &arr.len);
+                self.type_field("elem", &arr.elem);
+            }
+            TypeExprKind::Slice(elem) => {
+                self.begin("SliceType", ty.span);
+                self.type_field("elem", elem);
+            }
+            TypeExprKind::Map(m) => {
+                self.begin("MapType", ty.span);
+                self.type_field("key", &m.key);
+                self.type_field("value", &m.value);
+            }
+            TypeExprKind::Chan(c) => {
+                self.begin("ChanType", ty.span);
+                let dir = match c.dir {
+                    ChanDir::Both => "both",
+                    ChanDir::Send => "send",
+                    ChanDir::Recv => "recv",
+                };
+                self.str_field("dir", dir);
+                self.type_field("elem", &c.elem);
+            }
+            TypeExprKind::Port(elem) => {
+                self.begin("PortType", ty.span);
+                self.type_field("elem", elem);
+            }
+            TypeExprKind::Island => self.begin("IslandType", ty.span),
+            TypeExprKind::Func(f) => {
+                self.begin("FuncType", ty.span);
+                self.list("params", &f.params, |w, p| w.param(p));
+                self.list("results", &f.results, |w, p| w.param(p));
+            }
+            TypeExprKind::Struct(s) => {
+                self.begin("StructType", ty.span);
+                self.list("fields", &s.fields, |w, field| {
+                    w.begin("Field", field.span);
+                    w.idents("names", &field.names);
+                    w.type_field("type", &field.ty);
+                    w.opt("tag", field.tag.as_ref(), |w, tag| w.string(&tag.value));
+                    w.end();
+                });
+            }
+            TypeExprKind::Pointer(inner) => {
+                self.begin("PointerType", ty.span);
+                self.type_field("elem", inner);
+            }
+            TypeExprKind::Interface(i) => {
+                self.begin("InterfaceType", ty.span);
+                self.list("elems", &i.elems, |w, elem| match elem {
+                    InterfaceElem::Method(m) => {
+                        w.begin("Method", m.span);
+                        w.ident_field("name", &m.name);
+                        w.key("sig");
+                        w.func_sig(&m.sig);
+                        w.end();
+                    }
+                    InterfaceElem::Embedded(id) => {
+                        w.begin("Embedded", id.span);
+                        w.ident_field("name", id);
+                        w.end();
+                    }
+                    InterfaceElem::EmbeddedQualified { pkg, name, span } => {
+                        w.begin("Embedded", *span);
+                        w.ident_field("pkg", pkg);
+                        w.ident_field("name", name);
+                        w.end();
+                    }
+                });
+            }
+        }
+        self.end();
+    }
+
+    // =========================================================================
+    // Statements
+    // =========================================================================
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let span = stmt.span;
+        match &stmt.kind {
+            StmtKind::Empty => self.begin("EmptyStmt", span),
+            StmtKind::Block(b) => {
+                self.begin("BlockStmt", span);
+                self.stmts("stmts", &b.stmts);
+            }
+            StmtKind::Var(v) => {
+                self.begin("DeclStmt", span);
+                self.key("decl");
+                self.var_decl(v);
+            }
+            StmtKind::Const(c) => {
+                self.begin("DeclStmt", span);
+                self.key("decl");
+                self.const_decl(c);
+            }
+            StmtKind::Type(t) => {
+                self.begin("DeclStmt", span);
+                self.key("decl");
+                self.type_decl(t);
+            }
+            StmtKind::ShortVar(sv) => {
+                self.begin("ShortVarStmt", span);
+                self.idents("names", &sv.names);
+                self.exprs("values", &sv.values);
+            }
+            StmtKind::Expr(e) => {
+                self.begin("ExprStmt", span);
+                self.expr_field("expr", e);
+            }
+            StmtKind::Assign(a) => {
+                self.begin("AssignStmt", span);
+                self.exprs("lhs", &a.lhs);
+                self.str_field("op", assign_op_str(a.op));
+                self.exprs("rhs", &a.rhs);
+            }
+            StmtKind::IncDec(id) => {
+                self.begin("IncDecStmt", span);
+                self.expr_field("expr", &id.expr);
+                self.bool_field("inc", id.is_inc);
+            }
+            StmtKind::Return(r) => {
+                self.begin("ReturnStmt", span);
+                self.exprs("values", &r.values);
+            }
+            StmtKind::If(i) => {
+                self.begin("IfStmt", span);
+                self.opt("init", i.init.as_deref(), |w, s| w.stmt(s));
+                self.expr_field("cond", &i.cond);
+                self.key("then");
+                self.block(&i.then);
+                self.opt("else", i.else_.as_deref(), |w, s| w.stmt(s));
+            }
+            StmtKind::For(f) => {
+                match &f.clause {
+                    ForClause::Cond(cond) => {
+                        self.begin("ForStmt", span);
+                        self.opt("cond", cond.as_ref(), |w, e| w.expr(e));
+                    }
+                    ForClause::Three { init, cond, post } => {
+                        self.begin("ForStmt", span);
+                        self.opt("init", init.as_deref(), |w, s| w.stmt(s));
+                        self.opt("cond", cond.as_ref(), |w, e| w.expr(e));
+                        self.opt("post", post.as_deref(), |w, s| w.stmt(s));
+                    }
+                    ForClause::Range { key, value, define, expr } => {
+                        self.begin("RangeStmt", span);
+                        self.opt("key", key.as_ref(), |w, e| w.expr(e));
+                        self.opt("value", value.as_ref(), |w, e| w.expr(e));
+                        self.bool_field("define", *define);
+                        self.expr_field("expr", expr);
+                    }
+                }
+                self.key("body");
+                self.block(&f.body);
+            }
+            StmtKind::Switch(s) => {
+                self.begin("SwitchStmt", span);
+                self.opt("init", s.init.as_deref(), |w, s| w.stmt(s));
+                self.opt("tag", s.tag.as_ref(), |w, e| w.expr(e));
+                self.list("cases", &s.cases, |w, case| {
+                    w.begin("CaseClause", case.span);
+                    w.exprs("exprs", &case.exprs);
+                    w.stmts("body", &case.body);
+                    w.end();
+                });
+            }
+            StmtKind::TypeSwitch(s) => {
+                self.begin("TypeSwitchStmt", span);
+                self.opt("init", s.init.as_deref(), |w, s| w.stmt(s));
+                self.opt("assign", s.assign.as_ref(), |w, id| w.ident(id));
+                self.expr_field("expr", &s.expr);
+                self.list("cases", &s.cases, |w, case| {
+                    w.begin("TypeCaseClause", case.span);
+                    // `None` is the `nil` case.
+                    w.list("types", &case.types, |w, ty| match ty {
+                        Some(ty) => w.type_expr(ty),
+                        None => w.out.push_str("null"),
+                    });
+                    w.stmts("body", &case.body);
+                    w.end();
+                });
+            }
+            StmtKind::Select(s) => {
+                self.begin("SelectStmt", span);
+                self.list("cases", &s.cases, |w, case| {
+                    w.begin("CommClause", case.span);
+                    w.opt("comm", case.comm.as_ref(), |w, comm| match comm {
+                        CommClause::Send(send) => {
+                            w.begin("SendComm", send.chan.span.merge(send.value.span));
+                            w.expr_field("chan", &send.chan);
+                            w.expr_field("value", &send.value);
+                            w.end();
+                        }
+                        CommClause::Recv(recv) => {
+                            let start = recv.lhs.first().map_or(recv.expr.span, |id| id.span);
+                            w.begin("RecvComm", start.merge(recv.expr.span));
+                            w.idents("lhs", &recv.lhs);
+                            w.bool_field("define", recv.define);
+                            w.expr_field("expr", &recv.expr);
+                            w.end();
+                        }
+                    });
+                    w.stmts("body", &case.body);
+                    w.end();
+                });
+            }
+            StmtKind::Go(g) => {
+                self.begin("GoStmt", span);
+                self.opt("island", g.target_island.as_ref(), |w, e| w.expr(e));
+                self.expr_field("call", &g.call);
+            }
+            StmtKind::Defer(d) => {
+                self.begin("DeferStmt", span);
+                self.expr_field("call", &d.call);
+            }
+            StmtKind::ErrDefer(d) => {
+                self.begin("ErrDeferStmt", span);
+                self.expr_field("call", &d.call);
+            }
+            StmtKind::Fail(f) => {
+                self.begin("FailStmt", span);
+                self.expr_field("error", &f.error);
+            }
+            StmtKind::Send(s) => {
+                self.begin("SendStmt", span);
+                self.expr_field("chan", &s.chan);
+                self.expr_field("value", &s.value);
+            }
+            StmtKind::Break(b) => {
+                self.begin("BreakStmt", span);
+                self.opt("label", b.label.as_ref(), |w, id| w.ident(id));
+            }
+            StmtKind::Continue(c) => {
+                self.begin("ContinueStmt", span);
+                self.opt("label", c.label.as_ref(), |w, id| w.ident(id));
+            }
+            StmtKind::Goto(g) => {
+                self.begin("GotoStmt", span);
+                self.ident_field("label", &g.label);
+            }
+            StmtKind::Fallthrough => self.begin("FallthroughStmt", span),
+            StmtKind::Labeled(l) => {
+                self.begin("LabeledStmt", span);
+                self.ident_field("label", &l.label);
+                self.key("stmt");
+                self.stmt(&l.stmt);
+            }
+        }
+        self.end();
+    }
+
+    // =========================================================================
+    // Expressions
+    // =========================================================================
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Ident(id) => {
+                self.ident(id);
+                return;
+            }
+            ExprKind::IntLit(lit) => {
+                self.begin("IntLit", span);
+                self.key("raw");
+                self.symbol(lit.raw);
+            }
+            ExprKind::FloatLit(lit) => {
+                self.begin("FloatLit", span);
+                self.key("raw");
+                self.symbol(lit.raw);
+            }
+            ExprKind::RuneLit(lit) => {
+                self.begin("RuneLit", span);
+                self.key("raw");
+                self.symbol(lit.raw);
+            }
+            ExprKind::StringLit(lit) => {
+                self.begin("StringLit", span);
+                self.str_field("value", &lit.value);
+                self.bool_field("raw", lit.is_raw);
+            }
+            ExprKind::Binary(b) => {
+                self.begin("BinaryExpr", span);
+                self.str_field("op", &b.op.to_string());
+                self.expr_field("left", &b.left);
+                self.expr_field("right", &b.right);
+            }
+            ExprKind::Unary(u) => {
+                self.begin("UnaryExpr", span);
+                self.str_field("op", &u.op.to_string());
+                self.expr_field("operand", &u.operand);
+            }
+            ExprKind::Call(c) => {
+                self.begin("CallExpr", span);
+                self.expr_field("func", &c.func);
+                self.exprs("args", &c.args);
+                self.bool_field("spread", c.spread);
+            }
+            ExprKind::Index(idx) => {
+                self.begin("IndexExpr", span);
+                self.expr_field("expr", &idx.expr);
+                self.expr_field("index", &idx.index);
+            }
+            ExprKind::Slice(s) => {
+                self.begin("SliceExpr", span);
+                self.expr_field("expr", &s.expr);
+                self.opt("low", s.low.as_ref(), |w, e| w.expr(e));
+                self.opt("high", s.high.as_ref(), |w, e| w.expr(e));
+                self.opt("max", s.max.as_ref(), |w, e| w.expr(e));
+            }
+            ExprKind::Selector(s) => {
+                self.begin("SelectorExpr", span);
+                self.expr_field("expr", &s.expr);
+                self.ident_field("sel", &s.sel);
+            }
+            ExprKind::TypeAssert(ta) => {
+                self.begin("TypeAssertExpr", span);
+                self.expr_field("expr", &ta.expr);
+                // `None` is the `.(type)` form of a type switch.
+                self.opt("type", ta.ty.as_ref(), |w, ty| w.type_expr(ty));
+            }
+            ExprKind::CompositeLit(cl) => {
+                self.begin("CompositeLit", span);
+                self.opt("type", cl.ty.as_ref(), |w, ty| w.type_expr(ty));
+                self.list("elems", &cl.elems, |w, elem| {
+                    w.begin("Element", elem.span);
+                    w.opt("key", elem.key.as_ref(), |w, key| match key {
+                        CompositeLitKey::Ident(id) => w.ident(id),
+                        CompositeLitKey::Expr(e) => w.expr(e),
+                    });
+                    w.expr_field("value", &elem.value);
+                    w.end();
+                });
+            }
+            ExprKind::FuncLit(f) => {
+                self.begin("FuncLit", span);
+                self.key("sig");
+                self.func_sig(&f.sig);
+                self.key("body");
+                self.block(&f.body);
+            }
+            ExprKind::Conversion(c) => {
+                self.begin("ConversionExpr", span);
+                self.type_field("type", &c.ty);
+                self.expr_field("expr", &c.expr);
+            }
+            ExprKind::Receive(e) => {
+                self.begin("ReceiveExpr", span);
+                self.expr_field("chan", e);
+            }
+            ExprKind::Paren(e) => {
+                self.begin("ParenExpr", span);
+                self.expr_field("expr", e);
+            }
+            ExprKind::TypeAsExpr(ty) => {
+                self.begin("TypeExpr", span);
+                self.type_field("type", ty);
+            }
+            ExprKind::TryUnwrap(e) => {
+                self.begin("TryUnwrapExpr", span);
+                self.expr_field("expr", e);
+            }
+            ExprKind::DynAccess(d) => {
+                self.begin("DynAccessExpr", span);
+                self.expr_field("base", &d.base);
+                match &d.op {
+                    DynAccessOp::Field(id) => {
+                        self.str_field("op", "field");
+                        self.ident_field("field", id);
+                    }
+                    DynAccessOp::Index(idx) => {
+                        self.str_field("op", "index");
+                        self.expr_field("index", idx);
+                    }
+                    DynAccessOp::Call { args, spread } => {
+                        self.str_field("op", "call");
+                        self.exprs("args", args);
+                        self.bool_field("spread", *spread);
+                    }
+                    DynAccessOp::MethodCall { method, args, spread } => {
+                        self.str_field("op", "method_call");
+                        self.ident_field("method", method);
+                        self.exprs("args", args);
+                        self.bool_field("spread", *spread);
+                    }
+                }
+            }
+            ExprKind::Ellipsis => self.begin("Ellipsis", span),
+        }
+        self.end();
+    }
+}
+
+fn assign_op_str(op: AssignOp) -> &'static str {
+    match op {
+        AssignOp::Assign => "=",
+        AssignOp::Add => "+=",
+        AssignOp::Sub => "-=",
+        AssignOp::Mul => "*=",
+        AssignOp::Div => "/=",
+        AssignOp::Rem => "%=",
+        AssignOp::Shl => "<<=",
+        AssignOp::Shr => ">>=",
+        AssignOp::And => "&=",
+        AssignOp::Or => "|=",
+        AssignOp::Xor => "^=",
+        AssignOp::AndNot => "&^=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn json_of(source: &str) -> String {
+        let (file, diags, interner) = parse(source, 0);
+        assert!(!diags.has_errors(), "parse errors: {:?}", diags.iter().collect::<Vec<_>>());
+        to_json(&file, &interner)
+    }
+
+    #[test]
+    fn test_file_structure() {
+        let json = json_of("package main\n\nimport \"fmt\"\n\nfunc add(a, b int) int {\n\treturn a + b\n}\n");
+        assert!(json.starts_with("{\"kind\":\"File\""));
+        assert!(json.contains("\"package\":{\"kind\":\"Ident\",\"span\":{\"start\":8,\"end\":12},\"name\":\"main\"}"));
+        assert!(json.contains("{\"kind\":\"Import\",\"span\":{\"start\":14,"));
+        assert!(json.contains("{\"kind\":\"FuncDecl\""));
+        assert!(json.contains("\"name\":\"add\""));
+        assert!(json.contains("\"path\":\"fmt\""));
+        assert!(json.contains("{\"kind\":\"ReturnStmt\""));
+        assert!(json.contains("\"kind\":\"BinaryExpr\",\"span\":{\"start\":61,"));
+    }
+
+    /// Source text covered by the span of the first `kind` node.
+    fn span_text<'a>(json: &str, source: &'a str, kind: &str) -> &'a str {
+        let needle = format!("{{\"kind\":\"{}\",\"span\":{{\"start\":", kind);
+        let at = json.find(&needle).unwrap_or_else(|| panic!("no {} node with a span", kind)) + needle.len();
+        let rest = &json[at..];
+        let start: usize = rest.split(',').next().unwrap().parse().unwrap();
+        let end_at = rest.find("\"end\":").unwrap() + "\"end\":".len();
+        let end: usize = rest[end_at..].split('}').next().unwrap().parse().unwrap();
+        &source[start..end]
+    }
+
+    #[test]
+    fn test_spans_map_to_source() {
+        let source = "package p\nvar x = \"hi\"\n";
+        assert_eq!(span_text(&json_of(source), source, "StringLit"), "\"hi\"");
+
+        let source = "package p\nfunc f(ch chan int) {\n\tselect {\n\tcase ch <- 1:\n\tcase v, ok := <-ch:\n\t\t_, _ = v, ok\n\t}\n}\n";
+        let json = json_of(source);
+        assert_eq!(span_text(&json, source, "SendComm"), "ch <- 1");
+        assert_eq!(span_text(&json, source, "RecvComm"), "v, ok := <-ch");
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        let json = json_of("package p\nvar s = \"a\\\"b\\n\"\n");
+        assert!(json.contains("\"value\":\"a\\\"b\\n\""));
+    }
+
+    #[test]
+    fn test_balanced_output() {
+        let json = json_of(
+            "package p\ntype T struct { x int }\nfunc (t *T) M(ch chan int) {\n\tfor i := range 3 { ch <- i }\n\tselect {\n\tcase v := <-ch:\n\t\t_ = v\n\tdefault:\n\t}\n}\n",
+        );
+        let mut depth = 0i32;
+        let mut in_str = false;
+        let mut escaped = false;
+        for c in json.chars() {
+            if in_str {
+                match (escaped, c) {
+                    (true, _) => escaped = false,
+                    (false, '\\') => escaped = true,
+                    (false, '"') => in_str = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_str = true,
+                '{' | '[' => depth += 1,
+                '}' | ']' => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0);
+        }
+        assert_eq!(depth, 0);
+        // Every node carries a span
+        for (at, _) in json.match_indices("{\"kind\":") {
+            let node = &json[at..];
+            let kind_end = node[9..].find('"').unwrap() + 10;
+            assert!(node[kind_end..].starts_with(",\"span\":"), "node without span: {}", &node[..kind_end]);
+        }
+        assert!(json.contains("\"kind\":\"RangeStmt\""));
+        assert!(json.contains("\"kind\":\"RecvComm\""));
+        assert!(json.contains("\"receiver\":{\"kind\":\"Receiver\""));
+    }
+}
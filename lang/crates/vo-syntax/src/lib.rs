@@ -13,6 +13,7 @@ pub mod ast;
 pub mod parser;
 pub mod errors;
 pub mod display;
+pub mod json;
 
 pub use token::{Token, TokenKind};
 pub use lexer::Lexer;
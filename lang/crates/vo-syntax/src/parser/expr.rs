@@ -63,23 +63,25 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expr_prec(&mut self, min_prec: Precedence) -> ParseResult<Expr> {
-        let mut left = self.parse_prefix_expr()?;
-        
-        loop {
-            let prec = Precedence::from_token(&self.current.kind);
-            if prec <= min_prec {
-                break;
-            }
+        self.nested(|p| {
+            let mut left = p.parse_prefix_expr()?;
             
-            // Check for composite literal - only if allowed
-            if self.current.kind == TokenKind::LBrace && !self.allow_composite_lit {
-                break;
+            loop {
+                let prec = Precedence::from_token(&p.current.kind);
+                if prec <= min_prec {
+                    break;
+                }
+                
+                // Check for composite literal - only if allowed
+                if p.current.kind == TokenKind::LBrace && !p.allow_composite_lit {
+                    break;
+                }
+                
+                left = p.parse_infix_expr(left, prec)?;
             }
             
-            left = self.parse_infix_expr(left, prec)?;
-        }
-        
-        Ok(left)
+            Ok(left)
+        })
     }
 
     fn parse_prefix_expr(&mut self) -> ParseResult<Expr> {
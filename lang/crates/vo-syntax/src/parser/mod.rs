@@ -25,6 +25,14 @@ use crate::token::{Token, TokenKind};
 /// Parse result type.
 pub type ParseResult<T> = Result<T, ()>;
 
+/// How deeply expressions, types and statements may nest. Deeper input is
+/// reported as an error rather than parsed, since the parser (and every later
+/// pass over the AST) recurses once per level and would overflow the stack.
+/// A level costs up to ~10KB of stack in debug builds and ~2.5KB in release,
+/// so parsing, checking and compiling code at this depth fits a 2MB thread
+/// in debug builds and the ~1MB wasm stack in release.
+pub const MAX_NESTING_DEPTH: u32 = 100;
+
 /// ID counters state for multi-file parsing.
 #[derive(Debug, Clone, Default)]
 pub struct IdState {
@@ -53,6 +61,11 @@ pub struct Parser<'a> {
     allow_composite_lit: bool,
     /// Next ID counters for expressions, type expressions, and identifiers.
     next_ids: IdState,
+    /// Start of the last reported error; a second error at the same token is
+    /// a cascade of the first and is dropped.
+    last_error_pos: Option<BytePos>,
+    /// Current nesting of expressions, types and statements.
+    depth: u32,
 }
 
 impl<'a> Parser<'a> {
@@ -75,6 +88,8 @@ impl<'a> Parser<'a> {
             diagnostics: DiagnosticSink::new(),
             allow_composite_lit: true,
             next_ids: IdState::default(),
+            last_error_pos: None,
+            depth: 0,
         }
     }
 
@@ -99,6 +114,8 @@ impl<'a> Parser<'a> {
             diagnostics: DiagnosticSink::new(),
             allow_composite_lit: true,
             next_ids: ids,
+            last_error_pos: None,
+            depth: 0,
         }
     }
 
@@ -114,6 +131,7 @@ impl<'a> Parser<'a> {
 
     /// Takes the diagnostics, leaving an empty sink.
     pub fn take_diagnostics(&mut self) -> DiagnosticSink {
+        self.diagnostics.extend(self.lexer.take_diagnostics());
         std::mem::take(&mut self.diagnostics)
     }
 
@@ -173,29 +191,48 @@ impl<'a> Parser<'a> {
         // Parse package clause
         let package = if self.at(TokenKind::Package) {
             self.advance();
-            let name = self.parse_ident()?;
-            self.expect_semi();
-            Some(name)
+            match self.parse_ident() {
+                Ok(name) => {
+                    self.expect_semi();
+                    Some(name)
+                }
+                Err(()) => {
+                    self.synchronize_to_decl();
+                    None
+                }
+            }
         } else {
             None
         };
 
-        // Parse imports
+        // Parse imports and top-level declarations
         let mut imports = Vec::new();
-        while self.at(TokenKind::Import) {
-            let parsed = self.parse_import_or_group()?;
-            imports.extend(parsed);
-        }
-
-        // Parse top-level declarations
         let mut decls = Vec::new();
         while !self.at_eof() {
             if self.eat(TokenKind::Semicolon) {
                 continue;
             }
+            if self.at(TokenKind::Import) {
+                if !decls.is_empty() {
+                    self.error("imports must appear before other declarations");
+                }
+                match self.parse_import_or_group() {
+                    Ok(parsed) => imports.extend(parsed),
+                    Err(()) => self.synchronize_to_decl(),
+                }
+                continue;
+            }
+            let before = self.current.span.start;
             match self.parse_top_level_decl() {
                 Ok(decl) => decls.push(decl),
-                Err(()) => self.synchronize_to_decl(),
+                Err(()) => {
+                    // A decl that fails on its first token (e.g. a stray
+                    // identifier) must still be skipped, or sync stops right there.
+                    if self.current.span.start == before {
+                        self.advance();
+                    }
+                    self.synchronize_to_decl();
+                }
             }
         }
 
@@ -300,6 +337,10 @@ impl<'a> Parser<'a> {
             &mut self.current,
             std::mem::replace(&mut self.peek, self.lexer.next_token()),
         );
+        // Lexical errors surface through the parser's sink, in source order
+        if !self.lexer.diagnostics().is_empty() {
+            self.diagnostics.extend(self.lexer.take_diagnostics());
+        }
         token
     }
 
@@ -372,15 +413,41 @@ impl<'a> Parser<'a> {
 
     fn error(&mut self, message: impl Into<String>) {
         let span = self.current.span;
-        self.diagnostics
-            .emit(SyntaxError::UnexpectedToken.at_with_message(span, message));
+        self.error_at(span, message);
     }
 
     fn error_at(&mut self, span: Span, message: impl Into<String>) {
+        // The lexer has already reported why this token is invalid
+        if self.last_error_pos == Some(span.start)
+            || (self.current.kind == TokenKind::Invalid && self.current.span == span)
+        {
+            return;
+        }
+        self.last_error_pos = Some(span.start);
         self.diagnostics
             .emit(SyntaxError::UnexpectedToken.at_with_message(span, message));
     }
 
+    /// Run `parse` one nesting level deeper. Past `MAX_NESTING_DEPTH` this
+    /// reports an error and skips the rest of the file, so unwinding doesn't
+    /// retry at every level on the way out.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            self.diagnostics.emit(SyntaxError::NestingTooDeep.at(self.current.span));
+            while !self.at_eof() {
+                self.advance();
+            }
+            // The enclosing blocks and parens all end at EOF now; don't
+            // report each of them as unclosed
+            self.last_error_pos = Some(self.current.span.start);
+            return Err(());
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
     fn error_expected(&mut self, expected: &str) {
         self.error(format!(
             "expected {}, found {}",
@@ -389,15 +456,21 @@ impl<'a> Parser<'a> {
         ));
     }
 
+    /// Skips to the next top-level declaration keyword. Keywords inside a
+    /// brace block (e.g. a `var` in the body of a broken func) are skipped
+    /// with the block, so they don't start a bogus declaration.
     fn synchronize_to_decl(&mut self) {
+        let mut depth = 0usize;
         while !self.at_eof() {
-            if self.at_any(&[
-                TokenKind::Var,
-                TokenKind::Const,
-                TokenKind::Type,
-                TokenKind::Func,
-            ]) {
-                return;
+            match self.current.kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth = depth.saturating_sub(1),
+                TokenKind::Import | TokenKind::Var | TokenKind::Const | TokenKind::Type | TokenKind::Func
+                    if depth == 0 =>
+                {
+                    return;
+                }
+                _ => {}
             }
             self.advance();
         }
@@ -606,6 +679,9 @@ fn parse_escape_char(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option
 }
 
 /// Parses source code and returns the AST.
+///
+/// Never panics: lexical and syntax errors, including nesting deeper than
+/// `MAX_NESTING_DEPTH`, are reported in the returned diagnostics.
 /// 
 /// # Arguments
 /// * `source` - The source code text
@@ -1498,6 +1574,37 @@ mod tests {
         assert!(diags.has_errors());
     }
 
+    #[test]
+    fn test_error_recovery_reports_each_decl() {
+        let (file, diags) = parse_str(
+            "package p\nfunc a() { x := }\nfunc b( {}\nfunc c() int { return 1 }\nvar v = \n",
+        );
+        assert_eq!(diags.error_count(), 3);
+        // `a` (recovered inside its body) and `c` survive.
+        assert_eq!(file.decls.len(), 2);
+    }
+
+    #[test]
+    fn test_error_recovery_skips_broken_body() {
+        // The `var` inside the unterminated signature's body must not
+        // start a new top-level declaration.
+        let (file, diags) = parse_str("package p\nfunc a(x int {\n\tvar y int\n\treturn\n}\nfunc b() {}\n");
+        assert_eq!(diags.error_count(), 1);
+        assert_eq!(file.decls.len(), 1);
+    }
+
+    #[test]
+    fn test_error_recovery_after_bad_import() {
+        let (file, diags) = parse_str("package p\nimport \"fmt\" x\nimport \"os\"\nfunc a() {}\n");
+        assert_eq!(diags.error_count(), 1);
+        assert_eq!(file.imports.len(), 2);
+        assert_eq!(file.decls.len(), 1);
+
+        let (file, diags) = parse_str("package p\nfunc a() {}\nimport \"os\"\n");
+        assert_eq!(diags.error_count(), 1);
+        assert_eq!(file.imports.len(), 1);
+    }
+
     // =========================================================================
     // Global position tests
     // =========================================================================
@@ -1769,4 +1876,89 @@ mod tests {
             _ => panic!("expected type decl"),
         }
     }
+
+    // =========================================================================
+    // Malformed input tests
+    // =========================================================================
+
+    /// Sources nested `n` levels deep in each way the grammar recurses.
+    fn nested_sources(n: usize) -> Vec<String> {
+        vec![
+            format!("var x = {}1{}", "(".repeat(n), ")".repeat(n)),
+            format!("var x = {}true", "!".repeat(n)),
+            format!("var x {}int", "[]".repeat(n)),
+            format!("var x {}int", "*".repeat(n)),
+            format!("var x {}int", "func() ".repeat(n)),
+            format!("var x = {}{}", "[]any{".repeat(n), "}".repeat(n)),
+            format!("func f() {{{}{}}}", "{".repeat(n), "}".repeat(n)),
+            format!("func f() {{{}{}}}", "if x {".repeat(n), "}".repeat(n)),
+        ]
+    }
+
+    // Both run on the default 2MB test thread
+
+    #[test]
+    fn test_nesting_within_limit() {
+        for source in nested_sources(MAX_NESTING_DEPTH as usize - 10) {
+            parse_ok(&source);
+        }
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        for source in nested_sources(100_000) {
+            let (_, diags) = parse_str(&source);
+            let deep: Vec<_> = diags.iter()
+                .filter(|d| d.code == Some(SyntaxError::NestingTooDeep.code()))
+                .collect();
+            assert_eq!(deep.len(), 1, "{}...", &source[..40]);
+            // Nothing cascades from the skipped input
+            assert_eq!(diags.len(), 1, "{}...", &source[..40]);
+        }
+    }
+
+    #[test]
+    fn test_long_else_if_chain() {
+        let arms = MAX_NESTING_DEPTH as usize + 50;
+        let mut source = String::from("func f(x int) int {\n");
+        for i in 0..arms {
+            let prefix = if i == 0 { "if" } else { "} else if" };
+            source.push_str(&format!("{} x == {} {{\nreturn {}\n", prefix, i, i));
+        }
+        source.push_str("} else {\nreturn -1\n}\n}");
+        let file = parse_ok(&source);
+
+        let Decl::Func(func) = &file.decls[0] else { panic!("expected func decl") };
+        let mut stmt = &func.body.as_ref().unwrap().stmts[0];
+        let mut count = 0;
+        while let StmtKind::If(if_stmt) = &stmt.kind {
+            count += 1;
+            stmt = if_stmt.else_.as_deref().expect("chain ends in an else block");
+        }
+        assert_eq!(count, arms);
+        assert!(matches!(stmt.kind, StmtKind::Block(_)));
+    }
+
+    #[test]
+    fn test_malformed_input_reports_errors() {
+        let sources = [
+            "package main\nvar s = \"abc",
+            "package main\nvar s = `abc",
+            "package main\nvar r = '",
+            "package main\nvar r = '\\",
+            "package main\n/* never closed",
+            "package main\nvar s = \"\\u12\"",
+            "package main\nvar x = 0x",
+            "package main\nvar x = 1e+",
+            "package main\nfunc f() { x := \u{FFFD}\u{200B} }",
+            "\0\u{7f}@#$",
+            "func",
+            "import",
+            "(",
+        ];
+        for source in sources {
+            let (_, diags) = parse_str(source);
+            assert!(diags.has_errors(), "{:?}", source);
+        }
+    }
 }
@@ -9,6 +9,10 @@ use crate::ast::Ident;
 impl<'a> Parser<'a> {
     /// Parses a statement.
     pub fn parse_stmt(&mut self) -> ParseResult<Stmt> {
+        self.nested(Self::parse_stmt_inner)
+    }
+
+    fn parse_stmt_inner(&mut self) -> ParseResult<Stmt> {
         let start = self.current.span.start;
         
         let kind = match self.current.kind {
@@ -211,6 +215,37 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_if_stmt(&mut self) -> ParseResult<StmtKind> {
+        // `else if` chains are parsed in a loop rather than by recursing, so a
+        // long chain doesn't count against the nesting limit.
+        let mut arms = vec![(self.current.span.start, self.parse_if_arm()?)];
+        let mut else_ = None;
+        while self.eat(TokenKind::Else) {
+            if self.at(TokenKind::If) {
+                arms.push((self.current.span.start, self.parse_if_arm()?));
+            } else {
+                let else_block = self.parse_block()?;
+                else_ = Some(Box::new(Stmt {
+                    kind: StmtKind::Block(else_block.clone()),
+                    span: else_block.span,
+                }));
+                break;
+            }
+        }
+        
+        // Fold the arms back into nested IfStmts; each `else if` ends where the chain does
+        let end = self.current.span.start;
+        let (_, first) = arms.remove(0);
+        for (start, arm) in arms.into_iter().rev() {
+            else_ = Some(Box::new(Stmt {
+                kind: StmtKind::If(IfStmt { else_, ..arm }),
+                span: Span::new(start, end),
+            }));
+        }
+        Ok(StmtKind::If(IfStmt { else_, ..first }))
+    }
+
+    /// Parses `if [init;] cond { ... }`, leaving `else_` empty.
+    fn parse_if_arm(&mut self) -> ParseResult<IfStmt> {
         self.expect(TokenKind::If)?;
         
         // Disable composite literals in condition
@@ -239,23 +274,7 @@ impl<'a> Parser<'a> {
         self.allow_composite_lit = saved;
         
         let then = self.parse_block()?;
-        
-        let else_ = if self.eat(TokenKind::Else) {
-            if self.at(TokenKind::If) {
-                let else_if = self.parse_stmt()?;
-                Some(Box::new(else_if))
-            } else {
-                let else_block = self.parse_block()?;
-                Some(Box::new(Stmt {
-                    kind: StmtKind::Block(else_block.clone()),
-                    span: else_block.span,
-                }))
-            }
-        } else {
-            None
-        };
-        
-        Ok(StmtKind::If(IfStmt { init, cond, then, else_ }))
+        Ok(IfStmt { init, cond, then, else_: None })
     }
 
     fn parse_for_stmt(&mut self) -> ParseResult<StmtKind> {
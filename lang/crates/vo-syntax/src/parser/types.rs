@@ -8,6 +8,10 @@ use vo_common::span::Span;
 impl<'a> Parser<'a> {
     /// Parses a type expression.
     pub fn parse_type(&mut self) -> ParseResult<TypeExpr> {
+        self.nested(Self::parse_type_inner)
+    }
+
+    fn parse_type_inner(&mut self) -> ParseResult<TypeExpr> {
         let start = self.current.span.start;
         
         let kind = match self.current.kind {
@@ -21,9 +21,10 @@ pub fn exec_call(
     module: &Module,
 ) -> ExecResult {
     let func_id = (inst.a as u32) | ((inst.flags as u32) << 16);
-    let arg_start = inst.b as usize;
     let arg_slots = (inst.c >> 8) as usize;
     let ret_slots = (inst.c & 0xFF) as usize;
+    // Results go to the start of the buffer, args follow them
+    let arg_start = inst.b as usize + ret_slots;
 
     let func = &module.functions[func_id as usize];
 
@@ -63,9 +64,9 @@ pub fn exec_call_closure(
     let caller_bp = frames.last().map_or(0, |f| f.bp);
     let closure_ref = stack[caller_bp + inst.a as usize] as GcRef;
     let func_id = closure::func_id(closure_ref);
-    let arg_start = inst.b as usize;
     let arg_slots = (inst.c >> 8) as usize;
     let ret_slots = (inst.c & 0xFF) as u16;
+    let arg_start = inst.b as usize + ret_slots as usize;
 
     let func = &module.functions[func_id as usize];
 
@@ -114,6 +115,7 @@ pub fn exec_call_iface(
 ) -> ExecResult {
     let arg_slots = (inst.c >> 8) as usize;
     let ret_slots = (inst.c & 0xFF) as usize;
+    let arg_start = inst.b as usize + ret_slots;
     let method_idx = inst.flags as usize;
 
     let caller_bp = frames.last().map_or(0, |f| f.bp);
@@ -137,7 +139,7 @@ pub fn exec_call_iface(
     
     // Copy args directly (no Vec allocation)
     for i in 0..arg_slots {
-        stack[new_bp + recv_slots + i] = stack[caller_bp + inst.b as usize + i];
+        stack[new_bp + recv_slots + i] = stack[caller_bp + arg_start + i];
     }
     
     // Push frame
@@ -10,6 +10,7 @@ use vo_runtime::gc::{Gc, GcRef};
 use vo_runtime::objects::channel::{self, RecvResult, SendResult};
 use vo_runtime::objects::queue_state;
 
+use crate::fiber::SelectCaseKind;
 use crate::instruction::Instruction;
 
 pub enum ChanResult {
@@ -48,8 +49,22 @@ pub fn exec_chan_new(stack: &mut [u64], bp: usize, inst: &Instruction, gc: &mut
     Ok(())
 }
 
-pub fn exec_chan_send(stack: &[u64], bp: usize, fiber_id: u32, inst: &Instruction) -> ChanResult {
+/// `resumed` is set when the fiber blocked on this send and has been woken:
+/// its value was handed over with the registration, so the send is not redone.
+/// `claim` offers a parked receiver the value, see `claim_waiter`.
+pub fn exec_chan_send(
+    stack: &[u64],
+    bp: usize,
+    fiber_id: u32,
+    inst: &Instruction,
+    resumed: bool,
+    mut claim: impl FnMut(u64, GcRef, SelectCaseKind) -> bool,
+) -> ChanResult {
     let ch = stack[bp + inst.a as usize] as GcRef;
+    if resumed {
+        let state = channel::get_state(ch);
+        return if state.is_waiting_sender(&(fiber_id as u64)) { ChanResult::Yield } else { ChanResult::Continue };
+    }
     let elem_slots = inst.flags as usize;
     let src_start = bp + inst.b as usize;
 
@@ -57,7 +72,7 @@ pub fn exec_chan_send(stack: &[u64], bp: usize, fiber_id: u32, inst: &Instructio
     let cap = queue_state::capacity(ch);
     let state = channel::get_state(ch);
 
-    match state.try_send(value, cap) {
+    match state.try_send_claim(value, cap, |&w| claim(w, ch, SelectCaseKind::Recv)) {
         SendResult::DirectSend(receiver_id) => ChanResult::Wake(receiver_id as u32),
         SendResult::Buffered => ChanResult::Continue,
         SendResult::WouldBlock(value) => {
@@ -68,14 +83,21 @@ pub fn exec_chan_send(stack: &[u64], bp: usize, fiber_id: u32, inst: &Instructio
     }
 }
 
-pub fn exec_chan_recv(stack: &mut [u64], bp: usize, fiber_id: u32, inst: &Instruction) -> ChanResult {
+/// `claim` offers a parked sender the receive, see `claim_waiter`.
+pub fn exec_chan_recv(
+    stack: &mut [u64],
+    bp: usize,
+    fiber_id: u32,
+    inst: &Instruction,
+    mut claim: impl FnMut(u64, GcRef, SelectCaseKind) -> bool,
+) -> ChanResult {
     let ch = stack[bp + inst.b as usize] as GcRef;
     let elem_slots = ((inst.flags >> 1) & 0x7F) as usize;
     let has_ok = (inst.flags & 1) != 0;
     let dst_start = bp + inst.a as usize;
 
     let state = channel::get_state(ch);
-    let (result, value) = state.try_recv();
+    let (result, value) = state.try_recv_claim(|&w| claim(w, ch, SelectCaseKind::Send));
 
     match result {
         RecvResult::Success(woke_sender) => {
@@ -41,6 +41,7 @@ pub fn exec_call_extern(
     well_known: &WellKnownTypes,
     program_args: &[String],
     sentinel_errors: &mut SentinelErrorCache,
+    resume: &mut Option<u64>,
 ) -> ExecResult {
     // CallExtern: a=dst, b=extern_id, c=args_start, flags=arg_count
     let extern_id = inst.b as u32;
@@ -77,12 +78,15 @@ pub fn exec_call_extern(
         well_known,
         program_args,
         sentinel_errors,
+        resume,
     );
 
     match result {
         ExternResult::Ok => ExecResult::Continue,
         ExternResult::Yield => ExecResult::Yield,
         ExternResult::Block => ExecResult::Block,
+        // The panic value is already on the fiber
+        ExternResult::Repanic => ExecResult::Panic,
         ExternResult::Panic(msg) => {
             // Enhance error message with function name if it's a "not found" error
             if msg.contains("not found") || msg.contains("not registered") {
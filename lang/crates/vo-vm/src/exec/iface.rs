@@ -194,18 +194,12 @@ pub fn exec_iface_assert(
                 }
             }
         } else if src_vk == ValueKind::Array {
-            // Concrete type assertion for array: copy elements from GcRef
-            // Array layout: [ArrayHeader(2 slots)][elements...]
+            // Concrete type assertion for array: unpack elements from the heap array
             use vo_runtime::objects::array;
-            let gc_ref = slot1 as GcRef;
             let slots = target_slots.max(1);
             if slot1 != 0 {
-                // Copy data from after ArrayHeader (data_ptr_bytes skips ArrayHeader)
-                let data_ptr = array::data_ptr_bytes(gc_ref) as *const u64;
-                for i in 0..slots {
-                    let val = unsafe { *data_ptr.add(i as usize) };
-                    stack[bp + inst.a as usize + i as usize] = val;
-                }
+                let dst = bp + inst.a as usize;
+                array::read_slots(slot1 as GcRef, &mut stack[dst..dst + slots as usize]);
             } else {
                 for i in 0..slots {
                     stack[bp + inst.a as usize + i as usize] = 0;
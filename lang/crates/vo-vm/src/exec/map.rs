@@ -140,9 +140,9 @@ pub fn exec_map_len(stack: &mut [u64], bp: usize, inst: &Instruction) {
 /// MapIterInit: Initialize map iterator
 /// a=iter_slot (7 slots), b=map_reg
 #[inline]
-pub fn exec_map_iter_init(stack: &mut [u64], bp: usize, inst: &Instruction) {
+pub fn exec_map_iter_init(stack: &mut [u64], bp: usize, inst: &Instruction, gc: &mut Gc, sorted: bool) {
     let m = stack[bp + inst.b as usize] as GcRef;
-    let iter = map::iter_init(m);
+    let iter = if sorted { map::iter_init_sorted(gc, m) } else { map::iter_init(m) };
     
     let iter_slot = bp + inst.a as usize;
     const SLOTS: usize = map::MAP_ITER_SLOTS;
@@ -65,12 +65,12 @@ pub fn exec_ptr_get_n(stack: &mut [u64], bp: usize, inst: &Instruction) -> bool
 }
 
 /// PtrSetN: a=ptr, b=offset, c=src_start, flags=count
-/// Note: PtrSetN has no barrier support. For structs containing GcRefs,
-/// codegen emits individual PtrSet instructions (with barrier flags) for
-/// each slot using emit_ptr_set_with_slot_types().
+/// Codegen mostly emits individual PtrSet instructions (with barrier flags)
+/// for structs containing GcRefs, but heap-allocated return values are
+/// stored with PtrSetN, so it always takes the write barrier.
 /// Returns false if ptr is nil (caller should trigger panic)
 #[inline]
-pub fn exec_ptr_set_n(stack: &[u64], bp: usize, inst: &Instruction) -> bool {
+pub fn exec_ptr_set_n(stack: &[u64], bp: usize, inst: &Instruction, gc: &mut Gc) -> bool {
     let ptr = stack[bp + inst.a as usize] as GcRef;
     if ptr.is_null() {
         return false;
@@ -83,5 +83,6 @@ pub fn exec_ptr_set_n(stack: &[u64], bp: usize, inst: &Instruction) -> bool {
         let val = stack[src_start + i];
         unsafe { Gc::write_slot(ptr, offset + i, val) };
     }
+    gc.write_barrier(ptr, core::ptr::null_mut());
     true
 }
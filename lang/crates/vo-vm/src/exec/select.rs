@@ -7,14 +7,17 @@ use alloc::vec::Vec;
 
 use vo_runtime::gc::GcRef;
 use vo_runtime::objects::{channel, queue_state};
+use vo_runtime::objects::channel::{RecvResult, SendResult};
 
-use crate::fiber::{SelectCase, SelectCaseKind, SelectState};
+use crate::fiber::{Fiber, SelectCase, SelectCaseKind, SelectState};
 use crate::instruction::Instruction;
 
 /// Result of select execution (analogous to ChanResult)
 pub enum SelectResult {
     /// Select completed successfully
     Continue,
+    /// Select completed and made the fiber parked on the other end runnable
+    Wake(u32),
     /// No case ready, need to block and retry
     Block,
     /// Send on closed channel - panic
@@ -29,6 +32,7 @@ pub fn exec_select_begin(select_state: &mut Option<SelectState>, inst: &Instruct
         cases: Vec::with_capacity(case_count),
         has_default,
         woken_index: None,
+        parked: Vec::new(),
     });
 }
 
@@ -120,76 +124,153 @@ fn check_ready_case(stack: &[u64], bp: usize, state: &SelectState) -> SelectChec
     }
 }
 
-pub fn exec_select_exec(stack: &mut [u64], bp: usize, select_state: &mut Option<SelectState>, inst: &Instruction) -> SelectResult {
+/// `claim` offers a fiber parked on a channel the other end of a case,
+/// see `claim_waiter`.
+pub fn exec_select_exec(
+    stack: &mut [u64],
+    bp: usize,
+    fiber_id: u32,
+    select_state: &mut Option<SelectState>,
+    inst: &Instruction,
+    mut claim: impl FnMut(u64, GcRef, SelectCaseKind) -> bool,
+) -> SelectResult {
     let state = select_state.as_mut().expect("no active select");
 
-    // If woken by another goroutine, use that index
+    // A case completed while parked; select_done already withdrew us and
+    // stored any received value
     if let Some(idx) = state.woken_index.take() {
         stack[bp + inst.a as usize] = idx as u64;
         *select_state = None;
         return SelectResult::Continue;
     }
 
+    // Woken without a completed case (a channel closed): withdraw, look again
+    let waiter = fiber_id as u64;
+    withdraw(&mut state.parked, waiter);
+
     // Phase 1: Check which case can proceed (no mutation)
     let result = check_ready_case(stack, bp, state);
     
     // Phase 2: Execute the selected case
-    match result {
+    let woke = match result {
         SelectCheckResult::Send { idx, ch, elem_slots, val_reg } => {
             let chan_state = channel::get_state(ch);
-            // Send on closed channel panics (Go semantics)
-            if chan_state.closed {
-                *select_state = None;
-                return SelectResult::SendOnClosed;
-            }
             let val_start = bp + val_reg as usize;
             let value: Box<[u64]> = stack[val_start..val_start + elem_slots].into();
-            chan_state.buffer.push_back(value);
-            
+            let claim = |&w: &u64| claim(w, ch, SelectCaseKind::Recv);
+            let woke = match chan_state.try_send_claim(value, queue_state::capacity(ch), claim) {
+                SendResult::DirectSend(receiver) => Some(receiver),
+                SendResult::Buffered => None,
+                // Send on closed channel panics (Go semantics)
+                SendResult::Closed => {
+                    *select_state = None;
+                    return SelectResult::SendOnClosed;
+                }
+                // Shouldn't happen - we checked in phase 1, but handle gracefully
+                SendResult::WouldBlock(_) => return SelectResult::Block,
+            };
             stack[bp + inst.a as usize] = idx as u64;
-            *select_state = None;
-            SelectResult::Continue
+            woke
         }
         SelectCheckResult::Recv { idx, ch, elem_slots, val_reg, has_ok } => {
             let chan_state = channel::get_state(ch);
-            let dst_start = bp + val_reg as usize;
-            
-            let (value, ok) = if let Some(val) = chan_state.buffer.pop_front() {
-                (Some(val), true)
-            } else if let Some((_, val)) = chan_state.waiting_senders.pop_front() {
-                (Some(val), true)
-            } else if chan_state.closed {
-                (None, false)
-            } else {
+            let claim = |&w: &u64| claim(w, ch, SelectCaseKind::Send);
+            let (woke, value) = match chan_state.try_recv_claim(claim) {
+                (RecvResult::Success(woke), value) => (woke, value),
+                (RecvResult::Closed, _) => (None, None),
                 // Shouldn't happen - we checked in phase 1, but handle gracefully
-                return SelectResult::Block;
+                (RecvResult::WouldBlock, _) => return SelectResult::Block,
             };
-            
-            // Copy value to stack (or zero-fill for closed channel)
-            if let Some(val) = value {
-                for (i, &v) in val.iter().enumerate().take(elem_slots) {
-                    stack[dst_start + i] = v;
-                }
-            } else {
-                for i in 0..elem_slots {
-                    stack[dst_start + i] = 0;
-                }
-            }
-            if has_ok {
-                stack[dst_start + elem_slots] = ok as u64;
-            }
-            
+            store_recv(stack, bp + val_reg as usize, elem_slots, has_ok, value);
             stack[bp + inst.a as usize] = idx as u64;
-            *select_state = None;
-            SelectResult::Continue
+            woke
         }
         SelectCheckResult::Default => {
             stack[bp + inst.a as usize] = u64::MAX;
-            *select_state = None;
-            SelectResult::Continue
+            None
         }
         SelectCheckResult::None => {
-            SelectResult::Block
+            // Park on every case so a matching operation on any channel wakes us
+            for case in &state.cases {
+                let ch = stack[bp + case.chan_reg as usize] as GcRef;
+                state.parked.push(ch);
+                if ch.is_null() {
+                    continue;
+                }
+                let chan_state = channel::get_state(ch);
+                match case.kind {
+                    SelectCaseKind::Send => {
+                        let val_start = bp + case.val_reg as usize;
+                        chan_state.register_sender(waiter, stack[val_start..val_start + case.elem_slots as usize].into());
+                    }
+                    SelectCaseKind::Recv => chan_state.register_receiver(waiter),
+                }
+            }
+            return SelectResult::Block;
+        }
+    };
+    *select_state = None;
+    match woke {
+        Some(id) => SelectResult::Wake(id as u32),
+        None => SelectResult::Continue,
+    }
+}
+
+/// Ask `fiber`, parked on `ch`, to take the other end of an operation there:
+/// `kind` is the case a select must have on `ch` (Recv for a sender, Send for
+/// a receiver). A plain waiter always accepts. A select accepts only its
+/// first completed case, which it records; it must then be finished with
+/// `select_done` before anything else touches its channels.
+pub fn claim_waiter(fiber: &mut Fiber, ch: GcRef, kind: SelectCaseKind) -> bool {
+    let Some(state) = fiber.select_state.as_mut() else { return true };
+    if state.woken_index.is_some() {
+        return false;
+    }
+    let idx = state.cases.iter().zip(&state.parked).position(|(case, &c)| case.kind == kind && c == ch);
+    state.woken_index = idx;
+    idx.is_some()
+}
+
+/// Finish a select whose case `claim_waiter` accepted (Go's selectDone):
+/// withdraw it from every channel it is still parked on, and move a value
+/// sent to a receive case straight into the case's registers, so it is not
+/// left for another receiver in the channel's buffer. No-op for other fibers.
+pub fn select_done(fiber: &mut Fiber, waiter: u64) {
+    let Some(state) = fiber.select_state.as_mut() else { return };
+    let Some(idx) = state.woken_index else { return };
+    let case = &state.cases[idx];
+    if case.kind == SelectCaseKind::Recv {
+        // try_send_claim queued the value last, for the receiver it woke
+        let value = channel::get_state(state.parked[idx]).buffer.pop_back();
+        let bp = fiber.frames.last().expect("parked select has a frame").bp;
+        store_recv(&mut fiber.stack, bp + case.val_reg as usize, case.elem_slots as usize, case.has_ok, value);
+    }
+    withdraw(&mut state.parked, waiter);
+}
+
+/// Remove `waiter` from every channel in `parked`, dropping values it was
+/// offering to send, and clear `parked`.
+fn withdraw(parked: &mut Vec<GcRef>, waiter: u64) {
+    for ch in parked.drain(..) {
+        if !ch.is_null() {
+            channel::get_state(ch).remove_waiter(&waiter);
         }
     }
 }
+
+/// Store a received value, or the zero value if the channel is closed.
+fn store_recv(stack: &mut [u64], dst_start: usize, elem_slots: usize, has_ok: bool, value: Option<Box<[u64]>>) {
+    let ok = value.is_some();
+    if let Some(val) = value {
+        for (i, &v) in val.iter().enumerate().take(elem_slots) {
+            stack[dst_start + i] = v;
+        }
+    } else {
+        for i in 0..elem_slots {
+            stack[dst_start + i] = 0;
+        }
+    }
+    if has_ok {
+        stack[dst_start + elem_slots] = ok as u64;
+    }
+}
@@ -6,7 +6,7 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 use vo_runtime::gc::GcRef;
-use vo_runtime::InterfaceSlot;
+use vo_runtime::{InterfaceSlot, PanicKind, ValueKind};
 
 #[derive(Debug, Clone, Copy)]
 pub struct CallFrame {
@@ -170,7 +170,10 @@ pub struct SelectCase {
 pub struct SelectState {
     pub cases: Vec<SelectCase>,
     pub has_default: bool,
+    /// Case another fiber completed while the select was parked.
     pub woken_index: Option<usize>,
+    /// Channel of each case while the select is parked on them, else empty.
+    pub parked: Vec<GcRef>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -185,8 +188,9 @@ pub enum FiberStatus {
 pub enum PanicState {
     /// Recoverable panic (user code panic, runtime errors like bounds check).
     /// Can be caught by recover() in a defer.
-    /// Stores full interface{} value as InterfaceSlot.
-    Recoverable(InterfaceSlot),
+    /// Stores full interface{} value as InterfaceSlot, and for runtime errors
+    /// raised by the VM or JIT code, which one it is.
+    Recoverable(InterfaceSlot, Option<PanicKind>),
     /// Fatal panic (internal VM/JIT errors that cannot be recovered).
     /// Examples: blocking operation in JIT, unsupported operation.
     Fatal,
@@ -197,17 +201,22 @@ impl PanicState {
     pub fn message(&self) -> String {
         match self {
             PanicState::Fatal => "fatal error".to_string(),
-            PanicState::Recoverable(val) => {
+            PanicState::Recoverable(val, _) => {
                 if val.is_string() && !val.as_ref().is_null() {
                     return val.as_str().to_string();
                 }
+                // Scalars print their value, as in Go's "panic: 42"
+                let vk = val.value_kind();
+                if vk == ValueKind::Bool || vk.is_numeric() {
+                    return vo_runtime::builtins::format::format_interface(val.slot0, val.slot1);
+                }
                 "panic".to_string()
             }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Fiber {
     pub id: u32,
     pub status: FiberStatus,
@@ -217,9 +226,20 @@ pub struct Fiber {
     pub unwinding: Option<UnwindingState>,
     pub select_state: Option<SelectState>,
     pub panic_state: Option<PanicState>,
+    /// (func_id, pc) of the instruction that raised the current panic.
+    pub panic_loc: Option<(u32, u32)>,
     /// Incremented each time a new panic starts. Used to determine which defers can recover.
     /// A defer registered at generation N can only recover panics with generation > N.
     pub panic_generation: u64,
+    /// Continuation state of an extern suspended by `yield_and_resume`.
+    /// Set only while the fiber is parked on that CallExtern.
+    pub extern_resume: Option<u64>,
+    /// Calls made on behalf of this fiber that have no frame on its stack:
+    /// JIT->VM calls in progress, plus the caller's depth for a trampoline fiber.
+    pub call_depth: usize,
+    /// The last channel operation or select blocked, so only another fiber
+    /// can wake it. Stays set until the operation completes when retried.
+    pub chan_blocked: bool,
 }
 
 impl Fiber {
@@ -233,7 +253,11 @@ impl Fiber {
             unwinding: None,
             select_state: None,
             panic_state: None,
+            panic_loc: None,
             panic_generation: 0,
+            extern_resume: None,
+            call_depth: 0,
+            chan_blocked: false,
         }
     }
     
@@ -246,14 +270,24 @@ impl Fiber {
         self.unwinding = None;
         self.select_state = None;
         self.panic_state = None;
+        self.panic_loc = None;
         self.panic_generation = 0;
+        self.extern_resume = None;
+        self.call_depth = 0;
+        self.chan_blocked = false;
+    }
+
+    /// Vo call depth, counting calls that run outside this fiber's frames.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.call_depth + self.frames.len()
     }
     
     /// Check if current panic is recoverable and return the interface{} value if so.
     /// Used by recover() to consume the panic value.
     pub fn take_recoverable_panic(&mut self) -> Option<InterfaceSlot> {
         match self.panic_state.take() {
-            Some(PanicState::Recoverable(val)) => Some(val),
+            Some(PanicState::Recoverable(val, _)) => Some(val),
             other => {
                 self.panic_state = other; // Put it back if not recoverable
                 None
@@ -264,13 +298,31 @@ impl Fiber {
     /// Set a fatal (non-recoverable) panic.
     pub fn set_fatal_panic(&mut self) {
         self.panic_state = Some(PanicState::Fatal);
+        self.panic_loc = self.faulting_loc();
     }
     
     /// Set a recoverable panic with full interface{} value (InterfaceSlot).
     /// Also increments panic_generation so we can track which defers can recover.
     pub fn set_recoverable_panic(&mut self, msg: InterfaceSlot) {
+        self.set_panic(msg, None);
+    }
+    
+    /// Set a recoverable panic for a runtime error of `kind`.
+    pub fn set_runtime_panic(&mut self, msg: InterfaceSlot, kind: PanicKind) {
+        self.set_panic(msg, Some(kind));
+    }
+    
+    fn set_panic(&mut self, msg: InterfaceSlot, kind: Option<PanicKind>) {
         self.panic_generation += 1;
-        self.panic_state = Some(PanicState::Recoverable(msg));
+        self.panic_state = Some(PanicState::Recoverable(msg, kind));
+        self.panic_loc = self.faulting_loc();
+    }
+    
+    /// The instruction the top frame is executing (its pc has already advanced).
+    /// For a panic raised in JIT code this is the call into it, until the
+    /// caller sets the exact location.
+    fn faulting_loc(&self) -> Option<(u32, u32)> {
+        self.frames.last().map(|f| (f.func_id, f.pc.saturating_sub(1) as u32))
     }
     
     /// Get panic message for error reporting.
@@ -278,6 +330,14 @@ impl Fiber {
         self.panic_state.as_ref().map(|s| s.message())
     }
     
+    /// The runtime error the current panic is, if the VM or JIT code raised it.
+    pub fn panic_kind(&self) -> Option<PanicKind> {
+        match self.panic_state {
+            Some(PanicState::Recoverable(_, kind)) => kind,
+            _ => None,
+        }
+    }
+    
     /// Check if we're in panic unwinding mode AND directly in the defer function
     /// (not in a nested call from the defer function).
     /// Per Go semantics, recover() only works when called directly from defer.
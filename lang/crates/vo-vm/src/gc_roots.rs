@@ -2,11 +2,19 @@
 
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::rc::Weak;
+#[cfg(feature = "std")]
+use std::rc::Weak;
 
-use vo_runtime::gc::{scan_slots_by_types, Gc, GcRef};
+use vo_runtime::gc::{scan_slots_by_types, Gc, GcRef, HeapStats};
+use vo_runtime::gc_types::{finalize_object, scan_object};
+use vo_runtime::{SentinelErrorCache, SlotType};
 
-use crate::bytecode::{FunctionDef, GlobalDef};
+use crate::bytecode::{FunctionDef, GlobalDef, Module};
 use crate::fiber::{DeferEntry, Fiber, PanicState};
+use crate::instruction::Opcode;
+use crate::snapshot::HeapImage;
 use crate::vm::Vm;
 
 /// Scan a slice of raw GcRefs.
@@ -31,18 +39,148 @@ fn scan_defer_entry(gc: &mut Gc, entry: &DeferEntry) {
     }
 }
 
+/// Everything the collector treats as a root, borrowed alongside the heap.
+struct Roots<'a> {
+    module: &'a Module,
+    globals: &'a [u64],
+    fibers: &'a [Box<Fiber>],
+    trampoline_fibers: &'a [Box<Fiber>],
+    #[cfg(feature = "jit")]
+    jit_frames: &'a [vo_runtime::jit_api::JitFrameRoot],
+    sentinel_errors: &'a SentinelErrorCache,
+    cancel_root: GcRef,
+    snapshots: &'a [Weak<HeapImage>],
+}
+
+impl Roots<'_> {
+    fn scan(&self, gc: &mut Gc) {
+        let module = self.module;
+        scan_globals(gc, self.globals, &module.globals);
+        scan_fibers(gc, self.fibers, &module.functions);
+        // Also scan trampoline fibers (used for JIT->VM calls)
+        scan_fibers(gc, self.trampoline_fibers, &module.functions);
+        // Locals of JIT frames live on the native stack, outside any fiber
+        #[cfg(feature = "jit")]
+        for frame in self.jit_frames {
+            let func = &module.functions[frame.func_id as usize];
+            let locals = unsafe { core::slice::from_raw_parts(frame.locals, func.local_slots as usize) };
+            scan_slots_by_types(gc, locals, &func.slot_types);
+        }
+        for (slot0, slot1) in self.sentinel_errors.iter() {
+            scan_slots_by_types(gc, &[slot0, slot1], &[SlotType::Interface0, SlotType::Interface1]);
+        }
+        if !self.cancel_root.is_null() {
+            gc.mark_gray(self.cancel_root);
+        }
+        // Restoring a snapshot writes its objects back in place
+        for heap in self.snapshots.iter().filter_map(Weak::upgrade) {
+            for obj in heap.objects() {
+                gc.mark_gray(obj);
+            }
+        }
+    }
+}
+
 impl Vm {
+    /// Split the VM into the heap and its roots.
+    fn gc_and_roots(&mut self) -> Option<(&mut Gc, Roots<'_>)> {
+        let roots = Roots {
+            module: self.module.as_ref()?,
+            globals: &self.state.globals,
+            fibers: &self.scheduler.fibers,
+            trampoline_fibers: &self.scheduler.trampoline_fibers,
+            #[cfg(feature = "jit")]
+            jit_frames: &self.state.jit_frames,
+            sentinel_errors: &self.state.sentinel_errors,
+            cancel_root: self.state.cancel_root,
+            snapshots: &self.state.snapshots,
+        };
+        Some((&mut self.state.gc, roots))
+    }
+
     pub fn scan_roots(&mut self) {
-        if self.module.is_none() {
+        if let Some((gc, roots)) = self.gc_and_roots() {
+            roots.scan(gc);
+        }
+    }
+
+    /// Run a full stop-the-world collection.
+    ///
+    /// Safe to call from an extern: every live reference is reachable from
+    /// globals, fiber stacks, or registered JIT frames.
+    pub fn collect_garbage(&mut self) {
+        let Some((gc, roots)) = self.gc_and_roots() else { return };
+        let metas = &roots.module.struct_metas;
+        // A full collection assumes no incremental cycle is half done
+        gc.complete_cycle(|gc| roots.scan(gc), |gc, obj| scan_object(gc, obj, metas), finalize_object);
+        roots.scan(gc);
+        gc.collect(|gc, obj| scan_object(gc, obj, metas), finalize_object);
+    }
+
+    /// Run an in-progress incremental cycle to completion.
+    pub(crate) fn gc_complete_cycle(&mut self) {
+        let Some((gc, roots)) = self.gc_and_roots() else { return };
+        let metas = &roots.module.struct_metas;
+        gc.complete_cycle(|gc| roots.scan(gc), |gc, obj| scan_object(gc, obj, metas), finalize_object);
+    }
+
+    /// Collect incrementally: bounded steps at safepoints instead of never
+    /// collecting unless `collect_garbage` is called. Steps run between
+    /// scheduler time slices and at JIT safepoints (loop back-edges, calls).
+    pub fn set_incremental_gc(&mut self, on: bool) {
+        self.state.gc.set_incremental(on);
+    }
+
+    /// Heap usage so far: bytes allocated, objects on the heap, collections run.
+    pub fn heap_stats(&self) -> HeapStats {
+        self.state.gc.heap_stats()
+    }
+
+    /// Run one incremental GC step if one is due. Must only be called where
+    /// every live reference is rooted; extern calls hold references in Rust
+    /// locals, so no step runs while one is in progress.
+    pub fn gc_step(&mut self) {
+        if self.state.extern_calls > 0 || !self.state.gc.step_pending() {
+            return;
+        }
+        #[cfg(debug_assertions)]
+        if self.state.gc.take_forced_collect() {
+            self.collect_garbage();
             return;
         }
+        let Some((gc, roots)) = self.gc_and_roots() else { return };
+        let metas = &roots.module.struct_metas;
+        gc.step(|gc| roots.scan(gc), |gc, obj| scan_object(gc, obj, metas), finalize_object);
+    }
 
-        let module = self.module.as_ref().unwrap();
-        scan_globals(&mut self.state.gc, &self.state.globals, &module.globals);
-        scan_fibers(&mut self.state.gc, &self.scheduler.fibers, &module.functions);
-        // Also scan trampoline fibers (used for JIT->VM calls)
-        scan_fibers(&mut self.state.gc, &self.scheduler.trampoline_fibers, &module.functions);
+    /// Finish marking before a call to extern `extern_id`. Externs write
+    /// into heap objects without barriers, which is only safe outside the
+    /// marking phase; one never passed a reference cannot reach any.
+    pub(crate) fn gc_before_extern(&mut self, extern_id: u32) {
+        if !self.state.gc.is_marking() || self.state.extern_ref_args.get(extern_id as usize) == Some(&false) {
+            return;
+        }
+        let Some((gc, roots)) = self.gc_and_roots() else { return };
+        let metas = &roots.module.struct_metas;
+        gc.finish_marking(|gc| roots.scan(gc), |gc, obj| scan_object(gc, obj, metas));
+    }
+}
+
+/// For each extern of `module`, whether some CallExtern passes it a GcRef
+/// or interface slot. Argument slots without a recorded type count as one.
+pub(crate) fn extern_ref_args(module: &Module) -> Vec<bool> {
+    let mut ref_args = vec![false; module.externs.len()];
+    for func in &module.functions {
+        for inst in func.code.iter().filter(|inst| inst.opcode() == Opcode::CallExtern) {
+            let start = inst.c as usize;
+            let passes_ref = (start..start + inst.flags as usize)
+                .any(|slot| func.slot_types.get(slot).is_none_or(|&st| st != SlotType::Value));
+            if let Some(r) = ref_args.get_mut(inst.b as usize) {
+                *r |= passes_ref;
+            }
+        }
     }
+    ref_args
 }
 
 fn scan_globals(gc: &mut Gc, globals: &[u64], global_defs: &[GlobalDef]) {
@@ -101,7 +239,7 @@ fn scan_fibers(gc: &mut Gc, fibers: &[Box<Fiber>], functions: &[FunctionDef]) {
         }
 
         // Scan panic value (only Recoverable has interface{})
-        if let Some(PanicState::Recoverable(val)) = fiber.panic_state {
+        if let Some(PanicState::Recoverable(val, _)) = fiber.panic_state {
             if val.is_ref_type() && val.slot1 != 0 {
                 gc.mark_gray(val.as_ref());
             }
@@ -8,11 +8,15 @@ pub mod scheduler;
 pub mod vm;
 pub mod exec;
 mod gc_roots;
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod profiler;
 
 // Re-export from vo-common-core for backward compatibility
 pub use vo_runtime::bytecode;
 pub use vo_runtime::instruction;
 pub use vo_runtime::serialize;
+pub use vo_runtime::validate;
 
 // Re-export JitConfig for external use
 #[cfg(feature = "jit")]
@@ -0,0 +1,178 @@
+//! Sampling profiler.
+//!
+//! While it runs, the profiler records where the running fiber is, at most
+//! `hz` times a second. Interpreted code is sampled when a fiber's time slice
+//! ends, so the useful rate is bounded by how often that happens (every
+//! `TIME_SLICE` instructions at the latest). JIT code is sampled at its
+//! safepoints, loop back-edges and calls, which fire on every pass while
+//! profiling.
+//!
+//! A sample is the fiber's call stack plus the pc in its innermost function.
+//! Each one stands for one sampling interval, so a function's time is its
+//! sample count divided by `hz`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::bytecode::Module;
+use crate::fiber::Fiber;
+use crate::vm::Vm;
+
+/// Samples collected between `Vm::start_profiler` and `Vm::stop_profiler`.
+pub(crate) struct Profiler {
+    interval: Duration,
+    started: Instant,
+    next: Instant,
+    /// Sample counts by call stack (outermost function first) and leaf pc.
+    counts: HashMap<(Vec<u32>, u32), u64>,
+}
+
+impl Profiler {
+    fn new(hz: u32) -> Self {
+        let now = Instant::now();
+        let interval = Duration::from_secs(1) / hz.max(1);
+        Self { interval, started: now, next: now + interval, counts: HashMap::new() }
+    }
+
+    /// Record `fiber`'s position if a sample is due. `jit` is the function
+    /// and pc of the JIT safepoint being passed; interpreted frames below it
+    /// come from the fiber.
+    pub(crate) fn sample(&mut self, fiber: &Fiber, jit: Option<(u32, u32)>) {
+        let now = Instant::now();
+        if now < self.next {
+            return;
+        }
+        let mut stack: Vec<u32> = fiber.frames.iter().map(|f| f.func_id).collect();
+        let mut pc = fiber.frames.last().map_or(0, |f| f.pc as u32);
+        if let Some((func_id, jit_pc)) = jit {
+            // A compiled loop runs inside its function's interpreted frame
+            if stack.last() != Some(&func_id) {
+                stack.push(func_id);
+            }
+            pc = jit_pc;
+        }
+        if stack.is_empty() {
+            return;
+        }
+        *self.counts.entry((stack, pc)).or_insert(0) += 1;
+        self.next = now + self.interval;
+    }
+
+    fn report(self, module: Option<&Module>) -> ProfileReport {
+        let name = |func_id: u32| {
+            module
+                .and_then(|m| m.functions.get(func_id as usize))
+                .map_or_else(|| format!("func#{}", func_id), |f| f.name.clone())
+        };
+
+        let mut functions: HashMap<u32, FunctionProfile> = HashMap::new();
+        for ((stack, _), &count) in &self.counts {
+            for (depth, &func_id) in stack.iter().enumerate() {
+                let entry = functions.entry(func_id).or_insert_with(|| FunctionProfile {
+                    func_id,
+                    name: name(func_id),
+                    self_samples: 0,
+                    total_samples: 0,
+                });
+                // Recursive calls count once toward the total
+                if !stack[..depth].contains(&func_id) {
+                    entry.total_samples += count;
+                }
+            }
+            if let Some(&leaf) = stack.last() {
+                functions.get_mut(&leaf).unwrap().self_samples += count;
+            }
+        }
+        let mut functions: Vec<FunctionProfile> = functions.into_values().collect();
+        functions.sort_by(|a, b| b.self_samples.cmp(&a.self_samples).then(a.func_id.cmp(&b.func_id)));
+
+        let mut samples: Vec<ProfileSample> = self.counts.into_iter()
+            .map(|((stack, pc), count)| ProfileSample { stack, pc, count })
+            .collect();
+        samples.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| (&a.stack, a.pc).cmp(&(&b.stack, b.pc))));
+
+        ProfileReport { interval: self.interval, duration: self.started.elapsed(), samples, functions }
+    }
+}
+
+/// One sampled position and how many samples found the fiber there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSample {
+    /// Function ids of the call stack, outermost first.
+    pub stack: Vec<u32>,
+    /// Bytecode pc in the innermost function.
+    pub pc: u32,
+    pub count: u64,
+}
+
+/// Samples attributed to one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub func_id: u32,
+    pub name: String,
+    /// Samples taken while the function itself was running.
+    pub self_samples: u64,
+    /// Samples with the function anywhere on the stack.
+    pub total_samples: u64,
+}
+
+/// Result of `Vm::stop_profiler`.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Time each sample stands for: one second divided by `hz`.
+    pub interval: Duration,
+    /// Wall time the profiler ran for.
+    pub duration: Duration,
+    /// The `(stack, pc)` histogram, most frequent first.
+    pub samples: Vec<ProfileSample>,
+    /// Per-function totals, most self samples first.
+    pub functions: Vec<FunctionProfile>,
+}
+
+impl ProfileReport {
+    /// Number of samples taken.
+    pub fn total_samples(&self) -> u64 {
+        self.samples.iter().map(|s| s.count).sum()
+    }
+
+    /// Estimated time spent in `func` itself.
+    pub fn self_time(&self, func: &FunctionProfile) -> Duration {
+        self.interval.mul_f64(func.self_samples as f64)
+    }
+
+    /// The samples in folded-stack format, one `outer;inner count` line per
+    /// distinct stack, as read by flamegraph tools.
+    pub fn folded(&self) -> String {
+        let names: HashMap<u32, &str> = self.functions.iter().map(|f| (f.func_id, f.name.as_str())).collect();
+        let mut stacks: BTreeMap<String, u64> = BTreeMap::new();
+        for sample in &self.samples {
+            let line: Vec<&str> = sample.stack.iter().map(|id| names[id]).collect();
+            *stacks.entry(line.join(";")).or_insert(0) += sample.count;
+        }
+        let mut out = String::new();
+        for (line, count) in stacks {
+            out.push_str(&format!("{} {}\n", line, count));
+        }
+        out
+    }
+}
+
+impl Vm {
+    /// Start sampling the running fiber `hz` times a second, discarding
+    /// any profile already in progress. Profiling keeps JIT safepoints
+    /// calling into the VM, which slows JIT code down until it is stopped.
+    pub fn start_profiler(&mut self, hz: u32) {
+        self.state.profiler = Some(Profiler::new(hz));
+        self.state.gc.set_hold_safepoints(true);
+    }
+
+    /// Stop the profiler and return what it collected. Empty if it wasn't
+    /// running.
+    pub fn stop_profiler(&mut self) -> ProfileReport {
+        self.state.gc.set_hold_safepoints(false);
+        match self.state.profiler.take() {
+            Some(profiler) => profiler.report(self.module.as_deref()),
+            None => ProfileReport::default(),
+        }
+    }
+}
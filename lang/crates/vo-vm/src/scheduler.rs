@@ -5,6 +5,8 @@ use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
 #[cfg(feature = "std")]
 use std::collections::VecDeque;
 
+use vo_runtime::PanicKind;
+
 use crate::fiber::{Fiber, FiberStatus};
 
 /// Type-safe fiber ID that distinguishes regular fibers from trampoline fibers.
@@ -54,7 +56,7 @@ pub fn is_trampoline_fiber(id: u32) -> bool {
     id & TRAMPOLINE_FIBER_FLAG != 0
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scheduler {
     /// Fibers indexed by id (id == index).
     /// Box<Fiber> ensures stable addresses - Vec reallocation won't invalidate fiber pointers.
@@ -63,6 +65,8 @@ pub struct Scheduler {
     free_slots: Vec<u32>,
     pub ready_queue: VecDeque<u32>,
     pub current: Option<u32>,
+    /// The fiber running the entry function, until it exits.
+    pub main_fiber: Option<u32>,
     
     /// Trampoline fibers for JIT->VM calls (separate ID space with high bit set).
     /// Box<Fiber> ensures stable addresses.
@@ -78,6 +82,7 @@ impl Scheduler {
             free_slots: Vec::new(),
             ready_queue: VecDeque::new(),
             current: None,
+            main_fiber: None,
             trampoline_fibers: Vec::new(),
             trampoline_free_slots: Vec::new(),
         }
@@ -274,18 +279,23 @@ impl Scheduler {
         None
     }
 
-    /// Kill current fiber and return (panic_msg, error_location).
+    /// Kill current fiber and return (panic_msg, error_location, panic_kind).
     /// error_location is (func_id, pc) from the current frame if available.
-    pub fn kill_current(&mut self) -> (Option<String>, Option<(u32, u32)>) {
+    pub fn kill_current(&mut self) -> (Option<String>, Option<(u32, u32)>, Option<PanicKind>) {
         if let Some(id) = self.current {
             let fiber = &mut self.fibers[id as usize];
             let msg = fiber.panic_message();
-            let loc = fiber.current_frame().map(|f| (f.func_id, f.pc as u32));
+            let kind = fiber.panic_kind();
+            // Frames are already unwound; use where the panic was raised
+            let loc = fiber.panic_loc;
             fiber.status = FiberStatus::Dead;
             self.free_slots.push(id);
-            (msg, loc)
+            if self.main_fiber == Some(id) {
+                self.main_fiber = None;
+            }
+            (msg, loc, kind)
         } else {
-            (None, None)
+            (None, None, None)
         }
     }
 
@@ -300,6 +310,13 @@ impl Scheduler {
             self.fibers.iter().any(|f| f.status == FiberStatus::Suspended)
     }
 
+    /// True when the main fiber is still alive but nothing can run and every
+    /// live fiber waits on a channel, so no fiber is left to wake the others.
+    pub fn is_deadlocked(&self) -> bool {
+        self.main_fiber.is_some() && !self.has_runnable() &&
+            self.fibers.iter().all(|f| f.status == FiberStatus::Dead || f.chan_blocked)
+    }
+
 }
 
 impl Default for Scheduler {
@@ -0,0 +1,92 @@
+//! Snapshots of a program's state, to restore it later.
+//!
+//! A snapshot holds the globals, the scheduler with every fiber's stack, and
+//! the contents of every heap object. Objects are saved in place: `restore`
+//! writes each one's slots back at the same address, so references between
+//! them need no fixing up. To keep those addresses valid, the objects of a
+//! live snapshot are GC roots until it is dropped. Objects allocated after
+//! the snapshot become unreachable on restore and are collected as usual.
+//!
+//! Take and restore snapshots between runs, not from inside an extern: JIT
+//! frames and references held by externs are not captured. Port state is
+//! shared with other islands and is left as it is.
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+#[cfg(feature = "std")]
+use std::rc::Rc;
+
+use vo_runtime::gc::{Gc, GcRef};
+use vo_runtime::gc_types::{restore_native_state, save_native_state, NativeState};
+
+use crate::scheduler::Scheduler;
+use crate::vm::Vm;
+
+/// Program state captured by `Vm::snapshot`.
+pub struct VmSnapshot {
+    globals: Vec<u64>,
+    scheduler: Scheduler,
+    heap: Rc<HeapImage>,
+}
+
+/// Contents of every object on the heap when the snapshot was taken.
+pub(crate) struct HeapImage {
+    objects: Vec<SavedObject>,
+}
+
+struct SavedObject {
+    obj: GcRef,
+    slots: Box<[u64]>,
+    native: Option<NativeState>,
+}
+
+impl HeapImage {
+    pub(crate) fn objects(&self) -> impl Iterator<Item = GcRef> + '_ {
+        self.objects.iter().map(|saved| saved.obj)
+    }
+}
+
+fn data(obj: GcRef) -> &'static mut [u64] {
+    unsafe { core::slice::from_raw_parts_mut(obj, Gc::data_slots(obj)) }
+}
+
+impl Vm {
+    /// Capture globals, fibers and the heap. Collects garbage first so only
+    /// reachable objects are saved.
+    pub fn snapshot(&mut self) -> VmSnapshot {
+        debug_assert_eq!(self.state.extern_calls, 0, "snapshot taken inside an extern");
+        self.collect_garbage();
+        let objects = self.state.gc.objects().iter()
+            .map(|&obj| SavedObject {
+                obj,
+                slots: data(obj).into(),
+                native: save_native_state(obj),
+            })
+            .collect();
+        let heap = Rc::new(HeapImage { objects });
+        self.state.snapshots.retain(|s| s.strong_count() > 0);
+        self.state.snapshots.push(Rc::downgrade(&heap));
+        VmSnapshot {
+            globals: self.state.globals.clone(),
+            scheduler: self.scheduler.clone(),
+            heap,
+        }
+    }
+
+    /// Return globals, fibers and every object that existed at `snapshot` to
+    /// their saved state. The snapshot stays valid and can be restored again.
+    pub fn restore(&mut self, snapshot: &VmSnapshot) {
+        debug_assert_eq!(self.state.extern_calls, 0, "snapshot restored inside an extern");
+        // Writing slots back bypasses write barriers
+        self.gc_complete_cycle();
+        for saved in &snapshot.heap.objects {
+            data(saved.obj).copy_from_slice(&saved.slots);
+            if let Some(native) = &saved.native {
+                restore_native_state(saved.obj, native);
+            }
+        }
+        // Same module, same size: copy in place so JIT code keeps its globals pointer
+        self.state.globals.copy_from_slice(&snapshot.globals);
+        self.scheduler = snapshot.scheduler.clone();
+    }
+}
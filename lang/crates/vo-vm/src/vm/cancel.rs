@@ -0,0 +1,73 @@
+//! Host-driven cancellation of the `context` package's root context.
+//!
+//! `context.Background()` owns a Done channel that the package hands to the
+//! VM when it initializes. `Vm::cancel_all` closes that channel, waking every
+//! fiber waiting on it; the package's watcher goroutines then cancel every
+//! context derived from Background.
+
+use vo_runtime::bytecode::ExternDef;
+use vo_runtime::ffi::{ExternCallContext, ExternRegistry, ExternResult};
+use vo_runtime::objects::channel;
+
+use super::Vm;
+use crate::scheduler::FiberId;
+
+/// Register the externs the VM implements itself. Runs before extern
+/// validation in every load path.
+pub(super) fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef]) {
+    for (id, def) in externs.iter().enumerate() {
+        if def.name == "context_watchRoot" {
+            registry.register_with_context(id as u32, watch_root);
+        }
+    }
+}
+
+/// `func watchRoot(done chan struct{})`: remember Background's Done channel.
+/// If the host already cancelled, the root starts out closed.
+fn watch_root(call: &mut ExternCallContext) -> ExternResult {
+    let done = call.arg_ref(0);
+    let vm = call.vm_ptr() as *mut Vm;
+    // SAFETY: externs run on the VM's thread with `vm` pointing at the
+    // running VM. Only the cancellation fields are written, through the raw
+    // pointer, so the heap `call` borrows is never reborrowed.
+    unsafe {
+        (*vm).state.cancel_root = done;
+        if (*vm).state.cancelled {
+            channel::close(done);
+        }
+    }
+    ExternResult::Ok
+}
+
+impl Vm {
+    /// Cancel `context.Background()`, and with it every context derived
+    /// from it: the root Done channel closes and the fibers waiting on it
+    /// become runnable. Call between runs and resume with `run_scheduled`.
+    /// Contexts the program creates afterwards start out cancelled.
+    ///
+    /// `run` doesn't report a deadlock while some fiber waits on the root,
+    /// since only this call can wake it.
+    pub fn cancel_all(&mut self) {
+        self.state.cancelled = true;
+        let root = self.state.cancel_root;
+        if root.is_null() || channel::is_closed(root) {
+            return;
+        }
+        let state = channel::get_state(root);
+        state.close();
+        let receivers = state.take_waiting_receivers().into_iter().map(|id| id as u32);
+        let senders = state.take_waiting_senders().into_iter().map(|(id, _)| id as u32);
+        for id in receivers.chain(senders) {
+            let id = FiberId::from_raw(id);
+            self.scheduler.get_fiber_mut(id).chan_blocked = false;
+            self.scheduler.wake_fiber(id);
+        }
+    }
+
+    /// Some fiber is waiting to receive from the root Done channel. Fibers
+    /// waiting on derived contexts wait through their watcher goroutines.
+    pub(super) fn awaiting_cancel(&self) -> bool {
+        let root = self.state.cancel_root;
+        !root.is_null() && !channel::get_state(root).waiting_receivers.is_empty()
+    }
+}
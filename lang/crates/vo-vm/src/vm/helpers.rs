@@ -4,7 +4,7 @@
 use alloc::string::String;
 
 use vo_runtime::gc::{Gc, GcRef};
-use vo_runtime::InterfaceSlot;
+use vo_runtime::{InterfaceSlot, PanicKind};
 use vo_runtime::objects::{slice, string};
 use vo_runtime::slot::{Slot, slot_to_ptr, slot_to_usize};
 
@@ -74,6 +74,46 @@ pub fn string_index(s: GcRef, idx: usize) -> u8 {
 // Runtime panic helper (recoverable via defer/recover)
 // =============================================================================
 
+/// float64 -> uint64 the way the JIT does it: values below 2^63 go through
+/// the signed conversion, larger ones are offset by 2^63 first.
+#[inline(always)]
+pub fn f64_to_u64(a: f64) -> u64 {
+    const TWO_63: f64 = 9223372036854775808.0;
+    if a < TWO_63 {
+        a as i64 as u64
+    } else {
+        ((a - TWO_63) as i64 as u64) ^ (1 << 63)
+    }
+}
+
+/// math.Min/math.Max as the JIT lowers them (Cranelift fmin/fmax): NaN if
+/// either operand is NaN, and -0 orders below +0.
+#[inline(always)]
+pub fn min_f64(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_negative() { a } else { b }
+    } else if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+#[inline(always)]
+pub fn max_f64(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        f64::NAN
+    } else if a == b {
+        if a.is_sign_negative() { b } else { a }
+    } else if a > b {
+        a
+    } else {
+        b
+    }
+}
+
 /// Common runtime error messages
 pub const ERR_NIL_POINTER: &str = "runtime error: nil pointer dereference";
 pub const ERR_NIL_MAP_WRITE: &str = "runtime error: assignment to entry in nil map";
@@ -85,11 +125,29 @@ pub const ERR_TYPE_ASSERTION: &str = "runtime error: interface conversion: inter
 pub const ERR_SEND_ON_CLOSED: &str = "runtime error: send on closed channel";
 pub const ERR_CLOSE_NIL_CHANNEL: &str = "runtime error: close of nil channel";
 pub const ERR_CLOSE_CLOSED_CHANNEL: &str = "runtime error: close of closed channel";
+pub const ERR_STACK_OVERFLOW: &str = "runtime error: stack overflow";
 
 /// Trigger a recoverable runtime panic with proper unwind mechanism.
 /// Use this for all user-triggerable runtime errors (bounds check, nil access, etc.)
+/// `kind` says which one it is, so hosts don't have to parse `msg`.
 #[inline]
 pub fn runtime_panic(
+    gc: &mut Gc,
+    fiber: &mut Fiber,
+    stack: &mut Vec<u64>,
+    module: &Module,
+    kind: PanicKind,
+    msg: String,
+) -> ExecResult {
+    let panic_str = string::new_from_string(gc, msg);
+    fiber.set_runtime_panic(InterfaceSlot::from_string(panic_str), kind);
+    panic_unwind(fiber, stack, module)
+}
+
+/// Panic with the message an extern returned. Like a user `panic`, it
+/// carries no runtime error kind.
+#[inline]
+pub fn extern_panic(
     gc: &mut Gc,
     fiber: &mut Fiber,
     stack: &mut Vec<u64>,
@@ -97,8 +155,7 @@ pub fn runtime_panic(
     msg: String,
 ) -> ExecResult {
     let panic_str = string::new_from_string(gc, msg);
-    let slot0 = vo_runtime::objects::interface::pack_slot0(0, 0, vo_runtime::ValueKind::String);
-    fiber.set_recoverable_panic(InterfaceSlot::new(slot0, panic_str as u64));
+    fiber.set_recoverable_panic(InterfaceSlot::from_string(panic_str));
     panic_unwind(fiber, stack, module)
 }
 
@@ -24,7 +24,7 @@ pub fn run_island_thread(
     island_registry: IslandRegistry,
 ) {
     let mut vm = Vm::new();
-    vm.load((*module).clone());
+    vm.load(module);
     
     // Register our ability to send to other islands
     vm.state.island_registry = Some(island_registry);
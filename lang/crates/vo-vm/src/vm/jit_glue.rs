@@ -1,33 +1,38 @@
 //! JIT trampolines, context building, and JIT call implementations.
 
 use vo_runtime::jit_api::{JitResult, JitContext};
-use vo_runtime::InterfaceSlot;
+use vo_runtime::{InterfaceSlot, PanicKind};
 use vo_jit::JitFunc;
 
 use crate::bytecode::Module;
 use crate::fiber::Fiber;
 
+use super::helpers::ERR_STACK_OVERFLOW;
 use super::{Vm, VmState, ExecResult};
 
+/// JIT->VM calls allowed to nest on the native stack. Deeper calls are
+/// interpreted on trampoline frames, which live on the heap.
+pub(super) const MAX_JIT_DEPTH: usize = 256;
+
 // =============================================================================
 // JIT Panic Handling
 // =============================================================================
 
-/// Set recoverable panic state on fiber when JIT triggers a runtime panic.
-/// This is only called for runtime panics (nil pointer, bounds check, etc.)
-/// when vo_panic hasn't already set the panic message.
-#[inline]
-fn set_jit_runtime_panic(gc: &mut vo_runtime::gc::Gc, fiber: &mut Fiber) {
-    // Only set if panic_state is not already set (vo_panic may have set it for user panics)
-    if fiber.panic_state.is_some() {
-        return;
+/// Move the panic JIT code raised into the fiber: the message it stored, and
+/// the runtime error kind when it raised one itself.
+fn set_jit_panic(fiber: &mut Fiber, msg: InterfaceSlot, kind: u8) {
+    match PanicKind::from_u8(kind) {
+        Some(kind) => fiber.set_runtime_panic(msg, kind),
+        // A nil message with a panic already set came from a VM call
+        None if msg.is_nil() && fiber.panic_state.is_some() => {}
+        None => fiber.set_recoverable_panic(msg),
     }
-    let msg = vo_runtime::objects::string::new_from_string(
-        gc,
-        "runtime error: nil pointer dereference".to_string()
-    );
-    let slot0 = vo_runtime::objects::interface::pack_slot0(0, 0, vo_runtime::ValueKind::String);
-    fiber.set_recoverable_panic(InterfaceSlot::new(slot0, msg as u64));
+}
+
+/// Set a recoverable runtime error panic carrying `msg` on fiber.
+fn set_runtime_panic(gc: &mut vo_runtime::gc::Gc, fiber: &mut Fiber, kind: PanicKind, msg: &str) {
+    let msg = vo_runtime::objects::string::new_from_string(gc, msg.to_string());
+    fiber.set_runtime_panic(InterfaceSlot::from_string(msg), kind);
 }
 
 // =============================================================================
@@ -44,6 +49,7 @@ pub extern "C" fn call_extern_trampoline(
     arg_count: u32,
     ret: *mut u64,
     ret_slots: u32,
+    can_park: u32,
 ) -> JitResult {
     use vo_runtime::ffi::{ExternResult, ExternRegistry};
     use crate::bytecode::Module;
@@ -63,28 +69,54 @@ pub extern "C" fn call_extern_trampoline(
     
     let program_args = unsafe { &*ctx.program_args };
     let sentinel_errors = unsafe { &mut *ctx.sentinel_errors };
-    let result = registry.call(
-        extern_id,
-        &mut temp_stack,
-        0,
-        0,
-        arg_count as u16,
-        0,
-        gc,
-        &module.struct_metas,
-        &module.interface_metas,
-        &module.named_type_metas,
-        &module.runtime_types,
-        itab_cache,
-        &module.functions,
-        module,
-        ctx.vm,
-        ctx.fiber,
-        Some(super::closure_call_trampoline),
-        &module.well_known,
-        program_args,
-        sentinel_errors,
-    );
+    let vm = unsafe { &mut *(ctx.vm as *mut Vm) };
+    vm.gc_before_extern(extern_id);
+    vm.state.extern_calls += 1;
+    let mut resume = None;
+    let result = loop {
+        let result = registry.call(
+            extern_id,
+            &mut temp_stack,
+            0,
+            0,
+            arg_count as u16,
+            0,
+            gc,
+            &module.struct_metas,
+            &module.interface_metas,
+            &module.named_type_metas,
+            &module.runtime_types,
+            itab_cache,
+            &module.functions,
+            module,
+            ctx.vm,
+            ctx.fiber,
+            Some(super::closure_call_trampoline),
+            &module.well_known,
+            program_args,
+            sentinel_errors,
+            &mut resume,
+        );
+        if resume.is_none() {
+            break result;
+        }
+        // Suspended. If the JIT code can exit at this call straight back to the
+        // interpreter, deopt with the state on the fiber: the VM re-executes the
+        // CallExtern and parks the fiber there like any interpreted call.
+        if can_park != 0 && vm.state.jit_depth == 0 {
+            vm.state.extern_calls -= 1;
+            let fiber = unsafe { &mut *(ctx.fiber as *mut crate::fiber::Fiber) };
+            fiber.extern_resume = resume;
+            return JitResult::Deopt;
+        }
+        // Native frames hold the caller's state, so the fiber cannot park. Let
+        // the other fibers run a round in place, as blocked JIT->VM calls do.
+        // The extern call stays open meanwhile, so no GC step frees its arguments.
+        if !vm.scheduler.ready_queue.is_empty() {
+            vm.run_scheduler_round();
+        }
+    };
+    vm.state.extern_calls -= 1;
     
     match result {
         ExternResult::Ok => {
@@ -111,10 +143,11 @@ pub extern "C" fn call_extern_trampoline(
             let fiber = unsafe { &mut *(ctx.fiber as *mut crate::fiber::Fiber) };
             let gc = unsafe { &mut *ctx.gc };
             let panic_str = vo_runtime::objects::string::new_from_string(gc, msg);
-            let slot0 = vo_runtime::objects::interface::pack_slot0(0, 0, vo_runtime::ValueKind::String);
-            fiber.set_recoverable_panic(InterfaceSlot::new(slot0, panic_str as u64));
+            fiber.set_recoverable_panic(InterfaceSlot::from_string(panic_str));
             JitResult::Panic
         }
+        // The closure's panic is already on the fiber
+        ExternResult::Repanic => JitResult::Panic,
     }
 }
 
@@ -139,6 +172,16 @@ pub extern "C" fn vm_call_trampoline(
     }
 }
 
+/// Take a profiler sample and run an incremental GC step from a JIT safepoint.
+extern "C" fn gc_step_trampoline(ctx: *mut JitContext, func_id: u32, pc: u32) {
+    let vm = unsafe { &mut *((*ctx).vm as *mut Vm) };
+    if let Some(profiler) = vm.state.profiler.as_mut() {
+        let fiber = unsafe { &*((*ctx).fiber as *const crate::fiber::Fiber) };
+        profiler.sample(fiber, Some((func_id, pc)));
+    }
+    vm.gc_step();
+}
+
 // =============================================================================
 // JitContext Builder
 // =============================================================================
@@ -150,14 +193,13 @@ pub fn build_jit_ctx(
     vm_ptr: *mut std::ffi::c_void,
     fiber_ptr: *mut std::ffi::c_void,
     module_ptr: *const Module,
-    safepoint_flag: *const bool,
     panic_flag: *mut bool,
     panic_msg: *mut InterfaceSlot,
 ) -> JitContext {
     JitContext {
         gc: &mut state.gc as *mut _,
         globals: state.globals.as_mut_ptr(),
-        safepoint_flag,
+        safepoint_flag: state.gc.safepoint_flag(),
         panic_flag,
         panic_msg,
         vm: vm_ptr,
@@ -171,6 +213,13 @@ pub fn build_jit_ctx(
         jit_func_count,
         program_args: &state.program_args as *const _,
         sentinel_errors: &mut state.sentinel_errors as *mut _,
+        itab_table: state.itab_cache.table_ptr(),
+        jit_frames: &mut state.jit_frames as *mut _,
+        sorted_map_iter: state.sorted_map_iter,
+        gc_marking: state.gc.marking_flag(),
+        gc_step_fn: Some(gc_step_trampoline),
+        panic_pc: u32::MAX,
+        panic_kind: 0,
     }
 }
 
@@ -182,6 +231,7 @@ impl Vm {
     /// Call a JIT function with a fresh context (raw pointer version).
     pub(super) fn call_jit_direct(
         &mut self,
+        func_id: u32,
         jit_func: JitFunc,
         fiber_ptr: *mut std::ffi::c_void,
         args: *mut u64,
@@ -190,31 +240,32 @@ impl Vm {
         let jit_mgr = self.jit_mgr.as_ref().unwrap();
         let func_table_ptr = jit_mgr.func_table_ptr();
         let func_table_len = jit_mgr.func_table_len() as u32;
-        let safepoint_flag = false;
         let mut panic_flag = false;
         let mut panic_msg = InterfaceSlot::default();
         let vm_ptr = self as *mut _ as *mut std::ffi::c_void;
-        let module_ptr = self.module.as_ref()
+        let module_ptr = self.module.as_deref()
             .map(|m| m as *const _)
             .unwrap_or(std::ptr::null());
         
         let mut ctx = build_jit_ctx(
             &mut self.state, func_table_ptr, func_table_len,
             vm_ptr, fiber_ptr, module_ptr,
-            &safepoint_flag, &mut panic_flag,
+            &mut panic_flag,
             &mut panic_msg,
         );
+        // Frames registered by the callee (and anything it calls) end here
+        let frames_depth = self.state.jit_frames.len();
         let result = jit_func(&mut ctx, args, ret);
+        self.state.jit_frames.truncate(frames_depth);
         
         // Set recoverable panic state if JIT triggered panic
         if result == JitResult::Panic && panic_flag {
             let fiber = unsafe { &mut *(fiber_ptr as *mut Fiber) };
-            // Use actual panic message if vo_panic set it, otherwise use runtime error
-            if !panic_msg.is_nil() {
-                fiber.set_recoverable_panic(panic_msg);
-            } else {
-                set_jit_runtime_panic(&mut self.state.gc, fiber);
-            }
+            set_jit_panic(fiber, panic_msg, ctx.panic_kind);
+        }
+        if result == JitResult::Panic && ctx.panic_pc != u32::MAX {
+            let fiber = unsafe { &mut *(fiber_ptr as *mut Fiber) };
+            fiber.panic_loc = Some((func_id, ctx.panic_pc));
         }
         result
     }
@@ -223,7 +274,8 @@ impl Vm {
     /// that owns defer/recover), not the trampoline fiber. Trampoline fiber is only used as an
     /// execution container for VM interpretation.
     ///
-    /// This is the core logic for vm_call_trampoline.
+    /// This is the core logic for vm_call_trampoline. The call counts toward the
+    /// caller fiber's depth, and panics with a stack overflow past the limit.
     pub fn execute_jit_call_with_caller(
         &mut self,
         func_id: u32,
@@ -232,28 +284,58 @@ impl Vm {
         ret: *mut u64,
         ret_count: u32,
         caller_fiber_ptr: *mut std::ffi::c_void,
+    ) -> JitResult {
+        let caller = caller_fiber_ptr as *mut Fiber;
+        if let Some(fiber) = unsafe { caller.as_mut() } {
+            if fiber.depth() >= self.state.max_call_depth {
+                set_runtime_panic(&mut self.state.gc, fiber, PanicKind::StackOverflow, ERR_STACK_OVERFLOW);
+                return JitResult::Panic;
+            }
+            fiber.call_depth += 1;
+        }
+        self.state.jit_depth += 1;
+        let result = self.run_jit_call(func_id, args, arg_count, ret, ret_count, caller_fiber_ptr);
+        self.state.jit_depth -= 1;
+        if let Some(fiber) = unsafe { caller.as_mut() } {
+            fiber.call_depth -= 1;
+        }
+        result
+    }
+
+    fn run_jit_call(
+        &mut self,
+        func_id: u32,
+        args: *const u64,
+        arg_count: u32,
+        ret: *mut u64,
+        ret_count: u32,
+        caller_fiber_ptr: *mut std::ffi::c_void,
     ) -> JitResult {
         let module = match &self.module {
-            Some(m) => m as *const Module,
+            Some(m) => &**m as *const Module,
             None => return JitResult::Panic,
         };
         let module = unsafe { &*module };
         
         // Try JIT compilation/execution first
         // IMPORTANT: Use caller_fiber_ptr for JitContext.fiber so panic goes to the right fiber
-        if let Some(jit_mgr) = self.jit_mgr.as_mut() {
-            if let Some(jit_func) = jit_mgr.get_entry(func_id) {
-                // JIT function exists - call directly with caller fiber as JitContext.fiber
-                return self.call_jit_direct(jit_func, caller_fiber_ptr, args as *mut u64, ret);
+        // Past MAX_JIT_DEPTH (this call included), interpret instead of nesting deeper
+        let use_jit = self.state.jit_depth <= MAX_JIT_DEPTH;
+        if let Some(jit_mgr) = self.jit_mgr.as_mut().filter(|_| use_jit) {
+            let mut jit_func = jit_mgr.get_entry(func_id);
+            if jit_func.is_none() && jit_mgr.record_call(func_id) {
+                let func_def = &module.functions[func_id as usize];
+                jit_func = jit_mgr.compile_hot(func_id, func_def, module);
             }
             
-            if jit_mgr.record_call(func_id) {
-                let func_def = &module.functions[func_id as usize];
-                if jit_mgr.compile_full(func_id, func_def, module).is_ok() {
-                    if let Some(jit_func) = jit_mgr.get_entry(func_id) {
-                        return self.call_jit_direct(jit_func, caller_fiber_ptr, args as *mut u64, ret);
-                    }
+            if let Some(jit_func) = jit_func {
+                // JIT function exists - call directly with caller fiber as JitContext.fiber
+                let result = self.call_jit_direct(func_id, jit_func, caller_fiber_ptr, args as *mut u64, ret);
+                if result != JitResult::Deopt {
+                    return result;
                 }
+                // Nothing has happened yet: interpret the call from the start below
+                self.jit_mgr.as_mut().unwrap().deoptimize(func_id);
             }
         }
         
@@ -269,6 +351,8 @@ impl Vm {
         
         {
             let fiber = self.scheduler.trampoline_fiber_mut(trampoline_id);
+            // Inherit the caller's depth so recursion through JIT code is bounded
+            fiber.call_depth = unsafe { (caller_fiber_ptr as *const Fiber).as_ref() }.map_or(0, Fiber::depth);
             fiber.push_frame(func_id, local_slots, 0, func_ret_slots as u16);
             let bp = fiber.frames.last().unwrap().bp;
             for i in 0..param_slots.min(arg_count as usize) {
@@ -314,6 +398,7 @@ impl Vm {
             if let Some(panic_state) = trampoline_fiber.panic_state.take() {
                 let caller_fiber = unsafe { &mut *(caller_fiber_ptr as *mut Fiber) };
                 caller_fiber.panic_state = Some(panic_state);
+                caller_fiber.panic_loc = trampoline_fiber.panic_loc;
                 // Also propagate panic_generation so caller's defers can recover
                 caller_fiber.panic_generation = trampoline_fiber.panic_generation;
             }
@@ -324,42 +409,53 @@ impl Vm {
     }
 
     /// Call a JIT function inline from VM execution (resolve_call path).
+    /// `buf` is the call buffer: results are written to its start, args follow them.
     /// JitContext.fiber points to the current fiber for correct panic handling.
+    /// Returns None if the JIT code deoptimized; the caller then interprets the call.
     pub(super) fn call_jit_inline(
         &mut self,
         fiber_id: crate::scheduler::FiberId,
+        func_id: u32,
         jit_func: JitFunc,
-        arg_start: u16,
+        buf: u16,
         arg_slots: usize,
         func_ret_slots: usize,
         call_ret_slots: usize,
-    ) -> ExecResult {
+    ) -> Option<ExecResult> {
         // Read args from fiber stack
         let fiber = self.scheduler.get_fiber_mut(fiber_id);
+        let arg_start = buf + call_ret_slots as u16;
         let mut args: Vec<u64> = (0..arg_slots)
             .map(|i| fiber.read_reg(arg_start + i as u16))
             .collect();
         let fiber_ptr = fiber as *mut Fiber as *mut std::ffi::c_void;
         
         let mut ret_buf = vec![0u64; func_ret_slots.max(1)];
-        let result = self.call_jit_direct(jit_func, fiber_ptr, args.as_mut_ptr(), ret_buf.as_mut_ptr());
+        let result = self.call_jit_direct(func_id, jit_func, fiber_ptr, args.as_mut_ptr(), ret_buf.as_mut_ptr());
         
         match result {
             JitResult::Ok => {
                 // Write returns back to fiber stack
                 let fiber = self.scheduler.get_fiber_mut(fiber_id);
                 for i in 0..call_ret_slots.min(ret_buf.len()) {
-                    fiber.write_reg(arg_start + i as u16, ret_buf[i]);
+                    fiber.write_reg(buf + i as u16, ret_buf[i]);
                 }
-                ExecResult::Continue
+                Some(ExecResult::Continue)
             }
             JitResult::Block => {
                 // Blocking I/O: return Block to VM scheduler
-                ExecResult::Block
+                Some(ExecResult::Block)
             }
             JitResult::Panic => {
                 // panic_state already set by call_jit_direct
-                ExecResult::Panic
+                Some(ExecResult::Panic)
+            }
+            JitResult::Deopt => {
+                // A parked extern leaves its state on the fiber; the code is still good
+                if self.scheduler.get_fiber(fiber_id).extern_resume.is_none() {
+                    self.jit_mgr.as_mut().unwrap().deoptimize(func_id);
+                }
+                None
             }
         }
     }
@@ -375,6 +471,9 @@ impl Vm {
         loop_begin_pc: usize,
         bp: usize,
     ) -> Option<usize> {
+        if self.state.jit_depth >= MAX_JIT_DEPTH {
+            return None;
+        }
         let module = self.module.as_ref()?;
         let func_def = &module.functions[func_id as usize];
         let jit_mgr = self.jit_mgr.as_mut()?;
@@ -431,11 +530,10 @@ impl Vm {
         let jit_mgr = self.jit_mgr.as_ref().unwrap();
         let func_table_ptr = jit_mgr.func_table_ptr();
         let func_table_len = jit_mgr.func_table_len() as u32;
-        let safepoint_flag = false;
         let mut panic_flag = false;
         let mut panic_msg = InterfaceSlot::default();
         let vm_ptr = self as *mut _ as *mut std::ffi::c_void;
-        let module_ptr = self.module.as_ref()
+        let module_ptr = self.module.as_deref()
             .map(|m| m as *const _)
             .unwrap_or(std::ptr::null());
         
@@ -446,7 +544,7 @@ impl Vm {
         let mut ctx = build_jit_ctx(
             &mut self.state, func_table_ptr, func_table_len,
             vm_ptr, fiber_ptr, module_ptr,
-            &safepoint_flag, &mut panic_flag,
+            &mut panic_flag,
             &mut panic_msg,
         );
         
@@ -456,11 +554,12 @@ impl Vm {
             // Set panic state so defer/recover can work
             if panic_flag {
                 let fiber = self.scheduler.get_fiber_mut(fiber_id);
-                if !panic_msg.is_nil() {
-                    fiber.set_recoverable_panic(panic_msg);
-                } else {
-                    set_jit_runtime_panic(&mut self.state.gc, fiber);
-                }
+                set_jit_panic(fiber, panic_msg, ctx.panic_kind);
+            }
+            // The loop runs in the frame of its own function
+            if ctx.panic_pc != u32::MAX {
+                let fiber = self.scheduler.get_fiber_mut(fiber_id);
+                fiber.panic_loc = fiber.current_frame().map(|f| (f.func_id, ctx.panic_pc));
             }
             None
         } else {
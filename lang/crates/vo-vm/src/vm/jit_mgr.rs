@@ -9,12 +9,17 @@
 //! 2. **Loop OSR (On-Stack Replacement)** - TODO: Triggered when a loop becomes hot
 //!
 //! Currently only full function compilation is implemented. Loop OSR is planned.
+//!
+//! With `JitConfig::async_compile`, hot functions are compiled on a worker
+//! thread (`vo_jit::BackgroundCompiler`) while the VM keeps interpreting them;
+//! finished code is picked up on the next call. Loops always compile inline.
 
 use vo_runtime::bytecode::{FunctionDef, Module as VoModule};
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use vo_jit::{JitCompiler, JitError, JitFunc, LoopFunc, LoopInfo};
+use vo_jit::{BackgroundCompiler, FuncStat, JitCompiler, JitError, JitFunc, LoopFunc, LoopInfo};
 use vo_jit::loop_analysis::analyze_loops;
 
 // =============================================================================
@@ -30,6 +35,10 @@ pub struct JitConfig {
     pub loop_threshold: u32,
     /// Print Cranelift IR for compiled functions.
     pub debug_ir: bool,
+    /// Compile hot functions on a background thread instead of inline.
+    pub async_compile: bool,
+    /// Panic on signed overflow in compiled integer add/sub/mul (debugging aid).
+    pub checked_arith: bool,
 }
 
 impl Default for JitConfig {
@@ -38,6 +47,8 @@ impl Default for JitConfig {
             call_threshold: 100,
             loop_threshold: 50,
             debug_ir: false,
+            async_compile: false,
+            checked_arith: false,
         }
     }
 }
@@ -51,10 +62,14 @@ impl Default for JitConfig {
 pub enum CompileState {
     /// Not compiled, use VM interpreter.
     Interpreted,
+    /// Queued on the background compiler; still interpreted.
+    Compiling,
     /// Has full function JIT version.
     FullyCompiled,
     /// Cannot JIT (unsupported features), never retry.
     Unsupported,
+    /// JIT code deoptimized at runtime; interpreted from now on.
+    Deoptimized,
 }
 
 // =============================================================================
@@ -113,6 +128,12 @@ pub struct JitManager {
     
     /// Configuration.
     config: JitConfig,
+    
+    /// Background compiler (async_compile only).
+    background: Option<BackgroundCompiler>,
+    
+    /// Functions queued on the background compiler and not yet picked up.
+    pending: usize,
 }
 
 // SAFETY: func_table contains raw pointers to JIT code which is thread-safe to read.
@@ -127,26 +148,36 @@ impl JitManager {
             func_table: Vec::new(),
             compiler: JitCompiler::new()?,
             config: JitConfig::default(),
+            background: None,
+            pending: 0,
         })
     }
     
     /// Create a new JIT manager with custom config.
     pub fn with_config(config: JitConfig) -> Result<Self, JitError> {
-        let compiler = JitCompiler::with_debug(config.debug_ir)?;
+        let mut compiler = JitCompiler::with_debug(config.debug_ir)?;
+        compiler.set_checked_arith(config.checked_arith);
         Ok(Self {
             funcs: Vec::new(),
             func_table: Vec::new(),
             compiler,
             config,
+            background: None,
+            pending: 0,
         })
     }
     
-    /// Initialize for a module (call after module load).
-    pub fn init(&mut self, func_count: usize) {
+    /// Initialize for a module (call after module load or reload). The
+    /// background compiler, if enabled, is restarted on `module`.
+    pub fn init(&mut self, module: &Arc<VoModule>) {
+        let func_count = module.functions.len();
         self.funcs = (0..func_count)
             .map(|_| FunctionJitInfo::new())
             .collect();
         self.func_table = vec![std::ptr::null(); func_count];
+        self.pending = 0;
+        self.background = self.config.async_compile
+            .then(|| BackgroundCompiler::spawn(Arc::clone(module), self.config.debug_ir, self.config.checked_arith));
     }
     
     /// Get function table pointer for JIT code.
@@ -177,6 +208,34 @@ impl JitManager {
         }
     }
     
+    /// Get the compilation state of a function.
+    pub fn compile_state(&self, func_id: u32) -> Option<CompileState> {
+        self.funcs.get(func_id as usize).map(|info| info.state)
+    }
+    
+    /// Get the number of recorded calls for a function.
+    pub fn call_count(&self, func_id: u32) -> u32 {
+        self.funcs.get(func_id as usize).map(|info| info.call_count).unwrap_or(0)
+    }
+    
+    /// Per-function statistics: compile state, code size and call counts.
+    /// Code compiled on the background thread reports a code size of 0.
+    pub fn stats(&self) -> Vec<FuncStat> {
+        self.funcs.iter().enumerate()
+            .map(|(id, info)| FuncStat {
+                func_id: id as u32,
+                code_size: self.compiler.get(id as u32).map_or(0, |f| f.code_size),
+                compiled: info.state == CompileState::FullyCompiled,
+                call_count: info.call_count,
+            })
+            .collect()
+    }
+    
+    /// Cranelift IR of a function compiled inline with `JitConfig::debug_ir` on.
+    pub fn dump_cranelift_ir(&self, func_id: u32) -> Option<String> {
+        self.compiler.dump_cranelift_ir(func_id)
+    }
+    
     /// Resolve which version to use for a function call.
     /// Returns Some(jit_func) if JIT version available, None for VM fallback.
     /// Also handles hot tracking and triggers compilation when threshold reached.
@@ -188,13 +247,58 @@ impl JitManager {
         
         // 2. Record call, compile if hot
         if self.record_call(func_id) {
-            if self.compile_full(func_id, func_def, module).is_ok() {
-                return self.get_entry(func_id);
-            }
+            return self.compile_hot(func_id, func_def, module);
         }
         
-        // 3. Fall back to VM
-        None
+        // 3. A background compile may have just finished
+        self.get_entry(func_id)
+    }
+    
+    /// Compile a function that just became hot.
+    /// Returns the entry if it is available now; with async_compile the
+    /// function is queued and the VM keeps interpreting it.
+    pub fn compile_hot(&mut self, func_id: u32, func_def: &FunctionDef, module: &VoModule) -> Option<JitFunc> {
+        if let Some(bg) = &self.background {
+            let info = &mut self.funcs[func_id as usize];
+            if !self.compiler.can_jit(func_def, module) {
+                info.state = CompileState::Unsupported;
+            } else if bg.request(func_id, func_def) {
+                info.state = CompileState::Compiling;
+                self.pending += 1;
+            }
+            return None;
+        }
+        self.compile_full(func_id, func_def, module).ok()?;
+        self.get_entry(func_id)
+    }
+    
+    /// Install functions the background compiler has finished.
+    pub fn poll_background(&mut self) {
+        let Some(bg) = &self.background else { return };
+        while let Some(done) = bg.poll() {
+            self.pending -= 1;
+            let id = done.func_id as usize;
+            let info = &mut self.funcs[id];
+            match done.result {
+                Ok(ptr) => {
+                    info.full_entry = Some(ptr);
+                    info.state = CompileState::FullyCompiled;
+                    self.func_table[id] = ptr as *const u8;
+                }
+                Err(_) => info.state = CompileState::Unsupported,
+            }
+        }
+    }
+    
+    /// Drop a function's JIT code after it deoptimized, so later calls are
+    /// interpreted. Loop versions are kept: they exit to the VM precisely.
+    pub fn deoptimize(&mut self, func_id: u32) {
+        let id = func_id as usize;
+        if let Some(info) = self.funcs.get_mut(id) {
+            info.full_entry = None;
+            info.state = CompileState::Deoptimized;
+            self.func_table[id] = std::ptr::null();
+        }
     }
     
     // =========================================================================
@@ -202,7 +306,11 @@ impl JitManager {
     // =========================================================================
     
     /// Record a function call. Returns true if the function should be compiled.
+    /// Also installs any background compiles that finished since the last call.
     pub fn record_call(&mut self, func_id: u32) -> bool {
+        if self.pending > 0 {
+            self.poll_background();
+        }
         let id = func_id as usize;
         let info = self
             .funcs
@@ -8,6 +8,12 @@ use alloc::vec::Vec;
 use alloc::vec;
 #[cfg(not(feature = "std"))]
 use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 #[cfg(feature = "std")]
 use std::string::String;
@@ -16,23 +22,23 @@ use std::string::String;
 use std::vec::Vec;
 
 use vo_runtime::gc::GcRef;
+use vo_runtime::PanicKind;
 use vo_runtime::objects::{array, string};
 
 pub mod helpers;
 mod types;
+mod cancel;
 #[cfg(feature = "std")]
 pub mod island_thread;
 
 pub use helpers::{stack_get, stack_set};
-pub use types::{ExecResult, VmError, VmState, ErrorLocation, TIME_SLICE};
+pub use types::{ExecResult, VmError, VmState, ErrorLocation, TIME_SLICE, DEFAULT_MAX_CALL_DEPTH};
 #[cfg(feature = "std")]
 pub use types::IslandThread;
 
-use helpers::{slice_data_ptr, slice_len, slice_cap, string_len, string_index, runtime_panic, user_panic,
+use helpers::{slice_data_ptr, slice_len, slice_cap, string_len, string_index, runtime_panic, extern_panic, user_panic, panic_unwind, f64_to_u64, min_f64, max_f64,
     ERR_NIL_POINTER, ERR_NIL_MAP_WRITE, ERR_UNHASHABLE_TYPE, ERR_UNCOMPARABLE_TYPE, ERR_NEGATIVE_SHIFT, ERR_NIL_FUNC_CALL, ERR_TYPE_ASSERTION,
-    ERR_SEND_ON_CLOSED, ERR_CLOSE_NIL_CHANNEL, ERR_CLOSE_CLOSED_CHANNEL};
-#[cfg(feature = "jit")]
-use helpers::panic_unwind;
+    ERR_SEND_ON_CLOSED, ERR_CLOSE_NIL_CHANNEL, ERR_CLOSE_CLOSED_CHANNEL, ERR_STACK_OVERFLOW};
 
 use crate::bytecode::Module;
 use crate::exec;
@@ -49,6 +55,12 @@ pub mod jit_mgr;
 
 #[cfg(feature = "jit")]
 pub use jit_mgr::{JitManager, JitConfig};
+#[cfg(feature = "jit")]
+use jit_glue::MAX_JIT_DEPTH;
+
+/// Callback invoked before each interpreted instruction is dispatched.
+/// The fiber's current frame `pc` still points at `inst`.
+pub type TraceHook = Box<dyn FnMut(&Fiber, &Instruction)>;
 
 pub struct Vm {
     /// JIT manager (only available with "jit" feature).
@@ -56,15 +68,69 @@ pub struct Vm {
     /// JIT code memory must remain valid while scheduler/fibers are being dropped.
     #[cfg(feature = "jit")]
     pub jit_mgr: Option<JitManager>,
-    pub module: Option<Module>,
+    /// Shared with the JIT's background compiler and island threads.
+    pub module: Option<Arc<Module>>,
     pub scheduler: Scheduler,
     pub state: VmState,
+    /// Instruction trace hook; `None` costs one branch per instruction.
+    /// JIT-compiled code is not traced.
+    pub trace_hook: Option<TraceHook>,
 }
 
-fn validate_externs_registered(registry: &vo_runtime::ExternRegistry, externs: &[vo_runtime::bytecode::ExternDef]) {
+/// Check that `new` keeps every id `old` may have handed to a running
+/// program: its tables start with `old`'s, it has placeholders for the
+/// `runtime_itabs`, and named types only gain methods.
+#[cfg(feature = "std")]
+fn check_extends(old: &Module, new: &Module, runtime_itabs: &[u32]) -> Result<(), String> {
+    fn prefix<T: PartialEq>(what: &str, old: &[T], new: &[T]) -> Result<(), String> {
+        if new.len() < old.len() || new[..old.len()] != *old {
+            return Err(format!("{} changed", what));
+        }
+        Ok(())
+    }
+
+    // __entry__ comes last and is replaced along with the module
+    let entry = old.entry_func as usize;
+    prefix("functions", &old.functions[..entry], new.functions.get(..entry).unwrap_or(&[]))?;
+    prefix("struct metas", &old.struct_metas, &new.struct_metas)?;
+    prefix("interface metas", &old.interface_metas, &new.interface_metas)?;
+    prefix("runtime types", &old.runtime_types, &new.runtime_types)?;
+    prefix("constants", &old.constants, &new.constants)?;
+    prefix("globals", &old.globals, &new.globals)?;
+    prefix("itabs", &old.itabs, &new.itabs)?;
+    if runtime_itabs.iter().any(|&id| new.itabs.get(id as usize).is_none_or(|itab| !itab.methods.is_empty())) {
+        return Err("runtime itab ids were not reserved".to_string());
+    }
+    // Return slots of an extern may grow with new call sites
+    let extern_names = |m: &Module| m.externs.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    prefix("externs", &extern_names(old), &extern_names(new))?;
+    if new.named_type_metas.len() < old.named_type_metas.len() {
+        return Err("named types changed".to_string());
+    }
+    for (old_meta, new_meta) in old.named_type_metas.iter().zip(&new.named_type_metas) {
+        let kept = old_meta.name == new_meta.name
+            && old_meta.underlying_meta == new_meta.underlying_meta
+            && old_meta.methods.iter().all(|(name, m)| new_meta.methods.get(name) == Some(m));
+        if !kept {
+            return Err(format!("named type {} changed", old_meta.name));
+        }
+    }
+    if old.well_known != new.well_known {
+        return Err("well-known types changed".to_string());
+    }
+    Ok(())
+}
+
+/// Panic if any extern is unregistered, except those `mode` withholds on
+/// purpose; calling one of those panics at run time instead.
+fn validate_externs_registered(
+    registry: &vo_runtime::ExternRegistry,
+    externs: &[vo_runtime::bytecode::ExternDef],
+    mode: vo_stdlib::StdMode,
+) {
     let mut missing: Vec<(usize, &str)> = Vec::new();
     for (id, def) in externs.iter().enumerate() {
-        if !registry.has(id as u32) {
+        if !registry.has(id as u32) && !mode.withholds(&def.name) {
             missing.push((id, def.name.as_str()));
         }
     }
@@ -88,6 +154,7 @@ impl Vm {
             module: None,
             scheduler: Scheduler::new(),
             state: VmState::new(),
+            trace_hook: None,
         }
     }
     
@@ -152,21 +219,37 @@ impl Vm {
 
 impl Vm {
     pub fn module(&self) -> Option<&Module> {
-        self.module.as_ref()
+        self.module.as_deref()
     }
     
     pub fn set_program_args(&mut self, args: Vec<String>) {
         self.state.program_args = args;
     }
 
+    /// Limit how deep a fiber's calls may nest before it panics with
+    /// "stack overflow". Calls made by JIT code count toward the limit.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.state.max_call_depth = depth;
+    }
+
+    /// Choose which stdlib packages the next `load` registers. Defaults to
+    /// `StdMode::Full`; see `StdMode` for what each mode includes.
+    pub fn set_std_mode(&mut self, mode: vo_stdlib::StdMode) {
+        self.state.std_mode = mode;
+    }
+
+    /// Load `module`; pass an `Arc<Module>` to share one already held
+    /// elsewhere instead of copying it.
     #[cfg(feature = "std")]
-    pub fn load(&mut self, module: Module) {
+    pub fn load(&mut self, module: impl Into<Arc<Module>>) {
         self.load_with_extensions(module, None);
     }
     
     #[cfg(not(feature = "std"))]
-    pub fn load(&mut self, module: Module) {
-        vo_stdlib::register_externs(&mut self.state.extern_registry, &module.externs);
+    pub fn load(&mut self, module: impl Into<Arc<Module>>) {
+        let module = module.into();
+        vo_stdlib::register_externs(&mut self.state.extern_registry, &module.externs, self.state.std_mode);
+        cancel::register_externs(&mut self.state.extern_registry, &module.externs);
 
         self.finish_load(module);
     }
@@ -175,58 +258,130 @@ impl Vm {
     #[cfg(feature = "std")]
     pub fn load_with_extensions(
         &mut self,
-        module: Module,
+        module: impl Into<Arc<Module>>,
         ext_loader: Option<&vo_runtime::ext_loader::ExtensionLoader>,
     ) {
+        let module = module.into();
         #[cfg(not(target_arch = "wasm32"))]
         {
-            vo_stdlib::register_externs(&mut self.state.extern_registry, &module.externs);
+            vo_stdlib::register_externs(&mut self.state.extern_registry, &module.externs, self.state.std_mode);
         }
 
         // Register extern functions from extension loader (if provided)
         if let Some(loader) = ext_loader {
             self.state.extern_registry.register_from_extension_loader(loader, &module.externs);
         }
+        cancel::register_externs(&mut self.state.extern_registry, &module.externs);
 
-        validate_externs_registered(&self.state.extern_registry, &module.externs);
+        validate_externs_registered(&self.state.extern_registry, &module.externs, self.state.std_mode);
         
         self.finish_load(module);
     }
     
     /// Finish loading a module (shared by load and load_with_extensions).
-    fn finish_load(&mut self, module: Module) {
+    fn finish_load(&mut self, module: Arc<Module>) {
         let total_global_slots: usize = module.globals.iter().map(|g| g.slots as usize).sum();
         self.state.globals = vec![0u64; total_global_slots];
         // Initialize itab_cache from module's compile-time itabs
         self.state.itab_cache = ItabCache::from_module_itabs(module.itabs.clone());
         // Reset sentinel error cache for new module (prevents cross-module corruption)
         self.state.sentinel_errors = vo_runtime::SentinelErrorCache::new();
+        self.state.cancel_root = core::ptr::null_mut();
+        self.state.cancelled = false;
+        self.state.extern_ref_args = crate::gc_roots::extern_ref_args(&module);
         
         // Initialize JIT manager for this module
         #[cfg(feature = "jit")]
         if let Some(jit_mgr) = self.jit_mgr.as_mut() {
-            jit_mgr.init(module.functions.len());
+            jit_mgr.init(&module);
         }
         
         self.module = Some(module);
     }
 
-    pub fn run(&mut self) -> Result<(), VmError> {
-        let module = self.module.as_ref().ok_or(VmError::NoEntryFunction)?;
-        let entry_func = module.entry_func;
+    /// Swap in `module`, the loaded module recompiled with code appended (see
+    /// `CodegenOptions::file_units`), keeping the heap, globals and
+    /// goroutines. Every id the program may hold must keep its meaning in
+    /// `module`; if one does not, nothing changes and the mismatch is
+    /// returned. Globals `module` adds start zeroed: its new `__init__`
+    /// functions initialize them when run with `run_func`.
+    #[cfg(feature = "std")]
+    pub fn reload(&mut self, module: Module) -> Result<(), String> {
+        let old = self.module.as_ref().ok_or("no module loaded")?;
+        check_extends(old, &module, &self.runtime_itabs())?;
 
-        if entry_func as usize >= module.functions.len() {
-            return Err(VmError::InvalidFunctionId(entry_func));
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            vo_stdlib::register_externs(&mut self.state.extern_registry, &module.externs, self.state.std_mode);
         }
+        cancel::register_externs(&mut self.state.extern_registry, &module.externs);
+        validate_externs_registered(&self.state.extern_registry, &module.externs, self.state.std_mode);
+
+        let total_global_slots: usize = module.globals.iter().map(|g| g.slots as usize).sum();
+        self.state.globals.resize(total_global_slots, 0);
+        self.state.itab_cache.extend_from_module_itabs(&module.itabs);
+        self.state.extern_ref_args = crate::gc_roots::extern_ref_args(&module);
+
+        // Compiled code is dropped and the background compiler restarted on
+        // the new module; functions compile again once hot
+        let module = Arc::new(module);
+        #[cfg(feature = "jit")]
+        if let Some(jit_mgr) = self.jit_mgr.as_mut() {
+            jit_mgr.init(&module);
+        }
+
+        self.module = Some(module);
+        Ok(())
+    }
+
+    /// Itab ids handed out at runtime: those past the module's own itabs or
+    /// on its placeholders. A module for `reload` must keep them free.
+    pub fn runtime_itabs(&self) -> Vec<u32> {
+        let compiled = self.module.as_ref().map_or(&[][..], |m| m.itabs.as_slice());
+        (0..self.state.itab_cache.itab_count())
+            .filter(|&id| compiled.get(id as usize).is_none_or(|itab| itab.methods.is_empty()))
+            .collect()
+    }
+
+    pub fn run(&mut self) -> Result<(), VmError> {
+        let module = self.module.as_ref().ok_or(VmError::NoEntryFunction)?;
+        self.run_func(module.entry_func)
+    }
 
-        let func = &module.functions[entry_func as usize];
+    /// Run function `func_id`, which takes no arguments and returns nothing,
+    /// as the main goroutine until every goroutine is done or blocked.
+    pub fn run_func(&mut self, func_id: u32) -> Result<(), VmError> {
+        let module = self.module.as_ref().ok_or(VmError::NoEntryFunction)?;
+        let func = module.functions.get(func_id as usize).ok_or(VmError::InvalidFunctionId(func_id))?;
         let mut fiber = Fiber::new(0);
-        fiber.push_frame(entry_func, func.local_slots, 0, 0);
-        self.scheduler.spawn(fiber);
+        fiber.push_frame(func_id, func.local_slots, 0, 0);
+        self.scheduler.main_fiber = Some(self.scheduler.spawn(fiber));
 
-        self.run_scheduling_loop(None)
+        self.run_scheduling_loop(None)?;
+        if self.state.detect_deadlock && self.scheduler.is_deadlocked() && !self.awaiting_cancel() {
+            return Err(VmError::Deadlock);
+        }
+        Ok(())
     }
     
+    /// Run the entry function, writing one line per interpreted instruction:
+    /// `pc: opcode a b c | slot dump` (slots of the current frame).
+    #[cfg(feature = "std")]
+    pub fn run_traced<W: std::io::Write + 'static>(&mut self, mut writer: W) -> Result<(), VmError> {
+        self.trace_hook = Some(Box::new(move |fiber: &Fiber, inst: &Instruction| {
+            let Some(frame) = fiber.current_frame() else { return };
+            let slots = fiber.stack.get(frame.bp..).unwrap_or(&[]);
+            let _ = writeln!(
+                writer,
+                "{:>4}: {:?} {} {} {} | {:?}",
+                frame.pc, inst.opcode(), inst.a, inst.b, inst.c, slots
+            );
+        }));
+        let result = self.run();
+        self.trace_hook = None;
+        result
+    }
+
     /// Run existing runnable fibers without spawning entry fiber.
     /// Used for event handling after initial run.
     pub fn run_scheduled(&mut self) -> Result<(), VmError> {
@@ -283,6 +438,11 @@ impl Vm {
             };
 
             let result = self.run_fiber(fiber_id);
+            #[cfg(feature = "std")]
+            if let Some(profiler) = self.state.profiler.as_mut() {
+                profiler.sample(self.scheduler.get_fiber(fiber_id), None);
+            }
+            self.gc_step();
             
             match result {
                 ExecResult::Continue | ExecResult::Yield | ExecResult::Osr(_, _, _) => {
@@ -295,9 +455,9 @@ impl Vm {
                     self.scheduler.block_current();
                 }
                 ExecResult::Panic => {
-                    let (msg, loc_tuple) = self.scheduler.kill_current();
+                    let (msg, loc_tuple, kind) = self.scheduler.kill_current();
                     let loc = loc_tuple.map(|(func_id, pc)| ErrorLocation { func_id, pc });
-                    return Err(VmError::PanicUnwound { msg, loc });
+                    return Err(VmError::PanicUnwound { msg, loc, kind });
                 }
             }
         }
@@ -314,6 +474,7 @@ impl Vm {
         module: &Module,
         scheduler: &mut Scheduler,
     ) -> ExecResult {
+        fiber.chan_blocked = matches!(result, exec::ChanResult::Yield);
         match result {
             exec::ChanResult::Continue => ExecResult::Continue,
             exec::ChanResult::Yield => {
@@ -321,39 +482,62 @@ impl Vm {
                 ExecResult::Block
             }
             exec::ChanResult::Wake(id) => {
-                scheduler.wake_fiber(crate::scheduler::FiberId::from_raw(id));
+                Self::wake_claimed(scheduler, id);
                 // Channel operation made another fiber runnable; yield so it can run.
                 ExecResult::Yield
             }
             exec::ChanResult::WakeMultiple(ids) => {
                 for id in ids {
-                    scheduler.wake_fiber(crate::scheduler::FiberId::from_raw(id));
+                    let id = crate::scheduler::FiberId::from_raw(id);
+                    // Woken by close: a parked sender must retry and see the channel closed
+                    scheduler.get_fiber_mut(id).chan_blocked = false;
+                    scheduler.wake_fiber(id);
                 }
                 // Channel operation made other fibers runnable; yield so they can run.
                 ExecResult::Yield
             }
             exec::ChanResult::SendOnClosed => {
-                runtime_panic(gc, fiber, stack, module, ERR_SEND_ON_CLOSED.to_string())
+                runtime_panic(gc, fiber, stack, module, PanicKind::SendOnClosedChannel, ERR_SEND_ON_CLOSED.to_string())
             }
             exec::ChanResult::CloseNil => {
-                runtime_panic(gc, fiber, stack, module, ERR_CLOSE_NIL_CHANNEL.to_string())
+                runtime_panic(gc, fiber, stack, module, PanicKind::Other, ERR_CLOSE_NIL_CHANNEL.to_string())
             }
             exec::ChanResult::CloseClosed => {
-                runtime_panic(gc, fiber, stack, module, ERR_CLOSE_CLOSED_CHANNEL.to_string())
+                runtime_panic(gc, fiber, stack, module, PanicKind::Other, ERR_CLOSE_CLOSED_CHANNEL.to_string())
             }
         }
     }
     
+    /// Claim hook for channel operations: see `exec::claim_waiter`.
+    fn claim_waiter(scheduler: &mut Scheduler) -> impl FnMut(u64, GcRef, crate::fiber::SelectCaseKind) -> bool + '_ {
+        move |waiter, ch, kind| {
+            let id = crate::scheduler::FiberId::from_raw(waiter as u32);
+            exec::claim_waiter(scheduler.get_fiber_mut(id), ch, kind)
+        }
+    }
+
+    /// Wake the fiber on the other end of a completed channel operation,
+    /// first finishing it if it was a parked select.
+    fn wake_claimed(scheduler: &mut Scheduler, raw_id: u32) {
+        let id = crate::scheduler::FiberId::from_raw(raw_id);
+        exec::select_done(scheduler.get_fiber_mut(id), raw_id as u64);
+        scheduler.wake_fiber(id);
+    }
+
     /// Run one round of scheduler to let other fibers make progress.
-    /// Used when trampoline fiber blocks on channel operations.
+    /// Used when trampoline fiber blocks on channel operations, or when an
+    /// extern called from JIT code suspends where the fiber cannot park.
     #[cfg(feature = "jit")]
     fn run_scheduler_round(&mut self) {
         // Temporarily disable JIT to prevent nested trampoline calls
         #[cfg(feature = "jit")]
         let jit_mgr = self.jit_mgr.take();
+        // The fiber running the JIT code is still the current one afterwards
+        let current = self.scheduler.current;
         
         let _ = self.run_scheduling_loop(Some(1000));
         
+        self.scheduler.current = current;
         // Restore JIT manager
         #[cfg(feature = "jit")]
         { self.jit_mgr = jit_mgr; }
@@ -363,7 +547,7 @@ impl Vm {
     /// Uses FiberId for type-safe fiber access.
     fn run_fiber(&mut self, fiber_id: crate::scheduler::FiberId) -> ExecResult {
         let module_ptr = match &self.module {
-            Some(m) => m as *const Module,
+            Some(m) => &**m as *const Module,
             None => return ExecResult::Done,
         };
         // SAFETY: module_ptr is valid for the duration of run_fiber.
@@ -403,6 +587,9 @@ impl Vm {
         for _ in 0..TIME_SLICE {
             // SAFETY: codegen guarantees Return instruction at end of every function
             let inst = unsafe { *code.get_unchecked(frame.pc) };
+            if let Some(hook) = self.trace_hook.as_mut() {
+                hook(unsafe { &*fiber_ptr }, &inst);
+            }
             frame.pc += 1;
 
             // Single dispatch - all instructions handled here
@@ -483,28 +670,28 @@ impl Vm {
                     if exec::exec_ptr_get(stack, bp, &inst) {
                         ExecResult::Continue
                     } else {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_POINTER.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilPointerDereference, ERR_NIL_POINTER.to_string())
                     }
                 }
                 Opcode::PtrSet => {
                     if exec::exec_ptr_set(&stack, bp, &inst, &mut self.state.gc) {
                         ExecResult::Continue
                     } else {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_POINTER.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilPointerDereference, ERR_NIL_POINTER.to_string())
                     }
                 }
                 Opcode::PtrGetN => {
                     if exec::exec_ptr_get_n(stack, bp, &inst) {
                         ExecResult::Continue
                     } else {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_POINTER.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilPointerDereference, ERR_NIL_POINTER.to_string())
                     }
                 }
                 Opcode::PtrSetN => {
-                    if exec::exec_ptr_set_n(&stack, bp, &inst) {
+                    if exec::exec_ptr_set_n(&stack, bp, &inst, &mut self.state.gc) {
                         ExecResult::Continue
                     } else {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_POINTER.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilPointerDereference, ERR_NIL_POINTER.to_string())
                     }
                 }
                 Opcode::PtrAdd => {
@@ -539,7 +726,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize) as i64;
                     let b = stack_get(stack, bp + inst.c as usize) as i64;
                     if b == 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, "runtime error: integer divide by zero".to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::DivisionByZero, "runtime error: integer divide by zero".to_string())
                     } else {
                         stack_set(stack, bp + inst.a as usize, a.wrapping_div(b) as u64);
                         ExecResult::Continue
@@ -549,7 +736,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize) as i64;
                     let b = stack_get(stack, bp + inst.c as usize) as i64;
                     if b == 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, "runtime error: integer divide by zero".to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::DivisionByZero, "runtime error: integer divide by zero".to_string())
                     } else {
                         stack_set(stack, bp + inst.a as usize, a.wrapping_rem(b) as u64);
                         ExecResult::Continue
@@ -559,7 +746,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize);
                     let b = stack_get(stack, bp + inst.c as usize);
                     if b == 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, "runtime error: integer divide by zero".to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::DivisionByZero, "runtime error: integer divide by zero".to_string())
                     } else {
                         stack_set(stack, bp + inst.a as usize, a.wrapping_div(b));
                         ExecResult::Continue
@@ -569,7 +756,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize);
                     let b = stack_get(stack, bp + inst.c as usize);
                     if b == 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, "runtime error: integer divide by zero".to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::DivisionByZero, "runtime error: integer divide by zero".to_string())
                     } else {
                         stack_set(stack, bp + inst.a as usize, a.wrapping_rem(b));
                         ExecResult::Continue
@@ -611,6 +798,28 @@ impl Vm {
                     stack_set(stack, bp + inst.a as usize, (-a).to_bits());
                     ExecResult::Continue
                 }
+                Opcode::SqrtF => {
+                    let a = f64::from_bits(stack_get(stack, bp + inst.b as usize));
+                    stack_set(stack, bp + inst.a as usize, a.sqrt().to_bits());
+                    ExecResult::Continue
+                }
+                Opcode::AbsF => {
+                    let a = f64::from_bits(stack_get(stack, bp + inst.b as usize));
+                    stack_set(stack, bp + inst.a as usize, a.abs().to_bits());
+                    ExecResult::Continue
+                }
+                Opcode::MinF => {
+                    let a = f64::from_bits(stack_get(stack, bp + inst.b as usize));
+                    let b = f64::from_bits(stack_get(stack, bp + inst.c as usize));
+                    stack_set(stack, bp + inst.a as usize, min_f64(a, b).to_bits());
+                    ExecResult::Continue
+                }
+                Opcode::MaxF => {
+                    let a = f64::from_bits(stack_get(stack, bp + inst.b as usize));
+                    let b = f64::from_bits(stack_get(stack, bp + inst.c as usize));
+                    stack_set(stack, bp + inst.a as usize, max_f64(a, b).to_bits());
+                    ExecResult::Continue
+                }
 
                 // Integer comparison - inline
                 Opcode::EqI => {
@@ -748,7 +957,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize);
                     let b = stack_get(stack, bp + inst.c as usize) as i64;
                     if b < 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NEGATIVE_SHIFT.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NegativeShift, ERR_NEGATIVE_SHIFT.to_string())
                     } else {
                         // Go semantics: shift >= 64 returns 0
                         let result = if b >= 64 { 0 } else { a.wrapping_shl(b as u32) };
@@ -760,7 +969,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize) as i64;
                     let b = stack_get(stack, bp + inst.c as usize) as i64;
                     if b < 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NEGATIVE_SHIFT.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NegativeShift, ERR_NEGATIVE_SHIFT.to_string())
                     } else {
                         // Go semantics: signed right shift >= 64 returns 0 (positive) or -1 (negative)
                         let result = if b >= 64 { if a < 0 { -1i64 } else { 0i64 } } else { a.wrapping_shr(b as u32) };
@@ -772,7 +981,7 @@ impl Vm {
                     let a = stack_get(stack, bp + inst.b as usize);
                     let b = stack_get(stack, bp + inst.c as usize) as i64;
                     if b < 0 {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NEGATIVE_SHIFT.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NegativeShift, ERR_NEGATIVE_SHIFT.to_string())
                     } else {
                         // Go semantics: unsigned right shift >= 64 returns 0
                         let result = if b >= 64 { 0 } else { a.wrapping_shr(b as u32) };
@@ -808,33 +1017,44 @@ impl Vm {
                     }
                     ExecResult::Continue
                 }
+                Opcode::JumpTable => {
+                    // pc already points at entry 0; out of range skips all entries
+                    let index = stack_get(stack, bp + inst.a as usize);
+                    frame.pc += index.min(inst.b as u64) as usize;
+                    ExecResult::Continue
+                }
 
                 // Call instructions
+                Opcode::Call | Opcode::CallClosure | Opcode::CallIface
+                    if fiber.depth() >= self.state.max_call_depth =>
+                {
+                    runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::StackOverflow, ERR_STACK_OVERFLOW.to_string())
+                }
                 #[cfg(feature = "jit")]
                 Opcode::Call => {
                     let target_func_id = (inst.a as u32) | ((inst.flags as u32) << 16);
-                    let arg_start = inst.b;
+                    let buf = inst.b;
                     let arg_slots = (inst.c >> 8) as usize;
                     let call_ret_slots = (inst.c & 0xFF) as usize;
                     
-                    // Try JIT via resolve_call
+                    // Try JIT via resolve_call, unless JIT calls already fill the native stack
                     let target_func = &module.functions[target_func_id as usize];
                     let jit_func = self.jit_mgr.as_mut()
+                        .filter(|_| self.state.jit_depth < MAX_JIT_DEPTH)
                         .and_then(|mgr| mgr.resolve_call(target_func_id, target_func, module));
                     
-                    if let Some(jit_func) = jit_func {
-                        // Use func_def.ret_slots for buffer allocation (JIT writes based on func definition)
-                        // but only copy back call_ret_slots to caller's stack
-                        let func_ret_slots = target_func.ret_slots as usize;
-                        let result = self.call_jit_inline(fiber_id, jit_func, arg_start, arg_slots, func_ret_slots, call_ret_slots);
+                    // Use func_def.ret_slots for buffer allocation (JIT writes based on func definition)
+                    // but only copy back call_ret_slots to caller's stack
+                    let func_ret_slots = target_func.ret_slots as usize;
+                    // None: no JIT version, or it deoptimized - interpret the call
+                    let jit_result = jit_func.and_then(|jit_func| {
+                        self.call_jit_inline(fiber_id, target_func_id, jit_func, buf, arg_slots, func_ret_slots, call_ret_slots)
+                    });
+                    match jit_result {
                         // JIT already set fiber.panic_state, just run unwind to execute defers
-                        if matches!(result, ExecResult::Panic) {
-                            panic_unwind(fiber, stack, module)
-                        } else {
-                            result
-                        }
-                    } else {
-                        exec::exec_call(stack, &mut fiber.frames, &inst, module)
+                        Some(ExecResult::Panic) => panic_unwind(fiber, stack, module),
+                        Some(result) => result,
+                        None => exec::exec_call(stack, &mut fiber.frames, &inst, module),
                     }
                 }
                 #[cfg(not(feature = "jit"))]
@@ -843,10 +1063,13 @@ impl Vm {
                 }
                 Opcode::CallExtern => {
                     let mut extern_panic_msg: Option<String> = None;
+                    let mut extern_resume = fiber.extern_resume.take();
                     // Get pointers for closure calling capability
                     let vm_ptr = self as *mut Vm as *mut core::ffi::c_void;
                     let fiber_ptr = fiber as *mut crate::fiber::Fiber as *mut core::ffi::c_void;
                     let closure_call_fn: Option<vo_runtime::ffi::ClosureCallFn> = Some(closure_call_trampoline);
+                    self.gc_before_extern(inst.b as u32);
+                    self.state.extern_calls += 1;
                     let result = exec::exec_call_extern(
                         stack,
                         bp,
@@ -868,13 +1091,22 @@ impl Vm {
                         &module.well_known,
                         &self.state.program_args,
                         &mut self.state.sentinel_errors,
+                        &mut extern_resume,
                     );
+                    self.state.extern_calls -= 1;
+                    if extern_resume.is_some() {
+                        // Suspended by yield_and_resume: rewind PC so the extern
+                        // is re-entered with its state after other fibers run
+                        fiber.extern_resume = extern_resume;
+                        frame.pc -= 1;
+                    }
                     // Convert extern panic to recoverable runtime panic
                     if matches!(result, ExecResult::Panic) {
                         if let Some(msg) = extern_panic_msg {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, msg)
+                            extern_panic(&mut self.state.gc, fiber, stack, module, msg)
                         } else {
-                            result
+                            // Repanic: a closure the extern called set the panic
+                            panic_unwind(fiber, stack, module)
                         }
                     } else {
                         result
@@ -883,7 +1115,7 @@ impl Vm {
                 Opcode::CallClosure => {
                     let closure_ref = stack[bp + inst.a as usize] as vo_runtime::gc::GcRef;
                     if closure_ref.is_null() {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_FUNC_CALL.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilPointerDereference, ERR_NIL_FUNC_CALL.to_string())
                     } else {
                         exec::exec_call_closure(stack, &mut fiber.frames, &inst, module)
                     }
@@ -922,7 +1154,7 @@ impl Vm {
                     let len = if s.is_null() { 0 } else { string_len(s) };
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -983,6 +1215,18 @@ impl Vm {
                     stack_set(stack, bp + inst.a as usize + 1, width as u64);
                     ExecResult::Continue
                 }
+                Opcode::StrContains => {
+                    let s = stack_get(stack, bp + inst.b as usize) as GcRef;
+                    let sub = stack_get(stack, bp + inst.c as usize) as GcRef;
+                    stack_set(stack, bp + inst.a as usize, string::contains(s, sub) as u64);
+                    ExecResult::Continue
+                }
+                Opcode::StrIndexOf => {
+                    let s = stack_get(stack, bp + inst.b as usize) as GcRef;
+                    let sub = stack_get(stack, bp + inst.c as usize) as GcRef;
+                    stack_set(stack, bp + inst.a as usize, string::index_of(s, sub) as u64);
+                    ExecResult::Continue
+                }
 
                 // Array operations
                 Opcode::ArrayNew => {
@@ -997,7 +1241,7 @@ impl Vm {
                     let len = array::len(arr);
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -1043,7 +1287,7 @@ impl Vm {
                     let len = array::len(arr);
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -1073,6 +1317,7 @@ impl Vm {
                             }
                         }
                     }
+                    self.state.gc.write_barrier(arr, core::ptr::null_mut());
                     ExecResult::Continue
                     }
                 }
@@ -1080,18 +1325,26 @@ impl Vm {
                     // Get element address: a=dst, b=array_gcref, c=index, flags=elem_bytes
                     let arr = stack_get(stack, bp + inst.b as usize) as GcRef;
                     let idx = stack_get(stack, bp + inst.c as usize) as usize;
-                    let elem_bytes = inst.flags as usize;
-                    let base = array::data_ptr_bytes(arr);
-                    let addr = unsafe { base.add(idx * elem_bytes) } as u64;
-                    stack_set(stack, bp + inst.a as usize, addr);
-                    ExecResult::Continue
+                    let len = array::len(arr);
+                    if idx >= len {
+                        runtime_panic(
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
+                            format!("runtime error: index out of range [{}] with length {}", idx, len)
+                        )
+                    } else {
+                        let elem_bytes = inst.flags as usize;
+                        let base = array::data_ptr_bytes(arr);
+                        let addr = unsafe { base.add(idx * elem_bytes) } as u64;
+                        stack_set(stack, bp + inst.a as usize, addr);
+                        ExecResult::Continue
+                    }
                 }
 
                 // Slice operations
                 Opcode::SliceNew => {
                     match exec::exec_slice_new(stack, bp, &inst, &mut self.state.gc) {
                         Ok(()) => ExecResult::Continue,
-                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, msg),
+                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, msg),
                     }
                 }
                 Opcode::SliceGet => {
@@ -1102,7 +1355,7 @@ impl Vm {
                     let len = if s.is_null() { 0 } else { slice_len(s) };
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -1147,7 +1400,7 @@ impl Vm {
                     let len = if s.is_null() { 0 } else { slice_len(s) };
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -1176,6 +1429,7 @@ impl Vm {
                                 }
                             }
                         }
+                        self.state.gc.write_barrier(base as GcRef, core::ptr::null_mut());
                         ExecResult::Continue
                     }
                 }
@@ -1198,7 +1452,7 @@ impl Vm {
                         let lo = stack_get(stack, bp + inst.c as usize);
                         let hi = stack_get(stack, bp + inst.c as usize + 1);
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: slice bounds out of range [{}:{}]", lo, hi)
                         )
                     }
@@ -1211,11 +1465,19 @@ impl Vm {
                     // Get element address: a=dst, b=slice_reg, c=index, flags=elem_bytes
                     let s = stack_get(stack, bp + inst.b as usize) as GcRef;
                     let idx = stack_get(stack, bp + inst.c as usize) as usize;
-                    let elem_bytes = inst.flags as usize;
-                    let base = slice_data_ptr(s);
-                    let addr = unsafe { base.add(idx * elem_bytes) } as u64;
-                    stack_set(stack, bp + inst.a as usize, addr);
-                    ExecResult::Continue
+                    let len = if s.is_null() { 0 } else { slice_len(s) };
+                    if idx >= len {
+                        runtime_panic(
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
+                            format!("runtime error: index out of range [{}] with length {}", idx, len)
+                        )
+                    } else {
+                        let elem_bytes = inst.flags as usize;
+                        let base = slice_data_ptr(s);
+                        let addr = unsafe { base.add(idx * elem_bytes) } as u64;
+                        stack_set(stack, bp + inst.a as usize, addr);
+                        ExecResult::Continue
+                    }
                 }
 
                 // Map operations
@@ -1231,11 +1493,11 @@ impl Vm {
                     // nil map write panics (Go semantics)
                     let m = stack_get(stack, bp + inst.a as usize) as GcRef;
                     if m.is_null() {
-                        runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_NIL_MAP_WRITE.to_string())
+                        runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::NilMapWrite, ERR_NIL_MAP_WRITE.to_string())
                     } else {
                         let ok = exec::exec_map_set(&stack, bp, &inst, &mut self.state.gc, Some(module));
                         if !ok {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_UNHASHABLE_TYPE.to_string())
+                            runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, ERR_UNHASHABLE_TYPE.to_string())
                         } else {
                             ExecResult::Continue
                         }
@@ -1255,7 +1517,7 @@ impl Vm {
                     ExecResult::Continue
                 }
                 Opcode::MapIterInit => {
-                    exec::exec_map_iter_init(stack, bp, &inst);
+                    exec::exec_map_iter_init(stack, bp, &inst, &mut self.state.gc, self.state.sorted_map_iter);
                     ExecResult::Continue
                 }
                 Opcode::MapIterNext => {
@@ -1267,18 +1529,19 @@ impl Vm {
                 Opcode::ChanNew => {
                     match exec::exec_chan_new(stack, bp, &inst, &mut self.state.gc) {
                         Ok(()) => ExecResult::Continue,
-                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, msg),
+                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, msg),
                     }
                 }
                 Opcode::ChanSend => {
+                    self.state.gc.write_barrier(stack[bp + inst.a as usize] as GcRef, core::ptr::null_mut());
                     Self::handle_chan_result(
-                        exec::exec_chan_send(&stack, bp, fiber_id.to_raw(), &inst),
+                        exec::exec_chan_send(&stack, bp, fiber_id.to_raw(), &inst, fiber.chan_blocked, Self::claim_waiter(&mut self.scheduler)),
                         &mut self.state.gc, fiber, stack, module, &mut self.scheduler,
                     )
                 }
                 Opcode::ChanRecv => {
                     Self::handle_chan_result(
-                        exec::exec_chan_recv(stack, bp, fiber_id.to_raw(), &inst),
+                        exec::exec_chan_recv(stack, bp, fiber_id.to_raw(), &inst, Self::claim_waiter(&mut self.scheduler)),
                         &mut self.state.gc, fiber, stack, module, &mut self.scheduler,
                     )
                 }
@@ -1311,15 +1574,28 @@ impl Vm {
                     ExecResult::Continue
                 }
                 Opcode::SelectExec => {
-                    match exec::exec_select_exec(stack, bp, &mut fiber.select_state, &inst) {
+                    // Sent values are held by the channel
+                    if let Some(select) = &fiber.select_state {
+                        for case in select.cases.iter().filter(|c| matches!(c.kind, crate::fiber::SelectCaseKind::Send)) {
+                            self.state.gc.write_barrier(stack[bp + case.chan_reg as usize] as GcRef, core::ptr::null_mut());
+                        }
+                    }
+                    let result = exec::exec_select_exec(stack, bp, fiber_id.to_raw(), &mut fiber.select_state, &inst, Self::claim_waiter(&mut self.scheduler));
+                    fiber.chan_blocked = matches!(result, exec::SelectResult::Block);
+                    match result {
                         exec::SelectResult::Continue => ExecResult::Continue,
+                        exec::SelectResult::Wake(id) => {
+                            Self::wake_claimed(&mut self.scheduler, id);
+                            // Let the other end run, as for a plain channel operation
+                            ExecResult::Yield
+                        }
                         exec::SelectResult::Block => {
                             // Rewind PC so SelectExec is re-executed after other fibers run
                             frame.pc -= 1;
                             ExecResult::Block
                         }
                         exec::SelectResult::SendOnClosed => {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_SEND_ON_CLOSED.to_string())
+                            runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::SendOnClosedChannel, ERR_SEND_ON_CLOSED.to_string())
                         }
                     }
                 }
@@ -1374,7 +1650,7 @@ impl Vm {
                     let result = exec::exec_iface_assert(stack, bp, &inst, &mut self.state.itab_cache, module);
                     if matches!(result, ExecResult::Panic) {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module, ERR_TYPE_ASSERTION.to_string()
+                            &mut self.state.gc, fiber, stack, module, PanicKind::TypeAssertionFailed, ERR_TYPE_ASSERTION.to_string()
                         )
                     } else {
                         result
@@ -1384,7 +1660,7 @@ impl Vm {
                     let result = exec::exec_iface_eq(stack, bp, &inst, module);
                     if matches!(result, ExecResult::Panic) {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module, ERR_UNCOMPARABLE_TYPE.to_string()
+                            &mut self.state.gc, fiber, stack, module, PanicKind::Other, ERR_UNCOMPARABLE_TYPE.to_string()
                         )
                     } else {
                         result
@@ -1393,13 +1669,15 @@ impl Vm {
 
                 // Type conversion - inline
                 Opcode::ConvI2F => {
-                    let a = stack_get(stack, bp + inst.b as usize) as i64;
-                    stack_set(stack, bp + inst.a as usize, (a as f64).to_bits());
+                    let a = stack_get(stack, bp + inst.b as usize);
+                    let f = if inst.flags == 1 { a as f64 } else { a as i64 as f64 };
+                    stack_set(stack, bp + inst.a as usize, f.to_bits());
                     ExecResult::Continue
                 }
                 Opcode::ConvF2I => {
                     let a = f64::from_bits(stack_get(stack, bp + inst.b as usize));
-                    stack_set(stack, bp + inst.a as usize, a as i64 as u64);
+                    let r = if inst.flags == 1 { f64_to_u64(a) } else { a as i64 as u64 };
+                    stack_set(stack, bp + inst.a as usize, r);
                     ExecResult::Continue
                 }
                 Opcode::ConvF64F32 => {
@@ -1435,7 +1713,7 @@ impl Vm {
                     let len = stack_get(stack, bp + inst.b as usize) as usize;
                     if idx >= len {
                         runtime_panic(
-                            &mut self.state.gc, fiber, stack, module,
+                            &mut self.state.gc, fiber, stack, module, PanicKind::IndexOutOfBounds,
                             format!("runtime error: index out of range [{}] with length {}", idx, len)
                         )
                     } else {
@@ -1470,7 +1748,7 @@ impl Vm {
                     
                     // Start island thread with the command receiver and registry
                     let cmd_rx = result.command_rx;
-                    let module_arc = std::sync::Arc::new(module.clone());
+                    let module_arc = Arc::clone(self.module.as_ref().expect("module loaded"));
                     let registry_clone = registry.clone();
                     let join_handle = std::thread::spawn(move || {
                         island_thread::run_island_thread(next_id, module_arc, cmd_rx, registry_clone);
@@ -1493,7 +1771,7 @@ impl Vm {
                 Opcode::PortNew => {
                     match exec::exec_port_new(stack, bp, &inst, &mut self.state.gc) {
                         Ok(()) => ExecResult::Continue,
-                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, msg),
+                        Err(msg) => runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, msg),
                     }
                 }
                 #[cfg(feature = "std")]
@@ -1508,14 +1786,14 @@ impl Vm {
                             ExecResult::Continue
                         }
                         exec::PortResult::SendOnClosed => {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_SEND_ON_CLOSED.to_string())
+                            runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::SendOnClosedChannel, ERR_SEND_ON_CLOSED.to_string())
                         }
                         _ => ExecResult::Continue,
                     }
                 }
                 #[cfg(not(feature = "std"))]
                 Opcode::PortSend => {
-                    runtime_panic(&mut self.state.gc, fiber, stack, module, "Port not supported in no_std".to_string())
+                    runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, "Port not supported in no_std".to_string())
                 }
                 #[cfg(feature = "std")]
                 Opcode::PortRecv => {
@@ -1533,14 +1811,14 @@ impl Vm {
                 }
                 #[cfg(not(feature = "std"))]
                 Opcode::PortRecv => {
-                    runtime_panic(&mut self.state.gc, fiber, stack, module, "Port not supported in no_std".to_string())
+                    runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, "Port not supported in no_std".to_string())
                 }
                 #[cfg(feature = "std")]
                 Opcode::PortClose => {
                     match exec::exec_port_close(stack, bp, &inst) {
                         exec::PortResult::Continue => ExecResult::Continue,
                         exec::PortResult::CloseNil => {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_CLOSE_NIL_CHANNEL.to_string())
+                            runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, ERR_CLOSE_NIL_CHANNEL.to_string())
                         }
                         exec::PortResult::Closed(waiters) => {
                             for waiter in &waiters {
@@ -1556,7 +1834,7 @@ impl Vm {
                     match exec::exec_port_close(stack, bp, &inst) {
                         exec::PortResult::Continue => ExecResult::Continue,
                         exec::PortResult::CloseNil => {
-                            runtime_panic(&mut self.state.gc, fiber, stack, module, ERR_CLOSE_NIL_CHANNEL.to_string())
+                            runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, ERR_CLOSE_NIL_CHANNEL.to_string())
                         }
                         _ => ExecResult::Continue,
                     }
@@ -1630,6 +1908,12 @@ impl Vm {
 
                 Opcode::Invalid => ExecResult::Panic,
             };
+            #[cfg(debug_assertions)]
+            let result = if result == ExecResult::Continue && self.state.gc.stress_pending() {
+                self.gc_stress(fiber, stack, module)
+            } else {
+                result
+            };
 
             match result {
                 ExecResult::Continue => continue,
@@ -1648,6 +1932,20 @@ impl Vm {
         ExecResult::Continue
     }
 
+    /// Act on `Gc` stress settings between instructions: raise an injected
+    /// allocation failure, or run a forced collection.
+    #[cfg(debug_assertions)]
+    fn gc_stress(&mut self, fiber: &mut Fiber, stack: &mut Vec<u64>, module: &Module) -> ExecResult {
+        if self.state.gc.take_injected_oom() {
+            return runtime_panic(&mut self.state.gc, fiber, stack, module, PanicKind::Other, "runtime error: out of memory".to_string());
+        }
+        // Externs hold references in Rust locals, so wait until they return
+        if self.state.extern_calls == 0 && self.state.gc.take_forced_collect() {
+            self.collect_garbage();
+        }
+        ExecResult::Continue
+    }
+
     /// Execute a closure synchronously from extern function callback.
     /// Returns true on success, false on panic.
     pub fn execute_closure_sync(
@@ -1657,9 +1955,36 @@ impl Vm {
         ret: *mut u64,
         ret_count: u32,
     ) -> bool {
+        self.execute_closure_sync_with_caller(func_id, args, ret, ret_count, core::ptr::null_mut())
+    }
+
+    /// Like `execute_closure_sync`, but on panic moves the panic onto
+    /// `caller_fiber` (if non-null), so an extern can re-raise it with
+    /// `ExternResult::Repanic`.
+    fn execute_closure_sync_with_caller(
+        &mut self,
+        func_id: u32,
+        args: &[u64],
+        ret: *mut u64,
+        ret_count: u32,
+        caller_fiber: *mut crate::fiber::Fiber,
+    ) -> bool {
+        self.run_closure_sync(func_id, args, ret, ret_count, caller_fiber).is_ok()
+    }
+
+    /// Shared by the closure calls above; a panic is returned as
+    /// `VmError::PanicUnwound` carrying its message and location.
+    fn run_closure_sync(
+        &mut self,
+        func_id: u32,
+        args: &[u64],
+        ret: *mut u64,
+        ret_count: u32,
+        caller_fiber: *mut crate::fiber::Fiber,
+    ) -> Result<(), VmError> {
         let module = match &self.module {
-            Some(m) => m as *const Module,
-            None => return false,
+            Some(m) => &**m as *const Module,
+            None => return Err(VmError::NoEntryFunction),
         };
         let module = unsafe { &*module };
         
@@ -1697,17 +2022,56 @@ impl Vm {
             }
         };
         
-        if success {
+        let result = if success {
             let fiber = self.scheduler.trampoline_fiber(trampoline_id);
             for i in 0..(ret_count as usize) {
                 if i < fiber.stack.len() {
                     unsafe { *ret.add(i) = fiber.stack[i] };
                 }
             }
-        }
+            Ok(())
+        } else {
+            let trampoline_fiber = self.scheduler.trampoline_fiber_mut(trampoline_id);
+            let err = VmError::PanicUnwound {
+                msg: trampoline_fiber.panic_message(),
+                loc: trampoline_fiber.panic_loc.map(|(func_id, pc)| ErrorLocation { func_id, pc }),
+                kind: trampoline_fiber.panic_kind(),
+            };
+            if !caller_fiber.is_null() {
+                let caller_fiber = unsafe { &mut *caller_fiber };
+                let panic_state = trampoline_fiber.panic_state.take().unwrap_or(crate::fiber::PanicState::Fatal);
+                // A new panic on the caller, so its own defers can recover it
+                caller_fiber.panic_generation += 1;
+                caller_fiber.panic_state = Some(panic_state);
+                caller_fiber.panic_loc = trampoline_fiber.panic_loc;
+            }
+            Err(err)
+        };
         
         self.scheduler.release_trampoline_fiber(trampoline_id);
-        success
+        result
+    }
+
+    /// Call a Vo closure from native code and decode its return value.
+    /// `args` are the closure's parameter slots; captures and receivers are added here.
+    pub fn call_closure_ret<T: vo_runtime::FromSlots>(&mut self, closure: GcRef, args: &[u64]) -> Result<T, VmError> {
+        use vo_runtime::objects::closure;
+
+        let func_id = closure::func_id(closure);
+        let module = self.module.as_ref().ok_or(VmError::NoEntryFunction)?;
+        let func_def = module.functions.get(func_id as usize)
+            .ok_or(VmError::InvalidFunctionId(func_id))?;
+        let ret_slots = func_def.ret_slots as usize;
+        if ret_slots != T::SLOTS {
+            return Err(VmError::ReturnSlotMismatch { func_id, expected: T::SLOTS, found: ret_slots });
+        }
+        let full_args = helpers::build_closure_args(
+            closure as u64, closure, func_def, args.as_ptr(), args.len() as u32,
+        );
+
+        let mut ret = vec![0u64; ret_slots];
+        self.run_closure_sync(func_id, &full_args, ret.as_mut_ptr(), ret_slots as u32, core::ptr::null_mut())?;
+        Ok(T::from_slots(&ret))
     }
 
 }
@@ -1723,7 +2087,7 @@ impl Default for Vm {
 /// This allows extern functions like dyn_call_closure to execute closures.
 pub extern "C" fn closure_call_trampoline(
     vm: *mut core::ffi::c_void,
-    _caller_fiber: *mut core::ffi::c_void,
+    caller_fiber: *mut core::ffi::c_void,
     closure_ref: u64,
     args: *const u64,
     arg_count: u32,
@@ -1745,13 +2109,20 @@ pub extern "C" fn closure_call_trampoline(
         let func_def = &module.functions[func_id as usize];
         let full_args = build_closure_args(closure_ref, closure_gcref, func_def, args, arg_count);
         
-        vm.execute_closure_sync(func_id, &full_args, ret, ret_count)
+        vm.execute_closure_sync_with_caller(func_id, &full_args, ret, ret_count, caller_fiber as *mut Fiber)
     }));
     
     #[cfg(feature = "std")]
     return match result {
         Ok(true) => vo_runtime::ffi::ClosureCallResult::Ok,
-        _ => vo_runtime::ffi::ClosureCallResult::Panic
+        Ok(false) => vo_runtime::ffi::ClosureCallResult::Panic,
+        Err(_) => {
+            // A Rust panic inside the VM can't be recovered by Vo code
+            if let Some(fiber) = unsafe { (caller_fiber as *mut Fiber).as_mut() } {
+                fiber.set_fatal_panic();
+            }
+            vo_runtime::ffi::ClosureCallResult::Panic
+        }
     };
     
     // In no_std mode, no panic catching (panics will abort)
@@ -1765,7 +2136,7 @@ pub extern "C" fn closure_call_trampoline(
         let func_def = &module.functions[func_id as usize];
         let full_args = build_closure_args(closure_ref, closure_gcref, func_def, args, arg_count);
         
-        if vm.execute_closure_sync(func_id, &full_args, ret, ret_count) {
+        if vm.execute_closure_sync_with_caller(func_id, &full_args, ret, ret_count, caller_fiber as *mut Fiber) {
             vo_runtime::ffi::ClosureCallResult::Ok
         } else {
             vo_runtime::ffi::ClosureCallResult::Panic
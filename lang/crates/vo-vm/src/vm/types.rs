@@ -4,11 +4,16 @@
 use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::rc::Weak;
+#[cfg(feature = "std")]
+use std::rc::Weak;
 
 use vo_runtime::gc::{Gc, GcRef};
-use vo_runtime::SentinelErrorCache;
+use vo_runtime::{PanicKind, SentinelErrorCache};
 
 use crate::exec::ExternRegistry;
+use crate::snapshot::HeapImage;
 use vo_runtime::itab::ItabCache;
 
 #[cfg(feature = "std")]
@@ -29,6 +34,9 @@ pub type IslandRegistry = Arc<Mutex<HashMap<u32, Sender<IslandCommand>>>>;
 /// Time slice: number of instructions before forced yield check.
 pub const TIME_SLICE: u32 = 1000;
 
+/// Default limit on a fiber's call depth (see `Vm::set_max_call_depth`).
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1_000_000;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExecResult {
     Continue,
@@ -59,8 +67,14 @@ pub enum VmError {
     IndexOutOfBounds(Option<ErrorLocation>),
     NilPointerDereference(Option<ErrorLocation>),
     TypeAssertionFailed(Option<ErrorLocation>),
-    PanicUnwound { msg: Option<String>, loc: Option<ErrorLocation> },
+    /// A panic reached the top of a goroutine. `kind` is set when the VM or
+    /// JIT code raised it as a runtime error.
+    PanicUnwound { msg: Option<String>, loc: Option<ErrorLocation>, kind: Option<PanicKind> },
     SendOnClosedChannel(Option<ErrorLocation>),
+    /// A native caller expected a different number of return slots than the function has.
+    ReturnSlotMismatch { func_id: u32, expected: usize, found: usize },
+    /// `run` stopped with the main fiber and every other live fiber blocked on channels.
+    Deadlock,
 }
 
 /// Active island thread info.
@@ -80,6 +94,38 @@ pub struct VmState {
     pub program_args: Vec<String>,
     /// Per-VM sentinel error cache (reset on each module load).
     pub sentinel_errors: SentinelErrorCache,
+    /// Iterate maps in key order instead of bucket order (reproducible output for tests).
+    pub sorted_map_iter: bool,
+    /// Calls deeper than this panic with a stack overflow.
+    pub max_call_depth: usize,
+    /// Which stdlib externs `Vm::load` registers.
+    pub std_mode: vo_stdlib::StdMode,
+    /// Fail `run` with `VmError::Deadlock` when every fiber is stuck on a
+    /// channel. Waiting on `context.Background()` doesn't count, since the
+    /// host can still `cancel_all`. Hosts that wake fibers later from outside
+    /// the VM (event handlers) turn this off.
+    pub detect_deadlock: bool,
+    /// Extern calls in progress. Incremental GC steps wait for them to
+    /// return, since externs hold references the collector cannot see.
+    pub extern_calls: usize,
+    /// Per extern: some call passes it a reference (see `extern_ref_args`).
+    pub extern_ref_args: Vec<bool>,
+    /// Done channel of `context.Background()`, closed by `Vm::cancel_all`.
+    /// Null until the context package initializes.
+    pub cancel_root: GcRef,
+    /// `Vm::cancel_all` was called; a root registered later starts closed.
+    pub cancelled: bool,
+    /// Heaps of live snapshots; their objects are GC roots until dropped.
+    pub(crate) snapshots: Vec<Weak<HeapImage>>,
+    /// Samples of the running profiler, if `Vm::start_profiler` was called.
+    #[cfg(feature = "std")]
+    pub(crate) profiler: Option<crate::profiler::Profiler>,
+    /// JIT->VM calls currently nested on the native stack.
+    #[cfg(feature = "jit")]
+    pub jit_depth: usize,
+    /// Locals of JIT frames currently on the native stack (GC roots).
+    #[cfg(feature = "jit")]
+    pub jit_frames: Vec<vo_runtime::jit_api::JitFrameRoot>,
     /// Next island ID to assign
     pub next_island_id: u32,
     /// Active island threads (index = island_id - 1, since main island is 0)
@@ -105,6 +151,21 @@ impl VmState {
             extern_registry: ExternRegistry::new(),
             program_args: Vec::new(),
             sentinel_errors: SentinelErrorCache::new(),
+            sorted_map_iter: false,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            std_mode: vo_stdlib::StdMode::Full,
+            detect_deadlock: true,
+            extern_calls: 0,
+            extern_ref_args: Vec::new(),
+            cancel_root: core::ptr::null_mut(),
+            cancelled: false,
+            snapshots: Vec::new(),
+            #[cfg(feature = "std")]
+            profiler: None,
+            #[cfg(feature = "jit")]
+            jit_depth: 0,
+            #[cfg(feature = "jit")]
+            jit_frames: Vec::new(),
             next_island_id: 1, // 0 is main island
             #[cfg(feature = "std")]
             island_threads: Vec::new(),
@@ -49,6 +49,14 @@ export function compileAndRun(source: string, filename?: string) {
   return wasmModule.compileAndRun(source, filename);
 }
 
+/**
+ * Heap usage of the most recently run program.
+ */
+export function memoryStats() {
+  if (!wasmModule) throw new Error('vo-web not initialized. Call init() first.');
+  return wasmModule.memoryStats();
+}
+
 /**
  * Get version string.
  */
@@ -1,13 +1,15 @@
 //! Vo Web Runtime - WASM bindings and generic VM management.
 //!
 //! # Layers
-//! 1. **WASM API** (`compile`, `run`, `compileAndRun`) - for JS interop
+//! 1. **WASM API** (`compile`, `run`, `compileAndRun`, `memoryStats`) - for JS interop
 //! 2. **Generic VM API** (`create_vm`, `call_closure`) - for event-driven apps
 //!
 //! # Features
 //! - `compiler` (default): Full compiler chain
 //! - No features: Bytecode execution only
 
+use std::cell::Cell;
+
 use wasm_bindgen::prelude::*;
 
 #[cfg(feature = "compiler")]
@@ -184,7 +186,11 @@ pub fn compile_source_with_std_fs(source: &str, filename: &str, std_fs: MemoryFs
 /// Run bytecode.
 #[wasm_bindgen]
 pub fn run(bytecode: &[u8]) -> RunResult {
-    match create_vm(bytecode, |_, _| {}) {
+    run_bytecode(bytecode, true)
+}
+
+fn run_bytecode(bytecode: &[u8], validate: bool) -> RunResult {
+    match create_vm(bytecode, |_, _| {}, validate) {
         Ok(_) => RunResult {
             status: "ok".to_string(),
             stdout: vo_runtime::output::take_output(),
@@ -211,7 +217,49 @@ pub fn compile_and_run(source: &str, filename: Option<String>) -> RunResult {
         };
     }
     
-    run(&result.bytecode.unwrap())
+    // Freshly compiled, so there is nothing to validate
+    run_bytecode(&result.bytecode.unwrap(), false)
+}
+
+thread_local! {
+    static LAST_HEAP_STATS: Cell<HeapStats> = Cell::new(HeapStats::default());
+}
+
+/// Heap usage of a finished run.
+#[wasm_bindgen]
+pub struct MemoryStats {
+    bytes_allocated: usize,
+    live_objects: usize,
+    gc_cycles: usize,
+}
+
+#[wasm_bindgen]
+impl MemoryStats {
+    #[wasm_bindgen(getter, js_name = "bytesAllocated")]
+    pub fn bytes_allocated(&self) -> usize {
+        self.bytes_allocated
+    }
+
+    #[wasm_bindgen(getter, js_name = "liveObjects")]
+    pub fn live_objects(&self) -> usize {
+        self.live_objects
+    }
+
+    #[wasm_bindgen(getter, js_name = "gcCycles")]
+    pub fn gc_cycles(&self) -> usize {
+        self.gc_cycles
+    }
+}
+
+/// Heap usage of the program most recently run, whether it finished or failed.
+#[wasm_bindgen(js_name = "memoryStats")]
+pub fn memory_stats() -> MemoryStats {
+    let stats = LAST_HEAP_STATS.with(Cell::get);
+    MemoryStats {
+        bytes_allocated: stats.bytes_allocated,
+        live_objects: stats.live_objects,
+        gc_cycles: stats.gc_cycles,
+    }
 }
 
 // =============================================================================
@@ -221,15 +269,22 @@ pub fn compile_and_run(source: &str, filename: Option<String>) -> RunResult {
 pub use vo_vm::vm::Vm;
 pub use vo_vm::bytecode::{Module, ExternDef};
 pub use vo_runtime::ffi::ExternRegistry;
-pub use vo_runtime::gc::GcRef;
+pub use vo_runtime::gc::{GcRef, HeapStats};
 
 /// Type alias for extern registration function.
 pub type ExternRegistrar = fn(&mut ExternRegistry, &[ExternDef]);
 
 /// Create a VM from bytecode, register externs, and run initialization.
-pub fn create_vm(bytecode: &[u8], register_externs: ExternRegistrar) -> Result<Vm, String> {
+/// `validate` checks the module's operands first (see `vo_vm::validate`);
+/// pass false only for bytecode this build compiled itself.
+pub fn create_vm(bytecode: &[u8], register_externs: ExternRegistrar, validate: bool) -> Result<Vm, String> {
     let module = Module::deserialize(bytecode)
         .map_err(|e| format!("Failed to load bytecode: {:?}", e))?;
+    if validate {
+        if let Err(errors) = vo_vm::validate::validate(&module) {
+            return Err(format!("Invalid bytecode: {}", errors[0]));
+        }
+    }
     
     create_vm_from_module(module, register_externs)
 }
@@ -239,11 +294,13 @@ pub fn create_vm_from_module(module: Module, register_externs: ExternRegistrar)
     vo_runtime::output::clear_output();
     
     let mut vm = Vm::new();
+    // Event handlers may wake fibers after `run` returns.
+    vm.state.detect_deadlock = false;
     let reg = &mut vm.state.extern_registry;
     let exts = &module.externs;
     
     // stdlib (cross-platform)
-    vo_stdlib::register_externs(reg, exts);
+    vo_stdlib::register_externs(reg, exts, vo_stdlib::StdMode::Full);
     
     // wasm platform
     vo_web_runtime_wasm::os::register_externs(reg, exts);
@@ -255,7 +312,9 @@ pub fn create_vm_from_module(module: Module, register_externs: ExternRegistrar)
     register_externs(reg, exts);
     
     vm.load(module);
-    vm.run().map_err(|e| format!("{:?}", e))?;
+    let result = vm.run();
+    LAST_HEAP_STATS.with(|s| s.set(vm.heap_stats()));
+    result.map_err(|e| format!("{:?}", e))?;
     Ok(vm)
 }
 
@@ -263,25 +322,11 @@ pub fn create_vm_from_module(module: Module, register_externs: ExternRegistrar)
 pub fn call_closure(vm: &mut Vm, closure: GcRef, args: &[u64]) -> Result<(), String> {
     vo_runtime::output::clear_output();
     
-    use vo_runtime::objects::closure;
-    let func_id = closure::func_id(closure);
-    let module = vm.module().expect("module not set");
-    let func_def = &module.functions[func_id as usize];
-    
-    let full_args = vo_vm::vm::helpers::build_closure_args(
-        closure as u64,
-        closure,
-        func_def,
-        args.as_ptr(),
-        args.len() as u32,
-    );
-    
-    let mut ret: [u64; 0] = [];
-    if !vm.execute_closure_sync(func_id, &full_args, ret.as_mut_ptr(), 0) {
-        return Err("Closure panicked".to_string());
-    }
+    vm.call_closure_ret::<()>(closure, args).map_err(|_| "Closure panicked".to_string())?;
     
-    vm.run_scheduled().map_err(|e| format!("{:?}", e))?;
+    let result = vm.run_scheduled();
+    LAST_HEAP_STATS.with(|s| s.set(vm.heap_stats()));
+    result.map_err(|e| format!("{:?}", e))?;
     
     Ok(())
 }
@@ -45,6 +45,23 @@ function loadConfig() {
 
 const { skipTests: SKIP_TESTS, shouldFailTests: SHOULD_FAIL_TESTS } = loadConfig();
 
+// Header comments before `package` may exclude modes:
+// `// skip: jit wasm` or `// modes: vm jit` (same format as vo-test)
+function headerSkipsWasm(source) {
+  for (const line of source.split("\n")) {
+    const trimmed = line.trim();
+    if (trimmed === "") continue;
+    if (!trimmed.startsWith("//")) break;
+    const comment = trimmed.slice(2).trim();
+    if (comment.startsWith("skip:")) {
+      if (comment.slice(5).trim().split(/\s+/).includes("wasm")) return true;
+    } else if (comment.startsWith("modes:")) {
+      if (!comment.slice(6).trim().split(/\s+/).includes("wasm")) return true;
+    }
+  }
+  return false;
+}
+
 // Colors
 const GREEN = "\x1b[32m";
 const RED = "\x1b[31m";
@@ -79,8 +96,8 @@ async function runTest(filePath) {
   const source = readFileSync(filePath, "utf-8");
   const relPath = relative(TEST_DIR, filePath);
   
-  // Skip tests marked with skip=["wasm"] in config
-  if (SKIP_TESTS.has(relPath)) {
+  // Skip tests marked with skip=["wasm"] in config or in their header
+  if (SKIP_TESTS.has(relPath) || headerSkipsWasm(source)) {
     console.log(`  ${YELLOW}⊘${NC} ${relPath} [wasm skipped]`);
     return "skip";
   }
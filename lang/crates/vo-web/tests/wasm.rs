@@ -46,3 +46,29 @@ func main() {
     assert!(result.success(), "compile failed: {:?}", result.error_message());
     assert!(result.bytecode().is_some());
 }
+
+#[wasm_bindgen_test]
+#[cfg(feature = "compiler")]
+fn test_memory_stats() {
+    let run = |n: usize| {
+        let source = format!(r#"
+package main
+
+func main() {{
+    total := 0
+    for i := 0; i < {}; i++ {{
+        s := make([]int, 64)
+        total += len(s)
+    }}
+    println(total)
+}}
+"#, n);
+        let result = vo_web::compile_and_run(&source, None);
+        assert_eq!(result.status(), "ok", "{}", result.stderr());
+        vo_web::memory_stats()
+    };
+    let small = run(10);
+    let large = run(1000);
+    assert!(small.bytes_allocated() > 0);
+    assert!(large.bytes_allocated() > small.bytes_allocated() + 990 * 64 * 8);
+}
@@ -0,0 +1 @@
+module package_init
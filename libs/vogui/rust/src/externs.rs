@@ -3,7 +3,7 @@
 use vo_ext::prelude::*;
 use vo_runtime::objects::string;
 
-use crate::{PENDING_HANDLER, start_timeout as js_start_timeout, clear_timeout as js_clear_timeout, 
+use crate::{register_handler, LAST_RENDER, start_timeout as js_start_timeout, clear_timeout as js_clear_timeout, 
             start_interval as js_start_interval, clear_interval as js_clear_interval, 
             navigate as js_navigate, get_current_path as js_get_current_path};
 
@@ -13,8 +13,9 @@ use crate::{PENDING_HANDLER, start_timeout as js_start_timeout, clear_timeout as
 
 #[vo_extern_ctx("vogui", "registerEventHandler")]
 pub fn register_event_handler(ctx: &mut ExternCallContext) -> ExternResult {
+    let id = ctx.arg_i64(slots::ARG_ID) as i32;
     let handler = ctx.arg_ref(slots::ARG_HANDLER);
-    PENDING_HANDLER.with(|s| *s.borrow_mut() = Some(handler));
+    register_handler(id, handler);
     ExternResult::Ok
 }
 
@@ -22,9 +23,7 @@ pub fn register_event_handler(ctx: &mut ExternCallContext) -> ExternResult {
 pub fn emit_render(ctx: &mut ExternCallContext) -> ExternResult {
     let json_ref = ctx.arg_ref(slots::ARG_JSON);
     let json = if json_ref.is_null() { "" } else { string::as_str(json_ref) };
-    
-    vo_runtime::output::write("__VOGUI__");
-    vo_runtime::output::writeln(json);
+    LAST_RENDER.with(|s| *s.borrow_mut() = Some(json.to_string()));
     
     ExternResult::Ok
 }
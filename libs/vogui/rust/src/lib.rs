@@ -4,6 +4,7 @@
 //! VM management and event loop are handled by the caller (e.g., vo-playground).
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use vo_runtime::ffi::ExternRegistry;
 use vo_runtime::gc::GcRef;
 use vo_vm::bytecode::ExternDef;
@@ -14,9 +15,16 @@ mod externs;
 // Global State (for extern functions)
 // =============================================================================
 
+/// Handler ID whose closure receives events no other handler is registered for.
+/// Must match `DefaultHandlerID` in event.vo.
+pub const DEFAULT_HANDLER_ID: i32 = -100;
+
 thread_local! {
-    /// Pending event handler closure (set by registerEventHandler, consumed by caller)
-    pub static PENDING_HANDLER: RefCell<Option<GcRef>> = RefCell::new(None);
+    /// Pending event handler closures by handler ID (set by registerEventHandler, consumed by caller)
+    pub static PENDING_HANDLERS: RefCell<HashMap<i32, GcRef>> = RefCell::new(HashMap::new());
+    
+    /// Latest render JSON (set by emitRender, consumed by caller)
+    pub static LAST_RENDER: RefCell<Option<String>> = RefCell::new(None);
 }
 
 // =============================================================================
@@ -28,14 +36,32 @@ pub fn register_externs(registry: &mut ExternRegistry, externs: &[ExternDef]) {
     externs::vo_ext_register(registry, externs);
 }
 
-/// Take the pending event handler (if registerEventHandler was called).
-pub fn take_pending_handler() -> Option<GcRef> {
-    PENDING_HANDLER.with(|s| s.borrow_mut().take())
+/// Register an event handler closure under `handler_id`, replacing any previous one.
+pub fn register_handler(handler_id: i32, handler: GcRef) {
+    PENDING_HANDLERS.with(|s| s.borrow_mut().insert(handler_id, handler));
+}
+
+/// Take the pending event handlers (empty if registerEventHandler was never called).
+pub fn take_pending_handlers() -> HashMap<i32, GcRef> {
+    PENDING_HANDLERS.with(|s| std::mem::take(&mut *s.borrow_mut()))
+}
+
+/// Clear any pending handlers.
+pub fn clear_pending_handlers() {
+    PENDING_HANDLERS.with(|s| s.borrow_mut().clear());
+}
+
+/// Take the latest render JSON (None if emitRender wasn't called since the last take).
+pub fn take_last_render() -> Option<String> {
+    LAST_RENDER.with(|s| s.borrow_mut().take())
 }
 
-/// Clear any pending handler.
-pub fn clear_pending_handler() {
-    PENDING_HANDLER.with(|s| *s.borrow_mut() = None);
+/// Pick the handler for an event: the one registered for `handler_id`,
+/// else the default handler.
+pub fn route_event(handlers: &HashMap<i32, GcRef>, handler_id: i32) -> Option<GcRef> {
+    handlers.get(&handler_id)
+        .or_else(|| handlers.get(&DEFAULT_HANDLER_ID))
+        .copied()
 }
 
 // =============================================================================
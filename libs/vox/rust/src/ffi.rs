@@ -10,7 +10,9 @@ use vo_runtime::builtins::error_helper::{write_error_to, write_nil_error};
 use vo_runtime::output;
 use vo_common::symbol::SymbolInterner;
 use vo_syntax::parser;
+use vo_module::{ModFile, ModuleResolver};
 use vo_syntax::ast::File;
+use vo_syntax::json::to_json;
 
 use crate::printer::AstPrinter;
 use crate::format::format_text;
@@ -202,30 +204,45 @@ fn runner_compile_string(ctx: &mut ExternCallContext) -> ExternResult {
 #[vo_extern_ctx("libs/vox", "Run")]
 fn runner_run(ctx: &mut ExternCallContext) -> ExternResult {
     let module_id = ctx.arg_any_as_i64(slots::ARG_M);
-    
-    let stored = match get_module(module_id) {
-        Some(m) => m,
-        None => {
-            write_error_to(ctx, slots::RET_0, "invalid module handle");
-            return ExternResult::Ok;
-        }
-    };
+    ret_run_result(ctx, slots::RET_0, run_stored(module_id, RunMode::Vm, Vec::new()))
+}
+
+#[vo_extern_ctx("libs/vox", "RunArgs")]
+fn runner_run_args(ctx: &mut ExternCallContext) -> ExternResult {
+    let module_id = ctx.arg_any_as_i64(slots::ARG_M);
+    let args = read_string_slice(ctx.arg_ref(slots::ARG_ARGS));
+    ret_run_result(ctx, slots::RET_0, run_stored(module_id, RunMode::Vm, args))
+}
 
+/// Runs a stored module with `args` as the program's os.Args.
+fn run_stored(module_id: i64, mode: RunMode, args: Vec<String>) -> Result<(), String> {
+    let stored = get_module(module_id).ok_or("invalid module handle")?;
     let output = CompileOutput {
         module: stored.module,
         source_root: stored.source_root,
         extensions: stored.extensions,
     };
+    run(output, mode, args).map_err(|e| e.to_string())
+}
 
-    match run(output, RunMode::Vm, Vec::new()) {
-        Ok(()) => ctx.ret_nil_error(slots::RET_0),
-        Err(e) => {
-            write_error_to(ctx, slots::RET_0, &e.to_string());
-        }
+/// Writes a run result to the extern's error return slot.
+fn ret_run_result(ctx: &mut ExternCallContext, slot: u16, result: Result<(), String>) -> ExternResult {
+    match result {
+        Ok(()) => ctx.ret_nil_error(slot),
+        Err(e) => write_error_to(ctx, slot, &e),
     }
     ExternResult::Ok
 }
 
+/// Reads a Vo `[]string` into owned strings.
+fn read_string_slice(slice_ref: GcRef) -> Vec<String> {
+    if slice_ref.is_null() {
+        return Vec::new();
+    }
+    let slice = VoSlice::<VoStringElem>::from_ref(slice_ref);
+    (0..slice.len()).map(|i| slice.get(i)).collect()
+}
+
 #[vo_extern_ctx("libs/vox", "RunCapture")]
 fn runner_run_capture(ctx: &mut ExternCallContext) -> ExternResult {
     let module_id = ctx.arg_any_as_i64(slots::ARG_M);
@@ -260,28 +277,14 @@ fn runner_run_capture(ctx: &mut ExternCallContext) -> ExternResult {
 #[vo_extern_ctx("libs/vox", "RunJit")]
 fn runner_run_jit(ctx: &mut ExternCallContext) -> ExternResult {
     let module_id = ctx.arg_any_as_i64(slots::ARG_M);
-    
-    let stored = match get_module(module_id) {
-        Some(m) => m,
-        None => {
-            write_error_to(ctx, slots::RET_0, "invalid module handle");
-            return ExternResult::Ok;
-        }
-    };
-
-    let output = CompileOutput {
-        module: stored.module,
-        source_root: stored.source_root,
-        extensions: stored.extensions,
-    };
+    ret_run_result(ctx, slots::RET_0, run_stored(module_id, RunMode::Jit, Vec::new()))
+}
 
-    match run(output, RunMode::Jit, Vec::new()) {
-        Ok(()) => ctx.ret_nil_error(slots::RET_0),
-        Err(e) => {
-            write_error_to(ctx, slots::RET_0, &e.to_string());
-        }
-    }
-    ExternResult::Ok
+#[vo_extern_ctx("libs/vox", "RunJitArgs")]
+fn runner_run_jit_args(ctx: &mut ExternCallContext) -> ExternResult {
+    let module_id = ctx.arg_any_as_i64(slots::ARG_M);
+    let args = read_string_slice(ctx.arg_ref(slots::ARG_ARGS));
+    ret_run_result(ctx, slots::RET_0, run_stored(module_id, RunMode::Jit, args))
 }
 
 #[vo_extern_ctx("libs/vox", "RunFile")]
@@ -424,6 +427,21 @@ fn runner_print_ast(ctx: &mut ExternCallContext) -> ExternResult {
     ExternResult::Ok
 }
 
+#[vo_extern_ctx("libs/vox", "AstToJson")]
+fn runner_ast_to_json(ctx: &mut ExternCallContext) -> ExternResult {
+    let node_id = ctx.arg_any_as_i64(slots::ARG_NODE);
+    
+    let result = {
+        let nodes = AST_NODES.lock().unwrap();
+        nodes.get(node_id as usize)
+            .and_then(|n| n.as_ref())
+            .map(|ast| to_json(&ast.file, &ast.interner))
+    };
+    
+    ctx.ret_str(slots::RET_0, result.as_deref().unwrap_or(""));
+    ExternResult::Ok
+}
+
 #[vo_extern_ctx("libs/vox", "FreeAst")]
 fn runner_free_ast(ctx: &mut ExternCallContext) -> ExternResult {
     let node_id = ctx.arg_any_as_i64(slots::ARG_NODE);
@@ -431,6 +449,74 @@ fn runner_free_ast(ctx: &mut ExternCallContext) -> ExternResult {
     ExternResult::Ok
 }
 
+// ============ Module Functions ============
+
+/// Downloads a dependency and adds it to the project's vo.mod.
+fn get_module_dep(dir: &str, module: &str, version: &str) -> vo_module::ModuleResult<std::path::PathBuf> {
+    let resolver = ModuleResolver::new(dir);
+    let mod_path = resolver.mod_file_path();
+    let mut mod_file = ModFile::parse_file(&mod_path)?;
+    let root = resolver.download(module, version)?;
+    mod_file.require_module(module, version);
+    mod_file.write_file(&mod_path)?;
+    Ok(root)
+}
+
+#[vo_extern_ctx("libs/vox", "Get")]
+fn runner_get(ctx: &mut ExternCallContext) -> ExternResult {
+    let dir = ctx.arg_str(slots::ARG_DIR).to_string();
+    let spec = ctx.arg_str(slots::ARG_SPEC).to_string();
+    
+    let result = match spec.split_once('@') {
+        Some((module, version)) => get_module_dep(&dir, module, version).map_err(|e| e.to_string()),
+        None => Err(format!("missing version in {}, expected <module>@<version>", spec)),
+    };
+    
+    match result {
+        Ok(root) => {
+            ctx.ret_str(slots::RET_0, &root.to_string_lossy());
+            write_nil_error(ctx, slots::RET_1);
+        }
+        Err(msg) => {
+            ctx.ret_str(slots::RET_0, "");
+            write_error_to(ctx, slots::RET_1, &msg);
+        }
+    }
+    ExternResult::Ok
+}
+
+#[vo_extern_ctx("libs/vox", "ResolveModules")]
+fn runner_resolve_modules(ctx: &mut ExternCallContext) -> ExternResult {
+    let dir = ctx.arg_str(slots::ARG_DIR).to_string();
+    
+    let resolver = ModuleResolver::new(&dir);
+    let mod_path = resolver.mod_file_path();
+    let result = if mod_path.exists() {
+        ModFile::parse_file(&mod_path)
+            .and_then(|mod_file| resolver.compute_closure(&mod_file))
+            .map(|closure| {
+                for module in &closure.unverified {
+                    output::writeln(&format!("[VO:MODULE] warning: {} has no vo.sum entry, its contents are not verified", module));
+                }
+                closure.root_module
+            })
+    } else {
+        Ok(String::new())
+    };
+    
+    match result {
+        Ok(module) => {
+            ctx.ret_str(slots::RET_0, &module);
+            write_nil_error(ctx, slots::RET_1);
+        }
+        Err(e) => {
+            ctx.ret_str(slots::RET_0, "");
+            write_error_to(ctx, slots::RET_1, &e.to_string());
+        }
+    }
+    ExternResult::Ok
+}
+
 // ============ Bytecode I/O Functions ============
 
 #[vo_extern_ctx("libs/vox", "SaveBytecodeText")]
@@ -517,4 +603,23 @@ fn runner_load_bytecode_binary(ctx: &mut ExternCallContext) -> ExternResult {
     ExternResult::Ok
 }
 
+#[vo_extern_ctx("libs/vox", "ValidateBytecode")]
+fn runner_validate_bytecode(ctx: &mut ExternCallContext) -> ExternResult {
+    let module_id = ctx.arg_any_as_i64(slots::ARG_M);
+    
+    let module = match get_module(module_id) {
+        Some(m) => m,
+        None => {
+            write_error_to(ctx, slots::RET_0, "invalid module handle");
+            return ExternResult::Ok;
+        }
+    };
+    
+    match vo_vm::validate::validate(&module.module) {
+        Ok(()) => write_nil_error(ctx, slots::RET_0),
+        Err(errors) => write_error_to(ctx, slots::RET_0, &format!("invalid bytecode: {}", errors[0])),
+    }
+    ExternResult::Ok
+}
+
 vo_ext::export_extensions!();
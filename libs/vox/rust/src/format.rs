@@ -1,15 +1,53 @@
-//! Bytecode text format parser and formatter.
+//! Bytecode text format parser, formatter and differ.
 
-use vo_vm::bytecode::{Constant, FunctionDef, Module};
+use std::collections::HashMap;
+
+use vo_runtime::{SlotType, ValueKind, ValueMeta, ValueRttid};
+use vo_vm::bytecode::{
+    Constant, ExternDef, FieldMeta, FunctionDef, GlobalDef, InterfaceMeta, InterfaceMethodMeta,
+    Itab, MethodInfo, Module, NamedTypeMeta, StructMeta,
+};
 use vo_vm::instruction::{Instruction, Opcode};
 
-/// Parse bytecode text format into a Module.
-pub fn parse_text(_input: &str) -> Result<Module, String> {
-    Err("bytecode text format parsing not yet implemented".into())
+/// Parse the text written by `format_text` back into a Module.
+///
+/// Only what the listing shows is recovered: `format_text` of the result
+/// reproduces the input, but slot types, runtime types, debug info and the
+/// operand bits an instruction doesn't print come back empty or zero. The
+/// module is for inspecting and diffing, not for running.
+pub fn parse_text(input: &str) -> Result<Module, String> {
+    let opcodes: HashMap<String, Opcode> = (0..=u8::MAX)
+        .map(|v| {
+            let op = Opcode::from_u8(v);
+            (format!("{:?}", op), op)
+        })
+        .collect();
+
+    let mut module = Module::new(String::new());
+    let mut section = String::new();
+    for (n, line) in input.lines().enumerate() {
+        parse_line(&mut module, &mut section, &opcodes, line)
+            .map_err(|e| format!("line {}: {}", n + 1, e))?;
+    }
+    Ok(module)
 }
 
 /// Format a Module as text.
 pub fn format_text(module: &Module) -> String {
+    let mut out = format_header(module);
+
+    // Functions
+    out.push_str("## Functions\n\n");
+    for (i, f) in module.functions.iter().enumerate() {
+        out.push_str(&format_function(module, i as u32, f, true));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Everything `format_text` prints before the functions.
+fn format_header(module: &Module) -> String {
     let mut out = String::new();
 
     out.push_str(&format!("# Module: {}\n", module.name));
@@ -44,7 +82,9 @@ pub fn format_text(module: &Module) -> String {
             ));
             if !nt.methods.is_empty() {
                 out.push_str("#   methods:");
-                for (name, info) in &nt.methods {
+                let mut methods: Vec<_> = nt.methods.iter().collect();
+                methods.sort_by(|x, y| x.0.cmp(y.0));
+                for (name, info) in methods {
                     let ptr_str = if info.is_pointer_receiver { "*" } else { "" };
                     out.push_str(&format!(" {}{}=func_{}", ptr_str, name, info.func_id));
                 }
@@ -108,16 +148,120 @@ pub fn format_text(module: &Module) -> String {
         out.push('\n');
     }
 
-    // Functions
-    out.push_str("## Functions\n\n");
-    for (i, f) in module.functions.iter().enumerate() {
-        out.push_str(&format_function(i as u32, f));
-        out.push('\n');
-    }
+    out
+}
+
+/// Diff two modules in the text format of `format_text`.
+///
+/// Functions are paired by name rather than position, so adding or removing
+/// one function doesn't make every function after it differ. Function ids
+/// are left out of the compared text for the same reason. Each difference is
+/// reported under an `@@ func <name> @@` (or `@@ header @@`) heading with
+/// `-`/`+` lines and two lines of context. Returns an empty string when the
+/// modules format the same.
+pub fn diff_text(a: &Module, b: &Module) -> String {
+    let mut out = String::new();
+    push_hunks(&mut out, "header", &format_header(a), &format_header(b));
 
+    let keys_a = function_keys(a);
+    let keys_b = function_keys(b);
+    let body = |module: &Module, i: usize| format_function(module, i as u32, &module.functions[i], false);
+    let title = |name: &str| format!("func {}", name);
+
+    // Functions between two matched pairs exist on one side only
+    let (mut next_a, mut next_b) = (0, 0);
+    let end = (keys_a.len(), keys_b.len());
+    for (pa, pb) in lcs_pairs(&keys_a, &keys_b).into_iter().chain(std::iter::once(end)) {
+        for (i, key) in keys_a.iter().enumerate().take(pa).skip(next_a) {
+            push_hunks(&mut out, &title(&key.0), &body(a, i), "");
+        }
+        for (j, key) in keys_b.iter().enumerate().take(pb).skip(next_b) {
+            push_hunks(&mut out, &title(&key.0), "", &body(b, j));
+        }
+        if pa < keys_a.len() {
+            push_hunks(&mut out, &title(&keys_a[pa].0), &body(a, pa), &body(b, pb));
+        }
+        (next_a, next_b) = (pa + 1, pb + 1);
+    }
     out
 }
 
+/// Function names, numbered among functions that share a name.
+fn function_keys(module: &Module) -> Vec<(String, usize)> {
+    let mut seen: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    module.functions.iter().map(|f| {
+        let n = seen.entry(f.name.as_str()).or_insert(0);
+        *n += 1;
+        (f.name.clone(), *n - 1)
+    }).collect()
+}
+
+/// Index pairs of a longest common subsequence of `a` and `b`, in order.
+fn lcs_pairs<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    // len[i][j]: LCS length of a[i..] and b[j..]
+    let mut len = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            len[i][j] = if a[i] == b[j] { len[i + 1][j + 1] + 1 } else { len[i + 1][j].max(len[i][j + 1]) };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if len[i + 1][j] >= len[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Append a line diff of `old` and `new` under `@@ <title> @@`, if they differ.
+fn push_hunks(out: &mut String, title: &str, old: &str, new: &str) {
+    const CONTEXT: usize = 2;
+    if old == new {
+        return;
+    }
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // (tag, line) for the whole edit script
+    let mut script: Vec<(char, &str)> = Vec::new();
+    let (mut next_old, mut next_new) = (0, 0);
+    let end = (old.len(), new.len());
+    for (po, pn) in lcs_pairs(&old, &new).into_iter().chain(std::iter::once(end)) {
+        script.extend(old[next_old..po].iter().map(|l| ('-', *l)));
+        script.extend(new[next_new..pn].iter().map(|l| ('+', *l)));
+        if po < old.len() {
+            script.push((' ', old[po]));
+        }
+        (next_old, next_new) = (po + 1, pn + 1);
+    }
+
+    out.push_str(&format!("@@ {} @@\n", title));
+    // script[..shown] has been printed or skipped
+    let mut shown = 0;
+    for k in (0..script.len()).filter(|&k| script[k].0 != ' ') {
+        let to = (k + CONTEXT + 1).min(script.len());
+        if to <= shown {
+            continue;
+        }
+        let from = k.saturating_sub(CONTEXT).max(shown);
+        if from > shown && shown > 0 {
+            out.push_str("  ...\n");
+        }
+        for &(tag, line) in &script[from..to] {
+            out.push_str(&format!("{} {}\n", tag, line));
+        }
+        shown = to;
+    }
+}
+
 fn format_constant(c: &Constant) -> String {
     match c {
         Constant::Nil => "nil".to_string(),
@@ -128,21 +272,53 @@ fn format_constant(c: &Constant) -> String {
     }
 }
 
-fn format_function(func_id: u32, f: &FunctionDef) -> String {
+/// `ids` off leaves function ids out, so the text doesn't change when
+/// functions are renumbered.
+fn format_function(module: &Module, func_id: u32, f: &FunctionDef, ids: bool) -> String {
     let mut out = String::new();
+    let id = if ids { format!("func_{} ", func_id) } else { "func ".to_string() };
     out.push_str(&format!(
-        "func_{} {}(params={}, param_slots={}, locals={}, ret={}):\n",
-        func_id, f.name, f.param_count, f.param_slots, f.local_slots, f.ret_slots
+        "{}{}(params={}, param_slots={}, locals={}, ret={}):\n",
+        id, f.name, f.param_count, f.param_slots, f.local_slots, f.ret_slots
     ));
 
+    // Branch targets get a label line so control flow reads symbolically.
+    let mut targets: Vec<usize> = f.code.iter().enumerate()
+        .filter_map(|(pc, instr)| jump_target(pc, instr))
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+
     for (pc, instr) in f.code.iter().enumerate() {
-        out.push_str(&format!("  {:04}: {}\n", pc, format_instruction(instr)));
+        if targets.binary_search(&pc).is_ok() {
+            out.push_str(&format!("L{}:\n", pc));
+        }
+        out.push_str(&format!("  {:04}: {}\n", pc, format_instruction(module, pc, instr, ids)));
     }
 
     out
 }
 
-fn format_instruction(instr: &Instruction) -> String {
+/// Absolute target pc of a jump instruction (offsets are relative to the jump itself).
+fn jump_target(pc: usize, instr: &Instruction) -> Option<usize> {
+    match instr.opcode() {
+        Opcode::Jump | Opcode::JumpIf | Opcode::JumpIfNot => {
+            Some((pc as i64 + instr.imm32() as i64) as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Format a function reference, with its name when the module has it.
+fn func_ref(module: &Module, func_id: u32, ids: bool) -> String {
+    match module.functions.get(func_id as usize) {
+        Some(f) if ids => format!("func_{} <{}>", func_id, f.name),
+        Some(f) => format!("<{}>", f.name),
+        None => format!("func_{}", func_id),
+    }
+}
+
+fn format_instruction(module: &Module, pc: usize, instr: &Instruction, ids: bool) -> String {
     let op = instr.opcode();
     let a = instr.a;
     let b = instr.b;
@@ -195,6 +371,10 @@ fn format_instruction(instr: &Instruction) -> String {
         Opcode::MulF => format!("MulF          r{}, r{}, r{}", a, b, c),
         Opcode::DivF => format!("DivF          r{}, r{}, r{}", a, b, c),
         Opcode::NegF => format!("NegF          r{}, r{}", a, b),
+        Opcode::SqrtF => format!("SqrtF         r{}, r{}", a, b),
+        Opcode::AbsF => format!("AbsF          r{}, r{}", a, b),
+        Opcode::MinF => format!("MinF          r{}, r{}, r{}", a, b, c),
+        Opcode::MaxF => format!("MaxF          r{}, r{}, r{}", a, b, c),
 
         // CMP Integer (signed)
         Opcode::EqI => format!("EqI           r{}, r{}, r{}", a, b, c),
@@ -232,27 +412,28 @@ fn format_instruction(instr: &Instruction) -> String {
         Opcode::BoolNot => format!("BoolNot       r{}, r{}", a, b),
 
         // JUMP
-        Opcode::Jump => format!("Jump          pc_{}", instr.imm32()),
-        Opcode::JumpIf => format!("JumpIf        r{}, pc_{}", a, instr.imm32()),
-        Opcode::JumpIfNot => format!("JumpIfNot     r{}, pc_{}", a, instr.imm32()),
+        Opcode::Jump => format!("Jump          L{}", jump_target(pc, instr).unwrap()),
+        Opcode::JumpIf => format!("JumpIf        r{}, L{}", a, jump_target(pc, instr).unwrap()),
+        Opcode::JumpIfNot => format!("JumpIfNot     r{}, L{}", a, jump_target(pc, instr).unwrap()),
+        Opcode::JumpTable => format!("JumpTable     r{}, n={}", a, b),
 
         // CALL
-        // a=func_id_low, b=args_start, c=(arg_slots<<8|ret_slots), flags=func_id_high
+        // a=func_id_low, b=buffer (results, then args), c=(arg_slots<<8|ret_slots), flags=func_id_high
         Opcode::Call => {
             let func_id = a as u32 | ((flags as u32) << 16);
             let arg_slots = c >> 8;
             let ret_slots = c & 0xFF;
-            format!("Call          func_{}, args=r{}, arg_slots={}, ret_slots={}", func_id, b, arg_slots, ret_slots)
+            format!("Call          {}, buf=r{}, arg_slots={}, ret_slots={}", func_ref(module, func_id, ids), b, arg_slots, ret_slots)
         }
         // CallExtern: a=result_start, b=extern_id, c=arg_start, flags=arg_count
         Opcode::CallExtern => format!("CallExtern    r{}, extern_{}, args={}, count={}", a, b, c, flags),
-        // CallClosure: a=closure_reg, b=args_start, c=(arg_slots<<8|ret_slots)
+        // CallClosure: a=closure_reg, b=buffer (results, then args), c=(arg_slots<<8|ret_slots)
         Opcode::CallClosure => {
             let arg_slots = c >> 8;
             let ret_slots = c & 0xFF;
             format!("CallClosure   r{}, r{}, arg_slots={}, ret_slots={}", a, b, arg_slots, ret_slots)
         }
-        // CallIface: a=iface_slot, b=args_start, c=(arg_slots<<8|ret_slots), flags=method_idx
+        // CallIface: a=iface_slot, b=buffer (results, then args), c=(arg_slots<<8|ret_slots), flags=method_idx
         Opcode::CallIface => {
             let arg_slots = c >> 8;
             let ret_slots = c & 0xFF;
@@ -279,6 +460,8 @@ fn format_instruction(instr: &Instruction) -> String {
         Opcode::StrGt => format!("StrGt         r{}, r{}, r{}", a, b, c),
         Opcode::StrGe => format!("StrGe         r{}, r{}, r{}", a, b, c),
         Opcode::StrDecodeRune => format!("StrDecodeRune r{}, r{}, r{}", a, b, c),
+        Opcode::StrContains => format!("StrContains   r{}, r{}, r{}", a, b, c),
+        Opcode::StrIndexOf => format!("StrIndexOf    r{}, r{}, r{}", a, b, c),
 
         // ARRAY
         // ArrayNew: a=dst, b=meta_reg, c=len_reg, flags=elem_bytes_encoding
@@ -341,7 +524,7 @@ fn format_instruction(instr: &Instruction) -> String {
         // ClosureNew: a=dst, b=func_id_low, c=capture_count, flags=func_id_high
         Opcode::ClosureNew => {
             let func_id = b as u32 | ((flags as u32) << 16);
-            format!("ClosureNew    r{}, func_{}, captures={}", a, func_id, c)
+            format!("ClosureNew    r{}, {}, captures={}", a, func_ref(module, func_id, ids), c)
         }
         // ClosureGet: a=dst, b=capture_index (closure ref is always at r0)
         Opcode::ClosureGet => format!("ClosureGet    r{}, capture[{}]", a, b),
@@ -354,7 +537,7 @@ fn format_instruction(instr: &Instruction) -> String {
                 format!("GoStart       closure=r{}, args=r{}, slots={}", a, b, c)
             } else {
                 let func_id = a as u32 | (((flags >> 1) as u32) << 16);
-                format!("GoStart       func_{}, args=r{}, slots={}", func_id, b, c)
+                format!("GoStart       {}, args=r{}, slots={}", func_ref(module, func_id, ids), b, c)
             }
         }
 
@@ -366,7 +549,7 @@ fn format_instruction(instr: &Instruction) -> String {
                 format!("DeferPush     closure=r{}, args=r{}, slots={}", a, b, c)
             } else {
                 let func_id = a as u32 | (((flags >> 1) as u32) << 16);
-                format!("DeferPush     func_{}, args=r{}, slots={}", func_id, b, c)
+                format!("DeferPush     {}, args=r{}, slots={}", func_ref(module, func_id, ids), b, c)
             }
         }
         Opcode::ErrDeferPush => {
@@ -375,7 +558,7 @@ fn format_instruction(instr: &Instruction) -> String {
                 format!("ErrDeferPush  closure=r{}, args=r{}, slots={}", a, b, c)
             } else {
                 let func_id = a as u32 | (((flags >> 1) as u32) << 16);
-                format!("ErrDeferPush  func_{}, args=r{}, slots={}", func_id, b, c)
+                format!("ErrDeferPush  {}, args=r{}, slots={}", func_ref(module, func_id, ids), b, c)
             }
         }
         Opcode::Panic => format!("Panic         r{}", a),
@@ -388,8 +571,8 @@ fn format_instruction(instr: &Instruction) -> String {
         Opcode::IfaceEq => format!("IfaceEq       r{}, r{}, r{}", a, b, c),
 
         // CONV
-        Opcode::ConvI2F => format!("ConvI2F       r{}, r{}", a, b),
-        Opcode::ConvF2I => format!("ConvF2I       r{}, r{}", a, b),
+        Opcode::ConvI2F => format!("ConvI2F       r{}, r{}{}", a, b, if flags == 1 { ", u64" } else { "" }),
+        Opcode::ConvF2I => format!("ConvF2I       r{}, r{}{}", a, b, if flags == 1 { ", u64" } else { "" }),
         Opcode::ConvF64F32 => format!("ConvF64F32    r{}, r{}", a, b),
         Opcode::ConvF32F64 => format!("ConvF32F64    r{}, r{}", a, b),
         Opcode::Trunc => {
@@ -422,3 +605,584 @@ fn format_instruction(instr: &Instruction) -> String {
         Opcode::Invalid => format!("Invalid       op={}, flags={}, a={}, b={}, c={}", instr.op, flags, a, b, c),
     }
 }
+
+fn parse_line(
+    module: &mut Module,
+    section: &mut String,
+    opcodes: &HashMap<String, Opcode>,
+    line: &str,
+) -> Result<(), String> {
+    if line.is_empty() {
+        return Ok(());
+    }
+    if let Some(title) = line.strip_prefix("## ") {
+        *section = title.to_string();
+        return Ok(());
+    }
+    if let Some(name) = line.strip_prefix("# Module: ") {
+        module.name = name.to_string();
+        return Ok(());
+    }
+    if let Some(entry) = line.strip_prefix("# Entry: func_") {
+        module.entry_func = num(entry)?;
+        return Ok(());
+    }
+    if let Some(item) = line.strip_prefix("# [") {
+        let (_, item) = item.split_once("] ").ok_or("malformed entry")?;
+        return parse_entry(module, section, item);
+    }
+    if let Some(detail) = line.strip_prefix("#   ") {
+        return parse_entry_detail(module, section, detail);
+    }
+    if let Some(header) = line.strip_prefix("func_") {
+        let f = parse_function_header(header, module.functions.len())?;
+        module.functions.push(f);
+        return Ok(());
+    }
+    // Labels are recomputed from the jumps that target them
+    if line.starts_with('L') && line.ends_with(':') {
+        return Ok(());
+    }
+    if let Some(instr) = line.strip_prefix("  ") {
+        let f = module.functions.last_mut().ok_or("instruction outside a function")?;
+        let (pc, text) = instr.split_once(": ").ok_or("malformed instruction")?;
+        if num::<usize>(pc)? != f.code.len() {
+            return Err(format!("expected pc {}, found {}", f.code.len(), pc));
+        }
+        let instr = parse_instruction(opcodes, f.code.len(), text)?;
+        f.code.push(instr);
+        return Ok(());
+    }
+    Err(format!("unexpected line {:?}", line))
+}
+
+/// An indexed `# [i] ...` line of a header section.
+fn parse_entry(module: &mut Module, section: &str, item: &str) -> Result<(), String> {
+    match section {
+        "Struct Types" => {
+            let slots = item.strip_prefix('(').and_then(|s| s.strip_suffix(" slots)")).ok_or("malformed struct")?;
+            module.struct_metas.push(StructMeta {
+                slot_types: vec![SlotType::Value; num(slots)?],
+                fields: Vec::new(),
+                field_index: Default::default(),
+            });
+        }
+        "Named Types" => {
+            let (name, underlying) = item.rsplit_once(" (underlying: meta_id=").ok_or("malformed named type")?;
+            let (meta_id, vk) = underlying.strip_suffix(')').and_then(|s| s.split_once(", vk="))
+                .ok_or("malformed named type")?;
+            module.named_type_metas.push(NamedTypeMeta {
+                name: name.to_string(),
+                underlying_meta: ValueMeta::new(num(meta_id)?, value_kind(vk)?),
+                methods: Default::default(),
+            });
+        }
+        "Interface Types" => module.interface_metas.push(InterfaceMeta {
+            name: item.to_string(),
+            method_names: Vec::new(),
+            methods: Vec::new(),
+        }),
+        "Itabs" => {
+            let list = item.strip_prefix("methods: [").and_then(|s| s.strip_suffix(']')).ok_or("malformed itab")?;
+            let methods = list.split(", ").filter(|s| !s.is_empty()).map(num).collect::<Result<_, _>>()?;
+            module.itabs.push(Itab { methods });
+        }
+        "Constants" => module.constants.push(parse_constant(item)?),
+        "Globals" => {
+            let (name, rest) = item.rsplit_once(": ").ok_or("malformed global")?;
+            let (slots, rest) = rest.split_once(" slot(s), vk=").ok_or("malformed global")?;
+            let (vk, meta_id) = rest.split_once(", meta=").ok_or("malformed global")?;
+            let slots = num(slots)?;
+            module.globals.push(GlobalDef {
+                name: name.to_string(),
+                slots,
+                value_kind: num(vk)?,
+                meta_id: num(meta_id)?,
+                slot_types: vec![SlotType::Value; slots as usize],
+            });
+        }
+        "Externs" => {
+            let (sig, ret_slots) = item.rsplit_once(") -> ").ok_or("malformed extern")?;
+            let (name, param_slots) = sig.rsplit_once('(').ok_or("malformed extern")?;
+            module.externs.push(ExternDef {
+                name: name.to_string(),
+                param_slots: num(param_slots)?,
+                ret_slots: num(ret_slots)?,
+            });
+        }
+        _ => return Err(format!("unexpected entry in section {:?}", section)),
+    }
+    Ok(())
+}
+
+/// An indented `#   ...` line belonging to the previous entry.
+fn parse_entry_detail(module: &mut Module, section: &str, detail: &str) -> Result<(), String> {
+    match section {
+        "Struct Types" => {
+            let s = module.struct_metas.last_mut().ok_or("field before any struct")?;
+            let (name, rest) = detail.rsplit_once(": offset=").ok_or("malformed field")?;
+            let (offset, slot_count) = rest.split_once(", slots=").ok_or("malformed field")?;
+            s.field_index.insert(name.to_string(), s.fields.len());
+            s.fields.push(FieldMeta {
+                name: name.to_string(),
+                offset: num(offset)?,
+                slot_count: num(slot_count)?,
+                type_info: ValueRttid::default(),
+                embedded: false,
+                tag: None,
+            });
+        }
+        "Named Types" => {
+            let nt = module.named_type_metas.last_mut().ok_or("methods before any named type")?;
+            let list = detail.strip_prefix("methods:").ok_or("malformed methods")?;
+            for method in list.split_whitespace() {
+                let (name, func_id) = method.rsplit_once("=func_").ok_or("malformed method")?;
+                let (name, is_pointer_receiver) = match name.strip_prefix('*') {
+                    Some(name) => (name, true),
+                    None => (name, false),
+                };
+                nt.methods.insert(name.to_string(), MethodInfo {
+                    func_id: num(func_id)?,
+                    is_pointer_receiver,
+                    signature_rttid: 0,
+                });
+            }
+        }
+        "Interface Types" => {
+            let iface = module.interface_metas.last_mut().ok_or("method before any interface")?;
+            let name = detail.strip_prefix("method ").ok_or("malformed interface method")?;
+            iface.method_names.push(name.to_string());
+            iface.methods.push(InterfaceMethodMeta { name: name.to_string(), signature_rttid: 0 });
+        }
+        _ => return Err(format!("unexpected detail in section {:?}", section)),
+    }
+    Ok(())
+}
+
+/// `<id> <name>(params=, param_slots=, locals=, ret=):`, after `func_`.
+fn parse_function_header(header: &str, expected_id: usize) -> Result<FunctionDef, String> {
+    let (id, rest) = header.split_once(' ').ok_or("malformed function header")?;
+    if num::<usize>(id)? != expected_id {
+        return Err(format!("expected func_{}, found func_{}", expected_id, id));
+    }
+    let (name, counts) = rest.rsplit_once("(params=").ok_or("malformed function header")?;
+    let counts = counts.strip_suffix("):").ok_or("malformed function header")?;
+    let [param_count, param_slots, local_slots, ret_slots] = numbers(counts)?;
+    Ok(FunctionDef {
+        name: name.to_string(),
+        param_count: param_count as u16,
+        param_slots: param_slots as u16,
+        local_slots: local_slots as u16,
+        ret_slots: ret_slots as u16,
+        recv_slots: 0,
+        heap_ret_gcref_count: 0,
+        heap_ret_gcref_start: 0,
+        heap_ret_slots: Vec::new(),
+        is_closure: false,
+        error_ret_slot: -1,
+        code: Vec::new(),
+        slot_types: Vec::new(),
+        capture_types: Vec::new(),
+        param_types: Vec::new(),
+    })
+}
+
+fn parse_constant(text: &str) -> Result<Constant, String> {
+    if text == "nil" {
+        return Ok(Constant::Nil);
+    }
+    let (kind, value) = text.split_once(' ').ok_or("malformed constant")?;
+    Ok(match kind {
+        "bool" => Constant::Bool(num(value)?),
+        "int" => Constant::Int(num(value)?),
+        "float" => Constant::Float(num(value)?),
+        "string" => Constant::String(unquote(value)?),
+        _ => return Err(format!("unknown constant kind {:?}", kind)),
+    })
+}
+
+/// Undo the `{:?}` quoting of a string.
+fn unquote(quoted: &str) -> Result<String, String> {
+    let inner = quoted.strip_prefix('"').and_then(|s| s.strip_suffix('"')).ok_or("unquoted string")?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(ch @ ('\\' | '"' | '\'')) => ch,
+            Some('u') => {
+                let rest = chars.as_str();
+                let (hex, after) = rest.strip_prefix('{').and_then(|s| s.split_once('}'))
+                    .ok_or("malformed \\u escape")?;
+                let ch = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                    .ok_or("invalid \\u escape")?;
+                chars = after.chars();
+                ch
+            }
+            other => return Err(format!("unknown escape \\{}", other.map(String::from).unwrap_or_default())),
+        });
+    }
+    Ok(out)
+}
+
+fn parse_instruction(opcodes: &HashMap<String, Opcode>, pc: usize, text: &str) -> Result<Instruction, String> {
+    let (mnemonic, operands) = match text.split_once(' ') {
+        Some((m, rest)) => (m, rest.trim_start()),
+        None => (text, ""),
+    };
+    let op = *opcodes.get(mnemonic).ok_or_else(|| format!("unknown opcode {:?}", mnemonic))?;
+    let instr = |flags: i64, a: i64, b: i64, c: i64| Ok(Instruction::with_flags(op, flags as u8, a as u16, b as u16, c as u16));
+
+    match op {
+        Opcode::Hint => {
+            let [flags, a, bc] = numbers(operands)?;
+            instr(flags, a, bc & 0xFFFF, bc >> 16)
+        }
+        Opcode::LoadInt => {
+            let [a, imm] = numbers(operands)?;
+            let imm = imm as i32 as u32 as i64;
+            instr(0, a, imm & 0xFFFF, imm >> 16)
+        }
+        Opcode::Jump => {
+            let [target] = numbers(operands)?;
+            let offset = (target - pc as i64) as i32 as u32 as i64;
+            instr(0, 0, offset & 0xFFFF, offset >> 16)
+        }
+        Opcode::JumpIf | Opcode::JumpIfNot => {
+            let [a, target] = numbers(operands)?;
+            let offset = (target - pc as i64) as i32 as u32 as i64;
+            instr(0, a, offset & 0xFFFF, offset >> 16)
+        }
+        Opcode::Call => {
+            let (callee, rest) = operands.rsplit_once(", buf=").ok_or("malformed Call")?;
+            let func_id = parse_func_ref(callee)?;
+            let [b, arg_slots, ret_slots] = numbers(rest)?;
+            instr(func_id >> 16, func_id & 0xFFFF, b, arg_slots << 8 | ret_slots)
+        }
+        Opcode::CallClosure => {
+            let [a, b, arg_slots, ret_slots] = numbers(operands)?;
+            instr(0, a, b, arg_slots << 8 | ret_slots)
+        }
+        Opcode::CallIface => {
+            let [a, b, method, arg_slots, ret_slots] = numbers(operands)?;
+            instr(method, a, b, arg_slots << 8 | ret_slots)
+        }
+        Opcode::Return if operands.is_empty() => instr(0, 0, 0, 0),
+        Opcode::SliceSlice => {
+            let (regs, mode) = operands.split_once(", src=").ok_or("malformed SliceSlice")?;
+            let [a, b, c] = numbers(regs)?;
+            let (src, has_max) = match mode.strip_suffix(", has_max") {
+                Some(src) => (src, 2),
+                None => (mode, 0),
+            };
+            let is_array = match src {
+                "array" => 1,
+                "slice" => 0,
+                _ => return Err(format!("unknown SliceSlice source {:?}", src)),
+            };
+            instr(is_array | has_max, a, b, c)
+        }
+        Opcode::MapIterNext => {
+            let [a, b, c, key_slots, val_slots] = numbers(operands)?;
+            instr(key_slots | val_slots << 4, a, b, c)
+        }
+        Opcode::ChanRecv => {
+            let [a, b, slots] = numbers(operands)?;
+            instr(slots << 1, a, b, 0)
+        }
+        Opcode::ClosureNew => {
+            let (dst, rest) = operands.split_once(", ").ok_or("malformed ClosureNew")?;
+            let (callee, captures) = rest.rsplit_once(", captures=").ok_or("malformed ClosureNew")?;
+            let [a] = numbers(dst)?;
+            let func_id = parse_func_ref(callee)?;
+            instr(func_id >> 16, a, func_id & 0xFFFF, num(captures)?)
+        }
+        Opcode::GoStart | Opcode::DeferPush | Opcode::ErrDeferPush => {
+            if operands.starts_with("closure=") {
+                let [a, b, c] = numbers(operands)?;
+                return instr(1, a, b, c);
+            }
+            let (callee, rest) = operands.rsplit_once(", args=").ok_or("malformed call target")?;
+            let func_id = parse_func_ref(callee)?;
+            let [b, c] = numbers(rest)?;
+            instr((func_id >> 16) << 1, func_id & 0xFFFF, b, c)
+        }
+        Opcode::ConvI2F | Opcode::ConvF2I => {
+            let (regs, unsigned) = match operands.strip_suffix(", u64") {
+                Some(regs) => (regs, 1),
+                None => (operands, 0),
+            };
+            let [a, b] = numbers(regs)?;
+            instr(unsigned, a, b, 0)
+        }
+        Opcode::Trunc => {
+            let (regs, ty) = operands.rsplit_once(", ").ok_or("malformed Trunc")?;
+            let flags = match ty {
+                "i8" => 0x81,
+                "i16" => 0x82,
+                "i32" => 0x84,
+                "u8" => 0x01,
+                "u16" => 0x02,
+                "u32" => 0x04,
+                _ => return Err(format!("unknown Trunc type {:?}", ty)),
+            };
+            let [a, b] = numbers(regs)?;
+            instr(flags, a, b, 0)
+        }
+        Opcode::PortRecv => {
+            let [a, b, elem_slots, has_ok] = numbers(operands)?;
+            instr(elem_slots << 1 | has_ok, a, b, 0)
+        }
+        Opcode::Invalid => {
+            let [raw_op, flags, a, b, c] = numbers(operands)?;
+            Ok(Instruction { op: raw_op as u8, flags: flags as u8, a: a as u16, b: b as u16, c: c as u16 })
+        }
+        // Operands printed as a, b, flags
+        Opcode::GlobalGetN | Opcode::GlobalSetN | Opcode::PtrNew | Opcode::ChanSend
+        | Opcode::PortSend | Opcode::GoIsland => {
+            let [a, b, flags] = numbers(operands)?;
+            instr(flags, a, b, 0)
+        }
+        // Everything else prints a, b, c, flags in order, leaving off the unused tail
+        _ => match *numbers_vec(operands).as_slice() {
+            [a] => instr(0, a, 0, 0),
+            [a, b] => instr(0, a, b, 0),
+            [a, b, c] => instr(0, a, b, c),
+            [a, b, c, flags] => instr(flags, a, b, c),
+            _ => Err(format!("malformed operands {:?}", operands)),
+        },
+    }
+}
+
+/// `func_<id>`, optionally followed by ` <name>`.
+fn parse_func_ref(text: &str) -> Result<i64, String> {
+    let id = text.split(' ').next().and_then(|s| s.strip_prefix("func_")).ok_or("malformed function reference")?;
+    num(id)
+}
+
+fn value_kind(text: &str) -> Result<ValueKind, String> {
+    let raw: u8 = num(text)?;
+    let vk = ValueKind::from_u8(raw);
+    if vk as u8 != raw {
+        return Err(format!("unknown value kind {}", raw));
+    }
+    Ok(vk)
+}
+
+fn num<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.trim().parse().map_err(|_| format!("invalid value {:?}", text))
+}
+
+/// Exactly `N` integers from an operand list.
+fn numbers<const N: usize>(text: &str) -> Result<[i64; N], String> {
+    numbers_vec(text).try_into().map_err(|_| format!("expected {} operands in {:?}", N, text))
+}
+
+/// The integers in an operand list such as `r1, r2[r3], n=4`, in order.
+fn numbers_vec(text: &str) -> Vec<i64> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let negative = i > 0 && bytes[i - 1] == b'-';
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        let value: i64 = text[start..i].parse().unwrap_or(i64::MAX);
+        out.push(if negative { -value } else { value });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn func(name: &str, code: Vec<Instruction>) -> FunctionDef {
+        FunctionDef {
+            name: name.to_string(),
+            param_count: 0,
+            param_slots: 0,
+            local_slots: 2,
+            ret_slots: 1,
+            recv_slots: 0,
+            heap_ret_gcref_count: 0,
+            heap_ret_gcref_start: 0,
+            heap_ret_slots: vec![],
+            is_closure: false,
+            error_ret_slot: -1,
+            code,
+            slot_types: vec![],
+            capture_types: vec![],
+            param_types: vec![],
+        }
+    }
+
+    fn ret_int(name: &str, value: u16) -> FunctionDef {
+        func(name, vec![
+            Instruction::new(Opcode::LoadInt, 0, value, 0),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+        ])
+    }
+
+    /// `main` calls `callee` by id, so renumbering shows up in its text.
+    fn module(funcs: Vec<FunctionDef>, callee: &str) -> Module {
+        let mut m = Module::new("test".to_string());
+        let callee_id = funcs.iter().position(|f| f.name == callee).unwrap() as u16;
+        m.functions.push(func("main", vec![
+            Instruction::new(Opcode::Call, callee_id + 1, 0, 1),
+            Instruction::new(Opcode::Return, 0, 1, 0),
+        ]));
+        m.functions.extend(funcs);
+        m
+    }
+
+    /// A module touching every header section and the operand encodings the
+    /// parser has to invert specially.
+    fn rich_module() -> Module {
+        let mut m = Module::new("rich".to_string());
+        m.entry_func = 0;
+        m.struct_metas.push(StructMeta {
+            slot_types: vec![SlotType::Value; 3],
+            fields: vec![
+                FieldMeta { name: "x".into(), offset: 0, slot_count: 1, type_info: ValueRttid::default(), embedded: false, tag: None },
+                FieldMeta { name: "s".into(), offset: 1, slot_count: 2, type_info: ValueRttid::default(), embedded: false, tag: None },
+            ],
+            field_index: Default::default(),
+        });
+        let mut point = NamedTypeMeta {
+            name: "main.Point".into(),
+            underlying_meta: ValueMeta::new(0, ValueKind::Struct),
+            methods: Default::default(),
+        };
+        for (name, func_id, ptr) in [("String", 1, false), ("Move", 2, true), ("Abs", 1, false)] {
+            point.methods.insert(name.into(), MethodInfo { func_id, is_pointer_receiver: ptr, signature_rttid: 0 });
+        }
+        m.named_type_metas.push(point);
+        m.interface_metas.push(InterfaceMeta {
+            name: "fmt.Stringer".into(),
+            method_names: vec!["String".into()],
+            methods: vec![InterfaceMethodMeta { name: "String".into(), signature_rttid: 0 }],
+        });
+        m.itabs.push(Itab { methods: vec![] });
+        m.itabs.push(Itab { methods: vec![1, 2] });
+        m.constants = vec![
+            Constant::Nil,
+            Constant::Bool(true),
+            Constant::Int(-42),
+            Constant::Float(1.5),
+            Constant::Float(f64::INFINITY),
+            Constant::String("tab\there \"quoted\" \\ é\u{1}\n".into()),
+        ];
+        m.globals.push(GlobalDef { name: "main.count".into(), slots: 1, value_kind: ValueKind::Int as u8, meta_id: 0, slot_types: vec![SlotType::Value] });
+        m.externs.push(ExternDef { name: "fmt_Println".into(), param_slots: 2, ret_slots: 0 });
+
+        m.functions.push(func("main", vec![
+            Instruction::with_flags(Opcode::Hint, 3, 1, 0x0002, 0x0001),
+            Instruction::new(Opcode::LoadInt, 0, 0xFFFB, 0xFFFF), // -5
+            Instruction::new(Opcode::JumpIfNot, 0, 3, 0),         // -> 5
+            Instruction::new(Opcode::Call, 1, 2, 1 << 8 | 1),
+            Instruction::new(Opcode::Jump, 0, 0xFFFC, 0xFFFF),    // -> 0
+            Instruction::with_flags(Opcode::ClosureNew, 0, 4, 2, 1),
+            Instruction::with_flags(Opcode::GoStart, 1, 4, 5, 2),
+            Instruction::with_flags(Opcode::DeferPush, 0, 2, 5, 0),
+            Instruction::with_flags(Opcode::SliceSlice, 3, 6, 7, 8),
+            Instruction::with_flags(Opcode::MapIterNext, 0x21, 6, 7, 8),
+            Instruction::with_flags(Opcode::ChanRecv, 4, 6, 7, 0),
+            Instruction::with_flags(Opcode::Trunc, 0x82, 6, 7, 0),
+            Instruction::with_flags(Opcode::ConvI2F, 1, 6, 7, 0),
+            Instruction::with_flags(Opcode::PortRecv, 5, 6, 7, 0),
+            Instruction::with_flags(Opcode::CallIface, 2, 6, 7, 3 << 8 | 1),
+            Instruction::with_flags(Opcode::CallExtern, 2, 6, 0, 7),
+            Instruction::with_flags(Opcode::PtrNew, 2, 6, 7, 0),
+            Instruction::with_flags(Opcode::SliceGet, 1, 6, 7, 8),
+            Instruction::new(Opcode::StrNew, 6, 5, 0),
+            Instruction::new(Opcode::Return, 0, 0, 0),
+        ]));
+        m.functions.push(ret_int("main.Point.String", 1));
+        m.functions.push(ret_int("main.(*Point).Move", 2));
+        m
+    }
+
+    #[test]
+    fn test_format_labels_jump_targets() {
+        let text = format_text(&rich_module());
+        assert!(text.contains("L0:\n  0000: Hint"), "{}", text);
+        assert!(text.contains("L5:\n  0005: ClosureNew"), "{}", text);
+        assert!(text.contains("  0002: JumpIfNot     r0, L5\n"), "{}", text);
+        assert!(text.contains("  0004: Jump          L0\n"), "{}", text);
+        assert_eq!(text.matches("L0:").count(), 1, "{}", text);
+    }
+
+    #[test]
+    fn test_format_names_call_targets() {
+        let text = format_text(&rich_module());
+        assert!(text.contains("  0003: Call          func_1 <main.Point.String>, buf=r2, arg_slots=1, ret_slots=1\n"), "{}", text);
+        assert!(text.contains("  0005: ClosureNew    r4, func_2 <main.(*Point).Move>, captures=1\n"), "{}", text);
+        assert!(text.contains("  0007: DeferPush     func_2 <main.(*Point).Move>, args=r5, slots=0\n"), "{}", text);
+        assert!(text.contains("#   methods: Abs=func_1 *Move=func_2 String=func_1\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_parse_round_trips_text() {
+        let m = rich_module();
+        let text = format_text(&m);
+        let parsed = parse_text(&text).unwrap();
+        assert_eq!(format_text(&parsed), text);
+        assert_eq!(parsed.name, m.name);
+        assert_eq!(parsed.constants, m.constants);
+        assert_eq!(parsed.itabs, m.itabs);
+        assert_eq!(parsed.externs, m.externs);
+        for (p, f) in parsed.functions.iter().zip(&m.functions) {
+            assert_eq!(p.code, f.code, "{}", f.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_text() {
+        let text = format_text(&rich_module());
+        let err = parse_text(&text.replace("StrNew", "StrNope")).unwrap_err();
+        assert!(err.contains("unknown opcode \"StrNope\""), "{}", err);
+        let err = parse_text(&text.replace("  0001: LoadInt", "  0002: LoadInt")).unwrap_err();
+        assert!(err.contains("expected pc 1, found 0002"), "{}", err);
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        let a = module(vec![ret_int("f", 1), ret_int("g", 2)], "g");
+        assert_eq!(diff_text(&a, &a.clone()), "");
+    }
+
+    #[test]
+    fn test_diff_localized_to_changed_function() {
+        let a = module(vec![ret_int("f", 1), ret_int("g", 2), ret_int("h", 3)], "h");
+        let b = module(vec![ret_int("f", 1), ret_int("g", 20), ret_int("h", 3)], "h");
+        let diff = diff_text(&a, &b);
+        assert_eq!(diff.matches("@@").count(), 2, "unexpected diff:\n{}", diff);
+        assert!(diff.starts_with("@@ func g @@\n"), "unexpected diff:\n{}", diff);
+        assert!(diff.contains("-   0000: LoadInt       r0, 2\n"), "unexpected diff:\n{}", diff);
+        assert!(diff.contains("+   0000: LoadInt       r0, 20\n"), "unexpected diff:\n{}", diff);
+    }
+
+    #[test]
+    fn test_diff_inserted_function_does_not_cascade() {
+        let a = module(vec![ret_int("f", 1), ret_int("g", 2)], "g");
+        let b = module(vec![ret_int("new", 9), ret_int("f", 1), ret_int("g", 2)], "g");
+        let diff = diff_text(&a, &b);
+        assert_eq!(diff.matches("@@").count(), 2, "unexpected diff:\n{}", diff);
+        assert!(diff.starts_with("@@ func new @@\n"), "unexpected diff:\n{}", diff);
+        assert!(diff.lines().skip(1).all(|l| l.starts_with('+')), "unexpected diff:\n{}", diff);
+    }
+}
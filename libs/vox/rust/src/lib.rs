@@ -3,7 +3,7 @@
 //! This crate provides:
 //! - Re-exports of vo-engine (compile, run, etc.)
 //! - AST parsing and printing
-//! - Bytecode formatting
+//! - Bytecode formatting and diffing
 //! - FFI bindings for the vox package in Vo
 
 mod ffi;
@@ -16,4 +16,4 @@ pub use vo_engine::{run, RunMode, RunError, RuntimeError, RuntimeErrorKind};
 pub use vo_engine::Module;
 
 pub use printer::AstPrinter;
-pub use format::{format_text, parse_text};
+pub use format::{diff_text, format_text, parse_text};
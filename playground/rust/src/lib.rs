@@ -1,6 +1,7 @@
 //! Vo Playground WASM - combines vo-web (system) and vogui (GUI library).
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use wasm_bindgen::prelude::*;
 use include_dir::{include_dir, Dir};
@@ -16,7 +17,8 @@ static VOGUI_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/../../libs/vogui");
 
 struct GuiAppState {
     vm: Vm,
-    event_handler: GcRef,
+    /// Event handler closures by handler ID (see `vogui::route_event`).
+    event_handlers: HashMap<i32, GcRef>,
 }
 
 thread_local! {
@@ -79,14 +81,14 @@ pub fn init_gui_app(source: &str, filename: Option<String>) -> WasmGuiResult {
         Err(e) => return WasmGuiResult::compile_err(e),
     };
     
-    // Run
-    init_gui_app_bytecode(&bytecode)
+    // Run (freshly compiled, so there is nothing to validate)
+    run_gui_bytecode(&bytecode, false)
 }
 
 /// Initialize a GUI app from pre-compiled bytecode.
 #[wasm_bindgen(js_name = "initGuiAppBytecode")]
 pub fn init_gui_app_bytecode(bytecode: &[u8]) -> WasmGuiResult {
-    run_gui_bytecode(bytecode)
+    run_gui_bytecode(bytecode, true)
 }
 
 /// Handle a GUI event.
@@ -99,34 +101,36 @@ pub fn handle_gui_event(handler_id: i32, payload: &str) -> WasmGuiResult {
 // Core Implementation
 // =============================================================================
 
-fn run_gui_bytecode(bytecode: &[u8]) -> WasmGuiResult {
+fn run_gui_bytecode(bytecode: &[u8], validate: bool) -> WasmGuiResult {
     // Clear previous state
     GUI_STATE.with(|s| *s.borrow_mut() = None);
-    vogui::clear_pending_handler();
+    vogui::clear_pending_handlers();
+    vogui::take_last_render();
     
     // Create VM using vo-web's generic API
-    let vm = match vo_web::create_vm(bytecode, vogui::register_externs) {
+    let vm = match vo_web::create_vm(bytecode, vogui::register_externs, validate) {
         Ok(vm) => vm,
         Err(e) => return WasmGuiResult::err(e),
     };
     
     // Extract render output
     let stdout = vo_web::take_output();
-    let render_json = extract_render_json(&stdout);
+    log_program_output(&stdout);
+    let render_json = vogui::take_last_render().unwrap_or_default();
     
     if render_json.is_empty() {
         return WasmGuiResult::err(format!("No render output. stdout: {}", stdout));
     }
     
-    // Get event handler (vogui protocol)
-    let event_handler = match vogui::take_pending_handler() {
-        Some(h) => h,
-        None => return WasmGuiResult::err("registerEventHandler not called"),
-    };
+    // Get event handlers (vogui protocol)
+    let event_handlers = vogui::take_pending_handlers();
+    if event_handlers.is_empty() {
+        return WasmGuiResult::err("registerEventHandler not called");
+    }
     
     // Store state
     GUI_STATE.with(|s| {
-        *s.borrow_mut() = Some(GuiAppState { vm, event_handler });
+        *s.borrow_mut() = Some(GuiAppState { vm, event_handlers });
     });
     
     WasmGuiResult::ok(render_json)
@@ -140,32 +144,38 @@ fn handle_event(handler_id: i32, payload: &str) -> WasmGuiResult {
             None => return WasmGuiResult::err("GUI app not initialized"),
         };
         
+        let event_handler = match vogui::route_event(&state.event_handlers, handler_id) {
+            Some(h) => h,
+            None => return WasmGuiResult::err(format!("no event handler for id {}", handler_id)),
+        };
+        
         // Allocate payload string using vo-web API
         let payload_ref = vo_web::alloc_string(&mut state.vm, payload);
         
         // Call closure using vo-web API
         let args = [handler_id as u64, payload_ref as u64];
-        if let Err(e) = vo_web::call_closure(&mut state.vm, state.event_handler, &args) {
+        let called = vo_web::call_closure(&mut state.vm, event_handler, &args);
+        // Handlers may register further handlers (timers, new widgets)
+        state.event_handlers.extend(vogui::take_pending_handlers());
+        if let Err(e) = called {
             return WasmGuiResult::err(e);
         }
         
-        let stdout = vo_web::take_output();
-        let render_json = extract_render_json(&stdout);
+        log_program_output(&vo_web::take_output());
+        let render_json = vogui::take_last_render().unwrap_or_default();
         
         WasmGuiResult::ok(render_json)
     })
 }
 
-fn extract_render_json(stdout: &str) -> String {
-    let mut render_json = String::new();
-    for line in stdout.lines() {
-        if let Some(json) = line.strip_prefix("__VOGUI__") {
-            render_json = json.to_string();
-        } else if !line.is_empty() {
-            web_sys::console::log_1(&format!("[Vo] {}", line).into());
-        }
+/// Forward the program's own output to the browser console.
+fn log_program_output(stdout: &str) {
+    for line in stdout.lines().filter(|l| !l.is_empty()) {
+        #[cfg(target_arch = "wasm32")]
+        web_sys::console::log_1(&format!("[Vo] {}", line).into());
+        #[cfg(not(target_arch = "wasm32"))]
+        println!("[Vo] {}", line);
     }
-    render_json
 }
 
 fn add_vo_files_recursive(dir: &Dir, base_path: &str, fs: &mut MemoryFs) {
@@ -209,4 +219,108 @@ mod tests {
         assert!(fs.is_dir(std::path::Path::new("vogui")), "vogui dir not created");
         assert!(fs.exists(std::path::Path::new("vogui/app.vo")), "vogui/app.vo not found");
     }
+    
+    #[test]
+    fn test_event_handlers_routed_by_id() {
+        let source = r#"
+package main
+
+import gui "vogui"
+
+func main() {
+    gui.RegisterEventHandler(7, func(id int, payload string) { println("seven:", id, payload) })
+    gui.RegisterEventHandler(-1, func(id int, payload string) { println("timer:", id, payload) })
+    gui.Run(gui.App{
+        Init: func() any { return 0 },
+        View: func(state any) gui.Node { return gui.Text("hello") },
+    })
+}
+"#;
+        let result = init_gui_app(source, None);
+        assert_eq!(result.status(), "ok", "{}", result.error());
+        
+        let send = |handler_id: i32, payload: &str| {
+            vo_runtime::output::start_capture();
+            let result = handle_gui_event(handler_id, payload);
+            let stdout = vo_runtime::output::stop_capture();
+            assert_eq!(result.status(), "ok", "{}", result.error());
+            (stdout, result.render_json())
+        };
+        
+        let (stdout, render_json) = send(7, "a");
+        assert_eq!(stdout, "seven: 7 a\n");
+        assert!(render_json.is_empty());
+        
+        let (stdout, render_json) = send(-1, "{}");
+        assert_eq!(stdout, "timer: -1 {}\n");
+        assert!(render_json.is_empty());
+        
+        // Unregistered IDs reach Run's default handler, which re-renders
+        let (stdout, render_json) = send(-3, "");
+        assert_eq!(stdout, "");
+        assert!(render_json.contains("hello"), "unexpected render: {}", render_json);
+    }
+
+    #[test]
+    fn test_handler_registered_by_handler() {
+        let source = r#"
+package main
+
+import gui "vogui"
+
+func main() {
+    gui.RegisterEventHandler(1, func(id int, payload string) {
+        gui.RegisterEventHandler(2, func(id int, payload string) { println("two:", payload) })
+    })
+    gui.Run(gui.App{
+        Init: func() any { return 0 },
+        View: func(state any) gui.Node { return gui.Text("hello") },
+    })
+}
+"#;
+        let result = init_gui_app(source, None);
+        assert_eq!(result.status(), "ok", "{}", result.error());
+
+        let send = |handler_id: i32, payload: &str| {
+            vo_runtime::output::start_capture();
+            let result = handle_gui_event(handler_id, payload);
+            let stdout = vo_runtime::output::stop_capture();
+            assert_eq!(result.status(), "ok", "{}", result.error());
+            stdout
+        };
+
+        // Before handler 1 runs, id 2 falls through to the default handler
+        assert_eq!(send(2, "early"), "");
+        assert_eq!(send(1, ""), "");
+        assert_eq!(send(2, "late"), "two: late\n");
+    }
+
+    #[test]
+    fn test_render_output_separate_from_stdout() {
+        let source = r#"
+package main
+
+import gui "vogui"
+
+func main() {
+    // Runs once main blocks, i.e. after the first render was emitted.
+    go func() { println("__VOGUI__not a render") }()
+    gui.Run(gui.App{
+        Init: func() any { return 0 },
+        View: func(state any) gui.Node { return gui.Text("hello") },
+    })
+}
+"#;
+        vo_runtime::output::start_capture();
+        let result = init_gui_app(source, None);
+        let stdout = vo_runtime::output::stop_capture();
+        assert_eq!(result.status(), "ok", "{}", result.error());
+        let render_json = result.render_json();
+        assert!(render_json.starts_with('{'), "unexpected render: {}", render_json);
+        assert!(render_json.contains("hello"));
+        assert!(!render_json.contains("not a render"));
+        // The program's own output stays on stdout; render JSON never goes there.
+        assert!(stdout.contains("__VOGUI__not a render"));
+        assert!(!stdout.contains("hello"));
+    }
 }